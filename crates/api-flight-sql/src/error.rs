@@ -0,0 +1,104 @@
+use core_executor::error_code::ErrorCode;
+use datafusion::arrow::error::ArrowError;
+use datafusion::error::DataFusionError;
+use error_stack::ErrorExt;
+use error_stack_trace;
+use snafu::Location;
+use snafu::prelude::*;
+use tonic::Status;
+use uuid::Uuid;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Snafu)]
+#[snafu(visibility(pub(crate)))]
+#[error_stack_trace::debug]
+pub enum Error {
+    #[snafu(display("[FlightSQL] Missing Basic credentials in Authorization header"))]
+    MissingCredentials {
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("[FlightSQL] Invalid credentials"))]
+    InvalidCredentials {
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("[FlightSQL] Missing auth token"))]
+    MissingAuthToken {
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("[FlightSQL] Invalid auth token"))]
+    InvalidAuthToken {
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("[FlightSQL] Invalid statement ticket"))]
+    InvalidTicket {
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("[FlightSQL] Result of statement {handle} not found"))]
+    ResultNotFound {
+        handle: Uuid,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("[FlightSQL] Metastore error: {source}"))]
+    Metastore {
+        source: core_metastore::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("[FlightSQL] Failed to read catalog metadata"))]
+    Catalog {
+        #[snafu(source)]
+        error: DataFusionError,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("[FlightSQL] Failed to encode Arrow data"))]
+    Arrow {
+        #[snafu(source)]
+        error: ArrowError,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(transparent)]
+    Execution { source: core_executor::Error },
+}
+
+impl From<Error> for Status {
+    fn from(error: Error) -> Self {
+        tracing::error!(error_message = %error.output_msg(), "Flight SQL error");
+        match &error {
+            Error::MissingCredentials { .. }
+            | Error::InvalidCredentials { .. }
+            | Error::MissingAuthToken { .. }
+            | Error::InvalidAuthToken { .. } => Self::unauthenticated(error.to_string()),
+            Error::InvalidTicket { .. } => Self::invalid_argument(error.to_string()),
+            Error::ResultNotFound { .. } => Self::not_found(error.to_string()),
+            Error::Metastore { .. } | Error::Catalog { .. } | Error::Arrow { .. } => {
+                Self::internal(error.to_string())
+            }
+            Error::Execution { source } => {
+                let snowflake_error = source.to_snowflake_error();
+                let message = snowflake_error.display_error_message();
+                match snowflake_error.error_code() {
+                    ErrorCode::Internal => Self::internal(message),
+                    _ => Self::invalid_argument(message),
+                }
+            }
+        }
+    }
+}
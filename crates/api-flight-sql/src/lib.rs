@@ -0,0 +1,14 @@
+//! Arrow Flight SQL endpoint of the execution service.
+//!
+//! Clients open a session with the handshake, passing the credentials of a
+//! metastore user as `Authorization: Basic`, and send the returned bearer token
+//! along with every other call. Statement results are streamed as they are
+//! produced, catalog metadata is read from the catalogs of the session.
+pub mod error;
+pub mod results;
+pub mod server;
+pub mod service;
+pub mod state;
+
+#[cfg(test)]
+mod tests;
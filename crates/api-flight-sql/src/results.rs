@@ -0,0 +1,52 @@
+use core_executor::models::QueryResultStream;
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// How long the result of a statement waits for the client to fetch it
+const RESULTS_TTL: Duration = Duration::from_secs(10 * 60);
+
+struct PendingResult {
+    session_id: String,
+    result: QueryResultStream,
+    created_at: Instant,
+}
+
+/// Results of the statements executed with `GetFlightInfo`, addressed by the
+/// handle of their ticket until the session fetches them with `DoGet`. A result
+/// is streamed only once, dropping an expired one cancels its query.
+#[derive(Default)]
+pub struct PendingResults {
+    results: Mutex<HashMap<Uuid, PendingResult>>,
+}
+
+impl PendingResults {
+    pub fn insert(&self, session_id: &str, result: QueryResultStream) -> Uuid {
+        let handle = Uuid::new_v4();
+        let mut results = self.results.lock().unwrap_or_else(PoisonError::into_inner);
+        results.retain(|_, pending| pending.created_at.elapsed() < RESULTS_TTL);
+        results.insert(
+            handle,
+            PendingResult {
+                session_id: session_id.to_string(),
+                result,
+                created_at: Instant::now(),
+            },
+        );
+        handle
+    }
+
+    #[must_use]
+    pub fn take(&self, handle: Uuid, session_id: &str) -> Option<QueryResultStream> {
+        let mut results = self.results.lock().unwrap_or_else(PoisonError::into_inner);
+        let fetchable = results.get(&handle).is_some_and(|pending| {
+            pending.session_id == session_id && pending.created_at.elapsed() < RESULTS_TTL
+        });
+        if fetchable {
+            results.remove(&handle).map(|pending| pending.result)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use crate::service::EmbucketFlightSqlService;
+use crate::state::State;
+use arrow_flight::flight_service_server::FlightServiceServer;
+
+/// gRPC service of the Flight SQL endpoint, served with `tonic`
+#[must_use]
+pub fn create_flight_sql_service(state: State) -> FlightServiceServer<EmbucketFlightSqlService> {
+    FlightServiceServer::new(EmbucketFlightSqlService::new(state))
+}
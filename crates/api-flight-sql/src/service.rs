@@ -0,0 +1,562 @@
+use crate::error::{self as flight_sql_error, Result};
+use crate::state::State;
+use arrow_flight::encode::FlightDataEncoderBuilder;
+use arrow_flight::error::FlightError;
+use arrow_flight::flight_service_server::FlightService;
+use arrow_flight::sql::server::{FlightSqlService, PeekableFlightDataStream};
+use arrow_flight::sql::{
+    CommandGetCatalogs, CommandGetDbSchemas, CommandGetTables, CommandStatementQuery,
+    CommandStatementUpdate, ProstMessageExt, SqlInfo, TicketStatementQuery,
+};
+use arrow_flight::{
+    FlightDescriptor, FlightEndpoint, FlightInfo, HandshakeRequest, HandshakeResponse, Ticket,
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use core_executor::access_control::AccessControl;
+use core_executor::models::{QueryContext, QueryResultStream, SessionOptions};
+use core_executor::session::SESSION_INACTIVITY_EXPIRATION_SECONDS;
+use core_metastore::{AuthenticatedUser, GrantObject, Privilege, SchemaIdent, TableIdent};
+use datafusion::arrow::array::{Array, AsArray, RecordBatch};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Int64Type, Schema, SchemaRef};
+use datafusion::catalog::{CatalogProvider, CatalogProviderList};
+use datafusion::datasource::TableType;
+use datafusion::scalar::ScalarValue;
+use futures::{Stream, TryStreamExt};
+use prost::Message;
+use sha2::{Digest, Sha256};
+use snafu::{OptionExt, ResultExt};
+use std::pin::Pin;
+use std::sync::Arc;
+use tonic::metadata::MetadataMap;
+use tonic::{Request, Response, Status, Streaming};
+use uuid::Uuid;
+
+const AUTHORIZATION: &str = "authorization";
+const INFORMATION_SCHEMA: &str = "information_schema";
+/// Session of the calls without a bearer token, when no auth is required
+type DoGetStream = <EmbucketFlightSqlService as FlightService>::DoGetStream;
+type HandshakeStream =
+    Pin<Box<dyn Stream<Item = std::result::Result<HandshakeResponse, Status>> + Send>>;
+
+pub struct EmbucketFlightSqlService {
+    state: State,
+}
+
+impl EmbucketFlightSqlService {
+    #[must_use]
+    pub const fn new(state: State) -> Self {
+        Self { state }
+    }
+
+    /// Opens the session of the user given by the Basic credentials, the
+    /// password can also be one of the user's programmatic access tokens
+    pub(crate) async fn login(&self, metadata: &MetadataMap) -> Result<String> {
+        let (name, password) =
+            basic_credentials(metadata).context(flight_sql_error::MissingCredentialsSnafu)?;
+        let authenticated = self
+            .state
+            .metastore
+            .authenticate(&name, &password)
+            .await
+            .context(flight_sql_error::MetastoreSnafu)?
+            .context(flight_sql_error::InvalidCredentialsSnafu)?;
+
+        let session_id = Uuid::new_v4().to_string();
+        self.state
+            .execution_svc
+            .create_session_with_options(&session_id, session_options(&authenticated))
+            .await?;
+        Ok(session_id)
+    }
+
+    /// Session of the bearer token of the call
+    pub(crate) async fn session_id<T>(&self, request: &Request<T>) -> Result<String> {
+        let Some(token) = bearer_token(request.metadata()) else {
+            if self.state.config.require_auth {
+                return flight_sql_error::MissingAuthTokenSnafu.fail();
+            }
+            return self.connection_session(request).await;
+        };
+        if !self.state.execution_svc.session_exists(&token).await {
+            return flight_sql_error::InvalidAuthTokenSnafu.fail();
+        }
+        self.state
+            .execution_svc
+            .update_session_expiry(&token, SESSION_INACTIVITY_EXPIRATION_SECONDS)
+            .await?;
+        Ok(token)
+    }
+
+    /// Anonymous session of the connection of the call, when no auth is required.
+    /// Its id is derived from the connection address and a secret of the server,
+    /// so other clients can't guess it and use it as their bearer token.
+    pub(crate) async fn connection_session<T>(&self, request: &Request<T>) -> Result<String> {
+        let remote_addr = request
+            .remote_addr()
+            .context(flight_sql_error::MissingAuthTokenSnafu)?;
+        let session_id: String = Sha256::new()
+            .chain_update(self.state.connection_secret.as_bytes())
+            .chain_update(remote_addr.to_string())
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        self.state.execution_svc.create_session(&session_id).await?;
+        self.state
+            .execution_svc
+            .update_session_expiry(&session_id, SESSION_INACTIVITY_EXPIRATION_SECONDS)
+            .await?;
+        Ok(session_id)
+    }
+
+    /// Catalogs of the session, holding the catalog metadata the clients ask for
+    async fn catalog_list(&self, session_id: &str) -> Result<Arc<dyn CatalogProviderList>> {
+        let session = self.state.execution_svc.get_session(session_id).await?;
+        Ok(session.ctx.state().catalog_list().clone())
+    }
+
+    /// Privileges of the current role of the session, metadata calls only list
+    /// the objects the role can use
+    async fn access_control(&self, session_id: &str) -> Result<AccessControl> {
+        let session = self.state.execution_svc.get_session(session_id).await?;
+        Ok(session
+            .query_reader()
+            .access_control(self.state.metastore.as_ref())
+            .await?)
+    }
+}
+
+#[tonic::async_trait]
+impl FlightSqlService for EmbucketFlightSqlService {
+    type FlightService = Self;
+
+    #[tracing::instrument(
+        name = "api_flight_sql::do_handshake",
+        level = "debug",
+        skip(self, request),
+        err
+    )]
+    async fn do_handshake(
+        &self,
+        request: Request<Streaming<HandshakeRequest>>,
+    ) -> std::result::Result<Response<HandshakeStream>, Status> {
+        // Without auth, clients without credentials use the session of their connection
+        let session_id =
+            if !self.state.config.require_auth && basic_credentials(request.metadata()).is_none() {
+                self.connection_session(&request).await?
+            } else {
+                self.login(request.metadata()).await?
+            };
+        let authorization = format!("Bearer {session_id}")
+            .parse()
+            .map_err(|_| Status::internal("Invalid session token"))?;
+        let handshake = HandshakeResponse {
+            protocol_version: 0,
+            payload: session_id.into(),
+        };
+        let mut response: Response<HandshakeStream> =
+            Response::new(Box::pin(futures::stream::iter([Ok(handshake)])));
+        response.metadata_mut().insert(AUTHORIZATION, authorization);
+        Ok(response)
+    }
+
+    #[tracing::instrument(
+        name = "api_flight_sql::get_flight_info_statement",
+        level = "debug",
+        skip(self, request),
+        err
+    )]
+    async fn get_flight_info_statement(
+        &self,
+        query: CommandStatementQuery,
+        request: Request<FlightDescriptor>,
+    ) -> std::result::Result<Response<FlightInfo>, Status> {
+        let session_id = self.session_id(&request).await?;
+        let result = self
+            .state
+            .execution_svc
+            .query_stream(&session_id, &query.query, QueryContext::default())
+            .await
+            .map_err(flight_sql_error::Error::from)?;
+        let schema = result.schema();
+        let handle = self.state.results.insert(&session_id, result);
+        let ticket = TicketStatementQuery {
+            statement_handle: handle.as_bytes().to_vec().into(),
+        };
+        Ok(Response::new(flight_info(
+            &ticket,
+            &schema,
+            request.into_inner(),
+        )?))
+    }
+
+    #[tracing::instrument(
+        name = "api_flight_sql::do_get_statement",
+        level = "debug",
+        skip(self, request),
+        err
+    )]
+    async fn do_get_statement(
+        &self,
+        ticket: TicketStatementQuery,
+        request: Request<Ticket>,
+    ) -> std::result::Result<Response<DoGetStream>, Status> {
+        let session_id = self.session_id(&request).await?;
+        let handle = Uuid::from_slice(&ticket.statement_handle)
+            .ok()
+            .context(flight_sql_error::InvalidTicketSnafu)?;
+        let result = self
+            .state
+            .results
+            .take(handle, &session_id)
+            .context(flight_sql_error::ResultNotFoundSnafu { handle })?;
+        Ok(Response::new(stream_result(result)))
+    }
+
+    #[tracing::instrument(
+        name = "api_flight_sql::do_put_statement_update",
+        level = "debug",
+        skip(self, request),
+        err
+    )]
+    async fn do_put_statement_update(
+        &self,
+        ticket: CommandStatementUpdate,
+        request: Request<PeekableFlightDataStream>,
+    ) -> std::result::Result<i64, Status> {
+        let session_id = self.session_id(&request).await?;
+        let result = self
+            .state
+            .execution_svc
+            .query(&session_id, &ticket.query, QueryContext::default())
+            .await
+            .map_err(flight_sql_error::Error::from)?;
+        Ok(affected_rows(&result.records))
+    }
+
+    #[tracing::instrument(
+        name = "api_flight_sql::get_flight_info_catalogs",
+        level = "debug",
+        skip(self, request),
+        err
+    )]
+    async fn get_flight_info_catalogs(
+        &self,
+        query: CommandGetCatalogs,
+        request: Request<FlightDescriptor>,
+    ) -> std::result::Result<Response<FlightInfo>, Status> {
+        self.session_id(&request).await?;
+        let schema = query.clone().into_builder().schema();
+        Ok(Response::new(flight_info(
+            &query,
+            &schema,
+            request.into_inner(),
+        )?))
+    }
+
+    #[tracing::instrument(
+        name = "api_flight_sql::do_get_catalogs",
+        level = "debug",
+        skip(self, request),
+        err
+    )]
+    async fn do_get_catalogs(
+        &self,
+        query: CommandGetCatalogs,
+        request: Request<Ticket>,
+    ) -> std::result::Result<Response<DoGetStream>, Status> {
+        let session_id = self.session_id(&request).await?;
+        let catalog_list = self.catalog_list(&session_id).await?;
+        let access = self.access_control(&session_id).await?;
+        let mut builder = query.into_builder();
+        for catalog_name in catalog_list.catalog_names() {
+            if access.allows(
+                Privilege::Usage,
+                &GrantObject::Database(catalog_name.clone()),
+            ) {
+                builder.append(catalog_name);
+            }
+        }
+        let schema = builder.schema();
+        let batch = builder.build().context(flight_sql_error::ArrowSnafu)?;
+        Ok(Response::new(stream_batch(schema, batch)))
+    }
+
+    #[tracing::instrument(
+        name = "api_flight_sql::get_flight_info_schemas",
+        level = "debug",
+        skip(self, request),
+        err
+    )]
+    async fn get_flight_info_schemas(
+        &self,
+        query: CommandGetDbSchemas,
+        request: Request<FlightDescriptor>,
+    ) -> std::result::Result<Response<FlightInfo>, Status> {
+        self.session_id(&request).await?;
+        let schema = query.clone().into_builder().schema();
+        Ok(Response::new(flight_info(
+            &query,
+            &schema,
+            request.into_inner(),
+        )?))
+    }
+
+    #[tracing::instrument(
+        name = "api_flight_sql::do_get_schemas",
+        level = "debug",
+        skip(self, request),
+        err
+    )]
+    async fn do_get_schemas(
+        &self,
+        query: CommandGetDbSchemas,
+        request: Request<Ticket>,
+    ) -> std::result::Result<Response<DoGetStream>, Status> {
+        let session_id = self.session_id(&request).await?;
+        let catalog_list = self.catalog_list(&session_id).await?;
+        let access = self.access_control(&session_id).await?;
+        let catalogs = catalogs(catalog_list.as_ref(), query.catalog.as_deref());
+        // The builder applies the schema name pattern of the query
+        let mut builder = query.into_builder();
+        for (catalog_name, catalog) in catalogs {
+            for schema_name in catalog.schema_names() {
+                if schema_visible(&access, &catalog_name, &schema_name) {
+                    builder.append(&catalog_name, schema_name);
+                }
+            }
+        }
+        let schema = builder.schema();
+        let batch = builder.build().context(flight_sql_error::ArrowSnafu)?;
+        Ok(Response::new(stream_batch(schema, batch)))
+    }
+
+    #[tracing::instrument(
+        name = "api_flight_sql::get_flight_info_tables",
+        level = "debug",
+        skip(self, request),
+        err
+    )]
+    async fn get_flight_info_tables(
+        &self,
+        query: CommandGetTables,
+        request: Request<FlightDescriptor>,
+    ) -> std::result::Result<Response<FlightInfo>, Status> {
+        self.session_id(&request).await?;
+        let schema = query.clone().into_builder().schema();
+        Ok(Response::new(flight_info(
+            &query,
+            &schema,
+            request.into_inner(),
+        )?))
+    }
+
+    #[tracing::instrument(
+        name = "api_flight_sql::do_get_tables",
+        level = "debug",
+        skip(self, request),
+        err
+    )]
+    async fn do_get_tables(
+        &self,
+        query: CommandGetTables,
+        request: Request<Ticket>,
+    ) -> std::result::Result<Response<DoGetStream>, Status> {
+        let session_id = self.session_id(&request).await?;
+        let catalog_list = self.catalog_list(&session_id).await?;
+        let access = self.access_control(&session_id).await?;
+        let catalogs = catalogs(catalog_list.as_ref(), query.catalog.as_deref());
+        // The builder applies the name patterns and the table types of the query
+        let mut builder = query.into_builder();
+        for (catalog_name, catalog) in catalogs {
+            for schema_name in catalog.schema_names() {
+                if !schema_visible(&access, &catalog_name, &schema_name) {
+                    continue;
+                }
+                let Some(schema) = catalog.schema(&schema_name) else {
+                    continue;
+                };
+                for table_name in schema.table_names() {
+                    if !table_visible(&access, &catalog_name, &schema_name, &table_name) {
+                        continue;
+                    }
+                    let Some(table) = schema
+                        .table(&table_name)
+                        .await
+                        .context(flight_sql_error::CatalogSnafu)?
+                    else {
+                        continue;
+                    };
+                    builder
+                        .append(
+                            &catalog_name,
+                            &schema_name,
+                            &table_name,
+                            table_type_name(table.table_type()),
+                            table.schema().as_ref(),
+                        )
+                        .context(flight_sql_error::ArrowSnafu)?;
+                }
+            }
+        }
+        let schema = builder.schema();
+        let batch = builder.build().context(flight_sql_error::ArrowSnafu)?;
+        Ok(Response::new(stream_batch(schema, batch)))
+    }
+
+    async fn register_sql_info(&self, _id: i32, _result: &SqlInfo) {}
+}
+
+/// Flight info of a result fetched with a single ticket
+fn flight_info(
+    ticket: &impl ProstMessageExt,
+    schema: &Schema,
+    descriptor: FlightDescriptor,
+) -> Result<FlightInfo> {
+    let ticket = Ticket::new(ticket.as_any().encode_to_vec());
+    Ok(FlightInfo::new()
+        .try_with_schema(schema)
+        .context(flight_sql_error::ArrowSnafu)?
+        .with_endpoint(FlightEndpoint::new().with_ticket(ticket))
+        .with_descriptor(descriptor))
+}
+
+/// Streams the batches of the result as they are produced
+fn stream_result(result: QueryResultStream) -> DoGetStream {
+    let schema = result.schema();
+    let batches = futures::stream::unfold(result, |mut result| async move {
+        let batch = result.next_batch().await?;
+        Some((
+            batch.map_err(|error| FlightError::ExternalError(Box::new(error))),
+            result,
+        ))
+    });
+    Box::pin(
+        FlightDataEncoderBuilder::new()
+            .with_schema(schema)
+            .build(batches)
+            .map_err(Status::from),
+    )
+}
+
+fn stream_batch(schema: SchemaRef, batch: RecordBatch) -> DoGetStream {
+    Box::pin(
+        FlightDataEncoderBuilder::new()
+            .with_schema(schema)
+            .build(futures::stream::iter([Ok(batch)]))
+            .map_err(Status::from),
+    )
+}
+
+/// Rows affected by a DML statement, reported as the first value of its result.
+/// Flight SQL uses -1 when the count is unknown.
+fn affected_rows(records: &[RecordBatch]) -> i64 {
+    let [batch] = records else {
+        return -1;
+    };
+    if batch.num_rows() != 1 || batch.num_columns() == 0 {
+        return -1;
+    }
+    cast(batch.column(0), &DataType::Int64)
+        .ok()
+        .and_then(|column| {
+            let column = column.as_primitive_opt::<Int64Type>()?;
+            column.is_valid(0).then(|| column.value(0))
+        })
+        .unwrap_or(-1)
+}
+
+fn catalogs(
+    catalog_list: &dyn CatalogProviderList,
+    catalog: Option<&str>,
+) -> Vec<(String, Arc<dyn CatalogProvider>)> {
+    catalog_list
+        .catalog_names()
+        .into_iter()
+        .filter(|name| catalog.is_none_or(|catalog| catalog == name.as_str()))
+        .filter_map(|name| {
+            let provider = catalog_list.catalog(&name)?;
+            Some((name, provider))
+        })
+        .collect()
+}
+
+/// Schemas are listed with `USAGE` on them, the information schema with `USAGE`
+/// on its database
+fn schema_visible(access: &AccessControl, catalog: &str, schema: &str) -> bool {
+    if schema.eq_ignore_ascii_case(INFORMATION_SCHEMA) {
+        return access.allows(
+            Privilege::Usage,
+            &GrantObject::Database(catalog.to_string()),
+        );
+    }
+    access
+        .check_schema(
+            Privilege::Usage,
+            &SchemaIdent::new(catalog.to_string(), schema.to_string()),
+        )
+        .is_ok()
+}
+
+/// Tables are listed with `SELECT` on them, the views of the information schema
+/// along with their schema
+fn table_visible(access: &AccessControl, catalog: &str, schema: &str, table: &str) -> bool {
+    if schema.eq_ignore_ascii_case(INFORMATION_SCHEMA) {
+        return schema_visible(access, catalog, schema);
+    }
+    access
+        .check_table(Privilege::Select, &TableIdent::new(catalog, schema, table))
+        .is_ok()
+}
+
+const fn table_type_name(table_type: TableType) -> &'static str {
+    match table_type {
+        TableType::Base => "TABLE",
+        TableType::View => "VIEW",
+        TableType::Temporary => "LOCAL TEMPORARY",
+    }
+}
+
+/// The current database, schema and role of the user's session are the user defaults
+fn session_options(authenticated: &AuthenticatedUser) -> SessionOptions {
+    let AuthenticatedUser {
+        user,
+        role_restriction,
+    } = authenticated;
+    let current = [
+        ("database", user.default_database.clone()),
+        ("schema", user.default_schema.clone()),
+        (
+            "role",
+            role_restriction
+                .clone()
+                .or_else(|| user.default_role.clone()),
+        ),
+    ];
+    SessionOptions {
+        params: current
+            .into_iter()
+            .filter_map(|(name, value)| Some((name.to_string(), ScalarValue::Utf8(Some(value?)))))
+            .collect(),
+        client_info: None,
+        user: Some(user.name.clone()),
+        role_restriction: role_restriction.clone(),
+    }
+}
+
+/// User and password of an `Authorization: Basic <base64(user:password)>` header
+fn basic_credentials(metadata: &MetadataMap) -> Option<(String, String)> {
+    let value = metadata.get(AUTHORIZATION)?.to_str().ok()?;
+    let encoded = value.strip_prefix("Basic ")?;
+    let decoded = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (user, password) = decoded.split_once(':')?;
+    Some((user.to_string(), password.to_string()))
+}
+
+fn bearer_token(metadata: &MetadataMap) -> Option<String> {
+    let value = metadata.get(AUTHORIZATION)?.to_str().ok()?;
+    let token = value.strip_prefix("Bearer ")?.trim();
+    Some(token.to_string())
+}
@@ -0,0 +1,38 @@
+use crate::results::PendingResults;
+use core_executor::service::ExecutionService;
+use core_metastore::Metastore;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// Calls need the bearer token of a session opened with the handshake,
+    /// otherwise calls without a token use an anonymous session of their connection
+    pub require_auth: bool,
+}
+
+#[derive(Clone)]
+pub struct State {
+    pub execution_svc: Arc<dyn ExecutionService>,
+    pub metastore: Arc<dyn Metastore>,
+    pub config: Arc<Config>,
+    pub results: Arc<PendingResults>,
+    /// Secret the ids of the anonymous sessions of the connections are derived with
+    pub connection_secret: Uuid,
+}
+
+impl State {
+    pub fn new(
+        execution_svc: Arc<dyn ExecutionService>,
+        metastore: Arc<dyn Metastore>,
+        config: Config,
+    ) -> Self {
+        Self {
+            execution_svc,
+            metastore,
+            config: Arc::new(config),
+            results: Arc::default(),
+            connection_secret: Uuid::new_v4(),
+        }
+    }
+}
@@ -0,0 +1,282 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+use crate::service::EmbucketFlightSqlService;
+use crate::state::{Config, State};
+use arrow_flight::decode::FlightRecordBatchStream;
+use arrow_flight::error::FlightError;
+use arrow_flight::flight_service_server::FlightService;
+use arrow_flight::sql::server::FlightSqlService;
+use arrow_flight::sql::{
+    CommandGetCatalogs, CommandGetDbSchemas, CommandGetTables, CommandStatementQuery,
+    ProstMessageExt,
+};
+use arrow_flight::{FlightDescriptor, Ticket};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use core_executor::models::{QueryContext, SessionOptions};
+use core_executor::service::{CoreExecutionService, ExecutionService};
+use core_executor::utils::Config as ExecutionConfig;
+use core_history::SlateDBHistoryStore;
+use core_metastore::{SlateDBMetastore, bootstrap_user};
+use datafusion::arrow::array::{AsArray, RecordBatch};
+use datafusion::arrow::datatypes::Int64Type;
+use futures::TryStreamExt;
+use prost::Message;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tonic::metadata::MetadataMap;
+use tonic::transport::server::TcpConnectInfo;
+use tonic::{Code, Request};
+
+async fn make_service(require_auth: bool) -> (EmbucketFlightSqlService, Arc<CoreExecutionService>) {
+    let metastore = Arc::new(SlateDBMetastore::new_in_memory().await);
+    bootstrap_user(metastore.as_ref(), "embucket", "embucket")
+        .await
+        .expect("Failed to create user");
+    let execution_svc = Arc::new(
+        CoreExecutionService::new(
+            metastore.clone(),
+            Arc::new(SlateDBHistoryStore::new_in_memory().await),
+            Arc::new(ExecutionConfig::default()),
+        )
+        .await
+        .expect("Failed to create execution service"),
+    );
+    let service = EmbucketFlightSqlService::new(State::new(
+        execution_svc.clone(),
+        metastore,
+        Config { require_auth },
+    ));
+    (service, execution_svc)
+}
+
+fn command_ticket(command: &impl ProstMessageExt) -> Ticket {
+    Ticket::new(command.as_any().encode_to_vec())
+}
+
+fn basic_auth(user: &str, password: &str) -> MetadataMap {
+    let mut metadata = MetadataMap::new();
+    let credentials = STANDARD.encode(format!("{user}:{password}"));
+    metadata.insert(
+        "authorization",
+        format!("Basic {credentials}").parse().unwrap(),
+    );
+    metadata
+}
+
+fn authorized<T>(message: T, session_id: &str) -> Request<T> {
+    let mut request = Request::new(message);
+    request.metadata_mut().insert(
+        "authorization",
+        format!("Bearer {session_id}").parse().unwrap(),
+    );
+    request
+}
+
+async fn fetch(
+    service: &EmbucketFlightSqlService,
+    session_id: &str,
+    ticket: Ticket,
+) -> Vec<RecordBatch> {
+    let stream = service
+        .do_get(authorized(ticket, session_id))
+        .await
+        .unwrap()
+        .into_inner();
+    FlightRecordBatchStream::new_from_flight_data(stream.map_err(FlightError::from))
+        .try_collect()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_flight_sql_statements_and_metadata() {
+    let (service, execution_svc) = make_service(true).await;
+    let error = service
+        .login(&basic_auth("embucket", "wrong"))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("Invalid credentials"));
+    let session_id = service
+        .login(&basic_auth("embucket", "embucket"))
+        .await
+        .unwrap();
+
+    // Statement results are fetched with the ticket of their flight info
+    let info = service
+        .get_flight_info_statement(
+            CommandStatementQuery {
+                query: "SELECT 40 + 2 AS answer".to_string(),
+                transaction_id: None,
+            },
+            authorized(FlightDescriptor::new_cmd(Vec::new()), &session_id),
+        )
+        .await
+        .unwrap()
+        .into_inner();
+    let ticket = info.endpoint[0].ticket.clone().unwrap();
+    let batches = fetch(&service, &session_id, ticket.clone()).await;
+    assert_eq!(
+        batches[0].column(0).as_primitive::<Int64Type>().value(0),
+        42
+    );
+    // A result is streamed only once
+    let status = service
+        .do_get(authorized(ticket, &session_id))
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(status.code(), Code::NotFound);
+
+    let catalogs = fetch(
+        &service,
+        &session_id,
+        command_ticket(&CommandGetCatalogs {}),
+    )
+    .await;
+    let catalog_names = catalogs[0].column(0).as_string::<i32>();
+    assert!(catalog_names.iter().any(|name| name == Some("embucket")));
+
+    execution_svc
+        .query(
+            &session_id,
+            "CREATE TABLE embucket.public.flight (id INT)",
+            QueryContext::default(),
+        )
+        .await
+        .unwrap();
+    let tables = fetch(
+        &service,
+        &session_id,
+        command_ticket(&CommandGetTables {
+            catalog: Some("embucket".to_string()),
+            db_schema_filter_pattern: Some("public".to_string()),
+            table_name_filter_pattern: Some("flight".to_string()),
+            table_types: Vec::new(),
+            include_schema: false,
+        }),
+    )
+    .await;
+    assert_eq!(tables[0].num_rows(), 1);
+
+    // Calls need the bearer token of a session
+    let status = service
+        .get_flight_info_catalogs(
+            CommandGetCatalogs {},
+            Request::new(FlightDescriptor::new_cmd(Vec::new())),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn test_flight_sql_connection_sessions() {
+    let (service, execution_svc) = make_service(false).await;
+    let connection = |port: u16| {
+        let mut request = Request::new(FlightDescriptor::new_cmd(Vec::new()));
+        request.extensions_mut().insert(TcpConnectInfo {
+            local_addr: None,
+            remote_addr: Some(SocketAddr::from(([127, 0, 0, 1], port))),
+        });
+        request
+    };
+
+    // Without auth every connection gets an anonymous session of its own
+    let session_id = service.connection_session(&connection(5001)).await.unwrap();
+    assert_eq!(
+        service.connection_session(&connection(5001)).await.unwrap(),
+        session_id
+    );
+    assert_ne!(
+        service.connection_session(&connection(5002)).await.unwrap(),
+        session_id
+    );
+    let catalogs = service
+        .get_flight_info_catalogs(CommandGetCatalogs {}, connection(5001))
+        .await
+        .unwrap()
+        .into_inner();
+    let ticket = catalogs.endpoint[0].ticket.clone().unwrap();
+    assert_eq!(
+        fetch(&service, &session_id, ticket).await[0].num_columns(),
+        1
+    );
+
+    // Anonymous sessions work with tables in the bootstrapped schema
+    for query in [
+        "CREATE TABLE embucket.public.anonymous (id INT)",
+        "INSERT INTO embucket.public.anonymous VALUES (1), (2)",
+        "SELECT COUNT(*) FROM embucket.public.anonymous",
+    ] {
+        let info = service
+            .get_flight_info_statement(
+                CommandStatementQuery {
+                    query: query.to_string(),
+                    transaction_id: None,
+                },
+                connection(5001),
+            )
+            .await
+            .unwrap()
+            .into_inner();
+        let ticket = info.endpoint[0].ticket.clone().unwrap();
+        let batches = fetch(&service, &session_id, ticket).await;
+        if query.starts_with("SELECT") {
+            assert_eq!(batches[0].column(0).as_primitive::<Int64Type>().value(0), 2);
+        }
+    }
+    // Metadata lists only the schemas and tables the role of the session can use
+    execution_svc
+        .create_session_with_options(
+            "admin",
+            SessionOptions {
+                user: Some("embucket".to_string()),
+                ..SessionOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+    for query in [
+        "CREATE SCHEMA embucket.private",
+        "CREATE TABLE embucket.public.private (id INT)",
+    ] {
+        execution_svc
+            .query("admin", query, QueryContext::default())
+            .await
+            .unwrap();
+    }
+    let schemas = fetch(
+        &service,
+        &session_id,
+        command_ticket(&CommandGetDbSchemas {
+            catalog: Some("embucket".to_string()),
+            db_schema_filter_pattern: None,
+        }),
+    )
+    .await;
+    let schema_names: Vec<_> = schemas
+        .iter()
+        .flat_map(|batch| batch.column(1).as_string::<i32>().iter())
+        .flatten()
+        .collect();
+    assert!(schema_names.contains(&"public"));
+    assert!(!schema_names.contains(&"private"));
+    let tables = fetch(
+        &service,
+        &session_id,
+        command_ticket(&CommandGetTables {
+            catalog: Some("embucket".to_string()),
+            db_schema_filter_pattern: Some("public".to_string()),
+            table_name_filter_pattern: None,
+            table_types: Vec::new(),
+            include_schema: false,
+        }),
+    )
+    .await;
+    let table_names: Vec<_> = tables
+        .iter()
+        .flat_map(|batch| batch.column(2).as_string::<i32>().iter())
+        .flatten()
+        .collect();
+    assert_eq!(table_names, vec!["anonymous"]);
+}
@@ -20,6 +20,7 @@ pub enum Operation {
     GetTable,
     DeleteTable,
     ListTables,
+    Authenticate,
 }
 
 #[derive(Snafu)]
@@ -33,6 +34,18 @@ pub enum Error {
         #[snafu(implicit)]
         location: Location,
     },
+
+    #[snafu(display("[IcebergAPI] Missing Basic credentials in Authorization header"))]
+    MissingCredentials {
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("[IcebergAPI] Invalid credentials"))]
+    InvalidCredentials {
+        #[snafu(implicit)]
+        location: Location,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -60,6 +73,25 @@ impl IntoResponse for Error {
         tracing::error!(error_message = %self.output_msg(), "Iceberg API error");
         let metastore_error = match self {
             Self::Metastore { source, .. } => source,
+            Self::MissingCredentials { .. } | Self::InvalidCredentials { .. } => {
+                let code = http::StatusCode::UNAUTHORIZED;
+                // Record the result as part of the current span.
+                tracing::Span::current().record("status_code", code.as_u16());
+                let error = ErrorResponse {
+                    error: ErrorResponseMessage {
+                        message: self.to_string(),
+                        r#type: "NotAuthorizedException".to_string(),
+                        code: code.as_u16(),
+                    },
+                    status_code: code.as_u16(),
+                };
+                return (
+                    code,
+                    [(http::header::WWW_AUTHENTICATE, r#"Basic realm="iceberg""#)],
+                    Json(error),
+                )
+                    .into_response();
+            }
         };
 
         let message = metastore_error.to_string();
@@ -71,7 +103,11 @@ impl IntoResponse for Error {
             | core_metastore::Error::SchemaAlreadyExists { .. }
             | core_metastore::Error::TableAlreadyExists { .. }
             | core_metastore::Error::VolumeInUse { .. }
-            | core_metastore::Error::DatabaseInUse { .. } => http::StatusCode::CONFLICT,
+            | core_metastore::Error::DatabaseInUse { .. }
+            | core_metastore::Error::FileFormatAlreadyExists { .. }
+            | core_metastore::Error::StageAlreadyExists { .. }
+            | core_metastore::Error::UserAlreadyExists { .. }
+            | core_metastore::Error::RoleAlreadyExists { .. } => http::StatusCode::CONFLICT,
             core_metastore::Error::TableRequirementFailed { .. } => {
                 http::StatusCode::UNPROCESSABLE_ENTITY
             }
@@ -85,6 +121,10 @@ impl IntoResponse for Error {
             | core_metastore::Error::DatabaseNotFound { .. }
             | core_metastore::Error::SchemaNotFound { .. }
             | core_metastore::Error::TableNotFound { .. }
+            | core_metastore::Error::FileFormatNotFound { .. }
+            | core_metastore::Error::StageNotFound { .. }
+            | core_metastore::Error::UserNotFound { .. }
+            | core_metastore::Error::RoleNotFound { .. }
             | core_metastore::Error::ObjectNotFound { .. } => http::StatusCode::NOT_FOUND,
             core_metastore::Error::ObjectStore { .. }
             | core_metastore::Error::ObjectStorePath { .. }
@@ -96,6 +136,8 @@ impl IntoResponse for Error {
             | core_metastore::Error::Serde { .. }
             | core_metastore::Error::TableMetadataBuilder { .. }
             | core_metastore::Error::TableObjectStoreNotFound { .. }
+            | core_metastore::Error::PasswordHash { .. }
+            | core_metastore::Error::TaskJoin { .. }
             | core_metastore::Error::UrlParse { .. } => http::StatusCode::INTERNAL_SERVER_ERROR,
         };
 
@@ -0,0 +1,45 @@
+use crate::error::{self as api_iceberg_rest_error, Operation, Result};
+use crate::state::State as AppState;
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::IntoResponse;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use snafu::{OptionExt, ResultExt};
+
+/// User and password of an `Authorization: Basic <base64(user:password)>` header
+fn basic_credentials(headers: &http::HeaderMap) -> Option<(String, String)> {
+    let value = headers.get(http::header::AUTHORIZATION)?.to_str().ok()?;
+    let encoded = value.strip_prefix("Basic ")?;
+    let decoded = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (user, password) = decoded.split_once(':')?;
+    Some((user.to_string(), password.to_string()))
+}
+
+/// Requires the credentials of a metastore user with every catalog request
+#[tracing::instrument(
+    name = "api_iceberg_rest::layer::require_auth",
+    level = "trace",
+    skip(state, req, next),
+    err
+)]
+pub async fn require_auth(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<impl IntoResponse> {
+    let (user, password) = basic_credentials(req.headers())
+        .context(api_iceberg_rest_error::MissingCredentialsSnafu)?;
+    state
+        .metastore
+        .authenticate(&user, &password)
+        .await
+        .context(api_iceberg_rest_error::MetastoreSnafu {
+            operation: Operation::Authenticate,
+        })?
+        // The catalog isn't bound to a role, role restricted access tokens can't use it
+        .filter(|authenticated| authenticated.role_restriction.is_none())
+        .context(api_iceberg_rest_error::InvalidCredentialsSnafu)?;
+
+    Ok(next.run(req).await)
+}
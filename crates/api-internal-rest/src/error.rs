@@ -118,7 +118,11 @@ impl IntoResponse for Error {
                 | core_metastore::Error::SchemaAlreadyExists { .. }
                 | core_metastore::Error::TableAlreadyExists { .. }
                 | core_metastore::Error::VolumeInUse { .. }
-                | core_metastore::Error::DatabaseInUse { .. } => http::StatusCode::CONFLICT,
+                | core_metastore::Error::DatabaseInUse { .. }
+                | core_metastore::Error::FileFormatAlreadyExists { .. }
+                | core_metastore::Error::StageAlreadyExists { .. }
+                | core_metastore::Error::UserAlreadyExists { .. }
+                | core_metastore::Error::RoleAlreadyExists { .. } => http::StatusCode::CONFLICT,
                 core_metastore::Error::TableRequirementFailed { .. } => {
                     http::StatusCode::UNPROCESSABLE_ENTITY
                 }
@@ -132,6 +136,10 @@ impl IntoResponse for Error {
                 | core_metastore::Error::DatabaseNotFound { .. }
                 | core_metastore::Error::SchemaNotFound { .. }
                 | core_metastore::Error::TableNotFound { .. }
+                | core_metastore::Error::FileFormatNotFound { .. }
+                | core_metastore::Error::StageNotFound { .. }
+                | core_metastore::Error::UserNotFound { .. }
+                | core_metastore::Error::RoleNotFound { .. }
                 | core_metastore::Error::ObjectNotFound { .. } => http::StatusCode::NOT_FOUND,
                 core_metastore::Error::ObjectStore { .. }
                 | core_metastore::Error::ObjectStorePath { .. }
@@ -143,6 +151,8 @@ impl IntoResponse for Error {
                 | core_metastore::Error::Serde { .. }
                 | core_metastore::Error::TableMetadataBuilder { .. }
                 | core_metastore::Error::TableObjectStoreNotFound { .. }
+                | core_metastore::Error::PasswordHash { .. }
+                | core_metastore::Error::TaskJoin { .. }
                 | core_metastore::Error::UrlParse { .. } => http::StatusCode::INTERNAL_SERVER_ERROR,
             },
         };
@@ -7,6 +7,7 @@ use axum::{
 use snafu::ResultExt;
 
 use core_history::{QueryIdParam, QueryRecord, QueryRecordId};
+use core_metastore::data_file_cache::DataFileCacheStats;
 #[allow(clippy::wildcard_imports)]
 use core_metastore::{
     error::{self as metastore_error},
@@ -188,3 +189,11 @@ pub async fn query_by_id(
 
     Ok(Json(RwObject::new(query_record)))
 }
+
+/// Counters of the data files cache, `null` if the cache is disabled
+#[tracing::instrument(level = "debug", skip(state), ret(level = tracing::Level::TRACE))]
+pub async fn data_file_cache_stats(
+    State(state): State<AppState>,
+) -> Json<Option<DataFileCacheStats>> {
+    Json(state.metastore.data_file_cache_stats())
+}
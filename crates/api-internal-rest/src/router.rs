@@ -3,8 +3,8 @@ use axum::Router;
 use axum::routing::{delete, get, post, put};
 
 use crate::handlers::{
-    create_database, create_volume, delete_volume, get_database, get_volume, list_databases,
-    list_volumes, query_by_id, update_volume,
+    create_database, create_volume, data_file_cache_stats, delete_volume, get_database, get_volume,
+    list_databases, list_volumes, query_by_id, update_volume,
 };
 
 pub fn create_router() -> Router<State> {
@@ -18,4 +18,5 @@ pub fn create_router() -> Router<State> {
         .route("/databases", post(create_database))
         .route("/databases/{databaseName}", get(get_database))
         .route("/queries/{queryId}", get(query_by_id))
+        .route("/data-file-cache/stats", get(data_file_cache_stats))
 }
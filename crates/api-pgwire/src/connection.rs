@@ -0,0 +1,801 @@
+use crate::error::{self as pgwire_error, Error, Result};
+use crate::messages::{
+    BackendMessage, FieldDescription, FrontendMessage, StartupMessage, TEXT_FORMAT, Target,
+    read_message, read_startup_message,
+};
+use crate::pg_catalog::{self, CatalogSession};
+use crate::sql::{self, PARAMETERS, SessionCommand, StatementKind};
+use crate::state::State;
+use crate::tls::Stream;
+use crate::types;
+use bytes::BytesMut;
+use core_executor::models::{QueryContext, SessionOptions};
+use core_executor::session::SESSION_KEEP_ALIVE_EXPIRATION_SECONDS;
+use core_metastore::AuthenticatedUser;
+use datafusion::arrow::array::{Array, AsArray, RecordBatch, StringArray};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Field, Int64Type, Schema, SchemaRef};
+use datafusion::scalar::ScalarValue;
+use error_stack::ErrorExt;
+use snafu::{OptionExt, ResultExt, ensure};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio_rustls::rustls::ServerConfig;
+use uuid::Uuid;
+
+/// Output is sent once this much of it is buffered, and at the end of each query
+const FLUSH_THRESHOLD: usize = 64 * 1024;
+
+/// What a statement returned
+enum Outcome {
+    Rows {
+        schema: SchemaRef,
+        records: Vec<RecordBatch>,
+    },
+    Command(String),
+    Empty,
+}
+
+struct PreparedStatement {
+    query: String,
+    param_types: Vec<u32>,
+}
+
+/// A statement bound to its parameters, run on the first `Describe` or `Execute`
+struct Portal {
+    query: String,
+    result_formats: Vec<i16>,
+    outcome: Option<Outcome>,
+    /// Rows already sent by executes with a row limit
+    sent_rows: usize,
+}
+
+/// A client connection, holding the session its statements run in
+pub struct Connection<S> {
+    stream: BufReader<Stream<S>>,
+    out: BytesMut,
+    state: State,
+    session_id: String,
+    session_options: SessionOptions,
+    user: String,
+    process_id: i32,
+    /// Connection parameters, set with `SET` and reported in `pg_settings`
+    parameters: HashMap<&'static str, String>,
+    statements: HashMap<String, PreparedStatement>,
+    portals: HashMap<String, Portal>,
+    /// Messages are skipped until the next `Sync` after an error of the extended protocol
+    skip_until_sync: bool,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Connection<S> {
+    pub fn new(stream: S, state: State) -> Self {
+        Self {
+            stream: BufReader::new(Stream::Plain(stream)),
+            out: BytesMut::new(),
+            state,
+            session_id: Uuid::new_v4().to_string(),
+            session_options: SessionOptions::default(),
+            user: String::new(),
+            process_id: 0,
+            parameters: PARAMETERS
+                .iter()
+                .map(|(name, value, _)| (*name, (*value).to_string()))
+                .collect(),
+            statements: HashMap::new(),
+            portals: HashMap::new(),
+            skip_until_sync: false,
+        }
+    }
+
+    /// Serves the connection until the client terminates it, its session is
+    /// deleted when it ends
+    pub async fn run(mut self) -> Result<()> {
+        let parameters = loop {
+            match read_startup_message(&mut self.stream)
+                .await
+                .context(pgwire_error::IoSnafu)?
+            {
+                StartupMessage::SslRequest if !self.stream.get_ref().is_tls() => {
+                    if let Some(config) = self.state.config.tls.clone() {
+                        self.stream
+                            .write_all(b"S")
+                            .await
+                            .context(pgwire_error::IoSnafu)?;
+                        self.stream.flush().await.context(pgwire_error::IoSnafu)?;
+                        self = self.encrypt(config).await?;
+                    } else {
+                        self.decline_encryption().await?;
+                    }
+                }
+                // GSSAPI encryption is not supported, clients go on without it or give up
+                StartupMessage::SslRequest | StartupMessage::GssEncRequest => {
+                    self.decline_encryption().await?;
+                }
+                // Queries can't be cancelled yet
+                StartupMessage::CancelRequest { .. } => return Ok(()),
+                StartupMessage::Startup { parameters } => break parameters,
+            }
+        };
+        let result = self.serve(&parameters).await;
+        if !self.user.is_empty() {
+            let _ = self
+                .state
+                .execution_svc
+                .delete_session(&self.session_id)
+                .await;
+        }
+        result
+    }
+
+    async fn serve(&mut self, parameters: &HashMap<String, String>) -> Result<()> {
+        if !self.startup(parameters).await? {
+            return Ok(());
+        }
+        while let Some(message) = read_message(&mut self.stream)
+            .await
+            .context(pgwire_error::IoSnafu)?
+        {
+            if self.skip_until_sync
+                && !matches!(message, FrontendMessage::Sync | FrontendMessage::Terminate)
+            {
+                continue;
+            }
+            match message {
+                FrontendMessage::Query(query) => {
+                    self.simple_query(&query).await?;
+                    self.send(&BackendMessage::ReadyForQuery);
+                    self.flush().await?;
+                }
+                FrontendMessage::Sync => {
+                    self.skip_until_sync = false;
+                    self.send(&BackendMessage::ReadyForQuery);
+                    self.flush().await?;
+                }
+                FrontendMessage::Flush => self.flush().await?,
+                FrontendMessage::Terminate => break,
+                message => {
+                    if let Err(error) = self.extended_query(message).await {
+                        if matches!(error, Error::Io { .. }) {
+                            return Err(error);
+                        }
+                        self.send_error(&error);
+                        self.skip_until_sync = true;
+                    }
+                }
+            }
+        }
+        self.flush().await
+    }
+
+    /// Upgrades the connection to TLS, the handshake must follow the
+    /// `SSLRequest` without any data sent in between
+    async fn encrypt(mut self, config: Arc<ServerConfig>) -> Result<Self> {
+        ensure!(
+            self.stream.buffer().is_empty(),
+            pgwire_error::UnencryptedDataSnafu
+        );
+        let stream = self
+            .stream
+            .into_inner()
+            .encrypt(config)
+            .await
+            .context(pgwire_error::IoSnafu)?;
+        self.stream = BufReader::new(stream);
+        Ok(self)
+    }
+
+    /// Clients go on unencrypted or give up
+    async fn decline_encryption(&mut self) -> Result<()> {
+        self.stream
+            .write_all(b"N")
+            .await
+            .context(pgwire_error::IoSnafu)?;
+        self.stream.flush().await.context(pgwire_error::IoSnafu)
+    }
+
+    /// Authenticates the user and starts the session of the connection,
+    /// returns false when the client is turned away
+    async fn startup(&mut self, parameters: &HashMap<String, String>) -> Result<bool> {
+        match self.authenticate(parameters).await {
+            Ok(options) => self.session_options = options,
+            Err(error) => {
+                self.send_error(&error);
+                self.flush().await?;
+                return Ok(false);
+            }
+        }
+        if let Err(error) = self
+            .state
+            .execution_svc
+            .create_session_with_options(&self.session_id, self.session_options.clone())
+            .await
+        {
+            self.send_error(&error.into());
+            self.flush().await?;
+            return Ok(false);
+        }
+
+        let [a, b, c, d, e, f, g, h, ..] = *Uuid::new_v4().as_bytes();
+        self.process_id = i32::from_be_bytes([a, b, c, d]) & i32::MAX;
+        if let Some(application_name) = parameters.get("application_name") {
+            self.parameters
+                .insert("application_name", application_name.clone());
+        }
+        self.parameters
+            .insert("session_authorization", self.user.clone());
+        self.send(&BackendMessage::AuthenticationOk);
+        for (name, _, reported) in PARAMETERS {
+            if reported {
+                let value = self.parameters.get(name).cloned().unwrap_or_default();
+                self.send(&BackendMessage::ParameterStatus {
+                    name: name.to_string(),
+                    value,
+                });
+            }
+        }
+        self.send(&BackendMessage::ParameterStatus {
+            name: "session_authorization".to_string(),
+            value: self.user.clone(),
+        });
+        self.send(&BackendMessage::BackendKeyData {
+            process_id: self.process_id,
+            secret_key: i32::from_be_bytes([e, f, g, h]),
+        });
+        self.send(&BackendMessage::ReadyForQuery);
+        self.flush().await?;
+        Ok(true)
+    }
+
+    /// Options of the session of the user, the password can also be one of the
+    /// user's programmatic access tokens. Passwords are only accepted over TLS,
+    /// without authentication the session is an anonymous one whatever the
+    /// user name of the client
+    async fn authenticate(
+        &mut self,
+        parameters: &HashMap<String, String>,
+    ) -> Result<SessionOptions> {
+        let name = parameters.get("user").cloned().unwrap_or_default();
+        let mut options = if self.state.config.require_auth {
+            ensure!(
+                self.stream.get_ref().is_tls(),
+                pgwire_error::EncryptionRequiredSnafu { user: &name }
+            );
+            self.send(&BackendMessage::AuthenticationCleartextPassword);
+            self.flush().await?;
+            let password = match read_message(&mut self.stream)
+                .await
+                .context(pgwire_error::IoSnafu)?
+            {
+                Some(FrontendMessage::Password(password)) => password,
+                _ => String::new(),
+            };
+            let authenticated = self
+                .state
+                .metastore
+                .authenticate(&name, &password)
+                .await
+                .context(pgwire_error::MetastoreSnafu)?
+                .context(pgwire_error::AuthenticationFailedSnafu { user: &name })?;
+            session_options(&authenticated)
+        } else {
+            SessionOptions::default()
+        };
+
+        // The database of the connection is the current one, when there is such a database
+        if let Some(database) = parameters.get("database")
+            && self
+                .state
+                .metastore
+                .get_database(database)
+                .await
+                .context(pgwire_error::MetastoreSnafu)?
+                .is_some()
+        {
+            options.params.insert(
+                "database".to_string(),
+                ScalarValue::Utf8(Some(database.clone())),
+            );
+        }
+        self.user = if name.is_empty() {
+            "embucket".to_string()
+        } else {
+            name
+        };
+        Ok(options)
+    }
+
+    /// Keeps the session of the connection alive, connections outlive the
+    /// inactivity expiry of sessions
+    async fn session(&self) -> Result<()> {
+        let execution_svc = &self.state.execution_svc;
+        if !execution_svc
+            .update_session_expiry(&self.session_id, SESSION_KEEP_ALIVE_EXPIRATION_SECONDS)
+            .await?
+        {
+            execution_svc
+                .create_session_with_options(&self.session_id, self.session_options.clone())
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Runs the statements of a simple query, stopping at the first error
+    async fn simple_query(&mut self, query: &str) -> Result<()> {
+        let backslash_escapes = self.backslash_escapes().await;
+        let statements = sql::split_statements(query, backslash_escapes);
+        if statements.is_empty() {
+            self.send(&BackendMessage::EmptyQueryResponse);
+            return Ok(());
+        }
+        for statement in statements {
+            match self.run_statement(statement).await {
+                Ok(outcome) => {
+                    let fields = outcome_fields(&outcome, &[]);
+                    if matches!(outcome, Outcome::Rows { .. }) {
+                        self.send(&BackendMessage::RowDescription(fields.clone()));
+                    }
+                    self.send_outcome(&outcome, &fields, 0, 0).await?;
+                }
+                Err(error @ Error::Io { .. }) => return Err(error),
+                Err(error) => {
+                    self.send_error(&error);
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn extended_query(&mut self, message: FrontendMessage) -> Result<()> {
+        match message {
+            FrontendMessage::Parse {
+                name,
+                query,
+                param_types,
+            } => {
+                self.statements
+                    .insert(name, PreparedStatement { query, param_types });
+                self.send(&BackendMessage::ParseComplete);
+            }
+            FrontendMessage::Bind {
+                portal,
+                statement,
+                param_formats,
+                params,
+                result_formats,
+            } => {
+                let query = self.bind(&statement, &param_formats, &params).await?;
+                self.portals.insert(
+                    portal,
+                    Portal {
+                        query,
+                        result_formats,
+                        outcome: None,
+                        sent_rows: 0,
+                    },
+                );
+                self.send(&BackendMessage::BindComplete);
+            }
+            FrontendMessage::Describe {
+                target: Target::Statement,
+                name,
+            } => self.describe_statement(&name).await?,
+            FrontendMessage::Describe {
+                target: Target::Portal,
+                name,
+            } => {
+                let portal = self.take_portal(&name).await?;
+                match &portal.outcome {
+                    Some(Outcome::Rows { schema, .. }) if !schema.fields().is_empty() => {
+                        let fields = types::field_descriptions(schema, &portal.result_formats);
+                        self.send(&BackendMessage::RowDescription(fields));
+                    }
+                    _ => self.send(&BackendMessage::NoData),
+                }
+                self.portals.insert(name, portal);
+            }
+            FrontendMessage::Execute { portal, max_rows } => {
+                let mut state = self.take_portal(&portal).await?;
+                let result = match &state.outcome {
+                    Some(outcome) => {
+                        let fields = outcome_fields(outcome, &state.result_formats);
+                        self.send_outcome(outcome, &fields, state.sent_rows, max_rows)
+                            .await
+                    }
+                    None => Ok(0),
+                };
+                state.sent_rows += result.as_ref().copied().unwrap_or_default();
+                self.portals.insert(portal, state);
+                result?;
+            }
+            FrontendMessage::Close { target, name } => {
+                match target {
+                    Target::Statement => self.statements.remove(&name).map(|_| ()),
+                    Target::Portal => self.portals.remove(&name).map(|_| ()),
+                };
+                self.send(&BackendMessage::CloseComplete);
+            }
+            FrontendMessage::Password(_) => {
+                return pgwire_error::UnsupportedMessageSnafu { tag: 'p' }.fail();
+            }
+            FrontendMessage::Unsupported(tag) => {
+                return pgwire_error::UnsupportedMessageSnafu {
+                    tag: char::from(tag),
+                }
+                .fail();
+            }
+            FrontendMessage::Query(_)
+            | FrontendMessage::Sync
+            | FrontendMessage::Flush
+            | FrontendMessage::Terminate => {}
+        }
+        Ok(())
+    }
+
+    /// Query of the statement with its parameters bound
+    async fn bind(
+        &self,
+        statement: &str,
+        param_formats: &[i16],
+        params: &[Option<bytes::Bytes>],
+    ) -> Result<String> {
+        let prepared = self
+            .statements
+            .get(statement)
+            .context(pgwire_error::UnknownStatementSnafu { name: statement })?;
+        let backslash_escapes = self.backslash_escapes().await;
+        let expected = sql::parameter_count(&prepared.query, backslash_escapes);
+        if params.len() < expected {
+            return pgwire_error::ParameterCountSnafu {
+                given: params.len(),
+                expected,
+            }
+            .fail();
+        }
+        let literals = params
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let type_oid = prepared.param_types.get(index).copied().unwrap_or_default();
+                let format = match param_formats {
+                    [] => TEXT_FORMAT,
+                    [format] => *format,
+                    formats => formats.get(index).copied().unwrap_or(TEXT_FORMAT),
+                };
+                types::parameter_literal(type_oid, format, value.as_deref(), backslash_escapes)
+                    .context(pgwire_error::UnsupportedParameterFormatSnafu { index: index + 1 })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(sql::bind_parameters(
+            &prepared.query,
+            &literals,
+            backslash_escapes,
+        ))
+    }
+
+    /// Types of the parameters of a prepared statement and the columns of its rows,
+    /// planned with NULL parameters
+    async fn describe_statement(&mut self, name: &str) -> Result<()> {
+        let prepared = self
+            .statements
+            .get(name)
+            .context(pgwire_error::UnknownStatementSnafu { name })?;
+        let backslash_escapes = self.backslash_escapes().await;
+        let count = sql::parameter_count(&prepared.query, backslash_escapes)
+            .max(prepared.param_types.len());
+        // Parameters of unspecified types are sent as text
+        let param_types = (0..count)
+            .map(|index| match prepared.param_types.get(index) {
+                Some(0) | None => types::TEXT.oid,
+                Some(type_oid) => *type_oid,
+            })
+            .collect();
+        let nulls = vec!["NULL".to_string(); count];
+        let query = sql::bind_parameters(&prepared.query, &nulls, backslash_escapes);
+        self.send(&BackendMessage::ParameterDescription(param_types));
+
+        let schema = if sql::session_command(&query).is_some()
+            || sql::is_empty_statement(&query)
+            || sql::statement_kind(&query) != StatementKind::Rows
+        {
+            None
+        } else if let Some(result) = self.catalog_query(&query).await? {
+            Some(result?.0)
+        } else {
+            let result = self
+                .state
+                .execution_svc
+                .describe_query(&self.session_id, &query, QueryContext::default())
+                .await?;
+            Some(result.schema)
+        };
+        match schema {
+            Some(schema) if !schema.fields().is_empty() => {
+                let fields = types::field_descriptions(&schema, &[]);
+                self.send(&BackendMessage::RowDescription(fields));
+            }
+            _ => self.send(&BackendMessage::NoData),
+        }
+        Ok(())
+    }
+
+    /// The portal, run unless it already was
+    async fn take_portal(&mut self, name: &str) -> Result<Portal> {
+        let mut portal = self
+            .portals
+            .remove(name)
+            .context(pgwire_error::UnknownPortalSnafu { name })?;
+        if portal.outcome.is_none() {
+            let outcome = if sql::is_empty_statement(&portal.query) {
+                Ok(Outcome::Empty)
+            } else {
+                self.run_statement(&portal.query).await
+            };
+            match outcome {
+                Ok(outcome) => portal.outcome = Some(outcome),
+                Err(error) => {
+                    self.portals.insert(name.to_string(), portal);
+                    return Err(error);
+                }
+            }
+        }
+        Ok(portal)
+    }
+
+    async fn run_statement(&mut self, statement: &str) -> Result<Outcome> {
+        if let Some(command) = sql::session_command(statement) {
+            return Ok(self.session_command(command));
+        }
+        if let Some(result) = self.catalog_query(statement).await? {
+            let (schema, records) = result?;
+            return Ok(Outcome::Rows { schema, records });
+        }
+        let result = self
+            .state
+            .execution_svc
+            .query(&self.session_id, statement, QueryContext::default())
+            .await?;
+        Ok(match sql::statement_kind(statement) {
+            StatementKind::Rows => Outcome::Rows {
+                schema: result.schema,
+                records: result.records,
+            },
+            StatementKind::Dml(tag) => {
+                Outcome::Command(format!("{tag} {}", affected_rows(&result.records)))
+            }
+            StatementKind::Command(tag) => Outcome::Command(tag),
+        })
+    }
+
+    fn session_command(&mut self, command: SessionCommand) -> Outcome {
+        match command {
+            SessionCommand::Set { name, value } => {
+                self.set_parameter(name, value);
+                Outcome::Command("SET".to_string())
+            }
+            SessionCommand::Reset(name) => {
+                let value = PARAMETERS
+                    .iter()
+                    .find(|(parameter, ..)| *parameter == name)
+                    .map(|(_, value, _)| (*value).to_string())
+                    .unwrap_or_default();
+                self.set_parameter(name, value);
+                Outcome::Command("RESET".to_string())
+            }
+            SessionCommand::Show(name) => {
+                let value = self.parameters.get(name).cloned().unwrap_or_default();
+                let schema = Arc::new(Schema::new(vec![Field::new(name, DataType::Utf8, false)]));
+                let records = RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(StringArray::from(vec![value]))],
+                )
+                .map_or_else(|_| Vec::new(), |batch| vec![batch]);
+                Outcome::Rows { schema, records }
+            }
+            SessionCommand::Transaction(tag) => Outcome::Command(tag.to_string()),
+            SessionCommand::DiscardAll => {
+                self.statements.clear();
+                self.portals.clear();
+                Outcome::Command("DISCARD ALL".to_string())
+            }
+            SessionCommand::Deallocate(Some(name)) => {
+                self.statements.remove(&name);
+                Outcome::Command("DEALLOCATE".to_string())
+            }
+            SessionCommand::Deallocate(None) => {
+                self.statements.clear();
+                Outcome::Command("DEALLOCATE ALL".to_string())
+            }
+        }
+    }
+
+    fn set_parameter(&mut self, name: &'static str, value: String) {
+        let reported = PARAMETERS
+            .iter()
+            .any(|(parameter, _, reported)| *parameter == name && *reported);
+        if reported {
+            self.send(&BackendMessage::ParameterStatus {
+                name: name.to_string(),
+                value: value.clone(),
+            });
+        }
+        self.parameters.insert(name, value);
+    }
+
+    /// Result of a statement reading the `pg_catalog`, `None` for the other statements,
+    /// which run in the session kept alive here
+    async fn catalog_query(
+        &self,
+        statement: &str,
+    ) -> Result<Option<Result<(SchemaRef, Vec<RecordBatch>)>>> {
+        self.session().await?;
+        let session = self
+            .state
+            .execution_svc
+            .get_session(&self.session_id)
+            .await?;
+        let catalog_session = CatalogSession {
+            catalog_list: session.ctx.state().catalog_list().clone(),
+            database: session
+                .get_session_variable("database")
+                .unwrap_or_else(|| "embucket".to_string()),
+            schema: session
+                .get_session_variable("schema")
+                .unwrap_or_else(|| "public".to_string()),
+            user: &self.user,
+            parameters: &self.parameters,
+            process_id: self.process_id,
+        };
+        Ok(pg_catalog::query(statement, &catalog_session)
+            .await
+            .map(|result| result.context(pgwire_error::PgCatalogSnafu)))
+    }
+
+    /// Whether backslashes escape characters in the string literals of the
+    /// session dialect, as in Snowflake
+    async fn backslash_escapes(&self) -> bool {
+        match self.state.execution_svc.get_session(&self.session_id).await {
+            Ok(session) => session
+                .ctx
+                .state()
+                .config()
+                .options()
+                .sql_parser
+                .dialect
+                .as_str()
+                .eq_ignore_ascii_case("snowflake"),
+            Err(_) => false,
+        }
+    }
+
+    /// Sends the rows of the outcome, from the row `skip` on and at most `max_rows`
+    /// of them unless it is zero, followed by its completion. Returns the number of
+    /// rows sent.
+    async fn send_outcome(
+        &mut self,
+        outcome: &Outcome,
+        fields: &[FieldDescription],
+        skip: usize,
+        max_rows: usize,
+    ) -> Result<usize> {
+        let records = match outcome {
+            Outcome::Rows { records, .. } => records,
+            Outcome::Command(tag) => {
+                self.send(&BackendMessage::CommandComplete(tag.clone()));
+                return Ok(0);
+            }
+            Outcome::Empty => {
+                self.send(&BackendMessage::EmptyQueryResponse);
+                return Ok(0);
+            }
+        };
+        let limit = if max_rows == 0 { usize::MAX } else { max_rows };
+        let mut offset = 0;
+        let mut sent = 0;
+        for batch in records {
+            let rows = batch.num_rows();
+            let start = skip.saturating_sub(offset).min(rows);
+            let end = rows.min(start.saturating_add(limit - sent));
+            offset += rows;
+            if start == end {
+                continue;
+            }
+            for row in
+                types::data_rows(batch, fields, start..end).context(pgwire_error::ArrowSnafu)?
+            {
+                self.send(&row);
+            }
+            sent += end - start;
+            if self.out.len() >= FLUSH_THRESHOLD {
+                self.flush().await?;
+            }
+            if sent == limit {
+                break;
+            }
+        }
+        let total: usize = records.iter().map(RecordBatch::num_rows).sum();
+        if skip + sent < total {
+            self.send(&BackendMessage::PortalSuspended);
+        } else {
+            self.send(&BackendMessage::CommandComplete(format!("SELECT {total}")));
+        }
+        Ok(sent)
+    }
+
+    fn send(&mut self, message: &BackendMessage) {
+        message.encode(&mut self.out);
+    }
+
+    fn send_error(&mut self, error: &Error) {
+        tracing::error!(error_message = %error.output_msg(), "PostgreSQL wire protocol error");
+        self.send(&BackendMessage::ErrorResponse {
+            code: error.sqlstate().to_string(),
+            message: error.message(),
+        });
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        if !self.out.is_empty() {
+            let out = self.out.split();
+            self.stream
+                .write_all(&out)
+                .await
+                .context(pgwire_error::IoSnafu)?;
+        }
+        self.stream.flush().await.context(pgwire_error::IoSnafu)
+    }
+}
+
+/// Columns of the rows of the outcome, in the result formats of the portal
+fn outcome_fields(outcome: &Outcome, result_formats: &[i16]) -> Vec<FieldDescription> {
+    match outcome {
+        Outcome::Rows { schema, .. } => types::field_descriptions(schema, result_formats),
+        Outcome::Command(_) | Outcome::Empty => Vec::new(),
+    }
+}
+
+fn session_options(authenticated: &AuthenticatedUser) -> SessionOptions {
+    let AuthenticatedUser {
+        user,
+        role_restriction,
+    } = authenticated;
+    let current = [
+        ("database", user.default_database.clone()),
+        ("schema", user.default_schema.clone()),
+        (
+            "role",
+            role_restriction
+                .clone()
+                .or_else(|| user.default_role.clone()),
+        ),
+    ];
+    SessionOptions {
+        params: current
+            .into_iter()
+            .filter_map(|(name, value)| Some((name.to_string(), ScalarValue::Utf8(Some(value?)))))
+            .collect(),
+        client_info: None,
+        user: Some(user.name.clone()),
+        role_restriction: role_restriction.clone(),
+    }
+}
+
+/// Number of rows a DML statement changed, the sum of the counts of its result row
+fn affected_rows(records: &[RecordBatch]) -> i64 {
+    let [batch] = records else {
+        return 0;
+    };
+    if batch.num_rows() != 1 {
+        return 0;
+    }
+    batch
+        .columns()
+        .iter()
+        .filter_map(|column| {
+            let column = cast(column, &DataType::Int64).ok()?;
+            let column = column.as_primitive_opt::<Int64Type>()?;
+            column.is_valid(0).then(|| column.value(0))
+        })
+        .sum()
+}
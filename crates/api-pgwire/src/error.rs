@@ -0,0 +1,167 @@
+use core_executor::error_code::ErrorCode;
+use datafusion::arrow::error::ArrowError;
+use datafusion::error::DataFusionError;
+use error_stack_trace;
+use snafu::Location;
+use snafu::prelude::*;
+use tokio_rustls::rustls;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Snafu)]
+#[snafu(visibility(pub(crate)))]
+#[error_stack_trace::debug]
+pub enum Error {
+    #[snafu(display("[PgWire] Connection error"))]
+    Io {
+        #[snafu(source)]
+        error: std::io::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("password authentication failed for user \"{user}\""))]
+    AuthenticationFailed {
+        user: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("password authentication of user \"{user}\" requires an SSL connection"))]
+    EncryptionRequired {
+        user: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("received unencrypted data after SSL request"))]
+    UnencryptedData {
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("[PgWire] Failed to read '{path}': {error}"))]
+    TlsPem {
+        path: String,
+        #[snafu(source)]
+        error: rustls::pki_types::pem::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("[PgWire] Invalid TLS configuration: {error}"))]
+    TlsConfig {
+        #[snafu(source)]
+        error: rustls::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("prepared statement \"{name}\" does not exist"))]
+    UnknownStatement {
+        name: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("portal \"{name}\" does not exist"))]
+    UnknownPortal {
+        name: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display(
+        "bind message supplies {given} parameters, but statement requires {expected}"
+    ))]
+    ParameterCount {
+        given: usize,
+        expected: usize,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("unsupported binary format of parameter ${index}"))]
+    UnsupportedParameterFormat {
+        index: usize,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("unsupported frontend message type '{tag}'"))]
+    UnsupportedMessage {
+        tag: char,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("unrecognized configuration parameter \"{name}\""))]
+    UnknownParameter {
+        name: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("[PgWire] Metastore error: {source}"))]
+    Metastore {
+        source: core_metastore::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("{error}"))]
+    PgCatalog {
+        #[snafu(source)]
+        error: DataFusionError,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("[PgWire] Failed to encode result values: {error}"))]
+    Arrow {
+        #[snafu(source)]
+        error: ArrowError,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(transparent)]
+    Execution { source: core_executor::Error },
+}
+
+impl Error {
+    /// SQLSTATE code of the `ErrorResponse` sent for the error
+    #[must_use]
+    pub fn sqlstate(&self) -> &'static str {
+        match self {
+            Self::Io { .. } => "08006",
+            Self::AuthenticationFailed { .. } => "28P01",
+            Self::EncryptionRequired { .. } => "28000",
+            Self::UnknownStatement { .. } => "26000",
+            Self::UnknownPortal { .. } => "34000",
+            Self::ParameterCount { .. }
+            | Self::UnsupportedMessage { .. }
+            | Self::UnencryptedData { .. } => "08P01",
+            Self::UnsupportedParameterFormat { .. } => "0A000",
+            Self::UnknownParameter { .. } => "42704",
+            Self::PgCatalog { .. } => "42000",
+            Self::Metastore { .. }
+            | Self::Arrow { .. }
+            | Self::TlsPem { .. }
+            | Self::TlsConfig { .. } => "XX000",
+            Self::Execution { source } => match source.to_snowflake_error().error_code() {
+                ErrorCode::Internal => "XX000",
+                _ => "42000",
+            },
+        }
+    }
+
+    /// Message of the `ErrorResponse` sent for the error
+    #[must_use]
+    pub fn message(&self) -> String {
+        match self {
+            Self::Execution { source } => source.to_snowflake_error().display_error_message(),
+            _ => self.to_string(),
+        }
+    }
+}
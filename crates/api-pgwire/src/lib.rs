@@ -0,0 +1,19 @@
+//! PostgreSQL wire protocol endpoint of the execution service.
+//!
+//! Postgres clients connect as a metastore user, authenticating with its
+//! password over TLS, and run statements in a session of their own with the simple or
+//! the extended query protocol. Results are sent in the text format, or in the
+//! binary one for the fixed-size types. The `pg_catalog` queries of psql and
+//! DBeaver are answered from the catalogs of the session.
+pub mod connection;
+pub mod error;
+pub mod messages;
+pub mod pg_catalog;
+pub mod server;
+pub mod sql;
+pub mod state;
+pub mod tls;
+pub mod types;
+
+#[cfg(test)]
+mod tests;
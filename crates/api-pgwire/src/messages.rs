@@ -0,0 +1,404 @@
+//! Messages of the PostgreSQL frontend/backend protocol, version 3.0.
+//!
+//! See <https://www.postgresql.org/docs/current/protocol-message-formats.html>
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+const PROTOCOL_VERSION: i32 = 196_608;
+const SSL_REQUEST_CODE: i32 = 80_877_103;
+const GSSENC_REQUEST_CODE: i32 = 80_877_104;
+const CANCEL_REQUEST_CODE: i32 = 80_877_102;
+/// Messages above this length are rejected instead of being buffered
+const MAX_MESSAGE_LENGTH: usize = 1 << 30;
+
+/// Format code of values in the text format
+pub const TEXT_FORMAT: i16 = 0;
+/// Format code of values in the binary format
+pub const BINARY_FORMAT: i16 = 1;
+
+/// First message of a connection, which has no type byte
+#[derive(Debug, PartialEq, Eq)]
+pub enum StartupMessage {
+    SslRequest,
+    GssEncRequest,
+    CancelRequest { process_id: i32, secret_key: i32 },
+    Startup { parameters: HashMap<String, String> },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum FrontendMessage {
+    Query(String),
+    Parse {
+        name: String,
+        query: String,
+        param_types: Vec<u32>,
+    },
+    Bind {
+        portal: String,
+        statement: String,
+        param_formats: Vec<i16>,
+        params: Vec<Option<Bytes>>,
+        result_formats: Vec<i16>,
+    },
+    Describe {
+        target: Target,
+        name: String,
+    },
+    Execute {
+        portal: String,
+        max_rows: usize,
+    },
+    Close {
+        target: Target,
+        name: String,
+    },
+    Sync,
+    Flush,
+    Terminate,
+    Password(String),
+    Unsupported(u8),
+}
+
+/// What a `Describe` or a `Close` message is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Statement,
+    Portal,
+}
+
+/// Column of a `RowDescription`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDescription {
+    pub name: String,
+    pub type_oid: u32,
+    pub type_len: i16,
+    pub format: i16,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BackendMessage {
+    AuthenticationOk,
+    AuthenticationCleartextPassword,
+    ParameterStatus { name: String, value: String },
+    BackendKeyData { process_id: i32, secret_key: i32 },
+    ReadyForQuery,
+    RowDescription(Vec<FieldDescription>),
+    DataRow(Vec<Option<Vec<u8>>>),
+    CommandComplete(String),
+    EmptyQueryResponse,
+    ParseComplete,
+    BindComplete,
+    CloseComplete,
+    NoData,
+    PortalSuspended,
+    ParameterDescription(Vec<u32>),
+    ErrorResponse { code: String, message: String },
+}
+
+impl BackendMessage {
+    /// Appends the message to the output buffer
+    pub fn encode(&self, out: &mut BytesMut) {
+        let (tag, body) = match self {
+            Self::AuthenticationOk => (b'R', 0_i32.to_be_bytes().to_vec()),
+            Self::AuthenticationCleartextPassword => (b'R', 3_i32.to_be_bytes().to_vec()),
+            Self::ParameterStatus { name, value } => {
+                let mut body = BytesMut::new();
+                put_cstring(&mut body, name);
+                put_cstring(&mut body, value);
+                (b'S', body.to_vec())
+            }
+            Self::BackendKeyData {
+                process_id,
+                secret_key,
+            } => {
+                let mut body = BytesMut::new();
+                body.put_i32(*process_id);
+                body.put_i32(*secret_key);
+                (b'K', body.to_vec())
+            }
+            // Connections are never in a transaction block
+            Self::ReadyForQuery => (b'Z', vec![b'I']),
+            Self::RowDescription(fields) => {
+                let mut body = BytesMut::new();
+                body.put_i16(count(fields.len()));
+                for field in fields {
+                    put_cstring(&mut body, &field.name);
+                    // Table OID and column attribute number
+                    body.put_i32(0);
+                    body.put_i16(0);
+                    body.put_u32(field.type_oid);
+                    body.put_i16(field.type_len);
+                    // Type modifier
+                    body.put_i32(-1);
+                    body.put_i16(field.format);
+                }
+                (b'T', body.to_vec())
+            }
+            Self::DataRow(values) => {
+                let mut body = BytesMut::new();
+                body.put_i16(count(values.len()));
+                for value in values {
+                    match value {
+                        Some(value) => {
+                            body.put_i32(length(value.len()));
+                            body.put_slice(value);
+                        }
+                        None => body.put_i32(-1),
+                    }
+                }
+                (b'D', body.to_vec())
+            }
+            Self::CommandComplete(tag) => {
+                let mut body = BytesMut::new();
+                put_cstring(&mut body, tag);
+                (b'C', body.to_vec())
+            }
+            Self::EmptyQueryResponse => (b'I', Vec::new()),
+            Self::ParseComplete => (b'1', Vec::new()),
+            Self::BindComplete => (b'2', Vec::new()),
+            Self::CloseComplete => (b'3', Vec::new()),
+            Self::NoData => (b'n', Vec::new()),
+            Self::PortalSuspended => (b's', Vec::new()),
+            Self::ParameterDescription(types) => {
+                let mut body = BytesMut::new();
+                body.put_i16(count(types.len()));
+                for type_oid in types {
+                    body.put_u32(*type_oid);
+                }
+                (b't', body.to_vec())
+            }
+            Self::ErrorResponse { code, message } => {
+                let mut body = BytesMut::new();
+                for (field, value) in [
+                    (b'S', "ERROR"),
+                    (b'V', "ERROR"),
+                    (b'C', code.as_str()),
+                    (b'M', message.as_str()),
+                ] {
+                    body.put_u8(field);
+                    put_cstring(&mut body, value);
+                }
+                body.put_u8(0);
+                (b'E', body.to_vec())
+            }
+        };
+        out.put_u8(tag);
+        out.put_i32(length(body.len() + 4));
+        out.put_slice(&body);
+    }
+}
+
+pub async fn read_startup_message<R: AsyncRead + Unpin>(reader: &mut R) -> Result<StartupMessage> {
+    let mut body = read_body(reader).await?;
+    let code = get_i32(&mut body)?;
+    match code {
+        SSL_REQUEST_CODE => Ok(StartupMessage::SslRequest),
+        GSSENC_REQUEST_CODE => Ok(StartupMessage::GssEncRequest),
+        CANCEL_REQUEST_CODE => Ok(StartupMessage::CancelRequest {
+            process_id: get_i32(&mut body)?,
+            secret_key: get_i32(&mut body)?,
+        }),
+        PROTOCOL_VERSION => {
+            let mut parameters = HashMap::new();
+            loop {
+                let name = get_cstring(&mut body)?;
+                if name.is_empty() {
+                    break;
+                }
+                parameters.insert(name, get_cstring(&mut body)?);
+            }
+            Ok(StartupMessage::Startup { parameters })
+        }
+        _ => Err(invalid_data(format!("unsupported protocol version {code}"))),
+    }
+}
+
+/// Next message of the client, `None` once the client closed the connection
+pub async fn read_message<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<FrontendMessage>> {
+    let tag = match reader.read_u8().await {
+        Ok(tag) => tag,
+        Err(error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error),
+    };
+    let mut body = read_body(reader).await?;
+    let message = match tag {
+        b'Q' => FrontendMessage::Query(get_cstring(&mut body)?),
+        b'P' => {
+            let name = get_cstring(&mut body)?;
+            let query = get_cstring(&mut body)?;
+            let count = get_count(&mut body)?;
+            let param_types = (0..count)
+                .map(|_| get_u32(&mut body))
+                .collect::<Result<_>>()?;
+            FrontendMessage::Parse {
+                name,
+                query,
+                param_types,
+            }
+        }
+        b'B' => {
+            let portal = get_cstring(&mut body)?;
+            let statement = get_cstring(&mut body)?;
+            let param_formats = get_formats(&mut body)?;
+            let count = get_count(&mut body)?;
+            let params = (0..count)
+                .map(|_| {
+                    let len = get_i32(&mut body)?;
+                    let Ok(len) = usize::try_from(len) else {
+                        // -1 is a NULL
+                        return Ok(None);
+                    };
+                    ensure_remaining(&body, len)?;
+                    Ok(Some(body.split_to(len)))
+                })
+                .collect::<Result<_>>()?;
+            let result_formats = get_formats(&mut body)?;
+            FrontendMessage::Bind {
+                portal,
+                statement,
+                param_formats,
+                params,
+                result_formats,
+            }
+        }
+        b'D' => FrontendMessage::Describe {
+            target: get_target(&mut body)?,
+            name: get_cstring(&mut body)?,
+        },
+        b'E' => FrontendMessage::Execute {
+            portal: get_cstring(&mut body)?,
+            // Zero, and anything not positive, is no limit
+            max_rows: usize::try_from(get_i32(&mut body)?).unwrap_or_default(),
+        },
+        b'C' => FrontendMessage::Close {
+            target: get_target(&mut body)?,
+            name: get_cstring(&mut body)?,
+        },
+        b'S' => FrontendMessage::Sync,
+        b'H' => FrontendMessage::Flush,
+        b'X' => FrontendMessage::Terminate,
+        b'p' => FrontendMessage::Password(get_cstring(&mut body)?),
+        tag => FrontendMessage::Unsupported(tag),
+    };
+    Ok(Some(message))
+}
+
+async fn read_body<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Bytes> {
+    let len = reader.read_i32().await?;
+    let len = usize::try_from(len)
+        .ok()
+        .and_then(|len| len.checked_sub(4))
+        .filter(|len| *len <= MAX_MESSAGE_LENGTH)
+        .ok_or_else(|| invalid_data(format!("invalid message length {len}")))?;
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body).await?;
+    Ok(body.into())
+}
+
+/// Counts of the protocol are 16-bit, the server never sends that many values
+fn count(count: usize) -> i16 {
+    i16::try_from(count).unwrap_or(i16::MAX)
+}
+
+fn length(len: usize) -> i32 {
+    i32::try_from(len).unwrap_or(i32::MAX)
+}
+
+fn put_cstring(out: &mut BytesMut, value: &str) {
+    out.put_slice(value.as_bytes());
+    out.put_u8(0);
+}
+
+fn get_cstring(body: &mut Bytes) -> Result<String> {
+    let end = body
+        .iter()
+        .position(|byte| *byte == 0)
+        .ok_or_else(|| invalid_data("unterminated string"))?;
+    let value = body.split_to(end);
+    body.advance(1);
+    String::from_utf8(value.to_vec()).map_err(|_| invalid_data("string is not UTF-8"))
+}
+
+fn get_i32(body: &mut Bytes) -> Result<i32> {
+    ensure_remaining(body, 4)?;
+    Ok(body.get_i32())
+}
+
+fn get_u32(body: &mut Bytes) -> Result<u32> {
+    ensure_remaining(body, 4)?;
+    Ok(body.get_u32())
+}
+
+fn get_i16(body: &mut Bytes) -> Result<i16> {
+    ensure_remaining(body, 2)?;
+    Ok(body.get_i16())
+}
+
+fn get_count(body: &mut Bytes) -> Result<usize> {
+    let count = get_i16(body)?;
+    usize::try_from(count).map_err(|_| invalid_data(format!("invalid count {count}")))
+}
+
+fn get_formats(body: &mut Bytes) -> Result<Vec<i16>> {
+    let count = get_count(body)?;
+    (0..count).map(|_| get_i16(body)).collect()
+}
+
+fn get_target(body: &mut Bytes) -> Result<Target> {
+    ensure_remaining(body, 1)?;
+    match body.get_u8() {
+        b'S' => Ok(Target::Statement),
+        b'P' => Ok(Target::Portal),
+        target => Err(invalid_data(format!("invalid target {target}"))),
+    }
+}
+
+fn ensure_remaining(body: &Bytes, len: usize) -> Result<()> {
+    if body.remaining() < len {
+        return Err(invalid_data("message is too short"));
+    }
+    Ok(())
+}
+
+fn invalid_data(message: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_bind_message() {
+        let mut body = BytesMut::new();
+        put_cstring(&mut body, "");
+        put_cstring(&mut body, "s1");
+        body.put_i16(1);
+        body.put_i16(TEXT_FORMAT);
+        body.put_i16(2);
+        body.put_i32(2);
+        body.put_slice(b"42");
+        body.put_i32(-1);
+        body.put_i16(0);
+        let mut message = BytesMut::new();
+        message.put_u8(b'B');
+        message.put_i32(i32::try_from(body.len()).unwrap() + 4);
+        message.put_slice(&body);
+
+        let message = read_message(&mut message.as_ref()).await.unwrap();
+        assert_eq!(
+            message,
+            Some(FrontendMessage::Bind {
+                portal: String::new(),
+                statement: "s1".to_string(),
+                param_formats: vec![TEXT_FORMAT],
+                params: vec![Some(Bytes::from_static(b"42")), None],
+                result_formats: Vec::new(),
+            })
+        );
+        assert_eq!(read_message(&mut &b""[..]).await.unwrap(), None);
+    }
+}
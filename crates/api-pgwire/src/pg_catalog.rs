@@ -0,0 +1,1162 @@
+//! Answers the `pg_catalog` introspection queries of Postgres clients: psql and
+//! DBeaver list databases, schemas, tables and columns with them.
+//!
+//! Such queries are planned in a DataFusion context of their own, holding
+//! snapshots of the catalog tables built from the catalogs of the session and
+//! the functions of Postgres the clients call along with them.
+use crate::types::{self, PG_TYPES, pg_type};
+use datafusion::arrow::array::{Array, ArrayRef, AsArray, RecordBatch, StringArray};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, FieldRef, SchemaRef, UInt32Type};
+use datafusion::catalog::CatalogProviderList;
+use datafusion::common::{Result, ScalarValue};
+use datafusion::datasource::TableType;
+use datafusion::logical_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarUDF, ScalarUDFImpl, Signature, TypeSignature,
+    Volatility,
+};
+use datafusion::prelude::{SessionConfig, SessionContext};
+use datafusion::sql::parser::Statement as DFStatement;
+use datafusion::sql::sqlparser::ast::{
+    BinaryOperator, DataType as SqlDataType, Expr, Ident, ObjectName, Statement, Value,
+    ValueWithSpan, VisitMut, VisitorMut, visit_expressions, visit_relations,
+};
+use datafusion::sql::sqlparser::dialect::PostgreSqlDialect;
+use datafusion::sql::sqlparser::parser::Parser;
+use std::any::Any;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+/// Oid of the `pg_catalog` namespace
+const PG_CATALOG_OID: u32 = 11;
+/// Oid of the role owning every object, the connected user
+const OWNER_OID: u32 = 10;
+/// Oid of the `heap` access method of tables
+const HEAP_OID: u32 = 2;
+/// Oid of the `pg_default` tablespace
+const DEFAULT_TABLESPACE_OID: u32 = 1663;
+/// Oids below this one are reserved for the objects of Postgres itself
+const FIRST_NORMAL_OID: u32 = 16_384;
+
+/// Functions of Postgres the clients call, besides the `pg_` and `has_` ones
+const PG_FUNCTIONS: [&str; 5] = [
+    "version",
+    "format_type",
+    "obj_description",
+    "col_description",
+    "shobj_description",
+];
+
+/// The session a catalog query runs for
+pub struct CatalogSession<'a> {
+    pub catalog_list: Arc<dyn CatalogProviderList>,
+    pub database: String,
+    pub schema: String,
+    pub user: &'a str,
+    pub parameters: &'a HashMap<&'static str, String>,
+    pub process_id: i32,
+}
+
+/// Result of a query reading the `pg_catalog`, `None` for the other queries,
+/// which are left to the execution service
+pub async fn query(
+    sql: &str,
+    session: &CatalogSession<'_>,
+) -> Option<Result<(SchemaRef, Vec<RecordBatch>)>> {
+    let mut statements = Parser::parse_sql(&PostgreSqlDialect {}, sql).ok()?;
+    let [statement @ Statement::Query(_)] = statements.as_mut_slice() else {
+        return None;
+    };
+    let referenced = referenced_tables(statement)?;
+    Some(execute(statement.clone(), session, &referenced).await)
+}
+
+/// Catalog tables the query reads, `None` when it reads none of them and
+/// calls no Postgres functions
+fn referenced_tables(statement: &Statement) -> Option<BTreeSet<String>> {
+    let mut tables = BTreeSet::new();
+    let _ = visit_relations(statement, |name| {
+        let parts = name_parts(name);
+        if let [table] | [_, table] | [_, _, table] = parts.as_slice()
+            && table.starts_with("pg_")
+            && (parts.len() == 1 || parts[parts.len() - 2] == "pg_catalog")
+        {
+            tables.insert(table.clone());
+        }
+        ControlFlow::<()>::Continue(())
+    });
+    let mut pg_only = !tables.is_empty();
+    let _ = visit_expressions(statement, |expr| {
+        match expr {
+            Expr::Function(function) => {
+                let name = name_parts(&function.name).pop().unwrap_or_default();
+                if name.starts_with("pg_")
+                    || name.starts_with("has_")
+                    || PG_FUNCTIONS.contains(&name.as_str())
+                {
+                    pg_only = true;
+                }
+            }
+            Expr::Cast { data_type, .. } if cast_target(data_type).is_some() => pg_only = true,
+            _ => {}
+        }
+        ControlFlow::<()>::Continue(())
+    });
+    pg_only.then_some(tables)
+}
+
+fn name_parts(name: &ObjectName) -> Vec<String> {
+    name.0
+        .iter()
+        .filter_map(|part| part.as_ident())
+        .map(|ident| ident.value.to_ascii_lowercase())
+        .collect()
+}
+
+/// Postgres types of casts that DataFusion doesn't know
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CastTarget {
+    RegClass,
+    RegType,
+    RegNamespace,
+    /// Other `reg*` types and `oid`, which are all oids here
+    Oid,
+    Text,
+}
+
+fn cast_target(data_type: &SqlDataType) -> Option<CastTarget> {
+    let name = match data_type {
+        SqlDataType::Regclass => return Some(CastTarget::RegClass),
+        SqlDataType::Custom(name, _) => name_parts(name).pop()?,
+        _ => return None,
+    };
+    match name.as_str() {
+        "regclass" => Some(CastTarget::RegClass),
+        "regtype" => Some(CastTarget::RegType),
+        "regnamespace" => Some(CastTarget::RegNamespace),
+        "oid" | "regproc" | "regprocedure" | "regoper" | "regoperator" | "regrole"
+        | "regconfig" => Some(CastTarget::Oid),
+        "text" | "name" | "char" | "bpchar" | "varchar" => Some(CastTarget::Text),
+        _ => None,
+    }
+}
+
+/// Stable oid of a catalog object
+fn oid(kind: &str, name: &str) -> u32 {
+    let mut hasher = DefaultHasher::new();
+    kind.hash(&mut hasher);
+    name.hash(&mut hasher);
+    let range = u64::from(u32::MAX - FIRST_NORMAL_OID);
+    u32::try_from(hasher.finish() % range).unwrap_or_default() + FIRST_NORMAL_OID
+}
+
+struct Namespace {
+    oid: u32,
+    name: String,
+}
+
+struct Class {
+    oid: u32,
+    name: String,
+    namespace: u32,
+    kind: &'static str,
+    fields: Vec<FieldRef>,
+}
+
+/// Namespaces and relations of the current database
+struct Snapshot {
+    databases: Vec<(u32, String)>,
+    namespaces: Vec<Namespace>,
+    classes: Vec<Class>,
+    /// Relations of the current schema
+    visible: BTreeSet<u32>,
+}
+
+impl Snapshot {
+    async fn load(session: &CatalogSession<'_>, with_relations: bool) -> Result<Self> {
+        let catalog_list = &session.catalog_list;
+        let databases = catalog_list
+            .catalog_names()
+            .into_iter()
+            .map(|name| (oid("database", &name), name))
+            .collect();
+        let mut namespaces = vec![Namespace {
+            oid: PG_CATALOG_OID,
+            name: "pg_catalog".to_string(),
+        }];
+        let mut classes = Vec::new();
+        let mut visible = BTreeSet::new();
+        if let Some(catalog) = catalog_list.catalog(&session.database) {
+            for schema_name in catalog.schema_names() {
+                let qualified_name = format!("{}.{schema_name}", session.database);
+                let namespace = oid("namespace", &qualified_name);
+                namespaces.push(Namespace {
+                    oid: namespace,
+                    name: schema_name.clone(),
+                });
+                let Some(schema) = catalog.schema(&schema_name) else {
+                    continue;
+                };
+                for table_name in schema.table_names() {
+                    let class_oid = oid("class", &format!("{qualified_name}.{table_name}"));
+                    if schema_name == session.schema {
+                        visible.insert(class_oid);
+                    }
+                    let mut class = Class {
+                        oid: class_oid,
+                        name: table_name.clone(),
+                        namespace,
+                        kind: "r",
+                        fields: Vec::new(),
+                    };
+                    // Tables of remote volumes are only loaded when their columns are read
+                    if with_relations && let Some(table) = schema.table(&table_name).await? {
+                        if table.table_type() == TableType::View {
+                            class.kind = "v";
+                        }
+                        class.fields = table.schema().fields().iter().cloned().collect();
+                    }
+                    classes.push(class);
+                }
+            }
+        }
+        Ok(Self {
+            databases,
+            namespaces,
+            classes,
+            visible,
+        })
+    }
+
+    /// Oid of a `'name'::reg*` literal
+    fn lookup(&self, target: CastTarget, name: &str) -> u32 {
+        let name = name
+            .rsplit('.')
+            .next()
+            .unwrap_or(name)
+            .trim_matches('"')
+            .to_ascii_lowercase();
+        let found = match target {
+            CastTarget::RegClass => self
+                .classes
+                .iter()
+                .filter(|class| class.name.eq_ignore_ascii_case(&name))
+                .max_by_key(|class| self.visible.contains(&class.oid))
+                .map(|class| class.oid),
+            CastTarget::RegType => types::pg_type_by_name(&name).map(|pg_type| pg_type.oid),
+            CastTarget::RegNamespace => self
+                .namespaces
+                .iter()
+                .find(|namespace| namespace.name.eq_ignore_ascii_case(&name))
+                .map(|namespace| namespace.oid),
+            CastTarget::Oid | CastTarget::Text => name.parse().ok(),
+        };
+        found.unwrap_or_default()
+    }
+}
+
+/// Rewrites the Postgres syntax DataFusion can't plan
+struct PgSyntaxRewriter<'a> {
+    snapshot: &'a Snapshot,
+}
+
+impl VisitorMut for PgSyntaxRewriter<'_> {
+    type Break = ();
+
+    fn post_visit_expr(&mut self, expr: &mut Expr) -> ControlFlow<Self::Break> {
+        match expr {
+            Expr::Cast {
+                expr: inner,
+                data_type,
+                ..
+            } => match cast_target(data_type) {
+                Some(CastTarget::Text) => *data_type = SqlDataType::Text,
+                Some(target) => {
+                    *expr = if let Expr::Value(ValueWithSpan {
+                        value: Value::SingleQuotedString(name),
+                        ..
+                    }) = inner.as_ref()
+                    {
+                        let oid = self.snapshot.lookup(target, name);
+                        Expr::value(Value::Number(oid.to_string(), false))
+                    } else {
+                        *inner.clone()
+                    };
+                }
+                None => {}
+            },
+            Expr::Collate { expr: inner, .. } => *expr = *inner.clone(),
+            Expr::BinaryOp { op, .. } => {
+                if let BinaryOperator::PGCustomBinaryOperator(parts) = op
+                    && let Some(operator) = parts.last().and_then(|part| binary_operator(part))
+                {
+                    *op = operator;
+                }
+            }
+            Expr::Function(function) => {
+                let parts = name_parts(&function.name);
+                if let [schema, name] = parts.as_slice()
+                    && schema == "pg_catalog"
+                {
+                    function.name = ObjectName::from(vec![Ident::new(name)]);
+                }
+            }
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Operator of `OPERATOR(pg_catalog.<op>)`
+fn binary_operator(operator: &str) -> Option<BinaryOperator> {
+    Some(match operator {
+        "~" => BinaryOperator::PGRegexMatch,
+        "~*" => BinaryOperator::PGRegexIMatch,
+        "!~" => BinaryOperator::PGRegexNotMatch,
+        "!~*" => BinaryOperator::PGRegexNotIMatch,
+        "=" => BinaryOperator::Eq,
+        "<>" => BinaryOperator::NotEq,
+        "<" => BinaryOperator::Lt,
+        ">" => BinaryOperator::Gt,
+        "<=" => BinaryOperator::LtEq,
+        ">=" => BinaryOperator::GtEq,
+        _ => return None,
+    })
+}
+
+async fn execute(
+    mut statement: Statement,
+    session: &CatalogSession<'_>,
+    referenced: &BTreeSet<String>,
+) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    let with_relations = referenced.contains("pg_class") || referenced.contains("pg_attribute");
+    let snapshot = Snapshot::load(session, with_relations).await?;
+    let _ = statement.visit(&mut PgSyntaxRewriter {
+        snapshot: &snapshot,
+    });
+
+    let config = SessionConfig::new()
+        .with_default_catalog_and_schema(&session.database, "pg_catalog")
+        .with_create_default_catalog_and_schema(true);
+    let ctx = SessionContext::new_with_config(config);
+    for table in referenced {
+        if let Some(batch) = catalog_table(table, &snapshot, session)? {
+            ctx.register_batch(table, batch)?;
+        }
+    }
+    for function in functions(&snapshot, session) {
+        ctx.register_udf(function);
+    }
+    let plan = ctx
+        .state()
+        .statement_to_plan(DFStatement::Statement(Box::new(statement)))
+        .await?;
+    let dataframe = ctx.execute_logical_plan(plan).await?;
+    let schema = dataframe.schema().inner().clone();
+    Ok((schema, dataframe.collect().await?))
+}
+
+/// Null or constant column of a catalog table
+fn constant(value: ScalarValue, len: usize) -> Result<ArrayRef> {
+    value.to_array_of_size(len)
+}
+
+fn oids(values: impl IntoIterator<Item = u32>) -> ArrayRef {
+    Arc::new(datafusion::arrow::array::UInt32Array::from_iter_values(
+        values,
+    ))
+}
+
+fn strings<S: AsRef<str>>(values: impl IntoIterator<Item = S>) -> ArrayRef {
+    Arc::new(StringArray::from_iter_values(values))
+}
+
+fn int16s(values: impl IntoIterator<Item = i16>) -> ArrayRef {
+    Arc::new(datafusion::arrow::array::Int16Array::from_iter_values(
+        values,
+    ))
+}
+
+fn int32s(values: impl IntoIterator<Item = i32>) -> ArrayRef {
+    Arc::new(datafusion::arrow::array::Int32Array::from_iter_values(
+        values,
+    ))
+}
+
+fn bools(values: impl IntoIterator<Item = bool>) -> ArrayRef {
+    Arc::new(
+        values
+            .into_iter()
+            .map(Some)
+            .collect::<datafusion::arrow::array::BooleanArray>(),
+    )
+}
+
+fn text(value: &str) -> ScalarValue {
+    ScalarValue::Utf8(Some(value.to_string()))
+}
+
+const fn null_text() -> ScalarValue {
+    ScalarValue::Utf8(None)
+}
+
+/// Column types of the catalog tables with no rows
+#[derive(Clone, Copy)]
+enum Column {
+    Oid,
+    Text,
+    Bool,
+    Int2,
+    Int4,
+}
+
+const EMPTY_TABLES: [(&str, &[(&str, Column)]); 14] = [
+    (
+        "pg_description",
+        &[
+            ("objoid", Column::Oid),
+            ("classoid", Column::Oid),
+            ("objsubid", Column::Int4),
+            ("description", Column::Text),
+        ],
+    ),
+    (
+        "pg_shdescription",
+        &[
+            ("objoid", Column::Oid),
+            ("classoid", Column::Oid),
+            ("description", Column::Text),
+        ],
+    ),
+    (
+        "pg_attrdef",
+        &[
+            ("oid", Column::Oid),
+            ("adrelid", Column::Oid),
+            ("adnum", Column::Int2),
+            ("adbin", Column::Text),
+        ],
+    ),
+    (
+        "pg_collation",
+        &[
+            ("oid", Column::Oid),
+            ("collname", Column::Text),
+            ("collnamespace", Column::Oid),
+            ("collowner", Column::Oid),
+            ("collprovider", Column::Text),
+            ("collencoding", Column::Int4),
+            ("collcollate", Column::Text),
+            ("collctype", Column::Text),
+        ],
+    ),
+    (
+        "pg_index",
+        &[
+            ("indexrelid", Column::Oid),
+            ("indrelid", Column::Oid),
+            ("indnatts", Column::Int2),
+            ("indnkeyatts", Column::Int2),
+            ("indisunique", Column::Bool),
+            ("indisprimary", Column::Bool),
+            ("indisexclusion", Column::Bool),
+            ("indimmediate", Column::Bool),
+            ("indisclustered", Column::Bool),
+            ("indisvalid", Column::Bool),
+            ("indisready", Column::Bool),
+            ("indislive", Column::Bool),
+            ("indisreplident", Column::Bool),
+            ("indkey", Column::Text),
+            ("indexprs", Column::Text),
+            ("indpred", Column::Text),
+        ],
+    ),
+    (
+        "pg_constraint",
+        &[
+            ("oid", Column::Oid),
+            ("conname", Column::Text),
+            ("connamespace", Column::Oid),
+            ("contype", Column::Text),
+            ("condeferrable", Column::Bool),
+            ("condeferred", Column::Bool),
+            ("convalidated", Column::Bool),
+            ("conrelid", Column::Oid),
+            ("contypid", Column::Oid),
+            ("conindid", Column::Oid),
+            ("confrelid", Column::Oid),
+            ("confupdtype", Column::Text),
+            ("confdeltype", Column::Text),
+            ("confmatchtype", Column::Text),
+            ("conkey", Column::Text),
+            ("confkey", Column::Text),
+            ("conbin", Column::Text),
+        ],
+    ),
+    (
+        "pg_inherits",
+        &[
+            ("inhrelid", Column::Oid),
+            ("inhparent", Column::Oid),
+            ("inhseqno", Column::Int4),
+            ("inhdetachpending", Column::Bool),
+        ],
+    ),
+    (
+        "pg_proc",
+        &[
+            ("oid", Column::Oid),
+            ("proname", Column::Text),
+            ("pronamespace", Column::Oid),
+            ("proowner", Column::Oid),
+            ("prolang", Column::Oid),
+            ("prokind", Column::Text),
+            ("proretset", Column::Bool),
+            ("prorettype", Column::Oid),
+            ("proargtypes", Column::Text),
+            ("prosrc", Column::Text),
+        ],
+    ),
+    (
+        "pg_extension",
+        &[
+            ("oid", Column::Oid),
+            ("extname", Column::Text),
+            ("extowner", Column::Oid),
+            ("extnamespace", Column::Oid),
+            ("extversion", Column::Text),
+        ],
+    ),
+    (
+        "pg_trigger",
+        &[
+            ("oid", Column::Oid),
+            ("tgrelid", Column::Oid),
+            ("tgname", Column::Text),
+            ("tgenabled", Column::Text),
+            ("tgisinternal", Column::Bool),
+        ],
+    ),
+    (
+        "pg_policy",
+        &[
+            ("oid", Column::Oid),
+            ("polname", Column::Text),
+            ("polrelid", Column::Oid),
+        ],
+    ),
+    (
+        "pg_rewrite",
+        &[
+            ("oid", Column::Oid),
+            ("rulename", Column::Text),
+            ("ev_class", Column::Oid),
+        ],
+    ),
+    (
+        "pg_depend",
+        &[
+            ("classid", Column::Oid),
+            ("objid", Column::Oid),
+            ("objsubid", Column::Int4),
+            ("refclassid", Column::Oid),
+            ("refobjid", Column::Oid),
+            ("refobjsubid", Column::Int4),
+            ("deptype", Column::Text),
+        ],
+    ),
+    (
+        "pg_enum",
+        &[
+            ("oid", Column::Oid),
+            ("enumtypid", Column::Oid),
+            ("enumlabel", Column::Text),
+        ],
+    ),
+];
+
+/// Snapshot of a catalog table, `None` for the tables that aren't emulated
+#[allow(clippy::too_many_lines)]
+fn catalog_table(
+    name: &str,
+    snapshot: &Snapshot,
+    session: &CatalogSession<'_>,
+) -> Result<Option<RecordBatch>> {
+    let columns: Vec<(&str, ArrayRef)> = match name {
+        "pg_database" => {
+            let len = snapshot.databases.len();
+            vec![
+                ("oid", oids(snapshot.databases.iter().map(|(oid, _)| *oid))),
+                (
+                    "datname",
+                    strings(snapshot.databases.iter().map(|(_, name)| name)),
+                ),
+                (
+                    "datdba",
+                    constant(ScalarValue::UInt32(Some(OWNER_OID)), len)?,
+                ),
+                // UTF8
+                ("encoding", constant(ScalarValue::Int32(Some(6)), len)?),
+                ("datcollate", constant(text("C"), len)?),
+                ("datctype", constant(text("C"), len)?),
+                (
+                    "datistemplate",
+                    constant(ScalarValue::Boolean(Some(false)), len)?,
+                ),
+                (
+                    "datallowconn",
+                    constant(ScalarValue::Boolean(Some(true)), len)?,
+                ),
+                ("datconnlimit", constant(ScalarValue::Int32(Some(-1)), len)?),
+                (
+                    "dattablespace",
+                    constant(ScalarValue::UInt32(Some(DEFAULT_TABLESPACE_OID)), len)?,
+                ),
+                ("datacl", constant(null_text(), len)?),
+            ]
+        }
+        "pg_namespace" => {
+            let len = snapshot.namespaces.len();
+            vec![
+                (
+                    "oid",
+                    oids(snapshot.namespaces.iter().map(|namespace| namespace.oid)),
+                ),
+                (
+                    "nspname",
+                    strings(snapshot.namespaces.iter().map(|namespace| &namespace.name)),
+                ),
+                (
+                    "nspowner",
+                    constant(ScalarValue::UInt32(Some(OWNER_OID)), len)?,
+                ),
+                ("nspacl", constant(null_text(), len)?),
+            ]
+        }
+        "pg_class" => {
+            let classes = &snapshot.classes;
+            let len = classes.len();
+            let no = || constant(ScalarValue::Boolean(Some(false)), len);
+            let zero_oid = || constant(ScalarValue::UInt32(Some(0)), len);
+            vec![
+                ("oid", oids(classes.iter().map(|class| class.oid))),
+                ("relname", strings(classes.iter().map(|class| &class.name))),
+                (
+                    "relnamespace",
+                    oids(classes.iter().map(|class| class.namespace)),
+                ),
+                ("reltype", zero_oid()?),
+                ("reloftype", zero_oid()?),
+                (
+                    "relowner",
+                    constant(ScalarValue::UInt32(Some(OWNER_OID)), len)?,
+                ),
+                (
+                    "relam",
+                    oids(
+                        classes
+                            .iter()
+                            .map(|class| if class.kind == "r" { HEAP_OID } else { 0 }),
+                    ),
+                ),
+                ("relfilenode", oids(classes.iter().map(|class| class.oid))),
+                ("reltablespace", zero_oid()?),
+                ("relpages", constant(ScalarValue::Int32(Some(0)), len)?),
+                (
+                    "reltuples",
+                    constant(ScalarValue::Float32(Some(-1.0)), len)?,
+                ),
+                ("reltoastrelid", zero_oid()?),
+                ("relhasindex", no()?),
+                ("relisshared", no()?),
+                ("relpersistence", constant(text("p"), len)?),
+                ("relkind", strings(classes.iter().map(|class| class.kind))),
+                (
+                    "relnatts",
+                    int16s(
+                        classes
+                            .iter()
+                            .map(|class| i16::try_from(class.fields.len()).unwrap_or(i16::MAX)),
+                    ),
+                ),
+                ("relchecks", constant(ScalarValue::Int16(Some(0)), len)?),
+                ("relhasrules", no()?),
+                ("relhastriggers", no()?),
+                ("relhassubclass", no()?),
+                ("relrowsecurity", no()?),
+                ("relforcerowsecurity", no()?),
+                (
+                    "relispopulated",
+                    constant(ScalarValue::Boolean(Some(true)), len)?,
+                ),
+                ("relreplident", constant(text("d"), len)?),
+                ("relispartition", no()?),
+                ("relacl", constant(null_text(), len)?),
+                ("reloptions", constant(null_text(), len)?),
+                ("relpartbound", constant(null_text(), len)?),
+            ]
+        }
+        "pg_attribute" => {
+            let attributes: Vec<(u32, i16, &FieldRef)> = snapshot
+                .classes
+                .iter()
+                .flat_map(|class| {
+                    class.fields.iter().enumerate().map(|(index, field)| {
+                        let number = i16::try_from(index + 1).unwrap_or(i16::MAX);
+                        (class.oid, number, field)
+                    })
+                })
+                .collect();
+            let len = attributes.len();
+            let no = || constant(ScalarValue::Boolean(Some(false)), len);
+            let types = || {
+                attributes
+                    .iter()
+                    .map(|(_, _, field)| pg_type(field.data_type()))
+            };
+            vec![
+                ("attrelid", oids(attributes.iter().map(|(oid, ..)| *oid))),
+                (
+                    "attname",
+                    strings(attributes.iter().map(|(_, _, field)| field.name())),
+                ),
+                ("atttypid", oids(types().map(|pg_type| pg_type.oid))),
+                ("attlen", int16s(types().map(|pg_type| pg_type.len))),
+                (
+                    "attnum",
+                    int16s(attributes.iter().map(|(_, number, _)| *number)),
+                ),
+                ("attndims", constant(ScalarValue::Int32(Some(0)), len)?),
+                ("atttypmod", constant(ScalarValue::Int32(Some(-1)), len)?),
+                (
+                    "attnotnull",
+                    bools(attributes.iter().map(|(_, _, field)| !field.is_nullable())),
+                ),
+                ("atthasdef", no()?),
+                ("atthasmissing", no()?),
+                ("attidentity", constant(text(""), len)?),
+                ("attgenerated", constant(text(""), len)?),
+                ("attisdropped", no()?),
+                (
+                    "attislocal",
+                    constant(ScalarValue::Boolean(Some(true)), len)?,
+                ),
+                ("attinhcount", constant(ScalarValue::Int32(Some(0)), len)?),
+                ("attcollation", constant(ScalarValue::UInt32(Some(0)), len)?),
+                ("attacl", constant(null_text(), len)?),
+                ("attoptions", constant(null_text(), len)?),
+                ("attfdwoptions", constant(null_text(), len)?),
+            ]
+        }
+        "pg_type" => {
+            let len = PG_TYPES.len();
+            vec![
+                ("oid", oids(PG_TYPES.iter().map(|pg_type| pg_type.oid))),
+                (
+                    "typname",
+                    strings(PG_TYPES.iter().map(|pg_type| pg_type.name)),
+                ),
+                (
+                    "typnamespace",
+                    constant(ScalarValue::UInt32(Some(PG_CATALOG_OID)), len)?,
+                ),
+                (
+                    "typowner",
+                    constant(ScalarValue::UInt32(Some(OWNER_OID)), len)?,
+                ),
+                ("typlen", int16s(PG_TYPES.iter().map(|pg_type| pg_type.len))),
+                (
+                    "typbyval",
+                    bools(
+                        PG_TYPES
+                            .iter()
+                            .map(|pg_type| (1..=8).contains(&pg_type.len)),
+                    ),
+                ),
+                ("typtype", constant(text("b"), len)?),
+                (
+                    "typcategory",
+                    strings(PG_TYPES.iter().map(|pg_type| pg_type.category)),
+                ),
+                (
+                    "typispreferred",
+                    constant(ScalarValue::Boolean(Some(false)), len)?,
+                ),
+                (
+                    "typisdefined",
+                    constant(ScalarValue::Boolean(Some(true)), len)?,
+                ),
+                ("typdelim", constant(text(","), len)?),
+                ("typrelid", constant(ScalarValue::UInt32(Some(0)), len)?),
+                ("typelem", constant(ScalarValue::UInt32(Some(0)), len)?),
+                ("typarray", constant(ScalarValue::UInt32(Some(0)), len)?),
+                (
+                    "typnotnull",
+                    constant(ScalarValue::Boolean(Some(false)), len)?,
+                ),
+                ("typbasetype", constant(ScalarValue::UInt32(Some(0)), len)?),
+                ("typtypmod", constant(ScalarValue::Int32(Some(-1)), len)?),
+                ("typndims", constant(ScalarValue::Int32(Some(0)), len)?),
+                ("typcollation", constant(ScalarValue::UInt32(Some(0)), len)?),
+                ("typdefault", constant(null_text(), len)?),
+            ]
+        }
+        "pg_settings" => {
+            let mut parameters: Vec<_> = session.parameters.iter().collect();
+            parameters.sort();
+            let len = parameters.len();
+            vec![
+                ("name", strings(parameters.iter().map(|(name, _)| **name))),
+                (
+                    "setting",
+                    strings(parameters.iter().map(|(_, value)| value)),
+                ),
+                ("unit", constant(null_text(), len)?),
+                ("category", constant(text(""), len)?),
+                ("short_desc", constant(text(""), len)?),
+                ("context", constant(text("user"), len)?),
+                ("vartype", constant(text("string"), len)?),
+                ("source", constant(text("default"), len)?),
+                (
+                    "boot_val",
+                    strings(parameters.iter().map(|(_, value)| value)),
+                ),
+                (
+                    "reset_val",
+                    strings(parameters.iter().map(|(_, value)| value)),
+                ),
+                (
+                    "pending_restart",
+                    constant(ScalarValue::Boolean(Some(false)), len)?,
+                ),
+            ]
+        }
+        "pg_roles" => vec![
+            ("oid", oids([OWNER_OID])),
+            ("rolname", strings([session.user])),
+            ("rolsuper", bools([false])),
+            ("rolinherit", bools([true])),
+            ("rolcreaterole", bools([false])),
+            ("rolcreatedb", bools([false])),
+            ("rolcanlogin", bools([true])),
+            ("rolreplication", bools([false])),
+            ("rolconnlimit", int32s([-1])),
+            ("rolpassword", strings(["********"])),
+            ("rolvaliduntil", constant(null_text(), 1)?),
+            ("rolbypassrls", bools([false])),
+            ("rolconfig", constant(null_text(), 1)?),
+        ],
+        "pg_user" => vec![
+            ("usename", strings([session.user])),
+            ("usesysid", oids([OWNER_OID])),
+            ("usecreatedb", bools([false])),
+            ("usesuper", bools([false])),
+            ("userepl", bools([false])),
+            ("usebypassrls", bools([false])),
+            ("passwd", strings(["********"])),
+            ("valuntil", constant(null_text(), 1)?),
+            ("useconfig", constant(null_text(), 1)?),
+        ],
+        "pg_am" => vec![
+            ("oid", oids([HEAP_OID])),
+            ("amname", strings(["heap"])),
+            ("amtype", strings(["t"])),
+        ],
+        "pg_tablespace" => vec![
+            ("oid", oids([DEFAULT_TABLESPACE_OID])),
+            ("spcname", strings(["pg_default"])),
+            ("spcowner", oids([OWNER_OID])),
+            ("spcacl", constant(null_text(), 1)?),
+            ("spcoptions", constant(null_text(), 1)?),
+        ],
+        name => {
+            let Some((_, columns)) = EMPTY_TABLES.iter().find(|(table, _)| *table == name) else {
+                return Ok(None);
+            };
+            columns
+                .iter()
+                .map(|(column, kind)| {
+                    let value = match kind {
+                        Column::Oid => ScalarValue::UInt32(None),
+                        Column::Text => null_text(),
+                        Column::Bool => ScalarValue::Boolean(None),
+                        Column::Int2 => ScalarValue::Int16(None),
+                        Column::Int4 => ScalarValue::Int32(None),
+                    };
+                    Ok((*column, constant(value, 0)?))
+                })
+                .collect::<Result<_>>()?
+        }
+    };
+    Ok(Some(RecordBatch::try_from_iter(columns)?))
+}
+
+/// Postgres functions of the catalog queries
+fn functions(snapshot: &Snapshot, session: &CatalogSession<'_>) -> Vec<ScalarUDF> {
+    let user = text(session.user);
+    let mut functions: Vec<ScalarUDF> = [
+        ("version", text("PostgreSQL 14.0 (Embucket)")),
+        ("current_database", text(&session.database)),
+        ("current_schema", text(&session.schema)),
+        ("current_user", user.clone()),
+        ("session_user", user.clone()),
+        ("pg_get_userbyid", user),
+        ("pg_encoding_to_char", text("UTF8")),
+        (
+            "pg_backend_pid",
+            ScalarValue::Int32(Some(session.process_id)),
+        ),
+        ("pg_is_in_recovery", ScalarValue::Boolean(Some(false))),
+        ("pg_type_is_visible", ScalarValue::Boolean(Some(true))),
+        ("pg_function_is_visible", ScalarValue::Boolean(Some(true))),
+        ("has_table_privilege", ScalarValue::Boolean(Some(true))),
+        ("has_schema_privilege", ScalarValue::Boolean(Some(true))),
+        ("has_database_privilege", ScalarValue::Boolean(Some(true))),
+        ("has_column_privilege", ScalarValue::Boolean(Some(true))),
+        ("pg_has_role", ScalarValue::Boolean(Some(true))),
+        ("obj_description", null_text()),
+        ("col_description", null_text()),
+        ("shobj_description", null_text()),
+        ("pg_get_expr", null_text()),
+        ("pg_get_indexdef", null_text()),
+        ("pg_get_constraintdef", null_text()),
+        ("pg_get_viewdef", null_text()),
+        ("pg_get_triggerdef", null_text()),
+        ("pg_get_partkeydef", null_text()),
+        ("pg_tablespace_location", null_text()),
+        ("pg_total_relation_size", ScalarValue::Int64(None)),
+        ("pg_relation_size", ScalarValue::Int64(None)),
+        ("pg_table_size", ScalarValue::Int64(None)),
+        ("pg_database_size", ScalarValue::Int64(None)),
+    ]
+    .into_iter()
+    .map(|(name, value)| ScalarUDF::from(ConstantFunction::new(name, value)))
+    .collect();
+    functions.push(ScalarUDF::from(TableIsVisible::new(
+        snapshot.visible.clone(),
+    )));
+    functions.push(ScalarUDF::from(FormatType::new()));
+    functions
+}
+
+fn signature() -> Signature {
+    Signature::one_of(
+        vec![TypeSignature::VariadicAny, TypeSignature::Nullary],
+        Volatility::Stable,
+    )
+}
+
+/// Function returning the same value whatever its arguments
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct ConstantFunction {
+    name: String,
+    value: ScalarValue,
+    signature: Signature,
+}
+
+impl ConstantFunction {
+    fn new(name: &str, value: ScalarValue) -> Self {
+        Self {
+            name: name.to_string(),
+            value,
+            signature: signature(),
+        }
+    }
+}
+
+impl ScalarUDFImpl for ConstantFunction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(self.value.data_type())
+    }
+
+    fn invoke_with_args(&self, _args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        Ok(ColumnarValue::Scalar(self.value.clone()))
+    }
+}
+
+/// Oids of the first argument, as a column
+fn oid_argument(args: &ScalarFunctionArgs) -> Result<ArrayRef> {
+    let array = match args.args.first() {
+        Some(arg) => arg.to_array(args.number_rows)?,
+        None => constant(ScalarValue::UInt32(None), args.number_rows)?,
+    };
+    Ok(cast(&array, &DataType::UInt32)?)
+}
+
+/// `pg_table_is_visible(oid)`, relations of the current schema are visible
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct TableIsVisible {
+    visible: BTreeSet<u32>,
+    signature: Signature,
+}
+
+impl TableIsVisible {
+    fn new(visible: BTreeSet<u32>) -> Self {
+        Self {
+            visible,
+            signature: signature(),
+        }
+    }
+}
+
+impl ScalarUDFImpl for TableIsVisible {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &'static str {
+        "pg_table_is_visible"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Boolean)
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let oids = oid_argument(&args)?;
+        let visible: datafusion::arrow::array::BooleanArray = oids
+            .as_primitive::<UInt32Type>()
+            .iter()
+            .map(|oid| oid.map(|oid| oid < FIRST_NORMAL_OID || self.visible.contains(&oid)))
+            .collect();
+        Ok(ColumnarValue::Array(Arc::new(visible)))
+    }
+}
+
+/// `format_type(type_oid, typemod)`, the SQL name of a type
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct FormatType {
+    signature: Signature,
+}
+
+impl FormatType {
+    fn new() -> Self {
+        Self {
+            signature: signature(),
+        }
+    }
+}
+
+impl ScalarUDFImpl for FormatType {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &'static str {
+        "format_type"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Utf8)
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let oids = oid_argument(&args)?;
+        let names: StringArray = oids
+            .as_primitive::<UInt32Type>()
+            .iter()
+            .map(|oid| oid.map(format_type))
+            .collect();
+        Ok(ColumnarValue::Array(Arc::new(names)))
+    }
+}
+
+fn format_type(oid: u32) -> &'static str {
+    match oid {
+        16 => "boolean",
+        21 => "smallint",
+        23 => "integer",
+        20 => "bigint",
+        700 => "real",
+        701 => "double precision",
+        1043 => "character varying",
+        1083 => "time without time zone",
+        1114 => "timestamp without time zone",
+        1184 => "timestamp with time zone",
+        oid => types::pg_type_by_oid(oid).map_or("???", |pg_type| pg_type.name),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_catalog_queries() {
+        let parse = |sql| {
+            Parser::parse_sql(&PostgreSqlDialect {}, sql)
+                .unwrap()
+                .remove(0)
+        };
+        let tables = referenced_tables(&parse(
+            "SELECT n.nspname FROM pg_catalog.pg_namespace n JOIN pg_class c ON c.relnamespace = n.oid",
+        ))
+        .unwrap();
+        assert_eq!(
+            tables.into_iter().collect::<Vec<_>>(),
+            ["pg_class", "pg_namespace"]
+        );
+        assert!(
+            referenced_tables(&parse("SELECT pg_catalog.version()"))
+                .unwrap()
+                .is_empty()
+        );
+        assert!(referenced_tables(&parse("SELECT * FROM embucket.public.t")).is_none());
+    }
+
+    #[test]
+    fn test_rewrites_pg_syntax() {
+        let snapshot = Snapshot {
+            databases: Vec::new(),
+            namespaces: Vec::new(),
+            classes: vec![Class {
+                oid: 20_000,
+                name: "t".to_string(),
+                namespace: PG_CATALOG_OID,
+                kind: "r",
+                fields: Vec::new(),
+            }],
+            visible: BTreeSet::new(),
+        };
+        let mut statement = Parser::parse_sql(
+            &PostgreSqlDialect {},
+            "SELECT c.relname::pg_catalog.text FROM pg_catalog.pg_class c \
+             WHERE c.relname OPERATOR(pg_catalog.~) '^(t)$' COLLATE pg_catalog.default \
+             AND c.oid = 'public.t'::regclass AND pg_catalog.pg_table_is_visible(c.oid)",
+        )
+        .unwrap()
+        .remove(0);
+        let _ = statement.visit(&mut PgSyntaxRewriter {
+            snapshot: &snapshot,
+        });
+        let sql = statement.to_string();
+        assert!(sql.contains("c.relname::TEXT"));
+        assert!(sql.contains("c.relname ~ '^(t)$' AND"));
+        assert!(sql.contains("c.oid = 20000"));
+        assert!(sql.contains("AND pg_table_is_visible(c.oid)"));
+    }
+}
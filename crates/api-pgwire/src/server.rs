@@ -0,0 +1,22 @@
+use crate::connection::Connection;
+use crate::state::State;
+use error_stack::ErrorExt;
+use tokio::net::TcpListener;
+
+/// Accepts the connections of Postgres clients, each served by a task of its own
+pub async fn serve(listener: TcpListener, state: State) -> std::io::Result<()> {
+    loop {
+        let (stream, peer_addr) = listener.accept().await?;
+        let _ = stream.set_nodelay(true);
+        let state = state.clone();
+        tokio::spawn(async move {
+            if let Err(error) = Connection::new(stream, state).run().await {
+                tracing::debug!(
+                    %peer_addr,
+                    error_message = %error.output_msg(),
+                    "PostgreSQL connection closed"
+                );
+            }
+        });
+    }
+}
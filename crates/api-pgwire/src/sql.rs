@@ -0,0 +1,412 @@
+//! Lexical handling of the SQL text clients send, done before the statements
+//! reach the execution service.
+
+/// Configuration parameters of a connection: the name, the default value and
+/// whether changes are reported to the client with `ParameterStatus`.
+/// `SET` and `SHOW` of these are answered by the connection itself.
+pub const PARAMETERS: [(&str, &str, bool); 19] = [
+    ("server_version", "14.0", true),
+    ("server_encoding", "UTF8", true),
+    ("client_encoding", "UTF8", true),
+    ("DateStyle", "ISO, MDY", true),
+    ("TimeZone", "UTC", true),
+    ("IntervalStyle", "postgres", true),
+    ("integer_datetimes", "on", true),
+    ("standard_conforming_strings", "on", true),
+    ("application_name", "", true),
+    ("is_superuser", "off", true),
+    ("extra_float_digits", "1", false),
+    ("search_path", "\"$user\", public", false),
+    ("statement_timeout", "0", false),
+    ("lock_timeout", "0", false),
+    ("idle_in_transaction_session_timeout", "0", false),
+    ("client_min_messages", "notice", false),
+    ("bytea_output", "hex", false),
+    ("transaction_isolation", "read committed", false),
+    ("max_identifier_length", "63", false),
+];
+
+/// Canonical name of a connection parameter, names are case-insensitive
+#[must_use]
+pub fn parameter_name(name: &str) -> Option<&'static str> {
+    PARAMETERS
+        .iter()
+        .find(|(parameter, ..)| parameter.eq_ignore_ascii_case(name))
+        .map(|(parameter, ..)| *parameter)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Scan {
+    Code,
+    Quoted(char),
+    LineComment,
+    BlockComment,
+    DollarQuoted,
+}
+
+/// Characters of the text with their byte offset, flagged when they are code:
+/// outside of string literals, quoted identifiers and comments
+fn scan(sql: &str, backslash_escapes: bool) -> Vec<(usize, char, bool)> {
+    let mut scanned = Vec::with_capacity(sql.len());
+    let mut chars = sql.char_indices().peekable();
+    let mut state = Scan::Code;
+    while let Some((index, char)) = chars.next() {
+        let next = chars.peek().map(|(_, next)| *next);
+        let mut consume_next = false;
+        match state {
+            Scan::Code => match (char, next) {
+                ('\'' | '"', _) => state = Scan::Quoted(char),
+                ('-', Some('-')) => state = Scan::LineComment,
+                ('/', Some('*')) => {
+                    state = Scan::BlockComment;
+                    consume_next = true;
+                }
+                ('$', Some('$')) => {
+                    state = Scan::DollarQuoted;
+                    consume_next = true;
+                }
+                _ => {
+                    scanned.push((index, char, true));
+                    continue;
+                }
+            },
+            Scan::Quoted(quote) => {
+                if char == quote && next == Some(quote) {
+                    consume_next = true;
+                } else if char == quote {
+                    state = Scan::Code;
+                } else if char == '\\' && quote == '\'' && backslash_escapes {
+                    consume_next = true;
+                }
+            }
+            Scan::LineComment => {
+                if char == '\n' {
+                    state = Scan::Code;
+                }
+            }
+            Scan::BlockComment => {
+                if char == '*' && next == Some('/') {
+                    state = Scan::Code;
+                    consume_next = true;
+                }
+            }
+            Scan::DollarQuoted => {
+                if char == '$' && next == Some('$') {
+                    state = Scan::Code;
+                    consume_next = true;
+                }
+            }
+        }
+        scanned.push((index, char, false));
+        if consume_next && let Some((index, char)) = chars.next() {
+            scanned.push((index, char, false));
+        }
+    }
+    scanned
+}
+
+/// Statements of a simple query, which may hold several separated by `;`
+#[must_use]
+pub fn split_statements(sql: &str, backslash_escapes: bool) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let mut start = 0;
+    for (index, char, code) in scan(sql, backslash_escapes) {
+        if code && char == ';' {
+            statements.push(&sql[start..index]);
+            start = index + 1;
+        }
+    }
+    statements.push(&sql[start..]);
+    statements
+        .into_iter()
+        .map(str::trim)
+        .filter(|statement| !is_empty_statement(statement))
+        .collect()
+}
+
+/// Whether the statement holds nothing but whitespace and comments
+#[must_use]
+pub fn is_empty_statement(sql: &str) -> bool {
+    scan(sql, false)
+        .into_iter()
+        .all(|(_, char, code)| !code || char.is_whitespace() || char == ';')
+}
+
+/// Positions of the `$n` placeholders of the statement
+fn placeholders(sql: &str, backslash_escapes: bool) -> Vec<(usize, usize, usize)> {
+    let scanned = scan(sql, backslash_escapes);
+    let mut placeholders = Vec::new();
+    let mut position = 0;
+    while position < scanned.len() {
+        let (start, char, code) = scanned[position];
+        position += 1;
+        if !code || char != '$' {
+            continue;
+        }
+        let digits: String = scanned[position..]
+            .iter()
+            .take_while(|(_, char, code)| *code && char.is_ascii_digit())
+            .map(|(_, char, _)| *char)
+            .collect();
+        if let Ok(number) = digits.parse::<usize>() {
+            position += digits.len();
+            placeholders.push((start, start + 1 + digits.len(), number));
+        }
+    }
+    placeholders
+}
+
+/// Number of parameters of the statement, the highest `$n` placeholder
+#[must_use]
+pub fn parameter_count(sql: &str, backslash_escapes: bool) -> usize {
+    placeholders(sql, backslash_escapes)
+        .into_iter()
+        .map(|(.., number)| number)
+        .max()
+        .unwrap_or_default()
+}
+
+/// Replaces the `$n` placeholders with the literals of the bound parameters
+#[must_use]
+pub fn bind_parameters(sql: &str, literals: &[String], backslash_escapes: bool) -> String {
+    let mut bound = String::with_capacity(sql.len());
+    let mut copied = 0;
+    for (start, end, number) in placeholders(sql, backslash_escapes) {
+        let Some(literal) = number.checked_sub(1).and_then(|index| literals.get(index)) else {
+            continue;
+        };
+        bound.push_str(&sql[copied..start]);
+        bound.push_str(literal);
+        copied = end;
+    }
+    bound.push_str(&sql[copied..]);
+    bound
+}
+
+/// Leading keywords of the statement, in upper case
+fn keywords(sql: &str, count: usize) -> Vec<String> {
+    let code: String = scan(sql, false)
+        .into_iter()
+        .map(|(_, char, code)| if code { char } else { ' ' })
+        .collect();
+    code.split(|char: char| !char.is_ascii_alphanumeric() && char != '_')
+        .filter(|word| !word.is_empty())
+        .take(count)
+        .map(str::to_ascii_uppercase)
+        .collect()
+}
+
+/// What the client is told a statement returned
+#[derive(Debug, PartialEq, Eq)]
+pub enum StatementKind {
+    /// Rows, completed with `SELECT <rows>`
+    Rows,
+    /// Changed rows, completed with the tag followed by the number of rows
+    Dml(&'static str),
+    /// A command, completed with its tag
+    Command(String),
+}
+
+/// Modifiers skipped in the tags of `CREATE`, `DROP` and `ALTER` commands
+const TAG_MODIFIERS: [&str; 13] = [
+    "OR",
+    "REPLACE",
+    "TEMPORARY",
+    "TEMP",
+    "TRANSIENT",
+    "VOLATILE",
+    "EXTERNAL",
+    "SECURE",
+    "LOCAL",
+    "GLOBAL",
+    "IF",
+    "NOT",
+    "EXISTS",
+];
+
+#[must_use]
+pub fn statement_kind(sql: &str) -> StatementKind {
+    let keywords = keywords(sql, 8);
+    let Some(first) = keywords.first() else {
+        return StatementKind::Rows;
+    };
+    match first.as_str() {
+        "INSERT" => StatementKind::Dml("INSERT 0"),
+        "UPDATE" => StatementKind::Dml("UPDATE"),
+        "DELETE" => StatementKind::Dml("DELETE"),
+        "MERGE" => StatementKind::Dml("MERGE"),
+        "CREATE" | "DROP" | "ALTER" | "UNDROP" => {
+            let object = keywords[1..]
+                .iter()
+                .find(|keyword| !TAG_MODIFIERS.contains(&keyword.as_str()));
+            StatementKind::Command(match object {
+                Some(object) => format!("{first} {object}"),
+                None => first.clone(),
+            })
+        }
+        "USE" | "TRUNCATE" | "COPY" | "GRANT" | "REVOKE" | "COMMENT" | "PUT" | "REMOVE" => {
+            StatementKind::Command(first.clone())
+        }
+        // Snowflake variables, the connection parameters are handled by the connection
+        "SET" | "UNSET" => StatementKind::Command("SET".to_string()),
+        _ => StatementKind::Rows,
+    }
+}
+
+/// Statements answered by the connection without the execution service
+#[derive(Debug, PartialEq, Eq)]
+pub enum SessionCommand {
+    /// `SET` of a connection parameter
+    Set { name: &'static str, value: String },
+    /// `RESET` of a connection parameter
+    Reset(&'static str),
+    /// `SHOW` of a connection parameter
+    Show(&'static str),
+    /// Transaction control, statements run in auto-commit mode
+    Transaction(&'static str),
+    /// `DISCARD ALL`
+    DiscardAll,
+    /// `DEALLOCATE` of a prepared statement, `None` for `DEALLOCATE ALL`
+    Deallocate(Option<String>),
+}
+
+#[must_use]
+pub fn session_command(sql: &str) -> Option<SessionCommand> {
+    let sql = sql.trim().trim_end_matches(';').trim();
+    let keywords = keywords(sql, 3);
+    let keywords: Vec<&str> = keywords.iter().map(String::as_str).collect();
+    match keywords.as_slice() {
+        ["BEGIN" | "START", ..] => Some(SessionCommand::Transaction("BEGIN")),
+        ["COMMIT" | "END", ..] => Some(SessionCommand::Transaction("COMMIT")),
+        ["ROLLBACK" | "ABORT", ..] => Some(SessionCommand::Transaction("ROLLBACK")),
+        ["DISCARD", "ALL"] => Some(SessionCommand::DiscardAll),
+        ["DEALLOCATE", ..] => {
+            let name = sql
+                .split_whitespace()
+                .skip(1)
+                .find(|word| !word.eq_ignore_ascii_case("PREPARE"))?;
+            if name.eq_ignore_ascii_case("ALL") {
+                Some(SessionCommand::Deallocate(None))
+            } else {
+                Some(SessionCommand::Deallocate(Some(
+                    name.trim_matches('"').to_string(),
+                )))
+            }
+        }
+        ["SHOW", "TRANSACTION", "ISOLATION"] => Some(SessionCommand::Show("transaction_isolation")),
+        ["SHOW", name, ..] => parameter_name(name).map(SessionCommand::Show),
+        ["RESET", name, ..] => parameter_name(name).map(SessionCommand::Reset),
+        ["SET", ..] => set_command(sql),
+        _ => None,
+    }
+}
+
+/// `SET [ SESSION | LOCAL ] name { TO | = } value` of a connection parameter
+fn set_command(sql: &str) -> Option<SessionCommand> {
+    let mut rest = strip_keyword(sql, "SET ")?;
+    for modifier in ["SESSION ", "LOCAL "] {
+        rest = strip_keyword(rest, modifier).unwrap_or(rest);
+    }
+    let name_end = rest
+        .find(|char: char| !char.is_ascii_alphanumeric() && char != '_')
+        .unwrap_or(rest.len());
+    let name = parameter_name(&rest[..name_end])?;
+    let rest = rest[name_end..].trim_start();
+    let value = if let Some(value) = rest.strip_prefix('=') {
+        value
+    } else if let Some(value) = strip_keyword(rest, "TO ") {
+        value
+    } else {
+        return None;
+    };
+    let value = value
+        .trim()
+        .trim_matches(|char| char == '\'' || char == '"')
+        .to_string();
+    Some(SessionCommand::Set { name, value })
+}
+
+fn strip_keyword<'a>(sql: &'a str, keyword: &str) -> Option<&'a str> {
+    let prefix = sql.get(..keyword.len())?;
+    prefix
+        .eq_ignore_ascii_case(keyword)
+        .then(|| sql[keyword.len()..].trim_start())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_statements() {
+        assert_eq!(
+            split_statements("SELECT 'a;b'; -- c;\nSELECT \"d;\" /* ; */ ;; ", false),
+            ["SELECT 'a;b'", "-- c;\nSELECT \"d;\" /* ; */"]
+        );
+        assert_eq!(
+            split_statements("SELECT 'a\\';b'", true),
+            ["SELECT 'a\\';b'"]
+        );
+        assert!(split_statements(" -- nothing", false).is_empty());
+    }
+
+    #[test]
+    fn test_bind_parameters() {
+        let sql = "SELECT $1, '$2', $2 FROM t WHERE a = $10";
+        assert_eq!(parameter_count(sql, false), 10);
+        assert_eq!(
+            bind_parameters(sql, &["1".to_string(), "'x'".to_string()], false),
+            "SELECT 1, '$2', 'x' FROM t WHERE a = $10"
+        );
+    }
+
+    #[test]
+    fn test_statement_kind() {
+        assert_eq!(statement_kind("select 1"), StatementKind::Rows);
+        assert_eq!(
+            statement_kind("/* x */ insert into t values (1)"),
+            StatementKind::Dml("INSERT 0")
+        );
+        assert_eq!(
+            statement_kind("CREATE OR REPLACE TABLE t (a INT)"),
+            StatementKind::Command("CREATE TABLE".to_string())
+        );
+        assert_eq!(
+            statement_kind("drop schema if exists s"),
+            StatementKind::Command("DROP SCHEMA".to_string())
+        );
+    }
+
+    #[test]
+    fn test_session_commands() {
+        assert_eq!(
+            session_command("SET extra_float_digits = 3"),
+            Some(SessionCommand::Set {
+                name: "extra_float_digits",
+                value: "3".to_string(),
+            })
+        );
+        assert_eq!(
+            session_command("set datestyle to 'ISO'"),
+            Some(SessionCommand::Set {
+                name: "DateStyle",
+                value: "ISO".to_string(),
+            })
+        );
+        assert_eq!(
+            session_command("SHOW TimeZone;"),
+            Some(SessionCommand::Show("TimeZone"))
+        );
+        assert_eq!(
+            session_command("BEGIN READ ONLY"),
+            Some(SessionCommand::Transaction("BEGIN"))
+        );
+        assert_eq!(
+            session_command("DEALLOCATE PREPARE s1"),
+            Some(SessionCommand::Deallocate(Some("s1".to_string())))
+        );
+        // Snowflake variables are left to the execution service
+        assert_eq!(session_command("SET my_var = 1"), None);
+        assert_eq!(session_command("SHOW TABLES"), None);
+    }
+}
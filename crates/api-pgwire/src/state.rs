@@ -0,0 +1,35 @@
+use core_executor::service::ExecutionService;
+use core_metastore::Metastore;
+use std::sync::Arc;
+use tokio_rustls::rustls::ServerConfig;
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// Clients authenticate with the password, or a programmatic access token,
+    /// of a metastore user over TLS, otherwise sessions are anonymous whatever
+    /// the user name
+    pub require_auth: bool,
+    /// Server configuration of the connections upgraded with an `SSLRequest`
+    pub tls: Option<Arc<ServerConfig>>,
+}
+
+#[derive(Clone)]
+pub struct State {
+    pub execution_svc: Arc<dyn ExecutionService>,
+    pub metastore: Arc<dyn Metastore>,
+    pub config: Arc<Config>,
+}
+
+impl State {
+    pub fn new(
+        execution_svc: Arc<dyn ExecutionService>,
+        metastore: Arc<dyn Metastore>,
+        config: Config,
+    ) -> Self {
+        Self {
+            execution_svc,
+            metastore,
+            config: Arc::new(config),
+        }
+    }
+}
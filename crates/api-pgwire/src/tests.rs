@@ -0,0 +1,226 @@
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+use crate::connection::Connection;
+use crate::state::{Config, State};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use core_executor::service::CoreExecutionService;
+use core_executor::utils::Config as ExecutionConfig;
+use core_history::SlateDBHistoryStore;
+use core_metastore::{SlateDBMetastore, bootstrap_user};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+async fn connect(require_auth: bool) -> DuplexStream {
+    let metastore = Arc::new(SlateDBMetastore::new_in_memory().await);
+    bootstrap_user(metastore.as_ref(), "embucket", "embucket")
+        .await
+        .expect("Failed to create user");
+    let execution_svc = Arc::new(
+        CoreExecutionService::new(
+            metastore.clone(),
+            Arc::new(SlateDBHistoryStore::new_in_memory().await),
+            Arc::new(ExecutionConfig::default()),
+        )
+        .await
+        .expect("Failed to create execution service"),
+    );
+    let state = State::new(
+        execution_svc,
+        metastore,
+        Config {
+            require_auth,
+            tls: None,
+        },
+    );
+    let (client, server) = tokio::io::duplex(64 * 1024);
+    tokio::spawn(Connection::new(server, state).run());
+    client
+}
+
+fn cstring(body: &mut BytesMut, value: &str) {
+    body.put_slice(value.as_bytes());
+    body.put_u8(0);
+}
+
+async fn send(client: &mut DuplexStream, tag: u8, body: &[u8]) {
+    let mut message = BytesMut::new();
+    message.put_u8(tag);
+    message.put_i32(i32::try_from(body.len()).unwrap() + 4);
+    message.put_slice(body);
+    client.write_all(&message).await.unwrap();
+}
+
+async fn receive(client: &mut DuplexStream) -> (u8, Bytes) {
+    let tag = client.read_u8().await.unwrap();
+    let len = usize::try_from(client.read_i32().await.unwrap()).unwrap();
+    let mut body = vec![0; len - 4];
+    client.read_exact(&mut body).await.unwrap();
+    (tag, body.into())
+}
+
+/// Messages up to the next `ReadyForQuery`
+async fn receive_until_ready(client: &mut DuplexStream) -> Vec<(u8, Bytes)> {
+    let mut messages = Vec::new();
+    loop {
+        let message = receive(client).await;
+        let ready = message.0 == b'Z';
+        messages.push(message);
+        if ready {
+            return messages;
+        }
+    }
+}
+
+fn tags(messages: &[(u8, Bytes)]) -> String {
+    messages.iter().map(|(tag, _)| char::from(*tag)).collect()
+}
+
+/// Values of a `DataRow`, in the text format
+fn row_values(mut body: Bytes) -> Vec<Option<String>> {
+    let count = body.get_i16();
+    (0..count)
+        .map(|_| {
+            let len = usize::try_from(body.get_i32()).ok()?;
+            Some(String::from_utf8(body.split_to(len).to_vec()).unwrap())
+        })
+        .collect()
+}
+
+async fn startup(client: &mut DuplexStream) {
+    let mut startup = BytesMut::new();
+    startup.put_i32(196_608);
+    cstring(&mut startup, "user");
+    cstring(&mut startup, "embucket");
+    cstring(&mut startup, "database");
+    cstring(&mut startup, "embucket");
+    startup.put_u8(0);
+    let mut message = BytesMut::new();
+    message.put_i32(i32::try_from(startup.len()).unwrap() + 4);
+    message.put_slice(&startup);
+    client.write_all(&message).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_authentication_requires_tls() {
+    let mut client = connect(true).await;
+    let mut request = BytesMut::new();
+    request.put_i32(8);
+    request.put_i32(80_877_103);
+    client.write_all(&request).await.unwrap();
+    assert_eq!(client.read_u8().await.unwrap(), b'N');
+
+    startup(&mut client).await;
+    let (tag, body) = receive(&mut client).await;
+    assert_eq!(tag, b'E');
+    assert!(String::from_utf8_lossy(&body).contains("28000"));
+}
+
+#[tokio::test]
+async fn test_simple_query_protocol() {
+    let mut client = connect(false).await;
+    startup(&mut client).await;
+    let messages = receive_until_ready(&mut client).await;
+    assert_eq!(messages[0], (b'R', Bytes::from_static(&[0, 0, 0, 0])));
+    assert!(tags(&messages).ends_with("KZ"));
+
+    let mut query = BytesMut::new();
+    cstring(
+        &mut query,
+        "SELECT 1 AS one; SET extra_float_digits = 3; SELECT 'a' AS b",
+    );
+    send(&mut client, b'Q', &query).await;
+    let messages = receive_until_ready(&mut client).await;
+    assert_eq!(tags(&messages), "TDCCTDCZ");
+    assert_eq!(row_values(messages[1].1.clone()), [Some("1".to_string())]);
+    assert_eq!(&messages[2].1[..], b"SELECT 1\0");
+    assert_eq!(&messages[3].1[..], b"SET\0");
+
+    // Statements after an error are not run
+    let mut query = BytesMut::new();
+    cstring(&mut query, "SELECT * FROM missing; SELECT 1");
+    send(&mut client, b'Q', &query).await;
+    assert_eq!(tags(&receive_until_ready(&mut client).await), "EZ");
+
+    let mut query = BytesMut::new();
+    cstring(
+        &mut query,
+        "SELECT n.nspname FROM pg_catalog.pg_namespace n \
+         WHERE n.nspname OPERATOR(pg_catalog.~) '^(public)$' COLLATE pg_catalog.default",
+    );
+    send(&mut client, b'Q', &query).await;
+    let messages = receive_until_ready(&mut client).await;
+    assert_eq!(tags(&messages), "TDCZ");
+    assert_eq!(
+        row_values(messages[1].1.clone()),
+        [Some("public".to_string())]
+    );
+
+    // Anonymous sessions work with tables in the bootstrapped schema
+    let mut query = BytesMut::new();
+    cstring(
+        &mut query,
+        "CREATE TABLE embucket.public.anonymous (id INT); \
+         INSERT INTO embucket.public.anonymous VALUES (1), (2)",
+    );
+    send(&mut client, b'Q', &query).await;
+    assert!(!tags(&receive_until_ready(&mut client).await).contains('E'));
+    let mut query = BytesMut::new();
+    cstring(&mut query, "SELECT COUNT(*) FROM embucket.public.anonymous");
+    send(&mut client, b'Q', &query).await;
+    let messages = receive_until_ready(&mut client).await;
+    assert_eq!(tags(&messages), "TDCZ");
+    assert_eq!(row_values(messages[1].1.clone()), [Some("2".to_string())]);
+    send(&mut client, b'X', &[]).await;
+}
+
+#[tokio::test]
+async fn test_extended_query_protocol() {
+    let mut client = connect(false).await;
+    startup(&mut client).await;
+    receive_until_ready(&mut client).await;
+
+    let mut parse = BytesMut::new();
+    cstring(&mut parse, "s1");
+    cstring(&mut parse, "SELECT $1 || '!' AS greeting");
+    parse.put_i16(1);
+    parse.put_u32(25);
+    send(&mut client, b'P', &parse).await;
+    let mut describe = BytesMut::new();
+    describe.put_u8(b'S');
+    cstring(&mut describe, "s1");
+    send(&mut client, b'D', &describe).await;
+    let mut bind = BytesMut::new();
+    cstring(&mut bind, "");
+    cstring(&mut bind, "s1");
+    bind.put_i16(0);
+    bind.put_i16(1);
+    bind.put_i32(5);
+    bind.put_slice(b"it's!");
+    bind.put_i16(0);
+    send(&mut client, b'B', &bind).await;
+    let mut execute = BytesMut::new();
+    cstring(&mut execute, "");
+    execute.put_i32(0);
+    send(&mut client, b'E', &execute).await;
+    send(&mut client, b'S', &[]).await;
+
+    let messages = receive_until_ready(&mut client).await;
+    assert_eq!(tags(&messages), "1tT2DCZ");
+    assert_eq!(
+        row_values(messages[4].1.clone()),
+        [Some("it's!!".to_string())]
+    );
+
+    // Messages are skipped until the Sync after an error
+    let mut bind = BytesMut::new();
+    cstring(&mut bind, "");
+    cstring(&mut bind, "missing");
+    bind.put_i16(0);
+    bind.put_i16(0);
+    bind.put_i16(0);
+    send(&mut client, b'B', &bind).await;
+    send(&mut client, b'E', &execute).await;
+    send(&mut client, b'S', &[]).await;
+    let messages = receive_until_ready(&mut client).await;
+    assert_eq!(tags(&messages), "EZ");
+    assert!(String::from_utf8_lossy(&messages[0].1).contains("26000"));
+}
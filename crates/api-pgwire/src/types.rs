@@ -0,0 +1,451 @@
+//! Postgres types of Arrow columns, and their text and binary encodings.
+use crate::messages::{BINARY_FORMAT, BackendMessage, FieldDescription, TEXT_FORMAT};
+use datafusion::arrow::array::{Array, ArrayRef, AsArray, RecordBatch};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{
+    DataType, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Schema,
+};
+use datafusion::arrow::error::ArrowError;
+use datafusion::arrow::util::display::{ArrayFormatter, FormatOptions};
+use std::fmt::Display;
+use std::ops::Range;
+
+/// A type of the `pg_type` catalog
+#[derive(Debug, PartialEq, Eq)]
+pub struct PgType {
+    pub oid: u32,
+    pub name: &'static str,
+    /// Size of the fixed-size types, -1 for the variable-size ones
+    pub len: i16,
+    pub category: &'static str,
+}
+
+const fn pg_type_of(oid: u32, name: &'static str, len: i16, category: &'static str) -> PgType {
+    PgType {
+        oid,
+        name,
+        len,
+        category,
+    }
+}
+
+pub const BOOL: PgType = pg_type_of(16, "bool", 1, "B");
+pub const BYTEA: PgType = pg_type_of(17, "bytea", -1, "U");
+pub const NAME: PgType = pg_type_of(19, "name", 64, "S");
+pub const INT8: PgType = pg_type_of(20, "int8", 8, "N");
+pub const INT2: PgType = pg_type_of(21, "int2", 2, "N");
+pub const INT4: PgType = pg_type_of(23, "int4", 4, "N");
+pub const TEXT: PgType = pg_type_of(25, "text", -1, "S");
+pub const OID: PgType = pg_type_of(26, "oid", 4, "N");
+pub const FLOAT4: PgType = pg_type_of(700, "float4", 4, "N");
+pub const FLOAT8: PgType = pg_type_of(701, "float8", 8, "N");
+pub const VARCHAR: PgType = pg_type_of(1043, "varchar", -1, "S");
+pub const DATE: PgType = pg_type_of(1082, "date", 4, "D");
+pub const TIME: PgType = pg_type_of(1083, "time", 8, "D");
+pub const TIMESTAMP: PgType = pg_type_of(1114, "timestamp", 8, "D");
+pub const TIMESTAMPTZ: PgType = pg_type_of(1184, "timestamptz", 8, "D");
+pub const INTERVAL: PgType = pg_type_of(1186, "interval", 16, "T");
+pub const NUMERIC: PgType = pg_type_of(1700, "numeric", -1, "N");
+
+/// The types listed in `pg_type`
+pub const PG_TYPES: [&PgType; 17] = [
+    &BOOL,
+    &BYTEA,
+    &NAME,
+    &INT8,
+    &INT2,
+    &INT4,
+    &TEXT,
+    &OID,
+    &FLOAT4,
+    &FLOAT8,
+    &VARCHAR,
+    &DATE,
+    &TIME,
+    &TIMESTAMP,
+    &TIMESTAMPTZ,
+    &INTERVAL,
+    &NUMERIC,
+];
+
+/// Postgres type of the values of an Arrow column. Nested values are sent as text.
+#[must_use]
+pub const fn pg_type(data_type: &DataType) -> &'static PgType {
+    match data_type {
+        DataType::Boolean => &BOOL,
+        DataType::Int8 | DataType::Int16 | DataType::UInt8 => &INT2,
+        DataType::Int32 | DataType::UInt16 => &INT4,
+        DataType::Int64 | DataType::UInt32 | DataType::UInt64 => &INT8,
+        DataType::Float16 | DataType::Float32 => &FLOAT4,
+        DataType::Float64 => &FLOAT8,
+        DataType::Decimal32(..)
+        | DataType::Decimal64(..)
+        | DataType::Decimal128(..)
+        | DataType::Decimal256(..) => &NUMERIC,
+        DataType::Binary
+        | DataType::LargeBinary
+        | DataType::BinaryView
+        | DataType::FixedSizeBinary(_) => &BYTEA,
+        DataType::Date32 | DataType::Date64 => &DATE,
+        DataType::Time32(_) | DataType::Time64(_) => &TIME,
+        DataType::Timestamp(_, None) => &TIMESTAMP,
+        DataType::Timestamp(_, Some(_)) => &TIMESTAMPTZ,
+        DataType::Interval(_) | DataType::Duration(_) => &INTERVAL,
+        _ => &TEXT,
+    }
+}
+
+#[must_use]
+pub fn pg_type_by_oid(oid: u32) -> Option<&'static PgType> {
+    PG_TYPES.into_iter().find(|pg_type| pg_type.oid == oid)
+}
+
+#[must_use]
+pub fn pg_type_by_name(name: &str) -> Option<&'static PgType> {
+    PG_TYPES
+        .into_iter()
+        .find(|pg_type| pg_type.name.eq_ignore_ascii_case(name))
+}
+
+/// Arrow type of the values of the types sent in the binary format
+const fn binary_arrow_type(oid: u32) -> Option<DataType> {
+    match oid {
+        16 => Some(DataType::Boolean),
+        20 => Some(DataType::Int64),
+        21 => Some(DataType::Int16),
+        23 => Some(DataType::Int32),
+        700 => Some(DataType::Float32),
+        701 => Some(DataType::Float64),
+        _ => None,
+    }
+}
+
+/// Columns of a `RowDescription`, in the result formats the client asked for.
+/// Columns of types without a binary encoding are sent as text anyway, which
+/// clients see in the format of the description.
+#[must_use]
+pub fn field_descriptions(schema: &Schema, result_formats: &[i16]) -> Vec<FieldDescription> {
+    schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let pg_type = pg_type(field.data_type());
+            let format = match result_formats {
+                [] => TEXT_FORMAT,
+                [format] => *format,
+                formats => formats.get(index).copied().unwrap_or(TEXT_FORMAT),
+            };
+            let format = if format == BINARY_FORMAT && binary_arrow_type(pg_type.oid).is_some() {
+                BINARY_FORMAT
+            } else {
+                TEXT_FORMAT
+            };
+            FieldDescription {
+                name: field.name().clone(),
+                type_oid: pg_type.oid,
+                type_len: pg_type.len,
+                format,
+            }
+        })
+        .collect()
+}
+
+enum ColumnEncoder<'a> {
+    Text(ArrayFormatter<'a>),
+    Bool(&'a dyn Array),
+    Bytea(&'a dyn Array),
+    Binary(ArrayRef),
+}
+
+/// `DataRow`s of the rows of the batch, encoded as the fields describe
+pub fn data_rows(
+    batch: &RecordBatch,
+    fields: &[FieldDescription],
+    rows: Range<usize>,
+) -> Result<Vec<BackendMessage>, ArrowError> {
+    let options = FormatOptions::new()
+        .with_timestamp_format(Some("%Y-%m-%d %H:%M:%S%.f"))
+        .with_timestamp_tz_format(Some("%Y-%m-%d %H:%M:%S%.f%:z"))
+        .with_time_format(Some("%H:%M:%S%.f"));
+    let encoders = batch
+        .columns()
+        .iter()
+        .zip(fields)
+        .map(|(column, field)| {
+            if field.format == BINARY_FORMAT
+                && let Some(data_type) = binary_arrow_type(field.type_oid)
+            {
+                return Ok(ColumnEncoder::Binary(cast(column, &data_type)?));
+            }
+            Ok(match field.type_oid {
+                16 => ColumnEncoder::Bool(column.as_ref()),
+                17 => ColumnEncoder::Bytea(column.as_ref()),
+                _ => ColumnEncoder::Text(ArrayFormatter::try_new(column.as_ref(), &options)?),
+            })
+        })
+        .collect::<Result<Vec<_>, ArrowError>>()?;
+
+    rows.map(|row| {
+        let values = batch
+            .columns()
+            .iter()
+            .zip(&encoders)
+            .map(|(column, encoder)| {
+                if column.is_null(row) {
+                    return Ok(None);
+                }
+                encode_value(encoder, row).map(Some)
+            })
+            .collect::<Result<_, ArrowError>>()?;
+        Ok(BackendMessage::DataRow(values))
+    })
+    .collect()
+}
+
+fn encode_value(encoder: &ColumnEncoder<'_>, row: usize) -> Result<Vec<u8>, ArrowError> {
+    Ok(match encoder {
+        ColumnEncoder::Text(formatter) => formatter.value(row).try_to_string()?.into_bytes(),
+        ColumnEncoder::Bool(column) => {
+            let value = column.as_boolean().value(row);
+            if value { b"t".to_vec() } else { b"f".to_vec() }
+        }
+        ColumnEncoder::Bytea(column) => {
+            let bytes = match column.data_type() {
+                DataType::Binary => column.as_binary::<i32>().value(row),
+                DataType::LargeBinary => column.as_binary::<i64>().value(row),
+                DataType::BinaryView => column.as_binary_view().value(row),
+                _ => column.as_fixed_size_binary().value(row),
+            };
+            let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+            format!("\\x{hex}").into_bytes()
+        }
+        ColumnEncoder::Binary(column) => match column.data_type() {
+            DataType::Boolean => vec![u8::from(column.as_boolean().value(row))],
+            DataType::Int16 => column
+                .as_primitive::<Int16Type>()
+                .value(row)
+                .to_be_bytes()
+                .to_vec(),
+            DataType::Int32 => column
+                .as_primitive::<Int32Type>()
+                .value(row)
+                .to_be_bytes()
+                .to_vec(),
+            DataType::Int64 => column
+                .as_primitive::<Int64Type>()
+                .value(row)
+                .to_be_bytes()
+                .to_vec(),
+            DataType::Float32 => column
+                .as_primitive::<Float32Type>()
+                .value(row)
+                .to_be_bytes()
+                .to_vec(),
+            _ => column
+                .as_primitive::<Float64Type>()
+                .value(row)
+                .to_be_bytes()
+                .to_vec(),
+        },
+    })
+}
+
+/// SQL literal of a bound parameter, `None` when its binary format isn't supported
+#[must_use]
+pub fn parameter_literal(
+    type_oid: u32,
+    format: i16,
+    value: Option<&[u8]>,
+    backslash_escapes: bool,
+) -> Option<String> {
+    let Some(value) = value else {
+        return Some("NULL".to_string());
+    };
+    if format == BINARY_FORMAT {
+        return binary_parameter_literal(type_oid, value, backslash_escapes);
+    }
+    let value = String::from_utf8_lossy(value);
+    let literal = match type_oid {
+        20 | 21 | 23 | 26 | 1700 if value.trim().parse::<f64>().is_ok_and(f64::is_finite) => {
+            numeric_literal(value.trim())
+        }
+        700 | 701 => match value.trim().parse::<f64>() {
+            Ok(number) => float_literal(value.trim(), number.is_finite(), type_oid),
+            Err(_) => quote_literal(&value, backslash_escapes),
+        },
+        16 => match value.trim().to_ascii_lowercase().as_str() {
+            "t" | "true" | "y" | "yes" | "on" | "1" => "TRUE".to_string(),
+            "f" | "false" | "n" | "no" | "off" | "0" => "FALSE".to_string(),
+            _ => quote_literal(&value, backslash_escapes),
+        },
+        _ => quote_literal(&value, backslash_escapes),
+    };
+    Some(literal)
+}
+
+fn binary_parameter_literal(
+    type_oid: u32,
+    value: &[u8],
+    backslash_escapes: bool,
+) -> Option<String> {
+    let literal = match type_oid {
+        16 => (if value.first() == Some(&1) {
+            "TRUE"
+        } else {
+            "FALSE"
+        })
+        .to_string(),
+        21 => numeric_literal(i16::from_be_bytes(value.try_into().ok()?)),
+        23 => numeric_literal(i32::from_be_bytes(value.try_into().ok()?)),
+        20 => numeric_literal(i64::from_be_bytes(value.try_into().ok()?)),
+        700 => {
+            let value = f32::from_be_bytes(value.try_into().ok()?);
+            float_literal(value, value.is_finite(), type_oid)
+        }
+        701 => {
+            let value = f64::from_be_bytes(value.try_into().ok()?);
+            float_literal(value, value.is_finite(), type_oid)
+        }
+        25 | 1043 => quote_literal(std::str::from_utf8(value).ok()?, backslash_escapes),
+        _ => return None,
+    };
+    Some(literal)
+}
+
+/// Numbers are parenthesized, so that a negative one can't form a comment or
+/// another operator with what precedes it, as in `5-$1`
+fn numeric_literal(value: impl Display) -> String {
+    format!("({value})")
+}
+
+/// `NaN` and infinities have no literal, they are cast from a string
+fn float_literal(value: impl Display, finite: bool, type_oid: u32) -> String {
+    if finite {
+        return numeric_literal(value);
+    }
+    let sql_type = if type_oid == 700 { "FLOAT" } else { "DOUBLE" };
+    format!("CAST('{value}' AS {sql_type})")
+}
+
+/// Quotes a string literal, doubling quotes, and backslashes in the dialects where
+/// they escape characters
+#[must_use]
+pub fn quote_literal(value: &str, backslash_escapes: bool) -> String {
+    let mut literal = String::with_capacity(value.len() + 2);
+    literal.push('\'');
+    for char in value.chars() {
+        match char {
+            '\'' => literal.push_str("''"),
+            '\\' if backslash_escapes => literal.push_str("\\\\"),
+            char => literal.push(char),
+        }
+    }
+    literal.push('\'');
+    literal
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::{BooleanArray, Int32Array, StringArray};
+    use datafusion::arrow::datatypes::Field;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_data_rows() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("active", DataType::Boolean, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec![Some("a"), None])),
+                Arc::new(BooleanArray::from(vec![Some(true), Some(false)])),
+            ],
+        )
+        .unwrap();
+        let fields = field_descriptions(&schema, &[BINARY_FORMAT, TEXT_FORMAT, TEXT_FORMAT]);
+        assert_eq!(fields[0].type_oid, INT4.oid);
+        assert_eq!(fields[0].format, BINARY_FORMAT);
+        // Text has no binary encoding here
+        let fields = field_descriptions(&schema, &[BINARY_FORMAT]);
+        assert_eq!(fields[1].format, TEXT_FORMAT);
+
+        let rows = data_rows(&batch, &fields, 0..2).unwrap();
+        assert_eq!(
+            rows,
+            [
+                BackendMessage::DataRow(vec![
+                    Some(1_i32.to_be_bytes().to_vec()),
+                    Some(b"a".to_vec()),
+                    Some(vec![1]),
+                ]),
+                BackendMessage::DataRow(vec![
+                    Some(2_i32.to_be_bytes().to_vec()),
+                    None,
+                    Some(vec![0]),
+                ]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parameter_literals() {
+        assert_eq!(
+            parameter_literal(INT4.oid, TEXT_FORMAT, Some(b"42"), false).as_deref(),
+            Some("(42)")
+        );
+        // `SELECT 5-$1` with -1 must not become `5--1`, a comment
+        assert_eq!(
+            parameter_literal(INT4.oid, TEXT_FORMAT, Some(b"-1"), false).as_deref(),
+            Some("(-1)")
+        );
+        assert_eq!(
+            parameter_literal(
+                INT4.oid,
+                BINARY_FORMAT,
+                Some(&(-1_i32).to_be_bytes()),
+                false
+            )
+            .as_deref(),
+            Some("(-1)")
+        );
+        assert_eq!(
+            parameter_literal(FLOAT8.oid, TEXT_FORMAT, Some(b"NaN"), false).as_deref(),
+            Some("CAST('NaN' AS DOUBLE)")
+        );
+        assert_eq!(
+            parameter_literal(
+                FLOAT8.oid,
+                BINARY_FORMAT,
+                Some(&f64::NEG_INFINITY.to_be_bytes()),
+                false
+            )
+            .as_deref(),
+            Some("CAST('-inf' AS DOUBLE)")
+        );
+        assert_eq!(
+            parameter_literal(INT4.oid, TEXT_FORMAT, Some(b"1; DROP"), false).as_deref(),
+            Some("'1; DROP'")
+        );
+        assert_eq!(
+            parameter_literal(0, TEXT_FORMAT, Some(b"it's"), false).as_deref(),
+            Some("'it''s'")
+        );
+        assert_eq!(
+            parameter_literal(INT8.oid, BINARY_FORMAT, Some(&7_i64.to_be_bytes()), false)
+                .as_deref(),
+            Some("(7)")
+        );
+        assert_eq!(
+            parameter_literal(TEXT.oid, TEXT_FORMAT, None, false).as_deref(),
+            Some("NULL")
+        );
+        assert_eq!(quote_literal("a\\b", true), "'a\\\\b'");
+    }
+}
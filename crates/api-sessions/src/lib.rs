@@ -2,4 +2,4 @@ pub mod error;
 pub mod layer;
 pub mod session;
 
-pub use crate::session::DFSessionId;
+pub use crate::session::{DFSessionId, SessionUser};
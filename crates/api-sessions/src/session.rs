@@ -1,7 +1,9 @@
 use crate::error as session_error;
 use axum::extract::FromRequestParts;
 use core_executor::ExecutionAppState;
+use core_executor::models::SessionOptions;
 use core_executor::service::ExecutionService;
+use core_executor::session::SESSION_INACTIVITY_EXPIRATION_SECONDS;
 use http::header::COOKIE;
 use http::request::Parts;
 use http::{HeaderMap, HeaderName};
@@ -59,23 +61,47 @@ where
             .record("located_at", located_at)
             .record("session_id", session_id.clone());
 
-        Self::get_or_create_session(execution_svc, session_id).await
+        let user = req
+            .extensions
+            .get::<SessionUser>()
+            .map(|SessionUser(user)| user.clone());
+        Self::get_or_create_session(execution_svc, session_id, user).await
     }
 }
 
+/// User the session of a request is opened for, put in the request extensions by
+/// the auth layer. Sessions of requests without one have no user.
+#[derive(Debug, Clone)]
+pub struct SessionUser(pub String);
+
 impl DFSessionId {
     #[tracing::instrument(level = "info", skip(execution_svc), fields(sessions_count))]
     async fn get_or_create_session(
         execution_svc: Arc<dyn ExecutionService>,
         session_id: String,
+        user: Option<String>,
     ) -> Result<Self, session_error::Error> {
+        // A session opened for another user is not reused
+        if let Some(user) = &user
+            && let Ok(session) = execution_svc.get_session(&session_id).await
+            && session.user.as_ref() != Some(user)
+        {
+            execution_svc
+                .delete_session(&session_id)
+                .await
+                .context(session_error::ExecutionSnafu)?;
+        }
         if !execution_svc
-            .update_session_expiry(&session_id)
+            .update_session_expiry(&session_id, SESSION_INACTIVITY_EXPIRATION_SECONDS)
             .await
             .context(session_error::ExecutionSnafu)?
         {
+            let options = SessionOptions {
+                user,
+                ..SessionOptions::default()
+            };
             let _ = execution_svc
-                .create_session(&session_id)
+                .create_session_with_options(&session_id, options)
                 .await
                 .context(session_error::ExecutionSnafu)?;
         }
@@ -137,6 +163,7 @@ mod tests {
     use core_executor::models::QueryContext;
     use core_executor::service::ExecutionService;
     use core_executor::service::make_test_execution_svc;
+    use core_executor::session::SESSION_INACTIVITY_EXPIRATION_SECONDS;
     use core_executor::session::to_unix;
     use std::sync::atomic::Ordering;
     use std::time::Duration;
@@ -1,6 +1,9 @@
 cfg_if::cfg_if! {
     if #[cfg(feature = "default-server")] {
         use core_executor::models::ColumnInfo as ColumnInfoModel;
+        use core_executor::models::{
+            BindingValue as BindingValueModel, QueryBinding as QueryBindingModel,
+        };
         use core_history::result_set::Row;
     } else {
         // Define simple representation for Row
@@ -20,6 +23,8 @@ pub struct LoginRequestQueryParams {
     pub request_id: String,
     pub database_name: Option<String>,
     pub schema_name: Option<String>,
+    pub warehouse: Option<String>,
+    pub role_name: Option<String>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -35,8 +40,37 @@ pub struct LoginResponse {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct LoginResponseData {
     pub token: String,
+    /// Token to renew the session token with, the session id like the session token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub master_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validity_in_seconds: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub master_validity_in_seconds: Option<i64>,
+    /// Session parameters the session was created with
+    #[serde(default)]
+    pub parameters: Vec<SessionParameter>,
+    #[serde(default)]
+    pub session_info: SessionInfo,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionParameter {
+    pub name: String,
+    pub value: serde_json::Value,
+}
+
+/// Current database, schema, warehouse and role of a new session
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionInfo {
+    pub database_name: Option<String>,
+    pub schema_name: Option<String>,
+    pub warehouse_name: Option<String>,
+    pub role_name: Option<String>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -48,7 +82,15 @@ pub struct LoginRequestData {
     pub account_name: String,
     pub login_name: String,
     pub client_environment: ClientEnvironment,
+    /// Empty when logging in with a token
+    #[serde(default)]
     pub password: String,
+    /// `SNOWFLAKE` (password, the default), `SNOWFLAKE_JWT` or `PROGRAMMATIC_ACCESS_TOKEN`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authenticator: Option<String>,
+    /// The key-pair JWT or the programmatic access token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
     pub session_parameters: HashMap<String, serde_json::Value>,
 }
 
@@ -81,6 +123,100 @@ pub struct QueryRequest {
 pub struct QueryRequestBody {
     pub sql_text: String,
     pub async_exec: bool,
+    /// Values of the `?` / `:N` placeholders, keyed by their 1-based position
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bindings: Option<HashMap<String, QueryBinding>>,
+    /// Statement parameters, like `MULTI_STATEMENT_COUNT`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<HashMap<String, serde_json::Value>>,
+    /// Plan the query and describe its result without executing it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub describe_only: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryBinding {
+    #[serde(rename = "type")]
+    pub binding_type: String,
+    pub value: BindingValue,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BindingValue {
+    Single(Option<String>),
+    /// Array bind, used by drivers for bulk inserts
+    Array(Vec<Option<String>>),
+}
+
+#[cfg(feature = "default-server")]
+impl From<QueryBinding> for QueryBindingModel {
+    fn from(binding: QueryBinding) -> Self {
+        Self {
+            binding_type: binding.binding_type,
+            value: match binding.value {
+                BindingValue::Single(value) => BindingValueModel::Single(value),
+                BindingValue::Array(values) => BindingValueModel::Array(values),
+            },
+        }
+    }
+}
+
+/// Bindings in placeholder order
+#[cfg(feature = "default-server")]
+#[must_use]
+pub fn ordered_bindings(bindings: HashMap<String, QueryBinding>) -> Vec<QueryBindingModel> {
+    let mut bindings = bindings.into_iter().collect::<Vec<_>>();
+    bindings.sort_by_key(|(position, _)| position.parse::<usize>().unwrap_or(usize::MAX));
+    bindings
+        .into_iter()
+        .map(|(_, binding)| binding.into())
+        .collect()
+}
+
+/// Number of statements expected in the request, `0` for any number. Drivers send
+/// `MULTI_STATEMENT_COUNT` as a number or as a string.
+#[must_use]
+pub fn multi_statement_count(parameters: &HashMap<String, serde_json::Value>) -> Option<usize> {
+    match parameters.get("MULTI_STATEMENT_COUNT")? {
+        serde_json::Value::Number(count) => count.as_u64()?.try_into().ok(),
+        serde_json::Value::String(count) => count.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Body of `/session/token-request`, sent with the master token as authorization
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenewSessionRequest {
+    pub old_session_token: String,
+    /// Always `RENEW`
+    pub request_type: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenewSessionResponse {
+    pub data: Option<RenewSessionData>,
+    pub success: bool,
+    pub message: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenewSessionData {
+    pub session_token: String,
+    #[serde(rename = "validityInSecondsST")]
+    pub validity_in_seconds_st: i64,
+    pub master_token: String,
+    #[serde(rename = "validityInSecondsMT")]
+    pub validity_in_seconds_mt: i64,
+}
+
+/// Query parameters of `/session`, drivers close a connection with `delete=true`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloseSessionQueryParams {
+    #[serde(default)]
+    pub delete: bool,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -90,6 +226,49 @@ pub struct AbortRequestBody {
     pub request_id: Uuid, // duplicate in body, taken from snowflake connector
 }
 
+/// Response of `/monitoring/queries/{queryId}`, polled by the drivers for the status
+/// of asynchronous queries
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryStatusResponse {
+    pub data: Option<QueryStatusData>,
+    pub success: bool,
+    pub message: Option<String>,
+    pub code: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryStatusData {
+    pub queries: Vec<QueryMonitoringInfo>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryMonitoringInfo {
+    pub id: String,
+    /// `RUNNING`, `SUCCESS`, `FAILED_WITH_ERROR` or `ABORTED`, as the drivers name them
+    pub status: String,
+    pub sql_text: String,
+    /// Milliseconds since the epoch
+    pub start_time: i64,
+    pub end_time: i64,
+    pub total_duration: i64,
+    pub error_code: Option<String>,
+    pub error_message: Option<String>,
+    /// Progress of a running query
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<QueryProgressInfo>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryProgressInfo {
+    pub rows_scanned: usize,
+    pub bytes_scanned: usize,
+    pub files_processed: usize,
+    pub files_total: usize,
+    pub current_operator: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct ResponseData {
@@ -108,6 +287,66 @@ pub struct ResponseData {
     pub sql_state: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub query_id: Option<String>,
+    /// Result chunks following the inlined first one, downloaded by the client
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunks: Option<Vec<ChunkInfo>>,
+    /// Headers the client sends along with chunk downloads
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_headers: Option<HashMap<String, String>>,
+    /// Comma separated query ids of the statements of a multi-statement request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_ids: Option<String>,
+    /// Comma separated statement type ids of the statements of a multi-statement request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_types: Option<String>,
+    /// Files the client uploads for `PUT`, or downloads for `GET`
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub file_transfer: Option<FileTransferData>,
+}
+
+/// Instructions of a `PUT` or `GET` response. Files are transferred from and to
+/// presigned URLs, the way drivers transfer files of stages on GCS.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FileTransferData {
+    /// `UPLOAD` or `DOWNLOAD`
+    pub command: String,
+    #[serde(rename = "src_locations")]
+    pub src_locations: Vec<String>,
+    pub stage_info: StageInfo,
+    pub auto_compress: bool,
+    pub overwrite: bool,
+    pub source_compression: String,
+    pub parallel: u32,
+    /// Local directory `GET` downloads the files to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_location: Option<String>,
+    /// Download URLs of `GET`, one per source location
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presigned_urls: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct StageInfo {
+    pub location_type: String,
+    pub location: String,
+    pub path: String,
+    pub region: Option<String>,
+    pub creds: HashMap<String, String>,
+    pub is_client_side_encrypted: bool,
+    /// Upload URL of `PUT`, when it names a single file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presigned_url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkInfo {
+    pub url: String,
+    pub row_count: usize,
+    pub uncompressed_size: usize,
+    pub compressed_size: usize,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -154,8 +393,99 @@ impl From<ColumnInfoModel> for ColumnInfo {
     }
 }
 
+/// Credentials of the user created on startup, logins are validated against
+/// the metastore users. Requests are only served without auth when it's disabled.
 #[derive(Clone, Default)]
 pub struct Auth {
     pub demo_user: String,
     pub demo_password: String,
+    pub disabled: bool,
+}
+
+/// Body of `POST /api/v2/statements`, the SQL API
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatementRequest {
+    pub statement: String,
+    /// Timeout in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warehouse: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    /// Values of the `?` / `:N` placeholders, keyed by their 1-based position
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bindings: Option<HashMap<String, QueryBinding>>,
+    /// Session parameters of the statement, like `QUERY_TAG`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<HashMap<String, serde_json::Value>>,
+}
+
+/// Query parameters of `POST /api/v2/statements`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatementQueryParams {
+    /// Identifies the request, so a retried request doesn't run the statement again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<Uuid>,
+    /// Respond right away with the statement handle instead of the result
+    #[serde(default, rename = "async")]
+    pub async_exec: bool,
+    #[serde(default)]
+    pub retry: bool,
+}
+
+/// Query parameters of `GET /api/v2/statements/{statementHandle}`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatementPartitionQueryParams {
+    #[serde(default)]
+    pub partition: usize,
+}
+
+/// Response of the SQL API. Partitions after the first one only have `data`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatementResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_set_meta_data: Option<ResultSetMetaData>,
+    /// Rows of the partition, values are all strings in the `jsonv2` format
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Vec<Vec<Option<String>>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statement_status_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sql_state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statement_handle: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Milliseconds since the epoch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_on: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResultSetMetaData {
+    pub num_rows: usize,
+    /// Always `jsonv2`
+    pub format: String,
+    pub row_type: Vec<ColumnInfo>,
+    pub partition_info: Vec<PartitionInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PartitionInfo {
+    pub row_count: usize,
+    pub uncompressed_size: usize,
 }
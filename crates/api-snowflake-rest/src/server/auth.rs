@@ -0,0 +1,150 @@
+//! Login authentication.
+//!
+//! Besides passwords, users log in with a JWT signed with the private key of
+//! one of their RSA public keys (`authenticator=SNOWFLAKE_JWT`), or with one of
+//! their programmatic access tokens (`authenticator=PROGRAMMATIC_ACCESS_TOKEN`),
+//! which are also accepted in place of the password, as Snowflake does.
+//!
+//! The SQL API has no login, every request brings a key-pair JWT or a
+//! programmatic access token as bearer token.
+use crate::models::LoginRequestData;
+use crate::server::error::{self as api_snowflake_rest_error, Result};
+use core_metastore::{AuthenticatedUser, Metastore, User, public_key_fingerprint};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode};
+use serde::Deserialize;
+use snafu::{OptionExt, ResultExt};
+
+pub const JWT_AUTHENTICATOR: &str = "SNOWFLAKE_JWT";
+pub const ACCESS_TOKEN_AUTHENTICATOR: &str = "PROGRAMMATIC_ACCESS_TOKEN";
+/// `X-Snowflake-Authorization-Token-Type` of key-pair JWTs, the SQL API default.
+/// Access tokens have the `PROGRAMMATIC_ACCESS_TOKEN` type.
+pub const KEY_PAIR_JWT_TOKEN_TYPE: &str = "KEYPAIR_JWT";
+
+pub async fn authenticate(
+    metastore: &dyn Metastore,
+    data: &LoginRequestData,
+) -> Result<AuthenticatedUser> {
+    let authenticator = data.authenticator.as_deref().unwrap_or_default();
+    let token = data.token.as_deref().unwrap_or(&data.password);
+    let authenticated = if authenticator.eq_ignore_ascii_case(JWT_AUTHENTICATOR) {
+        key_pair_jwt_user(metastore, &data.login_name, token).await?
+    } else if authenticator.eq_ignore_ascii_case(ACCESS_TOKEN_AUTHENTICATOR) {
+        metastore
+            .authenticate_access_token(token)
+            .await
+            .context(api_snowflake_rest_error::MetastoreSnafu)?
+            .filter(|authenticated| {
+                authenticated
+                    .user
+                    .name
+                    .eq_ignore_ascii_case(&data.login_name)
+            })
+    } else {
+        metastore
+            .authenticate(&data.login_name, &data.password)
+            .await
+            .context(api_snowflake_rest_error::MetastoreSnafu)?
+    };
+    authenticated.context(api_snowflake_rest_error::InvalidAuthDataSnafu)
+}
+
+/// Authenticates a SQL API request by its bearer token
+pub async fn authenticate_bearer(
+    metastore: &dyn Metastore,
+    token_type: Option<&str>,
+    token: &str,
+) -> Result<AuthenticatedUser> {
+    let token_type = token_type.unwrap_or(KEY_PAIR_JWT_TOKEN_TYPE);
+    let authenticated = if token_type.eq_ignore_ascii_case(KEY_PAIR_JWT_TOKEN_TYPE) {
+        match key_pair_jwt_subject(token) {
+            Some(name) => key_pair_jwt_user(metastore, &name, token).await?,
+            None => None,
+        }
+    } else if token_type.eq_ignore_ascii_case(ACCESS_TOKEN_AUTHENTICATOR) {
+        metastore
+            .authenticate_access_token(token)
+            .await
+            .context(api_snowflake_rest_error::MetastoreSnafu)?
+    } else {
+        None
+    };
+    authenticated.context(api_snowflake_rest_error::InvalidAuthDataSnafu)
+}
+
+/// The enabled user a key-pair JWT is signed for
+async fn key_pair_jwt_user(
+    metastore: &dyn Metastore,
+    name: &str,
+    token: &str,
+) -> Result<Option<AuthenticatedUser>> {
+    let user = metastore
+        .get_user(&name.to_string())
+        .await
+        .context(api_snowflake_rest_error::MetastoreSnafu)?
+        .map(|user| user.data)
+        .filter(|user| !user.disabled && verify_key_pair_jwt(user, token));
+    Ok(user.map(|user| AuthenticatedUser {
+        user,
+        role_restriction: None,
+    }))
+}
+
+/// Claims of the JWTs issued by the connectors for key-pair authentication
+#[derive(Debug, Deserialize)]
+struct KeyPairClaims {
+    /// `ACCOUNT.USER.SHA256:<public key fingerprint>`
+    iss: String,
+    /// `ACCOUNT.USER`
+    sub: String,
+}
+
+/// Whether the JWT is signed with the private key of one of the user's public
+/// keys, is issued for that key and the user, and hasn't expired
+fn verify_key_pair_jwt(user: &User, token: &str) -> bool {
+    let mut validation = Validation::new(Algorithm::RS256);
+    validation.set_required_spec_claims(&["exp", "iss", "sub"]);
+    user.rsa_public_keys().any(|key| {
+        let Some(fingerprint) = public_key_fingerprint(key) else {
+            return false;
+        };
+        let Ok(decoding_key) = DecodingKey::from_rsa_pem(public_key_pem(key).as_bytes()) else {
+            return false;
+        };
+        decode::<KeyPairClaims>(token, &decoding_key, &validation).is_ok_and(|data| {
+            let KeyPairClaims { iss, sub } = data.claims;
+            let issued_for_key = iss
+                .strip_suffix(&fingerprint)
+                .and_then(|subject| subject.strip_suffix('.'))
+                .is_some_and(|subject| subject.eq_ignore_ascii_case(&sub));
+            let issued_for_user = sub
+                .split_once('.')
+                .is_some_and(|(_, name)| name.eq_ignore_ascii_case(&user.name));
+            issued_for_key && issued_for_user
+        })
+    })
+}
+
+/// User a key-pair JWT is issued for, read before its signature is verified
+/// against the public keys of that user
+fn key_pair_jwt_subject(token: &str) -> Option<String> {
+    let mut validation = Validation::new(Algorithm::RS256);
+    validation.insecure_disable_signature_validation();
+    validation.validate_exp = false;
+    validation.set_required_spec_claims(&["sub"]);
+    let data = decode::<KeyPairClaims>(token, &DecodingKey::from_secret(&[]), &validation).ok()?;
+    let (_, name) = data.claims.sub.split_once('.')?;
+    Some(name.to_lowercase())
+}
+
+/// Public keys are stored as the base64 body only
+fn public_key_pem(key: &str) -> String {
+    let lines: Vec<&str> = key
+        .as_bytes()
+        .chunks(64)
+        .filter_map(|line| std::str::from_utf8(line).ok())
+        .collect();
+    format!(
+        "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+        lines.join("\n")
+    )
+}
@@ -7,6 +7,7 @@ use core_executor::error_code::ErrorCode;
 use core_executor::snowflake_error::Entity;
 use core_history::QueryRecordId;
 use datafusion::arrow::error::ArrowError;
+use datafusion::error::DataFusionError;
 use error_stack::ErrorChainExt;
 use error_stack::ErrorExt;
 use error_stack_trace;
@@ -28,6 +29,22 @@ pub enum Error {
         location: Location,
     },
 
+    #[snafu(display("Failed to compress uploaded file with GZip"))]
+    GZipCompress {
+        #[snafu(source)]
+        error: std::io::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Failed to read uploaded file: {error}"))]
+    UploadBody {
+        #[snafu(source)]
+        error: axum::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
     #[snafu(display("Failed to parse login request"))]
     LoginRequestParse {
         #[snafu(source)]
@@ -106,6 +123,76 @@ pub enum Error {
         location: Location,
     },
 
+    #[snafu(display("Result chunk {index} of query {query_id} not found"))]
+    ResultChunkNotFound {
+        query_id: String,
+        index: usize,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Failed to compress result chunk with GZip"))]
+    ResultChunkCompress {
+        #[snafu(source)]
+        error: std::io::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Failed to create temporary file for result chunk: {error}"))]
+    ResultChunkTempFile {
+        #[snafu(source)]
+        error: DataFusionError,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Failed to read or write result chunk file: {error}"))]
+    ResultChunkIo {
+        #[snafu(source)]
+        error: std::io::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Statement {handle} not found"))]
+    StatementNotFound {
+        handle: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Stage file transfer not found or expired"))]
+    StageTransferNotFound {
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Object store error: {error}"))]
+    ObjectStore {
+        #[snafu(source)]
+        error: object_store::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display(
+        "Actual statement count {actual} did not match the desired statement count {expected}."
+    ))]
+    StatementCountMismatch {
+        expected: usize,
+        actual: usize,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Metastore error: {source}"))]
+    Metastore {
+        source: core_metastore::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
     #[snafu(transparent)]
     Execution { source: core_executor::Error },
 }
@@ -212,6 +299,7 @@ impl Error {
                 }
             }
             Self::GZipDecompress { .. }
+            | Self::UploadBody { .. }
             | Self::LoginRequestParse { .. }
             | Self::QueryBodyParse { .. }
             | Self::InvalidUuidFormat { .. } => {
@@ -222,6 +310,16 @@ impl Error {
                     ErrorCode::Other,
                 )
             }
+            Self::ResultChunkNotFound { .. } => (
+                http::StatusCode::NOT_FOUND,
+                SqlState::CantLocateQueryResult,
+                ErrorCode::Other,
+            ),
+            Self::StatementNotFound { .. } | Self::StageTransferNotFound { .. } => (
+                http::StatusCode::NOT_FOUND,
+                SqlState::Success,
+                ErrorCode::Other,
+            ),
             Self::MissingAuthToken { .. }
             | Self::MissingDbtSession { .. }
             | Self::InvalidAuthData { .. }
@@ -230,10 +328,25 @@ impl Error {
                 SqlState::Success,
                 ErrorCode::Other,
             ),
+            Self::Metastore { .. }
+            | Self::GZipCompress { .. }
+            | Self::ResultChunkCompress { .. }
+            | Self::ResultChunkTempFile { .. }
+            | Self::ResultChunkIo { .. } => (
+                http::StatusCode::INTERNAL_SERVER_ERROR,
+                SqlState::Success,
+                ErrorCode::Other,
+            ),
+            Self::ObjectStore { .. } => (
+                http::StatusCode::SERVICE_UNAVAILABLE,
+                SqlState::Success,
+                ErrorCode::Other,
+            ),
             Self::RowParse { .. }
             | Self::Utf8 { .. }
             | Self::Arrow { .. }
-            | Self::NotImplemented { .. } => {
+            | Self::NotImplemented { .. }
+            | Self::StatementCountMismatch { .. } => {
                 (http::StatusCode::OK, SqlState::Success, ErrorCode::Other)
             }
         };
@@ -269,6 +382,11 @@ impl Error {
                 query_result_format: None,
                 // Query uuid is returned to the user
                 query_id: Some(self.query_id().as_uuid().to_string()),
+                chunks: None,
+                chunk_headers: None,
+                result_ids: None,
+                result_types: None,
+                file_transfer: None,
             }),
             code: Some(error_code.to_string()),
         });
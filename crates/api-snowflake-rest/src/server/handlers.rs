@@ -1,42 +1,203 @@
+use super::auth::authenticate;
+use super::stage_transfers::TransferKind;
 use super::state::AppState;
 use crate::models::{
-    AbortRequestBody, JsonResponse, LoginRequestBody, LoginRequestData, LoginResponse,
-    LoginResponseData, QueryRequest, QueryRequestBody, ResponseData,
+    AbortRequestBody, CloseSessionQueryParams, FileTransferData, JsonResponse, LoginRequestBody,
+    LoginRequestQueryParams, LoginResponse, QueryRequest, QueryRequestBody, QueryStatusData,
+    QueryStatusResponse, RenewSessionData, RenewSessionRequest, RenewSessionResponse, ResponseData,
+    StageInfo, multi_statement_count, ordered_bindings,
 };
 use crate::server::error::{self as api_snowflake_rest_error, Result};
-use crate::server::helpers::{handle_historical_query_result, handle_query_ok_result};
+use crate::server::helpers::{
+    ChunkDownload, handle_historical_query_result, handle_multi_statement_result,
+    handle_query_ok_result, handle_query_stream_response, handle_query_stream_result,
+    login_response_data, login_session_options, query_monitoring_info, store_response,
+    stored_response,
+};
 use api_sessions::DFSessionId;
+use api_sessions::session::extract_token_from_auth;
 use axum::Json;
+use axum::body::{Body, BodyDataStream, Bytes};
 use axum::extract::{ConnectInfo, Path, Query, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
 use core_executor::RunningQueryId;
-use core_executor::models::QueryContext;
+use core_executor::models::{FileTransfer, FileTransferCommand, QueryContext};
+use core_executor::session::{
+    SESSION_INACTIVITY_EXPIRATION_SECONDS, SESSION_KEEP_ALIVE_EXPIRATION_SECONDS,
+};
+use core_executor::stage::{SourceCompression, is_file_transfer_statement};
+use core_executor::utils::{DataSerializationFormat, split_statements};
 use core_history::{QueryIdParam, QueryRecordId};
+use core_metastore::AuthenticatedUser;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use futures::StreamExt;
+use object_store::path::{Path as ObjectPath, PathPart};
+use object_store::{ObjectStore, PutPayload, WriteMultipart};
+use snafu::ResultExt;
+use std::collections::HashMap;
+use std::io::Write;
 use std::net::SocketAddr;
 use uuid::Uuid;
 
+pub(crate) fn header_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Chunked results are downloaded from the configured address of the server, with
+/// the authorization of the query
+fn chunk_download<'a>(
+    state: &'a AppState,
+    session_id: &'a str,
+    headers: &HeaderMap,
+) -> Option<ChunkDownload<'a>> {
+    let chunk_rows = state.config.result_chunk_rows?;
+    Some(ChunkDownload {
+        chunks: &state.result_chunks,
+        chunk_rows,
+        session_id,
+        base_url: &state.config.base_url,
+        headers: header_value(headers, header::AUTHORIZATION.as_str())
+            .map(|authorization| {
+                HashMap::from([(header::AUTHORIZATION.to_string(), authorization.to_string())])
+            })
+            .unwrap_or_default(),
+        ser_fmt: state.config.dbt_serialization_format,
+    })
+}
+
+/// Location of a stage file on the stage volume
+fn stage_file_location(root: &ObjectPath, name: &str) -> ObjectPath {
+    ObjectPath::from_iter(
+        root.parts().chain(
+            name.split('/')
+                .filter(|part| !part.is_empty())
+                .map(PathPart::from),
+        ),
+    )
+}
+
+/// File transfer instructions of a `PUT` or `GET` response. Every file gets a
+/// presigned URL served by [`upload_stage_file`] and [`download_stage_file`].
+async fn file_transfer_data(
+    state: &AppState,
+    file_transfer: FileTransfer,
+) -> Result<FileTransferData> {
+    let object_store = state
+        .metastore
+        .stage_object_store(&file_transfer.stage)
+        .await
+        .context(api_snowflake_rest_error::MetastoreSnafu)?;
+    let presigned_url = |name: &str, kind: TransferKind| {
+        let location = stage_file_location(&file_transfer.root, name);
+        let token = state
+            .stage_transfers
+            .insert(object_store.clone(), location, kind);
+        format!("{}/stage-transfers/{token}", state.config.base_url)
+    };
+
+    let (upload_url, download_urls) = match file_transfer.command {
+        FileTransferCommand::Upload => {
+            // Wildcard sources are uploaded file by file, the drivers ask for
+            // the URL of each file with a `PUT` of that file
+            let upload_url = file_transfer
+                .source_locations
+                .first()
+                .filter(|source| !source.contains(['*', '?']))
+                .map(|source| {
+                    let mut name = source.rsplit('/').next().unwrap_or(source).to_string();
+                    if file_transfer.auto_compress
+                        && SourceCompression::from_extension(&name) == SourceCompression::None
+                    {
+                        name.push_str(".gz");
+                    }
+                    presigned_url(
+                        &format!("{}/{name}", file_transfer.path),
+                        TransferKind::Upload {
+                            auto_compress: file_transfer.auto_compress,
+                            overwrite: file_transfer.overwrite,
+                        },
+                    )
+                });
+            (upload_url, None)
+        }
+        FileTransferCommand::Download => {
+            let download_urls = file_transfer
+                .source_locations
+                .iter()
+                .map(|source| presigned_url(source, TransferKind::Download))
+                .collect();
+            (None, Some(download_urls))
+        }
+    };
+
+    let stage_location = if file_transfer.path.is_empty() {
+        format!("{}/", file_transfer.stage)
+    } else {
+        format!(
+            "{}/{}/",
+            file_transfer.stage,
+            file_transfer.path.trim_end_matches('/')
+        )
+    };
+    Ok(FileTransferData {
+        command: file_transfer.command.to_string(),
+        src_locations: file_transfer.source_locations,
+        stage_info: StageInfo {
+            location_type: "GCS".to_string(),
+            location: stage_location,
+            path: file_transfer.path,
+            region: None,
+            creds: HashMap::new(),
+            is_client_side_encrypted: false,
+            presigned_url: upload_url,
+        },
+        auto_compress: file_transfer.auto_compress,
+        overwrite: file_transfer.overwrite,
+        source_compression: file_transfer.source_compression.to_string(),
+        parallel: file_transfer.parallel,
+        local_location: file_transfer.local_location,
+        presigned_urls: download_urls,
+    })
+}
+
 #[tracing::instrument(name = "api_snowflake_rest::login", level = "debug", skip(state), err, ret(level = tracing::Level::TRACE))]
 pub async fn login(
     State(state): State<AppState>,
-    // Query(_query_params): Query<LoginRequestQueryParams>,
-    Json(LoginRequestBody {
-        data:
-            LoginRequestData {
-                login_name,
-                password,
-                ..
-            },
-    }): Json<LoginRequestBody>,
+    Query(mut query_params): Query<LoginRequestQueryParams>,
+    Json(LoginRequestBody { data }): Json<LoginRequestBody>,
 ) -> Result<Json<LoginResponse>> {
-    if login_name != *state.config.auth.demo_user || password != *state.config.auth.demo_password {
-        return api_snowflake_rest_error::InvalidAuthDataSnafu.fail()?;
+    let AuthenticatedUser {
+        user,
+        role_restriction,
+    } = authenticate(state.metastore.as_ref(), &data).await?;
+
+    // The user defaults apply unless the client connects with its own
+    query_params.database_name = query_params
+        .database_name
+        .or_else(|| user.default_database.clone());
+    query_params.schema_name = query_params
+        .schema_name
+        .or_else(|| user.default_schema.clone());
+    query_params.role_name = query_params.role_name.or_else(|| user.default_role.clone());
+    // Sessions opened with a role restricted access token can only use that role
+    if role_restriction.is_some() {
+        query_params.role_name.clone_from(&role_restriction);
     }
 
     let session_id = uuid::Uuid::new_v4().to_string();
 
-    let _ = state.execution_svc.create_session(&session_id).await?;
+    let _ = state
+        .execution_svc
+        .create_session_with_options(
+            &session_id,
+            login_session_options(&user.name, role_restriction, &query_params, &data),
+        )
+        .await?;
 
     Ok(Json(LoginResponse {
-        data: Option::from(LoginResponseData { token: session_id }),
+        data: Option::from(login_response_data(session_id, &query_params, &data)),
         success: true,
         message: Option::from("successfully executed".to_string()),
     }))
@@ -55,16 +216,96 @@ pub async fn query(
     DFSessionId(session_id): DFSessionId,
     State(state): State<AppState>,
     Query(query): Query<QueryRequest>,
+    headers: HeaderMap,
     Json(QueryRequestBody {
         sql_text,
         async_exec,
+        bindings,
+        parameters,
+        describe_only,
     }): Json<QueryRequestBody>,
-) -> Result<Json<JsonResponse>> {
+) -> Result<Response> {
     let serialization_format = state.config.dbt_serialization_format;
+    let download = chunk_download(&state, &session_id, &headers);
     let query_context = QueryContext::default()
         .with_ip_address(addr.ip().to_string())
         .with_async_query(async_exec)
-        .with_request_id(query.request_id);
+        .with_request_id(query.request_id)
+        .with_bindings(bindings.map(ordered_bindings).unwrap_or_default());
+
+    // Drivers describe prepared statements before binding and executing them
+    if describe_only {
+        let result = state
+            .execution_svc
+            .describe_query(&session_id, &sql_text, query_context)
+            .await?;
+        return handle_query_ok_result(&sql_text, result, serialization_format, None)
+            .await
+            .map(IntoResponse::into_response);
+    }
+
+    // Scripts are executed statement by statement, `MULTI_STATEMENT_COUNT` = 1
+    // (the default) leaves multiple statements to fail as a single query
+    let statement_count = parameters.as_ref().and_then(multi_statement_count);
+    if let Some(statement_count) = statement_count.filter(|count| *count != 1) {
+        let statements = split_statements(&sql_text);
+        if statement_count != 0 && statements.len() != statement_count {
+            return api_snowflake_rest_error::StatementCountMismatchSnafu {
+                expected: statement_count,
+                actual: statements.len(),
+            }
+            .fail();
+        }
+        if !async_exec {
+            let result = state
+                .execution_svc
+                .query_multi_statement(&session_id, &sql_text, &statements, query_context)
+                .await?;
+            return handle_multi_statement_result(
+                result,
+                &state.result_chunks,
+                &session_id,
+                serialization_format,
+                download.as_ref(),
+            )
+            .await
+            .map(IntoResponse::into_response);
+        }
+        let handle = state
+            .execution_svc
+            .submit_multi_statement(&session_id, &sql_text, &statements, query_context)
+            .await?;
+        let query_uuid: Uuid = handle.query_id.as_uuid();
+        // `get_query` waits for the responses until they are stored
+        let pending = state.result_chunks.pending_response(query_uuid);
+        let state = state.clone();
+        let session_id = session_id.clone();
+        let headers = headers.clone();
+        tokio::spawn(async move {
+            let _pending = pending;
+            // a failed request is recorded to the query history as its parent query
+            let Ok(Ok(result)) = handle.rx.await else {
+                return;
+            };
+            let download = chunk_download(&state, &session_id, &headers);
+            let stored: Result<()> = async {
+                let response = handle_multi_statement_result(
+                    result,
+                    &state.result_chunks,
+                    &session_id,
+                    serialization_format,
+                    download.as_ref(),
+                )
+                .await?;
+                store_response(&state.result_chunks, &session_id, query_uuid, &response).await
+            }
+            .await;
+            if let Err(error) = stored {
+                tracing::warn!("Failed to store multi-statement responses: {error}");
+            }
+        });
+        return Ok(submitted_query_response(query_uuid));
+    }
 
     if async_exec {
         let query_handle = state
@@ -77,15 +318,7 @@ pub async fn query(
             .record("query_id", query_handle.query_id.as_i64())
             .record("query_uuid", query_uuid.to_string());
 
-        Ok(Json(JsonResponse {
-            data: Option::from(ResponseData {
-                query_id: Some(query_uuid.to_string()),
-                ..Default::default()
-            }),
-            success: true,
-            message: Option::from("successfully executed".to_string()),
-            code: None,
-        }))
+        Ok(submitted_query_response(query_uuid))
     } else {
         // find running query by request_id
         let session = state.execution_svc.get_session(&session_id).await?;
@@ -98,25 +331,72 @@ pub async fn query(
             let query_id = running_query?.query_id;
             let historical_result = state
                 .execution_svc
-                .wait_historical_query_result(query_id)
+                .wait_historical_query_result(&session.query_reader(), query_id)
                 .await?;
-            handle_historical_query_result(query_id, historical_result, serialization_format)
-        } else {
-            // execute new query
+            handle_historical_query_result(
+                query_id,
+                historical_result,
+                serialization_format,
+                download.as_ref(),
+            )
+            .await
+            .map(IntoResponse::into_response)
+        } else if !is_file_transfer_statement(&sql_text) {
+            // execute new query, streaming its result into chunks or into the response
             let result = state
+                .execution_svc
+                .query_stream(&session_id, &sql_text, query_context)
+                .await?;
+            if let Some(download) = &download {
+                handle_query_stream_result(&sql_text, result, serialization_format, download)
+                    .await
+                    .map(IntoResponse::into_response)
+            } else {
+                handle_query_stream_response(result, serialization_format).await
+            }
+        } else {
+            // execute new file transfer query
+            let mut result = state
                 .execution_svc
                 .query(&session_id, &sql_text, query_context)
                 .await?;
-            handle_query_ok_result(&sql_text, result, serialization_format)
+            let Some(file_transfer) = result.file_transfer.take() else {
+                return handle_query_ok_result(&sql_text, result, serialization_format, None)
+                    .await
+                    .map(IntoResponse::into_response);
+            };
+            let file_transfer = file_transfer_data(&state, file_transfer).await?;
+            let Json(mut response) =
+                handle_query_ok_result(&sql_text, result, serialization_format, None).await?;
+            if let Some(data) = response.data.as_mut() {
+                data.file_transfer = Some(file_transfer);
+            }
+            Ok(Json(response).into_response())
         }
     }
 }
 
+/// Response of an asynchronously executed query, with just its id
+fn submitted_query_response(query_uuid: Uuid) -> Response {
+    Json(JsonResponse {
+        data: Option::from(ResponseData {
+            query_id: Some(query_uuid.to_string()),
+            ..Default::default()
+        }),
+        success: true,
+        message: Option::from("successfully executed".to_string()),
+        code: None,
+    })
+    .into_response()
+}
+
 #[tracing::instrument(name = "api_snowflake_rest::get_query", level = "debug", skip(state), fields(query_id, query_uuid), err, ret(level = tracing::Level::TRACE))]
 pub async fn get_query(
+    DFSessionId(session_id): DFSessionId,
     State(state): State<AppState>,
     Path(query_id): Path<QueryIdParam>,
-) -> Result<Json<JsonResponse>> {
+    headers: HeaderMap,
+) -> Result<Response> {
     let query_id: QueryRecordId = query_id.into();
 
     let query_uuid: Uuid = query_id.as_uuid();
@@ -125,15 +405,89 @@ pub async fn get_query(
         .record("query_id", query_id.as_i64())
         .record("query_uuid", query_uuid.to_string());
 
+    // Multi-statement requests keep the whole responses of their statements
+    if let Some(response) = state.result_chunks.response(&session_id, query_uuid).await {
+        return stored_response(&response).await;
+    }
+
+    // Only the user who ran the query and admins can get its result
+    let session = state.execution_svc.get_session(&session_id).await?;
     let query_result = state
         .execution_svc
-        .wait_historical_query_result(query_id)
+        .wait_historical_query_result(&session.query_reader(), query_id)
         .await?;
     handle_historical_query_result(
         query_id,
         query_result,
         state.config.dbt_serialization_format,
+        chunk_download(&state, &session_id, &headers).as_ref(),
     )
+    .await
+    .map(IntoResponse::into_response)
+}
+
+/// Status of a query, unlike `get_query` it doesn't wait for the query to finish.
+/// Only the user who ran the query and admins can get it.
+#[tracing::instrument(name = "api_snowflake_rest::get_query_status", level = "debug", skip(state), fields(query_id, query_uuid), err, ret(level = tracing::Level::TRACE))]
+pub async fn get_query_status(
+    DFSessionId(session_id): DFSessionId,
+    State(state): State<AppState>,
+    Path(query_id): Path<QueryIdParam>,
+) -> Result<Json<QueryStatusResponse>> {
+    let query_id: QueryRecordId = query_id.into();
+
+    // Record the result as part of the current span.
+    tracing::Span::current()
+        .record("query_id", query_id.as_i64())
+        .record("query_uuid", query_id.as_uuid().to_string());
+
+    let query_record = state
+        .execution_svc
+        .query_status(&session_id, query_id)
+        .await?;
+    // finished queries have no progress
+    let progress = state.execution_svc.query_progress(query_id).ok();
+    Ok(Json(QueryStatusResponse {
+        data: Some(QueryStatusData {
+            queries: vec![query_monitoring_info(query_record, progress)],
+        }),
+        success: true,
+        message: None,
+        code: None,
+    }))
+}
+
+#[tracing::instrument(
+    name = "api_snowflake_rest::get_query_result_chunk",
+    level = "debug",
+    skip(state),
+    err
+)]
+pub async fn get_query_result_chunk(
+    DFSessionId(session_id): DFSessionId,
+    State(state): State<AppState>,
+    Path((query_id, index)): Path<(Uuid, usize)>,
+) -> Result<impl IntoResponse> {
+    let Some(chunk) = state.result_chunks.get(&session_id, query_id, index) else {
+        return api_snowflake_rest_error::ResultChunkNotFoundSnafu {
+            query_id: query_id.to_string(),
+            index,
+        }
+        .fail();
+    };
+    let serialization_format = state.config.dbt_serialization_format;
+    let content_type = if serialization_format == DataSerializationFormat::Arrow {
+        "application/octet-stream"
+    } else {
+        "application/json"
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CONTENT_ENCODING, "gzip"),
+        ],
+        chunk.body().await?,
+    ))
 }
 
 #[tracing::instrument(name = "api_snowflake_rest::abort", level = "debug", skip(state), err, ret(level = tracing::Level::TRACE))]
@@ -149,3 +503,242 @@ pub async fn abort(
         .abort_query(RunningQueryId::ByRequestId(request_id, sql_text))?;
     Ok(Json(serde_json::value::Value::Null))
 }
+
+/// The session a session request is for. Unlike `DFSessionId`, a missing session
+/// is not created.
+fn session_token(headers: &HeaderMap) -> Result<String> {
+    extract_token_from_auth(headers).map_or_else(
+        || api_snowflake_rest_error::MissingAuthTokenSnafu.fail(),
+        Ok,
+    )
+}
+
+const fn session_response() -> JsonResponse {
+    JsonResponse {
+        data: None,
+        success: true,
+        message: None,
+        code: None,
+    }
+}
+
+/// Keeps the session of an idle connection alive
+#[tracing::instrument(
+    name = "api_snowflake_rest::heartbeat",
+    level = "debug",
+    skip(state, headers),
+    err
+)]
+pub async fn heartbeat(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<JsonResponse>> {
+    let session_id = session_token(&headers)?;
+    if !state
+        .execution_svc
+        .update_session_expiry(&session_id, SESSION_KEEP_ALIVE_EXPIRATION_SECONDS)
+        .await?
+    {
+        return api_snowflake_rest_error::InvalidAuthTokenSnafu.fail();
+    }
+    Ok(Json(session_response()))
+}
+
+/// Renews the session token. Session and master tokens are both the session id,
+/// so renewing keeps the tokens and extends the session.
+#[tracing::instrument(
+    name = "api_snowflake_rest::renew_session",
+    level = "debug",
+    skip(state, headers),
+    err
+)]
+pub async fn renew_session(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(RenewSessionRequest {
+        old_session_token, ..
+    }): Json<RenewSessionRequest>,
+) -> Result<Json<RenewSessionResponse>> {
+    let session_id = session_token(&headers)?;
+    if old_session_token != session_id
+        || !state
+            .execution_svc
+            .update_session_expiry(&session_id, SESSION_KEEP_ALIVE_EXPIRATION_SECONDS)
+            .await?
+    {
+        return api_snowflake_rest_error::InvalidAuthTokenSnafu.fail();
+    }
+    Ok(Json(RenewSessionResponse {
+        data: Some(RenewSessionData {
+            session_token: session_id.clone(),
+            validity_in_seconds_st: SESSION_INACTIVITY_EXPIRATION_SECONDS,
+            master_token: session_id,
+            validity_in_seconds_mt: SESSION_KEEP_ALIVE_EXPIRATION_SECONDS,
+        }),
+        success: true,
+        message: None,
+    }))
+}
+
+/// Closes the session of a closed connection, freeing it right away instead
+/// of when it expires
+#[tracing::instrument(
+    name = "api_snowflake_rest::close_session",
+    level = "debug",
+    skip(state, headers),
+    err
+)]
+pub async fn close_session(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(CloseSessionQueryParams { delete }): Query<CloseSessionQueryParams>,
+) -> Result<Json<JsonResponse>> {
+    let session_id = session_token(&headers)?;
+    if delete {
+        state.execution_svc.delete_session(&session_id).await?;
+    }
+    Ok(Json(session_response()))
+}
+
+/// Uploads a file to the presigned URL of a `PUT`. Files are compressed with
+/// gzip unless they already are compressed or `AUTO_COMPRESS = FALSE`. The body
+/// is streamed to the stage in parts, compressed on the fly, it's never held
+/// in memory as a whole.
+#[tracing::instrument(
+    name = "api_snowflake_rest::upload_stage_file",
+    level = "debug",
+    skip(state, body),
+    err
+)]
+pub async fn upload_stage_file(
+    State(state): State<AppState>,
+    Path(token): Path<Uuid>,
+    body: Body,
+) -> Result<impl IntoResponse> {
+    let Some(transfer) = state.stage_transfers.get(token) else {
+        return api_snowflake_rest_error::StageTransferNotFoundSnafu.fail();
+    };
+    let TransferKind::Upload {
+        auto_compress,
+        overwrite,
+    } = transfer.kind
+    else {
+        return api_snowflake_rest_error::StageTransferNotFoundSnafu.fail();
+    };
+    if !overwrite {
+        match transfer.object_store.head(&transfer.location).await {
+            // Existing files are skipped, as by Snowflake
+            Ok(_) => return Ok(StatusCode::OK),
+            Err(object_store::Error::NotFound { .. }) => {}
+            Err(error) => return Err(error).context(api_snowflake_rest_error::ObjectStoreSnafu),
+        }
+    }
+    let mut chunks = body.into_data_stream();
+    // The leading bytes tell whether the file is compressed already
+    let mut head = Vec::new();
+    while head.len() < COMPRESSION_SIGNATURE_LEN
+        && let Some(chunk) = chunks.next().await
+    {
+        head.extend_from_slice(&chunk.context(api_snowflake_rest_error::UploadBodySnafu)?);
+    }
+    let compress = auto_compress
+        && SourceCompression::detect(transfer.location.as_ref(), &head) == SourceCompression::None;
+    // A multipart upload needs a part at least
+    if head.is_empty() && !compress {
+        transfer
+            .object_store
+            .put(&transfer.location, PutPayload::default())
+            .await
+            .context(api_snowflake_rest_error::ObjectStoreSnafu)?;
+        return Ok(StatusCode::OK);
+    }
+
+    let upload = transfer
+        .object_store
+        .put_multipart(&transfer.location)
+        .await
+        .context(api_snowflake_rest_error::ObjectStoreSnafu)?;
+    let mut writer = WriteMultipart::new(upload);
+    if let Err(error) = write_upload(&mut writer, compress, head.into(), chunks).await {
+        let _ = writer.abort().await;
+        return Err(error);
+    }
+    writer
+        .finish()
+        .await
+        .context(api_snowflake_rest_error::ObjectStoreSnafu)?;
+    Ok(StatusCode::OK)
+}
+
+/// Leading bytes of a file needed to tell its compression
+const COMPRESSION_SIGNATURE_LEN: usize = 4;
+/// Parts of an upload buffered while they are written to the object store
+const MAX_UPLOAD_PARTS_IN_FLIGHT: usize = 4;
+
+/// Writes the chunks of an uploaded body, starting with the leading bytes read
+/// already, in parts of the multipart upload
+async fn write_upload(
+    writer: &mut WriteMultipart,
+    compress: bool,
+    head: Bytes,
+    mut chunks: BodyDataStream,
+) -> Result<()> {
+    let mut encoder = compress.then(|| GzEncoder::new(Vec::new(), Compression::default()));
+    let mut chunk = head;
+    loop {
+        match &mut encoder {
+            Some(encoder) => {
+                encoder
+                    .write_all(&chunk)
+                    .context(api_snowflake_rest_error::GZipCompressSnafu)?;
+                // Compressed bytes are taken as they're produced
+                writer.write(&std::mem::take(encoder.get_mut()));
+            }
+            None => writer.put(chunk),
+        }
+        writer
+            .wait_for_capacity(MAX_UPLOAD_PARTS_IN_FLIGHT)
+            .await
+            .context(api_snowflake_rest_error::ObjectStoreSnafu)?;
+        match chunks.next().await {
+            Some(next) => chunk = next.context(api_snowflake_rest_error::UploadBodySnafu)?,
+            None => break,
+        }
+    }
+    if let Some(encoder) = encoder {
+        let tail = encoder
+            .finish()
+            .context(api_snowflake_rest_error::GZipCompressSnafu)?;
+        writer.write(&tail);
+    }
+    Ok(())
+}
+
+/// Downloads a file from a presigned URL of a `GET`
+#[tracing::instrument(
+    name = "api_snowflake_rest::download_stage_file",
+    level = "debug",
+    skip(state),
+    err
+)]
+pub async fn download_stage_file(
+    State(state): State<AppState>,
+    Path(token): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    let Some(transfer) = state
+        .stage_transfers
+        .get(token)
+        .filter(|transfer| transfer.kind == TransferKind::Download)
+    else {
+        return api_snowflake_rest_error::StageTransferNotFoundSnafu.fail();
+    };
+    let body = transfer
+        .object_store
+        .get(&transfer.location)
+        .await
+        .context(api_snowflake_rest_error::ObjectStoreSnafu)?
+        .bytes()
+        .await
+        .context(api_snowflake_rest_error::ObjectStoreSnafu)?;
+    Ok(([(header::CONTENT_TYPE, "application/octet-stream")], body))
+}
@@ -1,18 +1,39 @@
 use crate::SqlState;
-use crate::models::{JsonResponse, ResponseData};
+use crate::models::{
+    ChunkInfo, JsonResponse, LoginRequestData, LoginRequestQueryParams, LoginResponseData,
+    QueryMonitoringInfo, QueryProgressInfo, ResponseData, SessionInfo, SessionParameter,
+};
 use crate::server::error::{self as api_snowflake_rest_error, Error, Result};
+use crate::server::result_chunks::{ChunkSplitter, ResultChunk, ResultChunks, split_into_chunks};
 use axum::Json;
+use axum::body::{Body, Bytes};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
 use base64;
 use base64::engine::general_purpose::STANDARD as engine_base64;
 use base64::prelude::*;
-use core_executor::models::QueryResult;
+use core_executor::error_code::ErrorCode;
+use core_executor::models::{
+    ClientInfo, MultiStatementResult, QueryResult, QueryResultStream, SessionOptions,
+};
+use core_executor::progress::QueryProgress;
+use core_executor::session::{
+    SESSION_INACTIVITY_EXPIRATION_SECONDS, SESSION_KEEP_ALIVE_EXPIRATION_SECONDS,
+};
 use core_executor::utils::{DataSerializationFormat, convert_record_batches};
 use core_executor::{Result as ExecutionResult, error as ex_error};
-use core_history::QueryRecordId;
+use core_history::{QueryRecord, QueryRecordId, QueryStatus};
 use datafusion::arrow::ipc::MetadataVersion;
 use datafusion::arrow::ipc::writer::{IpcWriteOptions, StreamWriter};
 use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::scalar::ScalarValue;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use futures::StreamExt;
 use snafu::{ResultExt, location};
+use std::collections::HashMap;
+use std::io::{Read, Write};
 use uuid::Uuid;
 
 // https://arrow.apache.org/docs/format/Columnar.html#buffer-alignment-and-padding
@@ -23,7 +44,7 @@ use uuid::Uuid;
 // For more info see issue #115
 const ARROW_IPC_ALIGNMENT: usize = 8;
 
-fn records_to_arrow_string(recs: &Vec<RecordBatch>) -> std::result::Result<String, Error> {
+fn records_to_arrow_bytes(recs: &Vec<RecordBatch>) -> std::result::Result<Vec<u8>, Error> {
     let mut buf = Vec::new();
     let options = IpcWriteOptions::try_new(ARROW_IPC_ALIGNMENT, false, MetadataVersion::V5)
         .context(api_snowflake_rest_error::ArrowSnafu)?;
@@ -41,24 +62,455 @@ fn records_to_arrow_string(recs: &Vec<RecordBatch>) -> std::result::Result<Strin
             .context(api_snowflake_rest_error::ArrowSnafu)?;
         drop(writer);
     }
-    Ok(engine_base64.encode(buf))
+    Ok(buf)
+}
+
+fn records_to_arrow_string(recs: &Vec<RecordBatch>) -> std::result::Result<String, Error> {
+    Ok(engine_base64.encode(records_to_arrow_bytes(recs)?))
+}
+
+/// Session defaults and client info sent by the driver on login. The database, schema,
+/// warehouse and role of the login request become the current ones of the session.
+#[must_use]
+pub fn login_session_options(
+    user: &str,
+    role_restriction: Option<String>,
+    query: &LoginRequestQueryParams,
+    data: &LoginRequestData,
+) -> SessionOptions {
+    let mut params: HashMap<String, ScalarValue> = data
+        .session_parameters
+        .iter()
+        .filter_map(|(name, value)| Some((name.clone(), session_param_value(value)?)))
+        .collect();
+    let current = [
+        ("database", &query.database_name),
+        ("schema", &query.schema_name),
+        ("warehouse", &query.warehouse),
+        ("role", &query.role_name),
+    ];
+    for (name, value) in current {
+        if let Some(value) = value {
+            params.insert(name.to_string(), ScalarValue::Utf8(Some(value.clone())));
+        }
+    }
+
+    let environment = match serde_json::to_value(&data.client_environment) {
+        Ok(serde_json::Value::Object(environment)) => environment
+            .into_iter()
+            .filter_map(|(name, value)| match value {
+                serde_json::Value::Null => None,
+                serde_json::Value::String(value) => Some((name, value)),
+                value => Some((name, value.to_string())),
+            })
+            .collect(),
+        _ => HashMap::new(),
+    };
+    SessionOptions {
+        params,
+        client_info: Some(ClientInfo {
+            application: data.client_app_id.clone(),
+            version: data.client_app_version.clone(),
+            account_name: data.account_name.clone(),
+            environment,
+        }),
+        user: Some(user.to_string()),
+        role_restriction,
+    }
+}
+
+pub(crate) fn session_param_value(value: &serde_json::Value) -> Option<ScalarValue> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::Bool(value) => Some(ScalarValue::Boolean(Some(*value))),
+        serde_json::Value::Number(value) => Some(value.as_i64().map_or_else(
+            || ScalarValue::Float64(value.as_f64()),
+            |value| ScalarValue::Int64(Some(value)),
+        )),
+        serde_json::Value::String(value) => Some(ScalarValue::Utf8(Some(value.clone()))),
+        value => Some(ScalarValue::Utf8(Some(value.to_string()))),
+    }
+}
+
+/// Login response data, echoing the session parameters the session was created with
+#[must_use]
+pub fn login_response_data(
+    token: String,
+    query: &LoginRequestQueryParams,
+    data: &LoginRequestData,
+) -> LoginResponseData {
+    let mut parameters: Vec<SessionParameter> = data
+        .session_parameters
+        .iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(name, value)| SessionParameter {
+            name: name.to_ascii_uppercase(),
+            value: value.clone(),
+        })
+        .collect();
+    parameters.sort_by(|left, right| left.name.cmp(&right.name));
+    LoginResponseData {
+        master_token: Some(token.clone()),
+        validity_in_seconds: Some(SESSION_INACTIVITY_EXPIRATION_SECONDS),
+        master_validity_in_seconds: Some(SESSION_KEEP_ALIVE_EXPIRATION_SECONDS),
+        token,
+        parameters,
+        session_info: SessionInfo {
+            database_name: query.database_name.clone(),
+            schema_name: query.schema_name.clone(),
+            warehouse_name: query.warehouse.clone(),
+            role_name: query.role_name.clone(),
+        },
+    }
+}
+
+/// Where the chunks of a large result following the first one are downloaded from
+pub struct ChunkDownload<'a> {
+    pub chunks: &'a ResultChunks,
+    pub chunk_rows: usize,
+    pub session_id: &'a str,
+    /// Base URL of the server, as configured
+    pub base_url: &'a str,
+    pub headers: HashMap<String, String>,
+    pub ser_fmt: DataSerializationFormat,
+}
+
+impl ChunkDownload<'_> {
+    /// Renders and compresses a chunk, keeps it for download and returns its entry in
+    /// the chunk list of the response
+    async fn store_chunk(
+        &self,
+        query_uuid: Uuid,
+        index: usize,
+        chunk: &QueryResult,
+    ) -> Result<(ChunkInfo, ResultChunk)> {
+        let body = result_chunk_body(chunk, self.ser_fmt)?;
+        let compressed = gzip(&body)?;
+        let info = ChunkInfo {
+            url: format!(
+                "{}/queries/{query_uuid}/result/chunks/{index}",
+                self.base_url
+            ),
+            row_count: chunk.records.iter().map(RecordBatch::num_rows).sum(),
+            uncompressed_size: body.len(),
+            compressed_size: compressed.len(),
+        };
+        Ok((info, self.chunks.store(compressed).await))
+    }
+}
+
+pub fn gzip(body: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(body)
+        .context(api_snowflake_rest_error::ResultChunkCompressSnafu)?;
+    encoder
+        .finish()
+        .context(api_snowflake_rest_error::ResultChunkCompressSnafu)
+}
+
+/// Keeps all but the first chunk of a result for download, returns the first chunk
+/// along with the chunk list of the response
+async fn store_result_chunks(
+    query_result: QueryResult,
+    download: Option<&ChunkDownload<'_>>,
+) -> Result<(QueryResult, Option<Vec<ChunkInfo>>)> {
+    let Some(download) = download else {
+        return Ok((query_result, None));
+    };
+    let rows: usize = query_result.records.iter().map(RecordBatch::num_rows).sum();
+    if rows <= download.chunk_rows {
+        return Ok((query_result, None));
+    }
+    let query_uuid = query_result.query_id.as_uuid();
+    let mut chunks = split_into_chunks(query_result, download.chunk_rows);
+    let first = chunks.remove(0);
+    let mut chunk_infos = Vec::with_capacity(chunks.len());
+    let mut stored = Vec::with_capacity(chunks.len());
+    for (index, chunk) in chunks.iter().enumerate() {
+        let (info, chunk) = download.store_chunk(query_uuid, index, chunk).await?;
+        chunk_infos.push(info);
+        stored.push(chunk);
+    }
+    download
+        .chunks
+        .insert(download.session_id, query_uuid, stored);
+    Ok((first, Some(chunk_infos)))
+}
+
+/// Reads a streamed result: the first chunk is kept for the response, the following
+/// ones are stored for download as they are read
+async fn stream_result_chunks(
+    mut result: QueryResultStream,
+    download: &ChunkDownload<'_>,
+) -> Result<(QueryResult, Option<Vec<ChunkInfo>>)> {
+    let query_id = result.query_id;
+    let query_uuid = query_id.as_uuid();
+    let schema = result.schema();
+    let mut splitter = ChunkSplitter::new(download.chunk_rows);
+    let mut first = None;
+    let mut chunks = Vec::new();
+    let mut chunk_infos = Vec::new();
+    let mut store_chunk = async |records: Vec<RecordBatch>| -> Result<()> {
+        if first.is_none() {
+            first = Some(records);
+            return Ok(());
+        }
+        let chunk = QueryResult::new(records, schema.clone(), query_id);
+        let (info, chunk) = download
+            .store_chunk(query_uuid, chunks.len(), &chunk)
+            .await?;
+        chunk_infos.push(info);
+        chunks.push(chunk);
+        Ok(())
+    };
+    while let Some(batch) = result.next_batch().await {
+        for records in splitter.push(&batch?) {
+            store_chunk(records).await?;
+        }
+    }
+    if let Some(records) = splitter.finish() {
+        store_chunk(records).await?;
+    }
+
+    let first = QueryResult::new(first.unwrap_or_default(), schema, query_id);
+    if chunks.is_empty() {
+        return Ok((first, None));
+    }
+    download
+        .chunks
+        .insert(download.session_id, query_uuid, chunks);
+    Ok((first, Some(chunk_infos)))
+}
+
+/// Body of a downloaded result chunk: JSON rows without the enclosing brackets,
+/// or an Arrow IPC stream
+pub fn result_chunk_body(chunk: &QueryResult, ser_fmt: DataSerializationFormat) -> Result<Vec<u8>> {
+    if ser_fmt == DataSerializationFormat::Arrow {
+        let records = convert_record_batches(chunk, ser_fmt)?;
+        return records_to_arrow_bytes(&records);
+    }
+    let rows = chunk
+        .as_row_set(ser_fmt)?
+        .iter()
+        .map(serde_json::to_string)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context(api_snowflake_rest_error::RowParseSnafu)?;
+    Ok(rows.join(",").into_bytes())
+}
+
+#[tracing::instrument(name = "handle_query_ok_result", level = "debug", skip(download), err, ret(level = tracing::Level::TRACE))]
+pub async fn handle_query_ok_result(
+    sql_text: &str,
+    query_result: QueryResult,
+    ser_fmt: DataSerializationFormat,
+    download: Option<&ChunkDownload<'_>>,
+) -> Result<Json<JsonResponse>> {
+    let (query_result, chunks) = store_result_chunks(query_result, download).await?;
+    query_result_response(query_result, chunks, ser_fmt, download)
 }
 
-#[tracing::instrument(name = "handle_query_ok_result", level = "debug", err, ret(level = tracing::Level::TRACE))]
-pub fn handle_query_ok_result(
+#[tracing::instrument(
+    name = "handle_query_stream_result",
+    level = "debug",
+    skip(query_result, download),
+    fields(query_id = query_result.query_id.as_i64()),
+    err,
+    ret(level = tracing::Level::TRACE)
+)]
+pub async fn handle_query_stream_result(
     sql_text: &str,
+    query_result: QueryResultStream,
+    ser_fmt: DataSerializationFormat,
+    download: &ChunkDownload<'_>,
+) -> Result<Json<JsonResponse>> {
+    let (query_result, chunks) = stream_result_chunks(query_result, download).await?;
+    query_result_response(query_result, chunks, ser_fmt, Some(download))
+}
+
+/// Serializes the rows of a result batch by batch, into the rowset of a JSON result or
+/// the base64 encoded Arrow IPC stream of an Arrow result
+enum RowSetEncoder {
+    Json {
+        rows: usize,
+    },
+    Arrow {
+        writer: Option<StreamWriter<Vec<u8>>>,
+        /// Bytes of the IPC stream not base64 encoded yet, they are encoded in groups
+        /// of three bytes to keep the encoding of the whole stream
+        pending: Vec<u8>,
+    },
+}
+
+impl RowSetEncoder {
+    fn encode(&mut self, chunk: &QueryResult, ser_fmt: DataSerializationFormat) -> Result<Bytes> {
+        match self {
+            Self::Json { rows } => {
+                let mut body = String::new();
+                for row in chunk.as_row_set(ser_fmt)? {
+                    if *rows > 0 {
+                        body.push(',');
+                    }
+                    body.push_str(
+                        &serde_json::to_string(&row)
+                            .context(api_snowflake_rest_error::RowParseSnafu)?,
+                    );
+                    *rows += 1;
+                }
+                Ok(Bytes::from(body))
+            }
+            Self::Arrow { writer, pending } => {
+                for batch in convert_record_batches(chunk, ser_fmt)? {
+                    if writer.is_none() {
+                        let options = IpcWriteOptions::try_new(
+                            ARROW_IPC_ALIGNMENT,
+                            false,
+                            MetadataVersion::V5,
+                        )
+                        .context(api_snowflake_rest_error::ArrowSnafu)?;
+                        *writer = Some(
+                            StreamWriter::try_new_with_options(
+                                Vec::new(),
+                                batch.schema_ref(),
+                                options,
+                            )
+                            .context(api_snowflake_rest_error::ArrowSnafu)?,
+                        );
+                    }
+                    if let Some(writer) = writer.as_mut() {
+                        writer
+                            .write(&batch)
+                            .context(api_snowflake_rest_error::ArrowSnafu)?;
+                        pending.append(writer.get_mut());
+                    }
+                }
+                let encoded = pending.len() - pending.len() % 3;
+                let body = engine_base64.encode(&pending[..encoded]);
+                pending.drain(..encoded);
+                Ok(Bytes::from(body))
+            }
+        }
+    }
+
+    fn finish(&mut self) -> Result<Bytes> {
+        match self {
+            Self::Json { .. } => Ok(Bytes::new()),
+            Self::Arrow { writer, pending } => {
+                if let Some(mut writer) = writer.take() {
+                    writer
+                        .finish()
+                        .context(api_snowflake_rest_error::ArrowSnafu)?;
+                    pending.append(writer.get_mut());
+                }
+                Ok(Bytes::from(engine_base64.encode(std::mem::take(pending))))
+            }
+        }
+    }
+}
+
+/// Reads the next part of a streamed response body, `None` at its end
+async fn next_row_set_part(
+    result: &mut QueryResultStream,
+    encoder: &mut RowSetEncoder,
+    first: &mut Option<RecordBatch>,
+    suffix: &mut Option<String>,
+    ser_fmt: DataSerializationFormat,
+) -> Result<Option<Bytes>> {
+    let batch = match first.take() {
+        Some(batch) => Some(batch),
+        None => result.next_batch().await.transpose()?,
+    };
+    if let Some(batch) = batch {
+        let chunk = QueryResult::new(vec![batch], result.schema(), result.query_id);
+        return encoder.encode(&chunk, ser_fmt).map(Some);
+    }
+    let Some(suffix) = suffix.take() else {
+        return Ok(None);
+    };
+    let mut body = encoder.finish()?.to_vec();
+    body.extend_from_slice(suffix.as_bytes());
+    Ok(Some(Bytes::from(body)))
+}
+
+/// Response of a query whose result isn't returned in chunks. The rows are serialized
+/// into the response body as the result is read instead of collecting the whole result
+/// first. The first batch is read before responding, so a failing query gets the usual
+/// error response, a failure later on cuts the body short.
+#[tracing::instrument(
+    name = "handle_query_stream_response",
+    level = "debug",
+    skip(query_result),
+    fields(query_id = query_result.query_id.as_i64()),
+    err
+)]
+pub async fn handle_query_stream_response(
+    mut query_result: QueryResultStream,
+    ser_fmt: DataSerializationFormat,
+) -> Result<Response> {
+    let first = query_result.next_batch().await.transpose()?;
+    let empty = QueryResult::new(Vec::new(), query_result.schema(), query_result.query_id);
+    let Json(response) = query_result_response(empty, None, ser_fmt, None)?;
+    let response =
+        serde_json::to_string(&response).context(api_snowflake_rest_error::RowParseSnafu)?;
+    // The rows go between the brackets or quotes of the empty row set
+    let (row_set, encoder) = if ser_fmt == DataSerializationFormat::Arrow {
+        (
+            r#""rowsetBase64":""#,
+            RowSetEncoder::Arrow {
+                writer: None,
+                pending: Vec::new(),
+            },
+        )
+    } else {
+        (r#""rowset":[]"#, RowSetEncoder::Json { rows: 0 })
+    };
+    let (before, after) = response.split_once(row_set).unwrap_or((&response, ""));
+    let (open, close) = row_set.split_at(row_set.len() - 1);
+    let prefix = Bytes::from(format!("{before}{open}"));
+    let suffix = format!("{close}{after}");
+
+    let parts = futures::stream::try_unfold(
+        (query_result, encoder, first, Some(suffix)),
+        move |(mut result, mut encoder, mut first, mut suffix)| async move {
+            match next_row_set_part(&mut result, &mut encoder, &mut first, &mut suffix, ser_fmt)
+                .await
+            {
+                Ok(part) => Ok(part.map(|part| (part, (result, encoder, first, suffix)))),
+                Err(error) => {
+                    tracing::error!("Failed to stream query result: {error}");
+                    Err(std::io::Error::other(error.to_string()))
+                }
+            }
+        },
+    );
+    let body = futures::stream::once(async { Ok(prefix) }).chain(parts);
+    Ok((
+        [(header::CONTENT_TYPE, "application/json")],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
+
+fn query_result_response(
     query_result: QueryResult,
+    chunks: Option<Vec<ChunkInfo>>,
     ser_fmt: DataSerializationFormat,
+    download: Option<&ChunkDownload<'_>>,
 ) -> Result<Json<JsonResponse>> {
     let query_uuid: Uuid = query_result.query_id.as_uuid();
+    let row_type = query_result
+        .column_info()
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    let chunk_headers = chunks
+        .as_ref()
+        .and(download)
+        .map(|download| download.headers.clone());
 
     let json_resp = Json(JsonResponse {
         data: Option::from(ResponseData {
-            row_type: query_result
-                .column_info()
-                .into_iter()
-                .map(Into::into)
-                .collect(),
+            row_type,
             query_result_format: Some(ser_fmt.to_string().to_lowercase()),
             row_set: if ser_fmt == DataSerializationFormat::Json {
                 Option::from(query_result.as_row_set(ser_fmt)?)
@@ -75,6 +527,11 @@ pub fn handle_query_ok_result(
             query_id: Some(query_uuid.to_string()),
             error_code: None,
             sql_state: Some(SqlState::Success.to_string()),
+            chunks,
+            chunk_headers,
+            result_ids: None,
+            result_types: None,
+            file_transfer: None,
         }),
         success: true,
         message: Option::from("successfully executed".to_string()),
@@ -83,19 +540,77 @@ pub fn handle_query_ok_result(
     Ok(json_resp)
 }
 
+#[tracing::instrument(
+    name = "handle_multi_statement_result",
+    level = "debug",
+    skip(result, result_chunks, download),
+    err,
+    ret(level = tracing::Level::TRACE)
+)]
+pub async fn handle_multi_statement_result(
+    result: MultiStatementResult,
+    result_chunks: &ResultChunks,
+    session_id: &str,
+    ser_fmt: DataSerializationFormat,
+    download: Option<&ChunkDownload<'_>>,
+) -> Result<Json<JsonResponse>> {
+    let mut result_ids = Vec::with_capacity(result.statements.len());
+    let mut result_types = Vec::with_capacity(result.statements.len());
+    for statement in result.statements {
+        let query_uuid = statement.result.query_id.as_uuid();
+        result_ids.push(query_uuid.to_string());
+        result_types.push(statement.statement_type_id.to_string());
+        let (query_result, chunks) = store_result_chunks(statement.result, download).await?;
+        let response = query_result_response(query_result, chunks, ser_fmt, download)?;
+        store_response(result_chunks, session_id, query_uuid, &response).await?;
+    }
+    let mut response = query_result_response(result.result, None, ser_fmt, None)?;
+    if let Some(data) = response.data.as_mut() {
+        data.result_ids = Some(result_ids.join(","));
+        data.result_types = Some(result_types.join(","));
+    }
+    Ok(response)
+}
+
+/// Keeps the whole response of a query, served by `get_query` instead of the result
+/// recorded to the query history, which is truncated
+pub async fn store_response(
+    result_chunks: &ResultChunks,
+    session_id: &str,
+    query_uuid: Uuid,
+    response: &JsonResponse,
+) -> Result<()> {
+    let response = serde_json::to_vec(response).context(api_snowflake_rest_error::RowParseSnafu)?;
+    let response = result_chunks.store(gzip(&response)?).await;
+    result_chunks.insert_response(session_id, query_uuid, response);
+    Ok(())
+}
+
+/// Response kept by [`store_response`]
+pub async fn stored_response(response: &ResultChunk) -> Result<Response> {
+    let body = response.body().await?;
+    let mut json = Vec::new();
+    GzDecoder::new(&body[..])
+        .read_to_end(&mut json)
+        .context(api_snowflake_rest_error::ResultChunkIoSnafu)?;
+    Ok(([(header::CONTENT_TYPE, "application/json")], json).into_response())
+}
+
 #[tracing::instrument(
     name = "handle_historical_query_result",
     level = "debug",
+    skip(download),
     err,
     ret(level = tracing::Level::TRACE))
 ]
-pub fn handle_historical_query_result(
+pub async fn handle_historical_query_result(
     query_id: QueryRecordId,
     historical_query_result: ExecutionResult<QueryResult>,
     ser_fmt: DataSerializationFormat,
+    download: Option<&ChunkDownload<'_>>,
 ) -> Result<Json<JsonResponse>> {
     match historical_query_result {
-        Ok(query_result) => handle_query_ok_result("", query_result, ser_fmt),
+        Ok(query_result) => handle_query_ok_result("", query_result, ser_fmt, download).await,
         // Return the same response as it would be returned when error is propagated
         Err(error) => {
             // Create without using build(), and not using context which works with result
@@ -112,3 +627,79 @@ pub fn handle_historical_query_result(
         }
     }
 }
+
+/// Status of a query as reported by `/monitoring/queries/{queryId}`. Timed out
+/// queries are reported as failed, the same as Snowflake does. The record is
+/// the one of `ExecutionService::query_status`, which checks the query is one
+/// of the session user or that the user is an admin.
+#[must_use]
+pub fn query_monitoring_info(
+    query_record: QueryRecord,
+    progress: Option<QueryProgress>,
+) -> QueryMonitoringInfo {
+    let status = match query_record.status {
+        QueryStatus::Running => "RUNNING",
+        QueryStatus::Successful => "SUCCESS",
+        QueryStatus::Failed | QueryStatus::TimedOut => "FAILED_WITH_ERROR",
+        QueryStatus::Canceled => "ABORTED",
+    };
+    QueryMonitoringInfo {
+        id: query_record.id.as_uuid().to_string(),
+        status: status.to_string(),
+        sql_text: query_record.query,
+        start_time: query_record.start_time.timestamp_millis(),
+        end_time: query_record.end_time.timestamp_millis(),
+        total_duration: query_record.duration_ms,
+        error_code: query_record
+            .error
+            .as_ref()
+            .map(|_| ErrorCode::HistoricalQueryError.to_string()),
+        error_message: query_record.error,
+        progress: progress.map(|progress| QueryProgressInfo {
+            rows_scanned: progress.rows_scanned,
+            bytes_scanned: progress.bytes_scanned,
+            files_processed: progress.files_processed,
+            files_total: progress.files_total,
+            current_operator: progress.current_operator,
+        }),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::Int32Array;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::arrow::ipc::reader::StreamReader;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_row_set_encoder_arrow() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let mut encoder = RowSetEncoder::Arrow {
+            writer: None,
+            pending: Vec::new(),
+        };
+        let mut encoded = String::new();
+        for values in [vec![1, 2, 3], vec![4, 5]] {
+            let batch =
+                RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(values))])
+                    .unwrap();
+            let chunk = QueryResult::new(vec![batch], schema.clone(), QueryRecordId::default());
+            let part = encoder
+                .encode(&chunk, DataSerializationFormat::Arrow)
+                .unwrap();
+            encoded.push_str(std::str::from_utf8(&part).unwrap());
+        }
+        encoded.push_str(std::str::from_utf8(&encoder.finish().unwrap()).unwrap());
+
+        // The parts make up a single base64 encoded IPC stream
+        let stream = engine_base64.decode(encoded).unwrap();
+        let rows: Vec<usize> = StreamReader::try_new(stream.as_slice(), None)
+            .unwrap()
+            .map(|batch| batch.unwrap().num_rows())
+            .collect();
+        assert_eq!(rows, [3, 2]);
+    }
+}
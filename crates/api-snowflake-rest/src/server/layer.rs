@@ -17,8 +17,7 @@ pub async fn require_auth(
     req: Request,
     next: Next,
 ) -> error::Result<impl IntoResponse> {
-    // no demo user -> no auth required
-    if state.config.auth.demo_user.is_empty() || state.config.auth.demo_password.is_empty() {
+    if state.config.auth.disabled {
         return Ok(next.run(req).await);
     }
 
@@ -1,8 +1,13 @@
+pub mod auth;
 pub mod error;
 pub mod handlers;
 pub mod helpers;
 pub mod layer;
+pub mod result_chunks;
 pub mod router;
 pub mod server_models;
+pub mod sql_api;
+pub mod stage_transfers;
 pub mod state;
+pub mod statements;
 pub mod test_server;
@@ -0,0 +1,413 @@
+use crate::server::error::{self as api_snowflake_rest_error, Result};
+use crate::server::server_models::Config;
+use axum::body::Bytes;
+use core_executor::models::QueryResult;
+use datafusion::arrow::array::RecordBatch;
+use datafusion::error::Result as DataFusionResult;
+use datafusion::execution::DiskManager;
+use datafusion::execution::disk_manager::RefCountedTempFile;
+use snafu::ResultExt;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use uuid::Uuid;
+
+/// How long the chunks of a result stay downloadable
+const RESULT_CHUNKS_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Memory the chunks waiting for download take by default, further chunks are
+/// spilled to disk
+pub const DEFAULT_RESULT_CHUNKS_MEMORY_LIMIT: usize = 256 * 1024 * 1024;
+
+/// Chunks of large query results, kept after the query response (which inlines
+/// only the first chunk) until the client downloads them. Chunks are kept gzip
+/// compressed, in memory up to `memory_limit` bytes and spilled to disk above that.
+/// Whole responses of the statements of multi-statement requests are kept the same way.
+#[derive(Debug)]
+pub struct ResultChunks {
+    results: Mutex<HashMap<Uuid, StoredResult>>,
+    /// Responses being rendered, see [`ResultChunks::pending_response`]
+    pending: PendingResponses,
+    memory_limit: usize,
+    /// Bytes of the chunks kept in memory
+    in_memory: Arc<AtomicUsize>,
+    disk_manager: Arc<DiskManager>,
+}
+
+#[derive(Debug)]
+struct StoredResult {
+    session_id: String,
+    chunks: Vec<ResultChunk>,
+    response: Option<ResultChunk>,
+    stored_at: Instant,
+}
+
+type PendingResponses = Arc<Mutex<HashMap<Uuid, watch::Receiver<()>>>>;
+
+/// Response of a query being rendered, [`ResultChunks::response`] waits for it until
+/// it's dropped
+#[derive(Debug)]
+pub struct PendingResponse {
+    query_id: Uuid,
+    pending: PendingResponses,
+    _rendered: watch::Sender<()>,
+}
+
+impl Drop for PendingResponse {
+    fn drop(&mut self) {
+        self.pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.query_id);
+    }
+}
+
+/// Gzip compressed body of a chunk, kept in memory or spilled to disk
+#[derive(Debug, Clone)]
+pub enum ResultChunk {
+    Memory(Arc<MemoryChunk>),
+    Spilled(Arc<RefCountedTempFile>),
+}
+
+/// Chunk body counted against the memory limit until it's dropped
+#[derive(Debug)]
+pub struct MemoryChunk {
+    body: Bytes,
+    in_memory: Arc<AtomicUsize>,
+}
+
+impl Drop for MemoryChunk {
+    fn drop(&mut self) {
+        self.in_memory.fetch_sub(self.body.len(), Ordering::Relaxed);
+    }
+}
+
+impl ResultChunk {
+    /// The gzip compressed body, as it's downloaded
+    pub async fn body(&self) -> Result<Bytes> {
+        match self {
+            Self::Memory(chunk) => Ok(chunk.body.clone()),
+            Self::Spilled(file) => tokio::fs::read(file.path())
+                .await
+                .map(Bytes::from)
+                .context(api_snowflake_rest_error::ResultChunkIoSnafu),
+        }
+    }
+}
+
+impl ResultChunks {
+    #[must_use]
+    pub fn new(memory_limit: usize, disk_manager: Arc<DiskManager>) -> Self {
+        Self {
+            results: Mutex::default(),
+            pending: Arc::default(),
+            memory_limit,
+            in_memory: Arc::default(),
+            disk_manager,
+        }
+    }
+
+    /// Result chunks bounded by the configured memory limit, spilled to the OS temp
+    /// directory above that
+    pub fn from_config(config: &Config) -> DataFusionResult<Self> {
+        Ok(Self::new(
+            config
+                .result_chunks_memory_limit
+                .unwrap_or(DEFAULT_RESULT_CHUNKS_MEMORY_LIMIT),
+            Arc::new(DiskManager::builder().build()?),
+        ))
+    }
+
+    /// Keeps the gzip compressed body of a chunk in memory, or on disk once the chunks
+    /// in memory reach the memory limit. Without a usable disk the oldest results are
+    /// evicted to make room.
+    pub async fn store(&self, body: Vec<u8>) -> ResultChunk {
+        let body = Bytes::from(body);
+        if self.in_memory.load(Ordering::Relaxed) + body.len() > self.memory_limit {
+            match self.spill(&body).await {
+                Ok(file) => return ResultChunk::Spilled(Arc::new(file)),
+                Err(error) => {
+                    tracing::warn!("Failed to spill result chunk to disk: {error}");
+                    self.evict(body.len());
+                }
+            }
+        }
+        self.in_memory.fetch_add(body.len(), Ordering::Relaxed);
+        ResultChunk::Memory(Arc::new(MemoryChunk {
+            body,
+            in_memory: self.in_memory.clone(),
+        }))
+    }
+
+    async fn spill(&self, body: &[u8]) -> Result<RefCountedTempFile> {
+        let file = self
+            .disk_manager
+            .create_tmp_file("Result chunk")
+            .context(api_snowflake_rest_error::ResultChunkTempFileSnafu)?;
+        tokio::fs::write(file.path(), body)
+            .await
+            .context(api_snowflake_rest_error::ResultChunkIoSnafu)?;
+        Ok(file)
+    }
+
+    /// Drops the oldest results until `bytes` more fit in memory
+    fn evict(&self, bytes: usize) {
+        let mut results = self.results.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut oldest = results
+            .iter()
+            .map(|(query_id, result)| (result.stored_at, *query_id))
+            .collect::<Vec<_>>();
+        oldest.sort_unstable();
+        for (_, query_id) in oldest {
+            if self.in_memory.load(Ordering::Relaxed) + bytes <= self.memory_limit {
+                break;
+            }
+            results.remove(&query_id);
+        }
+    }
+
+    pub fn insert(&self, session_id: &str, query_id: Uuid, chunks: Vec<ResultChunk>) {
+        let mut results = self.results.lock().unwrap_or_else(PoisonError::into_inner);
+        results.retain(|_, result| result.stored_at.elapsed() < RESULT_CHUNKS_TTL);
+        results.insert(
+            query_id,
+            StoredResult {
+                session_id: session_id.to_string(),
+                chunks,
+                response: None,
+                stored_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Keeps the whole gzip compressed response of a query, along with its chunks
+    pub fn insert_response(&self, session_id: &str, query_id: Uuid, response: ResultChunk) {
+        let mut results = self.results.lock().unwrap_or_else(PoisonError::into_inner);
+        results
+            .entry(query_id)
+            .or_insert_with(|| StoredResult {
+                session_id: session_id.to_string(),
+                chunks: Vec::new(),
+                response: None,
+                stored_at: Instant::now(),
+            })
+            .response = Some(response);
+    }
+
+    /// Marks the response of a query as being rendered until the returned guard is
+    /// dropped, so it's waited for rather than missed
+    #[must_use]
+    pub fn pending_response(&self, query_id: Uuid) -> PendingResponse {
+        let (rendered, pending) = watch::channel(());
+        self.pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(query_id, pending);
+        PendingResponse {
+            query_id,
+            pending: self.pending.clone(),
+            _rendered: rendered,
+        }
+    }
+
+    /// Whole response of a query, only available to the session which ran the query.
+    /// Waits for a pending response to be rendered.
+    pub async fn response(&self, session_id: &str, query_id: Uuid) -> Option<ResultChunk> {
+        let pending = self
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&query_id)
+            .cloned();
+        if let Some(mut pending) = pending {
+            // nothing is sent, the wait ends once the pending response is dropped
+            let _ = pending.changed().await;
+        }
+        let results = self.results.lock().unwrap_or_else(PoisonError::into_inner);
+        results
+            .get(&query_id)
+            .filter(|result| {
+                result.session_id == session_id && result.stored_at.elapsed() < RESULT_CHUNKS_TTL
+            })
+            .and_then(|result| result.response.clone())
+    }
+
+    /// Chunk of a result, only available to the session which ran the query
+    #[must_use]
+    pub fn get(&self, session_id: &str, query_id: Uuid, index: usize) -> Option<ResultChunk> {
+        let results = self.results.lock().unwrap_or_else(PoisonError::into_inner);
+        results
+            .get(&query_id)
+            .filter(|result| {
+                result.session_id == session_id && result.stored_at.elapsed() < RESULT_CHUNKS_TTL
+            })
+            .and_then(|result| result.chunks.get(index).cloned())
+    }
+
+    /// Bytes of the chunks kept in memory
+    #[must_use]
+    pub fn memory_usage(&self) -> usize {
+        self.in_memory.load(Ordering::Relaxed)
+    }
+}
+
+/// Splits batches, as they are streamed in, into chunks of `chunk_rows` rows
+#[derive(Debug)]
+pub struct ChunkSplitter {
+    chunk_rows: usize,
+    rows: usize,
+    current: Vec<RecordBatch>,
+}
+
+impl ChunkSplitter {
+    #[must_use]
+    pub fn new(chunk_rows: usize) -> Self {
+        Self {
+            chunk_rows: chunk_rows.max(1),
+            rows: 0,
+            current: Vec::new(),
+        }
+    }
+
+    /// Adds a batch, returns the chunks it completes
+    pub fn push(&mut self, batch: &RecordBatch) -> Vec<Vec<RecordBatch>> {
+        let mut completed = Vec::new();
+        let mut offset = 0;
+        while offset < batch.num_rows() {
+            let length = (self.chunk_rows - self.rows).min(batch.num_rows() - offset);
+            self.current.push(batch.slice(offset, length));
+            offset += length;
+            self.rows += length;
+            if self.rows == self.chunk_rows {
+                completed.push(std::mem::take(&mut self.current));
+                self.rows = 0;
+            }
+        }
+        completed
+    }
+
+    /// The last, incomplete chunk
+    #[must_use]
+    pub fn finish(self) -> Option<Vec<RecordBatch>> {
+        (self.rows > 0).then_some(self.current)
+    }
+}
+
+/// Splits a result into chunks of at most `chunk_rows` rows
+#[must_use]
+pub fn split_into_chunks(query_result: QueryResult, chunk_rows: usize) -> Vec<QueryResult> {
+    let mut splitter = ChunkSplitter::new(chunk_rows);
+    let mut chunks: Vec<Vec<RecordBatch>> = query_result
+        .records
+        .iter()
+        .flat_map(|batch| splitter.push(batch))
+        .collect();
+    chunks.extend(splitter.finish());
+    if chunks.is_empty() {
+        chunks.push(Vec::new());
+    }
+    chunks
+        .into_iter()
+        .map(|records| QueryResult {
+            records,
+            schema: query_result.schema.clone(),
+            query_id: query_result.query_id,
+            file_transfer: query_result.file_transfer.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use core_history::QueryRecordId;
+    use datafusion::arrow::array::Int32Array;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+
+    fn chunk_rows(chunks: &[QueryResult]) -> Vec<usize> {
+        chunks
+            .iter()
+            .map(|chunk| chunk.records.iter().map(RecordBatch::num_rows).sum())
+            .collect()
+    }
+
+    #[test]
+    fn test_split_into_chunks() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = |values: Vec<i32>| {
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(values))]).unwrap()
+        };
+        let result = QueryResult {
+            records: vec![batch(vec![1, 2, 3]), batch(vec![4, 5, 6, 7])],
+            schema: schema.clone(),
+            query_id: QueryRecordId::default(),
+            file_transfer: None,
+        };
+
+        assert_eq!(
+            chunk_rows(&split_into_chunks(result.clone(), 2)),
+            [2, 2, 2, 1]
+        );
+        assert_eq!(chunk_rows(&split_into_chunks(result.clone(), 10)), [7]);
+        let result = QueryResult {
+            records: Vec::new(),
+            ..result
+        };
+        assert_eq!(chunk_rows(&split_into_chunks(result, 2)), [0]);
+    }
+
+    fn result_chunks(memory_limit: usize) -> ResultChunks {
+        ResultChunks::new(
+            memory_limit,
+            Arc::new(DiskManager::builder().build().unwrap()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_result_chunks_per_session() {
+        let chunks = result_chunks(DEFAULT_RESULT_CHUNKS_MEMORY_LIMIT);
+        let query_id = Uuid::new_v4();
+        let chunk = chunks.store(b"chunk".to_vec()).await;
+        chunks.insert("session", query_id, vec![chunk]);
+        assert!(chunks.get("session", query_id, 0).is_some());
+        assert!(chunks.get("session", query_id, 1).is_none());
+        assert!(chunks.get("other", query_id, 0).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_result_chunks_pending_response() {
+        let chunks = Arc::new(result_chunks(DEFAULT_RESULT_CHUNKS_MEMORY_LIMIT));
+        let query_id = Uuid::new_v4();
+        assert!(chunks.response("session", query_id).await.is_none());
+
+        let pending = chunks.pending_response(query_id);
+        let waiting = tokio::spawn({
+            let chunks = chunks.clone();
+            async move { chunks.response("session", query_id).await }
+        });
+        let response = chunks.store(b"response".to_vec()).await;
+        chunks.insert_response("session", query_id, response);
+        drop(pending);
+        let response = waiting.await.unwrap().unwrap();
+        assert_eq!(response.body().await.unwrap(), &b"response"[..]);
+        assert!(chunks.response("other", query_id).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_result_chunks_spill() {
+        let chunks = result_chunks(8);
+        let in_memory = chunks.store(b"chunk 1".to_vec()).await;
+        let spilled = chunks.store(b"chunk 2".to_vec()).await;
+        assert!(matches!(in_memory, ResultChunk::Memory(_)));
+        assert!(matches!(spilled, ResultChunk::Spilled(_)));
+        assert_eq!(chunks.memory_usage(), 7);
+        assert_eq!(spilled.body().await.unwrap(), &b"chunk 2"[..]);
+
+        // Memory is released along with the chunks of expired or evicted results
+        drop(in_memory);
+        assert_eq!(chunks.memory_usage(), 0);
+    }
+}
@@ -1,16 +1,21 @@
-use super::handlers::{abort, get_query, login, query};
+use super::handlers::{
+    abort, close_session, download_stage_file, get_query, get_query_result_chunk, get_query_status,
+    heartbeat, login, query, renew_session, upload_stage_file,
+};
+use super::sql_api::{cancel_statement, get_statement, submit_statement};
 use super::state::AppState;
 use axum::Router;
-use axum::routing::{get, post};
+use axum::routing::{get, post, put};
 
 use super::layer::require_auth;
+use super::result_chunks::ResultChunks;
 use super::server_models::Config;
 use super::state;
 use axum::middleware;
 use core_executor::service::CoreExecutionService;
 use core_executor::utils::Config as UtilsConfig;
 use core_history::SlateDBHistoryStore;
-use core_metastore::SlateDBMetastore;
+use core_metastore::{SlateDBMetastore, bootstrap_user};
 use std::sync::Arc;
 use tower::ServiceBuilder;
 use tower_http::compression::CompressionLayer;
@@ -20,11 +25,40 @@ pub fn create_auth_router() -> Router<AppState> {
     Router::new().route("/session/v1/login-request", post(login))
 }
 
+/// Presigned URLs of stage files. The drivers send no authorization along, the
+/// URL token is the authorization. Bodies are sent as is, without compression.
+/// Uploads are streamed to the stage, their size isn't limited.
+pub fn create_stage_transfer_router() -> Router<AppState> {
+    Router::new().route(
+        "/stage-transfers/{token}",
+        put(upload_stage_file).get(download_stage_file),
+    )
+}
+
+/// The SQL API, authenticated by the bearer token of each request
+pub fn create_sql_api_router() -> Router<AppState> {
+    Router::new()
+        .route("/api/v2/statements", post(submit_statement))
+        .route("/api/v2/statements/{statementHandle}", get(get_statement))
+        .route(
+            "/api/v2/statements/{statementHandle}/cancel",
+            post(cancel_statement),
+        )
+}
+
 pub fn create_router() -> Router<AppState> {
     Router::new()
         .route("/queries/v1/query-request", post(query))
         .route("/queries/v1/abort-request", post(abort))
         .route("/queries/{queryId}/result", get(get_query))
+        .route(
+            "/queries/{queryId}/result/chunks/{chunkIndex}",
+            get(get_query_result_chunk),
+        )
+        .route("/monitoring/queries/{queryId}", get(get_query_status))
+        .route("/session/heartbeat", post(heartbeat))
+        .route("/session/token-request", post(renew_session))
+        .route("/session", post(close_session))
 }
 
 // TODO: We should consider using this by both main and tests
@@ -35,9 +69,14 @@ pub async fn make_app(
     snowflake_rest_cfg: Config,
     execution_cfg: UtilsConfig,
 ) -> Result<Router, Box<dyn std::error::Error>> {
+    let metastore = Arc::new(metastore);
+    let auth = &snowflake_rest_cfg.auth;
+    if !auth.demo_user.is_empty() {
+        bootstrap_user(metastore.as_ref(), &auth.demo_user, &auth.demo_password).await?;
+    }
     let execution_svc = Arc::new(
         CoreExecutionService::new(
-            Arc::new(metastore),
+            metastore.clone(),
             Arc::new(history_store),
             Arc::new(execution_cfg),
         )
@@ -45,11 +84,17 @@ pub async fn make_app(
         .expect("Failed to create execution service"),
     );
 
+    let result_chunks = ResultChunks::from_config(&snowflake_rest_cfg)?;
+
     // Create the application state
 
     let snowflake_state = state::AppState {
         execution_svc,
+        metastore,
         config: snowflake_rest_cfg,
+        result_chunks: Arc::new(result_chunks),
+        stage_transfers: Arc::default(),
+        statements: Arc::default(),
     };
 
     let compression_layer = ServiceBuilder::new()
@@ -64,9 +109,17 @@ pub async fn make_app(
             require_auth,
         ));
     let snowflake_auth_router = create_auth_router()
-        .with_state(snowflake_state)
+        .with_state(snowflake_state.clone())
+        .layer(compression_layer.clone());
+    let snowflake_sql_api_router = create_sql_api_router()
+        .with_state(snowflake_state.clone())
         .layer(compression_layer);
-    let snowflake_router = snowflake_router.merge(snowflake_auth_router);
+    let snowflake_stage_transfer_router =
+        create_stage_transfer_router().with_state(snowflake_state);
+    let snowflake_router = snowflake_router
+        .merge(snowflake_auth_router)
+        .merge(snowflake_sql_api_router)
+        .merge(snowflake_stage_transfer_router);
 
     let router = Router::new().merge(snowflake_router);
 
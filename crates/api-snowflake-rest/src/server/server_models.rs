@@ -5,6 +5,15 @@ use core_executor::utils::DataSerializationFormat;
 pub struct Config {
     pub auth: Auth,
     pub dbt_serialization_format: DataSerializationFormat,
+    /// Results with more rows are returned in chunks of this many rows, only the
+    /// first one inlined in the query response
+    pub result_chunk_rows: Option<usize>,
+    /// Memory the result chunks waiting for download may take, further chunks are
+    /// spilled to disk
+    pub result_chunks_memory_limit: Option<usize>,
+    /// Address the clients reach the server at, used in the URLs of result chunks
+    /// and stage file transfers
+    pub base_url: String,
 }
 
 impl Config {
@@ -16,10 +25,28 @@ impl Config {
     }
     #[must_use]
     pub fn with_demo_credentials(mut self, demo_user: String, demo_password: String) -> Self {
-        self.auth = Auth {
-            demo_user,
-            demo_password,
-        };
+        self.auth.demo_user = demo_user;
+        self.auth.demo_password = demo_password;
+        self
+    }
+    #[must_use]
+    pub const fn with_auth_disabled(mut self, disabled: bool) -> Self {
+        self.auth.disabled = disabled;
+        self
+    }
+    #[must_use]
+    pub const fn with_result_chunk_rows(mut self, result_chunk_rows: Option<usize>) -> Self {
+        self.result_chunk_rows = result_chunk_rows;
+        self
+    }
+    #[must_use]
+    pub const fn with_result_chunks_memory_limit(mut self, memory_limit: Option<usize>) -> Self {
+        self.result_chunks_memory_limit = memory_limit;
+        self
+    }
+    #[must_use]
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
         self
     }
 }
@@ -0,0 +1,530 @@
+//! Snowflake SQL API v2, `/api/v2/statements`.
+//!
+//! Every statement runs in a session of its own, opened with the database,
+//! schema, warehouse and role of the request. Statements are submitted with
+//! `submit_query` and their results are kept with the result chunks, in
+//! partitions of `result_chunk_rows` rows, once they finish. The session is
+//! deleted along with it. Errors are read back from the query history.
+use super::auth::authenticate_bearer;
+use super::error::{self as api_snowflake_rest_error, Error, Result};
+use super::handlers::header_value;
+use super::helpers::{gzip, session_param_value};
+use super::result_chunks::{ResultChunk, split_into_chunks};
+use super::state::AppState;
+use super::statements::SubmittedStatement;
+use crate::models::{
+    PartitionInfo, ResultSetMetaData, StatementPartitionQueryParams, StatementQueryParams,
+    StatementRequest, StatementResponse, ordered_bindings,
+};
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use core_executor::RunningQueryId;
+use core_executor::access_control::QueryReader;
+use core_executor::models::{AsyncQueryHandle, QueryContext, QueryResult, SessionOptions};
+use core_executor::utils::DataSerializationFormat;
+use core_metastore::{AuthenticatedUser, User};
+use datafusion::scalar::ScalarValue;
+use flate2::read::GzDecoder;
+use serde::de::DeserializeOwned;
+use snafu::{OptionExt, ResultExt};
+use std::collections::HashMap;
+use std::io::Read;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// How long a statement submitted without `async=true` is waited for, before
+/// its handle is returned instead of its result
+const SUBMIT_WAIT: Duration = Duration::from_secs(45);
+/// How long a status request waits for a running statement
+const STATUS_WAIT: Duration = Duration::from_secs(1);
+/// Rows per result partition, unless `result_chunk_rows` is configured
+const DEFAULT_PARTITION_ROWS: usize = 10_000;
+const RESULT_FORMAT: &str = "jsonv2";
+
+const SUCCESS_CODE: &str = "090001";
+const SUCCESS_MESSAGE: &str = "Statement executed successfully.";
+const SUCCESS_SQL_STATE: &str = "00000";
+const IN_PROGRESS_CODE: &str = "333334";
+const IN_PROGRESS_MESSAGE: &str = "Asynchronous execution in progress. Use provided query id to perform query monitoring and management.";
+const CANCELED_CODE: &str = "000604";
+const CANCELED_MESSAGE: &str = "SQL execution canceled";
+const CANCELED_SQL_STATE: &str = "57014";
+
+pub type SqlApiResult<T> = std::result::Result<T, SqlApiError>;
+
+/// Errors of the SQL API, responded in its own body along with the statement
+/// handle. Failed statements respond with `422 Unprocessable Entity` instead of
+/// the `200` of the connector protocol.
+#[derive(Debug)]
+pub struct SqlApiError {
+    error: Error,
+    handle: Option<Uuid>,
+}
+
+impl SqlApiError {
+    #[must_use]
+    pub const fn with_handle(mut self, handle: Uuid) -> Self {
+        self.handle = Some(handle);
+        self
+    }
+}
+
+impl From<Error> for SqlApiError {
+    fn from(error: Error) -> Self {
+        Self {
+            error,
+            handle: None,
+        }
+    }
+}
+
+impl From<core_executor::Error> for SqlApiError {
+    fn from(error: core_executor::Error) -> Self {
+        Error::from(error).into()
+    }
+}
+
+impl IntoResponse for SqlApiError {
+    fn into_response(self) -> Response {
+        let (status, Json(body)) = self.error.prepare_response();
+        let status = if status == StatusCode::OK {
+            StatusCode::UNPROCESSABLE_ENTITY
+        } else {
+            status
+        };
+        let (code, sql_state) = body
+            .data
+            .map_or((None, None), |data| (data.error_code, data.sql_state));
+        let response = StatementResponse {
+            code,
+            sql_state,
+            message: body.message,
+            statement_handle: self.handle.map(|handle| handle.to_string()),
+            statement_status_url: self.handle.map(statement_status_url),
+            ..Default::default()
+        };
+        (status, Json(response)).into_response()
+    }
+}
+
+fn statement_status_url(handle: Uuid) -> String {
+    format!("/api/v2/statements/{handle}")
+}
+
+/// User of a request, authenticated by its bearer token. No user when auth
+/// is disabled, as with the connector protocol.
+async fn request_user(state: &AppState, headers: &HeaderMap) -> Result<Option<AuthenticatedUser>> {
+    if state.config.auth.disabled {
+        return Ok(None);
+    }
+    let Some(token) = header_value(headers, header::AUTHORIZATION.as_str())
+        .and_then(|authorization| authorization.strip_prefix("Bearer "))
+    else {
+        return api_snowflake_rest_error::MissingAuthTokenSnafu.fail();
+    };
+    let token_type = header_value(headers, "x-snowflake-authorization-token-type");
+    authenticate_bearer(state.metastore.as_ref(), token_type, token.trim())
+        .await
+        .map(Some)
+}
+
+/// A statement of the request user
+async fn user_statement(
+    state: &AppState,
+    headers: &HeaderMap,
+    handle: Uuid,
+) -> Result<SubmittedStatement> {
+    let user = request_user(state, headers).await?;
+    state
+        .statements
+        .get(handle, user.as_ref().map(|user| user.user.name.as_str()))
+        .context(api_snowflake_rest_error::StatementNotFoundSnafu {
+            handle: handle.to_string(),
+        })
+}
+
+/// Options of the statement session. The user defaults apply unless the request
+/// has its own, statements of a role restricted access token can only use that role.
+fn session_options(user: Option<&AuthenticatedUser>, request: &StatementRequest) -> SessionOptions {
+    let mut params: HashMap<String, ScalarValue> = request
+        .parameters
+        .iter()
+        .flatten()
+        .filter_map(|(name, value)| Some((name.clone(), session_param_value(value)?)))
+        .collect();
+    let user_default =
+        |default: fn(&User) -> Option<&String>| user.and_then(|user| default(&user.user).cloned());
+    let role = user
+        .and_then(|user| user.role_restriction.clone())
+        .or_else(|| request.role.clone())
+        .or_else(|| user_default(|user| user.default_role.as_ref()));
+    let current = [
+        (
+            "database",
+            request
+                .database
+                .clone()
+                .or_else(|| user_default(|user| user.default_database.as_ref())),
+        ),
+        (
+            "schema",
+            request
+                .schema
+                .clone()
+                .or_else(|| user_default(|user| user.default_schema.as_ref())),
+        ),
+        ("warehouse", request.warehouse.clone()),
+        ("role", role),
+    ];
+    for (name, value) in current {
+        if let Some(value) = value {
+            params.insert(name.to_string(), ScalarValue::Utf8(Some(value)));
+        }
+    }
+    SessionOptions {
+        params,
+        client_info: None,
+        user: user.map(|user| user.user.name.clone()),
+        role_restriction: user.and_then(|user| user.role_restriction.clone()),
+    }
+}
+
+fn partition_rows(state: &AppState) -> usize {
+    state
+        .config
+        .result_chunk_rows
+        .unwrap_or(DEFAULT_PARTITION_ROWS)
+}
+
+/// Rows of a partition, with all the values as strings
+fn partition_data(partition: &QueryResult) -> Result<Vec<Vec<Option<String>>>> {
+    Ok(partition
+        .as_row_set(DataSerializationFormat::Json)?
+        .into_iter()
+        .map(|row| {
+            row.0
+                .into_iter()
+                .map(|value| match value {
+                    serde_json::Value::Null => None,
+                    serde_json::Value::String(value) => Some(value),
+                    value => Some(value.to_string()),
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// Fields of all the responses about a statement
+fn statement_fields(statement: &SubmittedStatement) -> StatementResponse {
+    StatementResponse {
+        statement_handle: Some(statement.handle().to_string()),
+        statement_status_url: Some(statement_status_url(statement.handle())),
+        request_id: statement
+            .request_id
+            .map(|request_id| request_id.to_string()),
+        created_on: Some(statement.created_on),
+        ..Default::default()
+    }
+}
+
+fn in_progress_response(statement: &SubmittedStatement) -> (StatusCode, Json<StatementResponse>) {
+    (
+        StatusCode::ACCEPTED,
+        Json(StatementResponse {
+            code: Some(IN_PROGRESS_CODE.to_string()),
+            sql_state: Some(SUCCESS_SQL_STATE.to_string()),
+            message: Some(IN_PROGRESS_MESSAGE.to_string()),
+            ..statement_fields(statement)
+        }),
+    )
+}
+
+/// The first partition of the statement result along with the result metadata,
+/// or the statement handle if the statement doesn't finish within `wait`
+async fn statement_response(
+    state: &AppState,
+    statement: &SubmittedStatement,
+    wait: Duration,
+) -> SqlApiResult<(StatusCode, Json<StatementResponse>)> {
+    let handle = statement.handle();
+    let Ok(response) = tokio::time::timeout(
+        wait,
+        state.result_chunks.response(&statement.session_id, handle),
+    )
+    .await
+    else {
+        return Ok(in_progress_response(statement));
+    };
+    if let Some(response) = response {
+        let response = chunk_json(&response)
+            .await
+            .map_err(|error| SqlApiError::from(error).with_handle(handle))?;
+        return Ok((StatusCode::OK, Json(response)));
+    }
+
+    // Only the results of succeeded statements are kept, failures are recorded
+    // to the query history along with their error. The statement session is
+    // deleted once it finishes, the history is read as the statement user.
+    let reader = QueryReader {
+        user: statement.user.clone(),
+        ..QueryReader::default()
+    };
+    let error = match state
+        .execution_svc
+        .wait_historical_query_result(&reader, statement.query_id)
+        .await
+        .and_then(|result| result)
+    {
+        Err(error) => SqlApiError::from(error),
+        Ok(_) => api_snowflake_rest_error::ResultChunkNotFoundSnafu {
+            query_id: handle.to_string(),
+            index: 0,
+        }
+        .build()
+        .into(),
+    };
+    Err(error.with_handle(handle))
+}
+
+/// Waits for the statement to finish, keeps its result partitions and deletes its session
+async fn finish_statement(
+    state: AppState,
+    statement: SubmittedStatement,
+    query_handle: AsyncQueryHandle,
+) {
+    if let Ok(status) = query_handle.rx.await
+        && let Ok(query_result) = status.query_result
+        && let Err(error) = store_statement_result(&state, &statement, query_result).await
+    {
+        tracing::warn!("Failed to store statement result: {error}");
+    }
+    if let Err(error) = state
+        .execution_svc
+        .delete_session(&statement.session_id)
+        .await
+    {
+        tracing::warn!("Failed to delete statement session: {error}");
+    }
+}
+
+/// Keeps the response with the first partition of the result, and the later
+/// partitions as result chunks
+async fn store_statement_result(
+    state: &AppState,
+    statement: &SubmittedStatement,
+    query_result: QueryResult,
+) -> Result<()> {
+    let row_type = query_result
+        .column_info()
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    let mut data = None;
+    let mut partition_info = Vec::new();
+    let mut chunks = Vec::new();
+    for partition in split_into_chunks(query_result, partition_rows(state)) {
+        let rows = partition_data(&partition)?;
+        let json = serde_json::to_vec(&rows).context(api_snowflake_rest_error::RowParseSnafu)?;
+        partition_info.push(PartitionInfo {
+            row_count: rows.len(),
+            uncompressed_size: json.len(),
+        });
+        if data.is_none() {
+            data = Some(rows);
+        } else {
+            chunks.push(state.result_chunks.store(gzip(&json)?).await);
+        }
+    }
+    let response = StatementResponse {
+        result_set_meta_data: Some(ResultSetMetaData {
+            num_rows: partition_info
+                .iter()
+                .map(|partition| partition.row_count)
+                .sum(),
+            format: RESULT_FORMAT.to_string(),
+            row_type,
+            partition_info,
+        }),
+        data,
+        code: Some(SUCCESS_CODE.to_string()),
+        sql_state: Some(SUCCESS_SQL_STATE.to_string()),
+        message: Some(SUCCESS_MESSAGE.to_string()),
+        ..statement_fields(statement)
+    };
+    let response =
+        serde_json::to_vec(&response).context(api_snowflake_rest_error::RowParseSnafu)?;
+    let response = state.result_chunks.store(gzip(&response)?).await;
+    let handle = statement.handle();
+    state
+        .result_chunks
+        .insert(&statement.session_id, handle, chunks);
+    state
+        .result_chunks
+        .insert_response(&statement.session_id, handle, response);
+    Ok(())
+}
+
+/// JSON kept gzip compressed in a result chunk
+async fn chunk_json<T: DeserializeOwned>(chunk: &ResultChunk) -> Result<T> {
+    let body = chunk.body().await?;
+    let mut json = Vec::new();
+    GzDecoder::new(&body[..])
+        .read_to_end(&mut json)
+        .context(api_snowflake_rest_error::ResultChunkIoSnafu)?;
+    serde_json::from_slice(&json).context(api_snowflake_rest_error::RowParseSnafu)
+}
+
+/// Submits a statement, responding with its result unless it is submitted with
+/// `async=true` or runs longer than [`SUBMIT_WAIT`]
+#[tracing::instrument(
+    name = "api_snowflake_rest::submit_statement",
+    level = "debug",
+    skip(state, headers, request),
+    fields(query_id, query_uuid)
+)]
+pub async fn submit_statement(
+    State(state): State<AppState>,
+    Query(params): Query<StatementQueryParams>,
+    headers: HeaderMap,
+    Json(request): Json<StatementRequest>,
+) -> SqlApiResult<(StatusCode, Json<StatementResponse>)> {
+    let user = request_user(&state, &headers).await?;
+    let user_name = user.as_ref().map(|user| user.user.name.clone());
+
+    // A retried request gets the statement of its first attempt
+    let retried = params
+        .request_id
+        .filter(|_| params.retry)
+        .and_then(|request_id| {
+            state
+                .statements
+                .get_by_request_id(request_id, user_name.as_deref())
+        });
+    if let Some(statement) = retried {
+        return statement_response(&state, &statement, SUBMIT_WAIT).await;
+    }
+
+    let session_id = Uuid::new_v4().to_string();
+    state
+        .execution_svc
+        .create_session_with_options(&session_id, session_options(user.as_ref(), &request))
+        .await?;
+    let mut query_context = QueryContext::default()
+        .with_async_query(params.async_exec)
+        .with_bindings(request.bindings.map(ordered_bindings).unwrap_or_default());
+    if let Some(request_id) = params.request_id {
+        query_context = query_context.with_request_id(request_id);
+    }
+    let submitted = state
+        .execution_svc
+        .submit_query(&session_id, &request.statement, query_context)
+        .await;
+    let query_handle = match submitted {
+        Ok(query_handle) => query_handle,
+        Err(error) => {
+            state.execution_svc.delete_session(&session_id).await?;
+            return Err(error.into());
+        }
+    };
+    // Status requests wait for the result until it's stored
+    let pending = state
+        .result_chunks
+        .pending_response(query_handle.query_id.as_uuid());
+    let statement = state.statements.insert(
+        query_handle.query_id,
+        &session_id,
+        user_name,
+        params.request_id,
+    );
+    // Record the result as part of the current span.
+    tracing::Span::current()
+        .record("query_id", statement.query_id.as_i64())
+        .record("query_uuid", statement.handle().to_string());
+
+    if let Some(timeout) = request.timeout.filter(|timeout| *timeout > 0) {
+        // The statement is canceled once it runs longer than its timeout
+        let execution_svc = state.execution_svc.clone();
+        let query_id = statement.query_id;
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(timeout)).await;
+            // Fails if the statement already finished
+            let _ = execution_svc.abort_query(RunningQueryId::ByQueryId(query_id));
+        });
+    }
+
+    let finished = state.clone();
+    let submitted = statement.clone();
+    tokio::spawn(async move {
+        let _pending = pending;
+        finish_statement(finished, submitted, query_handle).await;
+    });
+
+    if params.async_exec {
+        return Ok(in_progress_response(&statement));
+    }
+    statement_response(&state, &statement, SUBMIT_WAIT).await
+}
+
+/// Status of a statement along with the first partition of its result, or
+/// another partition of the result with `partition=N`
+#[tracing::instrument(
+    name = "api_snowflake_rest::get_statement",
+    level = "debug",
+    skip(state, headers)
+)]
+pub async fn get_statement(
+    State(state): State<AppState>,
+    Path(handle): Path<Uuid>,
+    Query(StatementPartitionQueryParams { partition }): Query<StatementPartitionQueryParams>,
+    headers: HeaderMap,
+) -> SqlApiResult<(StatusCode, Json<StatementResponse>)> {
+    let statement = user_statement(&state, &headers, handle).await?;
+    if partition == 0 {
+        return statement_response(&state, &statement, STATUS_WAIT).await;
+    }
+
+    // Later partitions are requested once the result metadata is known, the
+    // first one is part of the response
+    let chunk = state
+        .result_chunks
+        .get(&statement.session_id, handle, partition - 1)
+        .context(api_snowflake_rest_error::ResultChunkNotFoundSnafu {
+            query_id: handle.to_string(),
+            index: partition,
+        })?;
+    let rows: Vec<Vec<Option<String>>> = chunk_json(&chunk)
+        .await
+        .map_err(|error| SqlApiError::from(error).with_handle(handle))?;
+    Ok((
+        StatusCode::OK,
+        Json(StatementResponse {
+            data: Some(rows),
+            ..Default::default()
+        }),
+    ))
+}
+
+#[tracing::instrument(
+    name = "api_snowflake_rest::cancel_statement",
+    level = "debug",
+    skip(state, headers)
+)]
+pub async fn cancel_statement(
+    State(state): State<AppState>,
+    Path(handle): Path<Uuid>,
+    headers: HeaderMap,
+) -> SqlApiResult<Json<StatementResponse>> {
+    let statement = user_statement(&state, &headers, handle).await?;
+    state
+        .execution_svc
+        .abort_query(RunningQueryId::ByQueryId(statement.query_id))
+        .map_err(|error| SqlApiError::from(error).with_handle(handle))?;
+    Ok(Json(StatementResponse {
+        code: Some(CANCELED_CODE.to_string()),
+        sql_state: Some(CANCELED_SQL_STATE.to_string()),
+        message: Some(CANCELED_MESSAGE.to_string()),
+        ..statement_fields(&statement)
+    }))
+}
@@ -0,0 +1,99 @@
+use object_store::ObjectStore;
+use object_store::path::Path;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// How long the presigned URLs of a `PUT` or `GET` stay usable
+const TRANSFER_URL_TTL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferKind {
+    Upload {
+        auto_compress: bool,
+        overwrite: bool,
+    },
+    Download,
+}
+
+/// A stage file the client is allowed to upload or download
+#[derive(Debug, Clone)]
+pub struct StageTransfer {
+    pub object_store: Arc<dyn ObjectStore>,
+    /// Location of the file on the stage volume
+    pub location: Path,
+    pub kind: TransferKind,
+    issued_at: Instant,
+}
+
+/// Stage files of `PUT` and `GET` responses, addressed by the token of their
+/// presigned URL. The token is the only authorization the driver sends along,
+/// the way it does with presigned URLs of cloud storage.
+#[derive(Debug, Default)]
+pub struct StageTransfers {
+    transfers: Mutex<HashMap<Uuid, StageTransfer>>,
+}
+
+impl StageTransfers {
+    /// Issues the token of a presigned URL
+    pub fn insert(
+        &self,
+        object_store: Arc<dyn ObjectStore>,
+        location: Path,
+        kind: TransferKind,
+    ) -> Uuid {
+        let token = Uuid::new_v4();
+        let mut transfers = self
+            .transfers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        transfers.retain(|_, transfer| transfer.issued_at.elapsed() < TRANSFER_URL_TTL);
+        transfers.insert(
+            token,
+            StageTransfer {
+                object_store,
+                location,
+                kind,
+                issued_at: Instant::now(),
+            },
+        );
+        token
+    }
+
+    #[must_use]
+    pub fn get(&self, token: Uuid) -> Option<StageTransfer> {
+        let transfers = self
+            .transfers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        transfers
+            .get(&token)
+            .filter(|transfer| transfer.issued_at.elapsed() < TRANSFER_URL_TTL)
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use object_store::memory::InMemory;
+
+    #[test]
+    fn test_stage_transfers() {
+        let transfers = StageTransfers::default();
+        let token = transfers.insert(
+            Arc::new(InMemory::new()),
+            Path::from("db/sch/.stages/uploads/data.csv.gz"),
+            TransferKind::Download,
+        );
+        let transfer = transfers.get(token).unwrap();
+        assert_eq!(
+            transfer.location.as_ref(),
+            "db/sch/.stages/uploads/data.csv.gz"
+        );
+        assert_eq!(transfer.kind, TransferKind::Download);
+        assert!(transfers.get(Uuid::new_v4()).is_none());
+    }
+}
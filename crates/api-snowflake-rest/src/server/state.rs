@@ -1,12 +1,20 @@
+use super::result_chunks::ResultChunks;
 use super::server_models::Config;
+use super::stage_transfers::StageTransfers;
+use super::statements::Statements;
 use core_executor::ExecutionAppState;
 use core_executor::service::ExecutionService;
+use core_metastore::Metastore;
 use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct AppState {
     pub execution_svc: Arc<dyn ExecutionService>,
+    pub metastore: Arc<dyn Metastore>,
     pub config: Config,
+    pub result_chunks: Arc<ResultChunks>,
+    pub stage_transfers: Arc<StageTransfers>,
+    pub statements: Arc<Statements>,
 }
 
 impl ExecutionAppState for AppState {
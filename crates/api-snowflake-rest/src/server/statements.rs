@@ -0,0 +1,126 @@
+use core_history::QueryRecordId;
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// How long the status and the result of a statement can be requested
+const STATEMENTS_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A statement submitted with the SQL API
+#[derive(Debug, Clone)]
+pub struct SubmittedStatement {
+    pub query_id: QueryRecordId,
+    /// Session the statement ran in, its result is stored under it
+    pub session_id: String,
+    /// User who submitted the statement, `None` when no auth is required
+    pub user: Option<String>,
+    pub request_id: Option<Uuid>,
+    /// Milliseconds since the epoch
+    pub created_on: i64,
+    submitted_at: Instant,
+}
+
+impl SubmittedStatement {
+    #[must_use]
+    pub fn handle(&self) -> Uuid {
+        self.query_id.as_uuid()
+    }
+}
+
+/// Statements of the SQL API, addressed by their statement handle. Only the
+/// user who submitted a statement can see it.
+#[derive(Debug, Default)]
+pub struct Statements {
+    statements: Mutex<HashMap<Uuid, SubmittedStatement>>,
+}
+
+impl Statements {
+    pub fn insert(
+        &self,
+        query_id: QueryRecordId,
+        session_id: &str,
+        user: Option<String>,
+        request_id: Option<Uuid>,
+    ) -> SubmittedStatement {
+        let statement = SubmittedStatement {
+            query_id,
+            session_id: session_id.to_string(),
+            user,
+            request_id,
+            created_on: i64::try_from(
+                time::OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000,
+            )
+            .unwrap_or_default(),
+            submitted_at: Instant::now(),
+        };
+        let mut statements = self
+            .statements
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        statements.retain(|_, statement| statement.submitted_at.elapsed() < STATEMENTS_TTL);
+        statements.insert(statement.handle(), statement.clone());
+        statement
+    }
+
+    #[must_use]
+    pub fn get(&self, handle: Uuid, user: Option<&str>) -> Option<SubmittedStatement> {
+        let statements = self
+            .statements
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        statements
+            .get(&handle)
+            .filter(|statement| {
+                statement.user.as_deref() == user
+                    && statement.submitted_at.elapsed() < STATEMENTS_TTL
+            })
+            .cloned()
+    }
+
+    /// The statement a retried request submitted before
+    #[must_use]
+    pub fn get_by_request_id(
+        &self,
+        request_id: Uuid,
+        user: Option<&str>,
+    ) -> Option<SubmittedStatement> {
+        let statements = self
+            .statements
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        statements
+            .values()
+            .find(|statement| {
+                statement.request_id == Some(request_id)
+                    && statement.user.as_deref() == user
+                    && statement.submitted_at.elapsed() < STATEMENTS_TTL
+            })
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statements_per_user() {
+        let statements = Statements::default();
+        let request_id = Uuid::new_v4();
+        let statement = statements.insert(
+            QueryRecordId::default(),
+            "session",
+            Some("ci".to_string()),
+            Some(request_id),
+        );
+        assert!(statements.get(statement.handle(), Some("ci")).is_some());
+        assert!(statements.get(statement.handle(), Some("other")).is_none());
+        assert!(statements.get(statement.handle(), None).is_none());
+        assert!(
+            statements
+                .get_by_request_id(request_id, Some("ci"))
+                .is_some()
+        );
+    }
+}
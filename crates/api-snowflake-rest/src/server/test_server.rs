@@ -17,11 +17,14 @@ pub async fn run_test_rest_api_server(data_format: &str) -> SocketAddr {
 
 #[allow(clippy::unwrap_used, clippy::expect_used)]
 pub async fn run_test_rest_api_server_with_config(
-    app_cfg: Config,
+    mut app_cfg: Config,
     execution_cfg: UtilsConfig,
 ) -> SocketAddr {
     let listener = tokio::net::TcpListener::bind("0.0.0.0:0").await.unwrap();
     let addr = listener.local_addr().unwrap();
+    if app_cfg.base_url.is_empty() {
+        app_cfg = app_cfg.with_base_url(format!("http://{addr}"));
+    }
 
     let traces_writer = std::fs::OpenOptions::new()
         .create(true)
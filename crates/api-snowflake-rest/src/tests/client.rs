@@ -1,7 +1,9 @@
 #![allow(clippy::unwrap_used, clippy::expect_used)]
 
 use crate::models::AbortRequestBody;
-use crate::models::{ClientEnvironment, LoginRequestBody, LoginRequestData, QueryRequestBody};
+use crate::models::{
+    ClientEnvironment, LoginRequestBody, LoginRequestData, QueryBinding, QueryRequestBody,
+};
 use reqwest;
 use reqwest::Method;
 use reqwest::StatusCode;
@@ -116,7 +118,12 @@ pub fn get_query_result_url(addr: &SocketAddr, query_id: &str) -> String {
     format!("http://{addr}/queries/{query_id}/result")
 }
 
-fn login_data(login: &str, passw: &str) -> LoginRequestBody {
+#[must_use]
+pub fn query_status_url(addr: &SocketAddr, query_id: &str) -> String {
+    format!("http://{addr}/monitoring/queries/{query_id}")
+}
+
+pub fn login_data(login: &str, passw: &str) -> LoginRequestBody {
     LoginRequestBody {
         data: LoginRequestData {
             client_app_id: String::new(),
@@ -138,6 +145,8 @@ fn login_data(login: &str, passw: &str) -> LoginRequestBody {
                 socket_timeout: None,
             },
             password: passw.to_string(),
+            authenticator: None,
+            token: None,
             session_parameters: HashMap::default(),
         },
     }
@@ -149,6 +158,17 @@ pub async fn login<T>(
     username: &str,
     password: &str,
 ) -> std::result::Result<(HeaderMap, T), TestHttpError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    login_request(client, &login_url(addr), login_data(username, password)).await
+}
+
+pub async fn login_request<T>(
+    client: &reqwest::Client,
+    url: &String,
+    body: LoginRequestBody,
+) -> std::result::Result<(HeaderMap, T), TestHttpError>
 where
     T: serde::de::DeserializeOwned,
 {
@@ -159,8 +179,8 @@ where
             header::CONTENT_TYPE,
             HeaderValue::from_static("application/json"),
         )]),
-        &login_url(addr),
-        json!(login_data(username, password)).to_string(),
+        url,
+        json!(body).to_string(),
     )
     .await
 }
@@ -174,6 +194,61 @@ pub async fn query<T>(
     query: &str,
     async_exec: bool,
 ) -> std::result::Result<(HeaderMap, T), TestHttpError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    query_with_bindings(
+        client,
+        addr,
+        access_token,
+        request_id,
+        retry_count,
+        query,
+        async_exec,
+        None,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn query_with_bindings<T>(
+    client: &reqwest::Client,
+    addr: &SocketAddr,
+    access_token: &str,
+    request_id: Uuid,
+    retry_count: u16,
+    query: &str,
+    async_exec: bool,
+    bindings: Option<HashMap<String, QueryBinding>>,
+) -> std::result::Result<(HeaderMap, T), TestHttpError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    query_request(
+        client,
+        addr,
+        access_token,
+        request_id,
+        retry_count,
+        QueryRequestBody {
+            sql_text: query.to_string(),
+            async_exec,
+            bindings,
+            parameters: None,
+            describe_only: false,
+        },
+    )
+    .await
+}
+
+pub async fn query_request<T>(
+    client: &reqwest::Client,
+    addr: &SocketAddr,
+    access_token: &str,
+    request_id: Uuid,
+    retry_count: u16,
+    body: QueryRequestBody,
+) -> std::result::Result<(HeaderMap, T), TestHttpError>
 where
     T: serde::de::DeserializeOwned,
 {
@@ -192,11 +267,7 @@ where
             ),
         ]),
         &query_url(addr, request_id, retry_count),
-        json!(QueryRequestBody {
-            sql_text: query.to_string(),
-            async_exec,
-        })
-        .to_string(),
+        json!(body).to_string(),
     )
     .await
 }
@@ -263,3 +334,57 @@ where
     )
     .await
 }
+
+pub async fn get_query_status<T>(
+    client: &reqwest::Client,
+    addr: &SocketAddr,
+    access_token: &str,
+    query_id: &str,
+) -> std::result::Result<(HeaderMap, T), TestHttpError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    http_req_with_headers::<T>(
+        client,
+        Method::GET,
+        HeaderMap::from_iter(vec![(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(format!("Snowflake Token=\"{access_token}\"").as_str())
+                .expect("Can't convert to HeaderValue"),
+        )]),
+        &query_status_url(addr, query_id),
+        String::new(),
+    )
+    .await
+}
+
+/// Heartbeat, token renewal and logout requests, `path` includes the query string
+pub async fn session_request<T>(
+    client: &reqwest::Client,
+    addr: &SocketAddr,
+    access_token: &str,
+    path: &str,
+    body: String,
+) -> std::result::Result<(HeaderMap, T), TestHttpError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    http_req_with_headers::<T>(
+        client,
+        Method::POST,
+        HeaderMap::from_iter(vec![
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            ),
+            (
+                header::AUTHORIZATION,
+                HeaderValue::from_str(format!("Snowflake Token=\"{access_token}\"").as_str())
+                    .expect("Can't convert to HeaderValue"),
+            ),
+        ]),
+        &format!("http://{addr}{path}"),
+        body,
+    )
+    .await
+}
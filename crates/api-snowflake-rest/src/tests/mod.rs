@@ -5,9 +5,17 @@ pub mod sql_macro;
 pub mod test_rest_quick_sqls;
 cfg_if::cfg_if! {
     if #[cfg(feature = "default-server")] {
+        pub mod test_bindings;
         pub mod test_gzip_encoding;
         pub mod test_generic_sqls;
+        pub mod test_login;
+        pub mod test_multi_statement;
+        pub mod test_query_status;
         pub mod test_requests_abort;
+        pub mod test_result_chunks;
+        pub mod test_session;
+        pub mod test_sql_api;
+        pub mod test_stage_transfer;
         pub use crate::server::test_server::run_test_rest_api_server;
     } else {
         pub mod external_server;
@@ -0,0 +1,99 @@
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::models::{
+        BindingValue, JsonResponse, LoginResponse, QueryBinding, QueryRequestBody,
+    };
+    use crate::server::test_server::run_test_rest_api_server;
+    use crate::tests::client::{login, query_request, query_with_bindings};
+    use crate::tests::sql_macro::JSON;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    fn binding(binding_type: &str, value: Option<&str>) -> QueryBinding {
+        QueryBinding {
+            binding_type: binding_type.to_string(),
+            value: BindingValue::Single(value.map(ToString::to_string)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_query_bindings() {
+        let addr = run_test_rest_api_server(JSON).await;
+        let client = reqwest::Client::new();
+        let (_headers, login_res) = login::<LoginResponse>(&client, &addr, "embucket", "embucket")
+            .await
+            .expect("Failed to login");
+        let access_token = login_res.data.map_or_else(String::new, |data| data.token);
+
+        let bindings = HashMap::from([
+            ("1".to_string(), binding("FIXED", Some("41"))),
+            ("2".to_string(), binding("TEXT", Some("it's"))),
+            ("3".to_string(), binding("BOOLEAN", None)),
+        ]);
+        let (_headers, res) = query_with_bindings::<JsonResponse>(
+            &client,
+            &addr,
+            &access_token,
+            Uuid::new_v4(),
+            0,
+            "SELECT ? + 1 AS n, ? AS s, ? IS NULL AS b",
+            false,
+            Some(bindings),
+        )
+        .await
+        .expect("Failed to run query");
+
+        let rows = res.data.and_then(|data| data.row_set).expect("No rows");
+        let row = rows[0]
+            .0
+            .iter()
+            .map(|value| {
+                value
+                    .as_str()
+                    .map_or_else(|| value.to_string(), ToString::to_string)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(row, ["42", "it's", "true"]);
+    }
+
+    #[tokio::test]
+    async fn test_query_describe_only() {
+        let addr = run_test_rest_api_server(JSON).await;
+        let client = reqwest::Client::new();
+        let (_headers, login_res) = login::<LoginResponse>(&client, &addr, "embucket", "embucket")
+            .await
+            .expect("Failed to login");
+        let access_token = login_res.data.map_or_else(String::new, |data| data.token);
+
+        let (_headers, res) = query_request::<serde_json::Value>(
+            &client,
+            &addr,
+            &access_token,
+            Uuid::new_v4(),
+            0,
+            QueryRequestBody {
+                sql_text: "SELECT CAST(? AS DECIMAL(10, 2)) AS d, 'a' AS s".to_string(),
+                async_exec: false,
+                bindings: None,
+                parameters: None,
+                describe_only: true,
+            },
+        )
+        .await
+        .expect("Failed to describe query");
+
+        assert_eq!(res["success"], true);
+        let data = &res["data"];
+        assert_eq!(data["rowset"], serde_json::json!([]));
+        let row_type = data["rowtype"].as_array().expect("No rowtype");
+        assert_eq!(row_type.len(), 2);
+        assert_eq!(row_type[0]["name"], "d");
+        assert_eq!(row_type[0]["type"], "fixed");
+        assert_eq!(row_type[0]["precision"], 10);
+        assert_eq!(row_type[0]["scale"], 2);
+        assert_eq!(row_type[0]["nullable"], true);
+        assert_eq!(row_type[1]["name"], "s");
+        assert_eq!(row_type[1]["type"], "text");
+    }
+}
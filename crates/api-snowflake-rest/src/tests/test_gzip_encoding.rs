@@ -28,6 +28,9 @@ mod tests {
         let query_request = QueryRequestBody {
             sql_text: "SELECT 1;".to_string(),
             async_exec: false,
+            bindings: None,
+            parameters: None,
+            describe_only: false,
         };
 
         let query_compressed_bytes = make_bytes_body(&query_request);
@@ -72,6 +75,8 @@ mod tests {
                     socket_timeout: None,
                 },
                 password: "embucket".to_string(),
+                authenticator: None,
+                token: None,
                 session_parameters: HashMap::default(),
             },
         };
@@ -0,0 +1,212 @@
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::models::{JsonResponse, LoginResponse, SessionInfo, SessionParameter};
+    use crate::server::server_models::Config;
+    use crate::server::test_server::{
+        run_test_rest_api_server, run_test_rest_api_server_with_config,
+    };
+    use crate::tests::client::{login, login_data, login_request, login_url, query};
+    use crate::tests::sql_macro::JSON;
+    use core_executor::utils::Config as UtilsConfig;
+    use core_metastore::public_key_fingerprint;
+    use jsonwebtoken::{Algorithm, EncodingKey, Header, encode};
+    use reqwest::StatusCode;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    const RSA_PRIVATE_KEY: &str = include_str!("keys/rsa_key.p8");
+    const RSA_PUBLIC_KEY: &str = include_str!("keys/rsa_key.pub");
+
+    /// The JWT the connectors issue for key-pair authentication
+    fn key_pair_jwt(user: &str, exp: i64) -> String {
+        let fingerprint = public_key_fingerprint(RSA_PUBLIC_KEY).unwrap();
+        let sub = format!("EMBUCKET.{}", user.to_uppercase());
+        let now = time::OffsetDateTime::now_utc().unix_timestamp();
+        let claims = json!({
+            "iss": format!("{sub}.{fingerprint}"),
+            "sub": sub,
+            "iat": now,
+            "exp": now + exp,
+        });
+        let key = EncodingKey::from_rsa_pem(RSA_PRIVATE_KEY.as_bytes()).unwrap();
+        encode(&Header::new(Algorithm::RS256), &claims, &key).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_login_session_parameters() {
+        let addr = run_test_rest_api_server(JSON).await;
+        let client = reqwest::Client::new();
+        let mut body = login_data("embucket", "embucket");
+        body.data.session_parameters.extend([
+            ("QUERY_TAG".to_string(), json!("etl")),
+            ("TIMEZONE".to_string(), json!("America/Los_Angeles")),
+        ]);
+        let url = format!(
+            "http://{addr}/session/v1/login-request?request_id=1&databaseName=embucket&schemaName=staging&warehouse=wh&roleName=analyst"
+        );
+        let (_headers, login_res) = login_request::<LoginResponse>(&client, &url, body)
+            .await
+            .expect("Failed to login");
+        let data = login_res.data.expect("No login data");
+        assert_eq!(
+            data.parameters,
+            [
+                SessionParameter {
+                    name: "QUERY_TAG".to_string(),
+                    value: json!("etl"),
+                },
+                SessionParameter {
+                    name: "TIMEZONE".to_string(),
+                    value: json!("America/Los_Angeles"),
+                },
+            ]
+        );
+        assert_eq!(
+            data.session_info,
+            SessionInfo {
+                database_name: Some("embucket".to_string()),
+                schema_name: Some("staging".to_string()),
+                warehouse_name: Some("wh".to_string()),
+                role_name: Some("analyst".to_string()),
+            }
+        );
+
+        // The session is created with the login defaults
+        let (_headers, res) = query::<JsonResponse>(
+            &client,
+            &addr,
+            &data.token,
+            Uuid::new_v4(),
+            0,
+            "SELECT CURRENT_SCHEMA() AS s, CURRENT_WAREHOUSE() AS w",
+            false,
+        )
+        .await
+        .expect("Failed to run query");
+        let rows = res.data.and_then(|data| data.row_set).expect("No rows");
+        let row = rows[0]
+            .0
+            .iter()
+            .map(|value| value.as_str().map(ToString::to_string))
+            .collect::<Vec<_>>();
+        assert_eq!(row, [Some("staging".to_string()), Some("wh".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_login_created_user() {
+        let addr = run_test_rest_api_server(JSON).await;
+        let client = reqwest::Client::new();
+        let (_headers, login_res) = login::<LoginResponse>(&client, &addr, "embucket", "embucket")
+            .await
+            .expect("Failed to login");
+        let token = login_res.data.expect("No login data").token;
+        for sql in [
+            "CREATE USER alice PASSWORD = 'secret' DEFAULT_ROLE = analyst DEFAULT_NAMESPACE = 'embucket.public'",
+            "CREATE USER bob PASSWORD = 'secret' DISABLED = TRUE",
+        ] {
+            query::<JsonResponse>(&client, &addr, &token, Uuid::new_v4(), 0, sql, false)
+                .await
+                .expect("Failed to create user");
+        }
+
+        // Login names are case-insensitive, the session gets the user defaults
+        let (_headers, login_res) = login::<LoginResponse>(&client, &addr, "ALICE", "secret")
+            .await
+            .expect("Failed to login");
+        let session_info = login_res.data.expect("No login data").session_info;
+        assert_eq!(session_info.database_name.as_deref(), Some("embucket"));
+        assert_eq!(session_info.schema_name.as_deref(), Some("public"));
+        assert_eq!(session_info.role_name.as_deref(), Some("analyst"));
+
+        for (user, password) in [("alice", "wrong"), ("bob", "secret"), ("carol", "secret")] {
+            let err = login::<LoginResponse>(&client, &addr, user, password)
+                .await
+                .expect_err("Login should fail");
+            assert_eq!(err.status, StatusCode::UNAUTHORIZED);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_auth_required_without_startup_user() {
+        // Auth is only skipped when it's disabled explicitly
+        let app_cfg = Config::new(JSON).expect("Failed to create config");
+        let addr = run_test_rest_api_server_with_config(app_cfg, UtilsConfig::default()).await;
+        let client = reqwest::Client::new();
+        let err = query::<JsonResponse>(&client, &addr, "", Uuid::new_v4(), 0, "SELECT 1", false)
+            .await
+            .expect_err("Query should require auth");
+        assert_eq!(err.status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_login_key_pair_and_access_token() {
+        let addr = run_test_rest_api_server(JSON).await;
+        let client = reqwest::Client::new();
+        let (_headers, login_res) = login::<LoginResponse>(&client, &addr, "embucket", "embucket")
+            .await
+            .expect("Failed to login");
+        let token = login_res.data.expect("No login data").token;
+        let run = |sql: String| {
+            let client = client.clone();
+            let token = token.clone();
+            async move {
+                query::<JsonResponse>(&client, &addr, &token, Uuid::new_v4(), 0, &sql, false)
+                    .await
+                    .expect("Failed to run query")
+                    .1
+            }
+        };
+        run("CREATE USER ci".to_string()).await;
+        run("CREATE ROLE analyst".to_string()).await;
+        run("GRANT ROLE analyst TO USER ci".to_string()).await;
+        run(format!(
+            "ALTER USER ci SET RSA_PUBLIC_KEY = '{RSA_PUBLIC_KEY}'"
+        ))
+        .await;
+        let res = run(
+            "ALTER USER ci ADD PROGRAMMATIC ACCESS TOKEN deploy ROLE_RESTRICTION = analyst"
+                .to_string(),
+        )
+        .await;
+        let rows = res.data.and_then(|data| data.row_set).expect("No rows");
+        let secret = rows[0].0[1].as_str().expect("No token secret").to_string();
+
+        let url = login_url(&addr);
+        let login_with = |authenticator: &str, token: String| {
+            let mut body = login_data("ci", "");
+            body.data.authenticator = Some(authenticator.to_string());
+            body.data.token = Some(token);
+            login_request::<LoginResponse>(&client, &url, body)
+        };
+        login_with("SNOWFLAKE_JWT", key_pair_jwt("ci", 60))
+            .await
+            .expect("Failed to login with key pair");
+        let (_headers, login_res) = login_with("PROGRAMMATIC_ACCESS_TOKEN", secret.clone())
+            .await
+            .expect("Failed to login with access token");
+        let session_info = login_res.data.expect("No login data").session_info;
+        assert_eq!(session_info.role_name.as_deref(), Some("analyst"));
+        // Access tokens are also accepted as passwords
+        login::<LoginResponse>(&client, &addr, "ci", &secret)
+            .await
+            .expect("Failed to login with access token as password");
+
+        for (authenticator, token) in [
+            ("SNOWFLAKE_JWT", key_pair_jwt("ci", -120)),
+            ("SNOWFLAKE_JWT", key_pair_jwt("embucket", 60)),
+            ("PROGRAMMATIC_ACCESS_TOKEN", "wrong".to_string()),
+        ] {
+            let err = login_with(authenticator, token)
+                .await
+                .expect_err("Login should fail");
+            assert_eq!(err.status, StatusCode::UNAUTHORIZED);
+        }
+
+        run("ALTER USER ci REMOVE PROGRAMMATIC ACCESS TOKEN deploy".to_string()).await;
+        let err = login_with("PROGRAMMATIC_ACCESS_TOKEN", secret)
+            .await
+            .expect_err("Login with a removed token should fail");
+        assert_eq!(err.status, StatusCode::UNAUTHORIZED);
+    }
+}
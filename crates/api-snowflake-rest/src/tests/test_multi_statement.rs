@@ -0,0 +1,161 @@
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::models::{JsonResponse, LoginResponse, QueryRequestBody};
+    use crate::server::server_models::Config;
+    use crate::server::test_server::{
+        run_test_rest_api_server, run_test_rest_api_server_with_config,
+    };
+    use crate::tests::client::{get_query_result, login, query_request};
+    use crate::tests::sql_macro::{DEMO_PASSWORD, DEMO_USER, JSON};
+    use core_executor::utils::Config as UtilsConfig;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    fn multi_statement(sql_text: &str, count: serde_json::Value) -> QueryRequestBody {
+        QueryRequestBody {
+            sql_text: sql_text.to_string(),
+            async_exec: false,
+            bindings: None,
+            parameters: Some(HashMap::from([(
+                "MULTI_STATEMENT_COUNT".to_string(),
+                count,
+            )])),
+            describe_only: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multi_statement_query() {
+        let addr = run_test_rest_api_server(JSON).await;
+        let client = reqwest::Client::new();
+        let (_headers, login_res) = login::<LoginResponse>(&client, &addr, "embucket", "embucket")
+            .await
+            .expect("Failed to login");
+        let access_token = login_res.data.map_or_else(String::new, |data| data.token);
+
+        let (_headers, res) = query_request::<JsonResponse>(
+            &client,
+            &addr,
+            &access_token,
+            Uuid::new_v4(),
+            0,
+            multi_statement("SET x = 41; SELECT $x + 1 AS x;", json!("2")),
+        )
+        .await
+        .expect("Failed to run statements");
+        assert!(res.success, "{:?}", res.message);
+        let data = res.data.expect("No data");
+        assert_eq!(data.result_types.as_deref(), Some("16384,4096"));
+        let result_ids = data.result_ids.expect("No result ids");
+        let result_ids = result_ids.split(',').collect::<Vec<_>>();
+        assert_eq!(result_ids.len(), 2);
+        assert_ne!(data.query_id.as_deref(), Some(result_ids[1]));
+
+        // Statement results are fetched by their ids
+        let (_headers, res) =
+            get_query_result::<JsonResponse>(&client, &addr, &access_token, result_ids[1])
+                .await
+                .expect("Failed to get statement result");
+        let rows = res.data.and_then(|data| data.row_set).expect("No rows");
+        assert_eq!(rows[0].0[0].as_str(), Some("42"));
+
+        // Any number of statements
+        let (_headers, res) = query_request::<JsonResponse>(
+            &client,
+            &addr,
+            &access_token,
+            Uuid::new_v4(),
+            0,
+            multi_statement("SELECT 1; SELECT 2; SELECT 3", json!(0)),
+        )
+        .await
+        .expect("Failed to run statements");
+        let result_ids = res.data.and_then(|data| data.result_ids).expect("No ids");
+        assert_eq!(result_ids.split(',').count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_multi_statement_async_query() {
+        // History records keep a single row, statement results are kept in full
+        let app_cfg = Config::new(JSON)
+            .expect("Failed to create config")
+            .with_demo_credentials(DEMO_USER.to_string(), DEMO_PASSWORD.to_string());
+        let addr = run_test_rest_api_server_with_config(
+            app_cfg,
+            UtilsConfig::default().with_query_history_rows_limit(1),
+        )
+        .await;
+        let client = reqwest::Client::new();
+        let (_headers, login_res) =
+            login::<LoginResponse>(&client, &addr, DEMO_USER, DEMO_PASSWORD)
+                .await
+                .expect("Failed to login");
+        let access_token = login_res.data.map_or_else(String::new, |data| data.token);
+
+        let (_headers, res) = query_request::<JsonResponse>(
+            &client,
+            &addr,
+            &access_token,
+            Uuid::new_v4(),
+            0,
+            QueryRequestBody {
+                async_exec: true,
+                ..multi_statement("SELECT 1; SELECT * FROM VALUES (1), (2), (3)", json!(2))
+            },
+        )
+        .await
+        .expect("Failed to submit statements");
+        assert!(res.success, "{:?}", res.message);
+        let query_id = res
+            .data
+            .and_then(|data| data.query_id)
+            .expect("No query id");
+
+        let (_headers, res) =
+            get_query_result::<JsonResponse>(&client, &addr, &access_token, &query_id)
+                .await
+                .expect("Failed to get result");
+        let data = res.data.expect("No data");
+        assert_eq!(data.result_types.as_deref(), Some("4096,4096"));
+        let result_ids = data.result_ids.expect("No result ids");
+        let result_ids = result_ids.split(',').collect::<Vec<_>>();
+
+        let (_headers, res) =
+            get_query_result::<JsonResponse>(&client, &addr, &access_token, result_ids[1])
+                .await
+                .expect("Failed to get statement result");
+        let rows = res.data.and_then(|data| data.row_set).expect("No rows");
+        assert_eq!(rows.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_multi_statement_count_mismatch() {
+        let addr = run_test_rest_api_server(JSON).await;
+        let client = reqwest::Client::new();
+        let (_headers, login_res) = login::<LoginResponse>(&client, &addr, "embucket", "embucket")
+            .await
+            .expect("Failed to login");
+        let access_token = login_res.data.map_or_else(String::new, |data| data.token);
+
+        let (_headers, res) = query_request::<JsonResponse>(
+            &client,
+            &addr,
+            &access_token,
+            Uuid::new_v4(),
+            0,
+            multi_statement("SELECT 1; SELECT 2", json!(3)),
+        )
+        .await
+        .expect("Failed to send statements");
+        assert!(!res.success);
+        assert!(
+            res.message
+                .as_deref()
+                .is_some_and(|message| message.contains("did not match")),
+            "{:?}",
+            res.message
+        );
+    }
+}
@@ -0,0 +1,139 @@
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::models::{JsonResponse, LoginResponse, QueryMonitoringInfo, QueryStatusResponse};
+    use crate::server::test_server::run_test_rest_api_server;
+    use crate::tests::client::{get_query_result, get_query_status, login, query};
+    use crate::tests::sql_macro::{JSON, query_id_from_snapshot};
+    use std::net::SocketAddr;
+    use uuid::Uuid;
+
+    async fn status(
+        client: &reqwest::Client,
+        addr: &SocketAddr,
+        access_token: &str,
+        query_id: &str,
+    ) -> QueryMonitoringInfo {
+        let (_headers, res) =
+            get_query_status::<QueryStatusResponse>(client, addr, access_token, query_id)
+                .await
+                .expect("Failed to get query status");
+        assert!(res.success);
+        res.data.expect("No status data").queries.remove(0)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_query_status() {
+        let addr = run_test_rest_api_server(JSON).await;
+        let client = reqwest::Client::new();
+
+        let (_headers, login_res) = login::<LoginResponse>(&client, &addr, "embucket", "embucket")
+            .await
+            .expect("Failed to login");
+        let access_token = login_res.data.map_or_else(String::new, |data| data.token);
+
+        let sql = "SELECT SLEEP(1)";
+        let (_headers, res) =
+            query::<JsonResponse>(&client, &addr, &access_token, Uuid::new_v4(), 0, sql, true)
+                .await
+                .expect("Failed to run query");
+        let query_id = query_id_from_snapshot(&res).expect("Failed to get query ID");
+
+        // Status is returned without waiting for the query to finish
+        let running = status(&client, &addr, &access_token, &query_id).await;
+        assert_eq!(running.status, "RUNNING");
+        assert_eq!(running.id, query_id);
+        assert_eq!(running.sql_text, sql);
+        assert_eq!(running.error_code, None);
+        assert!(running.progress.is_some());
+
+        let (_headers, _res) =
+            get_query_result::<JsonResponse>(&client, &addr, &access_token, &query_id)
+                .await
+                .expect("Failed to get query result");
+        let finished = status(&client, &addr, &access_token, &query_id).await;
+        assert_eq!(finished.status, "SUCCESS");
+        assert_eq!(finished.progress, None);
+        assert!(finished.total_duration >= 1000);
+        assert_eq!(
+            finished.end_time - finished.start_time,
+            finished.total_duration
+        );
+
+        let (_headers, res) = query::<JsonResponse>(
+            &client,
+            &addr,
+            &access_token,
+            Uuid::new_v4(),
+            0,
+            "SELECT * FROM missing_table",
+            true,
+        )
+        .await
+        .expect("Failed to run query");
+        let query_id = query_id_from_snapshot(&res).expect("Failed to get query ID");
+        let (_headers, _res) =
+            get_query_result::<JsonResponse>(&client, &addr, &access_token, &query_id)
+                .await
+                .expect("Failed to get query result");
+        let failed = status(&client, &addr, &access_token, &query_id).await;
+        assert_eq!(failed.status, "FAILED_WITH_ERROR");
+        assert!(failed.error_code.is_some());
+        assert!(failed.error_message.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_query_status_of_other_user() {
+        let addr = run_test_rest_api_server(JSON).await;
+        let client = reqwest::Client::new();
+
+        let (_headers, login_res) = login::<LoginResponse>(&client, &addr, "embucket", "embucket")
+            .await
+            .expect("Failed to login");
+        let access_token = login_res.data.map_or_else(String::new, |data| data.token);
+        let sql = "CREATE USER alice PASSWORD = 'secret'";
+        let (_headers, res) =
+            query::<JsonResponse>(&client, &addr, &access_token, Uuid::new_v4(), 0, sql, false)
+                .await
+                .expect("Failed to create user");
+        let query_id = query_id_from_snapshot(&res).expect("Failed to get query ID");
+
+        // Only the user who ran the query and admins get its status
+        let (_headers, login_res) = login::<LoginResponse>(&client, &addr, "alice", "secret")
+            .await
+            .expect("Failed to login");
+        let alice_token = login_res.data.map_or_else(String::new, |data| data.token);
+        let (_headers, res) =
+            get_query_status::<JsonResponse>(&client, &addr, &alice_token, &query_id)
+                .await
+                .expect("Failed to get query status");
+        assert!(!res.success);
+        let (_headers, res) =
+            get_query_result::<JsonResponse>(&client, &addr, &alice_token, &query_id)
+                .await
+                .expect("Failed to get query result");
+        assert!(!res.success);
+
+        let sql = "SELECT 1";
+        let (_headers, res) =
+            query::<JsonResponse>(&client, &addr, &alice_token, Uuid::new_v4(), 0, sql, false)
+                .await
+                .expect("Failed to run query");
+        let query_id = query_id_from_snapshot(&res).expect("Failed to get query ID");
+        assert_eq!(
+            status(&client, &addr, &alice_token, &query_id).await.status,
+            "SUCCESS"
+        );
+        assert_eq!(
+            status(&client, &addr, &access_token, &query_id)
+                .await
+                .status,
+            "SUCCESS"
+        );
+        let (_headers, res) =
+            get_query_result::<JsonResponse>(&client, &addr, &access_token, &query_id)
+                .await
+                .expect("Failed to get query result");
+        assert!(res.success);
+    }
+}
@@ -0,0 +1,103 @@
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::models::{JsonResponse, LoginResponse};
+    use crate::server::server_models::Config;
+    use crate::server::test_server::run_test_rest_api_server_with_config;
+    use crate::tests::client::{login, query};
+    use crate::tests::sql_macro::{DEMO_PASSWORD, DEMO_USER, JSON};
+    use core_executor::utils::Config as UtilsConfig;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_query_result_chunks() {
+        let app_cfg = Config::new(JSON)
+            .expect("Failed to create config")
+            .with_demo_credentials(DEMO_USER.to_string(), DEMO_PASSWORD.to_string())
+            .with_result_chunk_rows(Some(2));
+        let addr = run_test_rest_api_server_with_config(app_cfg, UtilsConfig::default()).await;
+        let client = reqwest::Client::new();
+        let (_headers, login_res) =
+            login::<LoginResponse>(&client, &addr, DEMO_USER, DEMO_PASSWORD)
+                .await
+                .expect("Failed to login");
+        let access_token = login_res.data.map_or_else(String::new, |data| data.token);
+
+        let (_headers, res) = query::<JsonResponse>(
+            &client,
+            &addr,
+            &access_token,
+            Uuid::new_v4(),
+            0,
+            "SELECT * FROM VALUES (1), (2), (3), (4), (5)",
+            false,
+        )
+        .await
+        .expect("Failed to run query");
+        let data = res.data.expect("No data");
+        // Only the first chunk is inlined
+        assert_eq!(data.row_set.expect("No rows").len(), 2);
+        let chunks = data.chunks.expect("No chunks");
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|chunk| chunk.row_count)
+                .collect::<Vec<_>>(),
+            [2, 1]
+        );
+        let chunk_headers = data.chunk_headers.expect("No chunk headers");
+
+        let mut rows = Vec::new();
+        for chunk in &chunks {
+            let mut request = client.get(&chunk.url);
+            for (name, value) in &chunk_headers {
+                request = request.header(name, value);
+            }
+            let response = request.send().await.unwrap();
+            assert_eq!(
+                response.headers().get(reqwest::header::CONTENT_ENCODING),
+                Some(&reqwest::header::HeaderValue::from_static("gzip"))
+            );
+            let compressed = response.bytes().await.unwrap();
+            assert_eq!(compressed.len(), chunk.compressed_size);
+            let mut body = String::new();
+            GzDecoder::new(compressed.as_ref())
+                .read_to_string(&mut body)
+                .unwrap();
+            assert_eq!(body.len(), chunk.uncompressed_size);
+            let chunk_rows: Vec<Vec<serde_json::Value>> =
+                serde_json::from_str(&format!("[{body}]")).unwrap();
+            rows.extend(chunk_rows);
+        }
+        let rows = rows
+            .iter()
+            .flatten()
+            .map(|value| {
+                value
+                    .as_str()
+                    .map_or_else(|| value.to_string(), ToString::to_string)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(rows, ["3", "4", "5"]);
+
+        // Chunks are not served without the session's authorization
+        let status = client.get(&chunks[0].url).send().await.unwrap().status();
+        assert_eq!(status, reqwest::StatusCode::UNAUTHORIZED);
+
+        // Chunks are downloaded from the configured address of the server
+        assert!(chunks[0].url.starts_with(&format!("http://{addr}/")));
+
+        // A missing chunk is reported as a missing query result
+        let mut request = client.get(chunks[0].url.replace("/chunks/0", "/chunks/9"));
+        for (name, value) in &chunk_headers {
+            request = request.header(name, value);
+        }
+        let res: JsonResponse = request.send().await.unwrap().json().await.unwrap();
+        assert_eq!(
+            res.data.and_then(|data| data.sql_state).as_deref(),
+            Some("42S01")
+        );
+    }
+}
@@ -0,0 +1,92 @@
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::models::{JsonResponse, LoginResponse, RenewSessionRequest, RenewSessionResponse};
+    use crate::server::test_server::run_test_rest_api_server;
+    use crate::tests::client::{login, query, session_request};
+    use crate::tests::sql_macro::JSON;
+    use reqwest::StatusCode;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_session_heartbeat_renew_and_close() {
+        let addr = run_test_rest_api_server(JSON).await;
+        let client = reqwest::Client::new();
+        let (_headers, login_res) = login::<LoginResponse>(&client, &addr, "embucket", "embucket")
+            .await
+            .expect("Failed to login");
+        let data = login_res.data.expect("No login data");
+        let token = data.token;
+        assert_eq!(data.master_token.as_deref(), Some(token.as_str()));
+
+        let (_headers, res) = session_request::<JsonResponse>(
+            &client,
+            &addr,
+            &token,
+            "/session/heartbeat?requestId=1",
+            String::new(),
+        )
+        .await
+        .expect("Failed to send heartbeat");
+        assert!(res.success);
+
+        let renew = |old_session_token: &str| {
+            json!(RenewSessionRequest {
+                old_session_token: old_session_token.to_string(),
+                request_type: "RENEW".to_string(),
+            })
+            .to_string()
+        };
+        let (_headers, res) = session_request::<RenewSessionResponse>(
+            &client,
+            &addr,
+            &token,
+            "/session/token-request?requestId=2",
+            renew(&token),
+        )
+        .await
+        .expect("Failed to renew session token");
+        let renewed = res.data.expect("No renewal data");
+        assert_eq!(renewed.session_token, token);
+        assert_eq!(renewed.master_token, token);
+        let err = session_request::<RenewSessionResponse>(
+            &client,
+            &addr,
+            &token,
+            "/session/token-request?requestId=3",
+            renew(&Uuid::new_v4().to_string()),
+        )
+        .await
+        .expect_err("Renewing another session should fail");
+        assert_eq!(err.status, StatusCode::UNAUTHORIZED);
+
+        let (_headers, res) = session_request::<JsonResponse>(
+            &client,
+            &addr,
+            &token,
+            "/session?delete=true&requestId=4",
+            String::new(),
+        )
+        .await
+        .expect("Failed to close session");
+        assert!(res.success);
+
+        // The session is gone, its token is no longer accepted
+        let err =
+            query::<JsonResponse>(&client, &addr, &token, Uuid::new_v4(), 0, "SELECT 1", false)
+                .await
+                .expect_err("Query in a closed session should fail");
+        assert_eq!(err.status, StatusCode::UNAUTHORIZED);
+        let err = session_request::<JsonResponse>(
+            &client,
+            &addr,
+            &token,
+            "/session/heartbeat?requestId=5",
+            String::new(),
+        )
+        .await
+        .expect_err("Heartbeat of a closed session should fail");
+        assert_eq!(err.status, StatusCode::UNAUTHORIZED);
+    }
+}
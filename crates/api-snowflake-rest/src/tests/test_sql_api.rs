@@ -0,0 +1,155 @@
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::models::{JsonResponse, LoginResponse, PartitionInfo, StatementResponse};
+    use crate::server::server_models::Config;
+    use crate::server::test_server::run_test_rest_api_server_with_config;
+    use crate::tests::client::{login, query};
+    use crate::tests::sql_macro::{DEMO_PASSWORD, DEMO_USER, JSON};
+    use core_executor::utils::Config as UtilsConfig;
+    use reqwest::StatusCode;
+    use serde_json::{Value, json};
+    use std::net::SocketAddr;
+    use uuid::Uuid;
+
+    /// Programmatic access token of the demo user
+    async fn access_token(client: &reqwest::Client, addr: &SocketAddr) -> String {
+        let (_headers, login_res) = login::<LoginResponse>(client, addr, DEMO_USER, DEMO_PASSWORD)
+            .await
+            .expect("Failed to login");
+        let session_token = login_res.data.map_or_else(String::new, |data| data.token);
+        let (_headers, res) = query::<JsonResponse>(
+            client,
+            addr,
+            &session_token,
+            Uuid::new_v4(),
+            0,
+            "ALTER USER embucket ADD PROGRAMMATIC ACCESS TOKEN sql_api",
+            false,
+        )
+        .await
+        .expect("Failed to add access token");
+        let rows = res.data.and_then(|data| data.row_set).expect("No rows");
+        rows[0].0[1].as_str().expect("No token secret").to_string()
+    }
+
+    async fn send(
+        request: reqwest::RequestBuilder,
+        token: &str,
+    ) -> (StatusCode, StatementResponse) {
+        let res = request
+            .bearer_auth(token)
+            .header(
+                "X-Snowflake-Authorization-Token-Type",
+                "PROGRAMMATIC_ACCESS_TOKEN",
+            )
+            .send()
+            .await
+            .unwrap();
+        (res.status(), res.json().await.unwrap())
+    }
+
+    fn data(response: &StatementResponse) -> Value {
+        json!(response.data)
+    }
+
+    #[tokio::test]
+    async fn test_sql_api_statements() {
+        let app_cfg = Config::new(JSON)
+            .expect("Failed to create config")
+            .with_demo_credentials(DEMO_USER.to_string(), DEMO_PASSWORD.to_string())
+            .with_result_chunk_rows(Some(2));
+        // Results are served in full, not as recorded to the query history
+        let addr = run_test_rest_api_server_with_config(
+            app_cfg,
+            UtilsConfig::default().with_query_history_rows_limit(1),
+        )
+        .await;
+        let client = reqwest::Client::new();
+        let token = access_token(&client, &addr).await;
+        let statements_url = format!("http://{addr}/api/v2/statements");
+
+        let (status, res) = send(
+            client.post(&statements_url).json(&json!({
+                "statement": "SELECT * FROM VALUES (1, 'a'), (2, NULL), (3, 'c')",
+            })),
+            &token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(res.code.as_deref(), Some("090001"));
+        let metadata = res.result_set_meta_data.as_ref().expect("No metadata");
+        assert_eq!(metadata.num_rows, 3);
+        assert_eq!(metadata.format, "jsonv2");
+        assert_eq!(metadata.row_type.len(), 2);
+        assert_eq!(
+            metadata
+                .partition_info
+                .iter()
+                .map(|PartitionInfo { row_count, .. }| *row_count)
+                .collect::<Vec<_>>(),
+            [2, 1]
+        );
+        // Values are strings, in the first partition
+        assert_eq!(data(&res), json!([["1", "a"], ["2", null]]));
+
+        let handle = res.statement_handle.expect("No statement handle");
+        let (status, res) = send(
+            client.get(format!("{statements_url}/{handle}?partition=1")),
+            &token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(res.result_set_meta_data.is_none());
+        assert_eq!(data(&res), json!([["3", "c"]]));
+
+        // Asynchronous statements are polled by their handle
+        let (status, res) = send(
+            client
+                .post(format!("{statements_url}?async=true"))
+                .json(&json!({
+                    "statement": "SELECT ? AS answer",
+                    "bindings": {"1": {"type": "FIXED", "value": "42"}},
+                })),
+            &token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(res.code.as_deref(), Some("333334"));
+        let status_url = format!(
+            "http://{addr}{}",
+            res.statement_status_url.expect("No status URL")
+        );
+        let res = loop {
+            let (status, res) = send(client.get(&status_url), &token).await;
+            if status == StatusCode::OK {
+                break res;
+            }
+            assert_eq!(status, StatusCode::ACCEPTED);
+        };
+        assert_eq!(data(&res), json!([["42"]]));
+
+        let (status, res) = send(
+            client.post(&statements_url).json(&json!({
+                "statement": "SELECT * FROM embucket.public.missing",
+            })),
+            &token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(res.message.is_some());
+
+        // Requests need a valid bearer token
+        let (status, _res) = send(
+            client
+                .post(&statements_url)
+                .json(&json!({"statement": "SELECT 1"})),
+            "invalid",
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _res) =
+            send(client.get(format!("{statements_url}/{handle}")), "invalid").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
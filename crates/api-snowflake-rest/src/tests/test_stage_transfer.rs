@@ -0,0 +1,115 @@
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use crate::models::{JsonResponse, LoginResponse, ResponseData};
+    use crate::server::test_server::run_test_rest_api_server;
+    use crate::tests::client::{login, query};
+    use crate::tests::sql_macro::{DEMO_PASSWORD, DEMO_USER, JSON};
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use std::net::SocketAddr;
+    use uuid::Uuid;
+
+    async fn run(
+        client: &reqwest::Client,
+        addr: &SocketAddr,
+        access_token: &str,
+        sql: &str,
+    ) -> ResponseData {
+        let (_headers, res) =
+            query::<JsonResponse>(client, addr, access_token, Uuid::new_v4(), 0, sql, false)
+                .await
+                .expect("Failed to run query");
+        assert!(res.success, "{sql} failed: {:?}", res.message);
+        res.data.expect("No data")
+    }
+
+    #[tokio::test]
+    async fn test_put_get_stage_files() {
+        let addr = run_test_rest_api_server(JSON).await;
+        let client = reqwest::Client::new();
+        let (_headers, login_res) =
+            login::<LoginResponse>(&client, &addr, DEMO_USER, DEMO_PASSWORD)
+                .await
+                .expect("Failed to login");
+        let access_token = login_res.data.map_or_else(String::new, |data| data.token);
+
+        run(
+            &client,
+            &addr,
+            &access_token,
+            "CREATE STAGE embucket.public.uploads",
+        )
+        .await;
+        let put = run(
+            &client,
+            &addr,
+            &access_token,
+            "PUT file:///tmp/data.csv @embucket.public.uploads/in",
+        )
+        .await
+        .file_transfer
+        .expect("No file transfer");
+        assert_eq!(put.command, "UPLOAD");
+        assert_eq!(put.src_locations, ["/tmp/data.csv"]);
+        assert_eq!(put.stage_info.location, "embucket.public.uploads/in/");
+        let upload_url = put.stage_info.presigned_url.expect("No upload URL");
+        let status = client
+            .put(&upload_url)
+            .body("a,b\n1,2\n")
+            .send()
+            .await
+            .unwrap()
+            .status();
+        assert_eq!(status, reqwest::StatusCode::OK);
+
+        let listed = run(
+            &client,
+            &addr,
+            &access_token,
+            "LIST @embucket.public.uploads",
+        )
+        .await
+        .row_set
+        .expect("No rows");
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].0[0], "uploads/in/data.csv.gz");
+
+        let get = run(
+            &client,
+            &addr,
+            &access_token,
+            "GET @embucket.public.uploads/in file:///tmp/out/",
+        )
+        .await
+        .file_transfer
+        .expect("No file transfer");
+        assert_eq!(get.command, "DOWNLOAD");
+        assert_eq!(get.src_locations, ["in/data.csv.gz"]);
+        assert_eq!(get.local_location.as_deref(), Some("/tmp/out/"));
+        let download_urls = get.presigned_urls.expect("No download URLs");
+        let body = client
+            .get(&download_urls[0])
+            .send()
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        // Uploaded files are compressed
+        let mut content = String::new();
+        GzDecoder::new(body.as_ref())
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "a,b\n1,2\n");
+
+        // Unknown tokens are rejected
+        let status = client
+            .get(format!("http://{addr}/stage-transfers/{}", Uuid::new_v4()))
+            .send()
+            .await
+            .unwrap()
+            .status();
+        assert_eq!(status, reqwest::StatusCode::NOT_FOUND);
+    }
+}
@@ -64,6 +64,13 @@ pub enum Error {
     },
 
     // programmatic errors goes here:
+    #[snafu(display("Metastore error: {source}"))]
+    Metastore {
+        source: core_metastore::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
     #[snafu(display("Can't add header to response: {error}"))]
     ResponseHeader {
         #[snafu(source)]
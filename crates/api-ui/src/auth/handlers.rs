@@ -1,7 +1,10 @@
 #![allow(clippy::needless_for_each)]
 use super::error::AuthErrorResponse;
 use super::error::CreateJwtSnafu;
-use crate::auth::error::{self as auth_error, BadRefreshTokenSnafu, TokenErrorKind};
+use super::layer::get_authorization_token;
+use crate::auth::error::{
+    self as auth_error, BadAuthTokenSnafu, BadRefreshTokenSnafu, TokenErrorKind,
+};
 use crate::auth::models::{AccountResponse, RefreshTokenResponse};
 use crate::auth::models::{AuthResponse, Claims, LoginPayload};
 use crate::error::Result;
@@ -177,7 +180,13 @@ pub async fn login(
     State(state): State<AppState>,
     Json(LoginPayload { username, password }): Json<LoginPayload>,
 ) -> Result<impl IntoResponse> {
-    if username != *state.auth_config.demo_user() || password != *state.auth_config.demo_password()
+    // The UI isn't bound to a role, role restricted access tokens can't log in to it
+    if !state
+        .metastore
+        .authenticate(&username, &password)
+        .await
+        .context(auth_error::MetastoreSnafu)?
+        .is_some_and(|authenticated| authenticated.role_restriction.is_none())
     {
         return auth_error::LoginSnafu.fail()?;
     }
@@ -320,19 +329,17 @@ pub async fn account(
     State(state): State<AppState>,
     headers: HeaderMap,
 ) -> Result<impl IntoResponse> {
-    // Simplest account info, also no auth checks.
-    // TODO: Move it to proper place when working with real account
-    // Check authentication
-
-    let auth = headers.get(http::header::AUTHORIZATION);
-    if auth.is_some() {
-        Ok((
-            headers,
-            Json(AccountResponse {
-                username: state.auth_config.demo_user().to_string(),
-            }),
-        ))
-    } else {
-        auth_error::NoAuthHeaderSnafu.fail()?
-    }
+    let access_token = get_authorization_token(&headers)?;
+    let jwt_secret = state.auth_config.jwt_secret();
+    ensure_jwt_secret_is_valid(jwt_secret)?;
+
+    let claims = get_claims_validate_jwt_token(access_token, &state.config.host, jwt_secret)
+        .context(BadAuthTokenSnafu)?;
+
+    Ok((
+        headers,
+        Json(AccountResponse {
+            username: claims.sub,
+        }),
+    ))
 }
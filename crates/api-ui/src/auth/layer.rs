@@ -1,6 +1,7 @@
 use super::error::{self as auth_error, BadAuthTokenSnafu, Result};
 use super::handlers::get_claims_validate_jwt_token;
 use crate::state::AppState;
+use api_sessions::SessionUser;
 use axum::{
     extract::{Request, State},
     middleware::Next,
@@ -9,7 +10,7 @@ use axum::{
 use http::HeaderMap;
 use snafu::ResultExt;
 
-fn get_authorization_token(headers: &HeaderMap) -> Result<&str> {
+pub(crate) fn get_authorization_token(headers: &HeaderMap) -> Result<&str> {
     let auth = headers.get(http::header::AUTHORIZATION);
 
     match auth {
@@ -27,16 +28,19 @@ fn get_authorization_token(headers: &HeaderMap) -> Result<&str> {
     }
 }
 
+/// Sessions of authenticated requests are opened for the user of the token.
+/// With auth disabled they are opened for the startup user, if there is one.
 pub async fn require_auth(
     State(state): State<AppState>,
-    req: Request,
+    mut req: Request,
     next: Next,
 ) -> Result<impl IntoResponse> {
-    // no demo user -> no auth required
-    if state.auth_config.jwt_secret().is_empty()
-        || state.auth_config.demo_user().is_empty()
-        || state.auth_config.demo_password().is_empty()
-    {
+    if state.auth_config.auth_disabled() {
+        let demo_user = state.auth_config.demo_user();
+        if !demo_user.is_empty() {
+            req.extensions_mut()
+                .insert(SessionUser(demo_user.to_string()));
+        }
         return Ok(next.run(req).await);
     }
 
@@ -44,8 +48,9 @@ pub async fn require_auth(
     let audience = state.config.host.clone();
     let jwt_secret = state.auth_config.jwt_secret();
 
-    let _ = get_claims_validate_jwt_token(access_token, &audience, jwt_secret)
+    let claims = get_claims_validate_jwt_token(access_token, &audience, jwt_secret)
         .context(BadAuthTokenSnafu)?;
+    req.extensions_mut().insert(SessionUser(claims.sub));
 
     Ok(next.run(req).await)
 }
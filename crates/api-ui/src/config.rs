@@ -9,11 +9,14 @@ pub struct WebConfig {
 }
 
 // Non serializable, no Clone, Copy, Debug traits
+// Demo credentials are those of the user created on startup,
+// logins are validated against the metastore users
 #[derive(Default)]
 pub struct AuthConfig {
     jwt_secret: String,
     demo_user: String,
     demo_password: String,
+    auth_disabled: bool,
 }
 
 impl AuthConfig {
@@ -32,6 +35,12 @@ impl AuthConfig {
         self
     }
 
+    #[must_use]
+    pub const fn with_auth_disabled(mut self, auth_disabled: bool) -> Self {
+        self.auth_disabled = auth_disabled;
+        self
+    }
+
     #[must_use]
     pub fn jwt_secret(&self) -> &str {
         &self.jwt_secret
@@ -46,4 +55,9 @@ impl AuthConfig {
     pub fn demo_password(&self) -> &str {
         &self.demo_password
     }
+
+    #[must_use]
+    pub const fn auth_disabled(&self) -> bool {
+        self.auth_disabled
+    }
 }
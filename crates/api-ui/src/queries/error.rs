@@ -107,10 +107,14 @@ impl IntoStatusCode for Error {
                 _ => StatusCode::INTERNAL_SERVER_ERROR,
             },
             Self::GetQueryRecord { source, .. } => match &source {
+                QueryError::Execution {
+                    source: core_executor::Error::QueryIsntRunning { .. },
+                    ..
+                } => StatusCode::NOT_FOUND,
                 QueryError::Store { source, .. } => match &source {
-                    core_history::Error::QueryGet { .. } | core_history::Error::BadKey { .. } => {
-                        StatusCode::NOT_FOUND
-                    }
+                    core_history::Error::QueryGet { .. }
+                    | core_history::Error::BadKey { .. }
+                    | core_history::Error::QueryProfileNotFound { .. } => StatusCode::NOT_FOUND,
                     _ => StatusCode::INTERNAL_SERVER_ERROR,
                 },
                 _ => StatusCode::INTERNAL_SERVER_ERROR,
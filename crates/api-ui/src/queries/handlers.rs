@@ -1,10 +1,13 @@
 #![allow(clippy::needless_for_each)]
 use crate::queries::error::{
-    DatetimeSnafu, ExecutionSnafu, GetQueryRecordSnafu, QueriesSnafu, StoreSnafu,
+    CreateResultSetSnafu, DatetimeSnafu, ExecutionSnafu, GetQueryRecordSnafu, QueriesSnafu,
+    QueryError, StoreSnafu,
 };
 use crate::queries::models::{
-    GetQueriesParams, QueriesResponse, QueryCreatePayload, QueryCreateResponse, QueryGetResponse,
-    QueryRecord, QueryRecordId, QueryResultGetResponse, QueryStatus, ResultSet,
+    GetQueriesParams, OperatorStats, QueriesResponse, QueryCreatePayload, QueryCreateResponse,
+    QueryExportPayload, QueryGetResponse, QueryProfileGetResponse, QueryProgress,
+    QueryProgressGetResponse, QueryRecord, QueryRecordId, QueryResultGetResponse, QueryStatus,
+    ResultSet,
 };
 use crate::state::AppState;
 use crate::{
@@ -15,8 +18,11 @@ use crate::{
     queries::error::{self as queries_errors},
 };
 use api_sessions::DFSessionId;
+use axum::body::{Body, Bytes};
 use axum::extract::ConnectInfo;
 use axum::extract::Path;
+use axum::http::header;
+use axum::response::IntoResponse;
 use axum::{
     Json,
     extract::{Query, State},
@@ -25,6 +31,7 @@ use chrono::{DateTime, Utc};
 use core_executor::models::{QueryContext, QueryResult};
 use core_history::WorksheetId;
 use datafusion::arrow::array::{Array, Int64Array, RecordBatch, StringArray};
+use datafusion::arrow::csv::WriterBuilder;
 use snafu::ResultExt;
 use std::collections::HashMap;
 use std::net::SocketAddr;
@@ -32,8 +39,8 @@ use utoipa::OpenApi;
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(query, queries, get_query, get_query_result),
-    components(schemas(QueriesResponse, QueryCreateResponse, QueryCreatePayload, QueryGetResponse, QueryRecord, QueryRecordId, ErrorResponse, WorksheetId, OrderDirection, ResultSet)),
+    paths(query, export_query, queries, get_query, get_query_result, get_query_progress, get_query_profile),
+    components(schemas(OperatorStats, QueriesResponse, QueryCreateResponse, QueryCreatePayload, QueryExportPayload, QueryGetResponse, QueryProfileGetResponse, QueryProgress, QueryProgressGetResponse, QueryRecord, QueryRecordId, ErrorResponse, WorksheetId, OrderDirection, ResultSet)),
     tags(
       (name = "queries", description = "Queries endpoints"),
     )
@@ -147,6 +154,94 @@ pub async fn query(
     Ok(Json(QueryCreateResponse(query_record)))
 }
 
+#[utoipa::path(
+    post,
+    path = "/ui/queries/export",
+    operation_id = "exportQuery",
+    tags = ["queries"],
+    request_body = QueryExportPayload,
+    responses(
+        (status = 200, description = "Streams the query result as CSV", body = String, content_type = "text/csv"),
+        (status = 401,
+         description = "Unauthorized",
+         headers(
+            ("WWW-Authenticate" = String, description = "Bearer authentication scheme with error details")
+         ),
+         body = ErrorResponse),
+        (status = 422, description = "Unprocessable entity", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[tracing::instrument(
+    name = "api_ui::export_query",
+    level = "info",
+    skip(state),
+    fields(query_id),
+    err
+)]
+pub async fn export_query(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    DFSessionId(session_id): DFSessionId,
+    State(state): State<AppState>,
+    Json(payload): Json<QueryExportPayload>,
+) -> Result<impl IntoResponse> {
+    let query_context = QueryContext::new(
+        payload
+            .context
+            .as_ref()
+            .and_then(|c| c.get("database").cloned()),
+        payload
+            .context
+            .as_ref()
+            .and_then(|c| c.get("schema").cloned()),
+        None,
+    )
+    .with_ip_address(addr.ip().to_string());
+
+    // The result is streamed to the client as it's produced, not collected in memory,
+    // and spooled to disk while the client is slow to read it
+    let result = state
+        .execution_svc
+        .query_stream(&session_id, &payload.query, query_context)
+        .await
+        .context(queries_errors::ExecutionSnafu)
+        .context(queries_errors::QuerySnafu)?;
+    let result = state.execution_svc.spool_result(result);
+    let query_uuid = result.query_id.as_uuid();
+    // Record the result as part of the current span.
+    tracing::Span::current().record("query_id", result.query_id.as_i64());
+
+    let schema = result.schema();
+    let csv = futures::stream::try_unfold((result, true), move |(mut result, header)| {
+        let schema = schema.clone();
+        async move {
+            let batch = match result.next_batch().await {
+                Some(batch) => batch.context(queries_errors::ExecutionSnafu)?,
+                // The header of an empty result
+                None if header => RecordBatch::new_empty(schema),
+                None => return Ok(None),
+            };
+            Ok::<_, QueryError>(Some((batch_to_csv(&batch, header)?, (result, false))))
+        }
+    });
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{query_uuid}.csv\""),
+            ),
+        ],
+        Body::from_stream(csv),
+    ))
+}
+
+fn batch_to_csv(batch: &RecordBatch, header: bool) -> std::result::Result<Bytes, QueryError> {
+    let mut writer = WriterBuilder::new().with_header(header).build(Vec::new());
+    writer.write(batch).context(CreateResultSetSnafu)?;
+    Ok(writer.into_inner().into())
+}
+
 #[utoipa::path(
     get,
     path = "/ui/queries/{queryRecordId}",
@@ -330,6 +425,75 @@ pub async fn get_query_result(
         .context(GetQueryRecordSnafu)?
 }
 
+#[utoipa::path(
+    get,
+    path = "/ui/queries/{queryRecordId}/progress",
+    operation_id = "getQueryProgress",
+    tags = ["queries"],
+    params(
+        ("queryRecordId" = QueryRecordId, Path, description = "Query Record Id")
+    ),
+    responses(
+        (status = 200, description = "Returns progress of the running query", body = QueryProgressGetResponse),
+        (status = 401,
+         description = "Unauthorized",
+         headers(
+            ("WWW-Authenticate" = String, description = "Bearer authentication scheme with error details")
+         ),
+         body = ErrorResponse),
+        (status = 404, description = "Query isn't running", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[tracing::instrument(name = "api_ui::get_query_progress", level = "info", skip(state), err, ret(level = tracing::Level::TRACE))]
+pub async fn get_query_progress(
+    State(state): State<AppState>,
+    Path(query_record_id): Path<QueryRecordId>,
+) -> Result<Json<QueryProgressGetResponse>> {
+    let progress = state
+        .execution_svc
+        .query_progress(query_record_id.into())
+        .context(ExecutionSnafu)
+        .context(GetQueryRecordSnafu)?;
+    Ok(Json(QueryProgressGetResponse(progress.into())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/ui/queries/{queryRecordId}/profile",
+    operation_id = "getQueryProfile",
+    tags = ["queries"],
+    params(
+        ("queryRecordId" = QueryRecordId, Path, description = "Query Record Id")
+    ),
+    responses(
+        (status = 200, description = "Returns statistics of the operators the query has executed", body = QueryProfileGetResponse),
+        (status = 401,
+         description = "Unauthorized",
+         headers(
+            ("WWW-Authenticate" = String, description = "Bearer authentication scheme with error details")
+         ),
+         body = ErrorResponse),
+        (status = 404, description = "Query has no profile", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[tracing::instrument(name = "api_ui::get_query_profile", level = "info", skip(state), err, ret(level = tracing::Level::TRACE))]
+pub async fn get_query_profile(
+    State(state): State<AppState>,
+    Path(query_record_id): Path<QueryRecordId>,
+) -> Result<Json<QueryProfileGetResponse>> {
+    let operator_stats = state
+        .history_store
+        .get_query_profile(query_record_id.into())
+        .await
+        .context(StoreSnafu)
+        .context(GetQueryRecordSnafu)?;
+    Ok(Json(QueryProfileGetResponse {
+        items: operator_stats.into_iter().map(Into::into).collect(),
+    }))
+}
+
 #[allow(clippy::result_large_err)]
 fn w_downcast_int64_column<'a>(
     batch: &'a RecordBatch,
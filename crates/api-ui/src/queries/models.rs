@@ -72,6 +72,13 @@ pub struct QueryCreatePayload {
 #[serde(rename_all = "camelCase")]
 pub struct QueryCreateResponse(pub QueryRecord);
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryExportPayload {
+    pub query: String,
+    pub context: Option<HashMap<String, String>>,
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum QueryStatus {
@@ -171,3 +178,63 @@ pub struct QueryGetResponse(pub QueryRecord);
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct QueryResultGetResponse(pub ResultSet);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryProgress {
+    pub rows_scanned: usize,
+    pub bytes_scanned: usize,
+    pub files_processed: usize,
+    pub files_total: usize,
+    pub current_operator: Option<String>,
+}
+
+impl From<core_executor::progress::QueryProgress> for QueryProgress {
+    fn from(progress: core_executor::progress::QueryProgress) -> Self {
+        Self {
+            rows_scanned: progress.rows_scanned,
+            bytes_scanned: progress.bytes_scanned,
+            files_processed: progress.files_processed,
+            files_total: progress.files_total,
+            current_operator: progress.current_operator,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryProgressGetResponse(pub QueryProgress);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OperatorStats {
+    pub operator_id: usize,
+    pub parent_operator_id: Option<usize>,
+    pub operator_type: String,
+    pub operator_attributes: String,
+    pub output_rows: Option<usize>,
+    pub elapsed_compute_ns: Option<usize>,
+    pub spilled_bytes: Option<usize>,
+    pub memory_peak_bytes: Option<usize>,
+}
+
+impl From<core_history::OperatorStats> for OperatorStats {
+    fn from(stats: core_history::OperatorStats) -> Self {
+        Self {
+            operator_id: stats.operator_id,
+            parent_operator_id: stats.parent_operator_id,
+            operator_type: stats.operator_type,
+            operator_attributes: stats.operator_attributes,
+            output_rows: stats.output_rows,
+            elapsed_compute_ns: stats.elapsed_compute_ns,
+            spilled_bytes: stats.spilled_bytes,
+            memory_peak_bytes: stats.memory_peak_bytes,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryProfileGetResponse {
+    pub items: Vec<OperatorStats>,
+}
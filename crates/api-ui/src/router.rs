@@ -8,7 +8,9 @@ use crate::navigation_trees::handlers::{
     ApiDoc as DatabasesNavigationApiDoc, get_navigation_trees,
 };
 use crate::queries::handlers::{ApiDoc as QueryApiDoc, get_query};
-use crate::queries::handlers::{get_query_result, queries, query};
+use crate::queries::handlers::{
+    export_query, get_query_profile, get_query_progress, get_query_result, queries, query,
+};
 use crate::schemas::handlers::ApiDoc as SchemasApiDoc;
 use crate::schemas::handlers::{create_schema, delete_schema, list_schemas};
 use crate::tables::handlers::{
@@ -111,8 +113,11 @@ pub fn create_router() -> Router<AppState> {
                 .patch(update_worksheet),
         )
         .route("/queries", post(query).get(queries))
+        .route("/queries/export", post(export_query))
         .route("/queries/{queryRecordId}", get(get_query))
         .route("/queries/{queryRecordId}/result", get(get_query_result))
+        .route("/queries/{queryRecordId}/progress", get(get_query_progress))
+        .route("/queries/{queryRecordId}/profile", get(get_query_profile))
         .route(
             "/databases/{databaseName}/schemas/{schemaName}/tables/{tableName}/rows",
             post(upload_file),
@@ -11,22 +11,28 @@ use axum::middleware;
 use core_executor::service::CoreExecutionService;
 use core_executor::utils::Config;
 use core_history::SlateDBHistoryStore;
-use core_metastore::SlateDBMetastore;
+use core_metastore::{SlateDBMetastore, bootstrap_user};
 use std::net::SocketAddr;
 use std::sync::Arc;
 
-#[allow(clippy::unwrap_used, clippy::expect_used)]
 pub async fn run_test_server_with_demo_auth(
     jwt_secret: String,
     demo_user: String,
     demo_password: String,
 ) -> SocketAddr {
+    run_test_server_with_auth_config(
+        AuthConfig::new(jwt_secret).with_demo_credentials(demo_user, demo_password),
+    )
+    .await
+}
+
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+async fn run_test_server_with_auth_config(auth_config: AuthConfig) -> SocketAddr {
     let listener = tokio::net::TcpListener::bind("0.0.0.0:0").await.unwrap();
     let addr = listener.local_addr().unwrap();
 
     let metastore = SlateDBMetastore::new_in_memory().await;
     let history = SlateDBHistoryStore::new_in_memory().await;
-    let auth_config = AuthConfig::new(jwt_secret).with_demo_credentials(demo_user, demo_password);
 
     let app = make_app(
         metastore,
@@ -49,9 +55,14 @@ pub async fn run_test_server_with_demo_auth(
     addr
 }
 
-#[allow(clippy::unwrap_used)]
 pub async fn run_test_server() -> SocketAddr {
-    run_test_server_with_demo_auth(String::new(), String::new(), String::new()).await
+    // Sessions are opened for the demo user, as auth is disabled
+    run_test_server_with_auth_config(
+        AuthConfig::default()
+            .with_demo_credentials("embucket".to_string(), "embucket".to_string())
+            .with_auth_disabled(true),
+    )
+    .await
 }
 
 #[allow(clippy::needless_pass_by_value, clippy::expect_used)]
@@ -63,6 +74,14 @@ pub async fn make_app(
 ) -> Result<Router, Box<dyn std::error::Error>> {
     let metastore = Arc::new(metastore);
     let history_store = Arc::new(history_store);
+    if !auth_config.demo_user().is_empty() {
+        bootstrap_user(
+            metastore.as_ref(),
+            auth_config.demo_user(),
+            auth_config.demo_password(),
+        )
+        .await?;
+    }
     let execution_svc = Arc::new(
         CoreExecutionService::new(
             metastore.clone(),
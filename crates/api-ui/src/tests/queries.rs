@@ -2,13 +2,13 @@
 
 use crate::error::ErrorResponse;
 use crate::queries::models::{
-    Column, QueriesResponse, QueryCreatePayload, QueryGetResponse, QueryRecord, QueryStatus,
-    ResultSet,
+    Column, QueriesResponse, QueryCreatePayload, QueryExportPayload, QueryGetResponse,
+    QueryProfileGetResponse, QueryProgressGetResponse, QueryRecord, QueryStatus, ResultSet,
 };
-use crate::tests::common::http_req;
+use crate::tests::common::{http_req, req};
 use crate::tests::server::run_test_server;
 use crate::worksheets::models::{Worksheet, WorksheetCreatePayload, WorksheetsResponse};
-use http::Method;
+use http::{Method, StatusCode, header};
 use serde_json::json;
 
 #[tokio::test]
@@ -455,3 +455,149 @@ async fn test_ui_async_query_infer_default_exec_mode() {
 
     assert_eq!(expected_result, result_set);
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_ui_query_progress() {
+    let addr = run_test_server().await;
+    let client = reqwest::Client::new();
+
+    let query_record = http_req::<QueryRecord>(
+        &client,
+        Method::POST,
+        &format!("http://{addr}/ui/queries"),
+        json!(QueryCreatePayload {
+            worksheet_id: None,
+            async_exec: true,
+            query: "SELECT sleep(1)".to_string(),
+            context: None,
+        })
+        .to_string(),
+    )
+    .await
+    .expect("Create query error");
+    assert_eq!(query_record.status, QueryStatus::Running);
+
+    let QueryProgressGetResponse(progress) = http_req::<QueryProgressGetResponse>(
+        &client,
+        Method::GET,
+        &format!("http://{addr}/ui/queries/{}/progress", query_record.id),
+        String::new(),
+    )
+    .await
+    .expect("Get query progress error");
+    assert_eq!(progress.files_total, 0);
+
+    std::thread::sleep(std::time::Duration::from_millis(1500));
+
+    let res = req(
+        &client,
+        Method::GET,
+        &format!("http://{addr}/ui/queries/{}/progress", query_record.id),
+        String::new(),
+    )
+    .await
+    .expect("Get query progress error");
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_ui_query_profile() {
+    let addr = run_test_server().await;
+    let client = reqwest::Client::new();
+
+    let query_record = http_req::<QueryRecord>(
+        &client,
+        Method::POST,
+        &format!("http://{addr}/ui/queries"),
+        json!(QueryCreatePayload {
+            worksheet_id: None,
+            async_exec: false,
+            query: "SELECT 1 AS a".to_string(),
+            context: None,
+        })
+        .to_string(),
+    )
+    .await
+    .expect("Create query error");
+
+    // The profile is recorded once the result of the query is delivered
+    let mut profile = None;
+    for _ in 0..50 {
+        profile = http_req::<QueryProfileGetResponse>(
+            &client,
+            Method::GET,
+            &format!("http://{addr}/ui/queries/{}/profile", query_record.id),
+            String::new(),
+        )
+        .await
+        .ok();
+        if profile.is_some() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    let profile = profile.expect("Get query profile error");
+    assert!(!profile.items.is_empty());
+    assert_eq!(profile.items[0].parent_operator_id, None);
+    assert_eq!(profile.items[0].output_rows, Some(1));
+
+    let res = req(
+        &client,
+        Method::GET,
+        &format!("http://{addr}/ui/queries/1/profile"),
+        String::new(),
+    )
+    .await
+    .expect("Get query profile error");
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_ui_export_query() {
+    let addr = run_test_server().await;
+    let client = reqwest::Client::new();
+
+    let res = req(
+        &client,
+        Method::POST,
+        &format!("http://{addr}/ui/queries/export"),
+        json!(QueryExportPayload {
+            query: "SELECT value AS v, 'x' AS s FROM generate_series(1, 3)".to_string(),
+            context: None,
+        })
+        .to_string(),
+    )
+    .await
+    .expect("Export query error");
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()[header::CONTENT_TYPE], "text/csv");
+    assert_eq!(res.text().await.unwrap(), "v,s\n1,x\n2,x\n3,x\n");
+
+    let res = req(
+        &client,
+        Method::POST,
+        &format!("http://{addr}/ui/queries/export"),
+        json!(QueryExportPayload {
+            query: "SELECT 1 AS a WHERE FALSE".to_string(),
+            context: None,
+        })
+        .to_string(),
+    )
+    .await
+    .expect("Export query error");
+    assert_eq!(res.text().await.unwrap(), "a\n");
+
+    let res = req(
+        &client,
+        Method::POST,
+        &format!("http://{addr}/ui/queries/export"),
+        json!(QueryExportPayload {
+            query: "SELECT * FROM missing_table".to_string(),
+            context: None,
+        })
+        .to_string(),
+    )
+    .await
+    .expect("Export query error");
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
@@ -1,8 +1,7 @@
 use crate::util::{
-    BenchmarkRun, CommonOpt, create_catalog, make_test_execution_svc, query_context,
-    set_session_variable_bool, set_session_variable_number, table_ref,
+    BenchmarkRun, CommonOpt, create_catalog, create_session, make_test_execution_svc,
+    query_context, set_session_variable_bool, set_session_variable_number, table_ref,
 };
-use core_executor::service::ExecutionService;
 use core_executor::session::UserSession;
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::common::exec_datafusion_err;
@@ -122,7 +121,7 @@ impl RunOpt {
         };
 
         let service = make_test_execution_svc().await;
-        let session = service.create_session("session_id").await?;
+        let session = create_session(&service, "session_id").await?;
 
         // Set the number of output parquet files during copy into
         set_session_variable_number(
@@ -150,7 +149,7 @@ impl RunOpt {
         for query_id in query_range {
             let mut millis = Vec::with_capacity(iterations);
             benchmark_run.start_new_case(&format!("Query {query_id}"));
-            let session = service.create_session("session_id").await?;
+            let session = create_session(&service, "session_id").await?;
 
             // Set prefer_hash_join session variable
             set_session_variable_bool(
@@ -4,11 +4,11 @@ use std::sync::Arc;
 
 use super::{TPCH_TABLES, get_query_sql, get_tpch_table_sql};
 use crate::util::{
-    BenchmarkRun, CommonOpt, create_catalog, make_test_execution_svc, query_context,
-    set_session_variable_bool, set_session_variable_number,
+    BenchmarkRun, CommonOpt, create_catalog, create_session, make_test_execution_svc,
+    query_context, set_session_variable_bool, set_session_variable_number,
 };
 
-use core_executor::service::{CoreExecutionService, ExecutionService};
+use core_executor::service::CoreExecutionService;
 use core_executor::session::UserSession;
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::arrow::util::pretty::pretty_format_batches;
@@ -82,7 +82,7 @@ impl RunOpt {
 
         println!("Create service, volume, database, schema");
         let service = make_test_execution_svc().await;
-        let session = service.create_session("session_id").await?;
+        let session = create_session(&service, "session_id").await?;
         let path = self.path.to_str().unwrap();
         create_catalog(path, &session, self.common.mem_table).await?;
 
@@ -125,7 +125,7 @@ impl RunOpt {
         service: &Arc<CoreExecutionService>,
     ) -> Result<Vec<QueryResult>> {
         let session_id = format!("session_id_{query_id}");
-        let session = service.create_session(&session_id).await?;
+        let session = create_session(service, &session_id).await?;
 
         // Set prefer_hash_join session variable
         set_session_variable_bool(
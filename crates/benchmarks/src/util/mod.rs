@@ -1,16 +1,18 @@
 mod options;
 mod run;
 
-use core_executor::models::QueryContext;
-use core_executor::service::CoreExecutionService;
+use core_executor::models::{QueryContext, SessionOptions};
+use core_executor::service::{CoreExecutionService, ExecutionService};
 use core_executor::session::UserSession;
 use core_executor::utils::Config;
 use core_history::SlateDBHistoryStore;
-use core_metastore::SlateDBMetastore;
+use core_metastore::{ACCOUNTADMIN_ROLE, SlateDBMetastore, bootstrap_user};
 use core_utils::Db;
 use datafusion::error::Result;
+use datafusion::scalar::ScalarValue;
 pub use options::{BoolDefaultTrue, CommonOpt};
 pub use run::{BenchQuery, BenchmarkRun};
+use std::collections::HashMap;
 use std::sync::Arc;
 
 /// Default catalog name used in benchmarks
@@ -19,6 +21,10 @@ pub const DEFAULT_CATALOG: &str = "benchmark";
 /// Default schema name used in benchmarks
 pub const DEFAULT_SCHEMA: &str = "public";
 
+/// User the benchmark sessions are opened for, sessions without a user only have
+/// the privileges of `PUBLIC`
+pub const BENCHMARK_USER: &str = "benchmark";
+
 #[must_use]
 pub fn query_context() -> QueryContext {
     QueryContext::new(
@@ -93,6 +99,9 @@ pub async fn make_test_execution_svc() -> Arc<CoreExecutionService> {
     // ));
     let db = Db::memory().await;
     let metastore = Arc::new(SlateDBMetastore::new(db.clone()));
+    bootstrap_user(metastore.as_ref(), BENCHMARK_USER, BENCHMARK_USER)
+        .await
+        .expect("Failed to create the benchmark user");
     let history_store = Arc::new(
         SlateDBHistoryStore::new(db.clone())
             .await
@@ -104,3 +113,22 @@ pub async fn make_test_execution_svc() -> Arc<CoreExecutionService> {
             .expect("Failed to create a execution service"),
     )
 }
+
+/// Opens a session of the benchmark user with the `ACCOUNTADMIN` role
+pub async fn create_session(
+    service: &CoreExecutionService,
+    session_id: &str,
+) -> Result<Arc<UserSession>> {
+    let options = SessionOptions {
+        params: HashMap::from([(
+            "role".to_string(),
+            ScalarValue::Utf8(Some(ACCOUNTADMIN_ROLE.to_string())),
+        )]),
+        client_info: None,
+        user: Some(BENCHMARK_USER.to_string()),
+        role_restriction: None,
+    };
+    Ok(service
+        .create_session_with_options(session_id, options)
+        .await?)
+}
@@ -0,0 +1,429 @@
+//! Role-based access control.
+//!
+//! Privileges are granted to roles and roles are granted to users or to other
+//! roles, which inherit their privileges. Queries of a session opened by a user
+//! run with the privileges of the session role: the privileges each logical plan
+//! needs are collected by [`plan_access`] and checked by `UserQuery` before the
+//! plan is executed. Sessions without a user only have the `PUBLIC` role.
+use crate::copy_into::LOAD_SCAN_NAME;
+use crate::error::{self as ex_error, Result};
+use async_trait::async_trait;
+use core_history::{HistoryStore, QueryRecord, QueryRecordId};
+use core_metastore::error::UtilSlateDBSnafu;
+use core_metastore::{
+    ACCOUNTADMIN_ROLE, FileFormatIdent, Grant, GrantObject, Grantee, Metastore, PUBLIC_ROLE,
+    Privilege, RoleIdent, SchemaIdent, StageIdent, TableIdent, UserIdent,
+};
+use core_utils::scan_iterator::ScanIterator;
+use datafusion::catalog::streaming::StreamingTable;
+use datafusion::datasource::source_as_provider;
+use datafusion::logical_expr::{DdlStatement, LogicalPlan, TableScan, WriteOp};
+use datafusion_common::tree_node::{TreeNode, TreeNodeRecursion};
+use datafusion_common::{DataFusionError, SchemaReference, TableReference};
+use embucket_functions::session_params::SessionParams;
+use embucket_functions::table::access::SessionAccess;
+use snafu::ResultExt;
+use std::sync::Arc;
+
+/// Privileges of a role, including the ones of the roles it inherits
+#[derive(Debug, Clone)]
+pub struct AccessControl {
+    pub role: RoleIdent,
+    pub roles: Vec<RoleIdent>,
+    grants: Vec<Grant>,
+}
+
+impl AccessControl {
+    /// Loads the privileges of the role, which must be granted to the user.
+    /// Without a user only `PUBLIC` can be loaded.
+    pub async fn load(
+        metastore: &dyn Metastore,
+        user: Option<&UserIdent>,
+        role: &str,
+    ) -> Result<Self> {
+        let user_roles = match user {
+            Some(user) => granted_roles(metastore, &Grantee::User(user.clone())).await?,
+            None => Vec::new(),
+        };
+        if !role.eq_ignore_ascii_case(PUBLIC_ROLE)
+            && !user_roles.iter().any(|r| r.eq_ignore_ascii_case(role))
+        {
+            return ex_error::RoleNotGrantedSnafu { role }.fail();
+        }
+
+        let mut roles = vec![role.to_string()];
+        for inherited in granted_roles(metastore, &Grantee::Role(role.to_string())).await? {
+            push_role(&mut roles, inherited);
+        }
+        push_role(&mut roles, PUBLIC_ROLE.to_string());
+
+        let mut grants = Vec::new();
+        for role in &roles {
+            let role_grants = metastore
+                .iter_grants(Some(role))
+                .collect()
+                .await
+                .context(UtilSlateDBSnafu)
+                .context(ex_error::MetastoreSnafu)?;
+            grants.extend(role_grants.into_iter().map(|grant| grant.data));
+        }
+        Ok(Self {
+            role: role.to_string(),
+            roles,
+            grants,
+        })
+    }
+
+    #[must_use]
+    pub fn is_admin(&self) -> bool {
+        self.roles
+            .iter()
+            .any(|role| role.eq_ignore_ascii_case(ACCOUNTADMIN_ROLE))
+    }
+
+    /// Whether the privilege is granted on the object, owners have all privileges
+    #[must_use]
+    pub fn allows(&self, privilege: Privilege, object: &GrantObject) -> bool {
+        self.is_admin()
+            || self.grants.iter().any(|grant| {
+                &grant.object == object
+                    && (grant.privilege == privilege || grant.privilege == Privilege::Ownership)
+            })
+    }
+
+    pub fn check(&self, privilege: Privilege, object: &GrantObject) -> Result<()> {
+        if self.allows(privilege, object) {
+            Ok(())
+        } else {
+            ex_error::InsufficientPrivilegesSnafu {
+                kind: object.kind().to_lowercase(),
+                name: object.to_string(),
+            }
+            .fail()
+        }
+    }
+
+    /// Privileges on a schema also need `USAGE` on the schema and its database
+    pub fn check_schema(&self, privilege: Privilege, schema: &SchemaIdent) -> Result<()> {
+        let object = GrantObject::Schema(schema.clone());
+        self.check(
+            Privilege::Usage,
+            &GrantObject::Database(schema.database.clone()),
+        )?;
+        self.check(Privilege::Usage, &object)?;
+        if privilege == Privilege::Usage {
+            return Ok(());
+        }
+        self.check(privilege, &object)
+    }
+
+    /// Privileges on a table also need `USAGE` on its database and schema
+    pub fn check_table(&self, privilege: Privilege, table: &TableIdent) -> Result<()> {
+        let schema = SchemaIdent::new(table.database.clone(), table.schema.clone());
+        self.check_schema(Privilege::Usage, &schema)?;
+        self.check(privilege, &GrantObject::Table(table.clone()))
+    }
+
+    /// Privileges on a stage also need `USAGE` on its database and schema
+    pub fn check_stage(&self, privilege: Privilege, stage: &StageIdent) -> Result<()> {
+        let schema = SchemaIdent::new(stage.database.clone(), stage.schema.clone());
+        self.check_schema(Privilege::Usage, &schema)?;
+        self.check(privilege, &GrantObject::Stage(stage.clone()))
+    }
+
+    /// Privileges on a file format also need `USAGE` on its database and schema
+    pub fn check_file_format(
+        &self,
+        privilege: Privilege,
+        file_format: &FileFormatIdent,
+    ) -> Result<()> {
+        let schema = SchemaIdent::new(file_format.database.clone(), file_format.schema.clone());
+        self.check_schema(Privilege::Usage, &schema)?;
+        self.check(privilege, &GrantObject::FileFormat(file_format.clone()))
+    }
+}
+
+fn push_role(roles: &mut Vec<RoleIdent>, role: RoleIdent) {
+    if !roles.iter().any(|r| r.eq_ignore_ascii_case(&role)) {
+        roles.push(role);
+    }
+}
+
+/// Roles granted to the grantee, directly or through the roles granted to them
+pub async fn granted_roles(metastore: &dyn Metastore, grantee: &Grantee) -> Result<Vec<RoleIdent>> {
+    let mut roles: Vec<RoleIdent> = Vec::new();
+    let mut pending = vec![grantee.clone()];
+    while let Some(grantee) = pending.pop() {
+        let role_grants = metastore
+            .iter_role_grants(Some(&grantee))
+            .collect()
+            .await
+            .context(UtilSlateDBSnafu)
+            .context(ex_error::MetastoreSnafu)?;
+        for grant in role_grants {
+            if !roles.iter().any(|r| r.eq_ignore_ascii_case(&grant.role)) {
+                roles.push(grant.role.clone());
+                pending.push(Grantee::Role(grant.role.clone()));
+            }
+        }
+    }
+    Ok(roles)
+}
+
+/// Who reads a query of the history. Queries are only visible to the user who
+/// ran them and to admins.
+#[derive(Debug, Clone, Default)]
+pub struct QueryReader {
+    pub user: Option<UserIdent>,
+    /// Role of the reader, `PUBLIC` when not set
+    pub role: Option<RoleIdent>,
+    /// Role of the access token the reader authenticated with
+    pub role_restriction: Option<RoleIdent>,
+}
+
+impl QueryReader {
+    pub async fn check(&self, metastore: &dyn Metastore, query: &QueryRecord) -> Result<()> {
+        if query.user == self.user {
+            return Ok(());
+        }
+        if self.access_control(metastore).await?.is_admin() {
+            return Ok(());
+        }
+        ex_error::InsufficientPrivilegesSnafu {
+            kind: "query",
+            name: query.id.as_uuid().to_string(),
+        }
+        .fail()
+    }
+
+    /// Privileges of the role of the reader
+    pub async fn access_control(&self, metastore: &dyn Metastore) -> Result<AccessControl> {
+        let role = self.role.as_deref().unwrap_or(PUBLIC_ROLE);
+        if let Some(restriction) = &self.role_restriction
+            && !restriction.eq_ignore_ascii_case(role)
+        {
+            return ex_error::RoleRestrictedSnafu { role, restriction }.fail();
+        }
+        AccessControl::load(metastore, self.user.as_ref(), role).await
+    }
+}
+
+/// Privileges of a session for the table functions reading the catalog or the query history.
+/// They are loaded on each check, the role of the session can change.
+#[derive(Debug)]
+pub struct SessionAccessControl {
+    metastore: Arc<dyn Metastore>,
+    history_store: Arc<dyn HistoryStore>,
+    session_params: Arc<SessionParams>,
+    user: Option<UserIdent>,
+    role_restriction: Option<RoleIdent>,
+}
+
+impl SessionAccessControl {
+    #[must_use]
+    pub const fn new(
+        metastore: Arc<dyn Metastore>,
+        history_store: Arc<dyn HistoryStore>,
+        session_params: Arc<SessionParams>,
+        user: Option<UserIdent>,
+        role_restriction: Option<RoleIdent>,
+    ) -> Self {
+        Self {
+            metastore,
+            history_store,
+            session_params,
+            user,
+            role_restriction,
+        }
+    }
+
+    #[must_use]
+    pub fn query_reader(&self) -> QueryReader {
+        QueryReader {
+            user: self.user.clone(),
+            role: self.session_params.get_property("role"),
+            role_restriction: self.role_restriction.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl SessionAccess for SessionAccessControl {
+    async fn check_table_select(&self, table: &TableIdent) -> datafusion_common::Result<()> {
+        self.query_reader()
+            .access_control(self.metastore.as_ref())
+            .await
+            .and_then(|access| access.check_table(Privilege::Select, table))
+            .map_err(|e| DataFusionError::External(Box::new(e)))
+    }
+
+    async fn check_query(&self, query_id: QueryRecordId) -> datafusion_common::Result<()> {
+        let query = self
+            .history_store
+            .get_query(query_id)
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        self.query_reader()
+            .check(self.metastore.as_ref(), &query)
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))
+    }
+}
+
+/// An access to an object a logical plan needs a privilege for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlanAccess {
+    Table(Privilege, TableReference),
+    /// Creating a table or a view, needs `CREATE` on its schema
+    CreateTable(TableReference),
+    /// Creating a schema, needs `CREATE` on its database
+    CreateSchema(SchemaReference),
+    DropSchema(SchemaReference),
+}
+
+/// Collects the objects the plan reads, writes, creates or drops, including
+/// the ones referenced in subqueries.
+#[must_use]
+pub fn plan_access(plan: &LogicalPlan) -> Vec<PlanAccess> {
+    let mut access = Vec::new();
+    let mut modified = Vec::new();
+    // The closure never fails
+    let _ = plan.apply_with_subqueries(|node| {
+        match node {
+            // Table functions are scanned as `<name>()` and the files of a load
+            // as `LOAD_SCAN_NAME`, they aren't tables of the catalog
+            LogicalPlan::TableScan(scan)
+                if !scan.table_name.table().ends_with("()") && !is_load_scan(scan) =>
+            {
+                access.push(PlanAccess::Table(
+                    Privilege::Select,
+                    scan.table_name.clone(),
+                ));
+            }
+            LogicalPlan::Dml(dml) => match &dml.op {
+                WriteOp::Insert(_) => {
+                    access.push(PlanAccess::Table(Privilege::Insert, dml.table_name.clone()));
+                }
+                WriteOp::Update | WriteOp::Delete => {
+                    let privilege = if dml.op == WriteOp::Update {
+                        Privilege::Update
+                    } else {
+                        Privilege::Delete
+                    };
+                    access.push(PlanAccess::Table(privilege, dml.table_name.clone()));
+                    modified.push(dml.table_name.clone());
+                }
+                WriteOp::Ctas => access.push(PlanAccess::CreateTable(dml.table_name.clone())),
+            },
+            LogicalPlan::Ddl(ddl) => match ddl {
+                DdlStatement::CreateMemoryTable(table) => {
+                    access.push(PlanAccess::CreateTable(table.name.clone()));
+                }
+                DdlStatement::CreateView(view) => {
+                    access.push(PlanAccess::CreateTable(view.name.clone()));
+                }
+                DdlStatement::CreateExternalTable(table) => {
+                    access.push(PlanAccess::CreateTable(table.name.clone()));
+                }
+                DdlStatement::DropTable(table) => {
+                    access.push(PlanAccess::Table(Privilege::Ownership, table.name.clone()));
+                }
+                DdlStatement::DropView(view) => {
+                    access.push(PlanAccess::Table(Privilege::Ownership, view.name.clone()));
+                }
+                DdlStatement::CreateCatalogSchema(schema) => {
+                    let schema = match schema.schema_name.split_once('.') {
+                        Some((catalog, schema)) => SchemaReference::Full {
+                            schema: schema.into(),
+                            catalog: catalog.into(),
+                        },
+                        None => SchemaReference::Bare {
+                            schema: schema.schema_name.as_str().into(),
+                        },
+                    };
+                    access.push(PlanAccess::CreateSchema(schema));
+                }
+                DdlStatement::DropCatalogSchema(schema) => {
+                    access.push(PlanAccess::DropSchema(schema.name.clone()));
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        Ok(TreeNodeRecursion::Continue)
+    });
+    // Updating or deleting rows reads the table without needing SELECT on it
+    access.retain(|access| {
+        !matches!(access, PlanAccess::Table(Privilege::Select, table) if modified.contains(table))
+    });
+    access
+}
+
+/// The files `COPY INTO <table>` loads, scanned by `LoadStream`
+fn is_load_scan(scan: &TableScan) -> bool {
+    scan.table_name == TableReference::bare(LOAD_SCAN_NAME)
+        && source_as_provider(&scan.source)
+            .is_ok_and(|provider| provider.as_any().is::<StreamingTable>())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use datafusion::prelude::SessionContext;
+
+    async fn access(sql: &str) -> Vec<PlanAccess> {
+        let ctx = SessionContext::new();
+        ctx.sql("CREATE TABLE t (a INT)").await.unwrap();
+        ctx.sql("CREATE TABLE u (a INT)").await.unwrap();
+        let plan = ctx.state().create_logical_plan(sql).await.unwrap();
+        plan_access(&plan)
+    }
+
+    #[tokio::test]
+    async fn test_plan_access() {
+        let select = access("SELECT * FROM t WHERE a IN (SELECT a FROM u)").await;
+        assert_eq!(select.len(), 2);
+        assert!(select.contains(&PlanAccess::Table(
+            Privilege::Select,
+            TableReference::bare("t")
+        )));
+        assert!(select.contains(&PlanAccess::Table(
+            Privilege::Select,
+            TableReference::bare("u")
+        )));
+        assert_eq!(
+            access("INSERT INTO t SELECT a FROM u").await,
+            [
+                PlanAccess::Table(Privilege::Insert, TableReference::bare("t")),
+                PlanAccess::Table(Privilege::Select, TableReference::bare("u")),
+            ]
+        );
+        assert_eq!(
+            access("DELETE FROM t WHERE a = 1").await,
+            [PlanAccess::Table(
+                Privilege::Delete,
+                TableReference::bare("t")
+            )]
+        );
+        assert_eq!(
+            access("DROP TABLE t").await,
+            [PlanAccess::Table(
+                Privilege::Ownership,
+                TableReference::bare("t")
+            )]
+        );
+        assert_eq!(
+            access("CREATE VIEW v AS SELECT * FROM u").await,
+            [
+                PlanAccess::CreateTable(TableReference::bare("v")),
+                PlanAccess::Table(Privilege::Select, TableReference::bare("u")),
+            ]
+        );
+        assert!(
+            access("SELECT * FROM generate_series(1, 3)")
+                .await
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,322 @@
+//! Query bindings: values sent by the client along with the query for its `?` / `:N`
+//! placeholders. Placeholders are renumbered to `DataFusion`'s `$N` placeholders and
+//! the values are bound to the logical plan, they are never interpolated into the SQL.
+use crate::error::{self as ex_error, Result};
+use crate::models::{BindingValue, QueryBinding};
+use datafusion::arrow::datatypes::{DataType, TimeUnit};
+use datafusion::scalar::ScalarValue;
+use datafusion::sql::sqlparser::ast::{
+    Expr, Insert, SetExpr, Statement, Value, ValueWithSpan, visit_expressions_mut,
+};
+use snafu::ResultExt;
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+
+const MILLIS_PER_DAY: i64 = 86_400_000;
+/// `TIMESTAMP_TZ` bindings carry the UTC offset in minutes, shifted by 1440
+const TZ_OFFSET_SHIFT: i64 = 1440;
+
+/// Rewrites `?` and `:N` placeholders to `$N`. With array bindings, the single row
+/// of an `INSERT ... VALUES` is repeated for every bound row.
+pub fn rewrite_placeholders(statement: &mut Statement, bindings: &[QueryBinding]) -> Result<()> {
+    let mut position = 0;
+    let _ = visit_expressions_mut(statement, |expr| {
+        if let Some(placeholder) = placeholder_mut(expr) {
+            if placeholder == "?" {
+                position += 1;
+                *placeholder = format!("${position}");
+            } else if let Some(index) = placeholder
+                .strip_prefix(':')
+                .filter(|index| index.parse::<usize>().is_ok())
+            {
+                *placeholder = format!("${index}");
+            }
+        }
+        ControlFlow::<()>::Continue(())
+    });
+
+    let Some(rows) = array_rows(bindings)? else {
+        return Ok(());
+    };
+    let Statement::Insert(Insert {
+        source: Some(source),
+        ..
+    }) = statement
+    else {
+        return ex_error::ArrayBindingsNotSupportedSnafu.fail();
+    };
+    let SetExpr::Values(values) = source.body.as_mut() else {
+        return ex_error::ArrayBindingsNotSupportedSnafu.fail();
+    };
+    let [row] = values.rows.as_slice() else {
+        return ex_error::ArrayBindingsNotSupportedSnafu.fail();
+    };
+    let width = bindings.len();
+    let row = row.clone();
+    values.rows = (0..rows)
+        .map(|bound_row| {
+            let mut row = row.clone();
+            let _ = visit_expressions_mut(&mut row, |expr| {
+                if let Some(placeholder) = placeholder_mut(expr) {
+                    if let Some(index) = placeholder
+                        .strip_prefix('$')
+                        .and_then(|index| index.parse::<usize>().ok())
+                        .filter(|index| (1..=width).contains(index))
+                    {
+                        *placeholder = format!("${}", bound_row * width + index);
+                    }
+                }
+                ControlFlow::<()>::Continue(())
+            });
+            row
+        })
+        .collect();
+    Ok(())
+}
+
+/// Parameter values for the `$N` placeholders left by [`rewrite_placeholders`]
+pub fn param_values(bindings: &[QueryBinding]) -> Result<HashMap<String, ScalarValue>> {
+    let rows = array_rows(bindings)?.unwrap_or(1);
+    let width = bindings.len();
+    let mut values = HashMap::with_capacity(rows * width);
+    for (index, binding) in bindings.iter().enumerate() {
+        for row in 0..rows {
+            let value = match &binding.value {
+                BindingValue::Single(value) => value.as_deref(),
+                BindingValue::Array(values) => values[row].as_deref(),
+            };
+            values.insert(
+                (row * width + index + 1).to_string(),
+                binding_scalar(&binding.binding_type, value)?,
+            );
+        }
+    }
+    Ok(values)
+}
+
+fn placeholder_mut(expr: &mut Expr) -> Option<&mut String> {
+    match expr {
+        Expr::Value(ValueWithSpan {
+            value: Value::Placeholder(placeholder),
+            ..
+        }) => Some(placeholder),
+        _ => None,
+    }
+}
+
+/// Number of rows bound by array bindings, `None` without any
+fn array_rows(bindings: &[QueryBinding]) -> Result<Option<usize>> {
+    let mut rows = None;
+    for binding in bindings {
+        if let BindingValue::Array(values) = &binding.value {
+            if rows.is_some_and(|rows| rows != values.len()) {
+                return ex_error::ArrayBindingsLengthMismatchSnafu.fail();
+            }
+            rows = Some(values.len());
+        }
+    }
+    Ok(rows)
+}
+
+fn binding_data_type(binding_type: &str) -> Result<DataType> {
+    match binding_type {
+        "FIXED" => Ok(DataType::Int64),
+        "REAL" => Ok(DataType::Float64),
+        "TEXT" => Ok(DataType::Utf8),
+        "BOOLEAN" => Ok(DataType::Boolean),
+        "DATE" => Ok(DataType::Date32),
+        "TIME" => Ok(DataType::Time64(TimeUnit::Nanosecond)),
+        "TIMESTAMP_NTZ" => Ok(DataType::Timestamp(TimeUnit::Nanosecond, None)),
+        "TIMESTAMP_LTZ" | "TIMESTAMP_TZ" => Ok(DataType::Timestamp(
+            TimeUnit::Nanosecond,
+            Some("UTC".into()),
+        )),
+        "BINARY" => Ok(DataType::Binary),
+        _ => ex_error::UnsupportedBindingTypeSnafu { binding_type }.fail(),
+    }
+}
+
+/// Converts a binding the way Snowflake drivers encode them: numbers and text as is,
+/// dates as epoch milliseconds, times and timestamps as nanoseconds and binaries as hex
+fn binding_scalar(binding_type: &str, value: Option<&str>) -> Result<ScalarValue> {
+    let binding_type = binding_type.to_ascii_uppercase();
+    let data_type = binding_data_type(&binding_type)?;
+    let Some(value) = value else {
+        return ScalarValue::try_from(&data_type).context(ex_error::DataFusionSnafu);
+    };
+    let invalid = || {
+        ex_error::InvalidBindingValueSnafu {
+            binding_type: binding_type.clone(),
+            value,
+        }
+        .build()
+    };
+    let int = |value: &str| value.trim().parse::<i64>().map_err(|_| invalid());
+    let scalar = match data_type {
+        DataType::Int64 => match value.parse::<i64>() {
+            Ok(value) => ScalarValue::Int64(Some(value)),
+            // Decimals keep their scale
+            Err(_) => {
+                let scale = value
+                    .split_once('.')
+                    .map_or(0, |(_, fraction)| fraction.len());
+                let scale = i8::try_from(scale).map_err(|_| invalid())?;
+                ScalarValue::try_from_string(value.to_string(), &DataType::Decimal128(38, scale))
+                    .map_err(|_| invalid())?
+            }
+        },
+        DataType::Float64 => ScalarValue::Float64(Some(value.parse().map_err(|_| invalid())?)),
+        DataType::Boolean => match value.to_ascii_lowercase().as_str() {
+            "true" | "t" | "1" => ScalarValue::Boolean(Some(true)),
+            "false" | "f" | "0" => ScalarValue::Boolean(Some(false)),
+            _ => return Err(invalid()),
+        },
+        DataType::Date32 => {
+            let days = int(value)?.div_euclid(MILLIS_PER_DAY);
+            ScalarValue::Date32(Some(i32::try_from(days).map_err(|_| invalid())?))
+        }
+        DataType::Time64(_) => ScalarValue::Time64Nanosecond(Some(int(value)?)),
+        DataType::Timestamp(_, None) => ScalarValue::TimestampNanosecond(Some(int(value)?), None),
+        DataType::Timestamp(_, Some(utc)) => match value.split_once(' ') {
+            Some((nanos, offset)) => {
+                let offset = int(offset)? - TZ_OFFSET_SHIFT;
+                let sign = if offset < 0 { '-' } else { '+' };
+                let offset = offset.abs();
+                ScalarValue::TimestampNanosecond(
+                    Some(int(nanos)?),
+                    Some(format!("{sign}{:02}:{:02}", offset / 60, offset % 60).into()),
+                )
+            }
+            None => ScalarValue::TimestampNanosecond(Some(int(value)?), Some(utc)),
+        },
+        DataType::Binary => ScalarValue::Binary(Some(decode_hex(value).ok_or_else(invalid)?)),
+        _ => ScalarValue::Utf8(Some(value.to_string())),
+    };
+    Ok(scalar)
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) || !value.is_ascii() {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&value[index..index + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use datafusion::sql::sqlparser::dialect::SnowflakeDialect;
+    use datafusion::sql::sqlparser::parser::Parser;
+
+    fn binding(binding_type: &str, value: BindingValue) -> QueryBinding {
+        QueryBinding {
+            binding_type: binding_type.to_string(),
+            value,
+        }
+    }
+
+    fn single(value: &str) -> BindingValue {
+        BindingValue::Single(Some(value.to_string()))
+    }
+
+    fn array(values: &[&str]) -> BindingValue {
+        BindingValue::Array(
+            values
+                .iter()
+                .map(|value| Some((*value).to_string()))
+                .collect(),
+        )
+    }
+
+    fn rewrite(sql: &str, bindings: &[QueryBinding]) -> Result<String> {
+        let mut statement = Parser::parse_sql(&SnowflakeDialect {}, sql)
+            .unwrap()
+            .remove(0);
+        rewrite_placeholders(&mut statement, bindings)?;
+        Ok(statement.to_string())
+    }
+
+    #[test]
+    fn test_rewrite_placeholders() {
+        let bindings = [binding("FIXED", single("1")), binding("TEXT", single("a"))];
+        assert_eq!(
+            rewrite("SELECT ? WHERE x = ?", &bindings).unwrap(),
+            "SELECT $1 WHERE x = $2"
+        );
+        assert_eq!(
+            rewrite("SELECT :2, :1, $name", &bindings).unwrap(),
+            "SELECT $2, $1, $name"
+        );
+
+        let bindings = [
+            binding("FIXED", array(&["1", "2", "3"])),
+            binding("TEXT", array(&["a", "b", "c"])),
+        ];
+        assert_eq!(
+            rewrite("INSERT INTO t VALUES (?, ?)", &bindings).unwrap(),
+            "INSERT INTO t VALUES ($1, $2), ($3, $4), ($5, $6)"
+        );
+        assert!(rewrite("SELECT ?, ?", &bindings).is_err());
+        assert!(
+            rewrite(
+                "INSERT INTO t VALUES (?, ?)",
+                &[
+                    binding("FIXED", array(&["1"])),
+                    binding("TEXT", array(&["a", "b"]))
+                ]
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_param_values() {
+        let values = param_values(&[
+            binding("FIXED", array(&["1", "2"])),
+            binding("TEXT", single("a")),
+        ])
+        .unwrap();
+        assert_eq!(values["1"], ScalarValue::Int64(Some(1)));
+        assert_eq!(values["2"], ScalarValue::Utf8(Some("a".to_string())));
+        assert_eq!(values["3"], ScalarValue::Int64(Some(2)));
+        assert_eq!(values["4"], ScalarValue::Utf8(Some("a".to_string())));
+    }
+
+    #[test]
+    fn test_binding_scalar() {
+        assert_eq!(
+            binding_scalar("fixed", Some("1.50")).unwrap(),
+            ScalarValue::Decimal128(Some(150), 38, 2)
+        );
+        assert_eq!(
+            binding_scalar("REAL", Some("1.5")).unwrap(),
+            ScalarValue::Float64(Some(1.5))
+        );
+        assert_eq!(
+            binding_scalar("BOOLEAN", Some("true")).unwrap(),
+            ScalarValue::Boolean(Some(true))
+        );
+        assert_eq!(
+            binding_scalar("DATE", Some("86400000")).unwrap(),
+            ScalarValue::Date32(Some(1))
+        );
+        assert_eq!(
+            binding_scalar("TIMESTAMP_TZ", Some("1000 1500")).unwrap(),
+            ScalarValue::TimestampNanosecond(Some(1000), Some("+01:00".into()))
+        );
+        assert_eq!(
+            binding_scalar("BINARY", Some("0aff")).unwrap(),
+            ScalarValue::Binary(Some(vec![10, 255]))
+        );
+        assert_eq!(
+            binding_scalar("TEXT", None).unwrap(),
+            ScalarValue::Utf8(None)
+        );
+        assert!(binding_scalar("FIXED", Some("x")).is_err());
+        assert!(binding_scalar("VARIANT", Some("1")).is_err());
+    }
+}
@@ -0,0 +1,916 @@
+//! `COPY INTO <table>` load bookkeeping: `ON_ERROR` / `VALIDATION_MODE` handling,
+//! file selection with load history, and the per-file result rows Snowflake returns
+//! for a load.
+use crate::error::{self as ex_error, Error, Result};
+use async_trait::async_trait;
+use core_metastore::{LoadHistory, TableIdent as MetastoreTableIdent};
+use datafusion::arrow::array::{Array, BooleanArray, Int64Array, RecordBatch, StringArray};
+use datafusion::arrow::compute::filter_record_batch;
+use datafusion::arrow::datatypes::{DataType, Field, Schema as ArrowSchema, SchemaRef};
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::streaming::PartitionStream;
+use datafusion_common::DataFusionError;
+use futures::TryStreamExt;
+use object_store::path::Path;
+use object_store::{ObjectMeta, ObjectStore};
+use regex::Regex;
+use snafu::ResultExt;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex, PoisonError};
+
+pub const ON_ERROR_OPTION: &str = "ON_ERROR";
+pub const FORCE_OPTION: &str = "FORCE";
+pub const PURGE_OPTION: &str = "PURGE";
+/// Name the files of a load are scanned as, they aren't a table of the catalog
+pub const LOAD_SCAN_NAME: &str = "external_location";
+
+/// Boolean copy options such as `FORCE` and `PURGE`
+pub fn parse_bool_option(option: &str, value: Option<&str>) -> Result<bool> {
+    let Some(value) = value else {
+        return Ok(false);
+    };
+    match value.trim_matches('\'').to_ascii_uppercase().as_str() {
+        "TRUE" => Ok(true),
+        "FALSE" => Ok(false),
+        _ => ex_error::InvalidCopyOptionSnafu {
+            option,
+            value: value.to_string(),
+        }
+        .fail(),
+    }
+}
+
+/// `ON_ERROR` copy option
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OnError {
+    Continue,
+    SkipFile,
+    /// `SKIP_FILE_<num>`: skip files with at least `num` error rows
+    SkipFileNum(usize),
+    /// `SKIP_FILE_<num>%`: skip files with more than `num` percent error rows
+    SkipFilePercent(f64),
+    AbortStatement,
+}
+
+impl OnError {
+    pub fn parse(value: &str) -> Result<Self> {
+        let value = value.trim_matches('\'').to_ascii_uppercase();
+        let invalid = || {
+            ex_error::InvalidCopyOptionSnafu {
+                option: ON_ERROR_OPTION,
+                value: value.clone(),
+            }
+            .build()
+        };
+        match value.as_str() {
+            "CONTINUE" => Ok(Self::Continue),
+            "SKIP_FILE" => Ok(Self::SkipFile),
+            "ABORT_STATEMENT" => Ok(Self::AbortStatement),
+            other => {
+                let limit = other.strip_prefix("SKIP_FILE_").ok_or_else(invalid)?;
+                if let Some(percent) = limit.strip_suffix('%') {
+                    percent
+                        .parse()
+                        .ok()
+                        .filter(|percent: &f64| (0.0..=100.0).contains(percent))
+                        .map(Self::SkipFilePercent)
+                        .ok_or_else(invalid)
+                } else {
+                    limit.parse().map(Self::SkipFileNum).map_err(|_| invalid())
+                }
+            }
+        }
+    }
+
+    /// Whether a file with `errors` error rows out of `rows_parsed` is skipped entirely
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn skips_file(self, errors: usize, rows_parsed: usize) -> bool {
+        match self {
+            Self::Continue => false,
+            Self::SkipFile | Self::AbortStatement => errors > 0,
+            Self::SkipFileNum(limit) => errors >= limit,
+            Self::SkipFilePercent(percent) => errors as f64 * 100.0 > percent * rows_parsed as f64,
+        }
+    }
+
+    /// Whether rows of a file are held back until the whole file has been checked
+    #[must_use]
+    pub const fn buffers_file(self) -> bool {
+        matches!(
+            self,
+            Self::SkipFile | Self::SkipFileNum(_) | Self::SkipFilePercent(_)
+        )
+    }
+
+    /// The `error_limit` reported in the load results
+    #[must_use]
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn error_limit(self, rows_parsed: usize) -> usize {
+        match self {
+            Self::Continue => rows_parsed,
+            Self::SkipFile | Self::AbortStatement => 1,
+            Self::SkipFileNum(limit) => limit,
+            Self::SkipFilePercent(percent) => {
+                (rows_parsed as f64 * percent / 100.0).ceil() as usize
+            }
+        }
+    }
+}
+
+/// `VALIDATION_MODE`: validate the files and return the errors instead of loading
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationMode {
+    ReturnErrors,
+    ReturnAllErrors,
+}
+
+impl ValidationMode {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim_matches('\'').to_ascii_uppercase().as_str() {
+            "RETURN_ERRORS" => Ok(Self::ReturnErrors),
+            "RETURN_ALL_ERRORS" => Ok(Self::ReturnAllErrors),
+            _ => ex_error::InvalidCopyOptionSnafu {
+                option: "VALIDATION_MODE",
+                value,
+            }
+            .fail(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum LoadStatus {
+    Loaded,
+    LoadFailed,
+    PartiallyLoaded,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum ErrorCategory {
+    Parsing,
+    Conversion,
+}
+
+/// A record, or a whole file, that could not be loaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowError {
+    pub error: String,
+    pub category: ErrorCategory,
+    /// Line the record starts at, `None` for errors affecting the whole file
+    pub line: Option<usize>,
+    /// Data row number, header lines excluded
+    pub row_number: Option<usize>,
+    pub column_name: Option<String>,
+    pub rejected_record: Option<String>,
+}
+
+impl RowError {
+    /// An error that prevents reading the file at all
+    #[must_use]
+    pub const fn file(error: String) -> Self {
+        Self {
+            error,
+            category: ErrorCategory::Parsing,
+            line: None,
+            row_number: None,
+            column_name: None,
+            rejected_record: None,
+        }
+    }
+}
+
+/// Outcome of loading a single file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileLoadResult {
+    pub file: String,
+    pub rows_parsed: usize,
+    pub rows_loaded: usize,
+    pub error_limit: usize,
+    pub errors: Vec<RowError>,
+}
+
+impl FileLoadResult {
+    #[must_use]
+    pub fn status(&self) -> LoadStatus {
+        if self.errors.is_empty() {
+            LoadStatus::Loaded
+        } else if self.rows_loaded == 0 {
+            LoadStatus::LoadFailed
+        } else {
+            LoadStatus::PartiallyLoaded
+        }
+    }
+}
+
+/// Per-file results of a load streamed into the insert, recorded as the files are
+/// read. Complete once the insert plan has run.
+#[derive(Debug, Default)]
+pub struct LoadResults {
+    files: Mutex<Vec<FileLoadResult>>,
+    /// The error that aborted the load with `ON_ERROR = ABORT_STATEMENT`
+    abort: Mutex<Option<Error>>,
+}
+
+impl LoadResults {
+    pub fn push(&self, result: FileLoadResult) {
+        self.files
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(result);
+    }
+
+    /// Keeps the error aborting the load, the insert plan fails with its text only
+    pub fn abort(&self, error: Error) -> DataFusionError {
+        let stream_error = DataFusionError::Execution(error.to_string());
+        *self.abort.lock().unwrap_or_else(PoisonError::into_inner) = Some(error);
+        stream_error
+    }
+
+    #[must_use]
+    pub fn take_abort(&self) -> Option<Error> {
+        self.abort
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+    }
+
+    #[must_use]
+    pub fn take(&self) -> Vec<FileLoadResult> {
+        std::mem::take(&mut *self.files.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+/// Columns of the rows a load streams into the insert: the target columns, all
+/// nullable, so that NULLs in non-nullable columns are reported per row by the
+/// [`LoadStream`] instead of failing the plan
+#[must_use]
+pub fn load_stream_schema(target: &SchemaRef) -> SchemaRef {
+    Arc::new(ArrowSchema::new(
+        target
+            .fields()
+            .iter()
+            .map(|field| field.as_ref().clone().with_nullable(true))
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// Rows of a single file being loaded, converted to the target columns
+#[async_trait]
+pub trait FileRows: Send {
+    /// The valid rows of the next batch along with the errors of its bad rows,
+    /// `None` once the file is read to the end. NULLs left in non-nullable columns
+    /// are reported by the [`LoadStream`], numbered after the rows parsed before.
+    async fn next_rows(&mut self) -> Result<Option<(RecordBatch, Vec<RowError>)>>;
+
+    /// Data rows read so far
+    fn rows_parsed(&self) -> usize;
+}
+
+/// Opens the files of a load in a given file format
+#[async_trait]
+pub trait FileRowsSource: Debug + Send + Sync {
+    async fn open(&self, file: &ObjectMeta, ctx: Arc<TaskContext>) -> Result<Box<dyn FileRows>>;
+}
+
+/// Rows to insert out of the files of a load, streamed as the files are read one after
+/// another. Bad rows are handled according to `ON_ERROR` and the outcome of each file
+/// is recorded in the [`LoadResults`]. With `SKIP_FILE` variants a file is only
+/// inserted once all of its rows have been checked, so that file is held in memory.
+#[derive(Debug, Clone)]
+pub struct LoadStream {
+    source: Arc<dyn FileRowsSource>,
+    /// Columns of the table loaded into, their nullability is checked per row
+    target: SchemaRef,
+    schema: SchemaRef,
+    files: Vec<ObjectMeta>,
+    on_error: OnError,
+    /// `VALIDATION_MODE`: files are checked, nothing is inserted
+    validate: bool,
+    results: Arc<LoadResults>,
+}
+
+impl LoadStream {
+    #[must_use]
+    pub fn new(
+        source: Arc<dyn FileRowsSource>,
+        target: SchemaRef,
+        files: Vec<ObjectMeta>,
+        on_error: OnError,
+        validate: bool,
+        results: Arc<LoadResults>,
+    ) -> Self {
+        Self {
+            source,
+            schema: load_stream_schema(&target),
+            target,
+            files,
+            on_error,
+            validate,
+            results,
+        }
+    }
+
+    fn aborts(&self) -> bool {
+        self.on_error == OnError::AbortStatement && !self.validate
+    }
+
+    /// The valid rows of the next batch of `file`, opened on first use. Bad rows are
+    /// added to `errors`, or fail the load with `ABORT_STATEMENT`.
+    async fn next_rows(
+        &self,
+        file: &ObjectMeta,
+        rows: &mut Option<Box<dyn FileRows>>,
+        errors: &mut Vec<RowError>,
+        ctx: &Arc<TaskContext>,
+    ) -> Result<Option<RecordBatch>> {
+        let opened = match rows.take() {
+            Some(opened) => opened,
+            None => self.source.open(file, ctx.clone()).await?,
+        };
+        let rows = rows.insert(opened);
+        let rows_before = rows.rows_parsed();
+        let Some((batch, mut batch_errors)) = rows.next_rows().await? else {
+            return Ok(None);
+        };
+        let (batch, null_errors) = null_errors(batch, &self.target, rows_before)?;
+        if !null_errors.is_empty() {
+            batch_errors.extend(null_errors);
+            batch_errors.sort_by_key(|error| error.line);
+        }
+        if self.aborts() {
+            if let Some(error) = batch_errors.first() {
+                return ex_error::CopyIntoFileLoadSnafu {
+                    error: error.error.clone(),
+                    file: file.location.to_string(),
+                    line: error.line.unwrap_or_default(),
+                }
+                .fail();
+            }
+        }
+        errors.extend(batch_errors);
+        Ok(Some(batch))
+    }
+}
+
+/// Drops the rows with NULLs in non-nullable target columns and reports them, the rows
+/// of the batch are numbered after the `rows_before` rows parsed before it
+fn null_errors(
+    batch: RecordBatch,
+    target: &SchemaRef,
+    rows_before: usize,
+) -> Result<(RecordBatch, Vec<RowError>)> {
+    let mut valid = vec![true; batch.num_rows()];
+    let mut errors = Vec::new();
+    for (column, field) in batch.columns().iter().zip(target.fields()) {
+        if field.is_nullable() || column.null_count() == 0 {
+            continue;
+        }
+        for (row, valid) in valid.iter_mut().enumerate() {
+            if !*valid || !column.is_null(row) {
+                continue;
+            }
+            *valid = false;
+            let row_number = rows_before + row + 1;
+            errors.push(RowError {
+                error: ex_error::NullInNonNullableColumnSnafu {
+                    column: field.name(),
+                }
+                .build()
+                .to_string(),
+                category: ErrorCategory::Conversion,
+                line: Some(row_number),
+                row_number: Some(row_number),
+                column_name: Some(field.name().clone()),
+                rejected_record: None,
+            });
+        }
+    }
+    if errors.is_empty() {
+        return Ok((batch, errors));
+    }
+    errors.sort_by_key(|error| error.row_number);
+    let rows =
+        filter_record_batch(&batch, &BooleanArray::from(valid)).context(ex_error::ArrowSnafu)?;
+    Ok((rows, errors))
+}
+
+impl PartitionStream for LoadStream {
+    fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    fn execute(&self, ctx: Arc<TaskContext>) -> SendableRecordBatchStream {
+        let load = self.clone();
+        let batches = async_stream::stream! {
+            for file in &load.files {
+                let mut errors = Vec::new();
+                let mut buffered = Vec::new();
+                let mut rows_loaded = 0;
+                let mut rows = None;
+                let failure = loop {
+                    match load.next_rows(file, &mut rows, &mut errors, &ctx).await {
+                        Ok(Some(batch)) if load.validate || batch.num_rows() == 0 => {}
+                        Ok(Some(batch)) if load.on_error.buffers_file() => buffered.push(batch),
+                        Ok(Some(batch)) => {
+                            rows_loaded += batch.num_rows();
+                            yield Ok(batch);
+                        }
+                        Ok(None) => break None,
+                        Err(error) => break Some(error),
+                    }
+                };
+                let rows_parsed = rows.as_ref().map_or(0, |rows| rows.rows_parsed());
+                match failure {
+                    Some(error) if load.aborts() => {
+                        yield Err(load.results.abort(error));
+                        return;
+                    }
+                    // A file that cannot be read to the end keeps the rows inserted already
+                    Some(error) => errors.push(RowError::file(error.to_string())),
+                    None if !load.on_error.skips_file(errors.len(), rows_parsed) => {
+                        for batch in buffered {
+                            rows_loaded += batch.num_rows();
+                            yield Ok(batch);
+                        }
+                    }
+                    None => {}
+                }
+                load.results.push(FileLoadResult {
+                    file: file.location.to_string(),
+                    rows_parsed,
+                    rows_loaded,
+                    error_limit: load.on_error.error_limit(rows_parsed),
+                    errors,
+                });
+            }
+        };
+        Box::pin(RecordBatchStreamAdapter::new(self.schema.clone(), batches))
+    }
+}
+
+/// Lists the files referenced by a `COPY INTO` location, either a single file
+/// or every file below a prefix
+pub async fn list_files(
+    store: &Arc<dyn ObjectStore>,
+    url: &ListingTableUrl,
+) -> Result<Vec<ObjectMeta>> {
+    if !url.is_collection() {
+        if let Ok(meta) = store.head(url.prefix()).await {
+            return Ok(vec![meta]);
+        }
+    }
+    let mut files: Vec<ObjectMeta> = store
+        .list(Some(url.prefix()))
+        .try_collect()
+        .await
+        .context(ex_error::ObjectStoreSnafu)?;
+    files.sort_by(|a, b| a.location.cmp(&b.location));
+    Ok(files)
+}
+
+/// Narrows the listed files down to the ones named in `FILES = (...)`, relative to
+/// the location, and to the ones whose full path matches the `PATTERN` regex
+pub fn filter_files(
+    files: Vec<ObjectMeta>,
+    url: &ListingTableUrl,
+    names: Option<&[String]>,
+    pattern: Option<&str>,
+) -> Result<Vec<ObjectMeta>> {
+    let names = names.map(|names| {
+        names
+            .iter()
+            .map(|name| Path::from(format!("{}/{name}", url.prefix())))
+            .collect::<Vec<_>>()
+    });
+    let pattern = pattern
+        .map(|pattern| {
+            Regex::new(&format!("^(?:{pattern})$")).map_err(|_| {
+                ex_error::InvalidCopyOptionSnafu {
+                    option: "PATTERN",
+                    value: pattern.to_string(),
+                }
+                .build()
+            })
+        })
+        .transpose()?;
+    Ok(files
+        .into_iter()
+        .filter(|file| {
+            names
+                .as_ref()
+                .is_none_or(|names| names.contains(&file.location))
+        })
+        .filter(|file| {
+            pattern
+                .as_ref()
+                .is_none_or(|pattern| pattern.is_match(file.location.as_ref()))
+        })
+        .collect())
+}
+
+/// Whether the load history says `file` was already loaded and has not changed since.
+/// Files that failed to load are attempted again.
+#[must_use]
+pub fn already_loaded(history: Option<&LoadHistory>, file: &ObjectMeta) -> bool {
+    history.is_some_and(|history| {
+        history.status != LoadStatus::LoadFailed.to_string()
+            && history.is_same_file(file.size, file.e_tag.as_deref(), file.last_modified)
+    })
+}
+
+/// Full URL of a listed file, identifying it in the load history
+#[must_use]
+pub fn file_name(url: &ListingTableUrl, file: &ObjectMeta) -> String {
+    format!("{}{}", url.object_store().as_str(), file.location)
+}
+
+/// Load history entry recording the outcome of loading `file` into `table`
+#[must_use]
+pub fn load_history(
+    table: MetastoreTableIdent,
+    url: &ListingTableUrl,
+    file: &ObjectMeta,
+    result: &FileLoadResult,
+) -> LoadHistory {
+    let first_error = result.errors.first();
+    LoadHistory {
+        table,
+        file: file_name(url, file),
+        size: file.size,
+        etag: file.e_tag.clone(),
+        last_modified: file.last_modified,
+        status: result.status().to_string(),
+        rows_parsed: to_u64(result.rows_parsed),
+        rows_loaded: to_u64(result.rows_loaded),
+        error_count: to_u64(result.errors.len()),
+        error_limit: to_u64(result.error_limit),
+        first_error: first_error.map(|error| error.error.clone()),
+        first_error_line: first_error.and_then(|error| error.line).map(to_u64),
+        first_error_column_name: first_error.and_then(|error| error.column_name.clone()),
+    }
+}
+
+/// Start of the names of every file listed below `url`
+#[must_use]
+pub fn location_prefix(url: &ListingTableUrl) -> String {
+    format!("{}{}", url.object_store().as_str(), url.prefix())
+}
+
+/// URL of a single listed file, to scan it on its own
+pub fn file_url(url: &ListingTableUrl, file: &ObjectMeta) -> Result<ListingTableUrl> {
+    ListingTableUrl::parse(format!("{}{}", url.object_store().as_str(), file.location))
+        .context(ex_error::DataFusionSnafu)
+}
+
+fn to_i64(value: usize) -> i64 {
+    i64::try_from(value).unwrap_or(i64::MAX)
+}
+
+fn to_u64(value: usize) -> u64 {
+    u64::try_from(value).unwrap_or(u64::MAX)
+}
+
+/// Snowflake's `COPY INTO <table>` result: one row per file
+pub fn load_results_batch(results: &[FileLoadResult]) -> Result<RecordBatch> {
+    let schema = Arc::new(ArrowSchema::new(vec![
+        Field::new("file", DataType::Utf8, false),
+        Field::new("status", DataType::Utf8, false),
+        Field::new("rows_parsed", DataType::Int64, false),
+        Field::new("rows_loaded", DataType::Int64, false),
+        Field::new("error_limit", DataType::Int64, false),
+        Field::new("errors_seen", DataType::Int64, false),
+        Field::new("first_error", DataType::Utf8, true),
+        Field::new("first_error_line", DataType::Int64, true),
+        Field::new("first_error_column_name", DataType::Utf8, true),
+    ]));
+    let first_errors = results.iter().map(|r| r.errors.first()).collect::<Vec<_>>();
+    RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(StringArray::from_iter_values(
+                results.iter().map(|r| r.file.clone()),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                results.iter().map(|r| r.status().to_string()),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                results.iter().map(|r| to_i64(r.rows_parsed)),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                results.iter().map(|r| to_i64(r.rows_loaded)),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                results.iter().map(|r| to_i64(r.error_limit)),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                results.iter().map(|r| to_i64(r.errors.len())),
+            )),
+            Arc::new(StringArray::from_iter(
+                first_errors.iter().map(|e| e.map(|e| e.error.clone())),
+            )),
+            Arc::new(Int64Array::from_iter(
+                first_errors
+                    .iter()
+                    .map(|e| e.and_then(|e| e.line).map(to_i64)),
+            )),
+            Arc::new(StringArray::from_iter(
+                first_errors
+                    .iter()
+                    .map(|e| e.and_then(|e| e.column_name.clone())),
+            )),
+        ],
+    )
+    .context(ex_error::ArrowSnafu)
+}
+
+/// `VALIDATION_MODE = RETURN_ERRORS` result: one row per error
+pub fn validation_errors_batch(results: &[FileLoadResult]) -> Result<RecordBatch> {
+    let schema = Arc::new(ArrowSchema::new(vec![
+        Field::new("error", DataType::Utf8, false),
+        Field::new("file", DataType::Utf8, false),
+        Field::new("line", DataType::Int64, true),
+        Field::new("category", DataType::Utf8, false),
+        Field::new("column_name", DataType::Utf8, true),
+        Field::new("row_number", DataType::Int64, true),
+        Field::new("rejected_record", DataType::Utf8, true),
+    ]));
+    let errors = results
+        .iter()
+        .flat_map(|r| r.errors.iter().map(move |e| (r.file.as_str(), e)))
+        .collect::<Vec<_>>();
+    RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(StringArray::from_iter_values(
+                errors.iter().map(|(_, e)| e.error.clone()),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                errors.iter().map(|(file, _)| *file),
+            )),
+            Arc::new(Int64Array::from_iter(
+                errors.iter().map(|(_, e)| e.line.map(to_i64)),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                errors.iter().map(|(_, e)| e.category.to_string()),
+            )),
+            Arc::new(StringArray::from_iter(
+                errors.iter().map(|(_, e)| e.column_name.clone()),
+            )),
+            Arc::new(Int64Array::from_iter(
+                errors.iter().map(|(_, e)| e.row_number.map(to_i64)),
+            )),
+            Arc::new(StringArray::from_iter(
+                errors.iter().map(|(_, e)| e.rejected_record.clone()),
+            )),
+        ],
+    )
+    .context(ex_error::ArrowSnafu)
+}
+
+/// Result of a `COPY INTO` that found nothing to load
+pub fn no_files_batch() -> Result<RecordBatch> {
+    let schema: SchemaRef = Arc::new(ArrowSchema::new(vec![Field::new(
+        "status",
+        DataType::Utf8,
+        false,
+    )]));
+    RecordBatch::try_new(
+        schema,
+        vec![Arc::new(StringArray::from(vec![
+            "Copy executed with 0 files processed.",
+        ]))],
+    )
+    .context(ex_error::ArrowSnafu)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_on_error_parse() {
+        assert_eq!(OnError::parse("continue").unwrap(), OnError::Continue);
+        assert_eq!(OnError::parse("'SKIP_FILE'").unwrap(), OnError::SkipFile);
+        assert_eq!(
+            OnError::parse("SKIP_FILE_3").unwrap(),
+            OnError::SkipFileNum(3)
+        );
+        assert_eq!(
+            OnError::parse("SKIP_FILE_10%").unwrap(),
+            OnError::SkipFilePercent(10.0)
+        );
+        assert_eq!(
+            OnError::parse("ABORT_STATEMENT").unwrap(),
+            OnError::AbortStatement
+        );
+        assert!(OnError::parse("SKIP_FILE_x").is_err());
+        assert!(OnError::parse("IGNORE").is_err());
+    }
+
+    #[test]
+    fn test_on_error_skips_file() {
+        assert!(!OnError::Continue.skips_file(5, 10));
+        assert!(OnError::SkipFile.skips_file(1, 10));
+        assert!(!OnError::SkipFile.skips_file(0, 10));
+        assert!(!OnError::SkipFileNum(3).skips_file(2, 10));
+        assert!(OnError::SkipFileNum(3).skips_file(3, 10));
+        assert!(!OnError::SkipFilePercent(20.0).skips_file(2, 10));
+        assert!(OnError::SkipFilePercent(20.0).skips_file(3, 10));
+        assert_eq!(OnError::SkipFilePercent(25.0).error_limit(10), 3);
+        assert_eq!(OnError::Continue.error_limit(10), 10);
+    }
+
+    fn object(location: &str) -> ObjectMeta {
+        ObjectMeta {
+            location: Path::from(location),
+            last_modified: chrono::DateTime::UNIX_EPOCH,
+            size: 10,
+            e_tag: Some("1".to_string()),
+            version: None,
+        }
+    }
+
+    #[test]
+    fn test_filter_files() {
+        let url = ListingTableUrl::parse("s3://bucket/data/").unwrap();
+        let files = || {
+            vec![
+                object("data/a.csv"),
+                object("data/b.csv"),
+                object("data/sub/c.json"),
+            ]
+        };
+        let locations = |files: Vec<ObjectMeta>| {
+            files
+                .into_iter()
+                .map(|file| file.location.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let names = ["b.csv".to_string(), "sub/c.json".to_string()];
+        assert_eq!(
+            locations(filter_files(files(), &url, Some(&names), None).unwrap()),
+            ["data/b.csv", "data/sub/c.json"]
+        );
+        assert_eq!(
+            locations(filter_files(files(), &url, None, Some(".*[.]csv")).unwrap()),
+            ["data/a.csv", "data/b.csv"]
+        );
+        // The pattern has to match the whole path
+        assert!(
+            filter_files(files(), &url, None, Some("a"))
+                .unwrap()
+                .is_empty()
+        );
+        assert!(filter_files(files(), &url, None, Some("(")).is_err());
+    }
+
+    #[test]
+    fn test_already_loaded() {
+        let url = ListingTableUrl::parse("s3://bucket/data/").unwrap();
+        let file = object("data/a.csv");
+        let mut result = FileLoadResult {
+            file: file.location.to_string(),
+            rows_parsed: 1,
+            rows_loaded: 1,
+            error_limit: 1,
+            errors: vec![],
+        };
+        let table = MetastoreTableIdent::new("db", "sch", "t");
+        let history = load_history(table.clone(), &url, &file, &result);
+        assert_eq!(history.file, "s3://bucket/data/a.csv");
+        assert!(history.file.starts_with(&location_prefix(&url)));
+        assert!(already_loaded(Some(&history), &file));
+        assert!(!already_loaded(None, &file));
+
+        let mut changed = object("data/a.csv");
+        changed.e_tag = Some("2".to_string());
+        assert!(!already_loaded(Some(&history), &changed));
+
+        result.rows_loaded = 0;
+        result.errors.push(RowError::file("bad".to_string()));
+        let failed = load_history(table, &url, &file, &result);
+        assert!(!already_loaded(Some(&failed), &file));
+    }
+
+    #[test]
+    fn test_load_status() {
+        let mut result = FileLoadResult {
+            file: "f.csv".to_string(),
+            rows_parsed: 2,
+            rows_loaded: 2,
+            error_limit: 1,
+            errors: vec![],
+        };
+        assert_eq!(result.status(), LoadStatus::Loaded);
+        result.errors.push(RowError::file("bad".to_string()));
+        result.rows_loaded = 1;
+        assert_eq!(result.status(), LoadStatus::PartiallyLoaded);
+        result.rows_loaded = 0;
+        assert_eq!(result.status(), LoadStatus::LoadFailed);
+        assert_eq!(result.status().to_string(), "LOAD_FAILED");
+
+        let batch = load_results_batch(&[result]).unwrap();
+        assert_eq!(batch.num_rows(), 1);
+        assert_eq!(batch.num_columns(), 9);
+    }
+    /// Rows of a single batch, whatever the file
+    #[derive(Debug)]
+    struct BatchRowsSource(RecordBatch);
+
+    struct BatchRows {
+        batch: Option<RecordBatch>,
+        rows_parsed: usize,
+    }
+
+    #[async_trait]
+    impl FileRowsSource for BatchRowsSource {
+        async fn open(
+            &self,
+            _file: &ObjectMeta,
+            _ctx: Arc<TaskContext>,
+        ) -> Result<Box<dyn FileRows>> {
+            Ok(Box::new(BatchRows {
+                batch: Some(self.0.clone()),
+                rows_parsed: 0,
+            }))
+        }
+    }
+
+    #[async_trait]
+    impl FileRows for BatchRows {
+        async fn next_rows(&mut self) -> Result<Option<(RecordBatch, Vec<RowError>)>> {
+            let Some(batch) = self.batch.take() else {
+                return Ok(None);
+            };
+            self.rows_parsed += batch.num_rows();
+            Ok(Some((batch, vec![])))
+        }
+
+        fn rows_parsed(&self) -> usize {
+            self.rows_parsed
+        }
+    }
+
+    async fn load_rows(
+        on_error: OnError,
+        validate: bool,
+    ) -> datafusion_common::Result<(usize, Vec<FileLoadResult>)> {
+        let target: SchemaRef = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            load_stream_schema(&target),
+            vec![
+                Arc::new(Int64Array::from(vec![Some(1), None, Some(3)])),
+                Arc::new(StringArray::from(vec![Some("a"), Some("b"), None])),
+            ],
+        )
+        .unwrap();
+        let results = Arc::new(LoadResults::default());
+        let stream = LoadStream::new(
+            Arc::new(BatchRowsSource(batch)),
+            target,
+            vec![object("data/a.parquet")],
+            on_error,
+            validate,
+            results.clone(),
+        );
+        let batches: Vec<RecordBatch> = stream
+            .execute(Arc::new(TaskContext::default()))
+            .try_collect()
+            .await?;
+        let rows = batches.iter().map(RecordBatch::num_rows).sum();
+        Ok((rows, results.take()))
+    }
+
+    #[tokio::test]
+    async fn test_load_stream_non_nullable_columns() {
+        // NULLs in non-nullable columns reject their rows only
+        let (rows, results) = load_rows(OnError::Continue, false).await.unwrap();
+        assert_eq!(rows, 2);
+        assert_eq!(results[0].status(), LoadStatus::PartiallyLoaded);
+        let error = &results[0].errors[0];
+        assert_eq!(results[0].errors.len(), 1);
+        assert_eq!(error.row_number, Some(2));
+        assert_eq!(error.column_name.as_deref(), Some("id"));
+        assert_eq!(error.error, "NULL result in a non-nullable column id");
+
+        let (rows, results) = load_rows(OnError::AbortStatement, true).await.unwrap();
+        assert_eq!(rows, 0);
+        assert_eq!(results[0].errors.len(), 1);
+
+        assert!(load_rows(OnError::AbortStatement, false).await.is_err());
+    }
+}
@@ -0,0 +1,1376 @@
+//! Snowflake compatible CSV decoding for `COPY INTO <table>`.
+//!
+//! Arrow's CSV reader covers only a part of Snowflake's CSV file format options:
+//! a single header line, single byte delimiters, UTF-8 input and no `NULL_IF` or
+//! `TRIM_SPACE` handling. CSV files are therefore decoded here into string columns,
+//! matched to the target table by position, and converted to the target types by
+//! [`CsvConverter`].
+//!
+//! Files are decoded and parsed as they are downloaded, [`CsvFileReader`] keeps only
+//! the records of the batch being built in memory.
+use crate::copy_into::{self, ErrorCategory, FileRows, FileRowsSource, RowError};
+use crate::error::{self as ex_error, Error, Result};
+use crate::file_format::FileFormatSpec;
+use async_trait::async_trait;
+use bytes::Bytes;
+use core_metastore::FileFormatType;
+use datafusion::arrow::array::{
+    Array, ArrayRef, AsArray, BooleanArray, RecordBatch, StringBuilder,
+};
+use datafusion::arrow::compute::filter_record_batch;
+use datafusion::arrow::datatypes::{DataType, Field, Schema as ArrowSchema, SchemaRef};
+use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
+use datafusion::execution::{FunctionRegistry, TaskContext};
+use datafusion::physical_expr::PhysicalExpr;
+use datafusion::prelude::SessionContext;
+use datafusion_common::{Column, DFSchema, DataFusionError};
+use datafusion_expr::{Expr, TryCast, lit};
+use encoding_rs::{CoderResult, Decoder, Encoding};
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use object_store::path::Path;
+use object_store::{ObjectMeta, ObjectStore};
+use snafu::{OptionExt, ResultExt};
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Data records per batch read from a file
+const BATCH_ROWS: usize = 8192;
+
+/// A parsed record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvRecord {
+    /// Line the record starts at
+    pub line: usize,
+    /// Byte range of the record in the file contents, delimiter excluded
+    pub span: Range<usize>,
+    /// `None` fields are SQL NULLs
+    pub fields: Vec<Option<String>>,
+}
+
+/// CSV file format options of `COPY INTO`, with Snowflake defaults.
+#[derive(Debug, Clone)]
+pub struct CsvLoadOptions {
+    pub skip_header: usize,
+    /// `None` for `FIELD_DELIMITER = NONE`
+    pub field_delimiter: Option<String>,
+    /// `None` for `RECORD_DELIMITER = NONE`
+    pub record_delimiter: Option<String>,
+    pub field_optionally_enclosed_by: Option<char>,
+    pub escape: Option<char>,
+    pub escape_unenclosed_field: Option<char>,
+    pub null_if: Vec<String>,
+    pub empty_field_as_null: bool,
+    pub trim_space: bool,
+    pub date_format: Option<String>,
+    pub timestamp_format: Option<String>,
+    pub encoding: &'static Encoding,
+    pub error_on_column_count_mismatch: bool,
+    /// `None` for `COMPRESSION = AUTO`
+    pub compression: Option<String>,
+}
+
+impl Default for CsvLoadOptions {
+    fn default() -> Self {
+        Self {
+            skip_header: 0,
+            field_delimiter: Some(",".to_string()),
+            record_delimiter: Some("\n".to_string()),
+            field_optionally_enclosed_by: None,
+            escape: None,
+            escape_unenclosed_field: Some('\\'),
+            null_if: vec!["\\N".to_string()],
+            empty_field_as_null: true,
+            trim_space: false,
+            date_format: None,
+            timestamp_format: None,
+            encoding: encoding_rs::UTF_8,
+            error_on_column_count_mismatch: true,
+            compression: None,
+        }
+    }
+}
+
+impl CsvLoadOptions {
+    /// Returns `None` for non CSV formats and for `PARSE_HEADER = TRUE`: those are
+    /// loaded by column name through DataFusion's own readers.
+    pub fn from_spec(spec: &FileFormatSpec) -> Result<Option<Self>> {
+        if spec.format_type != Some(FileFormatType::Csv)
+            || spec
+                .get("parse_header")
+                .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+        {
+            return Ok(None);
+        }
+        let mut options = Self::default();
+        if let Some(value) = spec.get("skip_header") {
+            options.skip_header = value
+                .trim()
+                .parse()
+                .ok()
+                .context(invalid_option("SKIP_HEADER", value))?;
+        }
+        if let Some(value) = spec.get("field_delimiter") {
+            options.field_delimiter = parse_string_option(value);
+        }
+        if let Some(value) = spec.get("record_delimiter") {
+            options.record_delimiter = parse_string_option(value);
+        }
+        if let Some(value) = spec.get("field_optionally_enclosed_by") {
+            options.field_optionally_enclosed_by =
+                parse_char_option("FIELD_OPTIONALLY_ENCLOSED_BY", value)?;
+        }
+        if let Some(value) = spec.get("escape") {
+            options.escape = parse_char_option("ESCAPE", value)?;
+        }
+        if let Some(value) = spec.get("escape_unenclosed_field") {
+            options.escape_unenclosed_field = parse_char_option("ESCAPE_UNENCLOSED_FIELD", value)?;
+        }
+        if let Some(values) = spec.get_list("null_if") {
+            options.null_if = values;
+        }
+        if let Some(value) = spec.get("empty_field_as_null") {
+            options.empty_field_as_null = parse_bool_option("EMPTY_FIELD_AS_NULL", value)?;
+        }
+        if let Some(value) = spec.get("trim_space") {
+            options.trim_space = parse_bool_option("TRIM_SPACE", value)?;
+        }
+        if let Some(value) = spec.get("error_on_column_count_mismatch") {
+            options.error_on_column_count_mismatch =
+                parse_bool_option("ERROR_ON_COLUMN_COUNT_MISMATCH", value)?;
+        }
+        options.date_format = parse_format_option(spec.get("date_format"));
+        options.timestamp_format = parse_format_option(spec.get("timestamp_format"));
+        if let Some(value) = spec.get("encoding") {
+            options.encoding =
+                parse_encoding(value).context(ex_error::UnsupportedEncodingSnafu {
+                    encoding: value.to_string(),
+                })?;
+        }
+        options.compression = spec
+            .get("compression")
+            .filter(|value| !value.eq_ignore_ascii_case("auto"))
+            .map(str::to_ascii_uppercase);
+        Ok(Some(options))
+    }
+
+    /// `COMPRESSION = AUTO` detects the compression from the file extension
+    fn compression_for(&self, location: &Path) -> Result<FileCompressionType> {
+        match self.compression.as_deref() {
+            None => Ok(match location.extension() {
+                Some("gz") => FileCompressionType::GZIP,
+                Some("bz2") => FileCompressionType::BZIP2,
+                Some("xz") => FileCompressionType::XZ,
+                Some("zst") => FileCompressionType::ZSTD,
+                _ => FileCompressionType::UNCOMPRESSED,
+            }),
+            Some("NONE") => Ok(FileCompressionType::UNCOMPRESSED),
+            Some(compression) => {
+                FileCompressionType::from_str(compression).context(ex_error::DataFusionSnafu)
+            }
+        }
+    }
+}
+
+fn invalid_option(
+    option: &str,
+    value: &str,
+) -> ex_error::InvalidFileFormatOptionSnafu<String, String> {
+    ex_error::InvalidFileFormatOptionSnafu {
+        option: option.to_string(),
+        value: value.to_string(),
+    }
+}
+
+fn is_none_value(value: &str) -> bool {
+    value.eq_ignore_ascii_case("none")
+}
+
+/// Delimiters may be given as escape sequences (`'\t'`, `'\\t'`) or hex codes (`'0x7C'`)
+fn parse_string_option(value: &str) -> Option<String> {
+    if is_none_value(value) {
+        return None;
+    }
+    if let Some(hex) = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        if let Some(ch) = u32::from_str_radix(hex, 16).ok().and_then(char::from_u32) {
+            return Some(ch.to_string());
+        }
+    }
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            match chars.next() {
+                Some('t') => result.push('\t'),
+                Some('n') => result.push('\n'),
+                Some('r') => result.push('\r'),
+                Some(other) => result.push(other),
+                None => result.push('\\'),
+            }
+        } else {
+            result.push(ch);
+        }
+    }
+    Some(result)
+}
+
+fn parse_char_option(option: &str, value: &str) -> Result<Option<char>> {
+    let Some(parsed) = parse_string_option(value) else {
+        return Ok(None);
+    };
+    let mut chars = parsed.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) => Ok(Some(ch)),
+        _ => invalid_option(option, value).fail(),
+    }
+}
+
+fn parse_bool_option(option: &str, value: &str) -> Result<bool> {
+    match value.to_ascii_uppercase().as_str() {
+        "TRUE" => Ok(true),
+        "FALSE" => Ok(false),
+        _ => invalid_option(option, value).fail(),
+    }
+}
+
+fn parse_format_option(value: Option<&str>) -> Option<String> {
+    value
+        .filter(|format| !format.eq_ignore_ascii_case("auto"))
+        .map(ToString::to_string)
+}
+
+/// Maps Snowflake encoding names (`UTF8`, `WINDOWS1252`, `ISO-8859-1`, ...) to
+/// their WHATWG counterparts
+fn parse_encoding(value: &str) -> Option<&'static Encoding> {
+    let upper = value.to_ascii_uppercase();
+    let label = match upper.as_str() {
+        "UTF8" => "utf-8".to_string(),
+        "UTF16" => "utf-16be".to_string(),
+        "UTF16BE" => "utf-16be".to_string(),
+        "UTF16LE" => "utf-16le".to_string(),
+        "EUCJP" => "euc-jp".to_string(),
+        "EUCKR" => "euc-kr".to_string(),
+        "SHIFTJIS" => "shift_jis".to_string(),
+        "KOI8R" => "koi8-r".to_string(),
+        _ => upper
+            .strip_prefix("WINDOWS")
+            .filter(|code| !code.starts_with('-'))
+            .map_or_else(|| upper.clone(), |code| format!("windows-{code}")),
+    };
+    Encoding::for_label(label.as_bytes())
+}
+
+/// Reads a single file batch by batch. Bytes are decompressed and decoded as they are
+/// downloaded, and only complete records are parsed: a record cut by the end of the
+/// data received so far is parsed again once the rest of it has been decoded.
+pub struct CsvFileReader {
+    file: String,
+    bytes: BoxStream<'static, datafusion_common::Result<Bytes>>,
+    decoder: Decoder,
+    options: CsvLoadOptions,
+    schema: SchemaRef,
+    /// Decoded text not returned in a batch yet, starting at a record boundary
+    pending: String,
+    eof: bool,
+    /// Line `pending` starts at
+    line: usize,
+    /// `SKIP_HEADER` records left to skip
+    header: usize,
+    rows_parsed: usize,
+}
+
+impl CsvFileReader {
+    pub async fn open(
+        store: &Arc<dyn ObjectStore>,
+        file: &ObjectMeta,
+        options: &CsvLoadOptions,
+        schema: SchemaRef,
+    ) -> Result<Self> {
+        let compression = options.compression_for(&file.location)?;
+        let stream = store
+            .get(&file.location)
+            .await
+            .context(ex_error::ObjectStoreSnafu)?
+            .into_stream()
+            .map_err(DataFusionError::from)
+            .boxed();
+        let bytes = compression
+            .convert_stream(stream)
+            .context(ex_error::DataFusionSnafu)?;
+        Ok(Self::new(
+            file.location.to_string(),
+            bytes,
+            options.clone(),
+            schema,
+        ))
+    }
+
+    fn new(
+        file: String,
+        bytes: BoxStream<'static, datafusion_common::Result<Bytes>>,
+        options: CsvLoadOptions,
+        schema: SchemaRef,
+    ) -> Self {
+        Self {
+            file,
+            bytes,
+            decoder: options.encoding.new_decoder(),
+            header: options.skip_header,
+            options,
+            schema,
+            pending: String::new(),
+            eof: false,
+            line: 1,
+            rows_parsed: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// Data records read so far, header lines excluded
+    #[must_use]
+    pub const fn rows_parsed(&self) -> usize {
+        self.rows_parsed
+    }
+
+    /// Next batch of up to `BATCH_ROWS` records, `None` once the file is exhausted
+    pub async fn next_batch(&mut self) -> Result<Option<CsvBatch>> {
+        // Header records are parsed and dropped, they don't count towards the batch size
+        let limit = BATCH_ROWS + self.header;
+        let mut records = Vec::new();
+        let mut parsed = 0;
+        loop {
+            parsed = parse_records_from(
+                &self.pending,
+                parsed,
+                &mut self.line,
+                &self.options,
+                self.eof,
+                limit,
+                &mut records,
+            );
+            if records.len() >= limit || self.eof {
+                break;
+            }
+            self.read().await?;
+        }
+        let rest = self.pending.split_off(parsed);
+        let text = std::mem::replace(&mut self.pending, rest);
+        let header = self.header.min(records.len());
+        self.header -= header;
+        let records: Vec<CsvRecord> = records.into_iter().skip(header).collect();
+        if records.is_empty() {
+            return Ok(None);
+        }
+        let batch = records_to_batch(text, records, &self.schema, &self.options, self.rows_parsed)?;
+        self.rows_parsed += batch.rows_parsed;
+        Ok(Some(batch))
+    }
+
+    /// Decodes the next chunk of the file into `pending`
+    async fn read(&mut self) -> Result<()> {
+        match self.bytes.next().await {
+            Some(chunk) => {
+                let chunk = chunk.context(ex_error::DataFusionSnafu)?;
+                self.decode(&chunk, false)
+            }
+            None => {
+                self.eof = true;
+                self.decode(&[], true)
+            }
+        }
+    }
+
+    fn decode(&mut self, mut bytes: &[u8], last: bool) -> Result<()> {
+        loop {
+            if let Some(length) = self.decoder.max_utf8_buffer_length(bytes.len()) {
+                self.pending.reserve(length);
+            }
+            let (result, read, had_errors) =
+                self.decoder
+                    .decode_to_string(bytes, &mut self.pending, last);
+            if had_errors {
+                return ex_error::InvalidCharacterEncodingSnafu {
+                    encoding: self.options.encoding.name(),
+                    file: self.file.clone(),
+                }
+                .fail();
+            }
+            bytes = &bytes[read..];
+            if result == CoderResult::InputEmpty {
+                return Ok(());
+            }
+        }
+    }
+}
+
+struct Cursor<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.pos += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn eat(&mut self, value: &str) -> bool {
+        if !value.is_empty() && self.rest().starts_with(value) {
+            self.pos += value.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn is_eof(&self) -> bool {
+        self.pos >= self.text.len()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Terminator {
+    Field,
+    Record,
+    Eof,
+}
+
+/// Splits decoded file contents into records. Header lines are not skipped here.
+#[must_use]
+pub fn parse_records(text: &str, options: &CsvLoadOptions) -> Vec<CsvRecord> {
+    let mut records = Vec::new();
+    parse_records_from(text, 0, &mut 1, options, true, usize::MAX, &mut records);
+    records
+}
+
+/// Parses the records of `text` from `start` until `records` holds `limit` of them,
+/// numbering lines from `line`. Unless `eof`, the text may end in the middle of a
+/// record: such a record is left unparsed. Returns the position parsing stopped at.
+fn parse_records_from(
+    text: &str,
+    start: usize,
+    line: &mut usize,
+    options: &CsvLoadOptions,
+    eof: bool,
+    limit: usize,
+    records: &mut Vec<CsvRecord>,
+) -> usize {
+    let mut cursor = Cursor { text, pos: start };
+    let mut fields = Vec::new();
+    let mut start = start;
+    while records.len() < limit && !cursor.is_eof() {
+        let field_start = cursor.pos;
+        let (field, terminator) = parse_field(&mut cursor, options);
+        fields.push(field);
+        if terminator == Terminator::Field {
+            continue;
+        }
+        if terminator == Terminator::Eof && !eof {
+            return start;
+        }
+        let end = record_end(text, field_start, cursor.pos, terminator, options);
+        records.push(CsvRecord {
+            line: *line,
+            span: start..end,
+            fields: std::mem::take(&mut fields),
+        });
+        *line += text[start..cursor.pos].matches('\n').count();
+        start = cursor.pos;
+    }
+    if !fields.is_empty() {
+        if !eof {
+            return start;
+        }
+        // The file ended right after a field delimiter
+        fields.push(finish_unenclosed("", String::new(), options));
+        records.push(CsvRecord {
+            line: *line,
+            span: start..text.len(),
+            fields,
+        });
+        *line += text[start..].matches('\n').count();
+        start = text.len();
+    }
+    start
+}
+
+/// End of the record contents: the position before the record delimiter
+fn record_end(
+    text: &str,
+    field_start: usize,
+    pos: usize,
+    terminator: Terminator,
+    options: &CsvLoadOptions,
+) -> usize {
+    if terminator == Terminator::Eof {
+        return pos;
+    }
+    let consumed = &text[field_start..pos];
+    match options.record_delimiter.as_deref() {
+        Some("\n") if consumed.ends_with("\r\n") => pos - 2,
+        Some(delimiter) if consumed.ends_with(delimiter) => pos - delimiter.len(),
+        _ => pos,
+    }
+}
+
+fn terminator(cursor: &mut Cursor<'_>, options: &CsvLoadOptions) -> Option<Terminator> {
+    if cursor.is_eof() {
+        return Some(Terminator::Eof);
+    }
+    if let Some(delimiter) = &options.field_delimiter {
+        if cursor.eat(delimiter) {
+            return Some(Terminator::Field);
+        }
+    }
+    match options.record_delimiter.as_deref() {
+        Some("\n") if cursor.eat("\r\n") || cursor.eat("\n") => Some(Terminator::Record),
+        Some(delimiter) if cursor.eat(delimiter) => Some(Terminator::Record),
+        _ => None,
+    }
+}
+
+fn skip_spaces(cursor: &mut Cursor<'_>) {
+    while matches!(cursor.peek(), Some(' ' | '\t')) {
+        cursor.bump();
+    }
+}
+
+fn parse_field(cursor: &mut Cursor<'_>, options: &CsvLoadOptions) -> (Option<String>, Terminator) {
+    let start = cursor.pos;
+    if options.trim_space {
+        skip_spaces(cursor);
+    }
+    match options.field_optionally_enclosed_by {
+        Some(quote) if cursor.peek() == Some(quote) => {
+            cursor.bump();
+            parse_enclosed_field(cursor, quote, options)
+        }
+        _ => {
+            cursor.pos = start;
+            parse_unenclosed_field(cursor, options)
+        }
+    }
+}
+
+fn parse_enclosed_field(
+    cursor: &mut Cursor<'_>,
+    quote: char,
+    options: &CsvLoadOptions,
+) -> (Option<String>, Terminator) {
+    let mut value = String::new();
+    while let Some(ch) = cursor.bump() {
+        if Some(ch) == options.escape && ch != quote {
+            if let Some(next) = cursor.bump() {
+                value.push(next);
+            }
+        } else if ch == quote {
+            if cursor.peek() == Some(quote) {
+                cursor.bump();
+                value.push(quote);
+            } else {
+                break;
+            }
+        } else {
+            value.push(ch);
+        }
+    }
+    // Anything between the closing quote and the delimiter is kept as is
+    let mut trailing = String::new();
+    let terminator = loop {
+        if let Some(terminator) = terminator(cursor, options) {
+            break terminator;
+        }
+        if let Some(ch) = cursor.bump() {
+            trailing.push(ch);
+        }
+    };
+    if options.trim_space {
+        value.push_str(trailing.trim());
+    } else {
+        value.push_str(&trailing);
+    }
+    let value = if options.null_if.contains(&value) {
+        None
+    } else {
+        Some(value)
+    };
+    (value, terminator)
+}
+
+fn parse_unenclosed_field(
+    cursor: &mut Cursor<'_>,
+    options: &CsvLoadOptions,
+) -> (Option<String>, Terminator) {
+    let start = cursor.pos;
+    let mut value = String::new();
+    let mut end;
+    let terminator = loop {
+        end = cursor.pos;
+        if let Some(terminator) = terminator(cursor, options) {
+            break terminator;
+        }
+        let Some(ch) = cursor.bump() else {
+            break Terminator::Eof;
+        };
+        if Some(ch) == options.escape_unenclosed_field {
+            match cursor.bump() {
+                Some('t') => value.push('\t'),
+                Some('n') => value.push('\n'),
+                Some('r') => value.push('\r'),
+                Some(next) => value.push(next),
+                None => value.push(ch),
+            }
+        } else {
+            value.push(ch);
+        }
+    };
+    let raw = &cursor.text[start..end];
+    (finish_unenclosed(raw, value, options), terminator)
+}
+
+/// `NULL_IF` is matched against the raw field, so the default `\N` still works
+/// with `\` as the `ESCAPE_UNENCLOSED_FIELD` character.
+fn finish_unenclosed(raw: &str, value: String, options: &CsvLoadOptions) -> Option<String> {
+    let (raw, value) = if options.trim_space {
+        (raw.trim(), value.trim().to_string())
+    } else {
+        (raw, value)
+    };
+    if raw.is_empty() && options.empty_field_as_null {
+        return None;
+    }
+    if options
+        .null_if
+        .iter()
+        .any(|null| null == raw || *null == value)
+    {
+        return None;
+    }
+    Some(value)
+}
+
+/// Schema the CSV records are loaded with: the target columns, all as strings
+#[must_use]
+pub fn load_schema(target: &SchemaRef) -> SchemaRef {
+    Arc::new(ArrowSchema::new(
+        target
+            .fields()
+            .iter()
+            .map(|field| Field::new(field.name(), DataType::Utf8, true))
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// String columns built from consecutive records of a single file
+#[derive(Debug)]
+pub struct CsvBatch {
+    /// Text of the records, which the spans of `rows` refer to
+    pub text: String,
+    pub batch: RecordBatch,
+    /// The record each row of `batch` comes from
+    pub rows: Vec<CsvRow>,
+    /// Records left out of `batch`
+    pub errors: Vec<RowError>,
+    /// Data records in the batch, header lines excluded
+    pub rows_parsed: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvRow {
+    pub line: usize,
+    pub row_number: usize,
+    pub span: Range<usize>,
+}
+
+/// Builds a batch of string columns out of data records, `rows_before` of which were
+/// read from the file already. Records with a wrong number of columns are reported as
+/// errors unless `ERROR_ON_COLUMN_COUNT_MISMATCH = FALSE`, in which case they are
+/// padded or truncated.
+pub fn records_to_batch(
+    text: String,
+    records: Vec<CsvRecord>,
+    schema: &SchemaRef,
+    options: &CsvLoadOptions,
+    rows_before: usize,
+) -> Result<CsvBatch> {
+    let columns = schema.fields().len();
+    let mut builders: Vec<StringBuilder> = (0..columns)
+        .map(|_| StringBuilder::with_capacity(records.len(), 0))
+        .collect();
+    let mut rows = Vec::with_capacity(records.len());
+    let mut errors = Vec::new();
+    let mut rows_parsed = 0;
+    for record in records {
+        rows_parsed += 1;
+        if record.fields.len() != columns && options.error_on_column_count_mismatch {
+            errors.push(RowError {
+                error: ex_error::CsvColumnCountMismatchSnafu {
+                    expected: columns,
+                    found: record.fields.len(),
+                }
+                .build()
+                .to_string(),
+                category: ErrorCategory::Parsing,
+                line: Some(record.line),
+                row_number: Some(rows_before + rows_parsed),
+                column_name: None,
+                rejected_record: Some(text[record.span].to_string()),
+            });
+            continue;
+        }
+        let mut fields = record.fields.into_iter();
+        for builder in &mut builders {
+            builder.append_option(fields.next().flatten());
+        }
+        rows.push(CsvRow {
+            line: record.line,
+            row_number: rows_before + rows_parsed,
+            span: record.span,
+        });
+    }
+    let arrays = builders
+        .into_iter()
+        .map(|mut builder| Arc::new(builder.finish()) as ArrayRef)
+        .collect();
+    let batch = RecordBatch::try_new(schema.clone(), arrays).context(ex_error::ArrowSnafu)?;
+    Ok(CsvBatch {
+        text,
+        batch,
+        rows,
+        errors,
+        rows_parsed,
+    })
+}
+
+fn load_expr(field: &Field, options: &CsvLoadOptions, ctx: &SessionContext) -> Result<Expr> {
+    let column = Expr::Column(Column::new_unqualified(field.name()));
+    let expr = match (
+        field.data_type(),
+        &options.date_format,
+        &options.timestamp_format,
+    ) {
+        (DataType::Utf8, _, _) => return Ok(column),
+        (DataType::Date32 | DataType::Date64, Some(format), _) => ctx
+            .udf("try_to_date")
+            .context(ex_error::DataFusionSnafu)?
+            .call(vec![column, lit(format.clone())]),
+        (DataType::Timestamp(_, _), _, Some(format)) => ctx
+            .udf("try_to_timestamp")
+            .context(ex_error::DataFusionSnafu)?
+            .call(vec![column, lit(format.clone())]),
+        _ => column,
+    };
+    Ok(Expr::TryCast(TryCast::new(
+        Box::new(expr),
+        field.data_type().clone(),
+    )))
+}
+
+/// Converts the string columns of [`load_schema`] to the target types, parsing
+/// dates and timestamps with `DATE_FORMAT` / `TIMESTAMP_FORMAT` when given.
+/// Values that cannot be converted become NULL and are reported by [`conversion_errors`].
+///
+/// Expressions are evaluated on each batch directly, so that rows keep their order.
+#[derive(Debug, Clone)]
+pub struct CsvConverter {
+    exprs: Vec<Arc<dyn PhysicalExpr>>,
+    target: SchemaRef,
+    schema: SchemaRef,
+}
+
+impl CsvConverter {
+    pub fn try_new(
+        target: &SchemaRef,
+        options: &CsvLoadOptions,
+        ctx: &SessionContext,
+    ) -> Result<Self> {
+        let df_schema = DFSchema::try_from(load_schema(target).as_ref().clone())
+            .context(ex_error::DataFusionSnafu)?;
+        let exprs = target
+            .fields()
+            .iter()
+            .map(|field| {
+                ctx.create_physical_expr(load_expr(field, options, ctx)?, &df_schema)
+                    .context(ex_error::DataFusionSnafu)
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            exprs,
+            target: target.clone(),
+            schema: copy_into::load_stream_schema(target),
+        })
+    }
+
+    /// The target columns, all nullable: NULLs in non-nullable columns are reported
+    /// by [`conversion_errors`]
+    #[must_use]
+    pub const fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    pub fn convert(&self, batch: &RecordBatch) -> Result<RecordBatch> {
+        let columns = self
+            .exprs
+            .iter()
+            .map(|expr| {
+                expr.evaluate(batch)
+                    .and_then(|value| value.into_array(batch.num_rows()))
+                    .context(ex_error::DataFusionSnafu)
+            })
+            .collect::<Result<_>>()?;
+        RecordBatch::try_new(self.schema.clone(), columns).context(ex_error::ArrowSnafu)
+    }
+}
+
+fn conversion_error(value: &str, data_type: &DataType) -> Error {
+    let kind = match data_type {
+        DataType::Date32 | DataType::Date64 => "Date",
+        DataType::Timestamp(_, _) => "Timestamp",
+        DataType::Time32(_) | DataType::Time64(_) => "Time",
+        DataType::Boolean => "Boolean value",
+        data_type if data_type.is_numeric() => "Numeric value",
+        data_type => {
+            return ex_error::CsvValueNotConvertibleSnafu {
+                value,
+                data_type: data_type.to_string(),
+            }
+            .build();
+        }
+    };
+    ex_error::CsvValueNotRecognizedSnafu { kind, value }.build()
+}
+
+/// Finds the rows whose values failed to convert to the target types, or are NULL
+/// in a non-nullable column. Returns a mask of the rows without errors.
+pub fn conversion_errors(
+    csv_batch: &CsvBatch,
+    converted: &RecordBatch,
+    target: &SchemaRef,
+) -> (Vec<RowError>, BooleanArray) {
+    let mut valid = vec![true; converted.num_rows()];
+    let mut errors = Vec::new();
+    for (index, field) in target.fields().iter().enumerate() {
+        let source = csv_batch.batch.column(index).as_string::<i32>();
+        let target_column = converted.column(index);
+        for (row, info) in csv_batch.rows.iter().enumerate() {
+            if !valid[row] || !target_column.is_null(row) {
+                continue;
+            }
+            let error = if source.is_valid(row) {
+                conversion_error(source.value(row), field.data_type())
+            } else if field.is_nullable() {
+                continue;
+            } else {
+                ex_error::NullInNonNullableColumnSnafu {
+                    column: field.name(),
+                }
+                .build()
+            };
+            valid[row] = false;
+            errors.push(RowError {
+                error: error.to_string(),
+                category: ErrorCategory::Conversion,
+                line: Some(info.line),
+                row_number: Some(info.row_number),
+                column_name: Some(field.name().clone()),
+                rejected_record: Some(csv_batch.text[info.span.clone()].to_string()),
+            });
+        }
+    }
+    errors.sort_by_key(|error| error.line);
+    (errors, BooleanArray::from(valid))
+}
+
+/// Opens the CSV files of a load, read through [`CsvFileReader`]
+#[derive(Debug)]
+pub struct CsvRowsSource {
+    store: Arc<dyn ObjectStore>,
+    options: CsvLoadOptions,
+    converter: CsvConverter,
+}
+
+impl CsvRowsSource {
+    #[must_use]
+    pub const fn new(
+        store: Arc<dyn ObjectStore>,
+        options: CsvLoadOptions,
+        converter: CsvConverter,
+    ) -> Self {
+        Self {
+            store,
+            options,
+            converter,
+        }
+    }
+}
+
+#[async_trait]
+impl FileRowsSource for CsvRowsSource {
+    async fn open(&self, file: &ObjectMeta, _ctx: Arc<TaskContext>) -> Result<Box<dyn FileRows>> {
+        let schema = load_schema(self.converter.schema());
+        let reader = CsvFileReader::open(&self.store, file, &self.options, schema).await?;
+        Ok(Box::new(CsvFileRows {
+            reader,
+            converter: self.converter.clone(),
+        }))
+    }
+}
+
+struct CsvFileRows {
+    reader: CsvFileReader,
+    converter: CsvConverter,
+}
+
+#[async_trait]
+impl FileRows for CsvFileRows {
+    async fn next_rows(&mut self) -> Result<Option<(RecordBatch, Vec<RowError>)>> {
+        let Some(csv_batch) = self.reader.next_batch().await? else {
+            return Ok(None);
+        };
+        let converted = self.converter.convert(&csv_batch.batch)?;
+        let (conversion_errors, valid) =
+            conversion_errors(&csv_batch, &converted, &self.converter.target);
+        let mut errors = csv_batch.errors;
+        errors.extend(conversion_errors);
+        errors.sort_by_key(|error| error.line);
+        let rows = filter_record_batch(&converted, &valid).context(ex_error::ArrowSnafu)?;
+        Ok(Some((rows, errors)))
+    }
+
+    fn rows_parsed(&self) -> usize {
+        self.reader.rows_parsed()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use core_metastore::FileFormatOptionValue;
+
+    fn options(values: &[(&str, &str)]) -> CsvLoadOptions {
+        let mut spec = FileFormatSpec {
+            format_type: Some(FileFormatType::Csv),
+            ..FileFormatSpec::default()
+        };
+        for (name, value) in values {
+            let value = if value.starts_with('(') {
+                FileFormatOptionValue::List(
+                    value
+                        .trim_matches(|c| c == '(' || c == ')')
+                        .split(',')
+                        .map(|v| v.trim().trim_matches('\'').to_string())
+                        .collect(),
+                )
+            } else {
+                FileFormatOptionValue::Single((*value).to_string())
+            };
+            spec.options.insert(name.to_ascii_uppercase(), value);
+        }
+        CsvLoadOptions::from_spec(&spec).unwrap().unwrap()
+    }
+
+    fn parse(text: &str, values: &[(&str, &str)]) -> Vec<Vec<Option<String>>> {
+        parse_records(text, &options(values))
+            .into_iter()
+            .map(|record| record.fields)
+            .collect()
+    }
+
+    fn record(fields: &[Option<&str>]) -> Vec<Option<String>> {
+        fields.iter().map(|f| f.map(ToString::to_string)).collect()
+    }
+
+    fn string_schema(columns: usize) -> SchemaRef {
+        Arc::new(ArrowSchema::new(
+            (0..columns)
+                .map(|i| Field::new(format!("c{i}"), DataType::Utf8, true))
+                .collect::<Vec<_>>(),
+        ))
+    }
+
+    fn batch(text: &str, columns: usize, values: &[(&str, &str)]) -> CsvBatch {
+        let options = options(values);
+        let records = parse_records(text, &options);
+        records_to_batch(
+            text.to_string(),
+            records,
+            &string_schema(columns),
+            &options,
+            0,
+        )
+        .unwrap()
+    }
+
+    /// Reads `bytes` delivered in chunks of `chunk` bytes
+    async fn read_batches(
+        bytes: &[u8],
+        chunk: usize,
+        columns: usize,
+        values: &[(&str, &str)],
+    ) -> (Vec<CsvBatch>, usize) {
+        let chunks: Vec<datafusion_common::Result<Bytes>> = bytes
+            .chunks(chunk)
+            .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+            .collect();
+        let mut reader = CsvFileReader::new(
+            "file.csv".to_string(),
+            futures::stream::iter(chunks).boxed(),
+            options(values),
+            string_schema(columns),
+        );
+        let mut batches = Vec::new();
+        while let Some(batch) = reader.next_batch().await.unwrap() {
+            batches.push(batch);
+        }
+        (batches, reader.rows_parsed())
+    }
+
+    #[test]
+    fn test_defaults() {
+        assert_eq!(
+            parse("a,b\r\n\"c\",\n", &[]),
+            vec![
+                record(&[Some("a"), Some("b")]),
+                record(&[Some("\"c\""), None])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_header_is_not_handled() {
+        let spec = FileFormatSpec {
+            format_type: Some(FileFormatType::Csv),
+            options: [(
+                "PARSE_HEADER".to_string(),
+                FileFormatOptionValue::Single("TRUE".to_string()),
+            )]
+            .into(),
+        };
+        assert!(CsvLoadOptions::from_spec(&spec).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_skip_header() {
+        let (batches, rows_parsed) =
+            read_batches(b"h1\nh2\n1\n2\n", 3, 1, &[("skip_header", "2")]).await;
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].batch.num_rows(), 2);
+        assert_eq!(rows_parsed, 2);
+        assert_eq!(batches[0].rows[0].line, 3);
+        assert_eq!(batches[0].rows[0].row_number, 1);
+        assert!(
+            CsvLoadOptions::from_spec(&FileFormatSpec {
+                format_type: Some(FileFormatType::Csv),
+                options: [(
+                    "SKIP_HEADER".to_string(),
+                    FileFormatOptionValue::Single("x".to_string()),
+                )]
+                .into(),
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_field_delimiter() {
+        assert_eq!(
+            parse("a||b\n", &[("field_delimiter", "||")]),
+            vec![record(&[Some("a"), Some("b")])]
+        );
+        assert_eq!(
+            parse("a\tb\n", &[("field_delimiter", "\\t")]),
+            vec![record(&[Some("a"), Some("b")])]
+        );
+        assert_eq!(
+            parse("a,b\n", &[("field_delimiter", "NONE")]),
+            vec![record(&[Some("a,b")])]
+        );
+    }
+
+    #[test]
+    fn test_record_delimiter() {
+        assert_eq!(
+            parse("a,b;c,d;", &[("record_delimiter", ";")]),
+            vec![
+                record(&[Some("a"), Some("b")]),
+                record(&[Some("c"), Some("d")])
+            ]
+        );
+        assert_eq!(
+            parse("a|b", &[("record_delimiter", "0x7C")]),
+            vec![record(&[Some("a")]), record(&[Some("b")])]
+        );
+    }
+
+    #[test]
+    fn test_field_optionally_enclosed_by() {
+        assert_eq!(
+            parse(
+                "\"a,b\",\"say \"\"hi\"\"\",\"\"\n",
+                &[("field_optionally_enclosed_by", "\"")]
+            ),
+            vec![record(&[Some("a,b"), Some("say \"hi\""), Some("")])]
+        );
+        assert_eq!(
+            parse("'x\ny',z\n", &[("field_optionally_enclosed_by", "'")]),
+            vec![record(&[Some("x\ny"), Some("z")])]
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            parse(
+                "\"a\\\"b\",c\n",
+                &[("field_optionally_enclosed_by", "\""), ("escape", "\\")]
+            ),
+            vec![record(&[Some("a\"b"), Some("c")])]
+        );
+    }
+
+    #[test]
+    fn test_escape_unenclosed_field() {
+        assert_eq!(
+            parse("a\\,b,c\n", &[]),
+            vec![record(&[Some("a,b"), Some("c")])]
+        );
+        assert_eq!(
+            parse("a#,b,c\n", &[("escape_unenclosed_field", "#")]),
+            vec![record(&[Some("a,b"), Some("c")])]
+        );
+        assert_eq!(
+            parse("a\\,b\n", &[("escape_unenclosed_field", "NONE")]),
+            vec![record(&[Some("a\\"), Some("b")])]
+        );
+    }
+
+    #[test]
+    fn test_null_if() {
+        assert_eq!(
+            parse("\\N,NULL,x\n", &[]),
+            vec![record(&[None, Some("NULL"), Some("x")])]
+        );
+        assert_eq!(
+            parse("\\N,NULL,x\n", &[("null_if", "('NULL', 'x')")]),
+            vec![record(&[Some("N"), None, None])]
+        );
+    }
+
+    #[test]
+    fn test_empty_field_as_null() {
+        assert_eq!(parse(",a\n", &[]), vec![record(&[None, Some("a")])]);
+        assert_eq!(
+            parse(",a\n", &[("empty_field_as_null", "false")]),
+            vec![record(&[Some(""), Some("a")])]
+        );
+        assert!(
+            CsvLoadOptions::from_spec(&FileFormatSpec {
+                format_type: Some(FileFormatType::Csv),
+                options: [(
+                    "EMPTY_FIELD_AS_NULL".to_string(),
+                    FileFormatOptionValue::Single("maybe".to_string()),
+                )]
+                .into(),
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_trim_space() {
+        assert_eq!(
+            parse(" a , \"b\" \n", &[("field_optionally_enclosed_by", "\"")]),
+            vec![record(&[Some(" a "), Some(" \"b\" ")])]
+        );
+        assert_eq!(
+            parse(
+                " a , \"b\" \n",
+                &[
+                    ("field_optionally_enclosed_by", "\""),
+                    ("trim_space", "true")
+                ]
+            ),
+            vec![record(&[Some("a"), Some("b")])]
+        );
+    }
+
+    #[test]
+    fn test_date_and_timestamp_format() {
+        let options = options(&[("date_format", "DD/MM/YYYY"), ("timestamp_format", "AUTO")]);
+        assert_eq!(options.date_format.as_deref(), Some("DD/MM/YYYY"));
+        assert_eq!(options.timestamp_format, None);
+    }
+
+    #[test]
+    fn test_encoding() {
+        assert_eq!(
+            options(&[("encoding", "UTF8")]).encoding,
+            encoding_rs::UTF_8
+        );
+        assert_eq!(
+            options(&[("encoding", "WINDOWS1252")]).encoding,
+            encoding_rs::WINDOWS_1252
+        );
+        assert_eq!(
+            options(&[("encoding", "ISO-8859-1")]).encoding,
+            encoding_rs::WINDOWS_1252
+        );
+        assert_eq!(
+            options(&[("encoding", "UTF16LE")]).encoding,
+            encoding_rs::UTF_16LE
+        );
+        let (text, _, _) = encoding_rs::WINDOWS_1252.decode(b"caf\xe9");
+        assert_eq!(text, "café");
+    }
+
+    #[test]
+    fn test_record_lines_and_spans() {
+        let text = "a,'x\ny'\r\nb,c";
+        let records = parse_records(text, &options(&[("field_optionally_enclosed_by", "'")]));
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].line, 1);
+        assert_eq!(&text[records[0].span.clone()], "a,'x\ny'");
+        assert_eq!(records[1].line, 3);
+        assert_eq!(&text[records[1].span.clone()], "b,c");
+    }
+
+    #[tokio::test]
+    async fn test_reader_chunks() {
+        let text = "1,'a\nb'\r\n2,\"caf\u{e9}\"\n3,c";
+        let values = [("field_optionally_enclosed_by", "'")];
+        let (expected, _) = read_batches(text.as_bytes(), text.len(), 2, &values).await;
+        assert_eq!(expected[0].batch.num_rows(), 3);
+        // Records, line breaks and multi-byte characters split across chunks
+        for chunk in 1..text.len() {
+            let (batches, rows_parsed) = read_batches(text.as_bytes(), chunk, 2, &values).await;
+            assert_eq!(rows_parsed, 3);
+            assert_eq!(batches.len(), 1);
+            assert_eq!(batches[0].batch, expected[0].batch);
+            assert_eq!(batches[0].rows, expected[0].rows);
+        }
+        assert_eq!(expected[0].rows[2].line, 4);
+        assert_eq!(
+            &expected[0].text[expected[0].rows[1].span.clone()],
+            "2,\"caf\u{e9}\""
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reader_batches() {
+        let text: String = (0..BATCH_ROWS + 10).map(|i| format!("{i}\n")).collect();
+        let (batches, rows_parsed) =
+            read_batches(text.as_bytes(), 1000, 1, &[("skip_header", "1")]).await;
+        assert_eq!(rows_parsed, BATCH_ROWS + 9);
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].batch.num_rows(), BATCH_ROWS);
+        assert_eq!(batches[1].rows[0].row_number, BATCH_ROWS + 1);
+        assert_eq!(batches[1].rows[0].line, BATCH_ROWS + 2);
+        assert_eq!(
+            &batches[1].text[batches[1].rows[0].span.clone()],
+            (BATCH_ROWS + 1).to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reader_encoding() {
+        let (batches, _) = read_batches(b"caf\xe9\n", 2, 1, &[("encoding", "WINDOWS1252")]).await;
+        assert_eq!(
+            batches[0].batch.column(0).as_string::<i32>().value(0),
+            "caf\u{e9}"
+        );
+
+        let chunks: Vec<datafusion_common::Result<Bytes>> =
+            vec![Ok(Bytes::from_static(b"caf\xe9\n"))];
+        let mut reader = CsvFileReader::new(
+            "file.csv".to_string(),
+            futures::stream::iter(chunks).boxed(),
+            options(&[]),
+            string_schema(1),
+        );
+        assert!(reader.next_batch().await.is_err());
+    }
+
+    #[test]
+    fn test_error_on_column_count_mismatch() {
+        let batch = batch("1,2,3\n4,5\n", 2, &[]);
+        assert_eq!(batch.batch.num_rows(), 1);
+        assert_eq!(batch.rows_parsed, 2);
+        assert_eq!(batch.errors.len(), 1);
+        assert_eq!(batch.errors[0].line, Some(1));
+        assert_eq!(batch.errors[0].rejected_record.as_deref(), Some("1,2,3"));
+        assert!(
+            batch.errors[0]
+                .error
+                .starts_with("Number of columns in file (3)")
+        );
+
+        let batch = batch(
+            "1,2,3\n4\n",
+            2,
+            &[("error_on_column_count_mismatch", "false")],
+        );
+        assert_eq!(batch.batch.num_rows(), 2);
+        assert_eq!(batch.batch.column(1).null_count(), 1);
+        assert!(batch.errors.is_empty());
+    }
+
+    #[test]
+    fn test_conversion_errors() {
+        let text = "1,a\nx,\n";
+        let options = options(&[]);
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Utf8, true),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let csv_batch = records_to_batch(
+            text.to_string(),
+            parse_records(text, &options),
+            &schema,
+            &options,
+            0,
+        )
+        .unwrap();
+        let target = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Int32, true),
+            Field::new("name", DataType::Utf8, false),
+        ]));
+        let converted = RecordBatch::try_new(
+            Arc::new(ArrowSchema::new(vec![
+                Field::new("id", DataType::Int32, true),
+                Field::new("name", DataType::Utf8, true),
+            ])),
+            vec![
+                Arc::new(datafusion::arrow::array::Int32Array::from(vec![
+                    Some(1),
+                    None,
+                ])),
+                csv_batch.batch.column(1).clone(),
+            ],
+        )
+        .unwrap();
+        let (errors, valid) = conversion_errors(&csv_batch, &converted, &target);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].error, "Numeric value 'x' is not recognized");
+        assert_eq!(errors[0].column_name.as_deref(), Some("id"));
+        assert_eq!(errors[0].line, Some(2));
+        assert_eq!(valid, BooleanArray::from(vec![true, false]));
+    }
+}
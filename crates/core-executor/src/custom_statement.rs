@@ -0,0 +1,171 @@
+use crate::error::Result;
+use crate::explain::{ExplainUsingStatement, parse_explain_statement};
+use crate::file_format::{FileFormatStatement, parse_file_format_statement};
+use crate::role::{RoleStatement, parse_role_statement};
+use crate::stage::{StageStatement, parse_stage_statement};
+use crate::user::{UserStatement, parse_user_statement};
+
+/// Statements which are not covered by the SQL parser grammar
+#[derive(Debug, Clone, PartialEq)]
+pub enum CustomStatement {
+    FileFormat(FileFormatStatement),
+    User(UserStatement),
+    Role(RoleStatement),
+    Stage(StageStatement),
+    Explain(ExplainUsingStatement),
+}
+
+/// Parses a statement not covered by the SQL parser grammar. The leading words of the query
+/// select the only parser which may accept it, so other queries are not tokenized here.
+pub fn parse_custom_statement(sql: &str, dialect: &str) -> Result<Option<CustomStatement>> {
+    let words = leading_words(sql, 5);
+    let word = |index: usize| words.get(index).map_or("", String::as_str);
+    let statement = match (word(0), object_word(&words)) {
+        ("PUT" | "GET" | "LIST" | "LS" | "REMOVE" | "RM", _)
+        | ("DROP" | "SHOW", "STAGE" | "STAGES") => {
+            parse_stage_statement(sql, dialect)?.map(CustomStatement::Stage)
+        }
+        // `ANALYZE`, `VERBOSE` and `FORMAT` are options of DataFusion's EXPLAIN
+        ("EXPLAIN", _) if !matches!(word(1), "ANALYZE" | "VERBOSE" | "FORMAT") => {
+            parse_explain_statement(sql, dialect)?.map(CustomStatement::Explain)
+        }
+        ("GRANT" | "REVOKE", _) | (_, "ROLE" | "ROLES" | "GRANTS") => {
+            parse_role_statement(sql, dialect)?.map(CustomStatement::Role)
+        }
+        (_, "USER" | "USERS") => parse_user_statement(sql, dialect)?.map(CustomStatement::User),
+        (_, "FILE") => parse_file_format_statement(sql, dialect)?.map(CustomStatement::FileFormat),
+        _ => None,
+    };
+    Ok(statement)
+}
+
+impl CustomStatement {
+    /// Snowflake statement type id, see [`crate::utils::statement_type_id`]
+    #[must_use]
+    pub const fn statement_type_id(&self) -> u32 {
+        match self {
+            Self::FileFormat(
+                FileFormatStatement::Show { .. } | FileFormatStatement::Describe { .. },
+            )
+            | Self::User(UserStatement::Show { .. } | UserStatement::ShowAccessTokens { .. })
+            | Self::Role(RoleStatement::ShowRoles { .. } | RoleStatement::ShowGrants(_))
+            | Self::Stage(StageStatement::Show { .. } | StageStatement::List { .. })
+            | Self::Explain(_) => 0x1000,
+            _ => 0x6000,
+        }
+    }
+}
+
+/// Kind of object of a DDL statement: the word after the verb and its modifiers
+fn object_word(words: &[String]) -> &str {
+    if !matches!(
+        words.first().map(String::as_str),
+        Some("CREATE" | "ALTER" | "DROP" | "SHOW" | "DESCRIBE" | "DESC")
+    ) {
+        return "";
+    }
+    words
+        .iter()
+        .skip(1)
+        .map(String::as_str)
+        .find(|word| !matches!(*word, "OR" | "REPLACE" | "TEMPORARY" | "TEMP"))
+        .unwrap_or_default()
+}
+
+/// Up to `limit` leading words of the query in upper case, skipping whitespace and comments.
+/// Stops at the first token which is not a bare word.
+fn leading_words(sql: &str, limit: usize) -> Vec<String> {
+    let mut words = Vec::with_capacity(limit);
+    let mut rest = sql;
+    while words.len() < limit {
+        rest = rest.trim_start();
+        if let Some(comment) = rest.strip_prefix("--") {
+            rest = comment.split_once('\n').map_or("", |(_, rest)| rest);
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = comment.split_once("*/").map_or("", |(_, rest)| rest);
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            if end == 0 {
+                break;
+            }
+            words.push(rest[..end].to_ascii_uppercase());
+            rest = &rest[end..];
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::ExplainFormat;
+
+    #[allow(clippy::unwrap_used)]
+    fn parse(sql: &str) -> Option<CustomStatement> {
+        parse_custom_statement(sql, "snowflake").unwrap()
+    }
+
+    #[test]
+    fn test_leading_words() {
+        assert_eq!(
+            leading_words(
+                "-- comment\n /* block */ create or replace FILE format f",
+                4
+            ),
+            vec!["CREATE", "OR", "REPLACE", "FILE"]
+        );
+        assert_eq!(leading_words("SELECT 1", 4), vec!["SELECT", "1"]);
+        assert!(leading_words("(SELECT 1)", 4).is_empty());
+    }
+
+    #[test]
+    fn test_parse_custom_statement() {
+        assert!(matches!(
+            parse("CREATE OR REPLACE FILE FORMAT f TYPE = CSV"),
+            Some(CustomStatement::FileFormat(_))
+        ));
+        assert!(matches!(
+            parse("SHOW USERS"),
+            Some(CustomStatement::User(_))
+        ));
+        assert!(matches!(
+            parse("GRANT ROLE analyst TO USER alice"),
+            Some(CustomStatement::Role(_))
+        ));
+        assert!(matches!(
+            parse("SHOW GRANTS TO ROLE analyst"),
+            Some(CustomStatement::Role(_))
+        ));
+        assert!(matches!(parse("LIST @s"), Some(CustomStatement::Stage(_))));
+        assert!(matches!(
+            parse("DROP STAGE s"),
+            Some(CustomStatement::Stage(_))
+        ));
+        assert!(matches!(
+            parse("EXPLAIN USING JSON SELECT 1"),
+            Some(CustomStatement::Explain(_))
+        ));
+        assert!(matches!(
+            parse("EXPLAIN SELECT 1"),
+            Some(CustomStatement::Explain(ExplainUsingStatement {
+                format: ExplainFormat::Tabular,
+                ..
+            }))
+        ));
+        assert!(parse("EXPLAIN ANALYZE SELECT 1").is_none());
+        assert!(parse("EXPLAIN INSERT INTO t VALUES (1)").is_none());
+        assert!(parse("SELECT * FROM users").is_none());
+        assert!(parse("CREATE TABLE file (id INT)").is_none());
+        assert!(parse("SHOW TABLES").is_none());
+    }
+
+    #[test]
+    fn test_custom_statement_type_id() {
+        let type_id = |sql| parse(sql).map(|statement| statement.statement_type_id());
+        assert_eq!(type_id("LIST @s"), Some(0x1000));
+        assert_eq!(type_id("EXPLAIN USING JSON SELECT 1"), Some(0x1000));
+        assert_eq!(type_id("CREATE USER alice"), Some(0x6000));
+    }
+}
@@ -16,6 +16,7 @@ pub struct SessionContextExprRewriter {
     pub schema: String,
     pub schemas: Vec<String>,
     pub warehouse: String,
+    pub role: String,
     pub session_id: String,
     pub version: String,
     pub query_context: QueryContext,
@@ -92,6 +93,26 @@ impl SessionContextExprRewriter {
             )))
         }
     }
+
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn query_progress(&self, query_id: String) -> Result<ScalarValue> {
+        let Ok(query_uuid) = Uuid::from_str(&query_id) else {
+            return Ok(utf8_val("Invalid UUID."));
+        };
+
+        let query_id = QueryRecordId::from(query_uuid);
+        let Ok(running_query) = self
+            .running_queries
+            .get(RunningQueryId::ByQueryId(query_id))
+        else {
+            return Ok(utf8_val(
+                "Identified SQL statement is not currently executing.",
+            ));
+        };
+        let progress = serde_json::to_string(&running_query.progress())
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        Ok(utf8_val(progress))
+    }
 }
 struct ExprRewriter<'a> {
     rewriter: &'a SessionContextExprRewriter,
@@ -108,7 +129,7 @@ impl TreeNodeRewriter for ExprRewriter<'_> {
                 "current_schema" => Some(utf8_val(&self.rewriter.schema)),
                 "current_warehouse" => Some(utf8_val(&self.rewriter.warehouse)),
                 "current_role_type" => Some(utf8_val("ROLE")),
-                "current_role" => Some(utf8_val("default")),
+                "current_role" => Some(utf8_val(&self.rewriter.role)),
                 "current_version" => Some(utf8_val(&self.rewriter.version)),
                 "current_client" => Some(utf8_val(format!("Embucket {}", &self.rewriter.version))),
                 "current_session" => Some(utf8_val(&self.rewriter.session_id)),
@@ -134,6 +155,13 @@ impl TreeNodeRewriter for ExprRewriter<'_> {
                     };
                     Some(self.rewriter.cancel_query(query_id)?)
                 }
+                "system$query_progress" => {
+                    let query_id = match fun.args.first() {
+                        Some(Expr::Literal(ScalarValue::Utf8(Some(value)), _)) => value.clone(),
+                        _ => String::default(),
+                    };
+                    Some(self.rewriter.query_progress(query_id)?)
+                }
                 _ => None,
             };
             if let Some(value) = scalar_value {
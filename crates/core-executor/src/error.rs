@@ -246,6 +246,98 @@ pub enum Error {
         location: Location,
     },
 
+    #[snafu(display("File format {name} does not exist or not authorized"))]
+    FileFormatNotFound {
+        name: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Stage {name} does not exist or not authorized"))]
+    StageNotFound {
+        name: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("User {name} does not exist or not authorized"))]
+    UserNotFound {
+        name: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Programmatic access token {name} does not exist for user {user}"))]
+    AccessTokenNotFound {
+        name: String,
+        user: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Unsupported user property {property}"))]
+    UnsupportedUserProperty {
+        property: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Invalid value '{value}' for user property {property}"))]
+    InvalidUserProperty {
+        property: String,
+        value: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Role {name} does not exist or not authorized"))]
+    RoleNotFound {
+        name: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Requested role '{role}' is not assigned to the executing user"))]
+    RoleNotGranted {
+        role: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display(
+        "Role '{role}' can't be used, the session is restricted to role '{restriction}'"
+    ))]
+    RoleRestricted {
+        role: String,
+        restriction: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Granting role {role} to role {grantee} would create a cycle"))]
+    CyclicRoleGrant {
+        role: String,
+        grantee: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Privilege {privilege} can't be granted on a {kind}"))]
+    InvalidGrant {
+        privilege: String,
+        kind: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Insufficient privileges to operate on {kind} '{name}'"))]
+    InsufficientPrivileges {
+        kind: String,
+        name: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
     #[snafu(display("Unsupported file format {format}"))]
     UnsupportedFileFormat {
         format: String,
@@ -253,6 +345,140 @@ pub enum Error {
         location: Location,
     },
 
+    #[snafu(display("Invalid value '{value}' for file format option {option}"))]
+    InvalidFileFormatOption {
+        option: String,
+        value: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Unsupported encoding {encoding}"))]
+    UnsupportedEncoding {
+        encoding: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Invalid {encoding} character encoding in file {file}"))]
+    InvalidCharacterEncoding {
+        encoding: String,
+        file: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display(
+        "Number of columns in file ({found}) does not match that of the corresponding table ({expected}), use file format option error_on_column_count_mismatch=false to ignore this error"
+    ))]
+    CsvColumnCountMismatch {
+        expected: usize,
+        found: usize,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("{kind} '{value}' is not recognized"))]
+    CsvValueNotRecognized {
+        kind: String,
+        value: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Value '{value}' cannot be converted to {data_type}"))]
+    CsvValueNotConvertible {
+        value: String,
+        data_type: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("NULL result in a non-nullable column {column}"))]
+    NullInNonNullableColumn {
+        column: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Invalid value '{value}' for copy option {option}"))]
+    InvalidCopyOption {
+        option: String,
+        value: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("{error}\n  File '{file}', line {line}"))]
+    CopyIntoFileLoad {
+        error: String,
+        file: String,
+        line: usize,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display(
+        "Files already existing at the unload destination: {destination}. Use overwrite option to force unloading."
+    ))]
+    UnloadFilesExist {
+        destination: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Failed to write unloaded file {file}: {error}"))]
+    UnloadWrite {
+        file: String,
+        #[snafu(source)]
+        error: std::io::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Failed to write unloaded file {file}: {error}"))]
+    UnloadParquetWrite {
+        file: String,
+        #[snafu(source)]
+        error: datafusion::parquet::errors::ParquetError,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Unloading to {format} files is not supported"))]
+    UnsupportedUnloadFormat {
+        format: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Unsupported binding type {binding_type}"))]
+    UnsupportedBindingType {
+        binding_type: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Invalid value '{value}' for binding of type {binding_type}"))]
+    InvalidBindingValue {
+        binding_type: String,
+        value: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Array bindings are only supported by INSERT ... VALUES with a single row"))]
+    ArrayBindingsNotSupported {
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Array bindings must all have the same number of values"))]
+    ArrayBindingsLengthMismatch {
+        #[snafu(implicit)]
+        location: Location,
+    },
+
     #[snafu(display("Cannot refresh catalog list: {source}"))]
     RefreshCatalogList {
         #[snafu(source(from(CatalogError, Box::new)))]
@@ -753,6 +979,12 @@ pub enum ObjectType {
     Database,
     Schema,
     Table,
+    FileFormat,
+    Stage,
+    User,
+    Role,
+    AccessToken,
+    Location,
 }
 
 impl Display for ObjectType {
@@ -762,6 +994,12 @@ impl Display for ObjectType {
             Self::Database => write!(f, "database"),
             Self::Schema => write!(f, "schema"),
             Self::Table => write!(f, "table"),
+            Self::FileFormat => write!(f, "file format"),
+            Self::Stage => write!(f, "stage"),
+            Self::User => write!(f, "user"),
+            Self::Role => write!(f, "role"),
+            Self::AccessToken => write!(f, "programmatic access token"),
+            Self::Location => write!(f, "external location"),
         }
     }
 }
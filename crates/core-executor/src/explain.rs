@@ -0,0 +1,490 @@
+//! `EXPLAIN [ USING { TABULAR | JSON | TEXT } ] <query>` statements.
+//!
+//! The `USING` clause isn't covered by the SQL parser grammar, so these statements are
+//! recognized here, before the regular parsing step. Without it the plan is tabular, as in
+//! Snowflake. DataFusion's `EXPLAIN` is left for its `ANALYZE`, `VERBOSE` and `FORMAT`
+//! options and for statements other than queries. The plan is described by Snowflake-like
+//! steps, derived from the optimized physical plan of the query, and Iceberg scans report
+//! the data files assigned to them after pruning, out of the data files of the table.
+use crate::error::{self as ex_error, Result};
+use crate::user::parse_word;
+use datafusion::arrow::array::{Int64Array, RecordBatch, StringArray};
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use datafusion::arrow::error::ArrowError;
+use datafusion::catalog::TableProvider;
+use datafusion::common::tree_node::{TreeNode, TreeNodeRecursion};
+use datafusion::datasource::physical_plan::FileScanConfig;
+use datafusion::datasource::source::DataSourceExec;
+use datafusion::datasource::source_as_provider;
+use datafusion::logical_expr::LogicalPlan;
+use datafusion::physical_plan::aggregates::{AggregateExec, AggregateMode};
+use datafusion::physical_plan::{ExecutionPlan, displayable};
+use datafusion::sql::sqlparser::ast::Query;
+use datafusion::sql::sqlparser::dialect::{Dialect, SnowflakeDialect, dialect_from_str};
+use datafusion::sql::sqlparser::keywords::Keyword;
+use datafusion::sql::sqlparser::parser::Parser;
+use datafusion::sql::sqlparser::tokenizer::Token;
+use datafusion_iceberg::DataFusionTable;
+use df_catalog::table::CachingTable;
+use iceberg_rust::catalog::tabular::Tabular;
+use serde_json::{Map, Value, json};
+use snafu::ResultExt;
+use std::fmt::Write;
+use std::sync::Arc;
+use url::Url;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExplainFormat {
+    Tabular,
+    Json,
+    Text,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExplainUsingStatement {
+    pub format: ExplainFormat,
+    pub query: Box<Query>,
+}
+
+/// Parses `EXPLAIN [ USING <format> ] <query>`, other statements, including `EXPLAIN`
+/// of statements other than queries, are left to the regular parser
+pub fn parse_explain_statement(sql: &str, dialect: &str) -> Result<Option<ExplainUsingStatement>> {
+    let dialect: Box<dyn Dialect> =
+        dialect_from_str(dialect).unwrap_or_else(|| Box::new(SnowflakeDialect {}));
+    let Ok(mut parser) = Parser::new(dialect.as_ref()).try_with_sql(sql) else {
+        return Ok(None);
+    };
+    if !parser.parse_keyword(Keyword::EXPLAIN) {
+        return Ok(None);
+    }
+
+    let format = if !parser.parse_keyword(Keyword::USING) {
+        if !starts_query(&parser) {
+            return Ok(None);
+        }
+        ExplainFormat::Tabular
+    } else if parse_word(&mut parser, "TABULAR") {
+        ExplainFormat::Tabular
+    } else if parse_word(&mut parser, "JSON") {
+        ExplainFormat::Json
+    } else if parse_word(&mut parser, "TEXT") {
+        ExplainFormat::Text
+    } else {
+        return parser
+            .expected("TABULAR, JSON or TEXT", parser.peek_token())
+            .context(ex_error::SqlParserSnafu);
+    };
+    let query = parser.parse_query().context(ex_error::SqlParserSnafu)?;
+
+    let _ = parser.consume_token(&Token::SemiColon);
+    if parser.peek_token().token != Token::EOF {
+        return parser
+            .expected("end of statement", parser.peek_token())
+            .context(ex_error::SqlParserSnafu);
+    }
+    Ok(Some(ExplainUsingStatement { format, query }))
+}
+
+/// Whether the next token starts a query
+fn starts_query(parser: &Parser) -> bool {
+    match parser.peek_token().token {
+        Token::Word(word) => matches!(
+            word.keyword,
+            Keyword::SELECT | Keyword::WITH | Keyword::VALUES
+        ),
+        Token::LParen => true,
+        _ => false,
+    }
+}
+
+/// Iceberg table read by a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScannedTable {
+    pub name: String,
+    /// Path of the table location within its object store
+    pub location: String,
+    /// Data files of the current snapshot
+    pub total_files: usize,
+}
+
+/// Iceberg tables scanned by the logical plan
+pub async fn scanned_tables(plan: &LogicalPlan) -> Vec<ScannedTable> {
+    let mut providers = Vec::new();
+    let _ = plan.apply_with_subqueries(|node| {
+        if let LogicalPlan::TableScan(scan) = node
+            && let Ok(provider) = source_as_provider(&scan.source)
+        {
+            providers.push((scan.table_name.to_string(), provider));
+        }
+        Ok(TreeNodeRecursion::Continue)
+    });
+
+    let mut tables = Vec::new();
+    for (name, provider) in providers {
+        let cached_table: Option<Arc<dyn TableProvider>> = provider
+            .as_any()
+            .downcast_ref::<CachingTable>()
+            .map(|caching_table| caching_table.table.clone());
+        let provider = cached_table.unwrap_or(provider);
+        let Some(table) = provider.as_any().downcast_ref::<DataFusionTable>() else {
+            continue;
+        };
+        let lock = table.tabular.read().await;
+        let Tabular::Table(table) = &*lock else {
+            continue;
+        };
+        let metadata = table.metadata();
+        let total_files = match metadata.current_snapshot(None) {
+            Ok(Some(snapshot)) => snapshot
+                .summary()
+                .other
+                .get("total-data-files")
+                .and_then(|value| value.parse::<usize>().ok())
+                .unwrap_or(0),
+            _ => 0,
+        };
+        let location = Url::parse(&metadata.location)
+            .map_or_else(|_| metadata.location.clone(), |url| url.path().to_string());
+        tables.push(ScannedTable {
+            name,
+            location: location.trim_matches('/').to_string(),
+            total_files,
+        });
+    }
+    tables
+}
+
+/// Data files of a scan
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanPartitions {
+    pub total: usize,
+    pub assigned: usize,
+    pub bytes_assigned: u64,
+}
+
+impl ScanPartitions {
+    fn add(&mut self, other: Self) {
+        self.total += other.total;
+        self.assigned += other.assigned;
+        self.bytes_assigned += other.bytes_assigned;
+    }
+}
+
+/// An operation of the plan, as shown by Snowflake `EXPLAIN`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExplainStep {
+    pub id: usize,
+    pub parent: Option<usize>,
+    pub operation: String,
+    pub objects: Option<String>,
+    pub expressions: Option<String>,
+    pub partitions: Option<ScanPartitions>,
+}
+
+/// Steps of the physical plan in pre-order, operators which only move batches between
+/// partitions, like `RepartitionExec`, are left out
+#[must_use]
+pub fn explain_steps(plan: &dyn ExecutionPlan, tables: &[ScannedTable]) -> Vec<ExplainStep> {
+    let mut steps = Vec::new();
+    add_steps(plan, None, tables, &mut steps);
+    if steps.first().is_some_and(|step| step.operation == "Result") {
+        return steps;
+    }
+    // Like in Snowflake plans, the result is always the root operation
+    let result = ExplainStep {
+        id: 0,
+        parent: None,
+        operation: "Result".to_string(),
+        objects: None,
+        expressions: output_columns(plan),
+        partitions: None,
+    };
+    std::iter::once(result)
+        .chain(steps.into_iter().map(|step| ExplainStep {
+            id: step.id + 1,
+            parent: Some(step.parent.map_or(0, |parent| parent + 1)),
+            ..step
+        }))
+        .collect()
+}
+
+fn add_steps(
+    plan: &dyn ExecutionPlan,
+    parent: Option<usize>,
+    tables: &[ScannedTable],
+    steps: &mut Vec<ExplainStep>,
+) {
+    let parent = match operation(plan, steps.is_empty()) {
+        Some(operation) => {
+            let id = steps.len();
+            let (objects, partitions) = scan_files(plan, tables).unzip();
+            steps.push(ExplainStep {
+                id,
+                parent,
+                operation,
+                objects: objects.flatten(),
+                expressions: expressions(plan),
+                partitions,
+            });
+            Some(id)
+        }
+        None => parent,
+    };
+    for child in plan.children() {
+        add_steps(child.as_ref(), parent, tables, steps);
+    }
+}
+
+fn operation(plan: &dyn ExecutionPlan, is_root: bool) -> Option<String> {
+    if let Some(aggregate) = plan.as_any().downcast_ref::<AggregateExec>()
+        && *aggregate.mode() == AggregateMode::Partial
+    {
+        return None;
+    }
+    let operation = match plan.name() {
+        "CoalesceBatchesExec"
+        | "CoalescePartitionsExec"
+        | "RepartitionExec"
+        | "CooperativeExec"
+        | "LocalLimitExec"
+        | "SortPreservingMergeExec" => return None,
+        "ProjectionExec" if is_root => "Result",
+        "ProjectionExec" => "Projection",
+        "FilterExec" => "Filter",
+        "DataSourceExec" => "TableScan",
+        "AggregateExec" => "Aggregate",
+        "SortExec" => "Sort",
+        "GlobalLimitExec" => "Limit",
+        "UnionExec" | "InterleaveExec" => "UnionAll",
+        "HashJoinExec" | "SortMergeJoinExec" | "NestedLoopJoinExec" | "SymmetricHashJoinExec" => {
+            "Join"
+        }
+        "CrossJoinExec" => "CartesianJoin",
+        "WindowAggExec" | "BoundedWindowAggExec" => "WindowFunction",
+        name => name.trim_end_matches("Exec"),
+    };
+    Some(operation.to_string())
+}
+
+fn expressions(plan: &dyn ExecutionPlan) -> Option<String> {
+    if plan.as_any().is::<DataSourceExec>() {
+        return output_columns(plan);
+    }
+    let line = displayable(plan).one_line().to_string();
+    line.split_once(": ")
+        .map(|(_name, expressions)| expressions.trim().to_string())
+        .filter(|expressions| !expressions.is_empty())
+}
+
+fn output_columns(plan: &dyn ExecutionPlan) -> Option<String> {
+    let columns = plan
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| field.name().clone())
+        .collect::<Vec<_>>();
+    (!columns.is_empty()).then(|| columns.join(", "))
+}
+
+/// Table and data files of a file scan, the table is known for Iceberg tables only
+fn scan_files(
+    plan: &dyn ExecutionPlan,
+    tables: &[ScannedTable],
+) -> Option<(Option<String>, ScanPartitions)> {
+    let config = plan
+        .as_any()
+        .downcast_ref::<DataSourceExec>()?
+        .data_source()
+        .as_any()
+        .downcast_ref::<FileScanConfig>()?;
+    let files = config
+        .file_groups
+        .iter()
+        .flat_map(|group| group.iter())
+        .collect::<Vec<_>>();
+    let assigned = files.len();
+    let bytes_assigned = files.iter().map(|file| file.object_meta.size).sum();
+    let table = files.first().and_then(|file| {
+        tables.iter().find(|table| {
+            file.object_meta
+                .location
+                .as_ref()
+                .starts_with(&table.location)
+        })
+    });
+    Some((
+        table.map(|table| table.name.clone()),
+        ScanPartitions {
+            total: table.map_or(assigned, |table| table.total_files.max(assigned)),
+            assigned,
+            bytes_assigned,
+        },
+    ))
+}
+
+fn global_stats(steps: &[ExplainStep]) -> ScanPartitions {
+    let mut stats = ScanPartitions::default();
+    for partitions in steps.iter().filter_map(|step| step.partitions) {
+        stats.add(partitions);
+    }
+    stats
+}
+
+fn to_int(value: impl TryInto<i64>) -> i64 {
+    value.try_into().unwrap_or(i64::MAX)
+}
+
+/// `EXPLAIN USING TABULAR`, the first row holds global statistics of the plan
+pub fn tabular_batch(steps: &[ExplainStep]) -> std::result::Result<RecordBatch, ArrowError> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("step", DataType::Int64, true),
+        Field::new("id", DataType::Int64, true),
+        Field::new("parent", DataType::Int64, true),
+        Field::new("operation", DataType::Utf8, false),
+        Field::new("objects", DataType::Utf8, true),
+        Field::new("expressions", DataType::Utf8, true),
+        Field::new("partitionsTotal", DataType::Int64, true),
+        Field::new("partitionsAssigned", DataType::Int64, true),
+        Field::new("bytesAssigned", DataType::Int64, true),
+    ]));
+    let global = global_stats(steps);
+    let global_step = ExplainStep {
+        id: 0,
+        parent: None,
+        operation: "GlobalStats".to_string(),
+        objects: None,
+        expressions: None,
+        partitions: Some(global),
+    };
+    let rows = std::iter::once(&global_step)
+        .chain(steps)
+        .collect::<Vec<_>>();
+    // `step` and `id` are NULL for the global statistics
+    let is_global = |index: usize| index == 0;
+
+    RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from_iter(
+                (0..rows.len()).map(|index| (!is_global(index)).then_some(1)),
+            )),
+            Arc::new(Int64Array::from_iter(rows.iter().enumerate().map(
+                |(index, step)| (!is_global(index)).then(|| to_int(step.id)),
+            ))),
+            Arc::new(Int64Array::from_iter(
+                rows.iter().map(|step| step.parent.map(to_int)),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                rows.iter().map(|step| step.operation.as_str()),
+            )),
+            Arc::new(StringArray::from_iter(
+                rows.iter().map(|step| step.objects.as_deref()),
+            )),
+            Arc::new(StringArray::from_iter(
+                rows.iter().map(|step| step.expressions.as_deref()),
+            )),
+            Arc::new(Int64Array::from_iter(rows.iter().map(|step| {
+                step.partitions.map(|partitions| to_int(partitions.total))
+            }))),
+            Arc::new(Int64Array::from_iter(rows.iter().map(|step| {
+                step.partitions
+                    .map(|partitions| to_int(partitions.assigned))
+            }))),
+            Arc::new(Int64Array::from_iter(rows.iter().map(|step| {
+                step.partitions
+                    .map(|partitions| to_int(partitions.bytes_assigned))
+            }))),
+        ],
+    )
+}
+
+/// `EXPLAIN USING JSON`, in the layout of Snowflake `SYSTEM$EXPLAIN_PLAN_JSON`
+#[must_use]
+pub fn json_plan(steps: &[ExplainStep]) -> String {
+    let global = global_stats(steps);
+    let operations = steps
+        .iter()
+        .map(|step| {
+            let mut operation = Map::new();
+            operation.insert("id".to_string(), json!(step.id));
+            if let Some(parent) = step.parent {
+                operation.insert("parentOperators".to_string(), json!([parent]));
+            }
+            operation.insert("operation".to_string(), json!(step.operation));
+            if let Some(objects) = &step.objects {
+                operation.insert("objects".to_string(), json!([objects]));
+            }
+            if let Some(expressions) = &step.expressions {
+                operation.insert("expressions".to_string(), json!([expressions]));
+            }
+            if let Some(partitions) = step.partitions {
+                operation.insert("partitionsTotal".to_string(), json!(partitions.total));
+                operation.insert("partitionsAssigned".to_string(), json!(partitions.assigned));
+                operation.insert(
+                    "bytesAssigned".to_string(),
+                    json!(partitions.bytes_assigned),
+                );
+            }
+            Value::Object(operation)
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "GlobalStats": {
+            "partitionsTotal": global.total,
+            "partitionsAssigned": global.assigned,
+            "bytesAssigned": global.bytes_assigned,
+        },
+        "Operations": [operations],
+    })
+    .to_string()
+}
+
+/// `EXPLAIN USING TEXT`, operations are indented by their depth in the plan
+#[must_use]
+pub fn text_plan(steps: &[ExplainStep]) -> String {
+    let global = global_stats(steps);
+    let mut text = format!(
+        "GlobalStats:\n    partitionsTotal={}\n    partitionsAssigned={}\n    bytesAssigned={}\nOperations:\n",
+        global.total, global.assigned, global.bytes_assigned
+    );
+    let mut depths: Vec<usize> = Vec::with_capacity(steps.len());
+    for step in steps {
+        let depth = step
+            .parent
+            .and_then(|parent| depths.get(parent))
+            .map_or(0, |depth| depth + 1);
+        depths.push(depth);
+        let _ = write!(
+            text,
+            "1:{:<5}{}->{}",
+            step.id,
+            " ".repeat(depth * 5),
+            step.operation
+        );
+        if let Some(objects) = &step.objects {
+            let _ = write!(text, "  {objects}");
+        }
+        if let Some(expressions) = &step.expressions {
+            let _ = write!(text, "  {expressions}");
+        }
+        if let Some(partitions) = step.partitions {
+            let _ = write!(
+                text,
+                "  {{partitionsTotal={}, partitionsAssigned={}, bytesAssigned={}}}",
+                partitions.total, partitions.assigned, partitions.bytes_assigned
+            );
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// `EXPLAIN USING JSON | TEXT` return the plan in a single `content` column
+pub fn content_batch(content: String) -> std::result::Result<RecordBatch, ArrowError> {
+    let schema = Arc::new(Schema::new(vec![Field::new(
+        "content",
+        DataType::Utf8,
+        false,
+    )]));
+    RecordBatch::try_new(schema, vec![Arc::new(StringArray::from(vec![content]))])
+}
@@ -0,0 +1,464 @@
+//! Named `FILE FORMAT` objects.
+//!
+//! `sqlparser` has no grammar for `CREATE / DROP / SHOW / DESCRIBE FILE FORMAT`,
+//! so these statements are recognized here, before the regular parsing step,
+//! and executed against the metastore by `UserQuery::file_format_query`.
+use crate::error::{self as ex_error, Result};
+use core_metastore::{FileFormatOptionValue, FileFormatType};
+use datafusion::sql::sqlparser::ast::{Ident, ObjectName};
+use datafusion::sql::sqlparser::dialect::{Dialect, SnowflakeDialect, dialect_from_str};
+use datafusion::sql::sqlparser::keywords::Keyword;
+use datafusion::sql::sqlparser::parser::{Parser, ParserError};
+use datafusion::sql::sqlparser::tokenizer::Token;
+use snafu::ResultExt;
+use sqlparser::ast::helpers::key_value_options::KeyValueOptions;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// Option referencing a named file format from an inline `FILE_FORMAT = (...)` clause
+pub const FORMAT_NAME_OPTION: &str = "FORMAT_NAME";
+const TYPE_OPTION: &str = "TYPE";
+const COMMENT_OPTION: &str = "COMMENT";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileFormatStatement {
+    Create {
+        name: ObjectName,
+        or_replace: bool,
+        if_not_exists: bool,
+        format_type: FileFormatType,
+        options: BTreeMap<String, FileFormatOptionValue>,
+        comment: Option<String>,
+    },
+    Drop {
+        name: ObjectName,
+        if_exists: bool,
+    },
+    Show {
+        like: Option<String>,
+        scope: ShowFileFormatsIn,
+    },
+    Describe {
+        name: ObjectName,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShowFileFormatsIn {
+    /// No `IN` clause: the current schema
+    Current,
+    Account,
+    Database(Option<ObjectName>),
+    Schema(Option<ObjectName>),
+}
+
+/// Format type and options of a `FILE_FORMAT = (...)` clause, with any `FORMAT_NAME`
+/// reference already resolved against the metastore.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileFormatSpec {
+    pub format_type: Option<FileFormatType>,
+    pub options: BTreeMap<String, FileFormatOptionValue>,
+}
+
+impl FileFormatSpec {
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.options
+            .get(&name.to_ascii_uppercase())
+            .and_then(FileFormatOptionValue::as_single)
+    }
+
+    #[must_use]
+    pub fn get_list(&self, name: &str) -> Option<Vec<String>> {
+        self.options
+            .get(&name.to_ascii_uppercase())
+            .map(FileFormatOptionValue::to_list)
+    }
+}
+
+impl From<&core_metastore::FileFormat> for FileFormatSpec {
+    fn from(file_format: &core_metastore::FileFormat) -> Self {
+        Self {
+            format_type: Some(file_format.format_type),
+            options: file_format.options.clone(),
+        }
+    }
+}
+
+/// Converts an inline `FILE_FORMAT = (...)` clause into a `FileFormatSpec`.
+/// `FORMAT_NAME` is kept among the options, so the caller can resolve it.
+pub fn inline_file_format(file_format: &KeyValueOptions) -> Result<FileFormatSpec> {
+    let mut spec = FileFormatSpec::default();
+    for option in &file_format.options {
+        let name = option.option_name.to_ascii_uppercase();
+        if name == TYPE_OPTION {
+            spec.format_type = Some(parse_format_type(&option.value)?);
+        } else {
+            spec.options.insert(name, parse_option_value(&option.value));
+        }
+    }
+    Ok(spec)
+}
+
+/// Returns the `FORMAT_NAME` referenced by an inline file format clause, if any
+#[must_use]
+pub fn format_name(spec: &FileFormatSpec) -> Option<ObjectName> {
+    spec.get(FORMAT_NAME_OPTION).map(|name| {
+        ObjectName::from(
+            name.split('.')
+                .map(|part| Ident::new(part.trim_matches('"')))
+                .collect::<Vec<_>>(),
+        )
+    })
+}
+
+fn parse_format_type(value: &str) -> Result<FileFormatType> {
+    FileFormatType::from_str(value).map_err(|_| {
+        ex_error::UnsupportedFileFormatSnafu {
+            format: value.to_string(),
+        }
+        .build()
+    })
+}
+
+/// List values (`NULL_IF = ('', 'NULL')`) may reach us as their textual form
+fn parse_option_value(value: &str) -> FileFormatOptionValue {
+    let trimmed = value.trim();
+    if let Some(inner) = trimmed
+        .strip_prefix('(')
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        let values = inner
+            .split(',')
+            .map(|v| v.trim().trim_matches('\'').to_string())
+            .filter(|v| !inner.trim().is_empty() || !v.is_empty())
+            .collect();
+        FileFormatOptionValue::List(values)
+    } else {
+        FileFormatOptionValue::Single(value.to_string())
+    }
+}
+
+/// Case-insensitive SQL `LIKE` match used by `SHOW FILE FORMATS LIKE '<pattern>'`
+#[must_use]
+pub fn matches_like_pattern(pattern: &str, value: &str) -> bool {
+    let mut regex = String::from("(?is)^");
+    for ch in pattern.chars() {
+        match ch {
+            '%' => regex.push_str(".*"),
+            '_' => regex.push('.'),
+            other => regex.push_str(&regex::escape(&other.to_string())),
+        }
+    }
+    regex.push('$');
+    regex::Regex::new(&regex).is_ok_and(|re| re.is_match(value))
+}
+
+/// Recognizes file format statements. Returns `Ok(None)` for any other SQL, so
+/// it can be handed over to the regular parser unchanged.
+pub fn parse_file_format_statement(
+    sql: &str,
+    dialect: &str,
+) -> Result<Option<FileFormatStatement>> {
+    let dialect: Box<dyn Dialect> =
+        dialect_from_str(dialect).unwrap_or_else(|| Box::new(SnowflakeDialect {}));
+    let Ok(mut parser) = Parser::new(dialect.as_ref()).try_with_sql(sql) else {
+        return Ok(None);
+    };
+
+    let statement = if parser.parse_keyword(Keyword::CREATE) {
+        let or_replace = parser.parse_keywords(&[Keyword::OR, Keyword::REPLACE]);
+        // Temporary file formats live as long as regular ones for now
+        let _ = parser.parse_keyword(Keyword::TEMPORARY) || parser.parse_keyword(Keyword::TEMP);
+        if !parse_file_format_words(&mut parser, false) {
+            return Ok(None);
+        }
+        parse_create(&mut parser, or_replace).context(ex_error::SqlParserSnafu)?
+    } else if parser.parse_keyword(Keyword::DROP) {
+        if !parse_file_format_words(&mut parser, false) {
+            return Ok(None);
+        }
+        let if_exists = parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+        let name = parser
+            .parse_object_name(false)
+            .context(ex_error::SqlParserSnafu)?;
+        FileFormatStatement::Drop { name, if_exists }
+    } else if parser.parse_keyword(Keyword::SHOW) {
+        if !parse_file_format_words(&mut parser, true) {
+            return Ok(None);
+        }
+        parse_show(&mut parser).context(ex_error::SqlParserSnafu)?
+    } else if parser.parse_keyword(Keyword::DESCRIBE) || parser.parse_keyword(Keyword::DESC) {
+        if !parse_file_format_words(&mut parser, false) {
+            return Ok(None);
+        }
+        let name = parser
+            .parse_object_name(false)
+            .context(ex_error::SqlParserSnafu)?;
+        FileFormatStatement::Describe { name }
+    } else {
+        return Ok(None);
+    };
+
+    let _ = parser.consume_token(&Token::SemiColon);
+    if parser.peek_token().token != Token::EOF {
+        return parser
+            .expected("end of statement", parser.peek_token())
+            .context(ex_error::SqlParserSnafu);
+    }
+    Ok(Some(statement))
+}
+
+/// Consumes `FILE FORMAT` (or `FILE FORMATS` when `plural` is set)
+fn parse_file_format_words(parser: &mut Parser, plural: bool) -> bool {
+    let format_word = if plural { "FORMATS" } else { "FORMAT" };
+    if is_word(&parser.peek_nth_token(0).token, "FILE")
+        && is_word(&parser.peek_nth_token(1).token, format_word)
+    {
+        parser.next_token();
+        parser.next_token();
+        true
+    } else {
+        false
+    }
+}
+
+pub(crate) fn is_word(token: &Token, word: &str) -> bool {
+    matches!(token, Token::Word(w) if w.quote_style.is_none() && w.value.eq_ignore_ascii_case(word))
+}
+
+fn parse_create(
+    parser: &mut Parser,
+    or_replace: bool,
+) -> std::result::Result<FileFormatStatement, ParserError> {
+    let if_not_exists = parser.parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+    let name = parser.parse_object_name(false)?;
+
+    let mut format_type = None;
+    let mut comment = None;
+    let mut options = BTreeMap::new();
+    for (option, value) in parse_options(parser)? {
+        match option.as_str() {
+            TYPE_OPTION => {
+                let value = value.as_single().unwrap_or_default();
+                format_type = Some(FileFormatType::from_str(value).map_err(|_| {
+                    ParserError::ParserError(format!("Unsupported file format type: {value}"))
+                })?);
+            }
+            COMMENT_OPTION => comment = value.as_single().map(ToString::to_string),
+            _ => {
+                options.insert(option, value);
+            }
+        }
+    }
+
+    Ok(FileFormatStatement::Create {
+        name,
+        or_replace,
+        if_not_exists,
+        // Snowflake defaults to CSV when TYPE is omitted
+        format_type: format_type.unwrap_or_default(),
+        options,
+        comment,
+    })
+}
+
+fn parse_show(parser: &mut Parser) -> std::result::Result<FileFormatStatement, ParserError> {
+    let like = if parser.parse_keyword(Keyword::LIKE) {
+        Some(parser.parse_literal_string()?)
+    } else {
+        None
+    };
+
+    let scope = if parser.parse_keyword(Keyword::IN) {
+        if parser.parse_keyword(Keyword::ACCOUNT) {
+            ShowFileFormatsIn::Account
+        } else if parser.parse_keyword(Keyword::DATABASE) {
+            ShowFileFormatsIn::Database(parse_optional_object_name(parser)?)
+        } else if parser.parse_keyword(Keyword::SCHEMA) {
+            ShowFileFormatsIn::Schema(parse_optional_object_name(parser)?)
+        } else {
+            ShowFileFormatsIn::Schema(Some(parser.parse_object_name(false)?))
+        }
+    } else {
+        ShowFileFormatsIn::Current
+    };
+    Ok(FileFormatStatement::Show { like, scope })
+}
+
+fn parse_optional_object_name(
+    parser: &mut Parser,
+) -> std::result::Result<Option<ObjectName>, ParserError> {
+    match parser.peek_token().token {
+        Token::EOF | Token::SemiColon => Ok(None),
+        _ => Ok(Some(parser.parse_object_name(false)?)),
+    }
+}
+
+/// Parses a whitespace or comma separated list of `NAME = value` options,
+/// where value is a string, a number, a bare word or a parenthesized list of those.
+fn parse_options(
+    parser: &mut Parser,
+) -> std::result::Result<Vec<(String, FileFormatOptionValue)>, ParserError> {
+    let mut options = Vec::new();
+    loop {
+        let _ = parser.consume_token(&Token::Comma);
+        let token = parser.peek_token();
+        let name = match &token.token {
+            Token::EOF | Token::SemiColon => break,
+            Token::Word(w) => w.value.to_ascii_uppercase(),
+            _ => return parser.expected("file format option", token),
+        };
+        parser.next_token();
+        parser.expect_token(&Token::Eq)?;
+
+        let value = if parser.consume_token(&Token::LParen) {
+            let mut values = Vec::new();
+            while !parser.consume_token(&Token::RParen) {
+                values.push(parse_option_literal(parser)?);
+                let _ = parser.consume_token(&Token::Comma);
+            }
+            FileFormatOptionValue::List(values)
+        } else {
+            FileFormatOptionValue::Single(parse_option_literal(parser)?)
+        };
+        options.push((name, value));
+    }
+    Ok(options)
+}
+
+fn parse_option_literal(parser: &mut Parser) -> std::result::Result<String, ParserError> {
+    let token = parser.next_token();
+    match token.token {
+        Token::SingleQuotedString(s) | Token::DoubleQuotedString(s) => Ok(s),
+        Token::Number(n, _) => Ok(n),
+        Token::Word(w) => Ok(w.value),
+        _ => parser.expected("file format option value", token),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn parse(sql: &str) -> Option<FileFormatStatement> {
+        parse_file_format_statement(sql, "snowflake").unwrap()
+    }
+
+    #[test]
+    fn test_parse_create_file_format() {
+        let Some(FileFormatStatement::Create {
+            name,
+            or_replace,
+            if_not_exists,
+            format_type,
+            options,
+            comment,
+        }) = parse(
+            "CREATE OR REPLACE FILE FORMAT my_csv TYPE = CSV FIELD_DELIMITER = '|' \
+             SKIP_HEADER = 1 NULL_IF = ('', 'NULL') COMMENT = 'pipes'",
+        )
+        else {
+            panic!("expected CREATE FILE FORMAT");
+        };
+        assert_eq!(name.to_string(), "my_csv");
+        assert!(or_replace);
+        assert!(!if_not_exists);
+        assert_eq!(format_type, FileFormatType::Csv);
+        assert_eq!(comment.as_deref(), Some("pipes"));
+        assert_eq!(
+            options.get("FIELD_DELIMITER"),
+            Some(&FileFormatOptionValue::Single("|".to_string()))
+        );
+        assert_eq!(
+            options.get("SKIP_HEADER"),
+            Some(&FileFormatOptionValue::Single("1".to_string()))
+        );
+        assert_eq!(
+            options.get("NULL_IF"),
+            Some(&FileFormatOptionValue::List(vec![
+                String::new(),
+                "NULL".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_parse_create_file_format_defaults_to_csv() {
+        let Some(FileFormatStatement::Create { format_type, .. }) =
+            parse("create file format if not exists db.sch.f;")
+        else {
+            panic!("expected CREATE FILE FORMAT");
+        };
+        assert_eq!(format_type, FileFormatType::Csv);
+    }
+
+    #[test]
+    fn test_parse_other_file_format_statements() {
+        assert_eq!(
+            parse("DROP FILE FORMAT IF EXISTS f"),
+            Some(FileFormatStatement::Drop {
+                name: ObjectName::from(vec![Ident::new("f")]),
+                if_exists: true,
+            })
+        );
+        assert_eq!(
+            parse("DESC FILE FORMAT f"),
+            Some(FileFormatStatement::Describe {
+                name: ObjectName::from(vec![Ident::new("f")]),
+            })
+        );
+        assert_eq!(
+            parse("SHOW FILE FORMATS LIKE '%csv%' IN DATABASE embucket"),
+            Some(FileFormatStatement::Show {
+                like: Some("%csv%".to_string()),
+                scope: ShowFileFormatsIn::Database(Some(ObjectName::from(vec![Ident::new(
+                    "embucket"
+                )]))),
+            })
+        );
+        assert_eq!(
+            parse("SHOW FILE FORMATS"),
+            Some(FileFormatStatement::Show {
+                like: None,
+                scope: ShowFileFormatsIn::Current,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_not_file_format_statement() {
+        assert_eq!(parse("CREATE TABLE t (a INT)"), None);
+        assert_eq!(parse("DROP TABLE f"), None);
+        assert_eq!(parse("SELECT 1"), None);
+        assert!(
+            parse_file_format_statement("CREATE FILE FORMAT f TYPE = XML", "snowflake").is_err()
+        );
+    }
+
+    #[test]
+    fn test_matches_like_pattern() {
+        assert!(matches_like_pattern("%csv%", "MY_CSV_FORMAT"));
+        assert!(matches_like_pattern("f_", "f1"));
+        assert!(!matches_like_pattern("f_", "f12"));
+        assert!(matches_like_pattern("a.b", "A.B"));
+        assert!(!matches_like_pattern("a.b", "axb"));
+    }
+
+    #[test]
+    fn test_parse_option_value() {
+        assert_eq!(
+            parse_option_value("('a', 'b')"),
+            FileFormatOptionValue::List(vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(
+            parse_option_value("()"),
+            FileFormatOptionValue::List(vec![])
+        );
+        assert_eq!(
+            parse_option_value("|"),
+            FileFormatOptionValue::Single("|".to_string())
+        );
+    }
+}
@@ -1,16 +1,31 @@
 pub use df_catalog as catalog;
+pub mod access_control;
+pub mod bindings;
+pub mod copy_into;
+pub mod csv;
+pub mod custom_statement;
 pub mod datafusion;
 pub mod dedicated_executor;
 pub mod duckdb;
 pub mod error;
 pub mod error_code;
+pub mod explain;
+pub mod file_format;
 pub mod models;
+pub mod profile;
+pub mod progress;
 pub mod query;
+pub mod result_cache;
+pub mod role;
 pub mod running_queries;
 pub mod service;
 pub mod session;
 pub mod snowflake_error;
+pub mod spool;
+pub mod stage;
 pub mod tracing;
+pub mod unload;
+pub mod user;
 pub mod utils;
 
 #[cfg(test)]
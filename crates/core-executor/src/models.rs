@@ -1,9 +1,11 @@
 use crate::Result;
 use crate::error as ex_error;
+use crate::stage::SourceCompression;
 use crate::utils::{DataSerializationFormat, convert_record_batches, convert_struct_to_timestamp};
 use arrow_schema::SchemaRef;
 use core_history::result_set::{Column, ResultSet, Row};
 use core_history::{QueryRecordId, QueryStatus};
+use core_metastore::StageIdent;
 use datafusion::arrow;
 use datafusion::arrow::array::RecordBatch;
 use datafusion::arrow::datatypes::{DataType, Field, Schema as ArrowSchema, TimeUnit};
@@ -11,10 +13,14 @@ use datafusion::arrow::json::StructMode;
 use datafusion::arrow::json::WriterBuilder;
 use datafusion::arrow::json::reader::ReaderBuilder;
 use datafusion::arrow::json::writer::JsonArray;
+use datafusion::physical_plan::SendableRecordBatchStream;
 use datafusion_common::arrow::datatypes::Schema;
+use datafusion_common::{DataFusionError, ScalarValue};
 use embucket_functions::to_snowflake_datatype;
+use futures::StreamExt;
+use object_store::path::Path;
 use serde::{Deserialize, Serialize};
-use snafu::ResultExt;
+use snafu::{IntoError, ResultExt};
 use std::collections::HashMap;
 use std::io::Cursor;
 use std::sync::Arc;
@@ -26,6 +32,87 @@ pub struct AsyncQueryHandle {
     pub rx: oneshot::Receiver<QueryResultStatus>,
 }
 
+/// Result of a multi-statement request: the result of the parent query,
+/// which the statements are recorded under, and the results of the statements
+pub struct MultiStatementResult {
+    pub result: QueryResult,
+    pub statements: Vec<StatementResult>,
+}
+
+/// Full result of a statement of a multi-statement request, unlike the result
+/// recorded to the query history it's not truncated
+pub struct StatementResult {
+    pub result: QueryResult,
+    /// Snowflake statement type id, see [`crate::utils::statement_type_id`]
+    pub statement_type_id: u32,
+}
+
+/// Multi-statement request executed in the background, its parent query is
+/// recorded as running until the statements finish
+pub struct MultiStatementHandle {
+    pub query_id: QueryRecordId,
+    pub rx: oneshot::Receiver<Result<MultiStatementResult>>,
+}
+
+/// Result of a query streamed while it's executed. The query is finished (and recorded
+/// to the history) once the stream is exhausted, or canceled when it's dropped before.
+pub struct QueryResultStream {
+    pub query_id: QueryRecordId,
+    pub stream: SendableRecordBatchStream,
+}
+
+impl QueryResultStream {
+    #[must_use]
+    pub fn schema(&self) -> SchemaRef {
+        self.stream.schema()
+    }
+
+    /// Next batch of the result, with stream errors converted back to query errors
+    pub async fn next_batch(&mut self) -> Option<Result<RecordBatch>> {
+        let batch = self.stream.next().await?;
+        Some(batch.map_err(|error| {
+            match error {
+                DataFusionError::External(error) => match error.downcast::<crate::Error>() {
+                    Ok(error) => *error,
+                    Err(error) => ex_error::QueryExecutionSnafu {
+                        query_id: self.query_id,
+                    }
+                    .into_error(
+                        ex_error::DataFusionSnafu.into_error(DataFusionError::External(error)),
+                    ),
+                },
+                error => ex_error::QueryExecutionSnafu {
+                    query_id: self.query_id,
+                }
+                .into_error(ex_error::DataFusionSnafu.into_error(error)),
+            }
+        }))
+    }
+}
+
+/// Client which opened a session, as reported by the driver on login
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ClientInfo {
+    pub application: String,
+    pub version: String,
+    pub account_name: String,
+    /// Driver environment, like `OS` or `OS_VERSION`
+    pub environment: HashMap<String, String>,
+}
+
+/// Defaults a session is created with
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct SessionOptions {
+    /// Session parameters, set as with `ALTER SESSION SET`. The `database`, `schema`,
+    /// `warehouse` and `role` parameters are the current ones, as set with `USE`.
+    pub params: HashMap<String, ScalarValue>,
+    pub client_info: Option<ClientInfo>,
+    /// User the session is opened for, its queries run with the privileges of the session role
+    pub user: Option<String>,
+    /// Role of the access token the session is opened with, the only role it can use
+    pub role_restriction: Option<String>,
+}
+
 #[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct QueryContext {
     pub database: Option<String>,
@@ -37,6 +124,26 @@ pub struct QueryContext {
     // async_query flag is not used
     // TODO: remove or use it
     pub async_query: bool,
+    /// Values bound to the `?` / `:N` placeholders of the query, in placeholder order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bindings: Vec<QueryBinding>,
+}
+
+/// A value bound by the client to a query placeholder, typed the way Snowflake
+/// drivers send it: `FIXED`, `REAL`, `TEXT`, `BOOLEAN`, `DATE`, `TIME`,
+/// `TIMESTAMP_NTZ` / `TIMESTAMP_LTZ` / `TIMESTAMP_TZ` or `BINARY`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct QueryBinding {
+    pub binding_type: String,
+    pub value: BindingValue,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum BindingValue {
+    Single(Option<String>),
+    /// Array bind: one value per row of a bulk `INSERT`
+    Array(Vec<Option<String>>),
 }
 
 impl QueryContext {
@@ -54,6 +161,7 @@ impl QueryContext {
             request_id: None,
             ip_address: None,
             async_query: false,
+            bindings: Vec::new(),
         }
     }
 
@@ -80,6 +188,12 @@ impl QueryContext {
         self.async_query = async_query;
         self
     }
+
+    #[must_use]
+    pub fn with_bindings(mut self, bindings: Vec<QueryBinding>) -> Self {
+        self.bindings = bindings;
+        self
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -89,6 +203,37 @@ pub struct QueryResult {
     /// This is required to construct a valid response even when `records` are empty
     pub schema: Arc<ArrowSchema>,
     pub query_id: QueryRecordId,
+    /// Files the client transfers to or from a stage, for `PUT` and `GET`
+    pub file_transfer: Option<FileTransfer>,
+}
+
+/// Direction of a stage file transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum FileTransferCommand {
+    Upload,
+    Download,
+}
+
+/// Stage file transfer of a `PUT` or `GET` statement. The statement only resolves
+/// the stage and the files, the files themselves are transferred by the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileTransfer {
+    pub command: FileTransferCommand,
+    pub stage: StageIdent,
+    /// Location of the stage root on the stage volume
+    pub root: Path,
+    /// Path within the stage the files are uploaded to, or downloaded from
+    pub path: String,
+    /// Local files to upload as given in `PUT`, or the stage files `GET`
+    /// downloads, relative to the stage
+    pub source_locations: Vec<String>,
+    /// Local directory `GET` downloads the files to
+    pub local_location: Option<String>,
+    pub auto_compress: bool,
+    pub source_compression: SourceCompression,
+    pub overwrite: bool,
+    pub parallel: u32,
 }
 
 impl QueryResult {
@@ -195,6 +340,7 @@ impl TryFrom<ResultSet> for QueryResult {
             records: batches,
             schema: schema_ref,
             query_id: result_set.id,
+            file_transfer: None,
         })
     }
 }
@@ -210,6 +356,7 @@ impl QueryResult {
             records,
             schema,
             query_id,
+            file_transfer: None,
         }
     }
     #[must_use]
@@ -218,6 +365,12 @@ impl QueryResult {
         self
     }
 
+    #[must_use]
+    pub fn with_file_transfer(mut self, file_transfer: FileTransfer) -> Self {
+        self.file_transfer = Some(file_transfer);
+        self
+    }
+
     #[must_use]
     pub fn column_info(&self) -> Vec<ColumnInfo> {
         ColumnInfo::from_schema(&self.schema)
@@ -0,0 +1,41 @@
+use core_history::OperatorStats;
+use datafusion::physical_plan::metrics::MetricsSet;
+use datafusion::physical_plan::{ExecutionPlan, displayable};
+
+/// Statistics of the operators of an executed physical plan, in pre-order
+#[must_use]
+pub fn operator_stats(plan: &dyn ExecutionPlan) -> Vec<OperatorStats> {
+    let mut operator_stats = Vec::new();
+    add_operator_stats(plan, None, &mut operator_stats);
+    operator_stats
+}
+
+fn add_operator_stats(
+    plan: &dyn ExecutionPlan,
+    parent_operator_id: Option<usize>,
+    operator_stats: &mut Vec<OperatorStats>,
+) {
+    let operator_id = operator_stats.len();
+    let metrics = plan.metrics().map(|metrics| metrics.aggregate_by_name());
+    operator_stats.push(OperatorStats {
+        operator_id,
+        parent_operator_id,
+        operator_type: plan.name().to_string(),
+        operator_attributes: displayable(plan)
+            .one_line()
+            .to_string()
+            .trim_end()
+            .to_string(),
+        output_rows: metrics.as_ref().and_then(MetricsSet::output_rows),
+        elapsed_compute_ns: metrics.as_ref().and_then(MetricsSet::elapsed_compute),
+        spilled_bytes: metrics.as_ref().and_then(MetricsSet::spilled_bytes),
+        // operators tracking their memory reservation report the peak of each partition
+        memory_peak_bytes: metrics
+            .as_ref()
+            .and_then(|metrics| metrics.sum_by_name("peak_mem_used"))
+            .map(|value| value.as_usize()),
+    });
+    for child in plan.children() {
+        add_operator_stats(child.as_ref(), Some(operator_id), operator_stats);
+    }
+}
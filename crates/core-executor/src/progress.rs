@@ -0,0 +1,98 @@
+use datafusion::datasource::physical_plan::FileScanConfig;
+use datafusion::datasource::source::DataSourceExec;
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion::physical_plan::metrics::{Label, MetricValue, MetricsSet};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Progress of a running query, derived from the metrics of its physical plan
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryProgress {
+    /// Rows produced by the scans of the plan
+    pub rows_scanned: usize,
+    pub bytes_scanned: usize,
+    /// Parquet files opened by the scans so far, of `files_total` files they read
+    pub files_processed: usize,
+    pub files_total: usize,
+    /// The deepest operator which has started but not finished yet
+    pub current_operator: Option<String>,
+}
+
+impl QueryProgress {
+    #[must_use]
+    pub fn from_plan(plan: &dyn ExecutionPlan) -> Self {
+        let mut progress = Self::default();
+        let mut current_operator = None;
+        progress.add_plan(plan, 0, &mut current_operator);
+        progress.current_operator = current_operator.map(|(_depth, name)| name);
+        progress
+    }
+
+    fn add_plan(
+        &mut self,
+        plan: &dyn ExecutionPlan,
+        depth: usize,
+        current_operator: &mut Option<(usize, String)>,
+    ) {
+        let metrics = plan.metrics().unwrap_or_default();
+        let children = plan.children();
+
+        if children.is_empty() {
+            self.rows_scanned += metrics.output_rows().unwrap_or_default();
+        }
+        self.bytes_scanned += metrics
+            .sum_by_name("bytes_scanned")
+            .map_or(0, |bytes| bytes.as_usize());
+        if let Some(source) = plan.as_any().downcast_ref::<DataSourceExec>()
+            && let Some(config) = source
+                .data_source()
+                .as_any()
+                .downcast_ref::<FileScanConfig>()
+        {
+            self.files_total += config
+                .file_groups
+                .iter()
+                .map(|group| group.len())
+                .sum::<usize>();
+            self.files_processed += opened_files(&metrics);
+        }
+        if is_running(&metrics)
+            && current_operator
+                .as_ref()
+                .is_none_or(|(current_depth, _)| depth > *current_depth)
+        {
+            *current_operator = Some((depth, plan.name().to_string()));
+        }
+
+        for child in children {
+            self.add_plan(child.as_ref(), depth + 1, current_operator);
+        }
+    }
+}
+
+/// Parquet scans register the metrics of a file, labeled with its name, when they open it
+fn opened_files(metrics: &MetricsSet) -> usize {
+    metrics
+        .iter()
+        .flat_map(|metric| metric.labels())
+        .filter(|label| label.name() == "filename")
+        .map(Label::value)
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// Operator has produced or computed something, and not all of its partitions are done
+fn is_running(metrics: &MetricsSet) -> bool {
+    let started = metrics.output_rows().unwrap_or_default() > 0
+        || metrics.elapsed_compute().unwrap_or_default() > 0;
+    let mut partitions_done = metrics
+        .iter()
+        .filter_map(|metric| match metric.value() {
+            MetricValue::EndTimestamp(timestamp) => Some(timestamp.value().is_some()),
+            _ => None,
+        })
+        .peekable();
+    let finished = partitions_done.peek().is_some() && partitions_done.all(|done| done);
+    started && !finished
+}
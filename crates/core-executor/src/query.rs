@@ -9,9 +9,17 @@ use super::error::{
     self as ex_error, Error, InvalidColumnIdentifierSnafu, MergeSourceNotSupportedSnafu,
     ObjectType as ExistingObjectType, RefreshCatalogListSnafu, Result,
 };
-use super::running_queries::RunningQueries;
+use super::running_queries::{RunningQueries, RunningQuery, RunningQueryId};
 use super::session::UserSession;
-use super::utils::{NormalizedIdent, is_logical_plan_effectively_empty};
+use super::utils::{NormalizedIdent, is_logical_plan_effectively_empty, statement_type_id};
+use crate::access_control::{AccessControl, PlanAccess, granted_roles, plan_access};
+use crate::bindings;
+use crate::copy_into::{
+    self, FORCE_OPTION, FileLoadResult, FileRows, FileRowsSource, LOAD_SCAN_NAME, LoadResults,
+    LoadStatus, LoadStream, ON_ERROR_OPTION, OnError, PURGE_OPTION, RowError, ValidationMode,
+};
+use crate::csv::{CsvConverter, CsvLoadOptions, CsvRowsSource};
+use crate::custom_statement::{CustomStatement, parse_custom_statement};
 use crate::datafusion::logical_plan::merge::MergeIntoCOWSink;
 use crate::datafusion::physical_optimizer::runtime_physical_optimizer_rules;
 use crate::datafusion::physical_plan::merge::{
@@ -24,17 +32,40 @@ use crate::duckdb::query::{
     query_duck_db_arrow,
 };
 use crate::error::{OperationOn, OperationType};
-use crate::models::{QueryContext, QueryResult};
-use core_history::HistoryStore;
+use crate::explain::{
+    ExplainFormat, ExplainUsingStatement, content_batch, explain_steps, json_plan, scanned_tables,
+    tabular_batch, text_plan,
+};
+use crate::file_format::{
+    FileFormatSpec, FileFormatStatement, ShowFileFormatsIn, format_name, inline_file_format,
+    matches_like_pattern,
+};
+use crate::models::{FileTransfer, FileTransferCommand, QueryContext, QueryResult};
+use crate::profile::operator_stats;
+use crate::result_cache::{TableSnapshot, USE_CACHED_RESULT, current_snapshots, table_snapshots};
+use crate::role::{GrantOn, GrantRow, GrantTo, RoleStatement, ShowGrants};
+use crate::stage::{
+    SourceCompression, StageFiles, StageLocation, StageStatement, parse_stage_location,
+};
+use crate::unload::{self, UnloadOptions, UnloadTarget, UnloadedFile};
+use crate::user::{AlterUserOperation, UserStatement, set_user_property, unset_user_property};
+use async_trait::async_trait;
+use core_history::{HistoryStore, QueryRecordId, QueryStatus};
+use core_metastore::error::UtilSlateDBSnafu;
 use core_metastore::{
-    AwsAccessKeyCredentials, AwsCredentials, FileVolume, Metastore, S3TablesVolume, S3Volume,
-    SchemaIdent as MetastoreSchemaIdent, TableCreateRequest as MetastoreTableCreateRequest,
-    TableFormat as MetastoreTableFormat, TableIdent as MetastoreTableIdent, TableIdent, Volume,
-    VolumeType, models::volumes::create_object_store_from_url,
+    ACCOUNTADMIN_ROLE, AwsAccessKeyCredentials, AwsCredentials, FileFormat as MetastoreFileFormat,
+    FileFormatIdent as MetastoreFileFormatIdent, FileFormatType, FileVolume,
+    Grant as MetastoreGrant, GrantObject, Grantee as MetastoreGrantee, Metastore, PUBLIC_ROLE,
+    Privilege, Role as MetastoreRole, RoleGrant, RoleIdent, RwObject, S3TablesVolume, S3Volume,
+    SchemaIdent as MetastoreSchemaIdent, Stage as MetastoreStage,
+    StageIdent as MetastoreStageIdent, TableCreateRequest as MetastoreTableCreateRequest,
+    TableFormat as MetastoreTableFormat, TableIdent as MetastoreTableIdent, TableIdent,
+    User as MetastoreUser, Volume, VolumeType, models::volumes::create_object_store_from_url,
 };
-use datafusion::arrow::array::{Int64Array, RecordBatch};
+use datafusion::arrow::array::{Int64Array, RecordBatch, StringArray};
 use datafusion::arrow::datatypes::{DataType, Field, Schema as ArrowSchema, SchemaRef};
 use datafusion::arrow::datatypes::{Fields, SchemaBuilder};
+use datafusion::catalog::streaming::StreamingTable;
 use datafusion::catalog::{CatalogProvider, SchemaProvider};
 use datafusion::catalog::{MemoryCatalogProvider, TableProvider};
 use datafusion::datasource::DefaultTableSource;
@@ -47,13 +78,14 @@ use datafusion::datasource::file_format::parquet::ParquetFormat;
 use datafusion::datasource::listing::{
     ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl,
 };
+use datafusion::execution::TaskContext;
 use datafusion::execution::session_state::{SessionContextProvider, SessionState};
 use datafusion::logical_expr::{self, col};
 use datafusion::logical_expr::{LogicalPlan, TableSource};
 use datafusion::physical_optimizer::PhysicalOptimizerRule;
 use datafusion::prelude::{CsvReadOptions, DataFrame};
 use datafusion::scalar::ScalarValue;
-use datafusion::sql::parser::{CreateExternalTable, Statement as DFStatement};
+use datafusion::sql::parser::{CreateExternalTable, ExplainStatement, Statement as DFStatement};
 use datafusion::sql::planner::ParserOptions;
 use datafusion::sql::resolve::resolve_table_references;
 use datafusion::sql::sqlparser::ast::{
@@ -79,20 +111,23 @@ use datafusion_iceberg::catalog::catalog::IcebergCatalog;
 use datafusion_iceberg::catalog::mirror::Mirror;
 use datafusion_iceberg::catalog::schema::IcebergSchema;
 use datafusion_iceberg::table::DataFusionTableConfigBuilder;
-use datafusion_physical_plan::collect;
+use datafusion_physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion_physical_plan::streaming::PartitionStream;
+use datafusion_physical_plan::{SendableRecordBatchStream, collect, execute_stream};
 use df_catalog::catalog::CachingCatalog;
 use df_catalog::catalog_list::CachedEntity;
 use df_catalog::table::CachingTable;
 use duckdb::Connection;
 use embucket_functions::semi_structured::variant::visitors::visit_all;
 use embucket_functions::session_params::SessionProperty;
+use embucket_functions::table::query_operator_stats::operator_stats_batch;
 use embucket_functions::visitors::{
     copy_into_identifiers, fetch_to_limit, functions_rewriter, inline_aliases_in_query,
     like_ilike_any, rlike_regexp_expr_rewriter, select_expr_aliases, table_functions,
     table_functions_cte_relation, timestamp, top_limit,
     unimplemented::functions_checker::visit as unimplemented_functions_checker,
 };
-use futures::TryStreamExt;
+use futures::{StreamExt, TryStreamExt};
 use iceberg_rust::catalog::Catalog;
 use iceberg_rust::catalog::create::CreateTableBuilder;
 use iceberg_rust::catalog::identifier::Identifier;
@@ -107,14 +142,15 @@ use iceberg_rust::spec::types::StructType;
 use iceberg_rust::spec::values::Value as IcebergValue;
 use iceberg_rust::table::manifest_list::snapshot_partition_bounds;
 use object_store::aws::{AmazonS3Builder, AmazonS3ConfigKey as S3Key, resolve_bucket_region};
-use object_store::{ClientOptions, ObjectStore};
+use object_store::path::{Path as ObjectPath, PathPart};
+use object_store::{ClientOptions, ObjectMeta, ObjectStore};
 use snafu::{OptionExt, ResultExt, location};
 use sqlparser::ast::helpers::key_value_options::KeyValueOptions;
 use sqlparser::ast::helpers::stmt_data_loading::StageParamsObject;
 use sqlparser::ast::{
-    AlterTableOperation, AssignmentTarget, CloudProviderParams, MergeAction, MergeClause,
-    MergeClauseKind, MergeInsertKind, ObjectNamePart, ObjectType, PivotValueSource, ShowObjects,
-    ShowStatementFilter, ShowStatementIn, ShowStatementInParentType as ShowType,
+    AlterTableOperation, AssignmentTarget, CloudProviderParams, CopyIntoSnowflakeKind, MergeAction,
+    MergeClause, MergeClauseKind, MergeInsertKind, ObjectNamePart, ObjectType, PivotValueSource,
+    ShowObjects, ShowStatementFilter, ShowStatementIn, ShowStatementInParentType as ShowType,
     TruncateTableTarget, Use, Value, visit_relations_mut,
 };
 use std::collections::hash_map::Entry;
@@ -124,6 +160,8 @@ use std::ops::ControlFlow;
 use std::result::Result as StdResult;
 use std::str::FromStr;
 use std::sync::Arc;
+use tokio::sync::OnceCell;
+use tokio::task::JoinSet;
 use tracing::Instrument;
 use tracing_attributes::instrument;
 use url::Url;
@@ -136,6 +174,8 @@ pub struct UserQuery {
     pub query: String,
     pub session: Arc<UserSession>,
     pub query_context: QueryContext,
+    // privileges of the session role, loaded once for the statement
+    access_control: OnceCell<AccessControl>,
 }
 
 pub enum IcebergCatalogResult {
@@ -156,6 +196,7 @@ impl UserQuery {
             query: query.into(),
             session,
             query_context,
+            access_control: OnceCell::new(),
         }
     }
 
@@ -172,6 +213,25 @@ impl UserQuery {
         Ok(statement)
     }
 
+    /// Parses the query and binds its placeholders, the bound query becomes `self.query`
+    fn bound_statement(&mut self) -> Result<DFStatement> {
+        let mut statement = self.parse_query().context(ex_error::DataFusionSnafu)?;
+        if let DFStatement::Statement(s) = &mut statement
+            && !self.query_context.bindings.is_empty()
+        {
+            bindings::rewrite_placeholders(s, &self.query_context.bindings)?;
+        }
+        self.query = statement.to_string();
+        Ok(statement)
+    }
+
+    /// Logical plan of a query statement, with its table and session references resolved
+    async fn query_plan(&self, query: &mut Query) -> Result<LogicalPlan> {
+        self.traverse_and_update_query(query).await;
+        let plan = self.get_custom_logical_plan(&query.to_string()).await?;
+        self.rewrite_session_references(&plan)
+    }
+
     pub fn statement(&self) -> std::result::Result<DFStatement, DataFusionError> {
         let state = self.session.ctx.state();
         let dialect = state.config().options().sql_parser.dialect.as_str();
@@ -179,9 +239,30 @@ impl UserQuery {
         Ok(statement)
     }
 
-    pub async fn plan(&self) -> std::result::Result<LogicalPlan, DataFusionError> {
-        let statement = self.parse_query()?;
-        self.session.ctx.state().statement_to_plan(statement).await
+    /// Snowflake statement type id of the query, see [`statement_type_id`]
+    pub fn statement_type_id(&self) -> Result<u32> {
+        let state = self.session.ctx.state();
+        let dialect = state.config().options().sql_parser.dialect.as_str();
+        if let Some(statement) = parse_custom_statement(&self.raw_query, dialect)? {
+            return Ok(statement.statement_type_id());
+        }
+        let statement = self.statement().context(ex_error::DataFusionSnafu)?;
+        Ok(statement_type_id(&statement))
+    }
+
+    /// Plans the query without executing it, the schema of the plan is the schema of
+    /// the query result. Placeholders without bindings are left untyped.
+    #[instrument(name = "UserQuery::plan", level = "debug", skip(self), err)]
+    pub async fn plan(&self) -> Result<LogicalPlan> {
+        let mut statement = self.parse_query().context(ex_error::DataFusionSnafu)?;
+        if let DFStatement::Statement(s) = &mut statement {
+            bindings::rewrite_placeholders(s, &self.query_context.bindings)?;
+            if let Statement::Query(subquery) = s.as_mut() {
+                self.traverse_and_update_query(subquery).await;
+            }
+        }
+        self.update_statement_references(&mut statement)?;
+        self.statement_to_plan(&statement).await
     }
 
     fn current_database(&self) -> String {
@@ -200,6 +281,137 @@ impl UserQuery {
             .unwrap_or_else(|| "public".to_string())
     }
 
+    fn current_role(&self) -> String {
+        self.session
+            .get_session_variable("role")
+            .unwrap_or_else(|| PUBLIC_ROLE.to_string())
+    }
+
+    /// Privileges of the session role, sessions without a user have the ones of `PUBLIC`
+    async fn access_control(&self) -> Result<&AccessControl> {
+        self.access_control
+            .get_or_try_init(|| async {
+                let role = self.current_role();
+                self.check_role_restriction(&role)?;
+                AccessControl::load(self.metastore.as_ref(), self.session.user.as_ref(), &role)
+                    .await
+            })
+            .await
+    }
+
+    /// Sessions opened with a role restricted access token can't use another role
+    fn check_role_restriction(&self, role: &str) -> Result<()> {
+        match &self.session.role_restriction {
+            Some(restriction) if !restriction.eq_ignore_ascii_case(role) => {
+                ex_error::RoleRestrictedSnafu { role, restriction }.fail()
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Account level statements, like managing users and roles, need `ACCOUNTADMIN`
+    async fn require_admin(&self, kind: ExistingObjectType, name: &str) -> Result<()> {
+        if self.access_control().await?.is_admin() {
+            return Ok(());
+        }
+        ex_error::InsufficientPrivilegesSnafu {
+            kind: kind.to_string(),
+            name,
+        }
+        .fail()
+    }
+
+    async fn check_table_privilege(
+        &self,
+        privilege: Privilege,
+        table: &ResolvedTableReference,
+    ) -> Result<()> {
+        self.access_control().await?.check_table(
+            privilege,
+            &TableIdent::new(&table.catalog, &table.schema, &table.table),
+        )
+    }
+
+    /// Checks the session role has the privileges on the objects the plan accesses
+    #[instrument(
+        name = "UserQuery::check_plan_privileges",
+        level = "trace",
+        skip(self, plan),
+        err
+    )]
+    async fn check_plan_privileges(&self, plan: &LogicalPlan) -> Result<()> {
+        let accesses = plan_access(plan);
+        if accesses.is_empty() {
+            return Ok(());
+        }
+        let access_control = self.access_control().await?;
+        if access_control.is_admin() {
+            return Ok(());
+        }
+        for access in accesses {
+            match access {
+                PlanAccess::Table(privilege, table) => {
+                    let table = self.resolve_table_ref(table);
+                    if *table.schema == *INFORMATION_SCHEMA {
+                        continue;
+                    }
+                    access_control.check_table(
+                        privilege,
+                        &TableIdent::new(&table.catalog, &table.schema, &table.table),
+                    )?;
+                }
+                PlanAccess::CreateTable(table) => {
+                    let table = self.resolve_table_ref(table);
+                    access_control.check_schema(
+                        Privilege::Create,
+                        &MetastoreSchemaIdent::new(
+                            table.catalog.to_string(),
+                            table.schema.to_string(),
+                        ),
+                    )?;
+                }
+                PlanAccess::CreateSchema(schema) => {
+                    let database =
+                        GrantObject::Database(self.resolve_schema_ref(schema).catalog.to_string());
+                    access_control.check(Privilege::Usage, &database)?;
+                    access_control.check(Privilege::Create, &database)?;
+                }
+                PlanAccess::DropSchema(schema) => {
+                    let schema = self.resolve_schema_ref(schema);
+                    access_control.check(
+                        Privilege::Ownership,
+                        &GrantObject::Schema(MetastoreSchemaIdent::new(
+                            schema.catalog.to_string(),
+                            schema.schema.to_string(),
+                        )),
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes the session role the owner of an object it created
+    async fn grant_ownership(&self, object: GrantObject) -> Result<()> {
+        self.metastore
+            .grant_privilege(MetastoreGrant {
+                privilege: Privilege::Ownership,
+                object,
+                role: self.current_role(),
+                granted_by: None,
+            })
+            .await
+            .context(ex_error::MetastoreSnafu)?;
+        Ok(())
+    }
+
+    async fn revoke_grants_on(&self, object: GrantObject) -> Result<()> {
+        self.metastore
+            .revoke_grants_on(&object)
+            .await
+            .context(ex_error::MetastoreSnafu)
+    }
+
     #[instrument(
         name = "UserQuery::refresh_catalog_partially",
         level = "debug",
@@ -276,7 +488,11 @@ impl UserQuery {
             database: current_database,
             schema: self.current_schema(),
             schemas,
-            warehouse: "default".to_string(),
+            warehouse: self
+                .session
+                .get_session_variable("warehouse")
+                .unwrap_or_else(|| "default".to_string()),
+            role: self.current_role(),
             session_id: self.session.ctx.session_id(),
             version: self.session.config.embucket_version.clone(),
             query_context: self.query_context.clone(),
@@ -330,6 +546,8 @@ impl UserQuery {
             let raw_statement = self.statement().context(ex_error::DataFusionSnafu)?;
             // Allow only SELECT statements for DuckDB acceleration mode
             if is_select_statement(&raw_statement) {
+                // DuckDB reads the tables directly, planning checks the privileges first
+                self.plan().await?;
                 // If DuckDB execution fails for any reason (unsupported syntax, internal error, etc.),
                 // we fall back to the default Embucket execution path below.
                 let result = self.execute_duck_db(raw_statement).await;
@@ -340,8 +558,32 @@ impl UserQuery {
             }
         }
 
-        let statement = self.parse_query().context(ex_error::DataFusionSnafu)?;
-        self.query = statement.to_string();
+        // FILE FORMAT, user, role, stage and Snowflake `EXPLAIN` statements are not covered by
+        // the SQL parser grammar
+        let dialect = self
+            .session
+            .ctx
+            .state()
+            .config()
+            .options()
+            .sql_parser
+            .dialect
+            .clone();
+        if let Some(statement) = parse_custom_statement(&self.raw_query, &dialect)? {
+            return match statement {
+                CustomStatement::FileFormat(statement) => {
+                    Box::pin(self.file_format_query(statement)).await
+                }
+                CustomStatement::User(statement) => Box::pin(self.user_query(statement)).await,
+                CustomStatement::Role(statement) => Box::pin(self.role_query(statement)).await,
+                CustomStatement::Stage(statement) => Box::pin(self.stage_query(statement)).await,
+                CustomStatement::Explain(statement) => {
+                    Box::pin(self.explain_query(statement)).await
+                }
+            };
+        }
+
+        let statement = self.bound_statement()?;
 
         // Record the result as part of the current span.
         tracing::Span::current().record("statement", format!("{statement:#?}"));
@@ -386,6 +628,16 @@ impl UserQuery {
                     if variable.is_empty() | value.is_empty() {
                         return ex_error::OnyUseWithVariablesSnafu.fail();
                     }
+                    // Users can only switch to the roles granted to them
+                    if variable == "role" {
+                        self.check_role_restriction(&value)?;
+                        AccessControl::load(
+                            self.metastore.as_ref(),
+                            self.session.user.as_ref(),
+                            &value,
+                        )
+                        .await?;
+                    }
                     let params = HashMap::from([(
                         variable.to_string(),
                         SessionProperty::from_str_value(
@@ -471,8 +723,7 @@ impl UserQuery {
                     return Box::pin(self.truncate_table(table_names)).await;
                 }
                 Statement::Query(mut subquery) => {
-                    self.traverse_and_update_query(subquery.as_mut()).await;
-                    return Box::pin(self.execute_with_custom_plan(&subquery.to_string())).await;
+                    return Box::pin(self.execute_with_result_cache(&mut subquery)).await;
                 }
                 Statement::Drop { .. } => return Box::pin(self.drop_query(*s)).await,
                 Statement::Merge { .. } => return Box::pin(self.merge_query(*s)).await,
@@ -487,10 +738,73 @@ impl UserQuery {
             }
         } else if let DFStatement::CreateExternalTable(cetable) = statement {
             return Box::pin(self.create_external_table_query(cetable)).await;
+        } else if let DFStatement::Explain(ExplainStatement {
+            analyze: true,
+            verbose: false,
+            statement,
+            ..
+        }) = statement
+            && let DFStatement::Statement(s) = *statement
+            && let Statement::Query(mut subquery) = *s
+        {
+            let plan = self.query_plan(&mut subquery).await?;
+            return Box::pin(self.explain_analyze(plan)).await;
         }
         self.execute_sql(&self.query).await
     }
 
+    /// Executes the query returning its result as a stream of record batches.
+    /// Queries are streamed as their batches are produced, other statements are
+    /// executed with [`Self::execute`] and their result is streamed from memory.
+    #[instrument(
+        name = "UserQuery::execute_stream",
+        level = "debug",
+        skip(self),
+        fields(
+            query_id = self.query_context.query_id.as_i64(),
+            query_uuid = self.query_context.query_id.as_uuid().to_string(),
+        ),
+        err
+    )]
+    pub async fn execute_stream(&mut self) -> Result<SendableRecordBatchStream> {
+        if self.session.config.use_duck_db
+            || self
+                .session
+                .get_session_variable_bool("embucket.execution.acceleration")
+        {
+            let raw_statement = self.statement().context(ex_error::DataFusionSnafu)?;
+            if is_select_statement(&raw_statement) {
+                self.plan().await?;
+                match self.execute_duck_db_stream(raw_statement).await {
+                    Ok(stream) => return Ok(stream),
+                    Err(e) => tracing::warn!("Acceleration execution failed: {}", e),
+                }
+            }
+        }
+
+        let is_query = matches!(
+            self.statement(),
+            Ok(DFStatement::Statement(statement)) if matches!(*statement, Statement::Query(_))
+        );
+        if is_query
+            && let DFStatement::Statement(s) = self.bound_statement()?
+            && let Statement::Query(mut subquery) = *s
+        {
+            let plan = self.query_plan(&mut subquery).await?;
+            let Some((key, snapshots)) = self.result_cache_entry(&subquery, &plan).await else {
+                return self.execute_logical_plan_stream(plan).await;
+            };
+            if let Some(result) = self.cached_result(&key, &snapshots, &plan).await? {
+                return Ok(result_stream(result));
+            }
+            let stream = self.execute_logical_plan_stream(plan).await?;
+            return Ok(self.cache_stream_result(stream, key, snapshots));
+        }
+
+        let result = Box::pin(self.execute()).await?;
+        Ok(result_stream(result))
+    }
+
     #[instrument(
         name = "UserQuery::execute_duck_db",
         level = "debug",
@@ -502,7 +816,35 @@ impl UserQuery {
         ),
         err
     )]
-    pub async fn execute_duck_db(&mut self, mut statement: DFStatement) -> Result<QueryResult> {
+    pub async fn execute_duck_db(&mut self, statement: DFStatement) -> Result<QueryResult> {
+        let stream = self.execute_duck_db_stream(statement).await?;
+        let schema = stream.schema().clone();
+        let records = stream
+            .try_collect::<Vec<_>>()
+            .await
+            .context(ex_error::DataFusionSnafu)?;
+        Ok::<QueryResult, Error>(QueryResult::new(
+            records,
+            schema,
+            self.query_context.query_id,
+        ))
+    }
+
+    #[instrument(
+        name = "UserQuery::execute_duck_db_stream",
+        level = "debug",
+        skip(self),
+        fields(
+            statement,
+            query_id = self.query_context.query_id.as_i64(),
+            query_uuid = self.query_context.query_id.as_uuid().to_string(),
+        ),
+        err
+    )]
+    pub async fn execute_duck_db_stream(
+        &mut self,
+        mut statement: DFStatement,
+    ) -> Result<SendableRecordBatchStream> {
         // Fully qualify all table references
         self.update_statement_references(&mut statement)?;
 
@@ -525,17 +867,7 @@ impl UserQuery {
             let _explain_result = execute_duck_db_explain(explain_conn, &sql).await?;
         }
 
-        let stream = query_duck_db_arrow(&conn, &sql)?;
-        let schema = stream.schema().clone();
-        let records = stream
-            .try_collect::<Vec<_>>()
-            .await
-            .context(ex_error::DataFusionSnafu)?;
-        Ok::<QueryResult, Error>(QueryResult::new(
-            records,
-            schema,
-            self.query_context.query_id,
-        ))
+        query_duck_db_arrow(&conn, &sql)
     }
 
     #[instrument(name = "UserQuery::get_catalog", level = "trace", skip(self), err)]
@@ -720,6 +1052,8 @@ impl UserQuery {
     ) -> Result<QueryResult> {
         let ident = &self.resolve_table_object_name(name.0.clone())?;
         let resolved = self.resolve_table_ref(ident);
+        self.check_table_privilege(Privilege::Ownership, &resolved)
+            .await?;
         // Inject more information to to the error
         let catalog = self.get_catalog(&resolved.catalog).map_err(|_| {
             ex_error::CatalogNotFoundSnafu {
@@ -768,8 +1102,12 @@ impl UserQuery {
         // DROP DATABASE is a special case, since it is not a part of iceberg catalog
         if object_type == ObjectType::Database {
             if let Some(database) = names.first() {
-                self.drop_catalog(&object_name_to_string(database), cascade)
-                    .await?;
+                let database = GrantObject::Database(object_name_to_string(database));
+                self.access_control()
+                    .await?
+                    .check(Privilege::Ownership, &database)?;
+                self.drop_catalog(&database.to_string(), cascade).await?;
+                self.revoke_grants_on(database).await?;
                 return self.status_response();
             }
             let database_name = names
@@ -829,12 +1167,15 @@ impl UserQuery {
                         .drop_table(&ident)
                         .await
                         .context(ex_error::IcebergSnafu)?;
-                    self.refresh_catalog_partially(CachedEntity::Table(MetastoreTableIdent {
+                    let table_ident = MetastoreTableIdent {
                         database: catalog_name.to_string(),
                         schema: schema_name,
                         table: ident.name().to_string(),
-                    }))
-                    .await?;
+                    };
+                    self.revoke_grants_on(GrantObject::Table(table_ident.clone()))
+                        .await?;
+                    self.refresh_catalog_partially(CachedEntity::Table(table_ident))
+                        .await?;
                 } else if let Some(IcebergError::NotFound(_)) = table_resp.as_ref().err() {
                     // Check if the schema exists first
                     if iceberg_catalog
@@ -880,11 +1221,14 @@ impl UserQuery {
                             .deregister_schema(&schema_name.clone(), cascade)
                             .context(ex_error::DataFusionSnafu)?;
                     }
-                    self.refresh_catalog_partially(CachedEntity::Schema(MetastoreSchemaIdent {
+                    let schema_ident = MetastoreSchemaIdent {
                         database: catalog_name.to_string(),
                         schema: schema_name,
-                    }))
-                    .await?;
+                    };
+                    self.revoke_grants_on(GrantObject::Schema(schema_ident.clone()))
+                        .await?;
+                    self.refresh_catalog_partially(CachedEntity::Schema(schema_ident))
+                        .await?;
                 }
                 self.status_response()
             }
@@ -966,6 +1310,8 @@ impl UserQuery {
             plan.clone(),
         )
         .await?;
+        self.grant_ownership(GrantObject::Table(ident.clone()))
+            .await?;
 
         // Now we have created table in the metastore, we need to register it in the catalog
         self.refresh_catalog_partially(CachedEntity::Table(ident))
@@ -1208,13 +1554,21 @@ impl UserQuery {
     pub async fn create_stage_query(&self, statement: Statement) -> Result<QueryResult> {
         let Statement::CreateStage {
             name,
+            or_replace,
+            if_not_exists,
             stage_params,
             file_format,
+            comment,
             ..
         } = statement
         else {
             return ex_error::OnlyCreateStageStatementsSnafu.fail();
         };
+        if stage_params.url.is_none() {
+            return self
+                .create_internal_stage(name, or_replace, if_not_exists, comment)
+                .await;
+        }
 
         let table_name = match name.0.last() {
             Some(ObjectNamePart::Identifier(ident)) => ident.value.clone(),
@@ -1226,24 +1580,14 @@ impl UserQuery {
             }
         };
 
-        let skip_header = file_format.options.iter().any(|option| {
-            option.option_name.eq_ignore_ascii_case("skip_header")
-                && option.value.eq_ignore_ascii_case("1")
-        });
+        let file_format = self.resolve_file_format(&file_format).await?;
+        let skip_header = file_format
+            .get("skip_header")
+            .is_some_and(|value| value.eq_ignore_ascii_case("1"));
 
         let field_optionally_enclosed_by = file_format
-            .options
-            .iter()
-            .find_map(|option| {
-                if option
-                    .option_name
-                    .eq_ignore_ascii_case("field_optionally_enclosed_by")
-                {
-                    Some(option.value.as_bytes()[0])
-                } else {
-                    None
-                }
-            })
+            .get("field_optionally_enclosed_by")
+            .and_then(|value| value.as_bytes().first().copied())
             .unwrap_or(b'"');
 
         let file_path = stage_params.url.unwrap_or_default();
@@ -1311,95 +1655,1667 @@ impl UserQuery {
         self.status_response()
     }
 
+    #[allow(clippy::too_many_lines)]
     #[instrument(
-        name = "UserQuery::copy_into_snowflake_query",
+        name = "UserQuery::file_format_query",
         level = "trace",
         skip(self),
         err
     )]
-    pub async fn copy_into_snowflake_query(&self, statement: Statement) -> Result<QueryResult> {
-        let Statement::CopyIntoSnowflake {
-            into,
-            from_obj,
-            from_obj_alias,
-            stage_params,
-            file_format,
-            ..
-        } = statement
-        else {
-            return ex_error::OnlyCopyIntoStatementsSnafu.fail();
-        };
-        let Some(from_obj) = from_obj else {
-            return ex_error::FromObjectRequiredForCopyIntoStatementsSnafu.fail();
-        };
-
-        let insert_into = self.resolve_table_object_name(into.0)?;
-
-        // Check if this copies from an external location
-        if let Some(location) = get_external_location(&from_obj) {
-            let insert_reference: datafusion_common::TableReference = (&insert_into).into();
-
-            let into_provider = self
-                .session
-                .ctx
-                .table_provider(insert_reference.clone())
-                .await
-                .context(ex_error::DataFusionSnafu)?;
-
-            let url = ListingTableUrl::parse(&location.value).context(ex_error::DataFusionSnafu)?;
+    pub async fn file_format_query(&self, statement: FileFormatStatement) -> Result<QueryResult> {
+        match statement {
+            FileFormatStatement::Create {
+                name,
+                or_replace,
+                if_not_exists,
+                format_type,
+                options,
+                comment,
+            } => {
+                let ident: MetastoreFileFormatIdent =
+                    self.resolve_table_object_name(name.0)?.into();
+                let access_control = self.access_control().await?;
+                access_control.check_schema(
+                    Privilege::Create,
+                    &MetastoreSchemaIdent::new(ident.database.clone(), ident.schema.clone()),
+                )?;
+                let file_format = MetastoreFileFormat {
+                    ident: ident.clone(),
+                    format_type,
+                    options,
+                    comment,
+                };
+                let exists = self
+                    .metastore
+                    .get_file_format(&ident)
+                    .await
+                    .context(ex_error::MetastoreSnafu)?
+                    .is_some();
+                if !exists {
+                    self.metastore
+                        .create_file_format(&ident, file_format)
+                        .await
+                        .context(ex_error::MetastoreSnafu)?;
+                    self.grant_ownership(GrantObject::FileFormat(ident)).await?;
+                } else if or_replace {
+                    // Replacing keeps the owner and the privileges of the file format
+                    access_control.check_file_format(Privilege::Ownership, &ident)?;
+                    self.metastore
+                        .update_file_format(&ident, file_format)
+                        .await
+                        .context(ex_error::MetastoreSnafu)?;
+                } else if !if_not_exists {
+                    return ex_error::ObjectAlreadyExistsSnafu {
+                        r#type: ExistingObjectType::FileFormat,
+                        name: ident.to_string(),
+                    }
+                    .fail();
+                }
+                self.created_entity_response()
+            }
+            FileFormatStatement::Drop { name, if_exists } => {
+                let ident: MetastoreFileFormatIdent =
+                    self.resolve_table_object_name(name.0)?.into();
+                let exists = self
+                    .metastore
+                    .get_file_format(&ident)
+                    .await
+                    .context(ex_error::MetastoreSnafu)?
+                    .is_some();
+                if exists {
+                    self.access_control()
+                        .await?
+                        .check_file_format(Privilege::Ownership, &ident)?;
+                    self.metastore
+                        .delete_file_format(&ident)
+                        .await
+                        .context(ex_error::MetastoreSnafu)?;
+                    self.revoke_grants_on(GrantObject::FileFormat(ident))
+                        .await?;
+                } else if !if_exists {
+                    return ex_error::FileFormatNotFoundSnafu {
+                        name: ident.to_string(),
+                    }
+                    .fail();
+                }
+                self.status_response()
+            }
+            FileFormatStatement::Show { like, scope } => {
+                let (schema_ident, database) = match scope {
+                    ShowFileFormatsIn::Current => (
+                        MetastoreSchemaIdent::new(self.current_database(), self.current_schema()),
+                        None,
+                    ),
+                    ShowFileFormatsIn::Account => (MetastoreSchemaIdent::default(), None),
+                    ShowFileFormatsIn::Database(name) => {
+                        let database = name.map_or_else(
+                            || self.current_database(),
+                            |name| match name.0.last() {
+                                Some(ObjectNamePart::Identifier(ident)) => {
+                                    self.normalize_ident(ident.clone()).value
+                                }
+                                _ => String::new(),
+                            },
+                        );
+                        (MetastoreSchemaIdent::default(), Some(database))
+                    }
+                    ShowFileFormatsIn::Schema(None) => (
+                        MetastoreSchemaIdent::new(self.current_database(), self.current_schema()),
+                        None,
+                    ),
+                    ShowFileFormatsIn::Schema(Some(name)) => {
+                        (self.resolve_schema_object_name(name.0)?.into(), None)
+                    }
+                };
+                let file_formats = self
+                    .metastore
+                    .iter_file_formats(&schema_ident)
+                    .collect()
+                    .await
+                    .context(UtilSlateDBSnafu)
+                    .context(ex_error::MetastoreSnafu)
+                    .map(|file_formats: Vec<RwObject<MetastoreFileFormat>>| {
+                        file_formats
+                            .into_iter()
+                            .filter(|ff| {
+                                database.as_ref().is_none_or(|db| &ff.ident.database == db)
+                            })
+                            .filter(|ff| {
+                                like.as_ref().is_none_or(|pattern| {
+                                    matches_like_pattern(pattern, &ff.ident.name)
+                                })
+                            })
+                            .collect::<Vec<_>>()
+                    })?;
+                self.show_file_formats_response(&file_formats)
+            }
+            FileFormatStatement::Describe { name } => {
+                let ident: MetastoreFileFormatIdent =
+                    self.resolve_table_object_name(name.0)?.into();
+                let file_format = self
+                    .metastore
+                    .get_file_format(&ident)
+                    .await
+                    .context(ex_error::MetastoreSnafu)?
+                    .context(ex_error::FileFormatNotFoundSnafu {
+                        name: ident.to_string(),
+                    })?;
+                let mut properties =
+                    vec![("TYPE".to_string(), file_format.format_type.to_string())];
+                properties.extend(
+                    file_format
+                        .options
+                        .iter()
+                        .map(|(name, value)| (name.clone(), value.to_string())),
+                );
+                if let Some(comment) = &file_format.comment {
+                    properties.push(("COMMENT".to_string(), comment.clone()));
+                }
+                let schema = Arc::new(ArrowSchema::new(vec![
+                    Field::new("property", DataType::Utf8, false),
+                    Field::new("property_value", DataType::Utf8, false),
+                ]));
+                let (names, values): (Vec<String>, Vec<String>) = properties.into_iter().unzip();
+                let batch = RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(StringArray::from(names)),
+                        Arc::new(StringArray::from(values)),
+                    ],
+                )
+                .context(ex_error::ArrowSnafu)?;
+                Ok(QueryResult::new(
+                    vec![batch],
+                    schema,
+                    self.query_context.query_id,
+                ))
+            }
+        }
+    }
 
-            let object_store = self
-                .get_object_store_from_stage_params(stage_params, &url)
-                .await?;
+    fn show_file_formats_response(
+        &self,
+        file_formats: &[RwObject<MetastoreFileFormat>],
+    ) -> Result<QueryResult> {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("created_on", DataType::Utf8, false),
+            Field::new("name", DataType::Utf8, false),
+            Field::new("database_name", DataType::Utf8, false),
+            Field::new("schema_name", DataType::Utf8, false),
+            Field::new("type", DataType::Utf8, false),
+            Field::new("comment", DataType::Utf8, true),
+            Field::new("format_options", DataType::Utf8, false),
+        ]));
+        let format_options = file_formats
+            .iter()
+            .map(|ff| serde_json::to_string(&ff.options).context(ex_error::SerdeParseSnafu))
+            .collect::<Result<Vec<_>>>()?;
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from_iter_values(
+                    file_formats.iter().map(|ff| ff.created_at.to_string()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    file_formats.iter().map(|ff| ff.ident.name.clone()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    file_formats.iter().map(|ff| ff.ident.database.clone()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    file_formats.iter().map(|ff| ff.ident.schema.clone()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    file_formats.iter().map(|ff| ff.format_type.to_string()),
+                )),
+                Arc::new(StringArray::from(
+                    file_formats
+                        .iter()
+                        .map(|ff| ff.comment.clone())
+                        .collect::<Vec<_>>(),
+                )),
+                Arc::new(StringArray::from(format_options)),
+            ],
+        )
+        .context(ex_error::ArrowSnafu)?;
+        Ok(QueryResult::new(
+            vec![batch],
+            schema,
+            self.query_context.query_id,
+        ))
+    }
 
-            self.session
-                .ctx
-                .register_object_store(url.object_store().as_ref(), object_store);
+    #[allow(clippy::too_many_lines)]
+    #[instrument(name = "UserQuery::stage_query", level = "trace", skip(self), err)]
+    pub async fn stage_query(&self, statement: StageStatement) -> Result<QueryResult> {
+        match statement {
+            StageStatement::Drop { name, if_exists } => {
+                let ident: MetastoreStageIdent = self.resolve_table_object_name(name.0)?.into();
+                let exists = self
+                    .metastore
+                    .get_stage(&ident)
+                    .await
+                    .context(ex_error::MetastoreSnafu)?
+                    .is_some();
+                if exists {
+                    self.access_control()
+                        .await?
+                        .check_stage(Privilege::Ownership, &ident)?;
+                    self.metastore
+                        .delete_stage(&ident)
+                        .await
+                        .context(ex_error::MetastoreSnafu)?;
+                    self.revoke_grants_on(GrantObject::Stage(ident)).await?;
+                } else if !if_exists {
+                    return ex_error::StageNotFoundSnafu {
+                        name: ident.to_string(),
+                    }
+                    .fail();
+                }
+                self.status_response()
+            }
+            StageStatement::Show { like } => {
+                let schema_ident =
+                    MetastoreSchemaIdent::new(self.current_database(), self.current_schema());
+                let stages = self
+                    .metastore
+                    .iter_stages(&schema_ident)
+                    .collect()
+                    .await
+                    .context(UtilSlateDBSnafu)
+                    .context(ex_error::MetastoreSnafu)
+                    .map(|stages: Vec<RwObject<MetastoreStage>>| {
+                        stages
+                            .into_iter()
+                            .filter(|stage| {
+                                like.as_ref().is_none_or(|pattern| {
+                                    matches_like_pattern(pattern, &stage.ident.name)
+                                })
+                            })
+                            .collect::<Vec<_>>()
+                    })?;
+                self.show_stages_response(&stages)
+            }
+            StageStatement::Put {
+                source,
+                location,
+                options,
+            } => {
+                let stage = self.resolve_stage(&location.name).await?;
+                let root = self.stage_url(&stage, "").await?.prefix().clone();
+                Ok(self.status_response()?.with_file_transfer(FileTransfer {
+                    command: FileTransferCommand::Upload,
+                    stage,
+                    root,
+                    path: location.path,
+                    source_locations: vec![source],
+                    local_location: None,
+                    auto_compress: options.auto_compress,
+                    source_compression: options.source_compression,
+                    overwrite: options.overwrite,
+                    parallel: options.parallel,
+                }))
+            }
+            StageStatement::Get {
+                location,
+                target,
+                parallel,
+                pattern,
+            } => {
+                let files = self.stage_files(&location, pattern.as_deref()).await?;
+                let source_locations = files
+                    .files
+                    .iter()
+                    .map(|file| files.relative_name(file))
+                    .collect();
+                Ok(self.status_response()?.with_file_transfer(FileTransfer {
+                    command: FileTransferCommand::Download,
+                    stage: files.stage,
+                    root: files.root,
+                    path: location.path,
+                    source_locations,
+                    local_location: Some(target),
+                    auto_compress: false,
+                    source_compression: SourceCompression::None,
+                    overwrite: true,
+                    parallel,
+                }))
+            }
+            StageStatement::List { location, pattern } => {
+                let files = self.stage_files(&location, pattern.as_deref()).await?;
+                let schema = Arc::new(ArrowSchema::new(vec![
+                    Field::new("name", DataType::Utf8, false),
+                    Field::new("size", DataType::Int64, false),
+                    Field::new("md5", DataType::Utf8, true),
+                    Field::new("last_modified", DataType::Utf8, false),
+                ]));
+                let batch = RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(StringArray::from_iter_values(
+                            files.files.iter().map(|file| files.listed_name(file)),
+                        )),
+                        Arc::new(Int64Array::from_iter_values(
+                            files
+                                .files
+                                .iter()
+                                .map(|file| i64::try_from(file.size).unwrap_or(i64::MAX)),
+                        )),
+                        Arc::new(StringArray::from(
+                            files
+                                .files
+                                .iter()
+                                .map(|file| {
+                                    file.e_tag
+                                        .as_ref()
+                                        .map(|e_tag| e_tag.trim_matches('"').to_string())
+                                })
+                                .collect::<Vec<_>>(),
+                        )),
+                        Arc::new(StringArray::from_iter_values(files.files.iter().map(
+                            |file| {
+                                file.last_modified
+                                    .format("%a, %d %b %Y %H:%M:%S GMT")
+                                    .to_string()
+                            },
+                        ))),
+                    ],
+                )
+                .context(ex_error::ArrowSnafu)?;
+                Ok(QueryResult::new(
+                    vec![batch],
+                    schema,
+                    self.query_context.query_id,
+                ))
+            }
+            StageStatement::Remove { location, pattern } => {
+                let files = self.stage_files(&location, pattern.as_deref()).await?;
+                for file in &files.files {
+                    files
+                        .object_store
+                        .delete(&file.location)
+                        .await
+                        .context(ex_error::ObjectStoreSnafu)?;
+                }
+                let schema = Arc::new(ArrowSchema::new(vec![
+                    Field::new("name", DataType::Utf8, false),
+                    Field::new("result", DataType::Utf8, false),
+                ]));
+                let batch = RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(StringArray::from_iter_values(
+                            files.files.iter().map(|file| files.listed_name(file)),
+                        )),
+                        Arc::new(StringArray::from_iter_values(
+                            files.files.iter().map(|_| "removed"),
+                        )),
+                    ],
+                )
+                .context(ex_error::ArrowSnafu)?;
+                Ok(QueryResult::new(
+                    vec![batch],
+                    schema,
+                    self.query_context.query_id,
+                ))
+            }
+        }
+    }
 
-            let config = self
-                .build_listing_table_config(file_format, &into_provider, url)
+    /// `CREATE STAGE` without a `URL`: an internal stage on the volume of its database
+    async fn create_internal_stage(
+        &self,
+        name: ObjectName,
+        or_replace: bool,
+        if_not_exists: bool,
+        comment: Option<String>,
+    ) -> Result<QueryResult> {
+        let ident: MetastoreStageIdent = self.resolve_table_object_name(name.0)?.into();
+        let access_control = self.access_control().await?;
+        access_control.check_schema(
+            Privilege::Create,
+            &MetastoreSchemaIdent::new(ident.database.clone(), ident.schema.clone()),
+        )?;
+        let exists = self
+            .metastore
+            .get_stage(&ident)
+            .await
+            .context(ex_error::MetastoreSnafu)?
+            .is_some();
+        if exists {
+            if if_not_exists {
+                return self.status_response();
+            } else if !or_replace {
+                return ex_error::ObjectAlreadyExistsSnafu {
+                    r#type: ExistingObjectType::Stage,
+                    name: ident.to_string(),
+                }
+                .fail();
+            }
+            // Replacing a stage drops the files uploaded to it
+            access_control.check_stage(Privilege::Ownership, &ident)?;
+            self.metastore
+                .delete_stage(&ident)
+                .await
+                .context(ex_error::MetastoreSnafu)?;
+            self.revoke_grants_on(GrantObject::Stage(ident.clone()))
                 .await?;
+        }
+        self.metastore
+            .create_stage(
+                &ident,
+                MetastoreStage {
+                    ident: ident.clone(),
+                    comment,
+                },
+            )
+            .await
+            .context(ex_error::MetastoreSnafu)?;
+        self.grant_ownership(GrantObject::Stage(ident)).await?;
+        self.status_response()
+    }
+
+    /// Stage of a location, files of a stage are read and written with `USAGE` on it
+    async fn resolve_stage(&self, name: &ObjectName) -> Result<MetastoreStageIdent> {
+        let ident: MetastoreStageIdent = self.resolve_table_object_name(name.0.clone())?.into();
+        self.metastore
+            .get_stage(&ident)
+            .await
+            .context(ex_error::MetastoreSnafu)?
+            .context(ex_error::StageNotFoundSnafu {
+                name: ident.to_string(),
+            })?;
+        self.access_control()
+            .await?
+            .check_stage(Privilege::Usage, &ident)?;
+        Ok(ident)
+    }
 
-            let table_provider =
-                ListingTable::try_new(config).context(ex_error::DataFusionSnafu)?;
+    /// URL of `path` within an internal stage, every segment of the path is a
+    /// part of its own so it can't leave the stage directory
+    async fn stage_url(&self, stage: &MetastoreStageIdent, path: &str) -> Result<ListingTableUrl> {
+        let stage_url = self
+            .metastore
+            .url_for_stage(stage)
+            .await
+            .context(ex_error::MetastoreSnafu)?;
+        let path = ObjectPath::from_iter(
+            path.split('/')
+                .filter(|part| !part.is_empty())
+                .map(PathPart::from),
+        );
+        ListingTableUrl::parse(format!("{stage_url}/{path}")).context(ex_error::DataFusionSnafu)
+    }
 
-            let builder = LogicalPlanBuilder::scan(
-                "external_location",
-                Arc::new(DefaultTableSource::new(Arc::new(table_provider))),
-                None,
-            )
+    /// Files of an internal stage below `location`, matching the `PATTERN` regex
+    async fn stage_files(
+        &self,
+        location: &StageLocation,
+        pattern: Option<&str>,
+    ) -> Result<StageFiles> {
+        let stage = self.resolve_stage(&location.name).await?;
+        let object_store = self
+            .metastore
+            .stage_object_store(&stage)
+            .await
+            .context(ex_error::MetastoreSnafu)?;
+        let root = self.stage_url(&stage, "").await?.prefix().clone();
+        let url = self.stage_url(&stage, &location.path).await?;
+        let files = copy_into::filter_files(
+            copy_into::list_files(&object_store, &url).await?,
+            &url,
+            None,
+            pattern,
+        )?;
+        Ok(StageFiles {
+            stage,
+            object_store,
+            root,
+            files,
+        })
+    }
+
+    fn show_stages_response(&self, stages: &[RwObject<MetastoreStage>]) -> Result<QueryResult> {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("created_on", DataType::Utf8, false),
+            Field::new("name", DataType::Utf8, false),
+            Field::new("database_name", DataType::Utf8, false),
+            Field::new("schema_name", DataType::Utf8, false),
+            Field::new("url", DataType::Utf8, false),
+            Field::new("comment", DataType::Utf8, true),
+            Field::new("type", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from_iter_values(
+                    stages.iter().map(|stage| stage.created_at.to_string()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    stages.iter().map(|stage| stage.ident.name.clone()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    stages.iter().map(|stage| stage.ident.database.clone()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    stages.iter().map(|stage| stage.ident.schema.clone()),
+                )),
+                Arc::new(StringArray::from_iter_values(stages.iter().map(|_| ""))),
+                Arc::new(StringArray::from(
+                    stages
+                        .iter()
+                        .map(|stage| stage.comment.clone())
+                        .collect::<Vec<_>>(),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    stages.iter().map(|_| "INTERNAL"),
+                )),
+            ],
+        )
+        .context(ex_error::ArrowSnafu)?;
+        Ok(QueryResult::new(
+            vec![batch],
+            schema,
+            self.query_context.query_id,
+        ))
+    }
+
+    #[allow(clippy::too_many_lines)]
+    #[instrument(name = "UserQuery::user_query", level = "trace", skip(self), err)]
+    pub async fn user_query(&self, statement: UserStatement) -> Result<QueryResult> {
+        match statement {
+            UserStatement::Create {
+                name,
+                or_replace,
+                if_not_exists,
+                properties,
+            } => {
+                let name = self.normalize_ident(name).value;
+                self.require_admin(ExistingObjectType::User, &name).await?;
+                let mut user = MetastoreUser::new(name.clone());
+                for (property, value) in &properties {
+                    set_user_property(&mut user, property, value)?;
+                }
+                let exists = self
+                    .metastore
+                    .get_user(&name)
+                    .await
+                    .context(ex_error::MetastoreSnafu)?
+                    .is_some();
+                if !exists {
+                    self.metastore
+                        .create_user(&name, user)
+                        .await
+                        .context(ex_error::MetastoreSnafu)?;
+                } else if or_replace {
+                    self.metastore
+                        .update_user(&name, user)
+                        .await
+                        .context(ex_error::MetastoreSnafu)?;
+                } else if !if_not_exists {
+                    return ex_error::ObjectAlreadyExistsSnafu {
+                        r#type: ExistingObjectType::User,
+                        name,
+                    }
+                    .fail();
+                }
+                self.created_entity_response()
+            }
+            UserStatement::Alter {
+                name,
+                if_exists,
+                operation,
+            } => {
+                let name = self.normalize_ident(name).value;
+                // Users manage their own access tokens
+                let own_access_token = matches!(
+                    operation,
+                    AlterUserOperation::AddAccessToken { .. }
+                        | AlterUserOperation::RemoveAccessToken(_)
+                ) && self
+                    .session
+                    .user
+                    .as_ref()
+                    .is_some_and(|user| user.eq_ignore_ascii_case(&name));
+                if !own_access_token {
+                    self.require_admin(ExistingObjectType::User, &name).await?;
+                }
+                let Some(user) = self
+                    .metastore
+                    .get_user(&name)
+                    .await
+                    .context(ex_error::MetastoreSnafu)?
+                else {
+                    if if_exists {
+                        return self.status_response();
+                    }
+                    return ex_error::UserNotFoundSnafu { name }.fail();
+                };
+                let mut user = user.data;
+                match operation {
+                    AlterUserOperation::Set(properties) => {
+                        for (property, value) in &properties {
+                            set_user_property(&mut user, property, value)?;
+                        }
+                    }
+                    AlterUserOperation::Unset(properties) => {
+                        for property in &properties {
+                            unset_user_property(&mut user, property)?;
+                        }
+                    }
+                    AlterUserOperation::RenameTo(new_name) => {
+                        let new_name = self.normalize_ident(new_name).value;
+                        user.name.clone_from(&new_name);
+                        // Users are keyed case-insensitively, changing the case only is an update
+                        if !new_name.eq_ignore_ascii_case(&name) {
+                            self.metastore
+                                .create_user(&new_name, user)
+                                .await
+                                .context(ex_error::MetastoreSnafu)?;
+                            self.rename_role_grantee(&name, &new_name).await?;
+                            self.metastore
+                                .delete_user(&name)
+                                .await
+                                .context(ex_error::MetastoreSnafu)?;
+                            return self.status_response();
+                        }
+                    }
+                    AlterUserOperation::AddAccessToken {
+                        name: token_name,
+                        days_to_expiry,
+                        role_restriction,
+                        comment,
+                    } => {
+                        let token_name = self.normalize_ident(token_name).value;
+                        // Tokens created in a role restricted session have the same restriction
+                        if let Some(role) = &role_restriction {
+                            self.check_role_restriction(role)?;
+                        }
+                        let role_restriction =
+                            role_restriction.or_else(|| self.session.role_restriction.clone());
+                        if user.access_token(&token_name).is_some() {
+                            return ex_error::ObjectAlreadyExistsSnafu {
+                                r#type: ExistingObjectType::AccessToken,
+                                name: token_name,
+                            }
+                            .fail();
+                        }
+                        let secret = user
+                            .add_access_token(
+                                &token_name,
+                                days_to_expiry,
+                                role_restriction,
+                                comment,
+                            )
+                            .context(ex_error::MetastoreSnafu)?;
+                        self.metastore
+                            .update_user(&name, user)
+                            .await
+                            .context(ex_error::MetastoreSnafu)?;
+                        return self.access_token_response(&token_name, &secret);
+                    }
+                    AlterUserOperation::RemoveAccessToken(token_name) => {
+                        let token_name = self.normalize_ident(token_name).value;
+                        if !user.remove_access_token(&token_name) {
+                            return ex_error::AccessTokenNotFoundSnafu {
+                                name: token_name,
+                                user: name,
+                            }
+                            .fail();
+                        }
+                    }
+                }
+                self.metastore
+                    .update_user(&name, user)
+                    .await
+                    .context(ex_error::MetastoreSnafu)?;
+                self.status_response()
+            }
+            UserStatement::Drop { name, if_exists } => {
+                let name = self.normalize_ident(name).value;
+                self.require_admin(ExistingObjectType::User, &name).await?;
+                let exists = self
+                    .metastore
+                    .get_user(&name)
+                    .await
+                    .context(ex_error::MetastoreSnafu)?
+                    .is_some();
+                if exists {
+                    self.metastore
+                        .delete_user(&name)
+                        .await
+                        .context(ex_error::MetastoreSnafu)?;
+                } else if !if_exists {
+                    return ex_error::UserNotFoundSnafu { name }.fail();
+                }
+                self.status_response()
+            }
+            UserStatement::Show { like } => {
+                let users = self
+                    .metastore
+                    .iter_users()
+                    .collect()
+                    .await
+                    .context(UtilSlateDBSnafu)
+                    .context(ex_error::MetastoreSnafu)
+                    .map(|users: Vec<RwObject<MetastoreUser>>| {
+                        users
+                            .into_iter()
+                            .filter(|user| {
+                                like.as_ref()
+                                    .is_none_or(|pattern| matches_like_pattern(pattern, &user.name))
+                            })
+                            .collect::<Vec<_>>()
+                    })?;
+                self.show_users_response(&users)
+            }
+            UserStatement::ShowAccessTokens { user } => {
+                let Some(name) = user
+                    .map(|user| self.normalize_ident(user).value)
+                    .or_else(|| self.session.user.clone())
+                else {
+                    return self.show_access_tokens_response(&[]);
+                };
+                if !self
+                    .session
+                    .user
+                    .as_ref()
+                    .is_some_and(|user| user.eq_ignore_ascii_case(&name))
+                {
+                    self.require_admin(ExistingObjectType::User, &name).await?;
+                }
+                let Some(user) = self
+                    .metastore
+                    .get_user(&name)
+                    .await
+                    .context(ex_error::MetastoreSnafu)?
+                else {
+                    return ex_error::UserNotFoundSnafu { name }.fail();
+                };
+                self.show_access_tokens_response(&[user.data])
+            }
+        }
+    }
+
+    fn access_token_response(&self, name: &str, secret: &str) -> Result<QueryResult> {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("token_name", DataType::Utf8, false),
+            Field::new("token_secret", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec![name])),
+                Arc::new(StringArray::from(vec![secret])),
+            ],
+        )
+        .context(ex_error::ArrowSnafu)?;
+        Ok(QueryResult::new(
+            vec![batch],
+            schema,
+            self.query_context.query_id,
+        ))
+    }
+
+    fn show_access_tokens_response(&self, users: &[MetastoreUser]) -> Result<QueryResult> {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("name", DataType::Utf8, false),
+            Field::new("user_name", DataType::Utf8, false),
+            Field::new("role_restriction", DataType::Utf8, true),
+            Field::new("expires_at", DataType::Utf8, false),
+            Field::new("status", DataType::Utf8, false),
+            Field::new("comment", DataType::Utf8, true),
+            Field::new("created_on", DataType::Utf8, false),
+        ]));
+        let tokens: Vec<_> = users
+            .iter()
+            .flat_map(|user| {
+                user.access_tokens
+                    .iter()
+                    .map(move |token| (user.name.as_str(), token))
+            })
+            .collect();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from_iter_values(
+                    tokens.iter().map(|(_, token)| token.name.clone()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    tokens.iter().map(|(user, _)| (*user).to_string()),
+                )),
+                Arc::new(StringArray::from(
+                    tokens
+                        .iter()
+                        .map(|(_, token)| token.role_restriction.clone())
+                        .collect::<Vec<_>>(),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    tokens.iter().map(|(_, token)| token.expires_at.to_string()),
+                )),
+                Arc::new(StringArray::from_iter_values(tokens.iter().map(
+                    |(_, token)| {
+                        if token.is_expired() {
+                            "EXPIRED"
+                        } else {
+                            "ACTIVE"
+                        }
+                    },
+                ))),
+                Arc::new(StringArray::from(
+                    tokens
+                        .iter()
+                        .map(|(_, token)| token.comment.clone())
+                        .collect::<Vec<_>>(),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    tokens.iter().map(|(_, token)| token.created_at.to_string()),
+                )),
+            ],
+        )
+        .context(ex_error::ArrowSnafu)?;
+        Ok(QueryResult::new(
+            vec![batch],
+            schema,
+            self.query_context.query_id,
+        ))
+    }
+
+    /// Grants the roles of a renamed user to its new name
+    async fn rename_role_grantee(&self, name: &str, new_name: &str) -> Result<()> {
+        let role_grants = self
+            .metastore
+            .iter_role_grants(Some(&MetastoreGrantee::User(name.to_string())))
+            .collect()
+            .await
+            .context(UtilSlateDBSnafu)
+            .context(ex_error::MetastoreSnafu)?;
+        for grant in role_grants {
+            self.metastore
+                .grant_role(RoleGrant {
+                    grantee: MetastoreGrantee::User(new_name.to_string()),
+                    ..grant.data
+                })
+                .await
+                .context(ex_error::MetastoreSnafu)?;
+        }
+        Ok(())
+    }
+
+    fn show_users_response(&self, users: &[RwObject<MetastoreUser>]) -> Result<QueryResult> {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("name", DataType::Utf8, false),
+            Field::new("created_on", DataType::Utf8, false),
+            Field::new("login_name", DataType::Utf8, false),
+            Field::new("comment", DataType::Utf8, true),
+            Field::new("disabled", DataType::Utf8, false),
+            Field::new("default_namespace", DataType::Utf8, true),
+            Field::new("default_role", DataType::Utf8, true),
+            Field::new("has_password", DataType::Utf8, false),
+            Field::new("has_rsa_public_key", DataType::Utf8, false),
+        ]));
+        let default_namespace = |user: &RwObject<MetastoreUser>| {
+            user.default_database
+                .as_ref()
+                .map(|database| match &user.default_schema {
+                    Some(schema) => format!("{database}.{schema}"),
+                    None => database.clone(),
+                })
+        };
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from_iter_values(
+                    users.iter().map(|user| user.name.clone()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    users.iter().map(|user| user.created_at.to_string()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    users.iter().map(|user| user.name.to_uppercase()),
+                )),
+                Arc::new(StringArray::from(
+                    users
+                        .iter()
+                        .map(|user| user.comment.clone())
+                        .collect::<Vec<_>>(),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    users.iter().map(|user| user.disabled.to_string()),
+                )),
+                Arc::new(StringArray::from(
+                    users.iter().map(default_namespace).collect::<Vec<_>>(),
+                )),
+                Arc::new(StringArray::from(
+                    users
+                        .iter()
+                        .map(|user| user.default_role.clone())
+                        .collect::<Vec<_>>(),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    users
+                        .iter()
+                        .map(|user| user.password_hash.is_some().to_string()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    users
+                        .iter()
+                        .map(|user| user.rsa_public_keys().next().is_some().to_string()),
+                )),
+            ],
+        )
+        .context(ex_error::ArrowSnafu)?;
+        Ok(QueryResult::new(
+            vec![batch],
+            schema,
+            self.query_context.query_id,
+        ))
+    }
+
+    #[allow(clippy::too_many_lines)]
+    #[instrument(name = "UserQuery::role_query", level = "trace", skip(self), err)]
+    pub async fn role_query(&self, statement: RoleStatement) -> Result<QueryResult> {
+        match statement {
+            RoleStatement::CreateRole {
+                name,
+                or_replace,
+                if_not_exists,
+                comment,
+            } => {
+                let name = self.normalize_ident(name).value;
+                self.require_admin(ExistingObjectType::Role, &name).await?;
+                let exists = self
+                    .metastore
+                    .get_role(&name)
+                    .await
+                    .context(ex_error::MetastoreSnafu)?
+                    .is_some();
+                if exists {
+                    if or_replace {
+                        self.metastore
+                            .delete_role(&name)
+                            .await
+                            .context(ex_error::MetastoreSnafu)?;
+                    } else if if_not_exists {
+                        return self.created_entity_response();
+                    } else {
+                        return ex_error::ObjectAlreadyExistsSnafu {
+                            r#type: ExistingObjectType::Role,
+                            name,
+                        }
+                        .fail();
+                    }
+                }
+                self.metastore
+                    .create_role(
+                        &name,
+                        MetastoreRole {
+                            name: name.clone(),
+                            comment,
+                        },
+                    )
+                    .await
+                    .context(ex_error::MetastoreSnafu)?;
+                self.created_entity_response()
+            }
+            RoleStatement::DropRole { name, if_exists } => {
+                let name = self.normalize_ident(name).value;
+                self.require_admin(ExistingObjectType::Role, &name).await?;
+                // The system roles can't be dropped
+                if name.eq_ignore_ascii_case(ACCOUNTADMIN_ROLE)
+                    || name.eq_ignore_ascii_case(PUBLIC_ROLE)
+                {
+                    return ex_error::InsufficientPrivilegesSnafu {
+                        kind: ExistingObjectType::Role.to_string(),
+                        name,
+                    }
+                    .fail();
+                }
+                let exists = self
+                    .metastore
+                    .get_role(&name)
+                    .await
+                    .context(ex_error::MetastoreSnafu)?
+                    .is_some();
+                if exists {
+                    self.metastore
+                        .delete_role(&name)
+                        .await
+                        .context(ex_error::MetastoreSnafu)?;
+                } else if !if_exists {
+                    return ex_error::RoleNotFoundSnafu { name }.fail();
+                }
+                self.status_response()
+            }
+            RoleStatement::ShowRoles { like } => {
+                let roles = self
+                    .metastore
+                    .iter_roles()
+                    .collect()
+                    .await
+                    .context(UtilSlateDBSnafu)
+                    .context(ex_error::MetastoreSnafu)
+                    .map(|roles: Vec<RwObject<MetastoreRole>>| {
+                        roles
+                            .into_iter()
+                            .filter(|role| {
+                                like.as_ref()
+                                    .is_none_or(|pattern| matches_like_pattern(pattern, &role.name))
+                            })
+                            .collect::<Vec<_>>()
+                    })?;
+                self.show_roles_response(&roles)
+            }
+            RoleStatement::Privileges {
+                revoke,
+                privileges,
+                object,
+                role,
+            } => {
+                let object = self.grant_object(object)?;
+                let role = self.normalize_ident(role).value;
+                if let Some(privilege) = privileges.iter().find(|p| !p.applies_to(&object)) {
+                    return ex_error::InvalidGrantSnafu {
+                        privilege: privilege.to_string(),
+                        kind: object.kind().to_lowercase(),
+                    }
+                    .fail();
+                }
+                // Owners manage the privileges on their objects
+                self.access_control()
+                    .await?
+                    .check(Privilege::Ownership, &object)?;
+                for privilege in privileges {
+                    let grant = MetastoreGrant {
+                        privilege,
+                        object: object.clone(),
+                        role: role.clone(),
+                        granted_by: Some(self.current_role()),
+                    };
+                    if revoke {
+                        self.metastore
+                            .revoke_privilege(&grant)
+                            .await
+                            .context(ex_error::MetastoreSnafu)?;
+                        continue;
+                    }
+                    // An object has a single owner, granting ownership transfers it
+                    if privilege == Privilege::Ownership {
+                        self.revoke_ownership(&object).await?;
+                    }
+                    self.metastore
+                        .grant_privilege(grant)
+                        .await
+                        .context(ex_error::MetastoreSnafu)?;
+                }
+                self.status_response()
+            }
+            RoleStatement::Role {
+                revoke,
+                role,
+                grantee,
+            } => {
+                let role = self.normalize_ident(role).value;
+                let grantee = match grantee {
+                    GrantTo::Role(name) => MetastoreGrantee::Role(self.normalize_ident(name).value),
+                    GrantTo::User(name) => MetastoreGrantee::User(self.normalize_ident(name).value),
+                };
+                self.require_admin(ExistingObjectType::Role, &role).await?;
+                if let (false, MetastoreGrantee::Role(grantee_role)) = (revoke, &grantee) {
+                    let inherited = granted_roles(
+                        self.metastore.as_ref(),
+                        &MetastoreGrantee::Role(role.clone()),
+                    )
+                    .await?;
+                    if grantee_role.eq_ignore_ascii_case(&role)
+                        || inherited
+                            .iter()
+                            .any(|r| r.eq_ignore_ascii_case(grantee_role))
+                    {
+                        return ex_error::CyclicRoleGrantSnafu {
+                            role,
+                            grantee: grantee_role.clone(),
+                        }
+                        .fail();
+                    }
+                }
+                let grant = RoleGrant {
+                    role,
+                    grantee,
+                    granted_by: Some(self.current_role()),
+                };
+                if revoke {
+                    self.metastore
+                        .revoke_role(&grant)
+                        .await
+                        .context(ex_error::MetastoreSnafu)?;
+                } else {
+                    self.metastore
+                        .grant_role(grant)
+                        .await
+                        .context(ex_error::MetastoreSnafu)?;
+                }
+                self.status_response()
+            }
+            RoleStatement::ShowGrants(show) => {
+                let rows = self.grant_rows(show).await?;
+                self.show_grants_response(&rows)
+            }
+        }
+    }
+
+    fn grant_object(&self, object: GrantOn) -> Result<GrantObject> {
+        Ok(match object {
+            GrantOn::Database(name) => GrantObject::Database(
+                name.0
+                    .into_iter()
+                    .filter_map(|part| match part {
+                        ObjectNamePart::Identifier(ident) => {
+                            Some(self.normalize_ident(ident).value)
+                        }
+                        ObjectNamePart::Function(_) => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("."),
+            ),
+            GrantOn::Schema(name) => {
+                GrantObject::Schema(self.resolve_schema_object_name(name.0)?.into())
+            }
+            GrantOn::Table(name) => {
+                GrantObject::Table(self.resolve_table_object_name(name.0)?.into())
+            }
+            GrantOn::Stage(name) => {
+                GrantObject::Stage(self.resolve_table_object_name(name.0)?.into())
+            }
+            GrantOn::FileFormat(name) => {
+                GrantObject::FileFormat(self.resolve_table_object_name(name.0)?.into())
+            }
+        })
+    }
+
+    async fn revoke_ownership(&self, object: &GrantObject) -> Result<()> {
+        let grants = self
+            .metastore
+            .iter_grants(None)
+            .collect()
+            .await
+            .context(UtilSlateDBSnafu)
+            .context(ex_error::MetastoreSnafu)?;
+        for grant in grants {
+            if grant.privilege == Privilege::Ownership && &grant.object == object {
+                self.metastore
+                    .revoke_privilege(&grant)
+                    .await
+                    .context(ex_error::MetastoreSnafu)?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn grant_rows(&self, show: ShowGrants) -> Result<Vec<GrantRow>> {
+        let grants = |role: Option<RoleIdent>| async move {
+            self.metastore
+                .iter_grants(role.as_ref())
+                .collect()
+                .await
+                .context(UtilSlateDBSnafu)
+                .context(ex_error::MetastoreSnafu)
+        };
+        let role_grants = |grantee: Option<MetastoreGrantee>| async move {
+            self.metastore
+                .iter_role_grants(grantee.as_ref())
+                .collect()
+                .await
+                .context(UtilSlateDBSnafu)
+                .context(ex_error::MetastoreSnafu)
+        };
+        let rows = match show {
+            ShowGrants::Current => match &self.session.user {
+                Some(user) => role_grants(Some(MetastoreGrantee::User(user.clone())))
+                    .await?
+                    .iter()
+                    .map(GrantRow::from)
+                    .collect(),
+                None => Vec::new(),
+            },
+            ShowGrants::On(object) => {
+                let object = self.grant_object(object)?;
+                grants(None)
+                    .await?
+                    .iter()
+                    .filter(|grant| grant.object == object)
+                    .map(GrantRow::from)
+                    .collect()
+            }
+            ShowGrants::To(GrantTo::Role(role)) => {
+                let role = self.normalize_ident(role).value;
+                let mut rows: Vec<GrantRow> = grants(Some(role.clone()))
+                    .await?
+                    .iter()
+                    .map(GrantRow::from)
+                    .collect();
+                rows.extend(
+                    role_grants(Some(MetastoreGrantee::Role(role)))
+                        .await?
+                        .iter()
+                        .map(GrantRow::from),
+                );
+                rows
+            }
+            ShowGrants::To(GrantTo::User(user)) => {
+                let user = self.normalize_ident(user).value;
+                role_grants(Some(MetastoreGrantee::User(user)))
+                    .await?
+                    .iter()
+                    .map(GrantRow::from)
+                    .collect()
+            }
+            ShowGrants::Of(role) => {
+                let role = self.normalize_ident(role).value;
+                role_grants(None)
+                    .await?
+                    .iter()
+                    .filter(|grant| grant.role.eq_ignore_ascii_case(&role))
+                    .map(GrantRow::from)
+                    .collect()
+            }
+        };
+        Ok(rows)
+    }
+
+    fn show_roles_response(&self, roles: &[RwObject<MetastoreRole>]) -> Result<QueryResult> {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("created_on", DataType::Utf8, false),
+            Field::new("name", DataType::Utf8, false),
+            Field::new("is_current", DataType::Utf8, false),
+            Field::new("comment", DataType::Utf8, true),
+        ]));
+        let current_role = self.current_role();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from_iter_values(
+                    roles.iter().map(|role| role.created_at.to_string()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    roles.iter().map(|role| role.name.clone()),
+                )),
+                Arc::new(StringArray::from_iter_values(roles.iter().map(|role| {
+                    if role.name.eq_ignore_ascii_case(&current_role) {
+                        "Y"
+                    } else {
+                        "N"
+                    }
+                }))),
+                Arc::new(StringArray::from(
+                    roles
+                        .iter()
+                        .map(|role| role.comment.clone())
+                        .collect::<Vec<_>>(),
+                )),
+            ],
+        )
+        .context(ex_error::ArrowSnafu)?;
+        Ok(QueryResult::new(
+            vec![batch],
+            schema,
+            self.query_context.query_id,
+        ))
+    }
+
+    fn show_grants_response(&self, rows: &[GrantRow]) -> Result<QueryResult> {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("created_on", DataType::Utf8, false),
+            Field::new("privilege", DataType::Utf8, false),
+            Field::new("granted_on", DataType::Utf8, false),
+            Field::new("name", DataType::Utf8, false),
+            Field::new("granted_to", DataType::Utf8, false),
+            Field::new("grantee_name", DataType::Utf8, false),
+            Field::new("granted_by", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from_iter_values(
+                    rows.iter().map(|row| row.created_on.clone()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    rows.iter().map(|row| row.privilege.clone()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    rows.iter().map(|row| row.granted_on.clone()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    rows.iter().map(|row| row.name.clone()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    rows.iter().map(|row| row.granted_to.clone()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    rows.iter().map(|row| row.grantee_name.clone()),
+                )),
+                Arc::new(StringArray::from(
+                    rows.iter()
+                        .map(|row| row.granted_by.clone())
+                        .collect::<Vec<_>>(),
+                )),
+            ],
+        )
+        .context(ex_error::ArrowSnafu)?;
+        Ok(QueryResult::new(
+            vec![batch],
+            schema,
+            self.query_context.query_id,
+        ))
+    }
+
+    #[instrument(
+        name = "UserQuery::copy_into_snowflake_query",
+        level = "trace",
+        skip(self),
+        err
+    )]
+    pub async fn copy_into_snowflake_query(&self, statement: Statement) -> Result<QueryResult> {
+        let Statement::CopyIntoSnowflake {
+            kind,
+            into,
+            from_obj,
+            from_obj_alias,
+            from_query,
+            stage_params,
+            file_format,
+            copy_options,
+            validation_mode,
+            files: file_names,
+            pattern,
+            partition,
+            ..
+        } = statement
+        else {
+            return ex_error::OnlyCopyIntoStatementsSnafu.fail();
+        };
+        if kind == CopyIntoSnowflakeKind::Location {
+            return self
+                .copy_into_location_query(
+                    &into,
+                    from_obj,
+                    from_query,
+                    stage_params,
+                    &file_format,
+                    &copy_options,
+                    partition,
+                )
+                .await;
+        }
+        let Some(from_obj) = from_obj else {
+            return ex_error::FromObjectRequiredForCopyIntoStatementsSnafu.fail();
+        };
+
+        let insert_into = self.resolve_table_object_name(into.0)?;
+        // Validating the files needs the same privileges as loading them
+        self.check_table_privilege(Privilege::Insert, &insert_into)
+            .await?;
+
+        let on_error = get_kv_option(&copy_options, ON_ERROR_OPTION)
+            .map(OnError::parse)
+            .transpose()?
+            .unwrap_or(OnError::AbortStatement);
+        let validation_mode = validation_mode
+            .as_deref()
+            .map(ValidationMode::parse)
+            .transpose()?;
+        let force =
+            copy_into::parse_bool_option(FORCE_OPTION, get_kv_option(&copy_options, FORCE_OPTION))?;
+        let purge =
+            copy_into::parse_bool_option(PURGE_OPTION, get_kv_option(&copy_options, PURGE_OPTION))?;
+
+        let table_ident: MetastoreTableIdent = insert_into.clone().into();
+        let insert_reference: datafusion_common::TableReference = (&insert_into).into();
+
+        let into_provider = self
+            .session
+            .ctx
+            .table_provider(insert_reference.clone())
+            .await
             .context(ex_error::DataFusionSnafu)?;
 
-            let builder = if let Some(alias) = from_obj_alias {
-                builder
-                    .alias(alias.to_string())
-                    .context(ex_error::DataFusionSnafu)?
-            } else {
-                builder
+        let (url, object_store) = self.copy_location(&from_obj, stage_params).await?;
+
+        self.session
+            .ctx
+            .register_object_store(url.object_store().as_ref(), object_store.clone());
+
+        let file_format = self.resolve_file_format(&file_format).await?;
+        let files = copy_into::filter_files(
+            copy_into::list_files(&object_store, &url).await?,
+            &url,
+            file_names.as_deref(),
+            pattern.as_deref(),
+        )?;
+        let validate = validation_mode.is_some();
+        // Files already loaded into the table are left out, unless loading them again is forced
+        let files = if validate || force {
+            files
+        } else {
+            self.unloaded_files(&table_ident, &url, files).await?
+        };
+        if files.is_empty() {
+            return self.copy_into_response(copy_into::no_files_batch()?);
+        }
+
+        let load = Arc::new(LoadResults::default());
+        let target = into_provider.schema();
+        let source: Arc<dyn FileRowsSource> =
+            if let Some(options) = CsvLoadOptions::from_spec(&file_format)? {
+                let converter = CsvConverter::try_new(&target, &options, &self.session.ctx)?;
+                Arc::new(CsvRowsSource::new(object_store.clone(), options, converter))
+            } else {
+                Arc::new(ListingRowsSource {
+                    state: self.session.ctx.state(),
+                    file_format,
+                    target: target.clone(),
+                    url: url.clone(),
+                })
+            };
+        let stream: Arc<dyn PartitionStream> = Arc::new(LoadStream::new(
+            source,
+            target.clone(),
+            files.clone(),
+            on_error,
+            validate,
+            load.clone(),
+        ));
+        let schema = stream.schema().clone();
+        // Files are read as the insert consumes their rows
+        let table =
+            StreamingTable::try_new(schema, vec![stream]).context(ex_error::DataFusionSnafu)?;
+        let builder =
+            LogicalPlanBuilder::scan(LOAD_SCAN_NAME, provider_as_source(Arc::new(table)), None)
+                .context(ex_error::DataFusionSnafu)?;
+        let builder = if let Some(alias) = from_obj_alias {
+            builder
+                .alias(alias.to_string())
+                .context(ex_error::DataFusionSnafu)?
+        } else {
+            builder
+        };
+        let input = builder.build().context(ex_error::DataFusionSnafu)?;
+
+        if validate {
+            // Reading the input checks the files, nothing is inserted
+            self.execute_logical_plan(input).await?;
+            return self.copy_into_response(copy_into::validation_errors_batch(&load.take())?);
+        }
+
+        let input = cast_input_to_target_schema(Arc::new(input), &target)?;
+        let plan = LogicalPlanBuilder::insert_into(
+            input,
+            insert_reference,
+            Arc::new(DefaultTableSource::new(into_provider)),
+            InsertOp::Append,
+        )
+        .context(ex_error::DataFusionSnafu)?
+        .build()
+        .context(ex_error::DataFusionSnafu)?;
+        if let Err(error) = self.execute_logical_plan(plan).await {
+            return Err(load.take_abort().unwrap_or(error));
+        }
+        let results = load.take();
+
+        let loads = files
+            .iter()
+            .zip(&results)
+            .map(|(file, result)| copy_into::load_history(table_ident.clone(), &url, file, result))
+            .collect();
+        self.metastore
+            .put_load_history(loads)
+            .await
+            .context(ex_error::MetastoreSnafu)?;
+        if purge {
+            purge_files(&object_store, &files, &results).await;
+        }
+
+        self.copy_into_response(copy_into::load_results_batch(&results)?)
+    }
+
+    /// Leaves out the files the load history of `table` records as already loaded
+    async fn unloaded_files(
+        &self,
+        table: &MetastoreTableIdent,
+        url: &ListingTableUrl,
+        files: Vec<ObjectMeta>,
+    ) -> Result<Vec<ObjectMeta>> {
+        // Every listed file is below the location, its history is read at once
+        let history = self
+            .metastore
+            .list_load_history(table, Some(&copy_into::location_prefix(url)))
+            .await
+            .context(ex_error::MetastoreSnafu)?
+            .into_iter()
+            .map(|load| (load.file.clone(), load))
+            .collect::<HashMap<_, _>>();
+        Ok(files
+            .into_iter()
+            .filter(|file| {
+                let load = history.get(&copy_into::file_name(url, file));
+                !copy_into::already_loaded(load.map(|load| &load.data), file)
+            })
+            .collect())
+    }
+
+    /// `COPY INTO <location>`: unloads the results of a query, or a whole table, to files
+    #[allow(clippy::too_many_arguments)]
+    async fn copy_into_location_query(
+        &self,
+        into: &ObjectName,
+        from_obj: Option<ObjectName>,
+        from_query: Option<Box<Query>>,
+        stage_params: StageParamsObject,
+        file_format: &KeyValueOptions,
+        copy_options: &KeyValueOptions,
+        partition: Option<Box<Expr>>,
+    ) -> Result<QueryResult> {
+        let plan = match (from_query, from_obj) {
+            (Some(query), _) => {
+                let mut statement = DFStatement::Statement(Box::new(Statement::Query(query)));
+                self.update_statement_references(&mut statement)?;
+                let plan = self.statement_to_plan(&statement).await?;
+                self.rewrite_session_references(&plan)?
+            }
+            (None, Some(table)) => {
+                let table = self.resolve_table_object_name(table.0)?;
+                let reference: TableReference = (&table).into();
+                let provider = self
+                    .session
+                    .ctx
+                    .table_provider(reference.clone())
+                    .await
+                    .context(ex_error::DataFusionSnafu)?;
+                LogicalPlanBuilder::scan(reference, provider_as_source(provider), None)
+                    .context(ex_error::DataFusionSnafu)?
+                    .build()
+                    .context(ex_error::DataFusionSnafu)?
+            }
+            (None, None) => return ex_error::FromObjectRequiredForCopyIntoStatementsSnafu.fail(),
+        };
+
+        let state = self.session.ctx.state();
+        let partitioned = partition.is_some();
+        let plan = if let Some(partition) = partition {
+            let context_provider = SessionContextProvider {
+                state: &state,
+                tables: HashMap::new(),
             };
+            let sql_planner = ExtendedSqlToRel::new(&context_provider, state.get_parser_options());
+            let partition = sql_planner
+                .as_ref()
+                .sql_to_expr(
+                    *partition,
+                    plan.schema(),
+                    &mut datafusion::sql::planner::PlannerContext::new(),
+                )
+                .context(ex_error::DataFusionSnafu)?;
+            DataFrame::new(state.clone(), plan)
+                .with_column(unload::PARTITION_COLUMN, partition)
+                .context(ex_error::DataFusionSnafu)?
+                .into_unoptimized_plan()
+        } else {
+            plan
+        };
+        self.check_plan_privileges(&plan).await?;
 
-            let input = builder.build().context(ex_error::DataFusionSnafu)?;
+        let (url, object_store) = self.copy_location(into, stage_params).await?;
+        let file_format = self.resolve_file_format(file_format).await?;
+        let options = UnloadOptions::new(&file_format, copy_options)?;
+        let detailed_output = options.detailed_output;
+        let target = Arc::new(UnloadTarget::new(object_store, &url, options, partitioned));
+        target.check_existing_files().await?;
 
-            let input = if input.schema().as_arrow() == &*into_provider.schema() {
-                input
-            } else {
-                cast_input_to_target_schema(Arc::new(input), &into_provider.schema())?
-            };
+        let session = self.session.clone();
+        let files = self
+            .session
+            .executor
+            .spawn(async move {
+                let df = session
+                    .ctx
+                    .execute_logical_plan(plan)
+                    .await
+                    .context(ex_error::DataFusionSnafu)?;
+                // Output partitions are written in parallel, each to its own files
+                let streams = if target.options.single {
+                    vec![
+                        df.execute_stream()
+                            .await
+                            .context(ex_error::DataFusionSnafu)?,
+                    ]
+                } else {
+                    df.execute_stream_partitioned()
+                        .await
+                        .context(ex_error::DataFusionSnafu)?
+                };
+                let mut tasks = JoinSet::new();
+                for (task, stream) in streams.into_iter().enumerate() {
+                    tasks.spawn(unload::unload_stream(stream, target.clone(), task));
+                }
+                let mut files = Vec::new();
+                while let Some(unloaded) = tasks.join_next().await {
+                    files.extend(unloaded.context(ex_error::JoinHandleSnafu)??);
+                }
+                files.sort_by(|a: &UnloadedFile, b| a.name.cmp(&b.name));
+                Ok::<Vec<UnloadedFile>, Error>(files)
+            })
+            .await
+            .context(ex_error::JobSnafu)??;
 
-            let plan = LogicalPlanBuilder::insert_into(
-                input,
-                insert_reference,
-                Arc::new(DefaultTableSource::new(into_provider)),
-                InsertOp::Append,
-            )
-            .context(ex_error::DataFusionSnafu)?
-            .build()
-            .context(ex_error::DataFusionSnafu)?;
+        self.copy_into_response(unload::unload_results_batch(&files, detailed_output)?)
+    }
 
-            self.execute_logical_plan(plan).await
-        } else {
-            ex_error::StagesNotSupportedSnafu.fail()
-        }
+    fn copy_into_response(&self, batch: RecordBatch) -> Result<QueryResult> {
+        Ok(QueryResult::new(
+            vec![batch.clone()],
+            batch.schema(),
+            self.query_context.query_id,
+        ))
     }
 
     #[allow(clippy::too_many_lines)]
@@ -1528,6 +3444,16 @@ impl UserQuery {
         };
 
         let target_ident = self.resolve_table_object_name(target_ident.0)?;
+        let resolved_target = self.resolve_table_ref(&target_ident);
+        for clause in &clauses {
+            let privilege = match clause.action {
+                MergeAction::Insert { .. } => Privilege::Insert,
+                MergeAction::Update { .. } => Privilege::Update,
+                MergeAction::Delete { .. } => Privilege::Delete,
+            };
+            self.check_table_privilege(privilege, &resolved_target)
+                .await?;
+        }
 
         let target_table = self
             .get_iceberg_table_provider(
@@ -1625,6 +3551,8 @@ impl UserQuery {
         external_volume: Option<String>,
     ) -> Result<QueryResult> {
         let catalog_name = object_name_to_string(&db_name);
+        self.require_admin(ExistingObjectType::Database, &catalog_name)
+            .await?;
         if external_volume.is_none() {
             return ex_error::ExternalVolumeRequiredForCreateDatabaseSnafu { name: catalog_name }
                 .fail();
@@ -1642,6 +3570,8 @@ impl UserQuery {
         }
         self.create_catalog(&catalog_name, &external_volume.unwrap_or_default())
             .await?;
+        self.grant_ownership(GrantObject::Database(catalog_name))
+            .await?;
         self.created_entity_response()
     }
 
@@ -1670,6 +3600,8 @@ impl UserQuery {
         if_not_exists: bool,
     ) -> Result<QueryResult> {
         let ident = object_name_to_string(&name);
+        self.require_admin(ExistingObjectType::Volume, &ident)
+            .await?;
 
         if let Ok(Some(_)) = self.metastore.get_volume(&ident).await {
             if if_not_exists {
@@ -1756,13 +3688,21 @@ impl UserQuery {
     #[instrument(name = "UserQuery::create_view", level = "trace", skip(self), err)]
     pub async fn create_view(&self, statement: Statement) -> Result<QueryResult> {
         let mut plan = self.sql_statement_to_plan(statement).await?;
-        match &mut plan {
+        let view = match &mut plan {
             LogicalPlan::Ddl(DdlStatement::CreateView(cv)) => {
                 cv.temporary = false;
+                self.resolve_table_ref(cv.name.clone())
             }
             _ => return ex_error::OnlyCreateViewStatementsSnafu.fail(),
-        }
-        self.execute_logical_plan(plan).await
+        };
+        let result = self.execute_logical_plan(plan).await?;
+        self.grant_ownership(GrantObject::Table(TableIdent::new(
+            &view.catalog,
+            &view.schema,
+            &view.table,
+        )))
+        .await?;
+        Ok(result)
     }
 
     #[instrument(name = "UserQuery::create_schema", level = "trace", skip(self), err)]
@@ -1818,6 +3758,8 @@ impl UserQuery {
             .create_namespace(&namespace, None)
             .await
             .context(ex_error::IcebergSnafu)?;
+        self.grant_ownership(GrantObject::Schema(ident.clone()))
+            .await?;
         if let Some(mirror) = Self::get_iceberg_mirror(&catalog) {
             catalog
                 .register_schema(
@@ -2356,12 +4298,22 @@ impl UserQuery {
         };
         let planner =
             ExtendedSqlToRel::new(&ctx_provider, self.session.ctx.state().get_parser_options());
-        planner
+        let plan = planner
             .sql_statement_to_plan(statement)
-            .context(ex_error::DataFusionSnafu)
+            .context(ex_error::DataFusionSnafu)?;
+        self.check_plan_privileges(&plan).await?;
+        Ok(plan)
     }
 
     async fn execute_sql(&self, query: &str) -> Result<QueryResult> {
+        let plan = self
+            .session
+            .ctx
+            .state()
+            .create_logical_plan(query)
+            .await
+            .context(ex_error::DataFusionSnafu)?;
+        self.check_plan_privileges(&plan).await?;
         let session = self.session.clone();
         let query_id = self.query_context.query_id;
         let query = query.to_string();
@@ -2387,8 +4339,10 @@ impl UserQuery {
     }
 
     async fn execute_logical_plan(&self, plan: LogicalPlan) -> Result<QueryResult> {
+        self.check_plan_privileges(&plan).await?;
         let session = self.session.clone();
         let query_id = self.query_context.query_id;
+        let running_query = self.running_query();
 
         let span = tracing::debug_span!("UserQuery::execute_logical_plan");
 
@@ -2397,12 +4351,20 @@ impl UserQuery {
             .executor
             .spawn(async move {
                 let mut schema = plan.schema().as_arrow().clone();
-                let records = session
+                let df = session
                     .ctx
                     .execute_logical_plan(plan)
                     .await
-                    .context(ex_error::DataFusionSnafu)?
-                    .collect()
+                    .context(ex_error::DataFusionSnafu)?;
+                let task_ctx = df.task_ctx();
+                let physical_plan = df
+                    .create_physical_plan()
+                    .await
+                    .context(ex_error::DataFusionSnafu)?;
+                if let Some(running_query) = &running_query {
+                    running_query.set_physical_plan(physical_plan.clone());
+                }
+                let records = collect(physical_plan, Arc::new(task_ctx))
                     .instrument(span)
                     .await
                     .context(ex_error::DataFusionSnafu)?;
@@ -2416,13 +4378,124 @@ impl UserQuery {
         Ok(stream)
     }
 
+    /// Describes the optimized physical plan of the query in the requested format,
+    /// without executing it
+    #[instrument(name = "UserQuery::explain_query", level = "trace", skip(self), err)]
+    pub async fn explain_query(&self, statement: ExplainUsingStatement) -> Result<QueryResult> {
+        let ExplainUsingStatement { format, mut query } = statement;
+        let plan = self.query_plan(&mut query).await?;
+        self.check_plan_privileges(&plan).await?;
+
+        let tables = scanned_tables(&plan).await;
+        let physical_plan = self
+            .session
+            .ctx
+            .state()
+            .create_physical_plan(&plan)
+            .await
+            .context(ex_error::DataFusionSnafu)?;
+        let steps = explain_steps(physical_plan.as_ref(), &tables);
+        let batch = match format {
+            ExplainFormat::Tabular => tabular_batch(&steps),
+            ExplainFormat::Json => content_batch(json_plan(&steps)),
+            ExplainFormat::Text => content_batch(text_plan(&steps)),
+        }
+        .context(ex_error::ArrowSnafu)?;
+        let schema = batch.schema();
+        Ok(QueryResult::new(
+            vec![batch],
+            schema,
+            self.query_context.query_id,
+        ))
+    }
+
+    /// Executes the plan and returns the statistics of its operators,
+    /// in the same layout as `GET_QUERY_OPERATOR_STATS`. It replaces the
+    /// annotated plan of DataFusion's `EXPLAIN ANALYZE`, which is still
+    /// returned for `EXPLAIN ANALYZE VERBOSE`.
+    async fn explain_analyze(&self, plan: LogicalPlan) -> Result<QueryResult> {
+        self.check_plan_privileges(&plan).await?;
+        let session = self.session.clone();
+        let query_id = self.query_context.query_id;
+        let running_query = self.running_query();
+
+        let span = tracing::debug_span!("UserQuery::explain_analyze");
+
+        self.session
+            .executor
+            .spawn(async move {
+                let df = session
+                    .ctx
+                    .execute_logical_plan(plan)
+                    .await
+                    .context(ex_error::DataFusionSnafu)?;
+                let task_ctx = df.task_ctx();
+                let physical_plan = df
+                    .create_physical_plan()
+                    .await
+                    .context(ex_error::DataFusionSnafu)?;
+                if let Some(running_query) = &running_query {
+                    running_query.set_physical_plan(physical_plan.clone());
+                }
+                collect(physical_plan.clone(), Arc::new(task_ctx))
+                    .instrument(span)
+                    .await
+                    .context(ex_error::DataFusionSnafu)?;
+                let batch = operator_stats_batch(
+                    &query_id.to_string(),
+                    &operator_stats(physical_plan.as_ref()),
+                )
+                .context(ex_error::DataFusionSnafu)?;
+                let schema = batch.schema();
+                Ok::<QueryResult, Error>(QueryResult::new(vec![batch], schema, query_id))
+            })
+            .await
+            .context(ex_error::JobSnafu)?
+    }
+
+    async fn execute_logical_plan_stream(
+        &self,
+        plan: LogicalPlan,
+    ) -> Result<SendableRecordBatchStream> {
+        self.check_plan_privileges(&plan).await?;
+        let session = self.session.clone();
+        let running_query = self.running_query();
+        let stream = self
+            .session
+            .executor
+            .spawn(async move {
+                let df = session
+                    .ctx
+                    .execute_logical_plan(plan)
+                    .await
+                    .context(ex_error::DataFusionSnafu)?;
+                let task_ctx = df.task_ctx();
+                let physical_plan = df
+                    .create_physical_plan()
+                    .await
+                    .context(ex_error::DataFusionSnafu)?;
+                if let Some(running_query) = &running_query {
+                    running_query.set_physical_plan(physical_plan.clone());
+                }
+                execute_stream(physical_plan, Arc::new(task_ctx)).context(ex_error::DataFusionSnafu)
+            })
+            .await
+            .context(ex_error::JobSnafu)??;
+        Ok(self
+            .session
+            .executor
+            .run_cpu_sendable_record_batch_stream(stream))
+    }
+
     async fn execute_logical_plan_with_custom_rules(
         &self,
         plan: LogicalPlan,
         rules: Vec<Arc<dyn PhysicalOptimizerRule + Send + Sync>>,
     ) -> Result<QueryResult> {
+        self.check_plan_privileges(&plan).await?;
         let session = self.session.clone();
         let query_id = self.query_context.query_id;
+        let running_query = self.running_query();
         let stream = self
             .session
             .executor
@@ -2443,6 +4516,9 @@ impl UserQuery {
                         .optimize(physical_plan, &ConfigOptions::new())
                         .context(ex_error::DataFusionSnafu)?;
                 }
+                if let Some(running_query) = &running_query {
+                    running_query.set_physical_plan(physical_plan.clone());
+                }
                 let records = collect(physical_plan, Arc::new(task_ctx))
                     .await
                     .context(ex_error::DataFusionSnafu)?;
@@ -2456,6 +4532,13 @@ impl UserQuery {
         Ok(stream)
     }
 
+    /// The query in the running queries registry, it reports progress of the plans it executes
+    fn running_query(&self) -> Option<RunningQuery> {
+        self.running_queries
+            .get(RunningQueryId::ByQueryId(self.query_context.query_id))
+            .ok()
+    }
+
     #[instrument(
         name = "UserQuery::execute_with_custom_plan",
         level = "trace",
@@ -2463,8 +4546,150 @@ impl UserQuery {
         err
     )]
     pub async fn execute_with_custom_plan(&self, query: &str) -> Result<QueryResult> {
-        let mut plan = self.get_custom_logical_plan(query).await?;
-        let session_params_map: HashMap<String, ScalarValue> = self
+        let plan = self.get_custom_logical_plan(query).await?;
+        let plan = self.rewrite_session_references(&plan)?;
+        self.execute_logical_plan(plan).await
+    }
+
+    /// Executes the query like [`Self::execute_with_custom_plan`], but with `USE_CACHED_RESULT`
+    /// enabled reuses the result of a previous run of the query, if the tables it reads haven't
+    /// changed since then
+    #[instrument(
+        name = "UserQuery::execute_with_result_cache",
+        level = "trace",
+        skip(self),
+        err
+    )]
+    pub async fn execute_with_result_cache(&self, query: &mut Query) -> Result<QueryResult> {
+        let plan = self.query_plan(query).await?;
+        let Some((key, snapshots)) = self.result_cache_entry(query, &plan).await else {
+            return self.execute_logical_plan(plan).await;
+        };
+        if let Some(result) = self.cached_result(&key, &snapshots, &plan).await? {
+            return Ok(result);
+        }
+        let result = self.execute_logical_plan(plan).await?;
+        let rows = result.records.iter().map(RecordBatch::num_rows).sum();
+        let size_bytes = result
+            .records
+            .iter()
+            .map(RecordBatch::get_array_memory_size)
+            .sum();
+        let result_cache = &self.session.result_cache;
+        if result_cache.fits_history(rows, size_bytes) {
+            result_cache.insert(key, snapshots, self.query_context.query_id);
+        }
+        Ok(result)
+    }
+
+    /// Key of the query in the result cache and the snapshots of the tables it reads,
+    /// `None` if `USE_CACHED_RESULT` is disabled or the result of the query can't be cached
+    async fn result_cache_entry(
+        &self,
+        query: &Query,
+        plan: &LogicalPlan,
+    ) -> Option<(String, Vec<TableSnapshot>)> {
+        if !self.session.get_session_variable_bool(USE_CACHED_RESULT) {
+            return None;
+        }
+        let snapshots = table_snapshots(plan).await?;
+        Some((self.result_cache_key(&query.to_string()), snapshots))
+    }
+
+    /// Result of a previous run of the query, while the tables it reads are still at the
+    /// snapshots in the catalog
+    async fn cached_result(
+        &self,
+        key: &str,
+        snapshots: &[TableSnapshot],
+        plan: &LogicalPlan,
+    ) -> Result<Option<QueryResult>> {
+        let Some(query_id) = self.session.result_cache.get(key, snapshots) else {
+            return Ok(None);
+        };
+        if current_snapshots(plan).await.as_deref() != Some(snapshots) {
+            return Ok(None);
+        }
+        // Privileges are checked as if the query was executed
+        self.check_plan_privileges(plan).await?;
+        Ok(self
+            .history_result(query_id)
+            .await
+            .map(|result| result.with_query_id(self.query_context.query_id)))
+    }
+
+    /// Records the result of the stream in the result cache once it's read to the end
+    fn cache_stream_result(
+        &self,
+        mut stream: SendableRecordBatchStream,
+        key: String,
+        snapshots: Vec<TableSnapshot>,
+    ) -> SendableRecordBatchStream {
+        let result_cache = self.session.result_cache.clone();
+        let query_id = self.query_context.query_id;
+        let schema = stream.schema();
+        let batches = async_stream::stream! {
+            let mut rows = 0;
+            let mut size_bytes = 0;
+            let mut failed = false;
+            while let Some(batch) = stream.next().await {
+                match &batch {
+                    Ok(batch) => {
+                        rows += batch.num_rows();
+                        size_bytes += batch.get_array_memory_size();
+                    }
+                    Err(_) => failed = true,
+                }
+                yield batch;
+            }
+            if !failed && result_cache.fits_history(rows, size_bytes) {
+                result_cache.insert(key, snapshots, query_id);
+            }
+        };
+        Box::pin(RecordBatchStreamAdapter::new(schema, batches))
+    }
+
+    /// Query text, its bindings and everything of the session which may change its result
+    fn result_cache_key(&self, query: &str) -> String {
+        let mut params = self
+            .session
+            .session_params
+            .properties
+            .iter()
+            .filter(|entry| entry.key() != USE_CACHED_RESULT)
+            .map(|entry| format!("{}={}", entry.key(), entry.value().value))
+            .collect::<Vec<_>>();
+        params.sort();
+        [
+            query.to_string(),
+            self.current_database(),
+            self.current_schema(),
+            self.session.user.clone().unwrap_or_default(),
+            params.join(","),
+            // Types and values of the bindings, the serialization can't fail
+            serde_json::to_string(&self.query_context.bindings).unwrap_or_default(),
+        ]
+        .join("\n")
+    }
+
+    /// Result of a successful query, as stored in the history, if it's stored in full
+    async fn history_result(&self, query_id: QueryRecordId) -> Option<QueryResult> {
+        let history_store = &self.session.history_store;
+        let record = history_store.get_query(query_id).await.ok()?;
+        if record.status != QueryStatus::Successful {
+            return None;
+        }
+        let result_set = history_store.get_query_result(query_id).await.ok()?;
+        // History keeps a limited number of rows of a result
+        if i64::try_from(result_set.rows.len()).ok()? != record.result_count {
+            return None;
+        }
+        QueryResult::try_from(result_set).ok()
+    }
+
+    /// Resolves session context functions, session variables and query bindings in a plan
+    fn rewrite_session_references(&self, plan: &LogicalPlan) -> Result<LogicalPlan> {
+        let mut session_params_map: HashMap<String, ScalarValue> = self
             .session
             .session_params
             .properties
@@ -2475,11 +4700,12 @@ impl UserQuery {
                 prop.to_scalar_value().map(|scalar| (key, scalar))
             })
             .collect();
+        // Values bound by the client to the `$N` placeholders
+        session_params_map.extend(bindings::param_values(&self.query_context.bindings)?);
         let session_params = ParamValues::Map(session_params_map);
 
-        plan = self
-            .session_context_expr_rewriter()
-            .rewrite_plan(&plan)
+        self.session_context_expr_rewriter()
+            .rewrite_plan(plan)
             .context(ex_error::DataFusionSnafu)?
             // Inject session-scoped parameter values into the logical plan.
             // These parameters can be referenced in SQL via $param_name or $1-style placeholders,
@@ -2492,8 +4718,7 @@ impl UserQuery {
             //
             // The call to `with_param_values` replaces the parameter references with actual values.
             .with_param_values(session_params)
-            .context(ex_error::DataFusionSnafu)?;
-        self.execute_logical_plan(plan).await
+            .context(ex_error::DataFusionSnafu)
     }
 
     async fn execute_scalar_query(&self, query_str: &str) -> Result<ScalarValue> {
@@ -3037,6 +5262,44 @@ impl UserQuery {
         Ok(target_provider)
     }
 
+    /// Files location of `COPY INTO`: an external location (`'s3://bucket/path/'`), or a
+    /// path within a named internal stage (`@stage/path/`).
+    /// Local files of the server and the credentials of a volume, used as the storage
+    /// integration of an external location, are only accessible to admins.
+    async fn copy_location(
+        &self,
+        location: &ObjectName,
+        stage_params: StageParamsObject,
+    ) -> Result<(ListingTableUrl, Arc<dyn ObjectStore>)> {
+        if let Some(location) = get_external_location(location) {
+            let url = ListingTableUrl::parse(&location.value).context(ex_error::DataFusionSnafu)?;
+            if url.scheme() == "file" {
+                self.require_admin(ExistingObjectType::Location, &location.value)
+                    .await?;
+            }
+            if let Some(volume) = &stage_params.storage_integration {
+                self.require_admin(ExistingObjectType::Volume, volume)
+                    .await?;
+            }
+            let object_store = self
+                .get_object_store_from_stage_params(stage_params, &url)
+                .await?;
+            return Ok((url, object_store));
+        }
+        let Some(location) = get_stage_location(location) else {
+            return ex_error::StagesNotSupportedSnafu.fail();
+        };
+        let location = parse_stage_location(&location).context(ex_error::SqlParserSnafu)?;
+        let stage = self.resolve_stage(&location.name).await?;
+        let url = self.stage_url(&stage, &location.path).await?;
+        let object_store = self
+            .metastore
+            .stage_object_store(&stage)
+            .await
+            .context(ex_error::MetastoreSnafu)?;
+        Ok((url, object_store))
+    }
+
     async fn get_object_store_from_stage_params(
         &self,
         stage_params: StageParamsObject,
@@ -3108,31 +5371,36 @@ impl UserQuery {
         }
     }
 
-    async fn build_listing_table_config(
-        &self,
-        file_format: KeyValueOptions,
-        into_provider: &Arc<dyn TableProvider>,
-        url: ListingTableUrl,
-    ) -> Result<ListingTableConfig> {
-        let config = ListingTableConfig::new(url.clone());
-        let config = if let Some((format, infer_schema)) = create_file_format(&file_format)? {
-            let options = ListingOptions::new(format);
-            let schema = if infer_schema {
-                options
-                    .infer_schema(&self.session.ctx.state(), &url)
-                    .await
-                    .context(ex_error::DataFusionSnafu)?
-            } else {
-                into_provider.schema()
-            };
-            config.with_listing_options(options).with_schema(schema)
-        } else {
-            config
+    /// Resolves an inline `FILE_FORMAT = (...)` clause. When it references a named
+    /// file format with `FORMAT_NAME`, the stored format is loaded from the metastore
+    /// and needs `USAGE` on it.
+    async fn resolve_file_format(&self, file_format: &KeyValueOptions) -> Result<FileFormatSpec> {
+        let spec = inline_file_format(file_format)?;
+        let Some(name) = format_name(&spec) else {
+            return Ok(spec);
         };
-        Ok(config)
+        let ident: MetastoreFileFormatIdent = self.resolve_table_object_name(name.0)?.into();
+        let named = self
+            .metastore
+            .get_file_format(&ident)
+            .await
+            .context(ex_error::MetastoreSnafu)?
+            .context(ex_error::FileFormatNotFoundSnafu {
+                name: ident.to_string(),
+            })?;
+        self.access_control()
+            .await?
+            .check_file_format(Privilege::Usage, &ident)?;
+        Ok(FileFormatSpec::from(&named.data))
     }
 }
 
+/// Stream of the batches of a result
+fn result_stream(result: QueryResult) -> SendableRecordBatchStream {
+    let batches = futures::stream::iter(result.records.into_iter().map(Ok));
+    Box::pin(RecordBatchStreamAdapter::new(result.schema, batches))
+}
+
 /// Builds a target schema with metadata columns added.
 ///
 /// This function takes a base schema and adds data file path and manifest file path columns
@@ -3547,6 +5815,115 @@ fn apply_show_filters(sql: String, filters: &[String]) -> String {
     }
 }
 
+/// `PURGE = TRUE`: removes the loaded files from the location. As in Snowflake, a
+/// file that cannot be removed does not fail the load.
+async fn purge_files(
+    object_store: &Arc<dyn ObjectStore>,
+    files: &[ObjectMeta],
+    results: &[FileLoadResult],
+) {
+    for (file, result) in files.iter().zip(results) {
+        if result.status() == LoadStatus::LoadFailed {
+            continue;
+        }
+        if let Err(error) = object_store.delete(&file.location).await {
+            tracing::warn!("Failed to purge loaded file {}: {error}", file.location);
+        }
+    }
+}
+
+/// Opens the files of a load through DataFusion's listing tables. Only whole files
+/// fail here, the only per-row check is the nullability one of the load stream.
+#[derive(Debug)]
+struct ListingRowsSource {
+    state: SessionState,
+    file_format: FileFormatSpec,
+    target: SchemaRef,
+    url: ListingTableUrl,
+}
+
+#[async_trait]
+impl FileRowsSource for ListingRowsSource {
+    async fn open(&self, file: &ObjectMeta, ctx: Arc<TaskContext>) -> Result<Box<dyn FileRows>> {
+        let url = copy_into::file_url(&self.url, file)?;
+        let config =
+            build_listing_table_config(&self.state, &self.file_format, &self.target, url).await?;
+        let table_provider = ListingTable::try_new(config).context(ex_error::DataFusionSnafu)?;
+        let input = LogicalPlanBuilder::scan(
+            "external_location",
+            provider_as_source(Arc::new(table_provider)),
+            None,
+        )
+        .context(ex_error::DataFusionSnafu)?
+        .build()
+        .context(ex_error::DataFusionSnafu)?;
+        let plan = self
+            .state
+            .create_physical_plan(&cast_input_to_target_schema(Arc::new(input), &self.target)?)
+            .await
+            .context(ex_error::DataFusionSnafu)?;
+        let stream = execute_stream(plan, ctx).context(ex_error::DataFusionSnafu)?;
+        Ok(Box::new(ListingFileRows {
+            stream,
+            schema: copy_into::load_stream_schema(&self.target),
+            rows_parsed: 0,
+        }))
+    }
+}
+
+struct ListingFileRows {
+    stream: SendableRecordBatchStream,
+    /// Columns of the load stream, the target columns made nullable
+    schema: SchemaRef,
+    rows_parsed: usize,
+}
+
+#[async_trait]
+impl FileRows for ListingFileRows {
+    async fn next_rows(&mut self) -> Result<Option<(RecordBatch, Vec<RowError>)>> {
+        let Some(batch) = self
+            .stream
+            .try_next()
+            .await
+            .context(ex_error::DataFusionSnafu)?
+        else {
+            return Ok(None);
+        };
+        self.rows_parsed += batch.num_rows();
+        let batch = RecordBatch::try_new(self.schema.clone(), batch.columns().to_vec())
+            .context(ex_error::ArrowSnafu)?;
+        Ok(Some((batch, vec![])))
+    }
+
+    fn rows_parsed(&self) -> usize {
+        self.rows_parsed
+    }
+}
+
+async fn build_listing_table_config(
+    state: &SessionState,
+    file_format: &FileFormatSpec,
+    target: &SchemaRef,
+    url: ListingTableUrl,
+) -> Result<ListingTableConfig> {
+    let config = ListingTableConfig::new(url.clone());
+    let config = if let Some((format, infer_schema)) = create_file_format(file_format)? {
+        let options = ListingOptions::new(format);
+        let schema = if infer_schema {
+            options
+                .infer_schema(state, &url)
+                .await
+                .context(ex_error::DataFusionSnafu)?
+        } else {
+            target.clone()
+        };
+        config.with_listing_options(options).with_schema(schema)
+    } else {
+        config
+    };
+    Ok(config)
+}
+
 pub fn cast_input_to_target_schema(
     input: Arc<LogicalPlan>,
     target_schema: &SchemaRef,
@@ -3609,6 +5986,18 @@ fn get_field<'a>(
     Ok((reference, input_field))
 }
 
+/// Stage reference of a `COPY INTO` location, like `@db.sch.stage/path/`. The parser
+/// splits it at the dots, it's joined back to be parsed as a whole.
+fn get_stage_location(location: &ObjectName) -> Option<String> {
+    let location = location
+        .0
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(".");
+    location.starts_with('@').then_some(location)
+}
+
 /// Checks if the `ObjectName` indicates an external location for a Snowflake COPY INTO statement.
 ///
 /// This function validates that the object name represents a valid external location by checking:
@@ -3682,21 +6071,23 @@ pub fn get_kv_option<'a>(options: &'a KeyValueOptions, key: &str) -> Option<&'a
         .map(|opt| opt.value.as_str())
 }
 
-fn create_file_format(
-    file_format: &KeyValueOptions,
-) -> Result<Option<(Arc<dyn FileFormat>, bool)>> {
-    if let Some(format_type) = get_kv_option(file_format, "type") {
-        if format_type.eq_ignore_ascii_case("parquet") {
-            Ok(Some((Arc::new(ParquetFormat::default()), true)))
-        } else if format_type.eq_ignore_ascii_case("csv") {
-            let infer_schema = get_kv_option(file_format, "parse_header")
+fn create_file_format(file_format: &FileFormatSpec) -> Result<Option<(Arc<dyn FileFormat>, bool)>> {
+    match file_format.format_type {
+        None => Ok(None),
+        Some(FileFormatType::Parquet) => Ok(Some((Arc::new(ParquetFormat::default()), true))),
+        Some(FileFormatType::Csv) => {
+            let infer_schema = file_format
+                .get("parse_header")
                 .is_some_and(|x| x.to_lowercase() == "true");
-            let has_header =
-                get_kv_option(file_format, "skip_header").is_some_and(|x| x.to_lowercase() == "1");
+            // Other CSV loads go through `CsvLoadOptions`, here the header holds column names
+            let has_header = infer_schema
+                || file_format
+                    .get("skip_header")
+                    .is_some_and(|x| x.to_lowercase() == "1");
 
             let mut csv_format = CsvFormat::default().with_has_header(has_header);
 
-            if let Some(compression) = get_kv_option(file_format, "compression") {
+            if let Some(compression) = file_format.get("compression") {
                 csv_format = csv_format.with_file_compression_type(
                     FileCompressionType::from_str(compression)
                         .context(ex_error::DataFusionSnafu)?,
@@ -3704,8 +6095,7 @@ fn create_file_format(
             }
 
             // Handle field_delimiter parameter
-            let csv_format = if let Some(delimiter) = get_kv_option(file_format, "field_delimiter")
-            {
+            let csv_format = if let Some(delimiter) = file_format.get("field_delimiter") {
                 if delimiter.len() == 1 {
                     csv_format.with_delimiter(delimiter.as_bytes()[0])
                 } else {
@@ -3716,18 +6106,15 @@ fn create_file_format(
             };
 
             Ok(Some((Arc::new(csv_format), infer_schema)))
-        } else if format_type.eq_ignore_ascii_case("json") {
-            Ok(Some((Arc::new(JsonFormat::default()), true)))
-        } else {
-            ex_error::UnsupportedFileFormatSnafu {
-                format: format_type,
-            }
-            .fail()
         }
-    } else {
-        Ok(None)
+        Some(FileFormatType::Json) => Ok(Some((Arc::new(JsonFormat::default()), true))),
+        Some(format_type @ FileFormatType::Avro) => ex_error::UnsupportedFileFormatSnafu {
+            format: format_type.to_string(),
+        }
+        .fail(),
     }
 }
+
 fn normalize_resolved_ref(table_ref: &ResolvedTableReference) -> ResolvedTableReference {
     ResolvedTableReference {
         catalog: Arc::from(table_ref.catalog.to_ascii_lowercase()),
@@ -0,0 +1,280 @@
+//! Result cache of queries, enabled by the `USE_CACHED_RESULT` session parameter.
+//!
+//! A query reuses the result of a previous query, as stored in the history, when both have
+//! the same text and session context and every table they read is an Iceberg table which is
+//! still at the same snapshot. Any commit to one of the tables changes its snapshot id, so
+//! the cached result is not used anymore and is replaced by the result of the next run.
+//! Snapshots are reloaded from the catalog when a cached result is validated, since the
+//! tables of a session may be behind the commits of other engines.
+//!
+//! Only results which the history keeps in full are cached, and the least recently used
+//! entries are evicted once the cache takes more than its capacity in bytes.
+use core_history::{QUERY_HISTORY_HARD_LIMIT_BYTES, QueryRecordId};
+use datafusion::catalog::TableProvider;
+use datafusion::common::tree_node::{TreeNode, TreeNodeRecursion};
+use datafusion::datasource::source_as_provider;
+use datafusion::logical_expr::{Expr, LogicalPlan, Volatility};
+use datafusion_iceberg::DataFusionTable;
+use df_catalog::table::CachingTable;
+use iceberg_rust::catalog::tabular::Tabular;
+use iceberg_rust::table::Table;
+use lru::LruCache;
+use std::mem::size_of;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Session parameter enabling the result cache
+pub const USE_CACHED_RESULT: &str = "use_cached_result";
+
+/// Snapshot of an Iceberg table read by a query, `None` for a table without snapshots
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableSnapshot {
+    pub table: String,
+    pub snapshot_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CachedResult {
+    snapshots: Vec<TableSnapshot>,
+    query_id: QueryRecordId,
+    size_bytes: usize,
+}
+
+#[derive(Debug)]
+struct Entries {
+    lru: LruCache<String, CachedResult>,
+    size_bytes: usize,
+}
+
+/// Ids of queries whose results can be reused, shared by all sessions
+#[derive(Debug)]
+pub struct ResultCache {
+    entries: Mutex<Entries>,
+    capacity_bytes: usize,
+    rows_limit: usize,
+}
+
+impl ResultCache {
+    /// Cache taking up to `capacity_bytes`, of the results of at most `rows_limit` rows
+    /// the history keeps in full
+    #[must_use]
+    pub fn new(capacity_bytes: usize, rows_limit: usize) -> Self {
+        Self {
+            entries: Mutex::new(Entries {
+                lru: LruCache::unbounded(),
+                size_bytes: 0,
+            }),
+            capacity_bytes,
+            rows_limit,
+        }
+    }
+
+    /// Query which has produced the result for the key, while its tables are at the snapshots
+    #[must_use]
+    pub fn get(&self, key: &str, snapshots: &[TableSnapshot]) -> Option<QueryRecordId> {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries
+            .lru
+            .get(key)
+            .filter(|cached| cached.snapshots == snapshots)
+            .map(|cached| cached.query_id)
+    }
+
+    /// Whether a result of `rows` rows taking `size_bytes` is saved to the history in full
+    #[must_use]
+    pub const fn fits_history(&self, rows: usize, size_bytes: usize) -> bool {
+        rows <= self.rows_limit && size_bytes <= QUERY_HISTORY_HARD_LIMIT_BYTES
+    }
+
+    /// Records the query result for the key, replacing the result of older snapshots,
+    /// and evicts the least recently used results over the capacity
+    pub fn insert(&self, key: String, snapshots: Vec<TableSnapshot>, query_id: QueryRecordId) {
+        let size_bytes = key.len()
+            + snapshots
+                .iter()
+                .map(|snapshot| snapshot.table.len() + size_of::<TableSnapshot>())
+                .sum::<usize>()
+            + size_of::<CachedResult>();
+        if size_bytes > self.capacity_bytes {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let cached = CachedResult {
+            snapshots,
+            query_id,
+            size_bytes,
+        };
+        if let Some(replaced) = entries.lru.put(key, cached) {
+            entries.size_bytes -= replaced.size_bytes;
+        }
+        entries.size_bytes += size_bytes;
+        while entries.size_bytes > self.capacity_bytes {
+            let Some((_, evicted)) = entries.lru.pop_lru() else {
+                break;
+            };
+            entries.size_bytes -= evicted.size_bytes;
+        }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .lru
+            .len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bytes taken by the cached entries
+    #[must_use]
+    pub fn size_bytes(&self) -> usize {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .size_bytes
+    }
+}
+
+/// Snapshots of the tables the plan reads, `None` if the result of the plan can't be
+/// cached: it reads other tables than Iceberg ones, or calls non-deterministic functions
+pub async fn table_snapshots(plan: &LogicalPlan) -> Option<Vec<TableSnapshot>> {
+    let mut snapshots = Vec::new();
+    for (table, provider) in scanned_tables(plan)? {
+        let iceberg_table = iceberg_table(provider).await?;
+        snapshots.push(TableSnapshot {
+            table,
+            snapshot_id: snapshot_id(&iceberg_table)?,
+        });
+    }
+    Some(snapshots)
+}
+
+/// Current snapshots of the tables the plan reads, as loaded from their catalogs
+pub async fn current_snapshots(plan: &LogicalPlan) -> Option<Vec<TableSnapshot>> {
+    let mut snapshots = Vec::new();
+    for (table, provider) in scanned_tables(plan)? {
+        let iceberg_table = iceberg_table(provider).await?;
+        let Tabular::Table(iceberg_table) = iceberg_table
+            .catalog()
+            .load_tabular(iceberg_table.identifier())
+            .await
+            .ok()?
+        else {
+            return None;
+        };
+        snapshots.push(TableSnapshot {
+            table,
+            snapshot_id: snapshot_id(&iceberg_table)?,
+        });
+    }
+    Some(snapshots)
+}
+
+/// Tables scanned by the plan, `None` if the plan calls non-deterministic functions
+fn scanned_tables(plan: &LogicalPlan) -> Option<Vec<(String, Arc<dyn TableProvider>)>> {
+    let mut providers = Vec::new();
+    let mut cacheable = true;
+    let _ = plan.apply_with_subqueries(|node| {
+        if let LogicalPlan::TableScan(scan) = node {
+            match source_as_provider(&scan.source) {
+                Ok(provider) => providers.push((scan.table_name.to_string(), provider)),
+                Err(_) => cacheable = false,
+            }
+        }
+        if node.expressions().iter().any(is_volatile) {
+            cacheable = false;
+        }
+        Ok(if cacheable {
+            TreeNodeRecursion::Continue
+        } else {
+            TreeNodeRecursion::Stop
+        })
+    });
+    cacheable.then_some(providers)
+}
+
+/// Iceberg table of the provider, as read by the session
+async fn iceberg_table(provider: Arc<dyn TableProvider>) -> Option<Table> {
+    let cached_table: Option<Arc<dyn TableProvider>> = provider
+        .as_any()
+        .downcast_ref::<CachingTable>()
+        .map(|caching_table| caching_table.table.clone());
+    let provider = cached_table.unwrap_or(provider);
+    let iceberg_table = provider.as_any().downcast_ref::<DataFusionTable>()?;
+    let lock = iceberg_table.tabular.read().await;
+    let Tabular::Table(iceberg_table) = &*lock else {
+        return None;
+    };
+    Some(iceberg_table.clone())
+}
+
+/// Current snapshot of the table, `Some(None)` for a table without snapshots
+fn snapshot_id(table: &Table) -> Option<Option<i64>> {
+    table
+        .metadata()
+        .current_snapshot(None)
+        .ok()
+        .map(|snapshot| snapshot.map(|snapshot| *snapshot.snapshot_id()))
+}
+
+/// Functions like `RANDOM()` or `CURRENT_TIMESTAMP()` give another result on every run
+fn is_volatile(expr: &Expr) -> bool {
+    expr.exists(|expr| {
+        Ok(matches!(
+            expr,
+            Expr::ScalarFunction(function)
+                if function.func.signature().volatility != Volatility::Immutable
+        ))
+    })
+    .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshots(snapshot_id: i64) -> Vec<TableSnapshot> {
+        vec![TableSnapshot {
+            table: "embucket.public.t".to_string(),
+            snapshot_id: Some(snapshot_id),
+        }]
+    }
+
+    #[test]
+    fn test_least_recently_used_results_evicted() {
+        let entry_bytes = "query1".len()
+            + "embucket.public.t".len()
+            + size_of::<TableSnapshot>()
+            + size_of::<CachedResult>();
+        let cache = ResultCache::new(2 * entry_bytes, 10);
+        cache.insert("query1".to_string(), snapshots(1), QueryRecordId(1));
+        cache.insert("query2".to_string(), snapshots(1), QueryRecordId(2));
+        assert_eq!(cache.get("query1", &snapshots(1)), Some(QueryRecordId(1)));
+        assert_eq!(cache.get("query1", &snapshots(2)), None);
+
+        cache.insert("query3".to_string(), snapshots(1), QueryRecordId(3));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.size_bytes(), 2 * entry_bytes);
+        assert_eq!(cache.get("query2", &snapshots(1)), None);
+        assert_eq!(cache.get("query1", &snapshots(1)), Some(QueryRecordId(1)));
+
+        // A newer result replaces the one of the older snapshot
+        cache.insert("query3".to_string(), snapshots(2), QueryRecordId(4));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.size_bytes(), 2 * entry_bytes);
+        assert_eq!(cache.get("query3", &snapshots(2)), Some(QueryRecordId(4)));
+    }
+
+    #[test]
+    fn test_results_over_history_limit() {
+        let cache = ResultCache::new(1024, 10);
+        assert!(cache.fits_history(10, 1024));
+        assert!(!cache.fits_history(11, 1024));
+        assert!(!cache.fits_history(10, QUERY_HISTORY_HARD_LIMIT_BYTES + 1));
+    }
+}
@@ -0,0 +1,456 @@
+//! Role and grant statements.
+//!
+//! `CREATE / DROP ROLE`, `GRANT`, `REVOKE`, `SHOW ROLES` and `SHOW GRANTS` are
+//! recognized here, before the regular parsing step, like user statements, and
+//! executed against the metastore by `UserQuery::role_query`.
+use crate::error::{self as ex_error, Result};
+use crate::user::parse_word;
+use core_metastore::{Grant, Grantee, Privilege, RoleGrant, RwObject};
+use datafusion::sql::sqlparser::ast::{Ident, ObjectName};
+use datafusion::sql::sqlparser::dialect::{Dialect, SnowflakeDialect, dialect_from_str};
+use datafusion::sql::sqlparser::keywords::Keyword;
+use datafusion::sql::sqlparser::parser::{Parser, ParserError};
+use datafusion::sql::sqlparser::tokenizer::Token;
+use snafu::ResultExt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RoleStatement {
+    CreateRole {
+        name: Ident,
+        or_replace: bool,
+        if_not_exists: bool,
+        comment: Option<String>,
+    },
+    DropRole {
+        name: Ident,
+        if_exists: bool,
+    },
+    ShowRoles {
+        like: Option<String>,
+    },
+    /// `GRANT` or `REVOKE` privileges on an object to or from a role
+    Privileges {
+        revoke: bool,
+        privileges: Vec<Privilege>,
+        object: GrantOn,
+        role: Ident,
+    },
+    /// `GRANT` or `REVOKE` a role to or from a user or another role
+    Role {
+        revoke: bool,
+        role: Ident,
+        grantee: GrantTo,
+    },
+    ShowGrants(ShowGrants),
+}
+
+/// An object privileges are granted on, as named in the statement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GrantOn {
+    Database(ObjectName),
+    Schema(ObjectName),
+    Table(ObjectName),
+    Stage(ObjectName),
+    FileFormat(ObjectName),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GrantTo {
+    Role(Ident),
+    User(Ident),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShowGrants {
+    /// Roles granted to the session user
+    Current,
+    On(GrantOn),
+    To(GrantTo),
+    /// Grants of the role to users and other roles
+    Of(Ident),
+}
+
+/// A row of `SHOW GRANTS`, a role granted to a grantee is shown as `USAGE` on the role
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrantRow {
+    pub created_on: String,
+    pub privilege: String,
+    pub granted_on: String,
+    pub name: String,
+    pub granted_to: String,
+    pub grantee_name: String,
+    pub granted_by: Option<String>,
+}
+
+impl From<&RwObject<Grant>> for GrantRow {
+    fn from(grant: &RwObject<Grant>) -> Self {
+        Self {
+            created_on: grant.created_at.to_string(),
+            privilege: grant.privilege.to_string(),
+            granted_on: grant.object.kind().to_string(),
+            name: grant.object.to_string(),
+            granted_to: Grantee::Role(grant.role.clone()).kind().to_string(),
+            grantee_name: grant.role.clone(),
+            granted_by: grant.granted_by.clone(),
+        }
+    }
+}
+
+impl From<&RwObject<RoleGrant>> for GrantRow {
+    fn from(grant: &RwObject<RoleGrant>) -> Self {
+        Self {
+            created_on: grant.created_at.to_string(),
+            privilege: Privilege::Usage.to_string(),
+            granted_on: "ROLE".to_string(),
+            name: grant.role.clone(),
+            granted_to: grant.grantee.kind().to_string(),
+            grantee_name: grant.grantee.name().to_string(),
+            granted_by: grant.granted_by.clone(),
+        }
+    }
+}
+
+/// Recognizes role and grant statements. Returns `Ok(None)` for any other SQL,
+/// so it can be handed over to the regular parser unchanged.
+pub fn parse_role_statement(sql: &str, dialect: &str) -> Result<Option<RoleStatement>> {
+    let dialect: Box<dyn Dialect> =
+        dialect_from_str(dialect).unwrap_or_else(|| Box::new(SnowflakeDialect {}));
+    let Ok(mut parser) = Parser::new(dialect.as_ref()).try_with_sql(sql) else {
+        return Ok(None);
+    };
+
+    let statement = if parser.parse_keyword(Keyword::CREATE) {
+        let or_replace = parser.parse_keywords(&[Keyword::OR, Keyword::REPLACE]);
+        if !parse_word(&mut parser, "ROLE") {
+            return Ok(None);
+        }
+        parse_create_role(&mut parser, or_replace).context(ex_error::SqlParserSnafu)?
+    } else if parser.parse_keyword(Keyword::DROP) {
+        if !parse_word(&mut parser, "ROLE") {
+            return Ok(None);
+        }
+        let if_exists = parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+        let name = parser
+            .parse_identifier()
+            .context(ex_error::SqlParserSnafu)?;
+        RoleStatement::DropRole { name, if_exists }
+    } else if parser.parse_keyword(Keyword::GRANT) {
+        parse_grant(&mut parser, false).context(ex_error::SqlParserSnafu)?
+    } else if parser.parse_keyword(Keyword::REVOKE) {
+        parse_grant(&mut parser, true).context(ex_error::SqlParserSnafu)?
+    } else if parser.parse_keyword(Keyword::SHOW) {
+        if parse_word(&mut parser, "ROLES") {
+            let like = if parser.parse_keyword(Keyword::LIKE) {
+                Some(
+                    parser
+                        .parse_literal_string()
+                        .context(ex_error::SqlParserSnafu)?,
+                )
+            } else {
+                None
+            };
+            RoleStatement::ShowRoles { like }
+        } else if parse_word(&mut parser, "GRANTS") {
+            parse_show_grants(&mut parser).context(ex_error::SqlParserSnafu)?
+        } else {
+            return Ok(None);
+        }
+    } else {
+        return Ok(None);
+    };
+
+    let _ = parser.consume_token(&Token::SemiColon);
+    if parser.peek_token().token != Token::EOF {
+        return parser
+            .expected("end of statement", parser.peek_token())
+            .context(ex_error::SqlParserSnafu);
+    }
+    Ok(Some(statement))
+}
+
+fn parse_create_role(
+    parser: &mut Parser,
+    or_replace: bool,
+) -> std::result::Result<RoleStatement, ParserError> {
+    let if_not_exists = parser.parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+    let name = parser.parse_identifier()?;
+    let comment = if parser.parse_keyword(Keyword::COMMENT) {
+        parser.expect_token(&Token::Eq)?;
+        Some(parser.parse_literal_string()?)
+    } else {
+        None
+    };
+    Ok(RoleStatement::CreateRole {
+        name,
+        or_replace,
+        if_not_exists,
+        comment,
+    })
+}
+
+fn parse_grant(
+    parser: &mut Parser,
+    revoke: bool,
+) -> std::result::Result<RoleStatement, ParserError> {
+    let to = if revoke { Keyword::FROM } else { Keyword::TO };
+
+    if parse_word(parser, "ROLE") {
+        let role = parser.parse_identifier()?;
+        parser.expect_keyword(to)?;
+        let grantee = parse_grant_to(parser)?;
+        return Ok(RoleStatement::Role {
+            revoke,
+            role,
+            grantee,
+        });
+    }
+
+    // Privileges are listed before the object, `ALL` depends on its kind
+    let all = parser.parse_keyword(Keyword::ALL);
+    let mut privileges = Vec::new();
+    if all {
+        let _ = parse_word(parser, "PRIVILEGES");
+    } else {
+        loop {
+            let token = parser.next_token();
+            let privilege = match &token.token {
+                Token::Word(w) => Privilege::from_str(&w.value).ok(),
+                _ => None,
+            };
+            let Some(privilege) = privilege else {
+                return parser.expected("privilege", token);
+            };
+            // CREATE TABLE / VIEW / SCHEMA are all granted as CREATE
+            if privilege == Privilege::Create {
+                let _ = parse_word(parser, "TABLE")
+                    || parse_word(parser, "VIEW")
+                    || parse_word(parser, "SCHEMA");
+            }
+            privileges.push(privilege);
+            if !parser.consume_token(&Token::Comma) {
+                break;
+            }
+        }
+    }
+
+    parser.expect_keyword(Keyword::ON)?;
+    let object = parse_grant_on(parser)?;
+    if all {
+        privileges = match object {
+            GrantOn::Table(_) => vec![
+                Privilege::Select,
+                Privilege::Insert,
+                Privilege::Update,
+                Privilege::Delete,
+            ],
+            GrantOn::Database(_) | GrantOn::Schema(_) => {
+                vec![Privilege::Usage, Privilege::Create]
+            }
+            GrantOn::Stage(_) | GrantOn::FileFormat(_) => vec![Privilege::Usage],
+        };
+    }
+    parser.expect_keyword(to)?;
+    if !parse_word(parser, "ROLE") {
+        return parser.expected("ROLE", parser.peek_token());
+    }
+    let role = parser.parse_identifier()?;
+    Ok(RoleStatement::Privileges {
+        revoke,
+        privileges,
+        object,
+        role,
+    })
+}
+
+fn parse_grant_on(parser: &mut Parser) -> std::result::Result<GrantOn, ParserError> {
+    let object = if parse_word(parser, "DATABASE") {
+        GrantOn::Database(parser.parse_object_name(false)?)
+    } else if parse_word(parser, "SCHEMA") {
+        GrantOn::Schema(parser.parse_object_name(false)?)
+    } else if parse_word(parser, "TABLE") || parse_word(parser, "VIEW") {
+        GrantOn::Table(parser.parse_object_name(false)?)
+    } else if parse_word(parser, "STAGE") {
+        GrantOn::Stage(parser.parse_object_name(false)?)
+    } else if parse_word(parser, "FILE") {
+        if !parse_word(parser, "FORMAT") {
+            return parser.expected("FORMAT", parser.peek_token());
+        }
+        GrantOn::FileFormat(parser.parse_object_name(false)?)
+    } else {
+        return parser.expected(
+            "DATABASE, SCHEMA, TABLE, VIEW, STAGE or FILE FORMAT",
+            parser.peek_token(),
+        );
+    };
+    Ok(object)
+}
+
+fn parse_grant_to(parser: &mut Parser) -> std::result::Result<GrantTo, ParserError> {
+    if parse_word(parser, "ROLE") {
+        Ok(GrantTo::Role(parser.parse_identifier()?))
+    } else if parse_word(parser, "USER") {
+        Ok(GrantTo::User(parser.parse_identifier()?))
+    } else {
+        parser.expected("ROLE or USER", parser.peek_token())
+    }
+}
+
+fn parse_show_grants(parser: &mut Parser) -> std::result::Result<RoleStatement, ParserError> {
+    let show = if parser.parse_keyword(Keyword::ON) {
+        ShowGrants::On(parse_grant_on(parser)?)
+    } else if parser.parse_keyword(Keyword::TO) {
+        ShowGrants::To(parse_grant_to(parser)?)
+    } else if parser.parse_keyword(Keyword::OF) {
+        if !parse_word(parser, "ROLE") {
+            return parser.expected("ROLE", parser.peek_token());
+        }
+        ShowGrants::Of(parser.parse_identifier()?)
+    } else {
+        ShowGrants::Current
+    };
+    Ok(RoleStatement::ShowGrants(show))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn parse(sql: &str) -> Option<RoleStatement> {
+        parse_role_statement(sql, "snowflake").unwrap()
+    }
+
+    fn name(name: &str) -> ObjectName {
+        ObjectName::from(name.split('.').map(Ident::new).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_parse_role_statements() {
+        assert_eq!(
+            parse("CREATE OR REPLACE ROLE IF NOT EXISTS analyst COMMENT = 'reads'"),
+            Some(RoleStatement::CreateRole {
+                name: Ident::new("analyst"),
+                or_replace: true,
+                if_not_exists: true,
+                comment: Some("reads".to_string()),
+            })
+        );
+        assert_eq!(
+            parse("drop role if exists analyst;"),
+            Some(RoleStatement::DropRole {
+                name: Ident::new("analyst"),
+                if_exists: true,
+            })
+        );
+        assert_eq!(
+            parse("SHOW ROLES LIKE 'a%'"),
+            Some(RoleStatement::ShowRoles {
+                like: Some("a%".to_string()),
+            })
+        );
+        assert_eq!(
+            parse("GRANT ROLE analyst TO USER alice"),
+            Some(RoleStatement::Role {
+                revoke: false,
+                role: Ident::new("analyst"),
+                grantee: GrantTo::User(Ident::new("alice")),
+            })
+        );
+        assert_eq!(
+            parse("REVOKE ROLE analyst FROM ROLE sysadmin"),
+            Some(RoleStatement::Role {
+                revoke: true,
+                role: Ident::new("analyst"),
+                grantee: GrantTo::Role(Ident::new("sysadmin")),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_privilege_grants() {
+        assert_eq!(
+            parse("GRANT SELECT, insert ON TABLE db.sch.tbl TO ROLE analyst"),
+            Some(RoleStatement::Privileges {
+                revoke: false,
+                privileges: vec![Privilege::Select, Privilege::Insert],
+                object: GrantOn::Table(name("db.sch.tbl")),
+                role: Ident::new("analyst"),
+            })
+        );
+        assert_eq!(
+            parse("GRANT USAGE, CREATE TABLE ON SCHEMA db.sch TO ROLE analyst"),
+            Some(RoleStatement::Privileges {
+                revoke: false,
+                privileges: vec![Privilege::Usage, Privilege::Create],
+                object: GrantOn::Schema(name("db.sch")),
+                role: Ident::new("analyst"),
+            })
+        );
+        assert_eq!(
+            parse("REVOKE ALL PRIVILEGES ON DATABASE db FROM ROLE analyst"),
+            Some(RoleStatement::Privileges {
+                revoke: true,
+                privileges: vec![Privilege::Usage, Privilege::Create],
+                object: GrantOn::Database(name("db")),
+                role: Ident::new("analyst"),
+            })
+        );
+        assert_eq!(
+            parse("GRANT OWNERSHIP ON VIEW v TO ROLE analyst"),
+            Some(RoleStatement::Privileges {
+                revoke: false,
+                privileges: vec![Privilege::Ownership],
+                object: GrantOn::Table(name("v")),
+                role: Ident::new("analyst"),
+            })
+        );
+        assert_eq!(
+            parse("GRANT ALL ON STAGE db.sch.stg TO ROLE analyst"),
+            Some(RoleStatement::Privileges {
+                revoke: false,
+                privileges: vec![Privilege::Usage],
+                object: GrantOn::Stage(name("db.sch.stg")),
+                role: Ident::new("analyst"),
+            })
+        );
+        assert_eq!(
+            parse("GRANT USAGE ON FILE FORMAT db.sch.fmt TO ROLE analyst"),
+            Some(RoleStatement::Privileges {
+                revoke: false,
+                privileges: vec![Privilege::Usage],
+                object: GrantOn::FileFormat(name("db.sch.fmt")),
+                role: Ident::new("analyst"),
+            })
+        );
+        assert!(parse_role_statement("GRANT FLY ON TABLE t TO ROLE r", "snowflake").is_err());
+    }
+
+    #[test]
+    fn test_parse_show_grants() {
+        assert_eq!(
+            parse("SHOW GRANTS"),
+            Some(RoleStatement::ShowGrants(ShowGrants::Current))
+        );
+        assert_eq!(
+            parse("SHOW GRANTS ON SCHEMA db.sch"),
+            Some(RoleStatement::ShowGrants(ShowGrants::On(GrantOn::Schema(
+                name("db.sch")
+            ))))
+        );
+        assert_eq!(
+            parse("SHOW GRANTS TO USER alice"),
+            Some(RoleStatement::ShowGrants(ShowGrants::To(GrantTo::User(
+                Ident::new("alice")
+            ))))
+        );
+        assert_eq!(
+            parse("SHOW GRANTS OF ROLE analyst"),
+            Some(RoleStatement::ShowGrants(ShowGrants::Of(Ident::new(
+                "analyst"
+            ))))
+        );
+        assert_eq!(parse("SHOW TABLES"), None);
+        assert_eq!(parse("CREATE TABLE roles (a INT)"), None);
+    }
+}
@@ -1,9 +1,12 @@
 use super::error::{self as ex_error, Result};
-use core_history::QueryRecordId;
+use crate::profile::operator_stats;
+use crate::progress::QueryProgress;
 use core_history::QueryStatus;
+use core_history::{OperatorStats, QueryRecordId};
 use dashmap::DashMap;
+use datafusion::physical_plan::ExecutionPlan;
 use snafu::OptionExt;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use tokio::sync::watch;
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
@@ -13,6 +16,8 @@ pub struct RunningQuery {
     pub query_id: QueryRecordId,
     pub request_id: Option<Uuid>,
     cancellation_token: CancellationToken,
+    // plan being executed, shared by the clones to report progress of the query
+    physical_plan: Arc<Mutex<Option<Arc<dyn ExecutionPlan>>>>,
     // user can be notified when query is finished
     tx: watch::Sender<QueryStatus>,
     rx: watch::Receiver<QueryStatus>,
@@ -32,6 +37,7 @@ impl RunningQuery {
             query_id,
             request_id: None,
             cancellation_token: CancellationToken::new(),
+            physical_plan: Arc::new(Mutex::new(None)),
             tx,
             rx,
         }
@@ -45,6 +51,33 @@ impl RunningQuery {
         self.cancellation_token.cancel();
     }
 
+    /// Sets the physical plan the query is executing, for statements running
+    /// several plans it is the latest one
+    pub fn set_physical_plan(&self, plan: Arc<dyn ExecutionPlan>) {
+        if let Ok(mut physical_plan) = self.physical_plan.lock() {
+            *physical_plan = Some(plan);
+        }
+    }
+
+    /// Progress of the query, empty until its physical plan is set
+    #[must_use]
+    pub fn progress(&self) -> QueryProgress {
+        self.physical_plan
+            .lock()
+            .ok()
+            .and_then(|plan| plan.as_deref().map(QueryProgress::from_plan))
+            .unwrap_or_default()
+    }
+
+    /// Statistics of the operators of the physical plan, `None` if the query had no plan
+    #[must_use]
+    pub fn operator_stats(&self) -> Option<Vec<OperatorStats>> {
+        self.physical_plan
+            .lock()
+            .ok()
+            .and_then(|plan| plan.as_deref().map(operator_stats))
+    }
+
     #[tracing::instrument(
         name = "RunningQuery::notify_query_finished",
         level = "trace",
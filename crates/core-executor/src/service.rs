@@ -2,6 +2,7 @@ use bytes::{Buf, Bytes};
 use datafusion::arrow::array::RecordBatch;
 use datafusion::arrow::csv::ReaderBuilder;
 use datafusion::arrow::csv::reader::Format;
+use datafusion::arrow::datatypes::{Schema as ArrowSchema, SchemaRef};
 use datafusion::catalog::CatalogProvider;
 use datafusion::catalog::{MemoryCatalogProvider, MemorySchemaProvider};
 use datafusion::common::runtime::set_join_set_tracer;
@@ -12,8 +13,10 @@ use datafusion::execution::memory_pool::{
     FairSpillPool, GreedyMemoryPool, MemoryPool, TrackConsumersPool,
 };
 use datafusion::execution::runtime_env::{RuntimeEnv, RuntimeEnvBuilder};
-use datafusion_common::TableReference;
-use snafu::ResultExt;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion_common::{DataFusionError, ScalarValue, TableReference};
+use futures::StreamExt;
+use snafu::{IntoError, ResultExt};
 use std::num::NonZeroUsize;
 use std::sync::atomic::Ordering;
 use std::vec;
@@ -21,38 +24,59 @@ use std::{collections::HashMap, sync::Arc};
 use time::{Duration as DateTimeDuration, OffsetDateTime};
 
 use super::error::{self as ex_error, Result};
-use super::models::{AsyncQueryHandle, QueryContext, QueryResult, QueryResultStatus};
+use super::models::{
+    AsyncQueryHandle, MultiStatementHandle, MultiStatementResult, QueryContext, QueryResult,
+    QueryResultStatus, QueryResultStream, SessionOptions, StatementResult,
+};
+use super::progress::QueryProgress;
 use super::running_queries::{RunningQueries, RunningQueriesRegistry, RunningQuery};
 use super::session::UserSession;
+use crate::access_control::QueryReader;
+use crate::result_cache::ResultCache;
 use crate::running_queries::RunningQueryId;
-use crate::session::{SESSION_INACTIVITY_EXPIRATION_SECONDS, to_unix};
+use crate::session::to_unix;
+use crate::spool;
 use crate::tracing::SpanTracer;
 use crate::utils::{Config, MemPoolType};
 use core_history::HistoryStore;
 use core_history::SlateDBHistoryStore;
-use core_history::{QueryRecordId, QueryResultError, QueryStatus};
+use core_history::{QueryRecord, QueryRecordId, QueryResultError, QueryStatus};
 use core_metastore::{
-    Database, Metastore, Schema, SchemaIdent, SlateDBMetastore, TableIdent as MetastoreTableIdent,
-    Volume, VolumeType,
+    Database, Grant, GrantObject, Metastore, PUBLIC_ROLE, Privilege, Schema, SchemaIdent,
+    SlateDBMetastore, TableIdent as MetastoreTableIdent, Volume, VolumeType, bootstrap_roles,
 };
 use df_catalog::catalog_list::{DEFAULT_CATALOG, EmbucketCatalogList};
+use embucket_functions::session_params::SessionProperty;
 use tokio::sync::RwLock;
 use tokio::sync::oneshot;
-use tokio::time::{Duration, timeout};
+use tokio::time::{Duration, Instant, sleep_until, timeout, timeout_at};
 use tracing::Instrument;
 use uuid::Uuid;
 
 const DEFAULT_SCHEMA: &str = "public";
+/// Result of the parent query of a multi-statement request
+const MULTI_STATEMENT_COLUMN: &str = "multiple statement execution";
+const MULTI_STATEMENT_MESSAGE: &str = "Multiple statements executed successfully.";
 
 #[async_trait::async_trait]
 pub trait ExecutionService: Send + Sync {
     async fn create_session(&self, session_id: &str) -> Result<Arc<UserSession>>;
-    async fn update_session_expiry(&self, session_id: &str) -> Result<bool>;
+    /// Creates a session with the session parameters and client info given on login.
+    /// An existing session is returned as is.
+    async fn create_session_with_options(
+        &self,
+        session_id: &str,
+        options: SessionOptions,
+    ) -> Result<Arc<UserSession>>;
+    /// Extends the session expiry to `validity_secs` from now, an expiry already
+    /// further away is kept. Returns false if there is no such session.
+    async fn update_session_expiry(&self, session_id: &str, validity_secs: i64) -> Result<bool>;
     async fn delete_expired_sessions(&self) -> Result<()>;
     async fn get_session(&self, session_id: &str) -> Result<Arc<UserSession>>;
     async fn session_exists(&self, session_id: &str) -> bool;
-    // Currently delete_session function is not used
-    // async fn delete_session(&self, session_id: String) -> Result<()>;
+    /// Deletes the session right away, the queries it is running are not aborted.
+    /// Returns false if there is no such session.
+    async fn delete_session(&self, session_id: &str) -> Result<bool>;
     fn get_sessions(&self) -> Arc<RwLock<HashMap<String, Arc<UserSession>>>>;
 
     /// Aborts a query by `query_id` or `request_id`.
@@ -105,6 +129,7 @@ pub trait ExecutionService: Send + Sync {
     /// or error which is just a simple wrapper around stringified error loaded from history
     /// # Arguments
     ///
+    /// * `reader` - Who reads the query, the user who ran it or an admin.
     /// * `query_id` - The ID of the query to wait for.
     ///
     /// # Returns
@@ -114,9 +139,33 @@ pub trait ExecutionService: Send + Sync {
     /// `Error::QueryExecution` with `query_id`.
     async fn wait_historical_query_result(
         &self,
+        reader: &QueryReader,
         query_id: QueryRecordId,
     ) -> Result<Result<QueryResult>>;
 
+    /// Returns the query history record of a query without waiting for it to finish
+    /// # Arguments
+    ///
+    /// * `session_id` - The ID of the user session, of the user who ran the query or of an admin.
+    /// * `query_id` - The ID of the query.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` of type `QueryRecord`. For a query which is still running the record has
+    /// `Running` status and `end_time` / `duration_ms` measured up to now.
+    async fn query_status(&self, session_id: &str, query_id: QueryRecordId) -> Result<QueryRecord>;
+
+    /// Returns progress of a running query, derived from metrics of its physical plan
+    /// # Arguments
+    ///
+    /// * `query_id` - The ID of the query.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` of type `QueryProgress`. The `Err` variant is `Error::QueryIsntRunning`
+    /// if the query has finished or doesn't exist.
+    fn query_progress(&self, query_id: QueryRecordId) -> Result<QueryProgress>;
+
     /// Synchronously executes a query and returns the result.
     /// It is a wrapper around `submit_query` and `wait_submitted_query_result`.
     ///
@@ -137,6 +186,92 @@ pub trait ExecutionService: Send + Sync {
         query_context: QueryContext,
     ) -> Result<QueryResult>;
 
+    /// Executes a query streaming its result instead of collecting it in memory.
+    /// The query timeout covers reading the whole result.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id` - The ID of the user session.
+    /// * `query` - The SQL query to be executed.
+    /// * `query_context` - The context of the query execution.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` of type `QueryResultStream`. The `Ok` variant contains the stream of the
+    /// query result, recorded to the query history once the stream is exhausted.
+    /// The `Err` variant contains an error of the query planning or execution start.
+    async fn query_stream(
+        &self,
+        session_id: &str,
+        query: &str,
+        query_context: QueryContext,
+    ) -> Result<QueryResultStream>;
+
+    /// Reads a streamed result ahead of a slow consumer, so the query doesn't wait for
+    /// it. Batches the consumer isn't reading yet are spooled to disk above
+    /// `Config::result_spool_memory_mb`.
+    fn spool_result(&self, result: QueryResultStream) -> QueryResultStream;
+
+    /// Executes the statements of a script one after another in the session, stopping
+    /// at the first failing one. Every statement is recorded in the query history, and
+    /// so is the whole script, as the parent query of the statements.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id` - The ID of the user session.
+    /// * `query` - The whole script, recorded as the parent query.
+    /// * `statements` - The statements of the script.
+    /// * `query_context` - The context of the statements execution.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` of type `MultiStatementResult`. The `Ok` variant contains the parent
+    /// query result and the results of the statements. The `Err` variant contains
+    /// the error of the failed statement.
+    async fn query_multi_statement(
+        &self,
+        session_id: &str,
+        query: &str,
+        statements: &[String],
+        query_context: QueryContext,
+    ) -> Result<MultiStatementResult>;
+
+    /// Submits the statements of a script for execution in the background, see
+    /// `query_multi_statement`. The parent query is recorded as running before
+    /// the statements start.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` of type `MultiStatementHandle`, with the id of the parent query
+    /// and the receiver of the result of the statements.
+    async fn submit_multi_statement(
+        &self,
+        session_id: &str,
+        query: &str,
+        statements: &[String],
+        query_context: QueryContext,
+    ) -> Result<MultiStatementHandle>;
+
+    /// Describes the result of a query without executing it: the query is planned and
+    /// the result has the schema of the plan and no rows. Recorded in the query history.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_id` - The ID of the user session.
+    /// * `query` - The SQL query to be described.
+    /// * `query_context` - The context of the query.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` of type `QueryResult`. The `Ok` variant contains the empty result,
+    /// and the `Err` variant contains the planning error.
+    async fn describe_query(
+        &self,
+        session_id: &str,
+        query: &str,
+        query_context: QueryContext,
+    ) -> Result<QueryResult>;
+
     async fn upload_data_to_table(
         &self,
         session_id: &str,
@@ -147,6 +282,7 @@ pub trait ExecutionService: Send + Sync {
     ) -> Result<usize>;
 }
 
+#[derive(Clone)]
 pub struct CoreExecutionService {
     metastore: Arc<dyn Metastore>,
     history_store: Arc<dyn HistoryStore>,
@@ -155,6 +291,7 @@ pub struct CoreExecutionService {
     catalog_list: Arc<EmbucketCatalogList>,
     runtime_env: Arc<RuntimeEnv>,
     queries: Arc<RunningQueriesRegistry>,
+    result_cache: Arc<ResultCache>,
 }
 
 impl CoreExecutionService {
@@ -169,9 +306,12 @@ impl CoreExecutionService {
         history_store: Arc<dyn HistoryStore>,
         config: Arc<Config>,
     ) -> Result<Self> {
+        // Sessions without a user have the PUBLIC role and own the objects they create
+        let _ = bootstrap_roles(metastore.as_ref()).await;
         if config.bootstrap_default_entities {
             // do not fail on bootstrap errors
             let _ = Self::bootstrap(metastore.clone()).await;
+            let _ = Self::bootstrap_public_grants(metastore.as_ref()).await;
         }
 
         Self::initialize_datafusion_tracer();
@@ -186,6 +326,105 @@ impl CoreExecutionService {
             catalog_list,
             runtime_env,
             queries: Arc::new(RunningQueriesRegistry::new()),
+            result_cache: Arc::new(ResultCache::new(
+                config.result_cache_memory_mb * 1024 * 1024,
+                config.query_history_rows_limit,
+            )),
+        })
+    }
+
+    /// Executes the statements of a multi-statement request one after another,
+    /// finishing its parent query record with the status of the last one
+    async fn execute_statements(
+        &self,
+        mut history_record: QueryRecord,
+        session_id: &str,
+        statements: &[String],
+        query_context: QueryContext,
+    ) -> Result<MultiStatementResult> {
+        let query_id = history_record.query_id();
+        let mut statement_results = Vec::with_capacity(statements.len());
+        for statement in statements {
+            match self
+                .execute_statement(session_id, statement, query_context.clone())
+                .await
+            {
+                Ok(statement_result) => statement_results.push(statement_result),
+                Err(err) => {
+                    history_record.finished_with_error(&QueryResultError {
+                        status: QueryStatus::Failed,
+                        message: err.to_snowflake_error().to_string(),
+                        diagnostic_message: format!("{err:?}"),
+                    });
+                    self.history_store
+                        .save_query_record(&history_record, None)
+                        .await;
+                    return Err(err);
+                }
+            }
+        }
+
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            MULTI_STATEMENT_COLUMN,
+            DataType::Utf8,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(StringArray::from(vec![MULTI_STATEMENT_MESSAGE]))],
+        )
+        .context(ex_error::ArrowSnafu)?;
+        let result = QueryResult::new(vec![batch], schema, query_id);
+
+        history_record.finished_with_status(QueryStatus::Successful, 1);
+        self.history_store
+            .save_query_record(
+                &history_record,
+                result
+                    .as_result_set(Some(self.config.query_history_rows_limit))
+                    .ok(),
+            )
+            .await;
+        Ok(MultiStatementResult {
+            result,
+            statements: statement_results,
+        })
+    }
+
+    async fn query_record(&self, query_id: QueryRecordId) -> Result<QueryRecord> {
+        self.history_store
+            .get_query(query_id)
+            .await
+            .context(ex_error::QueryHistorySnafu)
+            .context(ex_error::QueryExecutionSnafu { query_id })
+    }
+
+    /// Removes the query from the running queries, once its final history
+    /// record is saved, and notifies the listeners waiting for it
+    fn finish_running_query(&self, query_id: QueryRecordId, status: QueryStatus) {
+        if let Ok(running_query) = self.queries.remove(RunningQueryId::ByQueryId(query_id)) {
+            let _ = running_query.notify_query_finished(status);
+        }
+    }
+
+    /// Executes a statement of a multi-statement request, keeping its full result
+    async fn execute_statement(
+        &self,
+        session_id: &str,
+        statement: &str,
+        query_context: QueryContext,
+    ) -> Result<StatementResult> {
+        let result = self
+            .query(session_id, statement, query_context.clone())
+            .await?;
+        let statement_type_id = self
+            .get_session(session_id)
+            .await?
+            .query(statement, query_context)
+            .statement_type_id()?;
+        Ok(StatementResult {
+            result,
+            statement_type_id,
         })
     }
 
@@ -242,6 +481,57 @@ impl CoreExecutionService {
         Ok(())
     }
 
+    /// Grants `USAGE` and `CREATE` on the default database `embucket` and schema
+    /// `public` to `PUBLIC`, so sessions without a user can work in them.
+    /// Done on every start, like the bootstrap of the objects themselves.
+    #[tracing::instrument(
+        name = "CoreExecutionService::bootstrap_public_grants",
+        level = "info",
+        skip(metastore),
+        err
+    )]
+    async fn bootstrap_public_grants(metastore: &dyn Metastore) -> Result<()> {
+        let database = DEFAULT_CATALOG.to_string();
+        let schema = SchemaIdent::new(database.clone(), DEFAULT_SCHEMA.to_string());
+        let mut objects = Vec::new();
+        if metastore
+            .get_database(&database)
+            .await
+            .context(ex_error::BootstrapSnafu {
+                entity_type: "database",
+            })?
+            .is_some()
+        {
+            objects.push(GrantObject::Database(database));
+        }
+        if metastore
+            .get_schema(&schema)
+            .await
+            .context(ex_error::BootstrapSnafu {
+                entity_type: "schema",
+            })?
+            .is_some()
+        {
+            objects.push(GrantObject::Schema(schema));
+        }
+        for object in objects {
+            for privilege in [Privilege::Usage, Privilege::Create] {
+                metastore
+                    .grant_privilege(Grant {
+                        privilege,
+                        object: object.clone(),
+                        role: PUBLIC_ROLE.to_string(),
+                        granted_by: None,
+                    })
+                    .await
+                    .context(ex_error::BootstrapSnafu {
+                        entity_type: "grant",
+                    })?;
+            }
+        }
+        Ok(())
+    }
+
     #[tracing::instrument(
         name = "CoreExecutionService::catalog_list",
         level = "debug",
@@ -334,24 +624,75 @@ impl ExecutionService for CoreExecutionService {
         name = "ExecutionService::create_session",
         level = "debug",
         skip(self),
-        fields(new_sessions_count),
         err
     )]
     async fn create_session(&self, session_id: &str) -> Result<Arc<UserSession>> {
+        self.create_session_with_options(session_id, SessionOptions::default())
+            .await
+    }
+
+    #[tracing::instrument(
+        name = "ExecutionService::create_session_with_options",
+        level = "debug",
+        skip(self),
+        fields(new_sessions_count),
+        err
+    )]
+    async fn create_session_with_options(
+        &self,
+        session_id: &str,
+        mut options: SessionOptions,
+    ) -> Result<Arc<UserSession>> {
         {
             let sessions = self.df_sessions.read().await;
             if let Some(session) = sessions.get(session_id) {
                 return Ok(session.clone());
             }
         }
-        let user_session: Arc<UserSession> = Arc::new(UserSession::new(
-            self.metastore.clone(),
-            self.history_store.clone(),
-            self.queries.clone(),
-            self.config.clone(),
-            self.catalog_list.clone(),
-            self.runtime_env.clone(),
-        )?);
+        // Sessions of a user start with the default role of the user, unless opened with one
+        if let Some(user) = &options.user
+            && !options.params.contains_key("role")
+            && let Some(default_role) = self
+                .metastore
+                .get_user(user)
+                .await
+                .context(ex_error::MetastoreSnafu)?
+                .and_then(|user| user.data.default_role)
+        {
+            options
+                .params
+                .insert("role".to_string(), ScalarValue::Utf8(Some(default_role)));
+        }
+        let user_session: Arc<UserSession> = Arc::new(
+            UserSession::new(
+                self.metastore.clone(),
+                self.history_store.clone(),
+                self.queries.clone(),
+                self.config.clone(),
+                self.catalog_list.clone(),
+                self.runtime_env.clone(),
+                options.user,
+                options.role_restriction,
+            )?
+            .with_client_info(options.client_info)
+            .with_result_cache(self.result_cache.clone()),
+        );
+        if !options.params.is_empty() {
+            let df_session_id = user_session.ctx.session_id();
+            let params = options
+                .params
+                .into_iter()
+                .map(|(name, value)| {
+                    let property = SessionProperty::from_scalar_value(
+                        name.clone(),
+                        &value,
+                        df_session_id.clone(),
+                    );
+                    (name, property)
+                })
+                .collect();
+            user_session.set_session_variable(true, params)?;
+        }
         {
             tracing::trace!("Acquiring write lock for df_sessions");
             let mut sessions = self.df_sessions.write().await;
@@ -371,14 +712,14 @@ impl ExecutionService for CoreExecutionService {
         fields(old_sessions_count, new_sessions_count, now),
         err
     )]
-    async fn update_session_expiry(&self, session_id: &str) -> Result<bool> {
+    async fn update_session_expiry(&self, session_id: &str, validity_secs: i64) -> Result<bool> {
         let mut sessions = self.df_sessions.write().await;
 
         let res = if let Some(session) = sessions.get_mut(session_id) {
             let now = OffsetDateTime::now_utc();
-            let new_expiry =
-                to_unix(now + DateTimeDuration::seconds(SESSION_INACTIVITY_EXPIRATION_SECONDS));
-            session.expiry.store(new_expiry, Ordering::Relaxed);
+            let new_expiry = to_unix(now + DateTimeDuration::seconds(validity_secs));
+            // A regular request must not shorten the expiry given by a keep-alive
+            session.expiry.fetch_max(new_expiry, Ordering::Relaxed);
 
             // Record the result as part of the current span.
             tracing::Span::current().record("sessions_count", sessions.len());
@@ -467,22 +808,22 @@ impl ExecutionService for CoreExecutionService {
         sessions.contains_key(session_id)
     }
 
-    // #[tracing::instrument(
-    //     name = "ExecutionService::delete_session",
-    //     level = "debug",
-    //     skip(self),
-    //     fields(new_sessions_count),
-    //     err
-    // )]
-    // async fn delete_session(&self, session_id: String) -> Result<()> {
-    //     // TODO: Need to have a timeout for the lock
-    //     let mut session_list = self.df_sessions.write().await;
-    //     session_list.remove(&session_id);
-
-    //     // Record the result as part of the current span.
-    //     tracing::Span::current().record("new_sessions_count", session_list.len());
-    //     Ok(())
-    // }
+    #[tracing::instrument(
+        name = "ExecutionService::delete_session",
+        level = "debug",
+        skip(self),
+        fields(new_sessions_count),
+        err
+    )]
+    async fn delete_session(&self, session_id: &str) -> Result<bool> {
+        let mut session_list = self.df_sessions.write().await;
+        let deleted = session_list.remove(session_id).is_some();
+
+        // Record the result as part of the current span.
+        tracing::Span::current().record("new_sessions_count", session_list.len());
+        Ok(deleted)
+    }
+
     fn get_sessions(&self) -> Arc<RwLock<HashMap<String, Arc<UserSession>>>> {
         self.df_sessions.clone()
     }
@@ -547,6 +888,7 @@ impl ExecutionService for CoreExecutionService {
     )]
     async fn wait_historical_query_result(
         &self,
+        reader: &QueryReader,
         query_id: QueryRecordId,
     ) -> Result<Result<QueryResult>> {
         if let Ok(mut running_query) = self.queries.get(RunningQueryId::ByQueryId(query_id)) {
@@ -564,6 +906,7 @@ impl ExecutionService for CoreExecutionService {
             .await
             .context(ex_error::QueryHistorySnafu)
             .context(ex_error::QueryExecutionSnafu { query_id })?;
+        reader.check(self.metastore.as_ref(), &query_record).await?;
 
         if query_record.status == QueryStatus::Running {
             ex_error::QueryIsRunningSnafu { query_id }
@@ -592,6 +935,55 @@ impl ExecutionService for CoreExecutionService {
         }
     }
 
+    #[tracing::instrument(
+        name = "ExecutionService::query_status",
+        level = "debug",
+        skip(self),
+        fields(query_status, query_uuid = query_id.as_uuid().to_string()),
+        err
+    )]
+    async fn query_status(&self, session_id: &str, query_id: QueryRecordId) -> Result<QueryRecord> {
+        let user_session = self.get_session(session_id).await?;
+        let mut query_record = self.query_record(query_id).await?;
+        user_session
+            .query_reader()
+            .check(self.metastore.as_ref(), &query_record)
+            .await?;
+
+        if query_record.status == QueryStatus::Running {
+            if self.queries.is_running(RunningQueryId::ByQueryId(query_id)) {
+                query_record.end_time = chrono::Utc::now();
+                query_record.duration_ms = query_record
+                    .end_time
+                    .signed_duration_since(query_record.start_time)
+                    .num_milliseconds();
+            } else {
+                // A query is removed from running queries once its final record is saved,
+                // it has just finished or it was interrupted by a restart of the server
+                query_record = self.query_record(query_id).await?;
+                if query_record.status == QueryStatus::Running {
+                    query_record.status = QueryStatus::Canceled;
+                }
+            }
+        }
+        tracing::Span::current().record("query_status", query_record.status.to_string());
+        Ok(query_record)
+    }
+
+    #[tracing::instrument(
+        name = "ExecutionService::query_progress",
+        level = "debug",
+        skip(self),
+        fields(query_uuid = query_id.as_uuid().to_string()),
+        err
+    )]
+    fn query_progress(&self, query_id: QueryRecordId) -> Result<QueryProgress> {
+        Ok(self
+            .queries
+            .get(RunningQueryId::ByQueryId(query_id))?
+            .progress())
+    }
+
     #[tracing::instrument(
         name = "ExecutionService::abort_query",
         level = "debug",
@@ -625,6 +1017,7 @@ impl ExecutionService for CoreExecutionService {
         let mut history_record = self
             .history_store
             .new_query_record(query, query_context.worksheet_id);
+        history_record.user.clone_from(&user_session.user);
 
         let query_id = history_record.query_id();
 
@@ -716,9 +1109,6 @@ impl ExecutionService for CoreExecutionService {
             )
             .entered();
 
-            // remove query from running queries registry
-            let running_query = queries_ref.remove(RunningQueryId::ByQueryId(query_id));
-
             let query_status = query_result_status.status;
 
             let result_set = match &query_result_status.query_result {
@@ -754,6 +1144,9 @@ impl ExecutionService for CoreExecutionService {
             // This ensures all queries are traceable and auditable within a session, which enables
             // features like `last_query_id()` and enhances debugging and observability.
             history_store_ref.save_query_record(&history_record, result_set).await;
+            // remove query from running queries registry once its final record is saved,
+            // so that a `Running` record is only trusted while the query is registered
+            let running_query = queries_ref.remove(RunningQueryId::ByQueryId(query_id));
 
             // Send result to the result owner
             if tx.send(query_result_status).is_err() {
@@ -768,6 +1161,8 @@ impl ExecutionService for CoreExecutionService {
             // notify listeners that historical result is ready
             if let Ok(running_query) = running_query {
                 let _ = running_query.notify_query_finished(query_status);
+                // the profile is recorded once the result is delivered, not to delay it
+                save_query_profile(history_store_ref.as_ref(), &running_query).await;
             }
         }.instrument(alloc_span).instrument(child));
 
@@ -775,6 +1170,287 @@ impl ExecutionService for CoreExecutionService {
         Ok(AsyncQueryHandle { query_id, rx })
     }
 
+    #[tracing::instrument(
+        name = "ExecutionService::query_stream",
+        level = "debug",
+        skip(self),
+        fields(query_id, query_uuid, old_queries_count = self.queries.count()),
+        err
+    )]
+    async fn query_stream(
+        &self,
+        session_id: &str,
+        query: &str,
+        query_context: QueryContext,
+    ) -> Result<QueryResultStream> {
+        let user_session = self.get_session(session_id).await?;
+
+        if self.queries.count() >= self.config.max_concurrency_level {
+            return ex_error::ConcurrencyLimitSnafu.fail();
+        }
+
+        let mut history_record = self
+            .history_store
+            .new_query_record(query, query_context.worksheet_id);
+        history_record.user.clone_from(&user_session.user);
+        let query_id = history_record.query_id();
+
+        // Record the result as part of the current span.
+        tracing::Span::current()
+            .record("query_id", query_id.as_i64())
+            .record("query_uuid", query_id.as_uuid().to_string());
+
+        let running_query = if let Some(request_id) = &query_context.request_id {
+            RunningQuery::new(query_id).with_request_id(*request_id)
+        } else {
+            RunningQuery::new(query_id)
+        };
+        let mut query_obj = user_session.query(query, query_context.with_query_id(query_id));
+        let cancel_token = self.queries.add(running_query);
+
+        // Add query to history with status: Running
+        self.history_store
+            .save_query_record(&history_record, None)
+            .await;
+
+        let mut streamed_query = StreamedQuery {
+            history_record,
+            history_store: self.history_store.clone(),
+            queries: self.queries.clone(),
+            rows_limit: self.config.query_history_rows_limit,
+            rows: 0,
+            records: Vec::new(),
+            kept_rows: 0,
+            schema: Arc::new(ArrowSchema::empty()),
+            finished: false,
+        };
+
+        // The timeout covers the whole query, up to the end of its result
+        let deadline = Instant::now() + Duration::from_secs(self.config.query_timeout_secs);
+        let started = tokio::select! {
+            started = timeout_at(deadline, query_obj.execute_stream()) => {
+                match started {
+                    Ok(result) => result.map_err(|error| (error, QueryStatus::Failed)),
+                    Err(_) => Err((ex_error::QueryTimeoutSnafu.build(), QueryStatus::TimedOut)),
+                }
+            },
+            () = cancel_token.cancelled() => {
+                Err((ex_error::QueryCancelledSnafu { query_id }.build(), QueryStatus::Canceled))
+            }
+        };
+        let stream = match started {
+            Ok(stream) => stream,
+            Err((error, status)) => {
+                let error = ex_error::QueryExecutionSnafu { query_id }.into_error(error);
+                streamed_query.finish(status, Some(&error)).await;
+                return Err(error);
+            }
+        };
+        streamed_query.schema = stream.schema();
+
+        let schema = stream.schema();
+        let batches = async_stream::stream! {
+            let mut stream = stream;
+            loop {
+                // Cancellation and the timeout take precedence over ready batches
+                let (next, status) = tokio::select! {
+                    biased;
+                    () = cancel_token.cancelled() => (
+                        Some(ex_error::QueryCancelledSnafu { query_id }.fail()),
+                        QueryStatus::Canceled,
+                    ),
+                    () = sleep_until(deadline) => (
+                        Some(ex_error::QueryTimeoutSnafu.fail()),
+                        QueryStatus::TimedOut,
+                    ),
+                    next = stream.next() => (
+                        next.map(|batch| batch.context(ex_error::DataFusionSnafu)),
+                        QueryStatus::Failed,
+                    ),
+                };
+                match next {
+                    Some(Ok(batch)) => {
+                        streamed_query.add_batch(&batch);
+                        yield Ok(batch);
+                    }
+                    Some(Err(error)) => {
+                        let status = if cancel_token.is_cancelled() {
+                            QueryStatus::Canceled
+                        } else {
+                            status
+                        };
+                        let error = ex_error::QueryExecutionSnafu { query_id }.into_error(error);
+                        streamed_query.finish(status, Some(&error)).await;
+                        yield Err(DataFusionError::External(Box::new(error)));
+                        break;
+                    }
+                    None => {
+                        streamed_query.finish(QueryStatus::Successful, None).await;
+                        break;
+                    }
+                }
+            }
+        };
+        Ok(QueryResultStream {
+            query_id,
+            stream: Box::pin(RecordBatchStreamAdapter::new(schema, batches)),
+        })
+    }
+
+    fn spool_result(&self, result: QueryResultStream) -> QueryResultStream {
+        QueryResultStream {
+            query_id: result.query_id,
+            stream: spool::spooling_stream(
+                result.stream,
+                self.runtime_env.disk_manager.clone(),
+                self.config.result_spool_memory_mb * 1024 * 1024,
+            ),
+        }
+    }
+
+    #[tracing::instrument(
+        name = "ExecutionService::query_multi_statement",
+        level = "debug",
+        skip(self),
+        fields(query_id, query_uuid),
+        err
+    )]
+    async fn query_multi_statement(
+        &self,
+        session_id: &str,
+        query: &str,
+        statements: &[String],
+        query_context: QueryContext,
+    ) -> Result<MultiStatementResult> {
+        let handle = self
+            .submit_multi_statement(session_id, query, statements, query_context)
+            .await?;
+        let query_id = handle.query_id;
+
+        // Record the result as part of the current span.
+        tracing::Span::current()
+            .record("query_id", query_id.as_i64())
+            .record("query_uuid", query_id.as_uuid().to_string());
+
+        handle
+            .rx
+            .await
+            .context(ex_error::QueryResultRecvSnafu { query_id })?
+    }
+
+    #[tracing::instrument(
+        name = "ExecutionService::submit_multi_statement",
+        level = "debug",
+        skip(self),
+        fields(query_id, query_uuid),
+        err
+    )]
+    async fn submit_multi_statement(
+        &self,
+        session_id: &str,
+        query: &str,
+        statements: &[String],
+        query_context: QueryContext,
+    ) -> Result<MultiStatementHandle> {
+        // fail early on unknown sessions, before the parent query is recorded
+        let user_session = self.get_session(session_id).await?;
+
+        let mut history_record = self
+            .history_store
+            .new_query_record(query, query_context.worksheet_id);
+        history_record.user.clone_from(&user_session.user);
+        let query_id = history_record.query_id();
+
+        // Record the result as part of the current span.
+        tracing::Span::current()
+            .record("query_id", query_id.as_i64())
+            .record("query_uuid", query_id.as_uuid().to_string());
+
+        // Add query to history with status: Running
+        self.history_store
+            .save_query_record(&history_record, None)
+            .await;
+        self.queries.add(RunningQuery::new(query_id));
+
+        let (tx, rx) = oneshot::channel();
+        let service = self.clone();
+        let session_id = session_id.to_string();
+        let statements = statements.to_vec();
+        let child = tracing::info_span!("spawn_multi_statement_task");
+        tokio::spawn(
+            async move {
+                let result = service
+                    .execute_statements(history_record, &session_id, &statements, query_context)
+                    .await;
+                let status = if result.is_ok() {
+                    QueryStatus::Successful
+                } else {
+                    QueryStatus::Failed
+                };
+                service.finish_running_query(query_id, status);
+                let _ = tx.send(result);
+            }
+            .instrument(child),
+        );
+        Ok(MultiStatementHandle { query_id, rx })
+    }
+
+    #[tracing::instrument(
+        name = "ExecutionService::describe_query",
+        level = "debug",
+        skip(self),
+        fields(query_id, query_uuid),
+        err
+    )]
+    async fn describe_query(
+        &self,
+        session_id: &str,
+        query: &str,
+        query_context: QueryContext,
+    ) -> Result<QueryResult> {
+        let user_session = self.get_session(session_id).await?;
+        let mut history_record = self
+            .history_store
+            .new_query_record(query, query_context.worksheet_id);
+        history_record.user.clone_from(&user_session.user);
+        let query_id = history_record.query_id();
+
+        // Record the result as part of the current span.
+        tracing::Span::current()
+            .record("query_id", query_id.as_i64())
+            .record("query_uuid", query_id.as_uuid().to_string());
+
+        let query_obj = user_session.query(query, query_context.with_query_id(query_id));
+        match query_obj.plan().await {
+            Ok(plan) => {
+                let schema = Arc::new(plan.schema().as_arrow().clone());
+                let result = QueryResult::new(Vec::new(), schema, query_id);
+                history_record.finished_with_status(QueryStatus::Successful, 0);
+                self.history_store
+                    .save_query_record(
+                        &history_record,
+                        result
+                            .as_result_set(Some(self.config.query_history_rows_limit))
+                            .ok(),
+                    )
+                    .await;
+                Ok(result)
+            }
+            Err(err) => {
+                let err = ex_error::QueryExecutionSnafu { query_id }.into_error(err);
+                history_record.finished_with_error(&QueryResultError {
+                    status: QueryStatus::Failed,
+                    message: err.to_snowflake_error().to_string(),
+                    diagnostic_message: format!("{err:?}"),
+                });
+                self.history_store
+                    .save_query_record(&history_record, None)
+                    .await;
+                Err(err)
+            }
+        }
+    }
+
     #[tracing::instrument(
         name = "ExecutionService::upload_data_to_table",
         level = "debug",
@@ -893,6 +1569,112 @@ impl ExecutionService for CoreExecutionService {
     }
 }
 
+/// Records statistics of the operators of the plan the query has executed
+async fn save_query_profile(history_store: &dyn HistoryStore, running_query: &RunningQuery) {
+    if let Some(operator_stats) = running_query.operator_stats()
+        && let Err(err) = history_store
+            .add_query_profile(running_query.query_id, &operator_stats)
+            .await
+    {
+        tracing::error!(error = %err, "Failed to record query profile");
+    }
+}
+
+/// History bookkeeping of a query run by `ExecutionService::query_stream`,
+/// which keeps the first rows of the result for the query history
+struct StreamedQuery {
+    history_record: QueryRecord,
+    history_store: Arc<dyn HistoryStore>,
+    queries: Arc<RunningQueriesRegistry>,
+    rows_limit: usize,
+    rows: usize,
+    records: Vec<RecordBatch>,
+    kept_rows: usize,
+    schema: SchemaRef,
+    finished: bool,
+}
+
+impl StreamedQuery {
+    fn add_batch(&mut self, batch: &RecordBatch) {
+        self.rows += batch.num_rows();
+        if self.kept_rows < self.rows_limit {
+            let length = batch.num_rows().min(self.rows_limit - self.kept_rows);
+            self.records.push(batch.slice(0, length));
+            self.kept_rows += length;
+        }
+    }
+
+    /// Returns the future recording the query to the history, which removes
+    /// it from the running queries once its final record is saved
+    fn finish(
+        &mut self,
+        status: QueryStatus,
+        error: Option<&crate::Error>,
+    ) -> impl Future<Output = ()> + Send + use<> {
+        self.finished = true;
+        let query_id = self.history_record.query_id();
+
+        let result_set = if let Some(err) = error {
+            self.history_record.finished_with_error(&QueryResultError {
+                status,
+                message: err.to_snowflake_error().to_string(),
+                diagnostic_message: format!("{err:?}"),
+            });
+            None
+        } else {
+            // We are safe, no chance we will have more than i64::MAX rows
+            #[allow(clippy::unwrap_used)]
+            let result_count = i64::try_from(self.rows).unwrap();
+            self.history_record
+                .finished_with_status(status, result_count);
+            let query_result = QueryResult::new(
+                std::mem::take(&mut self.records),
+                self.schema.clone(),
+                query_id,
+            );
+            if let Ok(result_set) = query_result.as_result_set(Some(self.rows_limit)) {
+                Some(result_set)
+            } else {
+                tracing::error!("failed to convert query result {query_id} to result_set");
+                None
+            }
+        };
+
+        let history_record = self.history_record.clone();
+        let history_store = self.history_store.clone();
+        let queries = self.queries.clone();
+        async move {
+            history_store
+                .save_query_record(&history_record, result_set)
+                .await;
+            let running_query = queries.remove(RunningQueryId::ByQueryId(query_id));
+            if let Ok(running_query) = running_query {
+                // notify listeners that historical result is ready
+                let _ = running_query.notify_query_finished(status);
+                // the end of the result stream waits for the history record only
+                tokio::spawn(async move {
+                    save_query_profile(history_store.as_ref(), &running_query).await;
+                });
+            }
+        }
+    }
+}
+
+impl Drop for StreamedQuery {
+    fn drop(&mut self) {
+        // The result stream is dropped before its end
+        if !self.finished {
+            let query_id = self.history_record.query_id();
+            let error = ex_error::QueryExecutionSnafu { query_id }
+                .into_error(ex_error::QueryCancelledSnafu { query_id }.build());
+            let finish = self.finish(QueryStatus::Canceled, Some(&error));
+            if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+                runtime.spawn(finish);
+            }
+        }
+    }
+}
+
 //Test environment
 #[allow(clippy::expect_used)]
 pub async fn make_test_execution_svc() -> Arc<CoreExecutionService> {
@@ -3,14 +3,16 @@ use super::datafusion::functions::register_udfs;
 use super::datafusion::type_planner::CustomTypePlanner;
 use super::dedicated_executor::DedicatedExecutor;
 use super::error::{self as ex_error, Result};
+use crate::access_control::{QueryReader, SessionAccessControl};
 // TODO: We need to fix this after geodatafusion is updated to datafusion 47
 //use geodatafusion::udf::native::register_native as register_geo_native;
 use crate::datafusion::logical_analyzer::analyzer_rules;
 use crate::datafusion::logical_optimizer::split_ordered_aggregates::SplitOrderedAggregates;
 use crate::datafusion::physical_optimizer::physical_optimizer_rules;
 use crate::datafusion::query_planner::CustomQueryPlanner;
-use crate::models::QueryContext;
+use crate::models::{ClientInfo, QueryContext};
 use crate::query::UserQuery;
+use crate::result_cache::ResultCache;
 use crate::running_queries::RunningQueries;
 use crate::utils::Config;
 use core_history::HistoryStore;
@@ -25,6 +27,7 @@ use df_catalog::catalog_list::{DEFAULT_CATALOG, EmbucketCatalogList};
 use embucket_functions::expr_planner::CustomExprPlanner;
 use embucket_functions::register_udafs;
 use embucket_functions::session_params::{SessionParams, SessionProperty};
+use embucket_functions::table::access::SessionAccess;
 use embucket_functions::table::register_udtfs;
 use snafu::ResultExt;
 use std::collections::HashMap;
@@ -35,6 +38,9 @@ use std::thread::available_parallelism;
 use time::{Duration, OffsetDateTime};
 
 pub const SESSION_INACTIVITY_EXPIRATION_SECONDS: i64 = 5 * 60;
+/// How long a session kept alive by a client heartbeat or token renewal stays
+/// valid, so idle connections of a long job don't expire between heartbeats
+pub const SESSION_KEEP_ALIVE_EXPIRATION_SECONDS: i64 = 4 * 60 * 60;
 static MINIMUM_PARALLEL_OUTPUT_FILES: usize = 1;
 static PARALLEL_ROW_GROUP_RATIO: usize = 4;
 
@@ -49,15 +55,30 @@ pub struct UserSession {
     pub history_store: Arc<dyn HistoryStore>,
     // running_queries contains all the queries running across sessions
     pub running_queries: Arc<dyn RunningQueries>,
+    // results of previous queries, shared across sessions of the service
+    pub result_cache: Arc<ResultCache>,
     pub ctx: SessionContext,
     pub ident_normalizer: IdentNormalizer,
     pub executor: DedicatedExecutor,
     pub config: Arc<Config>,
     pub expiry: AtomicI64,
     pub session_params: Arc<SessionParams>,
+    /// Client which opened the session, if reported on login
+    pub client_info: Option<ClientInfo>,
+    /// User who opened the session. Sessions without a user only have the
+    /// privileges of `PUBLIC`.
+    pub user: Option<String>,
+    /// Role of the access token the session was opened with, `USE ROLE` and
+    /// new access tokens can't go past it
+    pub role_restriction: Option<String>,
+    /// Privileges of the session for the table functions reading the query history
+    pub access: Arc<SessionAccessControl>,
 }
 
 impl UserSession {
+    /// Sessions are opened by a user, `None` for sessions without one, which
+    /// can be restricted to the role of the access token they were opened with
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         metastore: Arc<dyn Metastore>,
         history_store: Arc<dyn HistoryStore>,
@@ -65,6 +86,8 @@ impl UserSession {
         config: Arc<Config>,
         catalog_list: Arc<EmbucketCatalogList>,
         runtime_env: Arc<RuntimeEnv>,
+        user: Option<String>,
+        role_restriction: Option<String>,
     ) -> Result<Self> {
         let sql_parser_dialect = config
             .sql_parser_dialect
@@ -122,7 +145,21 @@ impl UserSession {
         let mut ctx = SessionContext::new_with_state(state);
         register_udfs(&mut ctx, &session_params_arc).context(ex_error::RegisterUDFSnafu)?;
         register_udafs(&mut ctx).context(ex_error::RegisterUDAFSnafu)?;
-        register_udtfs(&ctx, history_store.clone());
+        let access = Arc::new(SessionAccessControl::new(
+            metastore.clone(),
+            history_store.clone(),
+            session_params_arc.clone(),
+            user.clone(),
+            role_restriction.clone(),
+        ));
+        let udtf_access: Arc<dyn SessionAccess> = access.clone();
+        register_udtfs(
+            &ctx,
+            history_store.clone(),
+            metastore.clone(),
+            &session_params_arc,
+            &udtf_access,
+        );
         register_json_udfs(&mut ctx).context(ex_error::RegisterUDFSnafu)?;
         //register_geo_native(&ctx);
         //register_geo_udfs(&ctx);
@@ -132,6 +169,10 @@ impl UserSession {
             metastore,
             history_store,
             running_queries,
+            result_cache: Arc::new(ResultCache::new(
+                config.result_cache_memory_mb * 1024 * 1024,
+                config.query_history_rows_limit,
+            )),
             ctx,
             ident_normalizer: IdentNormalizer::new(enable_ident_normalization),
             executor: DedicatedExecutor::builder().build(),
@@ -141,10 +182,26 @@ impl UserSession {
                     + Duration::seconds(SESSION_INACTIVITY_EXPIRATION_SECONDS),
             )),
             session_params: session_params_arc,
+            client_info: None,
+            user,
+            role_restriction,
+            access,
         };
         Ok(session)
     }
 
+    #[must_use]
+    pub fn with_client_info(mut self, client_info: Option<ClientInfo>) -> Self {
+        self.client_info = client_info;
+        self
+    }
+
+    #[must_use]
+    pub fn with_result_cache(mut self, result_cache: Arc<ResultCache>) -> Self {
+        self.result_cache = result_cache;
+        self
+    }
+
     pub fn query<S>(self: &Arc<Self>, query: S, query_context: QueryContext) -> UserQuery
     where
         S: Into<String>,
@@ -213,6 +270,12 @@ impl UserSession {
         }
         false
     }
+
+    /// The session as a reader of the query history, with its current role
+    #[must_use]
+    pub fn query_reader(&self) -> QueryReader {
+        self.access.query_reader()
+    }
 }
 
 #[must_use]
@@ -0,0 +1,302 @@
+//! Spooling of streamed query results: the result is read ahead of its consumer and,
+//! once the batches waiting for a slow consumer exceed a memory limit, further batches
+//! are written to temporary Arrow IPC files and read back when the consumer gets to them.
+use crate::error::{self as ex_error, Result};
+use datafusion::arrow::array::RecordBatch;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::error::ArrowError;
+use datafusion::arrow::ipc::reader::StreamReader;
+use datafusion::arrow::ipc::writer::StreamWriter;
+use datafusion::execution::DiskManager;
+use datafusion::execution::disk_manager::RefCountedTempFile;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion_common::DataFusionError;
+use futures::StreamExt;
+use snafu::ResultExt;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+
+/// Batches are spooled to files of about this size
+const SPOOL_FILE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Spool files or groups of in memory batches waiting for the consumer, reading the
+/// result ahead of the consumer pauses above that
+const SPOOL_CHANNEL_CAPACITY: usize = 64;
+
+/// Record batches written to a temporary Arrow IPC file, removed when dropped
+#[derive(Debug)]
+pub struct SpoolFile {
+    file: RefCountedTempFile,
+    schema: SchemaRef,
+    rows: usize,
+}
+
+impl SpoolFile {
+    pub async fn write(
+        disk_manager: &DiskManager,
+        schema: SchemaRef,
+        batches: &[RecordBatch],
+    ) -> Result<Self> {
+        let file = disk_manager
+            .create_tmp_file("Query result spool")
+            .context(ex_error::DataFusionSnafu)?;
+        let rows = batches.iter().map(RecordBatch::num_rows).sum();
+        let path = file.path().to_path_buf();
+        let batches = batches.to_vec();
+        let file_schema = schema.clone();
+        tokio::task::spawn_blocking(move || -> std::result::Result<(), ArrowError> {
+            let mut writer =
+                StreamWriter::try_new(BufWriter::new(File::create(path)?), &file_schema)?;
+            for batch in &batches {
+                writer.write(batch)?;
+            }
+            writer.finish()
+        })
+        .await
+        .context(ex_error::JoinHandleSnafu)?
+        .context(ex_error::ArrowSnafu)?;
+        Ok(Self { file, schema, rows })
+    }
+
+    pub async fn read(&self) -> Result<Vec<RecordBatch>> {
+        let path = self.file.path().to_path_buf();
+        tokio::task::spawn_blocking(move || -> std::result::Result<_, ArrowError> {
+            StreamReader::try_new(BufReader::new(File::open(path)?), None)?.collect()
+        })
+        .await
+        .context(ex_error::JoinHandleSnafu)?
+        .context(ex_error::ArrowSnafu)
+    }
+
+    #[must_use]
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    #[must_use]
+    pub const fn rows(&self) -> usize {
+        self.rows
+    }
+}
+
+enum Spooled {
+    Batches(Vec<RecordBatch>),
+    File(SpoolFile),
+}
+
+type SpoolSender = mpsc::Sender<datafusion_common::Result<Spooled>>;
+
+/// Reads `stream` ahead of the consumer of the returned stream. Batches the consumer
+/// hasn't taken yet are kept in memory up to `memory_limit` bytes, the ones above are
+/// spooled to disk. At most `SPOOL_CHANNEL_CAPACITY` spool files or groups of batches
+/// wait for the consumer, reading `stream` waits for the consumer above that.
+/// Dropping the returned stream stops reading `stream`.
+#[must_use]
+pub fn spooling_stream(
+    mut stream: SendableRecordBatchStream,
+    disk_manager: Arc<DiskManager>,
+    memory_limit: usize,
+) -> SendableRecordBatchStream {
+    let schema = stream.schema();
+    let (tx, mut rx): (SpoolSender, _) = mpsc::channel(SPOOL_CHANNEL_CAPACITY);
+    // Size of the batches sent to the consumer and not taken yet
+    let in_memory = Arc::new(AtomicUsize::new(0));
+
+    let producer_schema = schema.clone();
+    let producer_in_memory = in_memory.clone();
+    tokio::spawn(async move {
+        let fits_in_memory =
+            |bytes: usize| producer_in_memory.load(Ordering::Relaxed) + bytes <= memory_limit;
+        let mut pending: Vec<RecordBatch> = Vec::new();
+        let mut pending_bytes = 0;
+        while let Some(batch) = stream.next().await {
+            let batch = match batch {
+                Ok(batch) => batch,
+                Err(error) => {
+                    let _ = tx.send(Err(error)).await;
+                    return;
+                }
+            };
+            pending_bytes += batch.get_array_memory_size();
+            pending.push(batch);
+
+            if fits_in_memory(pending_bytes) {
+                match tx.try_reserve() {
+                    Ok(permit) => {
+                        producer_in_memory.fetch_add(pending_bytes, Ordering::Relaxed);
+                        permit.send(Ok(Spooled::Batches(std::mem::take(&mut pending))));
+                        pending_bytes = 0;
+                        continue;
+                    }
+                    // The consumer is gone
+                    Err(TrySendError::Closed(())) => return,
+                    // Too much is waiting for the consumer, spool the batches
+                    Err(TrySendError::Full(())) => {}
+                }
+            }
+            if pending_bytes >= SPOOL_FILE_BYTES {
+                let batches = std::mem::take(&mut pending);
+                if !spool(
+                    &tx,
+                    &disk_manager,
+                    &producer_schema,
+                    batches,
+                    &producer_in_memory,
+                )
+                .await
+                {
+                    return;
+                }
+                pending_bytes = 0;
+            }
+        }
+        if pending.is_empty() {
+            return;
+        }
+        if fits_in_memory(pending_bytes) {
+            producer_in_memory.fetch_add(pending_bytes, Ordering::Relaxed);
+            let _ = tx.send(Ok(Spooled::Batches(pending))).await;
+        } else {
+            spool(
+                &tx,
+                &disk_manager,
+                &producer_schema,
+                pending,
+                &producer_in_memory,
+            )
+            .await;
+        }
+    });
+
+    let batches = async_stream::stream! {
+        while let Some(spooled) = rx.recv().await {
+            match spooled {
+                Ok(Spooled::Batches(batches)) => {
+                    let bytes = batches.iter().map(RecordBatch::get_array_memory_size).sum();
+                    in_memory.fetch_sub(bytes, Ordering::Relaxed);
+                    for batch in batches {
+                        yield Ok(batch);
+                    }
+                }
+                Ok(Spooled::File(file)) => match file.read().await {
+                    Ok(batches) => {
+                        for batch in batches {
+                            yield Ok(batch);
+                        }
+                    }
+                    Err(error) => {
+                        yield Err(DataFusionError::External(Box::new(error)));
+                        break;
+                    }
+                },
+                Err(error) => {
+                    yield Err(error);
+                    break;
+                }
+            }
+        }
+    };
+    Box::pin(RecordBatchStreamAdapter::new(schema, batches))
+}
+
+/// Sends batches to the consumer through a spool file, waiting while the channel is
+/// full. Without a usable disk (e.g. disabled disk manager) the batches are sent in
+/// memory. Returns `false` once the consumer is gone.
+async fn spool(
+    tx: &SpoolSender,
+    disk_manager: &DiskManager,
+    schema: &SchemaRef,
+    batches: Vec<RecordBatch>,
+    in_memory: &AtomicUsize,
+) -> bool {
+    match SpoolFile::write(disk_manager, schema.clone(), &batches).await {
+        Ok(file) => tx.send(Ok(Spooled::File(file))).await.is_ok(),
+        Err(error) => {
+            tracing::warn!("Failed to spool query result: {error}");
+            let bytes = batches.iter().map(RecordBatch::get_array_memory_size).sum();
+            in_memory.fetch_add(bytes, Ordering::Relaxed);
+            tx.send(Ok(Spooled::Batches(batches))).await.is_ok()
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::Int32Array;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::execution::disk_manager::DiskManagerMode;
+    use futures::TryStreamExt;
+
+    fn batches(count: i32) -> (SchemaRef, Vec<RecordBatch>) {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batches = (0..count)
+            .map(|index| {
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(Int32Array::from(vec![index; 1000]))],
+                )
+                .unwrap()
+            })
+            .collect();
+        (schema, batches)
+    }
+
+    #[tokio::test]
+    async fn test_spool_file() {
+        let disk_manager = DiskManager::builder().build().unwrap();
+        let (schema, batches) = batches(3);
+        let file = SpoolFile::write(&disk_manager, schema, &batches)
+            .await
+            .unwrap();
+        assert_eq!(file.rows(), 3000);
+        assert_eq!(file.read().await.unwrap(), batches);
+    }
+
+    #[tokio::test]
+    async fn test_spooling_stream_keeps_order() {
+        let disk_manager = Arc::new(DiskManager::builder().build().unwrap());
+        let (schema, batches) = batches(50);
+        for memory_limit in [0, 10_000, usize::MAX] {
+            let stream = Box::pin(RecordBatchStreamAdapter::new(
+                schema.clone(),
+                futures::stream::iter(batches.clone().into_iter().map(Ok)),
+            ));
+            let spooled = spooling_stream(stream, disk_manager.clone(), memory_limit);
+            let result: Vec<RecordBatch> = spooled.try_collect().await.unwrap();
+            assert_eq!(result, batches);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_spooling_stream_waits_for_consumer() {
+        // Without a disk everything waiting for the consumer is kept in memory
+        let disk_manager = Arc::new(
+            DiskManager::builder()
+                .with_mode(DiskManagerMode::Disabled)
+                .build()
+                .unwrap(),
+        );
+        let (schema, batches) = batches(10_000);
+        let read = Arc::new(AtomicUsize::new(0));
+        let source_read = read.clone();
+        let stream = Box::pin(RecordBatchStreamAdapter::new(
+            schema,
+            futures::stream::iter(batches).map(move |batch| {
+                source_read.fetch_add(1, Ordering::Relaxed);
+                Ok(batch)
+            }),
+        ));
+        let mut spooled = spooling_stream(stream, disk_manager, usize::MAX);
+        assert!(spooled.next().await.is_some());
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(read.load(Ordering::Relaxed) < 10_000);
+        assert_eq!(spooled.count().await, 9_999);
+    }
+}
@@ -0,0 +1,630 @@
+//! Internal stage statements.
+//!
+//! `DROP STAGE`, `SHOW STAGES` and the file staging commands `PUT`, `GET`,
+//! `LIST` and `REMOVE` are recognized here, before the regular parsing step, like
+//! `FILE FORMAT` statements, and executed by `UserQuery::stage_query`.
+//!
+//! Stage locations (`@db.sch.stage/path/`) and local files (`file:///tmp/*.csv`)
+//! don't tokenize as SQL, so the file staging commands are split into words instead.
+use crate::error::{self as ex_error, Result};
+use crate::file_format::is_word;
+use core_metastore::StageIdent;
+use datafusion::sql::sqlparser::ast::{Ident, ObjectName};
+use datafusion::sql::sqlparser::dialect::{Dialect, SnowflakeDialect, dialect_from_str};
+use datafusion::sql::sqlparser::keywords::Keyword;
+use datafusion::sql::sqlparser::parser::{Parser, ParserError};
+use datafusion::sql::sqlparser::tokenizer::Token;
+use object_store::path::Path;
+use object_store::{ObjectMeta, ObjectStore};
+use snafu::ResultExt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+const LOCAL_FILE_PREFIX: &str = "file://";
+/// Default number of files the client transfers in parallel
+pub const DEFAULT_PARALLEL: u32 = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StageStatement {
+    Drop {
+        name: ObjectName,
+        if_exists: bool,
+    },
+    Show {
+        like: Option<String>,
+    },
+    /// `PUT file://<path> @<stage>[/<path>] [options]`
+    Put {
+        source: String,
+        location: StageLocation,
+        options: PutOptions,
+    },
+    /// `GET @<stage>[/<path>] file://<directory> [PARALLEL = n] [PATTERN = '<regex>']`
+    Get {
+        location: StageLocation,
+        target: String,
+        parallel: u32,
+        pattern: Option<String>,
+    },
+    /// `LIST @<stage>[/<path>] [PATTERN = '<regex>']`
+    List {
+        location: StageLocation,
+        pattern: Option<String>,
+    },
+    /// `REMOVE @<stage>[/<path>] [PATTERN = '<regex>']`
+    Remove {
+        location: StageLocation,
+        pattern: Option<String>,
+    },
+}
+
+/// Named stage and the path within it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageLocation {
+    pub name: ObjectName,
+    /// Path relative to the stage, without leading and trailing slashes
+    pub path: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PutOptions {
+    pub auto_compress: bool,
+    pub source_compression: SourceCompression,
+    pub overwrite: bool,
+    pub parallel: u32,
+}
+
+impl Default for PutOptions {
+    fn default() -> Self {
+        Self {
+            auto_compress: true,
+            source_compression: SourceCompression::AutoDetect,
+            overwrite: false,
+            parallel: DEFAULT_PARALLEL,
+        }
+    }
+}
+
+/// `SOURCE_COMPRESSION` of the files uploaded with `PUT`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, strum::EnumString, strum::Display)]
+#[strum(ascii_case_insensitive, serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum SourceCompression {
+    #[default]
+    AutoDetect,
+    Gzip,
+    Bz2,
+    Brotli,
+    Zstd,
+    Deflate,
+    RawDeflate,
+    None,
+}
+
+impl SourceCompression {
+    /// Compression of a file, told by its leading bytes. Brotli and raw deflate
+    /// streams have no signature, they are only told by the file extension.
+    #[must_use]
+    pub fn detect(name: &str, data: &[u8]) -> Self {
+        match data {
+            [0x1f, 0x8b, ..] => Self::Gzip,
+            [b'B', b'Z', b'h', ..] => Self::Bz2,
+            [0x28, 0xb5, 0x2f, 0xfd, ..] => Self::Zstd,
+            [0x78, 0x01 | 0x5e | 0x9c | 0xda, ..] => Self::Deflate,
+            _ => Self::from_extension(name),
+        }
+    }
+
+    #[must_use]
+    pub fn from_extension(name: &str) -> Self {
+        let extension = name
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("gz") => Self::Gzip,
+            Some("bz2") => Self::Bz2,
+            Some("br") => Self::Brotli,
+            Some("zst") => Self::Zstd,
+            Some("deflate") => Self::Deflate,
+            Some("raw_deflate") => Self::RawDeflate,
+            _ => Self::None,
+        }
+    }
+}
+
+/// Files listed from an internal stage
+#[derive(Debug)]
+pub struct StageFiles {
+    pub stage: StageIdent,
+    pub object_store: Arc<dyn ObjectStore>,
+    /// Path of the stage root on its volume
+    pub root: Path,
+    pub files: Vec<ObjectMeta>,
+}
+
+impl StageFiles {
+    /// Name of a file relative to the stage root
+    #[must_use]
+    pub fn relative_name(&self, file: &ObjectMeta) -> String {
+        let location = file.location.as_ref();
+        location
+            .strip_prefix(self.root.as_ref())
+            .unwrap_or(location)
+            .trim_start_matches('/')
+            .to_string()
+    }
+
+    /// Name of a file as `LIST` shows it, prefixed with the stage name
+    #[must_use]
+    pub fn listed_name(&self, file: &ObjectMeta) -> String {
+        format!(
+            "{}/{}",
+            self.stage.name.to_lowercase(),
+            self.relative_name(file)
+        )
+    }
+}
+
+/// Recognizes stage statements. Returns `Ok(None)` for any other SQL, so
+/// it can be handed over to the regular parser unchanged.
+pub fn parse_stage_statement(sql: &str, dialect: &str) -> Result<Option<StageStatement>> {
+    match first_word(sql).as_str() {
+        "PUT" | "GET" | "LIST" | "LS" | "REMOVE" | "RM" => parse_file_staging_command(sql)
+            .map(Some)
+            .context(ex_error::SqlParserSnafu),
+        "DROP" | "SHOW" => parse_stage_ddl(sql, dialect),
+        _ => Ok(None),
+    }
+}
+
+/// Whether the SQL is a `PUT` or `GET`, which respond with file transfer
+/// instructions instead of rows
+#[must_use]
+pub fn is_file_transfer_statement(sql: &str) -> bool {
+    matches!(first_word(sql).as_str(), "PUT" | "GET")
+}
+
+fn first_word(sql: &str) -> String {
+    sql.trim_start()
+        .split(|c: char| c.is_whitespace() || c == ';')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_uppercase()
+}
+
+fn parse_stage_ddl(sql: &str, dialect: &str) -> Result<Option<StageStatement>> {
+    let dialect: Box<dyn Dialect> =
+        dialect_from_str(dialect).unwrap_or_else(|| Box::new(SnowflakeDialect {}));
+    let Ok(mut parser) = Parser::new(dialect.as_ref()).try_with_sql(sql) else {
+        return Ok(None);
+    };
+
+    let statement = if parser.parse_keyword(Keyword::DROP) {
+        if !is_word(&parser.peek_token().token, "STAGE") {
+            return Ok(None);
+        }
+        parser.next_token();
+        let if_exists = parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+        let name = parser
+            .parse_object_name(false)
+            .context(ex_error::SqlParserSnafu)?;
+        StageStatement::Drop { name, if_exists }
+    } else if parser.parse_keyword(Keyword::SHOW) {
+        if !is_word(&parser.peek_token().token, "STAGES") {
+            return Ok(None);
+        }
+        parser.next_token();
+        let like = if parser.parse_keyword(Keyword::LIKE) {
+            Some(
+                parser
+                    .parse_literal_string()
+                    .context(ex_error::SqlParserSnafu)?,
+            )
+        } else {
+            None
+        };
+        StageStatement::Show { like }
+    } else {
+        return Ok(None);
+    };
+
+    let _ = parser.consume_token(&Token::SemiColon);
+    if parser.peek_token().token != Token::EOF {
+        return parser
+            .expected("end of statement", parser.peek_token())
+            .context(ex_error::SqlParserSnafu);
+    }
+    Ok(Some(statement))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Word {
+    Bare(String),
+    Quoted(String),
+    Eq,
+}
+
+impl Word {
+    fn value(&self) -> &str {
+        match self {
+            Self::Bare(value) | Self::Quoted(value) => value,
+            Self::Eq => "=",
+        }
+    }
+}
+
+/// Splits a file staging command into words: whitespace separated, `=` on its own,
+/// single quoted strings (with `''` escapes) kept whole
+fn split_words(sql: &str) -> std::result::Result<Vec<Word>, ParserError> {
+    let mut words = Vec::new();
+    let mut chars = sql.trim().trim_end_matches(';').chars().peekable();
+    let mut current = String::new();
+    let flush = |current: &mut String, words: &mut Vec<Word>| {
+        if !current.is_empty() {
+            words.push(Word::Bare(std::mem::take(current)));
+        }
+    };
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\'' => {
+                flush(&mut current, &mut words);
+                let mut quoted = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\'') if chars.peek() == Some(&'\'') => {
+                            chars.next();
+                            quoted.push('\'');
+                        }
+                        Some('\'') => break,
+                        Some(ch) => quoted.push(ch),
+                        None => {
+                            return Err(ParserError::ParserError(
+                                "Unterminated string literal".to_string(),
+                            ));
+                        }
+                    }
+                }
+                words.push(Word::Quoted(quoted));
+            }
+            '=' => {
+                flush(&mut current, &mut words);
+                words.push(Word::Eq);
+            }
+            ch if ch.is_whitespace() => flush(&mut current, &mut words),
+            ch => current.push(ch),
+        }
+    }
+    flush(&mut current, &mut words);
+    Ok(words)
+}
+
+fn parse_file_staging_command(sql: &str) -> std::result::Result<StageStatement, ParserError> {
+    let words = split_words(sql)?;
+    let mut words = words.iter();
+    let command = words
+        .next()
+        .map(|word| word.value().to_ascii_uppercase())
+        .unwrap_or_default();
+    let mut next = |expected: &str| {
+        words
+            .next()
+            .cloned()
+            .ok_or_else(|| ParserError::ParserError(format!("Expected {expected}")))
+    };
+
+    let statement = match command.as_str() {
+        "PUT" => {
+            let source = local_path(&next("local file")?)?;
+            let location = parse_stage_location(next("stage location")?.value())?;
+            let mut options = PutOptions::default();
+            for (name, value) in parse_options(&mut words)? {
+                match name.as_str() {
+                    "AUTO_COMPRESS" => options.auto_compress = parse_bool(&name, &value)?,
+                    "OVERWRITE" => options.overwrite = parse_bool(&name, &value)?,
+                    "PARALLEL" => options.parallel = parse_parallel(&value)?,
+                    "SOURCE_COMPRESSION" => {
+                        options.source_compression = SourceCompression::from_str(&value)
+                            .map_err(|_| invalid_option(&name, &value))?;
+                    }
+                    _ => return Err(unknown_option(&name)),
+                }
+            }
+            StageStatement::Put {
+                source,
+                location,
+                options,
+            }
+        }
+        "GET" => {
+            let location = parse_stage_location(next("stage location")?.value())?;
+            let target = local_path(&next("local directory")?)?;
+            let mut parallel = DEFAULT_PARALLEL;
+            let mut pattern = None;
+            for (name, value) in parse_options(&mut words)? {
+                match name.as_str() {
+                    "PARALLEL" => parallel = parse_parallel(&value)?,
+                    "PATTERN" => pattern = Some(value),
+                    _ => return Err(unknown_option(&name)),
+                }
+            }
+            StageStatement::Get {
+                location,
+                target,
+                parallel,
+                pattern,
+            }
+        }
+        _ => {
+            let location = parse_stage_location(next("stage location")?.value())?;
+            let mut pattern = None;
+            for (name, value) in parse_options(&mut words)? {
+                match name.as_str() {
+                    "PATTERN" => pattern = Some(value),
+                    _ => return Err(unknown_option(&name)),
+                }
+            }
+            if matches!(command.as_str(), "LIST" | "LS") {
+                StageStatement::List { location, pattern }
+            } else {
+                StageStatement::Remove { location, pattern }
+            }
+        }
+    };
+    Ok(statement)
+}
+
+fn parse_options<'a>(
+    words: &mut impl Iterator<Item = &'a Word>,
+) -> std::result::Result<Vec<(String, String)>, ParserError> {
+    let mut options = Vec::new();
+    while let Some(name) = words.next() {
+        let Word::Bare(name) = name else {
+            return Err(ParserError::ParserError(format!(
+                "Expected option, found '{}'",
+                name.value()
+            )));
+        };
+        if words.next() != Some(&Word::Eq) {
+            return Err(ParserError::ParserError(format!(
+                "Expected '=' after {name}"
+            )));
+        }
+        let value = words
+            .next()
+            .ok_or_else(|| ParserError::ParserError(format!("Expected value for {name}")))?;
+        options.push((name.to_ascii_uppercase(), value.value().to_string()));
+    }
+    Ok(options)
+}
+
+fn local_path(word: &Word) -> std::result::Result<String, ParserError> {
+    word.value()
+        .strip_prefix(LOCAL_FILE_PREFIX)
+        .filter(|path| !path.is_empty())
+        .map(ToString::to_string)
+        .ok_or_else(|| {
+            ParserError::ParserError(format!(
+                "Expected a local file starting with {LOCAL_FILE_PREFIX}, found '{}'",
+                word.value()
+            ))
+        })
+}
+
+/// Parses `@[db.][schema.]stage[/path]`. User (`@~`) and table (`@%table`) stages are
+/// rejected, only named stages are supported.
+pub fn parse_stage_location(location: &str) -> std::result::Result<StageLocation, ParserError> {
+    let Some(reference) = location.strip_prefix('@') else {
+        return Err(ParserError::ParserError(format!(
+            "Expected a stage location starting with @, found '{location}'"
+        )));
+    };
+    if reference.starts_with('~') || reference.starts_with('%') {
+        return Err(ParserError::ParserError(format!(
+            "Unsupported stage location '{location}': only named stages are supported"
+        )));
+    }
+
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut was_quoted = false;
+    let mut path = "";
+    for (index, ch) in reference.char_indices() {
+        match ch {
+            '"' => {
+                quoted = !quoted;
+                was_quoted = true;
+            }
+            '.' if !quoted => {
+                parts.push(stage_name_part(std::mem::take(&mut current), was_quoted));
+                was_quoted = false;
+            }
+            '/' if !quoted => {
+                path = &reference[index..];
+                break;
+            }
+            ch => current.push(ch),
+        }
+    }
+    parts.push(stage_name_part(current, was_quoted));
+    if quoted || parts.iter().any(|part| part.value.is_empty()) || parts.len() > 3 {
+        return Err(ParserError::ParserError(format!(
+            "Invalid stage location '{location}'"
+        )));
+    }
+    // Paths stay within the stage, relative segments would reach other stages or tables
+    let path = path.trim_matches('/');
+    if !path.is_empty()
+        && path
+            .split('/')
+            .any(|segment| matches!(segment, "" | "." | ".."))
+    {
+        return Err(ParserError::ParserError(format!(
+            "Invalid stage path in '{location}'"
+        )));
+    }
+    Ok(StageLocation {
+        name: ObjectName::from(parts),
+        path: path.to_string(),
+    })
+}
+
+fn stage_name_part(value: String, quoted: bool) -> Ident {
+    if quoted {
+        Ident::with_quote('"', value)
+    } else {
+        Ident::new(value)
+    }
+}
+
+fn parse_bool(name: &str, value: &str) -> std::result::Result<bool, ParserError> {
+    match value.to_ascii_uppercase().as_str() {
+        "TRUE" => Ok(true),
+        "FALSE" => Ok(false),
+        _ => Err(invalid_option(name, value)),
+    }
+}
+
+fn parse_parallel(value: &str) -> std::result::Result<u32, ParserError> {
+    value
+        .parse::<u32>()
+        .ok()
+        .filter(|parallel| (1..=99).contains(parallel))
+        .ok_or_else(|| invalid_option("PARALLEL", value))
+}
+
+fn invalid_option(name: &str, value: &str) -> ParserError {
+    ParserError::ParserError(format!("Invalid value '{value}' for option {name}"))
+}
+
+fn unknown_option(name: &str) -> ParserError {
+    ParserError::ParserError(format!("Unknown option {name}"))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn parse(sql: &str) -> Option<StageStatement> {
+        parse_stage_statement(sql, "snowflake").unwrap()
+    }
+
+    #[test]
+    fn test_parse_put() {
+        let Some(StageStatement::Put {
+            source,
+            location,
+            options,
+        }) = parse(
+            "PUT 'file:///tmp/my data/*.csv' @db.sch.\"My Stage\"/daily/ \
+             AUTO_COMPRESS=FALSE SOURCE_COMPRESSION = gzip OVERWRITE = TRUE PARALLEL = 8;",
+        )
+        else {
+            panic!("expected PUT");
+        };
+        assert_eq!(source, "/tmp/my data/*.csv");
+        assert_eq!(location.name.to_string(), "db.sch.\"My Stage\"");
+        assert_eq!(location.path, "daily");
+        assert_eq!(
+            options,
+            PutOptions {
+                auto_compress: false,
+                source_compression: SourceCompression::Gzip,
+                overwrite: true,
+                parallel: 8,
+            }
+        );
+
+        let Some(StageStatement::Put { options, .. }) = parse("put file:///tmp/a.csv @s") else {
+            panic!("expected PUT");
+        };
+        assert_eq!(options, PutOptions::default());
+    }
+
+    #[test]
+    fn test_parse_get_list_remove() {
+        let Some(StageStatement::Get {
+            location,
+            target,
+            parallel,
+            pattern,
+        }) = parse("GET @s/out file:///tmp/out/ PATTERN = '.*[.]csv[.]gz'")
+        else {
+            panic!("expected GET");
+        };
+        assert_eq!(location.name.to_string(), "s");
+        assert_eq!(location.path, "out");
+        assert_eq!(target, "/tmp/out/");
+        assert_eq!(parallel, DEFAULT_PARALLEL);
+        assert_eq!(pattern.as_deref(), Some(".*[.]csv[.]gz"));
+
+        assert!(matches!(
+            parse("ls @sch.s"),
+            Some(StageStatement::List { pattern: None, .. })
+        ));
+        assert!(matches!(
+            parse("RM @s/old PATTERN='.*'"),
+            Some(StageStatement::Remove {
+                pattern: Some(_),
+                ..
+            })
+        ));
+        assert_eq!(
+            parse("DROP STAGE IF EXISTS s"),
+            Some(StageStatement::Drop {
+                name: ObjectName::from(vec![Ident::new("s")]),
+                if_exists: true,
+            })
+        );
+        assert_eq!(
+            parse("SHOW STAGES LIKE 'up%'"),
+            Some(StageStatement::Show {
+                like: Some("up%".to_string())
+            })
+        );
+        assert_eq!(parse("DROP TABLE s"), None);
+        assert_eq!(parse("SELECT 1"), None);
+
+        assert!(is_file_transfer_statement("  get @s file:///tmp/"));
+        assert!(!is_file_transfer_statement("LIST @s"));
+    }
+
+    #[test]
+    fn test_parse_invalid_stage_commands() {
+        for sql in [
+            "PUT /tmp/a.csv @s",
+            "PUT file:///tmp/a.csv @~",
+            "PUT file:///tmp/a.csv @%t",
+            "PUT file:///tmp/a.csv @s PARALLEL = 100",
+            "PUT file:///tmp/a.csv @s COMPRESSION = GZIP",
+            "GET @s",
+            "LIST s",
+            "LIST @s/..",
+            "LIST @s//a",
+            "REMOVE @s/../x",
+            "GET @s/./a file:///tmp/",
+        ] {
+            assert!(
+                parse_stage_statement(sql, "snowflake").is_err(),
+                "{sql} should fail"
+            );
+        }
+    }
+
+    #[test]
+    fn test_source_compression() {
+        assert_eq!(
+            SourceCompression::detect("a.csv", &[0x1f, 0x8b, 0x08]),
+            SourceCompression::Gzip
+        );
+        assert_eq!(
+            SourceCompression::detect("a.csv", b"a,b\n"),
+            SourceCompression::None
+        );
+        assert_eq!(
+            SourceCompression::detect("a.csv.br", b"\x0b\x02"),
+            SourceCompression::Brotli
+        );
+        assert_eq!(SourceCompression::AutoDetect.to_string(), "AUTO_DETECT");
+    }
+}
@@ -1,7 +1,7 @@
 #![allow(clippy::result_large_err)]
 #![allow(clippy::large_enum_variant)]
 use super::e2e_s3tables_aws::s3tables_client;
-use crate::models::QueryContext;
+use crate::models::{QueryContext, SessionOptions};
 use crate::service::{CoreExecutionService, ExecutionService};
 use crate::utils::Config;
 use aws_sdk_s3tables;
@@ -11,6 +11,7 @@ use core_metastore::Metastore;
 use core_metastore::RwObject;
 use core_metastore::SlateDBMetastore;
 use core_metastore::Volume as MetastoreVolume;
+use core_metastore::bootstrap_user;
 use core_metastore::error::UtilSlateDBSnafu;
 use core_metastore::models::volumes::AwsAccessKeyCredentials;
 use core_metastore::models::volumes::AwsCredentials;
@@ -68,6 +69,16 @@ pub const E2E_S3TABLESVOLUME_PREFIX: &str = "E2E_S3TABLESVOLUME_";
 pub const TEST_SESSION_ID1: &str = "test_session_id1";
 pub const TEST_SESSION_ID2: &str = "test_session_id2";
 pub const TEST_SESSION_ID3: &str = "test_session_id3";
+pub const TEST_USER: &str = "embucket";
+
+/// Options of the test sessions, opened for the admin [`TEST_USER`]
+#[must_use]
+pub fn test_session_options() -> SessionOptions {
+    SessionOptions {
+        user: Some(TEST_USER.to_string()),
+        ..SessionOptions::default()
+    }
+}
 
 #[derive(Clone)]
 pub struct VolumeConfig {
@@ -378,22 +389,25 @@ impl ExecutorWithObjectStore {
     }
 
     pub async fn create_sessions(&self) -> Result<(), Error> {
+        bootstrap_user(self.metastore.as_ref(), TEST_USER, TEST_USER)
+            .await
+            .context(TestMetastoreSnafu)?;
         self.executor
-            .create_session(TEST_SESSION_ID1)
+            .create_session_with_options(TEST_SESSION_ID1, test_session_options())
             .await
             .context(TestExecutionSnafu {
                 query: "create session TEST_SESSION_ID1",
             })?;
 
         self.executor
-            .create_session(TEST_SESSION_ID2)
+            .create_session_with_options(TEST_SESSION_ID2, test_session_options())
             .await
             .context(TestExecutionSnafu {
                 query: "create session TEST_SESSION_ID2",
             })?;
 
         self.executor
-            .create_session(TEST_SESSION_ID3)
+            .create_session_with_options(TEST_SESSION_ID3, test_session_options())
             .await
             .context(TestExecutionSnafu {
                 query: "create session TEST_SESSION_ID3",
@@ -11,7 +11,8 @@ use crate::tests::e2e::e2e_common::{
     MINIO_OBJECT_STORE_PREFIX, ObjectStoreType, ParallelTest, S3ObjectStore, TEST_SESSION_ID1,
     TEST_SESSION_ID2, TEST_SESSION_ID3, TestQuery, TestQueryCallback, TestVolumeType, VolumeConfig,
     copy_env_to_new_prefix, create_executor, create_executor_with_early_volumes_creation,
-    create_s3tables_client, exec_parallel_test_plan, s3_tables_volume, test_suffix,
+    create_s3tables_client, exec_parallel_test_plan, s3_tables_volume, test_session_options,
+    test_suffix,
 };
 use crate::tests::e2e::e2e_s3tables_aws::{
     delete_s3tables_bucket_table, delete_s3tables_bucket_table_policy,
@@ -210,7 +211,7 @@ async fn template_test_s3_store_single_executor_with_old_and_freshly_created_ses
     let newly_created_session = "newly_created_session";
     executor
         .executor
-        .create_session(newly_created_session)
+        .create_session_with_options(newly_created_session, test_session_options())
         .await
         .expect("Failed to create newly_created_session");
 
@@ -715,7 +716,7 @@ async fn test_e2e_file_store_s3_tables_volumes_deny_rw_create_table_inconsistenc
     // Here use freshly created sessions instead of precreated
     let session3 = "session3";
     exec.executor
-        .create_session(session3)
+        .create_session_with_options(session3, test_session_options())
         .await
         .expect("Failed to create session3");
 
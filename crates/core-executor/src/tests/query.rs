@@ -11,8 +11,8 @@ use core_history::{HistoryStore, QueryRecord};
 use core_metastore::Metastore;
 use core_metastore::SlateDBMetastore;
 use core_metastore::{
-    Database as MetastoreDatabase, Schema as MetastoreSchema, SchemaIdent as MetastoreSchemaIdent,
-    Volume as MetastoreVolume,
+    ACCOUNTADMIN_ROLE, Database as MetastoreDatabase, Schema as MetastoreSchema,
+    SchemaIdent as MetastoreSchemaIdent, Volume as MetastoreVolume, bootstrap_user,
 };
 use core_utils::Db;
 use datafusion::sql::parser::DFParser;
@@ -134,6 +134,10 @@ pub async fn create_df_session() -> Arc<UserSession> {
         )
         .await
         .expect("Failed to create schema");
+    // The queries of the tests run as an admin, tests of access control use the service
+    bootstrap_user(metastore.as_ref(), "embucket", "embucket")
+        .await
+        .expect("Failed to create user");
     let config = Arc::new(Config::default());
     let catalog_list = CoreExecutionService::catalog_list(metastore.clone(), history_store.clone())
         .await
@@ -148,9 +152,20 @@ pub async fn create_df_session() -> Arc<UserSession> {
             Arc::new(Config::default()),
             catalog_list,
             runtime_env,
+            Some("embucket".to_string()),
+            None,
         )
         .expect("Failed to create user session"),
     );
+    // Set without a session id, so the role isn't listed by the SHOW VARIABLES snapshots
+    let mut params = HashMap::new();
+    params.insert(
+        "role".to_string(),
+        SessionProperty::from_str_value("role".to_string(), ACCOUNTADMIN_ROLE.to_string(), None),
+    );
+    user_session
+        .set_session_variable(true, params)
+        .expect("Failed to set role");
 
     for query in TABLE_SETUP.split(';') {
         if !query.is_empty() {
@@ -344,31 +359,32 @@ test_query!(
 
 // https://docs.snowflake.com/en/sql-reference/sql/explain
 // https://datafusion.apache.org/user-guide/sql/explain.html
-// Datafusion has different output format.
+// Datafusion has different output format, `FORMAT indent` keeps it
+// since `EXPLAIN` alone is Snowflake's tabular plan.
 // Check session config ExplainOptions for the full list of options
 // logical_only_plan flag is used to only print logical plans
 // since physical plan contains dynamic files names
 test_query!(
     explain_select,
-    "EXPLAIN SELECT * FROM embucket.public.employee_table",
+    "EXPLAIN FORMAT indent SELECT * FROM embucket.public.employee_table",
     setup_queries = ["SET datafusion.explain.logical_plan_only = true"],
     snapshot_path = "session"
 );
 test_query!(
     explain_select_limit,
-    "EXPLAIN SELECT * FROM embucket.public.employee_table limit 1",
+    "EXPLAIN FORMAT indent SELECT * FROM embucket.public.employee_table limit 1",
     setup_queries = ["SET datafusion.explain.logical_plan_only = true"],
     snapshot_path = "session"
 );
 test_query!(
     explain_select_column,
-    "EXPLAIN SELECT last_name FROM embucket.public.employee_table limit 1",
+    "EXPLAIN FORMAT indent SELECT last_name FROM embucket.public.employee_table limit 1",
     setup_queries = ["SET datafusion.explain.logical_plan_only = true"],
     snapshot_path = "session"
 );
 test_query!(
     explain_select_missing_column,
-    "EXPLAIN SELECT missing FROM embucket.public.employee_table limit 1",
+    "EXPLAIN FORMAT indent SELECT missing FROM embucket.public.employee_table limit 1",
     setup_queries = ["SET datafusion.explain.logical_plan_only = true"],
     snapshot_path = "session"
 );
@@ -1,21 +1,30 @@
 use crate::Error;
-use crate::models::{QueryContext, QueryResult};
-use crate::running_queries::RunningQueryId;
+use crate::models::{
+    BindingValue, ClientInfo, QueryBinding, QueryContext, QueryResult, SessionOptions,
+};
+use crate::running_queries::{RunningQueries, RunningQueryId};
 use crate::service::{CoreExecutionService, ExecutionService};
+use crate::session::{
+    SESSION_INACTIVITY_EXPIRATION_SECONDS, SESSION_KEEP_ALIVE_EXPIRATION_SECONDS,
+};
 use crate::utils::Config;
-use core_history::QueryStatus;
 use core_history::SlateDBHistoryStore;
 use core_history::entities::worksheet::Worksheet;
 use core_history::{GetQueriesParams, HistoryStore};
+use core_history::{OperatorStats, QueryRecordId, QueryStatus};
 use core_metastore::Metastore;
 use core_metastore::SlateDBMetastore;
+use core_metastore::bootstrap_user;
 use core_metastore::models::table::TableIdent as MetastoreTableIdent;
 use core_metastore::{
     Database as MetastoreDatabase, Schema as MetastoreSchema, SchemaIdent as MetastoreSchemaIdent,
     Volume as MetastoreVolume,
 };
+use datafusion::scalar::ScalarValue;
 use datafusion::{arrow::csv::reader::Format, assert_batches_eq};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::Ordering;
 
 #[tokio::test]
 #[allow(clippy::expect_used)]
@@ -728,6 +737,267 @@ async fn test_submitted_query_abort_by_query_id() {
     assert_eq!(query_record.status, QueryStatus::Canceled);
 }
 
+#[tokio::test]
+#[allow(clippy::expect_used)]
+async fn test_submitted_query_progress() {
+    let metastore = Arc::new(SlateDBMetastore::new_in_memory().await);
+    let history_store = Arc::new(SlateDBHistoryStore::new_in_memory().await);
+    let execution_svc =
+        CoreExecutionService::new(metastore, history_store, Arc::new(Config::default()))
+            .await
+            .expect("Failed to create execution service");
+
+    let _session = execution_svc
+        .create_session("test_session_id")
+        .await
+        .expect("Failed to create session");
+
+    let query_handle = execution_svc
+        .submit_query(
+            "test_session_id",
+            "SELECT sleep(1) FROM (VALUES (1), (2), (3))",
+            QueryContext::default(),
+        )
+        .await
+        .expect("Failed to submit query");
+    let query_id = query_handle.query_id;
+
+    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+    let progress = execution_svc
+        .query_progress(query_id)
+        .expect("Failed to get progress of running query");
+    assert!(progress.current_operator.is_some());
+    assert_eq!(progress.files_total, 0);
+
+    execution_svc
+        .wait_submitted_query_result(query_handle)
+        .await
+        .expect("Failed to execute query");
+    let err = execution_svc
+        .query_progress(query_id)
+        .expect_err("Finished query has no progress");
+    assert!(matches!(err, Error::QueryIsntRunning { .. }));
+}
+
+/// Profiles are recorded once the result of the query is delivered
+#[allow(clippy::expect_used)]
+async fn query_profile(
+    history_store: &SlateDBHistoryStore,
+    query_id: QueryRecordId,
+) -> Vec<OperatorStats> {
+    for _ in 0..50 {
+        if let Ok(profile) = history_store.get_query_profile(query_id).await {
+            return profile;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    history_store
+        .get_query_profile(query_id)
+        .await
+        .expect("Failed to get query profile")
+}
+
+#[tokio::test]
+#[allow(clippy::expect_used, clippy::unwrap_used)]
+async fn test_query_operator_stats() {
+    let metastore = Arc::new(SlateDBMetastore::new_in_memory().await);
+    let history_store = Arc::new(SlateDBHistoryStore::new_in_memory().await);
+    let execution_svc = CoreExecutionService::new(
+        metastore,
+        history_store.clone(),
+        Arc::new(Config::default()),
+    )
+    .await
+    .expect("Failed to create execution service");
+
+    let _session = execution_svc
+        .create_session("test_session_id")
+        .await
+        .expect("Failed to create session");
+
+    let result = execution_svc
+        .query(
+            "test_session_id",
+            "SELECT a FROM (VALUES (1), (2), (3)) AS t(a) WHERE a > 1",
+            QueryContext::default(),
+        )
+        .await
+        .expect("Failed to execute query");
+    let profile = query_profile(&history_store, result.query_id).await;
+    assert!(profile.len() > 1);
+    assert_eq!(profile[0].operator_id, 0);
+    assert_eq!(profile[0].parent_operator_id, None);
+    assert_eq!(profile[0].output_rows, Some(2));
+    assert!(
+        profile[1..]
+            .iter()
+            .all(|stats| stats.parent_operator_id.is_some())
+    );
+
+    let stats = execution_svc
+        .query(
+            "test_session_id",
+            &format!(
+                "SELECT operator_id, operator_type FROM TABLE(GET_QUERY_OPERATOR_STATS('{}'))",
+                result.query_id
+            ),
+            QueryContext::default(),
+        )
+        .await
+        .expect("Failed to get query operator stats");
+    let rows: usize = stats.records.iter().map(|batch| batch.num_rows()).sum();
+    assert_eq!(rows, profile.len());
+    let operator_type =
+        ScalarValue::try_from_array(stats.records[0].column_by_name("OPERATOR_TYPE").unwrap(), 0)
+            .unwrap();
+    assert_eq!(
+        operator_type,
+        ScalarValue::Utf8(Some(profile[0].operator_type.clone()))
+    );
+
+    let explain = execution_svc
+        .query(
+            "test_session_id",
+            "EXPLAIN ANALYZE SELECT a FROM (VALUES (1), (2), (3)) AS t(a) WHERE a > 1",
+            QueryContext::default(),
+        )
+        .await
+        .expect("Failed to explain analyze query");
+    let rows: usize = explain.records.iter().map(|batch| batch.num_rows()).sum();
+    assert_eq!(rows, profile.len());
+    assert!(
+        explain.records[0]
+            .column_by_name("OPERATOR_STATISTICS")
+            .is_some()
+    );
+}
+
+#[tokio::test]
+#[allow(clippy::expect_used)]
+async fn test_query_result_cache() {
+    let metastore = Arc::new(SlateDBMetastore::new_in_memory().await);
+    let history_store = Arc::new(SlateDBHistoryStore::new_in_memory().await);
+    let execution_svc = CoreExecutionService::new(
+        metastore,
+        history_store.clone(),
+        Arc::new(Config::default().with_query_history_rows_limit(3)),
+    )
+    .await
+    .expect("Failed to create execution service");
+
+    let _session = execution_svc
+        .create_session("test_session_id")
+        .await
+        .expect("Failed to create session");
+    let svc = &execution_svc;
+    let query =
+        move |query: &'static str| svc.query("test_session_id", query, QueryContext::default());
+    let stream = move |query: &'static str| async move {
+        let mut result = svc
+            .query_stream("test_session_id", query, QueryContext::default())
+            .await
+            .expect("Failed to start query");
+        let mut rows = 0;
+        while let Some(batch) = result.next_batch().await {
+            rows += batch.expect("Failed to read result").num_rows();
+        }
+        (result.query_id, rows)
+    };
+    let rows = |result: &QueryResult| -> usize {
+        result.records.iter().map(|batch| batch.num_rows()).sum()
+    };
+    // A query served from the cache doesn't execute a plan, so it has no profile,
+    // which is recorded once the result is delivered
+    let history = &history_store;
+    let is_cached = move |query_id: QueryRecordId| async move {
+        for _ in 0..10 {
+            if history.get_query_profile(query_id).await.is_ok() {
+                return false;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        true
+    };
+
+    query("CREATE TABLE embucket.public.cached (a INT)")
+        .await
+        .expect("Failed to create table");
+    query("INSERT INTO embucket.public.cached VALUES (1), (2)")
+        .await
+        .expect("Failed to insert");
+    let sql = "SELECT a FROM embucket.public.cached ORDER BY a";
+
+    // Results are cached only when enabled
+    query(sql).await.expect("Failed to execute query");
+    let result = query(sql).await.expect("Failed to execute query");
+    assert!(!is_cached(result.query_id).await);
+
+    query("ALTER SESSION SET USE_CACHED_RESULT = TRUE")
+        .await
+        .expect("Failed to enable result cache");
+    let first = query(sql).await.expect("Failed to execute query");
+    let second = query(sql).await.expect("Failed to execute query");
+    assert_ne!(first.query_id, second.query_id);
+    assert_eq!(rows(&second), 2);
+    assert!(!is_cached(first.query_id).await);
+    assert!(is_cached(second.query_id).await);
+
+    // A commit to the table invalidates its cached results
+    query("INSERT INTO embucket.public.cached VALUES (3)")
+        .await
+        .expect("Failed to insert");
+    let result = query(sql).await.expect("Failed to execute query");
+    assert_eq!(rows(&result), 3);
+    assert!(!is_cached(result.query_id).await);
+    let result = query(sql).await.expect("Failed to execute query");
+    assert!(is_cached(result.query_id).await);
+
+    // Streamed results are cached once they are read to the end
+    let filtered = "SELECT a FROM embucket.public.cached WHERE a > 1";
+    let (query_id, _) = stream(filtered).await;
+    assert!(!is_cached(query_id).await);
+    let (query_id, streamed_rows) = stream(filtered).await;
+    assert_eq!(streamed_rows, 2);
+    assert!(is_cached(query_id).await);
+
+    // Results the history keeps partially are not cached
+    query("INSERT INTO embucket.public.cached VALUES (4)")
+        .await
+        .expect("Failed to insert");
+    query(sql).await.expect("Failed to execute query");
+    let result = query(sql).await.expect("Failed to execute query");
+    assert_eq!(rows(&result), 4);
+    assert!(!is_cached(result.query_id).await);
+
+    // Non-deterministic queries are not cached
+    let sql = "SELECT a, RANDOM() FROM embucket.public.cached";
+    query(sql).await.expect("Failed to execute query");
+    let result = query(sql).await.expect("Failed to execute query");
+    assert!(!is_cached(result.query_id).await);
+
+    // Results are cached per values bound to the query
+    let bound = move |value: &'static str| {
+        let binding = QueryBinding {
+            binding_type: "FIXED".to_string(),
+            value: BindingValue::Single(Some(value.to_string())),
+        };
+        svc.query(
+            "test_session_id",
+            "SELECT a FROM embucket.public.cached WHERE a = ?",
+            QueryContext::default().with_bindings(vec![binding]),
+        )
+    };
+    bound("2").await.expect("Failed to execute query");
+    let result = bound("2").await.expect("Failed to execute query");
+    assert!(is_cached(result.query_id).await);
+    let result = bound("1").await.expect("Failed to execute query");
+    assert!(!is_cached(result.query_id).await);
+    assert_batches_eq!(
+        &["+---+", "| a |", "+---+", "| 1 |", "+---+"],
+        &result.records
+    );
+}
+
 #[tokio::test]
 #[allow(clippy::expect_used)]
 async fn test_submitted_query_abort_by_request_id() {
@@ -837,3 +1107,889 @@ async fn test_submitted_query_ok() {
     assert_eq!(query_record.query_id(), query_id);
     assert_eq!(query_record.status, QueryStatus::Successful);
 }
+
+#[tokio::test]
+#[allow(clippy::expect_used)]
+async fn test_query_stream() {
+    let metastore = Arc::new(SlateDBMetastore::new_in_memory().await);
+    let history_store = Arc::new(SlateDBHistoryStore::new_in_memory().await);
+    // Spool everything the test isn't reading yet
+    let execution_svc = CoreExecutionService::new(
+        metastore,
+        history_store.clone(),
+        Arc::new(Config::default().with_result_spool_memory_mb(0)),
+    )
+    .await
+    .expect("Failed to create execution service");
+
+    let session = execution_svc
+        .create_session("test_session_id")
+        .await
+        .expect("Failed to create session");
+
+    let result = execution_svc
+        .query_stream(
+            "test_session_id",
+            "SELECT value FROM generate_series(1, 100000)",
+            QueryContext::default(),
+        )
+        .await
+        .expect("Failed to start query");
+    let mut result = execution_svc.spool_result(result);
+    assert_eq!(result.schema().fields().len(), 1);
+
+    let mut rows = 0;
+    while let Some(batch) = result.next_batch().await {
+        rows += batch.expect("Failed to read result").num_rows();
+    }
+    assert_eq!(rows, 100_000);
+
+    let query_record = history_store
+        .get_query(result.query_id)
+        .await
+        .expect("Failed to get query at history store");
+    assert_eq!(query_record.status, QueryStatus::Successful);
+    assert_eq!(query_record.result_count, 100_000);
+    assert!(
+        !session
+            .running_queries
+            .is_running(RunningQueryId::ByQueryId(result.query_id))
+    );
+
+    // Statements other than queries are streamed from their collected result
+    let mut result = execution_svc
+        .query_stream(
+            "test_session_id",
+            "CREATE TABLE embucket.public.streamed (a INT)",
+            QueryContext::default(),
+        )
+        .await
+        .expect("Failed to create table");
+    assert!(result.next_batch().await.is_some());
+}
+
+#[tokio::test]
+#[allow(clippy::expect_used)]
+async fn test_query_stream_timeout() {
+    let metastore = Arc::new(SlateDBMetastore::new_in_memory().await);
+    let history_store = Arc::new(SlateDBHistoryStore::new_in_memory().await);
+    let execution_svc = CoreExecutionService::new(
+        metastore,
+        history_store.clone(),
+        Arc::new(Config::default().with_query_timeout(1)),
+    )
+    .await
+    .expect("Failed to create execution service");
+
+    let _session = execution_svc
+        .create_session("test_session_id")
+        .await
+        .expect("Failed to create session");
+
+    let mut result = execution_svc
+        .query_stream(
+            "test_session_id",
+            "SELECT value FROM generate_series(1, 1000000)",
+            QueryContext::default(),
+        )
+        .await
+        .expect("Failed to start query");
+    assert!(matches!(result.next_batch().await, Some(Ok(_))));
+
+    // The timeout covers reading the result too
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    assert!(matches!(result.next_batch().await, Some(Err(_))));
+    let query_record = history_store
+        .get_query(result.query_id)
+        .await
+        .expect("Failed to get query at history store");
+    assert_eq!(query_record.status, QueryStatus::TimedOut);
+}
+
+#[tokio::test]
+#[allow(clippy::expect_used)]
+async fn test_query_stream_dropped() {
+    let metastore = Arc::new(SlateDBMetastore::new_in_memory().await);
+    let history_store = Arc::new(SlateDBHistoryStore::new_in_memory().await);
+    let execution_svc = CoreExecutionService::new(
+        metastore,
+        history_store.clone(),
+        Arc::new(Config::default()),
+    )
+    .await
+    .expect("Failed to create execution service");
+
+    let session = execution_svc
+        .create_session("test_session_id")
+        .await
+        .expect("Failed to create session");
+
+    let mut result = execution_svc
+        .query_stream(
+            "test_session_id",
+            "SELECT value FROM generate_series(1, 1000000)",
+            QueryContext::default(),
+        )
+        .await
+        .expect("Failed to start query");
+    let query_id = result.query_id;
+    let _ = result.next_batch().await;
+    assert!(
+        session
+            .running_queries
+            .is_running(RunningQueryId::ByQueryId(query_id))
+    );
+    drop(result);
+
+    // The dropped query is recorded in the background
+    let mut status = QueryStatus::Running;
+    for _ in 0..50 {
+        status = history_store
+            .get_query(query_id)
+            .await
+            .expect("Failed to get query at history store")
+            .status;
+        if status != QueryStatus::Running {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(status, QueryStatus::Canceled);
+    assert!(
+        !session
+            .running_queries
+            .is_running(RunningQueryId::ByQueryId(query_id))
+    );
+}
+
+#[tokio::test]
+#[allow(clippy::expect_used)]
+async fn test_query_multi_statement() {
+    let metastore = Arc::new(SlateDBMetastore::new_in_memory().await);
+    let history_store = Arc::new(SlateDBHistoryStore::new_in_memory().await);
+    let execution_svc = CoreExecutionService::new(
+        metastore,
+        history_store.clone(),
+        Arc::new(Config::default()),
+    )
+    .await
+    .expect("Failed to create execution service");
+
+    let _session = execution_svc
+        .create_session("test_session_id")
+        .await
+        .expect("Failed to create session");
+
+    let script = "SET x = 2; SELECT $x AS x";
+    let statements = ["SET x = 2".to_string(), "SELECT $x AS x".to_string()];
+    let result = execution_svc
+        .query_multi_statement(
+            "test_session_id",
+            script,
+            &statements,
+            QueryContext::default(),
+        )
+        .await
+        .expect("Failed to execute statements");
+    let statement_types = result
+        .statements
+        .iter()
+        .map(|statement| statement.statement_type_id)
+        .collect::<Vec<_>>();
+    assert_eq!(statement_types, [0x4000, 0x1000]);
+    assert_batches_eq!(
+        [
+            "+--------------------------------------------+",
+            "| multiple statement execution               |",
+            "+--------------------------------------------+",
+            "| Multiple statements executed successfully. |",
+            "+--------------------------------------------+",
+        ],
+        &result.result.records
+    );
+
+    // Statements run in the same session, one after another
+    let statement_result = history_store
+        .get_query_result(result.statements[1].result.query_id)
+        .await
+        .expect("Failed to get statement result");
+    assert_eq!(statement_result.rows.len(), 1);
+    let parent_record = history_store
+        .get_query(result.result.query_id)
+        .await
+        .expect("Failed to get parent query");
+    assert_eq!(parent_record.query, script);
+    assert_eq!(parent_record.status, QueryStatus::Successful);
+
+    // Execution stops at the first failing statement
+    let statements = [
+        "SELECT * FROM missing_table".to_string(),
+        "CREATE TABLE embucket.public.after_failure (a INT)".to_string(),
+    ];
+    execution_svc
+        .query_multi_statement(
+            "test_session_id",
+            &statements.join(";"),
+            &statements,
+            QueryContext::default(),
+        )
+        .await
+        .expect_err("Statement should fail");
+    execution_svc
+        .query(
+            "test_session_id",
+            "SELECT * FROM embucket.public.after_failure",
+            QueryContext::default(),
+        )
+        .await
+        .expect_err("Statement after the failed one shouldn't run");
+}
+
+#[tokio::test]
+#[allow(clippy::expect_used)]
+async fn test_submit_multi_statement() {
+    let metastore = Arc::new(SlateDBMetastore::new_in_memory().await);
+    let history_store = Arc::new(SlateDBHistoryStore::new_in_memory().await);
+    let execution_svc = CoreExecutionService::new(
+        metastore,
+        history_store.clone(),
+        Arc::new(Config::default().with_query_history_rows_limit(1)),
+    )
+    .await
+    .expect("Failed to create execution service");
+
+    let _session = execution_svc
+        .create_session("test_session_id")
+        .await
+        .expect("Failed to create session");
+
+    let statements = [
+        "SELECT 1".to_string(),
+        "SELECT * FROM VALUES (1), (2), (3)".to_string(),
+    ];
+    let handle = execution_svc
+        .submit_multi_statement(
+            "test_session_id",
+            &statements.join(";"),
+            &statements,
+            QueryContext::default(),
+        )
+        .await
+        .expect("Failed to submit statements");
+    let result = handle
+        .rx
+        .await
+        .expect("Failed to receive result")
+        .expect("Failed to execute statements");
+    assert_eq!(result.result.query_id, handle.query_id);
+
+    // Statement results are kept in full, unlike their history records
+    let statement_result = &result.statements[1].result;
+    assert_eq!(
+        statement_result
+            .records
+            .iter()
+            .map(datafusion::arrow::array::RecordBatch::num_rows)
+            .sum::<usize>(),
+        3
+    );
+    let parent_record = history_store
+        .get_query(handle.query_id)
+        .await
+        .expect("Failed to get parent query");
+    assert_eq!(parent_record.status, QueryStatus::Successful);
+}
+
+#[tokio::test]
+#[allow(clippy::expect_used)]
+async fn test_describe_query() {
+    let metastore = Arc::new(SlateDBMetastore::new_in_memory().await);
+    let history_store = Arc::new(SlateDBHistoryStore::new_in_memory().await);
+    let execution_svc = CoreExecutionService::new(
+        metastore,
+        history_store.clone(),
+        Arc::new(Config::default()),
+    )
+    .await
+    .expect("Failed to create execution service");
+
+    let _session = execution_svc
+        .create_session("test_session_id")
+        .await
+        .expect("Failed to create session");
+
+    let query = "SELECT 1 AS a, CAST(1.5 AS DECIMAL(10, 2)) AS b, ? AS c";
+    let result = execution_svc
+        .describe_query("test_session_id", query, QueryContext::default())
+        .await
+        .expect("Failed to describe query");
+    assert!(result.records.is_empty());
+    let column_info = result.column_info();
+    let names = column_info
+        .iter()
+        .map(|column| column.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["a", "b", "c"]);
+    assert_eq!(column_info[1].r#type, "fixed");
+    assert_eq!(column_info[1].precision, Some(10));
+    assert_eq!(column_info[1].scale, Some(2));
+
+    let query_record = history_store
+        .get_query(result.query_id)
+        .await
+        .expect("Failed to get query");
+    assert_eq!(query_record.query, query);
+    assert_eq!(query_record.status, QueryStatus::Successful);
+
+    execution_svc
+        .describe_query(
+            "test_session_id",
+            "SELECT * FROM missing_table",
+            QueryContext::default(),
+        )
+        .await
+        .expect_err("Describing a query of a missing table should fail");
+}
+
+#[tokio::test]
+#[allow(clippy::expect_used)]
+async fn test_create_session_with_options() {
+    let metastore = Arc::new(SlateDBMetastore::new_in_memory().await);
+    let history_store = Arc::new(SlateDBHistoryStore::new_in_memory().await);
+    let execution_svc =
+        CoreExecutionService::new(metastore, history_store, Arc::new(Config::default()))
+            .await
+            .expect("Failed to create execution service");
+
+    let client_info = ClientInfo {
+        application: "PythonConnector".to_string(),
+        version: "3.12.0".to_string(),
+        account_name: "test_account".to_string(),
+        environment: HashMap::from([("OS".to_string(), "Linux".to_string())]),
+    };
+    let options = SessionOptions {
+        params: HashMap::from([
+            (
+                "TIMEZONE".to_string(),
+                ScalarValue::Utf8(Some("America/Los_Angeles".to_string())),
+            ),
+            (
+                "QUERY_TAG".to_string(),
+                ScalarValue::Utf8(Some("etl".to_string())),
+            ),
+            (
+                "schema".to_string(),
+                ScalarValue::Utf8(Some("staging".to_string())),
+            ),
+        ]),
+        client_info: Some(client_info.clone()),
+        user: None,
+        role_restriction: None,
+    };
+    let session = execution_svc
+        .create_session_with_options("test_session_id", options)
+        .await
+        .expect("Failed to create session");
+
+    assert_eq!(session.client_info, Some(client_info));
+    assert_eq!(
+        session.get_session_variable("timezone").as_deref(),
+        Some("America/Los_Angeles")
+    );
+    assert_eq!(
+        session.get_session_variable("query_tag").as_deref(),
+        Some("etl")
+    );
+    assert_eq!(
+        session.get_session_variable("schema").as_deref(),
+        Some("staging")
+    );
+}
+
+#[tokio::test]
+#[allow(clippy::expect_used)]
+async fn test_user_statements() {
+    let metastore = Arc::new(SlateDBMetastore::new_in_memory().await);
+    let history_store = Arc::new(SlateDBHistoryStore::new_in_memory().await);
+    let execution_svc = CoreExecutionService::new(
+        metastore.clone(),
+        history_store,
+        Arc::new(Config::default()),
+    )
+    .await
+    .expect("Failed to create execution service");
+    bootstrap_user(metastore.as_ref(), "admin", "admin")
+        .await
+        .expect("Failed to bootstrap user");
+
+    // Managing users needs ACCOUNTADMIN, the default role of the bootstrapped user
+    let _session = execution_svc
+        .create_session_with_options(
+            "test_session_id",
+            SessionOptions {
+                user: Some("admin".to_string()),
+                ..SessionOptions::default()
+            },
+        )
+        .await
+        .expect("Failed to create session");
+    let svc = &execution_svc;
+    let run =
+        move |query: &'static str| svc.query("test_session_id", query, QueryContext::default());
+
+    run("CREATE USER alice PASSWORD = 'secret' DEFAULT_ROLE = analyst DEFAULT_NAMESPACE = 'embucket.public'")
+        .await
+        .expect("Failed to create user");
+    run("CREATE USER alice PASSWORD = 'other'")
+        .await
+        .expect_err("Creating an existing user should fail");
+    run("CREATE USER IF NOT EXISTS alice PASSWORD = 'other'")
+        .await
+        .expect("Failed to create user if not exists");
+
+    let user = metastore
+        .authenticate(&"alice".to_string(), "secret")
+        .await
+        .expect("Failed to authenticate user")
+        .expect("User not authenticated")
+        .user;
+    assert_eq!(user.default_role.as_deref(), Some("analyst"));
+    assert_eq!(user.default_database.as_deref(), Some("embucket"));
+    assert_eq!(user.default_schema.as_deref(), Some("public"));
+
+    run("ALTER USER alice SET DISABLED = TRUE")
+        .await
+        .expect("Failed to alter user");
+    assert!(
+        metastore
+            .authenticate(&"alice".to_string(), "secret")
+            .await
+            .expect("Failed to authenticate user")
+            .is_none()
+    );
+
+    let result = run("SHOW USERS LIKE 'ali%'")
+        .await
+        .expect("Failed to show users");
+    assert_eq!(
+        result
+            .records
+            .iter()
+            .map(datafusion::arrow::array::RecordBatch::num_rows)
+            .sum::<usize>(),
+        1
+    );
+
+    run("ALTER USER alice RENAME TO bob")
+        .await
+        .expect("Failed to rename user");
+    run("DROP USER bob").await.expect("Failed to drop user");
+    run("DROP USER bob")
+        .await
+        .expect_err("Dropping a missing user should fail");
+    run("DROP USER IF EXISTS bob")
+        .await
+        .expect("Failed to drop user if exists");
+    let users = metastore
+        .iter_users()
+        .collect()
+        .await
+        .expect("Failed to list users");
+    assert_eq!(
+        users
+            .iter()
+            .map(|user| user.data.name.as_str())
+            .collect::<Vec<_>>(),
+        ["admin"]
+    );
+}
+
+#[tokio::test]
+#[allow(clippy::expect_used)]
+async fn test_role_based_access_control() {
+    let metastore = Arc::new(SlateDBMetastore::new_in_memory().await);
+    let history_store = Arc::new(SlateDBHistoryStore::new_in_memory().await);
+    let execution_svc = CoreExecutionService::new(
+        metastore.clone(),
+        history_store,
+        Arc::new(Config::default()),
+    )
+    .await
+    .expect("Failed to create execution service");
+    bootstrap_user(metastore.as_ref(), "admin", "admin")
+        .await
+        .expect("Failed to bootstrap user");
+
+    let session_options = |user: &str, role: &str| SessionOptions {
+        params: HashMap::from([(
+            "role".to_string(),
+            ScalarValue::Utf8(Some(role.to_string())),
+        )]),
+        client_info: None,
+        user: Some(user.to_string()),
+        role_restriction: None,
+    };
+    execution_svc
+        .create_session_with_options("admin_session", session_options("admin", "ACCOUNTADMIN"))
+        .await
+        .expect("Failed to create session");
+    let svc = &execution_svc;
+    let admin =
+        move |query: &'static str| svc.query("admin_session", query, QueryContext::default());
+    let alice =
+        move |query: &'static str| svc.query("alice_session", query, QueryContext::default());
+
+    admin("CREATE TABLE embucket.public.t (a INT)")
+        .await
+        .expect("Failed to create table");
+    admin("CREATE ROLE analyst")
+        .await
+        .expect("Failed to create role");
+    admin("CREATE USER alice PASSWORD = 'secret'")
+        .await
+        .expect("Failed to create user");
+    admin("GRANT ROLE analyst TO USER alice")
+        .await
+        .expect("Failed to grant role");
+
+    execution_svc
+        .create_session_with_options("alice_session", session_options("alice", "analyst"))
+        .await
+        .expect("Failed to create session");
+    alice("SELECT * FROM embucket.public.t")
+        .await
+        .expect_err("Selecting without privileges should fail");
+    alice("CREATE ROLE reader")
+        .await
+        .expect_err("Creating a role without ACCOUNTADMIN should fail");
+    alice("USE ROLE ACCOUNTADMIN")
+        .await
+        .expect_err("Using a role not granted to the user should fail");
+
+    // Queries of other users are only visible to admins
+    let admin_query = admin("SELECT 1").await.expect("Failed to run query");
+    svc.query(
+        "alice_session",
+        &format!(
+            "SELECT * FROM TABLE(GET_QUERY_OPERATOR_STATS('{}'))",
+            admin_query.query_id
+        ),
+        QueryContext::default(),
+    )
+    .await
+    .expect_err("Getting the operator stats of another user's query should fail");
+    let alice_session = execution_svc
+        .get_session("alice_session")
+        .await
+        .expect("Failed to get session");
+    execution_svc
+        .wait_historical_query_result(&alice_session.query_reader(), admin_query.query_id)
+        .await
+        .expect_err("Getting the result of another user's query should fail");
+    let admin_session = execution_svc
+        .get_session("admin_session")
+        .await
+        .expect("Failed to get session");
+    let alice_query = alice("SELECT 1").await.expect("Failed to run query");
+    execution_svc
+        .wait_historical_query_result(&admin_session.query_reader(), alice_query.query_id)
+        .await
+        .expect("Failed to get the result of a query as admin")
+        .expect("Query failed");
+
+    admin("GRANT USAGE ON DATABASE embucket TO ROLE analyst")
+        .await
+        .expect("Failed to grant usage on database");
+    admin("GRANT USAGE ON SCHEMA embucket.public TO ROLE analyst")
+        .await
+        .expect("Failed to grant usage on schema");
+    admin("GRANT SELECT ON TABLE embucket.public.t TO ROLE analyst")
+        .await
+        .expect("Failed to grant select");
+    alice("SELECT * FROM embucket.public.t")
+        .await
+        .expect("Failed to select with privileges");
+    alice("INSERT INTO embucket.public.t VALUES (1)")
+        .await
+        .expect_err("Inserting without privileges should fail");
+
+    let grants = admin("SHOW GRANTS TO ROLE analyst")
+        .await
+        .expect("Failed to show grants");
+    assert_eq!(
+        grants
+            .records
+            .iter()
+            .map(datafusion::arrow::array::RecordBatch::num_rows)
+            .sum::<usize>(),
+        3
+    );
+
+    admin("REVOKE SELECT ON TABLE embucket.public.t FROM ROLE analyst")
+        .await
+        .expect("Failed to revoke select");
+    alice("SELECT * FROM embucket.public.t")
+        .await
+        .expect_err("Selecting after revoking privileges should fail");
+
+    // Stages are created with CREATE on their schema, used with USAGE and dropped by their owner
+    alice("CREATE STAGE embucket.public.alice_stage")
+        .await
+        .expect_err("Creating a stage without CREATE on the schema should fail");
+    admin("CREATE STAGE embucket.public.s")
+        .await
+        .expect("Failed to create stage");
+    alice("LIST @embucket.public.s")
+        .await
+        .expect_err("Listing a stage without privileges should fail");
+    admin("GRANT USAGE ON STAGE embucket.public.s TO ROLE analyst")
+        .await
+        .expect("Failed to grant usage on stage");
+    alice("LIST @embucket.public.s")
+        .await
+        .expect("Failed to list stage with privileges");
+    alice("DROP STAGE embucket.public.s")
+        .await
+        .expect_err("Dropping a stage without ownership should fail");
+
+    // So are file formats
+    alice("CREATE FILE FORMAT embucket.public.alice_csv TYPE = CSV")
+        .await
+        .expect_err("Creating a file format without CREATE on the schema should fail");
+    admin("CREATE FILE FORMAT embucket.public.csv TYPE = CSV")
+        .await
+        .expect("Failed to create file format");
+    alice("CREATE OR REPLACE FILE FORMAT embucket.public.csv TYPE = JSON")
+        .await
+        .expect_err("Replacing a file format without ownership should fail");
+    alice("DROP FILE FORMAT embucket.public.csv")
+        .await
+        .expect_err("Dropping a file format without ownership should fail");
+    admin("GRANT OWNERSHIP ON FILE FORMAT embucket.public.csv TO ROLE analyst")
+        .await
+        .expect("Failed to grant ownership on file format");
+    alice("DROP FILE FORMAT embucket.public.csv")
+        .await
+        .expect("Failed to drop file format with ownership");
+
+    // Sessions of a role restricted access token keep to the role, so do their new tokens
+    let restricted_options = SessionOptions {
+        role_restriction: Some("analyst".to_string()),
+        ..session_options("alice", "analyst")
+    };
+    execution_svc
+        .create_session_with_options("restricted_session", restricted_options)
+        .await
+        .expect("Failed to create session");
+    let restricted =
+        move |query: &'static str| svc.query("restricted_session", query, QueryContext::default());
+    restricted("USE ROLE PUBLIC")
+        .await
+        .expect_err("Using a role other than the restricted one should fail");
+    restricted("ALTER USER alice ADD PAT other ROLE_RESTRICTION = 'PUBLIC'")
+        .await
+        .expect_err("Creating a token for another role should fail");
+    restricted("ALTER USER alice ADD PAT ci")
+        .await
+        .expect("Failed to add access token");
+    let user = metastore
+        .get_user(&"alice".to_string())
+        .await
+        .expect("Failed to get user")
+        .expect("User not found");
+    assert_eq!(
+        user.access_token("ci")
+            .and_then(|token| token.role_restriction.as_deref()),
+        Some("analyst")
+    );
+
+    // Sessions without a user only have the privileges of PUBLIC, DuckDB acceleration included
+    let anonymous_options = SessionOptions {
+        params: HashMap::from([(
+            "embucket.execution.acceleration".to_string(),
+            ScalarValue::Utf8(Some("true".to_string())),
+        )]),
+        client_info: None,
+        user: None,
+        role_restriction: None,
+    };
+    execution_svc
+        .create_session_with_options("anonymous_session", anonymous_options)
+        .await
+        .expect("Failed to create session");
+    svc.query(
+        "anonymous_session",
+        "SELECT * FROM embucket.public.t",
+        QueryContext::default(),
+    )
+    .await
+    .expect_err("Selecting without a user should fail");
+}
+
+#[tokio::test]
+#[allow(clippy::expect_used)]
+async fn test_copy_into_access_control() {
+    let metastore = Arc::new(SlateDBMetastore::new_in_memory().await);
+    let history_store = Arc::new(SlateDBHistoryStore::new_in_memory().await);
+    let execution_svc = CoreExecutionService::new(
+        metastore.clone(),
+        history_store,
+        Arc::new(Config::default()),
+    )
+    .await
+    .expect("Failed to create execution service");
+    bootstrap_user(metastore.as_ref(), "admin", "admin")
+        .await
+        .expect("Failed to bootstrap user");
+
+    let session_options = |user: &str, role: &str| SessionOptions {
+        params: HashMap::from([(
+            "role".to_string(),
+            ScalarValue::Utf8(Some(role.to_string())),
+        )]),
+        client_info: None,
+        user: Some(user.to_string()),
+        role_restriction: None,
+    };
+    execution_svc
+        .create_session_with_options("admin_session", session_options("admin", "ACCOUNTADMIN"))
+        .await
+        .expect("Failed to create session");
+    let svc = &execution_svc;
+    let admin =
+        move |query: &'static str| svc.query("admin_session", query, QueryContext::default());
+    let alice =
+        move |query: &'static str| svc.query("alice_session", query, QueryContext::default());
+
+    for query in [
+        "CREATE TABLE embucket.public.t (id INT, name VARCHAR)",
+        "CREATE TABLE embucket.public.other (id INT, name VARCHAR)",
+        "CREATE STAGE embucket.public.s",
+        "COPY INTO @embucket.public.s/in/ \
+         FROM (SELECT * FROM (VALUES (1, 'a'), (2, 'b')) AS v(id, name)) \
+         FILE_FORMAT = (TYPE = CSV COMPRESSION = NONE)",
+        "CREATE ROLE loader",
+        "CREATE USER alice PASSWORD = 'secret'",
+        "GRANT ROLE loader TO USER alice",
+        "GRANT USAGE ON DATABASE embucket TO ROLE loader",
+        "GRANT USAGE ON SCHEMA embucket.public TO ROLE loader",
+        "GRANT USAGE ON STAGE embucket.public.s TO ROLE loader",
+        "GRANT INSERT ON TABLE embucket.public.t TO ROLE loader",
+    ] {
+        admin(query).await.expect("Failed to set up");
+    }
+    execution_svc
+        .create_session_with_options("alice_session", session_options("alice", "loader"))
+        .await
+        .expect("Failed to create session");
+
+    // Loading needs INSERT on the table, the files aren't a table to SELECT from
+    alice(
+        "COPY INTO embucket.public.t FROM @embucket.public.s/in/ \
+         FILE_FORMAT = (TYPE = CSV) VALIDATION_MODE = RETURN_ERRORS",
+    )
+    .await
+    .expect("Failed to validate with privileges");
+    alice("COPY INTO embucket.public.t FROM @embucket.public.s/in/ FILE_FORMAT = (TYPE = CSV)")
+        .await
+        .expect("Failed to load with privileges");
+    alice(
+        "COPY INTO embucket.public.other FROM @embucket.public.s/in/ \
+         FILE_FORMAT = (TYPE = CSV) VALIDATION_MODE = RETURN_ERRORS",
+    )
+    .await
+    .expect_err("Validating without INSERT should fail");
+    alice("COPY INTO embucket.public.other FROM @embucket.public.s/in/ FILE_FORMAT = (TYPE = CSV)")
+        .await
+        .expect_err("Loading without INSERT should fail");
+
+    // Reading the load history of a table needs SELECT on it
+    let copy_history = "SELECT file_name FROM \
+         TABLE(INFORMATION_SCHEMA.COPY_HISTORY(TABLE_NAME => 'embucket.public.t'))";
+    alice(copy_history)
+        .await
+        .expect_err("Reading the load history without SELECT should fail");
+
+    // Unloading needs SELECT on what is read, partitioned or not
+    alice("COPY INTO @embucket.public.s/out/ FROM embucket.public.t")
+        .await
+        .expect_err("Unloading a table without SELECT should fail");
+    alice(
+        "COPY INTO @embucket.public.s/out/ FROM (SELECT * FROM embucket.public.t) \
+         PARTITION BY (name)",
+    )
+    .await
+    .expect_err("Unloading a query without SELECT should fail");
+    admin("GRANT SELECT ON TABLE embucket.public.t TO ROLE loader")
+        .await
+        .expect("Failed to grant select");
+    alice("COPY INTO @embucket.public.s/out/ FROM embucket.public.t")
+        .await
+        .expect("Failed to unload with privileges");
+    alice(copy_history)
+        .await
+        .expect("Failed to read the load history with privileges");
+
+    // Local files of the server and the credentials of volumes are only for admins
+    alice("COPY INTO 'file:///tmp/embucket_unload/' FROM embucket.public.t")
+        .await
+        .expect_err("Unloading to local files without being an admin should fail");
+    alice(
+        "COPY INTO embucket.public.t FROM 's3://bucket/in/' STORAGE_INTEGRATION = embucket \
+         FILE_FORMAT = (TYPE = CSV)",
+    )
+    .await
+    .expect_err("Loading with the credentials of a volume without being an admin should fail");
+}
+
+#[tokio::test]
+#[allow(clippy::expect_used)]
+async fn test_session_keep_alive_and_delete() {
+    let metastore = Arc::new(SlateDBMetastore::new_in_memory().await);
+    let history_store = Arc::new(SlateDBHistoryStore::new_in_memory().await);
+    let execution_svc =
+        CoreExecutionService::new(metastore, history_store, Arc::new(Config::default()))
+            .await
+            .expect("Failed to create execution service");
+    let session = execution_svc
+        .create_session("test_session_id")
+        .await
+        .expect("Failed to create session");
+    let expiry = || session.expiry.load(Ordering::Relaxed);
+    let created_expiry = expiry();
+
+    assert!(
+        execution_svc
+            .update_session_expiry("test_session_id", SESSION_KEEP_ALIVE_EXPIRATION_SECONDS)
+            .await
+            .expect("Failed to update session expiry")
+    );
+    let kept_alive_expiry = expiry();
+    assert!(kept_alive_expiry > created_expiry);
+    // A regular request doesn't shorten the keep-alive
+    execution_svc
+        .update_session_expiry("test_session_id", SESSION_INACTIVITY_EXPIRATION_SECONDS)
+        .await
+        .expect("Failed to update session expiry");
+    assert_eq!(expiry(), kept_alive_expiry);
+
+    assert!(
+        execution_svc
+            .delete_session("test_session_id")
+            .await
+            .expect("Failed to delete session")
+    );
+    assert!(!execution_svc.session_exists("test_session_id").await);
+    assert!(
+        !execution_svc
+            .delete_session("test_session_id")
+            .await
+            .expect("Failed to delete session")
+    );
+    assert!(
+        !execution_svc
+            .update_session_expiry("test_session_id", SESSION_KEEP_ALIVE_EXPIRATION_SECONDS)
+            .await
+            .expect("Failed to update session expiry")
+    );
+}
@@ -0,0 +1,87 @@
+use crate::models::{BindingValue, QueryBinding, QueryContext};
+use crate::tests::query::create_df_session;
+use datafusion::arrow::util::pretty::pretty_format_batches;
+
+fn binding(binding_type: &str, value: BindingValue) -> QueryBinding {
+    QueryBinding {
+        binding_type: binding_type.to_string(),
+        value,
+    }
+}
+
+fn array(values: &[Option<&str>]) -> BindingValue {
+    BindingValue::Array(
+        values
+            .iter()
+            .map(|value| value.map(ToString::to_string))
+            .collect(),
+    )
+}
+
+#[allow(clippy::unwrap_used)]
+#[tokio::test]
+async fn test_bindings_bulk_insert() {
+    let session = create_df_session().await;
+    session
+        .query(
+            "CREATE TABLE embucket.public.t (id INT, name VARCHAR, created DATE)",
+            QueryContext::default(),
+        )
+        .execute()
+        .await
+        .unwrap();
+
+    session
+        .query(
+            "INSERT INTO embucket.public.t VALUES (?, ?, ?)",
+            QueryContext::default().with_bindings(vec![
+                binding("FIXED", array(&[Some("1"), Some("2"), Some("3")])),
+                binding("TEXT", array(&[Some("a"), None, Some("c'")])),
+                binding(
+                    "DATE",
+                    BindingValue::Single(Some("1700006400000".to_string())),
+                ),
+            ]),
+        )
+        .execute()
+        .await
+        .unwrap();
+    let selected = session
+        .query(
+            "SELECT id, name, created FROM embucket.public.t WHERE id >= :1 ORDER BY id",
+            QueryContext::default().with_bindings(vec![binding(
+                "FIXED",
+                BindingValue::Single(Some("2".to_string())),
+            )]),
+        )
+        .execute()
+        .await
+        .unwrap();
+    assert_eq!(
+        pretty_format_batches(&selected.records)
+            .unwrap()
+            .to_string(),
+        "+----+------+------------+
+| id | name | created    |
++----+------+------------+
+| 2  |      | 2023-11-15 |
+| 3  | c'   | 2023-11-15 |
++----+------+------------+"
+    );
+}
+
+#[allow(clippy::unwrap_used)]
+#[tokio::test]
+async fn test_bindings_array_outside_insert() {
+    let session = create_df_session().await;
+    let err = session
+        .query(
+            "SELECT ?",
+            QueryContext::default()
+                .with_bindings(vec![binding("FIXED", array(&[Some("1"), Some("2")]))]),
+        )
+        .execute()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Array bindings"), "{err}");
+}
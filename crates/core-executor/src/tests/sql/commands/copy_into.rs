@@ -0,0 +1,510 @@
+use crate::models::{FileTransferCommand, QueryContext};
+use crate::session::UserSession;
+use crate::test_query;
+use crate::tests::query::create_df_session;
+use datafusion::arrow::array::RecordBatch;
+use datafusion::arrow::util::display::array_value_to_string;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+// CSV file format options
+test_query!(
+    copy_into_csv_options,
+    "SELECT * FROM embucket.public.t ORDER BY id",
+    setup_queries = [
+        "CREATE TABLE embucket.public.t (id INT, name VARCHAR, born DATE)",
+        concat!(
+            "COPY INTO embucket.public.t FROM 'file://",
+            env!("CARGO_MANIFEST_DIR"),
+            "/src/tests/data/copy_into/options.csv' FILE_FORMAT = (TYPE = CSV SKIP_HEADER = 2 \
+             FIELD_DELIMITER = ';' FIELD_OPTIONALLY_ENCLOSED_BY = '''' TRIM_SPACE = TRUE \
+             NULL_IF = ('NULL') DATE_FORMAT = 'DD/MM/YYYY')"
+        ),
+    ],
+    snapshot_path = "copy_into"
+);
+test_query!(
+    copy_into_csv_encoding,
+    "SELECT * FROM embucket.public.t",
+    setup_queries = [
+        "CREATE TABLE embucket.public.t (name VARCHAR)",
+        concat!(
+            "COPY INTO embucket.public.t FROM 'file://",
+            env!("CARGO_MANIFEST_DIR"),
+            "/src/tests/data/copy_into/windows1252.csv' \
+             FILE_FORMAT = (TYPE = CSV ENCODING = 'WINDOWS1252')"
+        ),
+    ],
+    snapshot_path = "copy_into"
+);
+test_query!(
+    copy_into_csv_column_count_mismatch_ignored,
+    "SELECT * FROM embucket.public.t ORDER BY a",
+    setup_queries = [
+        "CREATE TABLE embucket.public.t (a INT, b INT)",
+        concat!(
+            "COPY INTO embucket.public.t FROM 'file://",
+            env!("CARGO_MANIFEST_DIR"),
+            "/src/tests/data/copy_into/column_count.csv' \
+             FILE_FORMAT = (TYPE = CSV ERROR_ON_COLUMN_COUNT_MISMATCH = FALSE)"
+        ),
+    ],
+    snapshot_path = "copy_into"
+);
+
+// ON_ERROR and VALIDATION_MODE: bad_rows/data.csv has a bad value on line 2 and
+// an extra column on line 4
+test_query!(
+    copy_into_on_error_continue,
+    concat!(
+        "COPY INTO embucket.public.t FROM 'file://",
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/tests/data/copy_into/bad_rows/' FILE_FORMAT = (TYPE = CSV) ON_ERROR = CONTINUE"
+    ),
+    setup_queries = ["CREATE TABLE embucket.public.t (id INT, name VARCHAR)"],
+    exclude_columns = ["file"],
+    snapshot_path = "copy_into"
+);
+test_query!(
+    copy_into_on_error_continue_rows,
+    "SELECT * FROM embucket.public.t ORDER BY id",
+    setup_queries = [
+        "CREATE TABLE embucket.public.t (id INT, name VARCHAR)",
+        concat!(
+            "COPY INTO embucket.public.t FROM 'file://",
+            env!("CARGO_MANIFEST_DIR"),
+            "/src/tests/data/copy_into/bad_rows/' FILE_FORMAT = (TYPE = CSV) ON_ERROR = CONTINUE"
+        ),
+    ],
+    snapshot_path = "copy_into"
+);
+test_query!(
+    copy_into_on_error_skip_file,
+    concat!(
+        "COPY INTO embucket.public.t FROM 'file://",
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/tests/data/copy_into/bad_rows/' FILE_FORMAT = (TYPE = CSV) ON_ERROR = SKIP_FILE"
+    ),
+    setup_queries = ["CREATE TABLE embucket.public.t (id INT, name VARCHAR)"],
+    exclude_columns = ["file"],
+    snapshot_path = "copy_into"
+);
+// Two errors out of four rows are within the limit of three
+test_query!(
+    copy_into_on_error_skip_file_num,
+    concat!(
+        "COPY INTO embucket.public.t FROM 'file://",
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/tests/data/copy_into/bad_rows/data.csv' FILE_FORMAT = (TYPE = CSV) \
+         ON_ERROR = SKIP_FILE_3"
+    ),
+    setup_queries = ["CREATE TABLE embucket.public.t (id INT, name VARCHAR)"],
+    exclude_columns = ["file"],
+    snapshot_path = "copy_into"
+);
+test_query!(
+    copy_into_validation_mode,
+    concat!(
+        "COPY INTO embucket.public.t FROM 'file://",
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/tests/data/copy_into/bad_rows/data.csv' FILE_FORMAT = (TYPE = CSV) \
+         VALIDATION_MODE = RETURN_ERRORS"
+    ),
+    setup_queries = ["CREATE TABLE embucket.public.t (id INT, name VARCHAR)"],
+    exclude_columns = ["file"],
+    snapshot_path = "copy_into"
+);
+test_query!(
+    copy_into_no_files,
+    concat!(
+        "COPY INTO embucket.public.t FROM 'file://",
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/tests/data/copy_into/' FILES = ('missing.csv') FILE_FORMAT = (TYPE = CSV)"
+    ),
+    setup_queries = ["CREATE TABLE embucket.public.t (id INT)"],
+    snapshot_path = "copy_into"
+);
+// Loaded by column name through a listing table, files counted one by one
+test_query!(
+    copy_into_parse_header,
+    concat!(
+        "COPY INTO embucket.public.t FROM 'file://",
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/tests/data/copy_into/header/' FILE_FORMAT = (TYPE = CSV PARSE_HEADER = TRUE)"
+    ),
+    setup_queries = ["CREATE TABLE embucket.public.t (id INT, name VARCHAR)"],
+    exclude_columns = ["file"],
+    snapshot_path = "copy_into"
+);
+
+/// Writes `files` into a temporary directory, removed once it's dropped
+#[allow(clippy::unwrap_used)]
+fn write_files(files: &[(&str, &[u8])]) -> TempDir {
+    let dir = TempDir::new().unwrap();
+    for (name, contents) in files {
+        std::fs::write(dir.path().join(name), contents).unwrap();
+    }
+    dir
+}
+
+/// `file://` URL of a file in `src/tests/data/copy_into`
+fn data_url(file: &str) -> String {
+    format!(
+        "file://{}/src/tests/data/copy_into/{file}",
+        env!("CARGO_MANIFEST_DIR")
+    )
+}
+
+/// Runs a failing `COPY INTO embucket.public.t FROM '<url>' FILE_FORMAT = (TYPE = CSV)`
+/// against a fresh table with the given column definitions
+#[allow(clippy::unwrap_used)]
+async fn copy_error(columns: &str, url: &str) -> String {
+    let session = create_df_session().await;
+    run(
+        &session,
+        &format!("CREATE TABLE embucket.public.t ({columns})"),
+    )
+    .await;
+    session
+        .query(
+            format!("COPY INTO embucket.public.t FROM '{url}' FILE_FORMAT = (TYPE = CSV)"),
+            QueryContext::default(),
+        )
+        .execute()
+        .await
+        .unwrap_err()
+        .to_string()
+}
+
+// File paths are part of these errors, so they are not snapshotted
+#[tokio::test]
+async fn test_copy_into_csv_errors() {
+    let err = copy_error("name VARCHAR", &data_url("windows1252.csv")).await;
+    assert!(err.contains("Invalid UTF-8 character encoding"), "{err}");
+
+    let err = copy_error("a INT, b INT", &data_url("column_count.csv")).await;
+    assert!(err.contains("Number of columns in file (3)"), "{err}");
+
+    let err = copy_error("id INT, name VARCHAR", &data_url("bad_rows/")).await;
+    assert!(err.contains("Numeric value 'x' is not recognized"), "{err}");
+    assert!(err.contains("data.csv', line 2"), "{err}");
+}
+
+/// Values of a result column, rendered as strings
+#[allow(clippy::unwrap_used)]
+fn column(batches: &[RecordBatch], name: &str) -> Vec<String> {
+    batches
+        .iter()
+        .flat_map(|batch| {
+            let column = batch.column_by_name(name).unwrap().clone();
+            (0..batch.num_rows())
+                .map(move |row| array_value_to_string(&column, row).unwrap())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Runs `query` in `session` and returns its result batches
+#[allow(clippy::unwrap_used)]
+async fn run(session: &Arc<UserSession>, query: &str) -> Vec<RecordBatch> {
+    session
+        .query(query, QueryContext::default())
+        .execute()
+        .await
+        .unwrap()
+        .records
+}
+
+#[allow(clippy::unwrap_used)]
+#[tokio::test]
+async fn test_copy_into_load_history() {
+    let dir = write_files(&[
+        ("a.csv", b"1\n".as_slice()),
+        ("b.csv", b"2\n"),
+        ("c.json", b"3\n"),
+    ]);
+    let session = create_df_session().await;
+    run(&session, "CREATE TABLE embucket.public.t (id INT)").await;
+    let copy = |selection: &str, copy_options: &str| {
+        format!(
+            "COPY INTO embucket.public.t FROM 'file://{}/' {selection} \
+             FILE_FORMAT = (TYPE = CSV) {copy_options}",
+            dir.path().display()
+        )
+    };
+
+    let copied = run(&session, &copy("FILES = ('a.csv')", "")).await;
+    assert_eq!(column(&copied, "file").len(), 1);
+    assert!(column(&copied, "file")[0].ends_with("a.csv"));
+
+    // a.csv is already loaded, c.json does not match the pattern
+    let copied = run(&session, &copy("PATTERN = '.*[.]csv'", "")).await;
+    assert_eq!(column(&copied, "file").len(), 1);
+    assert!(column(&copied, "file")[0].ends_with("b.csv"));
+
+    let copied = run(&session, &copy("PATTERN = '.*[.]csv'", "")).await;
+    assert_eq!(
+        column(&copied, "status"),
+        ["Copy executed with 0 files processed."]
+    );
+
+    let copied = run(&session, &copy("PATTERN = '.*a[.]csv'", "FORCE = TRUE")).await;
+    assert_eq!(column(&copied, "rows_loaded"), ["1"]);
+
+    let history = run(
+        &session,
+        "SELECT file_name, row_count, status FROM \
+         TABLE(INFORMATION_SCHEMA.COPY_HISTORY(TABLE_NAME => 't')) ORDER BY file_name",
+    )
+    .await;
+    assert_eq!(column(&history, "row_count"), ["1", "1"]);
+    assert_eq!(column(&history, "status"), ["Loaded", "Loaded"]);
+    assert!(column(&history, "file_name")[0].ends_with("a.csv"));
+
+    let table = run(&session, "SELECT id FROM embucket.public.t ORDER BY id").await;
+    assert_eq!(column(&table, "id"), ["1", "1", "2"]);
+}
+
+#[allow(clippy::unwrap_used)]
+#[tokio::test]
+async fn test_copy_into_purge() {
+    let dir = write_files(&[("a.csv", b"1\n".as_slice()), ("b.csv", b"x\n")]);
+    let session = create_df_session().await;
+    run(&session, "CREATE TABLE embucket.public.t (id INT)").await;
+    let copied = run(
+        &session,
+        &format!(
+            "COPY INTO embucket.public.t FROM 'file://{}/' \
+             FILE_FORMAT = (TYPE = CSV) ON_ERROR = SKIP_FILE PURGE = TRUE",
+            dir.path().display()
+        ),
+    )
+    .await;
+    assert_eq!(column(&copied, "status"), ["LOADED", "LOAD_FAILED"]);
+    // Only the loaded file is removed
+    assert!(!dir.path().join("a.csv").exists());
+    assert!(dir.path().join("b.csv").exists());
+}
+
+/// Paths of all files under `dir`, relative to it and sorted
+#[allow(clippy::unwrap_used)]
+fn list_files(dir: &Path) -> Vec<String> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(current) = dirs.pop() {
+        for entry in std::fs::read_dir(current).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                files.push(
+                    path.strip_prefix(dir)
+                        .unwrap()
+                        .to_string_lossy()
+                        .to_string(),
+                );
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+#[allow(clippy::unwrap_used)]
+#[tokio::test]
+async fn test_copy_into_location_csv() {
+    let dir = write_files(&[]);
+    let session = create_df_session().await;
+    let unload = format!(
+        "COPY INTO 'file://{}/out.csv' \
+         FROM (SELECT * FROM (VALUES (1, 'a'), (2, NULL)) AS v(id, name) ORDER BY id) \
+         FILE_FORMAT = (TYPE = CSV COMPRESSION = NONE) HEADER = TRUE SINGLE = TRUE",
+        dir.path().display()
+    );
+
+    let unloaded = run(&session, &unload).await;
+    assert_eq!(column(&unloaded, "rows_unloaded"), ["2"]);
+    assert_eq!(list_files(dir.path()), ["out.csv"]);
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("out.csv")).unwrap(),
+        "id,name\n1,a\n2,\\N\n"
+    );
+
+    let err = session
+        .query(&unload, QueryContext::default())
+        .execute()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Use overwrite option"), "{err}");
+
+    run(&session, &format!("{unload} OVERWRITE = TRUE")).await;
+    assert_eq!(list_files(dir.path()), ["out.csv"]);
+}
+
+#[allow(clippy::unwrap_used)]
+#[tokio::test]
+async fn test_copy_into_location_partition_by() {
+    let dir = write_files(&[]);
+    let session = create_df_session().await;
+    run(
+        &session,
+        "CREATE TABLE embucket.public.t (id INT, grp VARCHAR)",
+    )
+    .await;
+    run(
+        &session,
+        "INSERT INTO embucket.public.t VALUES (1, 'x'), (2, 'y'), (3, 'x')",
+    )
+    .await;
+
+    let unloaded = run(
+        &session,
+        &format!(
+            "COPY INTO 'file://{}/' FROM embucket.public.t \
+             PARTITION BY ('grp=' || grp) FILE_FORMAT = (TYPE = JSON) DETAILED_OUTPUT = TRUE",
+            dir.path().display()
+        ),
+    )
+    .await;
+    assert_eq!(column(&unloaded, "row_count"), ["2", "1"]);
+    let files = list_files(dir.path());
+    assert_eq!(files.len(), 2);
+    assert!(files[0].starts_with("grp=x/data_0_"), "{files:?}");
+    assert!(files[0].ends_with(".json.gz"), "{files:?}");
+    assert!(files[1].starts_with("grp=y/data_0_"), "{files:?}");
+}
+
+#[allow(clippy::unwrap_used)]
+#[tokio::test]
+async fn test_copy_into_location_parquet() {
+    let dir = write_files(&[]);
+    let session = create_df_session().await;
+    let unloaded = run(
+        &session,
+        &format!(
+            "COPY INTO 'file://{}/result' \
+             FROM (SELECT * FROM (VALUES (1, 'a'), (2, 'b'), (3, 'c')) AS v(id, name)) \
+             FILE_FORMAT = (TYPE = PARQUET)",
+            dir.path().display()
+        ),
+    )
+    .await;
+    assert_eq!(column(&unloaded, "rows_unloaded"), ["3"]);
+    assert!(
+        list_files(dir.path())
+            .iter()
+            .all(|file| file.starts_with("result_0_") && file.ends_with(".snappy.parquet"))
+    );
+
+    // Unloaded files load back
+    run(
+        &session,
+        "CREATE TABLE embucket.public.t (id BIGINT, name VARCHAR)",
+    )
+    .await;
+    run(
+        &session,
+        &format!(
+            "COPY INTO embucket.public.t FROM 'file://{}/' FILE_FORMAT = (TYPE = PARQUET)",
+            dir.path().display()
+        ),
+    )
+    .await;
+    let table = run(&session, "SELECT name FROM embucket.public.t ORDER BY id").await;
+    assert_eq!(column(&table, "name"), ["a", "b", "c"]);
+}
+
+#[allow(clippy::unwrap_used)]
+#[tokio::test]
+async fn test_copy_into_internal_stage() {
+    let session = create_df_session().await;
+    run(&session, "CREATE STAGE embucket.public.s").await;
+
+    let unloaded = run(
+        &session,
+        "COPY INTO @embucket.public.s/out/ \
+         FROM (SELECT * FROM (VALUES (1, 'a'), (2, 'b')) AS v(id, name)) \
+         FILE_FORMAT = (TYPE = CSV COMPRESSION = NONE)",
+    )
+    .await;
+    assert_eq!(column(&unloaded, "rows_unloaded"), ["2"]);
+    let listed = column(&run(&session, "LIST @embucket.public.s/out").await, "name");
+    assert_eq!(listed.len(), 1);
+    assert!(listed[0].starts_with("s/out/data_"), "{listed:?}");
+
+    run(
+        &session,
+        "CREATE TABLE embucket.public.t (id INT, name VARCHAR)",
+    )
+    .await;
+    run(
+        &session,
+        "COPY INTO embucket.public.t FROM @embucket.public.s/out/ FILE_FORMAT = (TYPE = CSV)",
+    )
+    .await;
+    let table = run(&session, "SELECT name FROM embucket.public.t ORDER BY id").await;
+    assert_eq!(column(&table, "name"), ["a", "b"]);
+
+    // PUT and GET only resolve the transfer, the files are moved by the client
+    let put = session
+        .query(
+            "PUT file:///tmp/data.csv @embucket.public.s/in AUTO_COMPRESS = FALSE",
+            QueryContext::default(),
+        )
+        .execute()
+        .await
+        .unwrap()
+        .file_transfer
+        .unwrap();
+    assert_eq!(put.command, FileTransferCommand::Upload);
+    assert_eq!(put.stage.to_string(), "embucket.public.s");
+    assert!(put.root.as_ref().ends_with("public/.stages/s"));
+    assert_eq!(put.path, "in");
+    assert_eq!(put.source_locations, ["/tmp/data.csv"]);
+    assert!(!put.auto_compress);
+    let get = session
+        .query(
+            "GET @embucket.public.s file:///tmp/out/",
+            QueryContext::default(),
+        )
+        .execute()
+        .await
+        .unwrap()
+        .file_transfer
+        .unwrap();
+    assert_eq!(get.command, FileTransferCommand::Download);
+    assert_eq!(get.local_location.as_deref(), Some("/tmp/out/"));
+    assert_eq!(
+        get.source_locations,
+        [listed[0].trim_start_matches("s/").to_string()]
+    );
+
+    let removed = run(&session, "REMOVE @embucket.public.s PATTERN = '.*[.]csv'").await;
+    assert_eq!(column(&removed, "result"), ["removed"]);
+    assert_eq!(
+        run(&session, "LIST @embucket.public.s").await[0].num_rows(),
+        0
+    );
+
+    // Paths can't leave the stage directory
+    for sql in [
+        "LIST @embucket.public.s/..",
+        "REMOVE @embucket.public.s/../x",
+    ] {
+        let err = session
+            .query(sql, QueryContext::default())
+            .execute()
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid stage path"), "{err}");
+    }
+
+    run(&session, "DROP STAGE embucket.public.s").await;
+    let err = session
+        .query("LIST @embucket.public.s", QueryContext::default())
+        .execute()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("does not exist"), "{err}");
+}
@@ -0,0 +1,136 @@
+use crate::models::QueryContext;
+use crate::session::UserSession;
+use crate::test_query;
+use crate::tests::query::create_df_session;
+use datafusion::arrow::array::RecordBatch;
+use datafusion::arrow::util::display::array_value_to_string;
+use std::sync::Arc;
+
+/// Values of a result column, rendered as strings, NULL as an empty string
+#[allow(clippy::unwrap_used)]
+fn column(batches: &[RecordBatch], name: &str) -> Vec<String> {
+    batches
+        .iter()
+        .flat_map(|batch| {
+            let column = batch.column_by_name(name).unwrap().clone();
+            (0..batch.num_rows())
+                .map(move |row| array_value_to_string(&column, row).unwrap())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[allow(clippy::unwrap_used)]
+async fn run(session: &Arc<UserSession>, sql: &str) -> Vec<RecordBatch> {
+    session
+        .query(sql, QueryContext::default())
+        .execute()
+        .await
+        .unwrap()
+        .records
+}
+
+/// Table of two data files, written by separate inserts
+async fn create_table() -> Arc<UserSession> {
+    let session = create_df_session().await;
+    run(
+        &session,
+        "CREATE TABLE embucket.public.t (id INT, name VARCHAR)",
+    )
+    .await;
+    run(
+        &session,
+        "INSERT INTO embucket.public.t VALUES (1, 'a'), (2, 'b')",
+    )
+    .await;
+    run(
+        &session,
+        "INSERT INTO embucket.public.t VALUES (6, 'c'), (7, 'd')",
+    )
+    .await;
+    session
+}
+
+#[tokio::test]
+async fn test_explain_using_tabular() {
+    let session = create_table().await;
+    let plan = run(
+        &session,
+        "EXPLAIN USING TABULAR SELECT name FROM embucket.public.t WHERE id > 5",
+    )
+    .await;
+
+    let operations = column(&plan, "operation");
+    assert_eq!(operations[0], "GlobalStats");
+    assert_eq!(operations[1], "Result");
+    assert!(operations.contains(&"Filter".to_string()));
+    let scan = operations.iter().position(|op| op == "TableScan").unwrap();
+    assert_eq!(column(&plan, "objects")[scan], "embucket.public.t");
+    // The data file of the first insert is pruned by the filter
+    assert_eq!(column(&plan, "partitionsTotal")[scan], "2");
+    assert_eq!(column(&plan, "partitionsAssigned")[scan], "1");
+    assert_eq!(
+        column(&plan, "partitionsAssigned")[0],
+        column(&plan, "partitionsAssigned")[scan]
+    );
+    // Steps are listed parents first, ids start after the global statistics row
+    let parents = column(&plan, "parent");
+    assert_eq!(parents[1], "");
+    let scan_parent: usize = parents[scan].parse().unwrap();
+    assert!(scan_parent < scan - 1);
+}
+
+#[tokio::test]
+async fn test_explain_without_using() {
+    let session = create_table().await;
+    let sql = "SELECT name FROM embucket.public.t WHERE id > 5";
+    let plan = run(&session, &format!("EXPLAIN {sql}")).await;
+    let tabular = run(&session, &format!("EXPLAIN USING TABULAR {sql}")).await;
+    assert_eq!(column(&plan, "operation"), column(&tabular, "operation"));
+}
+
+#[tokio::test]
+async fn test_explain_using_json_and_text() {
+    let session = create_table().await;
+    let json = run(
+        &session,
+        "EXPLAIN USING JSON SELECT name FROM embucket.public.t",
+    )
+    .await;
+    let json: serde_json::Value = serde_json::from_str(&column(&json, "content")[0])
+        .expect("EXPLAIN USING JSON returns a JSON document");
+    assert_eq!(json["GlobalStats"]["partitionsTotal"], 2);
+    assert_eq!(json["GlobalStats"]["partitionsAssigned"], 2);
+    assert_eq!(json["Operations"][0][0]["operation"], "Result");
+
+    let text = run(
+        &session,
+        "EXPLAIN USING TEXT SELECT name FROM embucket.public.t",
+    )
+    .await;
+    let text = &column(&text, "content")[0];
+    assert!(text.starts_with("GlobalStats:\n    partitionsTotal=2\n"));
+    assert!(text.contains("->TableScan  embucket.public.t"));
+}
+
+#[tokio::test]
+async fn test_explain_using_invalid_format() {
+    let session = create_df_session().await;
+    let result = session
+        .query("EXPLAIN USING XML SELECT 1", QueryContext::default())
+        .execute()
+        .await;
+    assert!(result.is_err());
+}
+
+// Timings and the query id vary from run to run
+test_query!(
+    explain_analyze,
+    "EXPLAIN ANALYZE SELECT 1 AS a",
+    exclude_columns = [
+        "QUERY_ID",
+        "OPERATOR_STATISTICS",
+        "EXECUTION_TIME_BREAKDOWN"
+    ],
+    snapshot_path = "explain"
+);
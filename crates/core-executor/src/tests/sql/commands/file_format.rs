@@ -0,0 +1,17 @@
+use crate::test_query;
+
+// COPY INTO with a named file format
+test_query!(
+    copy_into_with_named_file_format,
+    "SELECT * FROM embucket.public.file_format_t ORDER BY id",
+    setup_queries = [
+        "CREATE FILE FORMAT embucket.public.pipe_csv TYPE = CSV FIELD_DELIMITER = '|' SKIP_HEADER = 1 NULL_IF = ('NULL')",
+        "CREATE TABLE embucket.public.file_format_t (id INT, name VARCHAR)",
+        concat!(
+            "COPY INTO embucket.public.file_format_t FROM 'file://",
+            env!("CARGO_MANIFEST_DIR"),
+            "/src/tests/data/pipe_separated.csv' FILE_FORMAT = (FORMAT_NAME = 'embucket.public.pipe_csv')"
+        ),
+    ],
+    snapshot_path = "file_format"
+);
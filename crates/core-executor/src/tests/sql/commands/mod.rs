@@ -1,4 +1,8 @@
+mod bindings;
+mod copy_into;
+mod explain;
 mod fetch;
+mod file_format;
 mod ilike_any;
 mod like_any;
 mod pivot;
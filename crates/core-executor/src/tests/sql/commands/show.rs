@@ -220,7 +220,7 @@ test_query!(
 test_query!(
     use_role,
     "SHOW VARIABLES",
-    setup_queries = ["USE ROLE test_role"],
+    setup_queries = ["USE ROLE PUBLIC"],
     exclude_columns = ["created_on", "updated_on", "session_id"],
     snapshot_path = "show"
 );
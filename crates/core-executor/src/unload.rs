@@ -0,0 +1,590 @@
+//! `COPY INTO <location>`: unloading query results to CSV, JSON or Parquet files.
+//!
+//! Every output partition of the query plan is written by its own task, rolling
+//! over to a new file once `MAX_FILE_SIZE` is reached. With `PARTITION BY` the rows
+//! of each partition value go to their own sub-directory of the location.
+use crate::csv::CsvLoadOptions;
+use crate::error::{self as ex_error, Result};
+use crate::file_format::FileFormatSpec;
+use crate::query::get_kv_option;
+use core_metastore::FileFormatType;
+use datafusion::arrow::array::{
+    Array, ArrayRef, AsArray, Int64Array, RecordBatch, StringArray, UInt32Array,
+};
+use datafusion::arrow::compute::{cast, take_record_batch};
+use datafusion::arrow::csv::WriterBuilder;
+use datafusion::arrow::datatypes::{DataType, Field, Schema as ArrowSchema, SchemaRef};
+use datafusion::arrow::json::LineDelimitedWriter;
+use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::parquet::arrow::AsyncArrowWriter;
+use datafusion::parquet::basic::Compression;
+use datafusion::parquet::file::properties::WriterProperties;
+use datafusion_physical_plan::SendableRecordBatchStream;
+use futures::TryStreamExt;
+use object_store::buffered::BufWriter;
+use object_store::path::Path;
+use object_store::{ObjectMeta, ObjectStore};
+use snafu::ResultExt;
+use sqlparser::ast::helpers::key_value_options::KeyValueOptions;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// Column holding the `PARTITION BY` value of each row, added to the unloaded plan
+pub const PARTITION_COLUMN: &str = "__unload_partition";
+/// Directory of the rows whose `PARTITION BY` value is NULL
+const NULL_PARTITION: &str = "_NULL_";
+const DEFAULT_MAX_FILE_SIZE: usize = 16 * 1024 * 1024;
+const DEFAULT_FILE_NAME: &str = "data";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnloadFormat {
+    Csv,
+    Json,
+    Parquet,
+}
+
+/// File format and copy options of a `COPY INTO <location>`
+#[derive(Debug, Clone)]
+pub struct UnloadOptions {
+    pub format: UnloadFormat,
+    /// Compression of CSV and JSON files
+    pub compression: FileCompressionType,
+    pub parquet_compression: Compression,
+    pub field_delimiter: u8,
+    pub quote: Option<u8>,
+    /// How NULL values are written to CSV files: the first `NULL_IF` value
+    pub null: String,
+    pub header: bool,
+    pub max_file_size: usize,
+    pub single: bool,
+    pub overwrite: bool,
+    pub detailed_output: bool,
+    /// File extension, compression included
+    extension: String,
+}
+
+impl UnloadOptions {
+    pub fn new(file_format: &FileFormatSpec, copy_options: &KeyValueOptions) -> Result<Self> {
+        let format = match file_format.format_type.unwrap_or_default() {
+            FileFormatType::Csv => UnloadFormat::Csv,
+            FileFormatType::Json => UnloadFormat::Json,
+            FileFormatType::Parquet => UnloadFormat::Parquet,
+            other => {
+                return ex_error::UnsupportedUnloadFormatSnafu {
+                    format: other.to_string(),
+                }
+                .fail();
+            }
+        };
+        let compression_option = file_format
+            .get("compression")
+            .map(str::to_ascii_uppercase)
+            .unwrap_or_else(|| "AUTO".to_string());
+        let invalid_compression = || {
+            ex_error::InvalidFileFormatOptionSnafu {
+                option: "COMPRESSION",
+                value: compression_option.clone(),
+            }
+            .build()
+        };
+
+        // Text formats are gzipped and Parquet files snappy compressed by default
+        let (compression, compression_extension) = match compression_option.as_str() {
+            _ if format == UnloadFormat::Parquet => (FileCompressionType::UNCOMPRESSED, ""),
+            "NONE" => (FileCompressionType::UNCOMPRESSED, ""),
+            "AUTO" | "GZIP" => (FileCompressionType::GZIP, ".gz"),
+            "BZ2" => (FileCompressionType::BZIP2, ".bz2"),
+            "XZ" => (FileCompressionType::XZ, ".xz"),
+            "ZSTD" => (FileCompressionType::ZSTD, ".zst"),
+            _ => return Err(invalid_compression()),
+        };
+        let parquet_compression = match compression_option.as_str() {
+            _ if format != UnloadFormat::Parquet => Compression::UNCOMPRESSED,
+            "NONE" => Compression::UNCOMPRESSED,
+            "AUTO" | "SNAPPY" => Compression::SNAPPY,
+            other => Compression::from_str(other).map_err(|_| invalid_compression())?,
+        };
+        let extension = match format {
+            UnloadFormat::Csv => format!(".csv{compression_extension}"),
+            UnloadFormat::Json => format!(".json{compression_extension}"),
+            UnloadFormat::Parquet if parquet_compression == Compression::UNCOMPRESSED => {
+                ".parquet".to_string()
+            }
+            UnloadFormat::Parquet => format!(
+                ".{}.parquet",
+                parquet_compression.to_string().to_ascii_lowercase()
+            ),
+        };
+
+        let csv = if format == UnloadFormat::Csv {
+            CsvLoadOptions::from_spec(file_format)?.unwrap_or_default()
+        } else {
+            CsvLoadOptions::default()
+        };
+        let field_delimiter = match csv.field_delimiter.as_deref().map(str::as_bytes) {
+            Some([delimiter]) => *delimiter,
+            _ => {
+                return ex_error::InvalidFileFormatOptionSnafu {
+                    option: "FIELD_DELIMITER",
+                    value: csv.field_delimiter.unwrap_or_default(),
+                }
+                .fail();
+            }
+        };
+        let quote = csv
+            .field_optionally_enclosed_by
+            .and_then(|quote| u8::try_from(quote).ok());
+
+        let max_file_size = get_kv_option(copy_options, "MAX_FILE_SIZE")
+            .map(|value| {
+                value.parse().map_err(|_| {
+                    ex_error::InvalidCopyOptionSnafu {
+                        option: "MAX_FILE_SIZE",
+                        value,
+                    }
+                    .build()
+                })
+            })
+            .transpose()?
+            .unwrap_or(DEFAULT_MAX_FILE_SIZE);
+
+        Ok(Self {
+            format,
+            compression,
+            parquet_compression,
+            field_delimiter,
+            quote,
+            null: csv.null_if.into_iter().next().unwrap_or_default(),
+            header: bool_option(copy_options, "HEADER")?,
+            max_file_size,
+            single: bool_option(copy_options, "SINGLE")?,
+            overwrite: bool_option(copy_options, "OVERWRITE")?,
+            detailed_output: bool_option(copy_options, "DETAILED_OUTPUT")?,
+            extension,
+        })
+    }
+}
+
+fn bool_option(copy_options: &KeyValueOptions, option: &str) -> Result<bool> {
+    crate::copy_into::parse_bool_option(option, get_kv_option(copy_options, option))
+}
+
+/// Where unloaded files go: `<dir>/[<partition>/]<name>_0_<task>_<seq><extension>`,
+/// or exactly the location for `SINGLE = TRUE`
+#[derive(Debug)]
+pub struct UnloadTarget {
+    store: Arc<dyn ObjectStore>,
+    dir: String,
+    /// File name, or prefix of the file names
+    name: String,
+    partitioned: bool,
+    pub options: UnloadOptions,
+}
+
+impl UnloadTarget {
+    /// A location ending with `/` is a directory, otherwise its last part is the
+    /// file name (prefix)
+    #[must_use]
+    pub fn new(
+        store: Arc<dyn ObjectStore>,
+        url: &ListingTableUrl,
+        options: UnloadOptions,
+        partitioned: bool,
+    ) -> Self {
+        let prefix = url.prefix().to_string();
+        let (dir, name) = if url.is_collection() {
+            (prefix, DEFAULT_FILE_NAME.to_string())
+        } else {
+            prefix.rsplit_once('/').map_or_else(
+                || (String::new(), prefix.clone()),
+                |(dir, name)| (dir.to_string(), name.to_string()),
+            )
+        };
+        Self {
+            store,
+            dir,
+            name,
+            partitioned,
+            options,
+        }
+    }
+
+    /// Without `OVERWRITE = TRUE` files left by an earlier unload are not replaced
+    pub async fn check_existing_files(&self) -> Result<()> {
+        if self.options.overwrite {
+            return Ok(());
+        }
+        let dir = Path::from(self.dir.as_str());
+        // Partitioned files are one level down, in the partition directories
+        let files: Vec<ObjectMeta> = if self.partitioned {
+            self.store
+                .list(Some(&dir))
+                .try_collect()
+                .await
+                .context(ex_error::ObjectStoreSnafu)?
+        } else {
+            self.store
+                .list_with_delimiter(Some(&dir))
+                .await
+                .context(ex_error::ObjectStoreSnafu)?
+                .objects
+        };
+        let exists = files.iter().any(|file| {
+            file.location
+                .filename()
+                .is_some_and(|name| name.starts_with(&self.name))
+        });
+        if exists {
+            return ex_error::UnloadFilesExistSnafu {
+                destination: self.dir.clone(),
+            }
+            .fail();
+        }
+        Ok(())
+    }
+
+    fn file_prefix(&self, partition: Option<&str>) -> String {
+        [Some(self.dir.as_str()), partition, Some(self.name.as_str())]
+            .into_iter()
+            .flatten()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn file_path(&self, partition: Option<&str>, task: usize, seq: usize) -> Path {
+        let prefix = self.file_prefix(partition);
+        if self.options.single {
+            Path::from(prefix)
+        } else {
+            Path::from(format!("{prefix}_0_{task}_{seq}{}", self.options.extension))
+        }
+    }
+
+    fn open_file(
+        &self,
+        partition: Option<&str>,
+        task: usize,
+        seq: usize,
+        schema: SchemaRef,
+    ) -> Result<OpenFile> {
+        let path = self.file_path(partition, task, seq);
+        let out = BufWriter::new(self.store.clone(), path.clone());
+        let writer = if self.options.format == UnloadFormat::Parquet {
+            let properties = WriterProperties::builder()
+                .set_compression(self.options.parquet_compression)
+                .build();
+            FileWriter::Parquet(
+                AsyncArrowWriter::try_new(out, schema, Some(properties)).context(
+                    ex_error::UnloadParquetWriteSnafu {
+                        file: path.to_string(),
+                    },
+                )?,
+            )
+        } else {
+            FileWriter::Text(
+                self.options
+                    .compression
+                    .convert_async_writer(out)
+                    .context(ex_error::DataFusionSnafu)?,
+            )
+        };
+        Ok(OpenFile {
+            path,
+            writer,
+            rows: 0,
+            bytes: 0,
+        })
+    }
+
+    /// Name of an unloaded file relative to the location
+    fn relative_name(&self, path: &Path) -> String {
+        let path = path.to_string();
+        path.strip_prefix(&self.dir)
+            .map(|name| name.trim_start_matches('/').to_string())
+            .unwrap_or(path)
+    }
+}
+
+enum FileWriter {
+    Text(Box<dyn AsyncWrite + Send + Unpin>),
+    Parquet(AsyncArrowWriter<BufWriter>),
+}
+
+struct OpenFile {
+    path: Path,
+    writer: FileWriter,
+    rows: usize,
+    /// Bytes written before compression
+    bytes: usize,
+}
+
+impl OpenFile {
+    async fn write(&mut self, batch: &RecordBatch, options: &UnloadOptions) -> Result<()> {
+        match &mut self.writer {
+            FileWriter::Text(out) => {
+                let data = serialize_text(batch, options, options.header && self.rows == 0)?;
+                out.write_all(&data)
+                    .await
+                    .context(ex_error::UnloadWriteSnafu {
+                        file: self.path.to_string(),
+                    })?;
+                self.bytes += data.len();
+            }
+            FileWriter::Parquet(writer) => {
+                writer
+                    .write(batch)
+                    .await
+                    .context(ex_error::UnloadParquetWriteSnafu {
+                        file: self.path.to_string(),
+                    })?;
+                self.bytes = writer.bytes_written() + writer.in_progress_size();
+            }
+        }
+        self.rows += batch.num_rows();
+        Ok(())
+    }
+
+    async fn close(self, target: &UnloadTarget) -> Result<UnloadedFile> {
+        let file = self.path.to_string();
+        let input_bytes = match self.writer {
+            FileWriter::Text(mut out) => {
+                out.shutdown()
+                    .await
+                    .context(ex_error::UnloadWriteSnafu { file: file.clone() })?;
+                self.bytes
+            }
+            FileWriter::Parquet(mut writer) => {
+                writer
+                    .finish()
+                    .await
+                    .context(ex_error::UnloadParquetWriteSnafu { file })?;
+                writer.bytes_written()
+            }
+        };
+        let output_bytes = target
+            .store
+            .head(&self.path)
+            .await
+            .context(ex_error::ObjectStoreSnafu)?
+            .size;
+        Ok(UnloadedFile {
+            name: target.relative_name(&self.path),
+            rows: self.rows,
+            input_bytes,
+            output_bytes: usize::try_from(output_bytes).unwrap_or(usize::MAX),
+        })
+    }
+}
+
+/// A file written by an unload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnloadedFile {
+    /// Path relative to the unload location
+    pub name: String,
+    pub rows: usize,
+    pub input_bytes: usize,
+    pub output_bytes: usize,
+}
+
+fn serialize_text(batch: &RecordBatch, options: &UnloadOptions, header: bool) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    if options.format == UnloadFormat::Csv {
+        let mut builder = WriterBuilder::new()
+            .with_header(header)
+            .with_delimiter(options.field_delimiter)
+            .with_null(options.null.clone());
+        if let Some(quote) = options.quote {
+            builder = builder.with_quote(quote);
+        }
+        let mut writer = builder.build(&mut data);
+        writer.write(batch).context(ex_error::ArrowSnafu)?;
+    } else {
+        let mut writer = LineDelimitedWriter::new(&mut data);
+        writer.write(batch).context(ex_error::ArrowSnafu)?;
+        writer.finish().context(ex_error::ArrowSnafu)?;
+    }
+    Ok(data)
+}
+
+/// Splits a batch by the values of its last, `PARTITION BY`, column, which is dropped
+fn split_partitions(batch: &RecordBatch) -> Result<Vec<(Option<String>, RecordBatch)>> {
+    let last = batch.num_columns().saturating_sub(1);
+    let values = cast(batch.column(last), &DataType::Utf8).context(ex_error::ArrowSnafu)?;
+    let values = values.as_string::<i32>();
+    let data = batch
+        .project(&(0..last).collect::<Vec<_>>())
+        .context(ex_error::ArrowSnafu)?;
+
+    let mut partitions: Vec<(Option<String>, Vec<u32>)> = Vec::new();
+    let mut positions: HashMap<Option<&str>, usize> = HashMap::new();
+    for row in 0..values.len() {
+        let value = values.is_valid(row).then(|| values.value(row));
+        let position = *positions.entry(value).or_insert_with(|| {
+            partitions.push((value.map(ToString::to_string), Vec::new()));
+            partitions.len() - 1
+        });
+        partitions[position]
+            .1
+            .push(u32::try_from(row).unwrap_or(u32::MAX));
+    }
+    partitions
+        .into_iter()
+        .map(|(value, rows)| {
+            take_record_batch(&data, &UInt32Array::from(rows))
+                .map(|batch| (value, batch))
+                .context(ex_error::ArrowSnafu)
+        })
+        .collect()
+}
+
+/// Writes one output partition of the unloaded plan, returning the files written
+pub async fn unload_stream(
+    mut stream: SendableRecordBatchStream,
+    target: Arc<UnloadTarget>,
+    task: usize,
+) -> Result<Vec<UnloadedFile>> {
+    let mut open_files: HashMap<Option<String>, OpenFile> = HashMap::new();
+    let mut next_seq: HashMap<Option<String>, usize> = HashMap::new();
+    let mut unloaded = Vec::new();
+    while let Some(batch) = stream.try_next().await.context(ex_error::DataFusionSnafu)? {
+        let partitions = if target.partitioned {
+            split_partitions(&batch)?
+        } else {
+            vec![(None, batch)]
+        };
+        for (partition, batch) in partitions {
+            if batch.num_rows() == 0 {
+                continue;
+            }
+            let file = match open_files.entry(partition.clone()) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let seq = next_seq.entry(partition.clone()).or_default();
+                    let directory = target
+                        .partitioned
+                        .then(|| partition.as_deref().unwrap_or(NULL_PARTITION));
+                    let file = target.open_file(directory, task, *seq, batch.schema())?;
+                    *seq += 1;
+                    entry.insert(file)
+                }
+            };
+            file.write(&batch, &target.options).await?;
+            if !target.options.single && file.bytes >= target.options.max_file_size {
+                if let Some(file) = open_files.remove(&partition) {
+                    unloaded.push(file.close(&target).await?);
+                }
+            }
+        }
+    }
+    for file in open_files.into_values() {
+        unloaded.push(file.close(&target).await?);
+    }
+    Ok(unloaded)
+}
+
+fn to_i64(value: usize) -> i64 {
+    i64::try_from(value).unwrap_or(i64::MAX)
+}
+
+/// Snowflake's `COPY INTO <location>` result: totals, or one row per file
+/// with `DETAILED_OUTPUT = TRUE`
+pub fn unload_results_batch(files: &[UnloadedFile], detailed: bool) -> Result<RecordBatch> {
+    if detailed {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("file_name", DataType::Utf8, false),
+            Field::new("file_size", DataType::Int64, false),
+            Field::new("row_count", DataType::Int64, false),
+        ]));
+        return RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from_iter_values(
+                    files.iter().map(|file| file.name.clone()),
+                )),
+                Arc::new(Int64Array::from_iter_values(
+                    files.iter().map(|file| to_i64(file.output_bytes)),
+                )),
+                Arc::new(Int64Array::from_iter_values(
+                    files.iter().map(|file| to_i64(file.rows)),
+                )),
+            ],
+        )
+        .context(ex_error::ArrowSnafu);
+    }
+    let schema = Arc::new(ArrowSchema::new(vec![
+        Field::new("rows_unloaded", DataType::Int64, false),
+        Field::new("input_bytes", DataType::Int64, false),
+        Field::new("output_bytes", DataType::Int64, false),
+    ]));
+    let total = |value: fn(&UnloadedFile) -> usize| -> ArrayRef {
+        Arc::new(Int64Array::from(vec![to_i64(
+            files.iter().map(value).sum(),
+        )]))
+    };
+    RecordBatch::try_new(
+        schema,
+        vec![
+            total(|file| file.rows),
+            total(|file| file.input_bytes),
+            total(|file| file.output_bytes),
+        ],
+    )
+    .context(ex_error::ArrowSnafu)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::Int32Array;
+    use datafusion::arrow::datatypes::Int32Type;
+
+    #[test]
+    fn test_split_partitions() {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new(PARTITION_COLUMN, DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3, 4])),
+                Arc::new(StringArray::from(vec![
+                    Some("a"),
+                    None,
+                    Some("a"),
+                    Some("b"),
+                ])),
+            ],
+        )
+        .unwrap();
+        let partitions = split_partitions(&batch).unwrap();
+        let partitions = partitions
+            .iter()
+            .map(|(value, batch)| {
+                (
+                    value.clone(),
+                    batch.num_columns(),
+                    batch
+                        .column(0)
+                        .as_primitive::<Int32Type>()
+                        .values()
+                        .to_vec(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            partitions,
+            vec![
+                (Some("a".to_string()), 1, vec![1, 3]),
+                (None, 1, vec![2]),
+                (Some("b".to_string()), 1, vec![4]),
+            ]
+        );
+    }
+}
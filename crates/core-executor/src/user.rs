@@ -0,0 +1,468 @@
+//! User management statements.
+//!
+//! `CREATE / ALTER / DROP USER`, `SHOW USERS` and the programmatic access token
+//! statements are recognized here, before the
+//! regular parsing step, like `FILE FORMAT` statements, and executed against the
+//! metastore user store by `UserQuery::user_query`.
+use crate::error::{self as ex_error, Result};
+use crate::file_format::is_word;
+use core_metastore::{User, normalize_public_key};
+use datafusion::sql::sqlparser::ast::Ident;
+use datafusion::sql::sqlparser::dialect::{Dialect, SnowflakeDialect, dialect_from_str};
+use datafusion::sql::sqlparser::keywords::Keyword;
+use datafusion::sql::sqlparser::parser::{Parser, ParserError};
+use datafusion::sql::sqlparser::tokenizer::Token;
+use snafu::ResultExt;
+
+const PASSWORD_PROPERTY: &str = "PASSWORD";
+const DISABLED_PROPERTY: &str = "DISABLED";
+const DEFAULT_ROLE_PROPERTY: &str = "DEFAULT_ROLE";
+const DEFAULT_NAMESPACE_PROPERTY: &str = "DEFAULT_NAMESPACE";
+const COMMENT_PROPERTY: &str = "COMMENT";
+const RSA_PUBLIC_KEY_PROPERTY: &str = "RSA_PUBLIC_KEY";
+const RSA_PUBLIC_KEY_2_PROPERTY: &str = "RSA_PUBLIC_KEY_2";
+
+/// Days a programmatic access token is valid for, unless `DAYS_TO_EXPIRY` is given
+pub const DEFAULT_ACCESS_TOKEN_DAYS: i64 = 15;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserStatement {
+    Create {
+        name: Ident,
+        or_replace: bool,
+        if_not_exists: bool,
+        properties: Vec<(String, String)>,
+    },
+    Alter {
+        name: Ident,
+        if_exists: bool,
+        operation: AlterUserOperation,
+    },
+    Drop {
+        name: Ident,
+        if_exists: bool,
+    },
+    Show {
+        like: Option<String>,
+    },
+    /// `SHOW USER PROGRAMMATIC ACCESS TOKENS [FOR USER name]`
+    ShowAccessTokens {
+        user: Option<Ident>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlterUserOperation {
+    Set(Vec<(String, String)>),
+    Unset(Vec<String>),
+    RenameTo(Ident),
+    /// `ADD PROGRAMMATIC ACCESS TOKEN name [DAYS_TO_EXPIRY = n] [ROLE_RESTRICTION = role] [COMMENT = '...']`
+    AddAccessToken {
+        name: Ident,
+        days_to_expiry: i64,
+        role_restriction: Option<String>,
+        comment: Option<String>,
+    },
+    RemoveAccessToken(Ident),
+}
+
+/// Applies a `CREATE USER` / `ALTER USER ... SET` property to the user
+pub fn set_user_property(user: &mut User, property: &str, value: &str) -> Result<()> {
+    match property {
+        PASSWORD_PROPERTY => user.set_password(value).context(ex_error::MetastoreSnafu)?,
+        DISABLED_PROPERTY => {
+            user.disabled = match value.to_ascii_uppercase().as_str() {
+                "TRUE" => true,
+                "FALSE" => false,
+                _ => {
+                    return ex_error::InvalidUserPropertySnafu { property, value }.fail();
+                }
+            }
+        }
+        DEFAULT_ROLE_PROPERTY => user.default_role = Some(value.to_string()),
+        DEFAULT_NAMESPACE_PROPERTY => {
+            let (database, schema) = match value.split_once('.') {
+                Some((database, schema)) => (database, Some(schema.to_string())),
+                None => (value, None),
+            };
+            user.default_database = Some(database.to_string());
+            user.default_schema = schema;
+        }
+        COMMENT_PROPERTY => user.comment = Some(value.to_string()),
+        RSA_PUBLIC_KEY_PROPERTY => user.rsa_public_key = Some(normalize_public_key(value)),
+        RSA_PUBLIC_KEY_2_PROPERTY => user.rsa_public_key_2 = Some(normalize_public_key(value)),
+        _ => return ex_error::UnsupportedUserPropertySnafu { property }.fail(),
+    }
+    Ok(())
+}
+
+/// Resets an `ALTER USER ... UNSET` property to its default
+pub fn unset_user_property(user: &mut User, property: &str) -> Result<()> {
+    match property {
+        PASSWORD_PROPERTY => user.password_hash = None,
+        DISABLED_PROPERTY => user.disabled = false,
+        DEFAULT_ROLE_PROPERTY => user.default_role = None,
+        DEFAULT_NAMESPACE_PROPERTY => {
+            user.default_database = None;
+            user.default_schema = None;
+        }
+        COMMENT_PROPERTY => user.comment = None,
+        RSA_PUBLIC_KEY_PROPERTY => user.rsa_public_key = None,
+        RSA_PUBLIC_KEY_2_PROPERTY => user.rsa_public_key_2 = None,
+        _ => return ex_error::UnsupportedUserPropertySnafu { property }.fail(),
+    }
+    Ok(())
+}
+
+/// Recognizes user statements. Returns `Ok(None)` for any other SQL, so
+/// it can be handed over to the regular parser unchanged.
+pub fn parse_user_statement(sql: &str, dialect: &str) -> Result<Option<UserStatement>> {
+    let dialect: Box<dyn Dialect> =
+        dialect_from_str(dialect).unwrap_or_else(|| Box::new(SnowflakeDialect {}));
+    let Ok(mut parser) = Parser::new(dialect.as_ref()).try_with_sql(sql) else {
+        return Ok(None);
+    };
+
+    let statement = if parser.parse_keyword(Keyword::CREATE) {
+        let or_replace = parser.parse_keywords(&[Keyword::OR, Keyword::REPLACE]);
+        if !parse_word(&mut parser, "USER") {
+            return Ok(None);
+        }
+        let if_not_exists = parser.parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+        let name = parser
+            .parse_identifier()
+            .context(ex_error::SqlParserSnafu)?;
+        let properties = parse_properties(&mut parser).context(ex_error::SqlParserSnafu)?;
+        UserStatement::Create {
+            name,
+            or_replace,
+            if_not_exists,
+            properties,
+        }
+    } else if parser.parse_keyword(Keyword::ALTER) {
+        if !parse_word(&mut parser, "USER") {
+            return Ok(None);
+        }
+        parse_alter(&mut parser).context(ex_error::SqlParserSnafu)?
+    } else if parser.parse_keyword(Keyword::DROP) {
+        if !parse_word(&mut parser, "USER") {
+            return Ok(None);
+        }
+        let if_exists = parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+        let name = parser
+            .parse_identifier()
+            .context(ex_error::SqlParserSnafu)?;
+        UserStatement::Drop { name, if_exists }
+    } else if parser.parse_keyword(Keyword::SHOW) {
+        if parse_word(&mut parser, "USER") {
+            if !(parse_word(&mut parser, "PROGRAMMATIC")
+                && parse_word(&mut parser, "ACCESS")
+                && parse_word(&mut parser, "TOKENS"))
+            {
+                return Ok(None);
+            }
+            let user = if parser.parse_keyword(Keyword::FOR) && parse_word(&mut parser, "USER") {
+                Some(
+                    parser
+                        .parse_identifier()
+                        .context(ex_error::SqlParserSnafu)?,
+                )
+            } else {
+                None
+            };
+            return finish_statement(&mut parser, UserStatement::ShowAccessTokens { user });
+        }
+        if !parse_word(&mut parser, "USERS") {
+            return Ok(None);
+        }
+        let like = if parser.parse_keyword(Keyword::LIKE) {
+            Some(
+                parser
+                    .parse_literal_string()
+                    .context(ex_error::SqlParserSnafu)?,
+            )
+        } else {
+            None
+        };
+        UserStatement::Show { like }
+    } else {
+        return Ok(None);
+    };
+    finish_statement(&mut parser, statement)
+}
+
+fn finish_statement(
+    parser: &mut Parser,
+    statement: UserStatement,
+) -> Result<Option<UserStatement>> {
+    let _ = parser.consume_token(&Token::SemiColon);
+    if parser.peek_token().token != Token::EOF {
+        return parser
+            .expected("end of statement", parser.peek_token())
+            .context(ex_error::SqlParserSnafu);
+    }
+    Ok(Some(statement))
+}
+
+/// `PROGRAMMATIC ACCESS TOKEN` or its `PAT` abbreviation
+fn parse_access_token_keywords(parser: &mut Parser) -> bool {
+    parse_word(parser, "PAT")
+        || (parse_word(parser, "PROGRAMMATIC")
+            && parse_word(parser, "ACCESS")
+            && parse_word(parser, "TOKEN"))
+}
+
+pub(crate) fn parse_word(parser: &mut Parser, word: &str) -> bool {
+    if is_word(&parser.peek_token().token, word) {
+        parser.next_token();
+        true
+    } else {
+        false
+    }
+}
+
+fn parse_alter(parser: &mut Parser) -> std::result::Result<UserStatement, ParserError> {
+    let if_exists = parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+    let name = parser.parse_identifier()?;
+    let operation = if parser.parse_keyword(Keyword::SET) {
+        AlterUserOperation::Set(parse_properties(parser)?)
+    } else if parser.parse_keyword(Keyword::UNSET) {
+        let mut properties = Vec::new();
+        loop {
+            properties.push(parser.parse_identifier()?.value.to_ascii_uppercase());
+            if !parser.consume_token(&Token::Comma) {
+                break;
+            }
+        }
+        AlterUserOperation::Unset(properties)
+    } else if parser.parse_keywords(&[Keyword::RENAME, Keyword::TO]) {
+        AlterUserOperation::RenameTo(parser.parse_identifier()?)
+    } else if parse_word(parser, "ADD") && parse_access_token_keywords(parser) {
+        let name = parser.parse_identifier()?;
+        let mut days_to_expiry = DEFAULT_ACCESS_TOKEN_DAYS;
+        let mut role_restriction = None;
+        let mut comment = None;
+        for (property, value) in parse_properties(parser)? {
+            match property.as_str() {
+                "DAYS_TO_EXPIRY" => {
+                    days_to_expiry = value.parse().map_err(|_| {
+                        ParserError::ParserError(format!("Invalid DAYS_TO_EXPIRY value {value}"))
+                    })?;
+                }
+                "ROLE_RESTRICTION" => role_restriction = Some(value),
+                COMMENT_PROPERTY => comment = Some(value),
+                _ => {
+                    return Err(ParserError::ParserError(format!(
+                        "Unsupported access token property {property}"
+                    )));
+                }
+            }
+        }
+        AlterUserOperation::AddAccessToken {
+            name,
+            days_to_expiry,
+            role_restriction,
+            comment,
+        }
+    } else if parse_word(parser, "REMOVE") && parse_access_token_keywords(parser) {
+        AlterUserOperation::RemoveAccessToken(parser.parse_identifier()?)
+    } else {
+        return parser.expected(
+            "SET, UNSET, RENAME TO, ADD or REMOVE PROGRAMMATIC ACCESS TOKEN",
+            parser.peek_token(),
+        );
+    };
+    Ok(UserStatement::Alter {
+        name,
+        if_exists,
+        operation,
+    })
+}
+
+/// Parses a whitespace or comma separated list of `NAME = value` properties,
+/// where value is a string, a number or a bare word.
+fn parse_properties(
+    parser: &mut Parser,
+) -> std::result::Result<Vec<(String, String)>, ParserError> {
+    let mut properties = Vec::new();
+    loop {
+        let _ = parser.consume_token(&Token::Comma);
+        let token = parser.peek_token();
+        let name = match &token.token {
+            Token::EOF | Token::SemiColon => break,
+            Token::Word(w) => w.value.to_ascii_uppercase(),
+            _ => return parser.expected("user property", token),
+        };
+        parser.next_token();
+        parser.expect_token(&Token::Eq)?;
+
+        let token = parser.next_token();
+        let value = match token.token {
+            Token::SingleQuotedString(s) | Token::DoubleQuotedString(s) => s,
+            Token::Number(n, _) => n,
+            Token::Word(w) => w.value,
+            _ => return parser.expected("user property value", token),
+        };
+        properties.push((name, value));
+    }
+    Ok(properties)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn parse(sql: &str) -> Option<UserStatement> {
+        parse_user_statement(sql, "snowflake").unwrap()
+    }
+
+    #[test]
+    fn test_parse_create_user() {
+        assert_eq!(
+            parse(
+                "CREATE OR REPLACE USER IF NOT EXISTS alice PASSWORD = 'secret' \
+                 DEFAULT_ROLE = analyst DEFAULT_NAMESPACE = 'db.sch' DISABLED = FALSE;"
+            ),
+            Some(UserStatement::Create {
+                name: Ident::new("alice"),
+                or_replace: true,
+                if_not_exists: true,
+                properties: vec![
+                    ("PASSWORD".to_string(), "secret".to_string()),
+                    ("DEFAULT_ROLE".to_string(), "analyst".to_string()),
+                    ("DEFAULT_NAMESPACE".to_string(), "db.sch".to_string()),
+                    ("DISABLED".to_string(), "FALSE".to_string()),
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_other_user_statements() {
+        assert_eq!(
+            parse("ALTER USER IF EXISTS alice SET DISABLED = TRUE, COMMENT = 'left'"),
+            Some(UserStatement::Alter {
+                name: Ident::new("alice"),
+                if_exists: true,
+                operation: AlterUserOperation::Set(vec![
+                    ("DISABLED".to_string(), "TRUE".to_string()),
+                    ("COMMENT".to_string(), "left".to_string()),
+                ]),
+            })
+        );
+        assert_eq!(
+            parse("alter user alice unset default_role, comment"),
+            Some(UserStatement::Alter {
+                name: Ident::new("alice"),
+                if_exists: false,
+                operation: AlterUserOperation::Unset(vec![
+                    "DEFAULT_ROLE".to_string(),
+                    "COMMENT".to_string(),
+                ]),
+            })
+        );
+        assert_eq!(
+            parse("ALTER USER alice RENAME TO bob"),
+            Some(UserStatement::Alter {
+                name: Ident::new("alice"),
+                if_exists: false,
+                operation: AlterUserOperation::RenameTo(Ident::new("bob")),
+            })
+        );
+        assert_eq!(
+            parse("DROP USER IF EXISTS alice"),
+            Some(UserStatement::Drop {
+                name: Ident::new("alice"),
+                if_exists: true,
+            })
+        );
+        assert_eq!(
+            parse("SHOW USERS LIKE 'a%'"),
+            Some(UserStatement::Show {
+                like: Some("a%".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_access_token_statements() {
+        assert_eq!(
+            parse(
+                "ALTER USER alice ADD PROGRAMMATIC ACCESS TOKEN ci \
+                 DAYS_TO_EXPIRY = 30 ROLE_RESTRICTION = 'ANALYST' COMMENT = 'ci'"
+            ),
+            Some(UserStatement::Alter {
+                name: Ident::new("alice"),
+                if_exists: false,
+                operation: AlterUserOperation::AddAccessToken {
+                    name: Ident::new("ci"),
+                    days_to_expiry: 30,
+                    role_restriction: Some("ANALYST".to_string()),
+                    comment: Some("ci".to_string()),
+                },
+            })
+        );
+        assert_eq!(
+            parse("ALTER USER alice ADD PAT ci"),
+            Some(UserStatement::Alter {
+                name: Ident::new("alice"),
+                if_exists: false,
+                operation: AlterUserOperation::AddAccessToken {
+                    name: Ident::new("ci"),
+                    days_to_expiry: DEFAULT_ACCESS_TOKEN_DAYS,
+                    role_restriction: None,
+                    comment: None,
+                },
+            })
+        );
+        assert_eq!(
+            parse("ALTER USER alice REMOVE PROGRAMMATIC ACCESS TOKEN ci"),
+            Some(UserStatement::Alter {
+                name: Ident::new("alice"),
+                if_exists: false,
+                operation: AlterUserOperation::RemoveAccessToken(Ident::new("ci")),
+            })
+        );
+        assert_eq!(
+            parse("SHOW USER PROGRAMMATIC ACCESS TOKENS FOR USER alice"),
+            Some(UserStatement::ShowAccessTokens {
+                user: Some(Ident::new("alice")),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_not_user_statement() {
+        assert_eq!(parse("CREATE TABLE users (a INT)"), None);
+        assert_eq!(parse("SHOW TABLES"), None);
+        assert_eq!(parse("SELECT CURRENT_USER()"), None);
+        assert!(parse_user_statement("ALTER USER alice RESET PASSWORD", "snowflake").is_err());
+    }
+
+    #[test]
+    fn test_set_user_property() {
+        let mut user = User::new("alice".to_string());
+        set_user_property(&mut user, "DEFAULT_NAMESPACE", "db.sch").unwrap();
+        set_user_property(&mut user, "DISABLED", "true").unwrap();
+        set_user_property(&mut user, "PASSWORD", "secret").unwrap();
+        assert_eq!(user.default_database.as_deref(), Some("db"));
+        assert_eq!(user.default_schema.as_deref(), Some("sch"));
+        assert!(user.disabled);
+        assert!(user.verify_password("secret"));
+        assert!(set_user_property(&mut user, "DISABLED", "maybe").is_err());
+        assert!(set_user_property(&mut user, "EMAIL", "a@b.c").is_err());
+        set_user_property(
+            &mut user,
+            "RSA_PUBLIC_KEY",
+            "-----BEGIN PUBLIC KEY-----\nMIIB\nIjAN\n-----END PUBLIC KEY-----",
+        )
+        .unwrap();
+        assert_eq!(user.rsa_public_key.as_deref(), Some("MIIBIjAN"));
+
+        unset_user_property(&mut user, "PASSWORD").unwrap();
+        unset_user_property(&mut user, "DEFAULT_NAMESPACE").unwrap();
+        assert!(!user.verify_password("secret"));
+        assert_eq!(user.default_database, None);
+    }
+}
@@ -2,7 +2,9 @@ use super::models::QueryResult;
 use crate::error::{ArrowSnafu, CantCastToSnafu, Result};
 use chrono::{DateTime, FixedOffset, Offset, TimeZone};
 use clap::ValueEnum;
+use core_metastore::FileFormatIdent as MetastoreFileFormatIdent;
 use core_metastore::SchemaIdent as MetastoreSchemaIdent;
+use core_metastore::StageIdent as MetastoreStageIdent;
 use core_metastore::TableIdent as MetastoreTableIdent;
 use datafusion::arrow::array::timezone::Tz;
 use datafusion::arrow::array::{
@@ -19,16 +21,21 @@ use datafusion::arrow::datatypes::{Field, Schema, TimeUnit};
 use datafusion::arrow::error::ArrowError;
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::common::ScalarValue;
+use datafusion::sql::parser::Statement as DFStatement;
 use datafusion_common::{ResolvedTableReference, TableReference};
 use datafusion_expr::{Expr, LogicalPlan};
 use embucket_functions::conversion::to_timestamp::parse_timezone;
 use snafu::{OptionExt, ResultExt};
-use sqlparser::ast::{Ident, ObjectName};
+use sqlparser::ast::{Ident, ObjectName, Statement};
+use sqlparser::dialect::SnowflakeDialect;
+use sqlparser::tokenizer::{Token, Tokenizer};
 use std::collections::HashMap;
 use std::sync::Arc;
 use strum::{Display, EnumString};
 
 pub static DEFAULT_QUERY_HISTORY_ROWS_LIMIT: usize = 50;
+pub static DEFAULT_RESULT_SPOOL_MEMORY_MB: usize = 64;
+pub static DEFAULT_RESULT_CACHE_MEMORY_MB: usize = 16;
 
 #[derive(Clone, Debug)]
 pub struct Config {
@@ -43,6 +50,12 @@ pub struct Config {
     pub mem_enable_track_consumers_pool: Option<bool>,
     pub disk_pool_size_mb: Option<usize>,
     pub query_history_rows_limit: usize,
+    /// Memory a streamed result may take while its client is not reading it,
+    /// further batches are spooled to disk
+    pub result_spool_memory_mb: usize,
+    /// Memory taken by the entries of the result cache, least recently used entries are
+    /// evicted over it
+    pub result_cache_memory_mb: usize,
     pub use_duck_db: bool,
     pub use_duck_db_explain: bool,
 }
@@ -60,6 +73,8 @@ impl Default for Config {
             mem_enable_track_consumers_pool: None,
             disk_pool_size_mb: None,
             query_history_rows_limit: DEFAULT_QUERY_HISTORY_ROWS_LIMIT,
+            result_spool_memory_mb: DEFAULT_RESULT_SPOOL_MEMORY_MB,
+            result_cache_memory_mb: DEFAULT_RESULT_CACHE_MEMORY_MB,
             use_duck_db: false,
             use_duck_db_explain: false,
         }
@@ -93,6 +108,18 @@ impl Config {
         self.query_history_rows_limit = limit;
         self
     }
+
+    #[must_use]
+    pub const fn with_result_spool_memory_mb(mut self, memory_mb: usize) -> Self {
+        self.result_spool_memory_mb = memory_mb;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_result_cache_memory_mb(mut self, memory_mb: usize) -> Self {
+        self.result_cache_memory_mb = memory_mb;
+        self
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, EnumString, Debug, Display, Default)]
@@ -845,6 +872,28 @@ impl From<NormalizedIdent> for MetastoreSchemaIdent {
     }
 }
 
+impl From<NormalizedIdent> for MetastoreFileFormatIdent {
+    fn from(ident: NormalizedIdent) -> Self {
+        let ident = ident.0;
+        Self {
+            name: ident[2].value.clone(),
+            schema: ident[1].value.clone(),
+            database: ident[0].value.clone(),
+        }
+    }
+}
+
+impl From<NormalizedIdent> for MetastoreStageIdent {
+    fn from(ident: NormalizedIdent) -> Self {
+        let ident = ident.0;
+        Self {
+            name: ident[2].value.clone(),
+            schema: ident[1].value.clone(),
+            database: ident[0].value.clone(),
+        }
+    }
+}
+
 impl From<NormalizedIdent> for ObjectName {
     fn from(ident: NormalizedIdent) -> Self {
         Self::from(ident.0)
@@ -863,6 +912,73 @@ impl std::fmt::Display for NormalizedIdent {
     }
 }
 
+/// Splits a script into its statements at the `;` separators outside of literals,
+/// identifiers and comments, skipping empty statements. Literals are kept escaped,
+/// the statements are rebuilt from their tokens.
+#[must_use]
+pub fn split_statements(sql: &str) -> Vec<String> {
+    let Ok(tokens) = Tokenizer::new(&SnowflakeDialect {}, sql)
+        .with_unescape(false)
+        .tokenize()
+    else {
+        // Left to the parser to report
+        return vec![sql.to_string()];
+    };
+    tokens
+        .split(|token| *token == Token::SemiColon)
+        .filter(|tokens| {
+            tokens
+                .iter()
+                .any(|token| !matches!(token, Token::Whitespace(_)))
+        })
+        .map(|tokens| {
+            tokens
+                .iter()
+                .map(ToString::to_string)
+                .collect::<String>()
+                .trim()
+                .to_string()
+        })
+        .collect()
+}
+
+/// Snowflake statement type id of a parsed statement, which drivers use to tell
+/// statements returning rows from DML (returning the number of affected rows)
+/// and other statements
+#[must_use]
+pub fn statement_type_id(statement: &DFStatement) -> u32 {
+    let statement = match statement {
+        DFStatement::Statement(statement) => statement,
+        DFStatement::Explain(_) => return 0x1000,
+        DFStatement::CopyTo(_) => return 0x3000,
+        _ => return 0x6000,
+    };
+    match &**statement {
+        Statement::Query { .. }
+        | Statement::Explain { .. }
+        | Statement::ExplainTable { .. }
+        | Statement::ShowTables { .. }
+        | Statement::ShowColumns { .. }
+        | Statement::ShowViews { .. }
+        | Statement::ShowObjects { .. }
+        | Statement::ShowSchemas { .. }
+        | Statement::ShowDatabases { .. }
+        | Statement::ShowFunctions { .. }
+        | Statement::ShowVariable { .. }
+        | Statement::ShowVariables { .. } => 0x1000,
+        Statement::Insert { .. } => 0x3100,
+        Statement::Update { .. } => 0x3200,
+        Statement::Delete { .. } => 0x3300,
+        Statement::Merge { .. } => 0x3400,
+        Statement::CopyIntoSnowflake { .. } | Statement::Truncate { .. } => 0x3000,
+        Statement::Use { .. } | Statement::Set { .. } | Statement::AlterSession { .. } => 0x4000,
+        Statement::StartTransaction { .. }
+        | Statement::Commit { .. }
+        | Statement::Rollback { .. } => 0x5000,
+        _ => 0x6000,
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used, clippy::as_conversions, clippy::expect_used)]
 mod tests {
@@ -1412,4 +1528,48 @@ mod tests {
             .collect::<Vec<_>>();
         assert_eq!(row, [1, 2]);
     }
+
+    #[test]
+    fn test_split_statements() {
+        assert_eq!(
+            split_statements("SELECT 1; SELECT ';' AS \"a;b\" -- c;\n;\n/* ; */ SELECT 2;"),
+            [
+                "SELECT 1",
+                "SELECT ';' AS \"a;b\" -- c;",
+                "/* ; */ SELECT 2"
+            ]
+        );
+        assert_eq!(
+            split_statements("CREATE FUNCTION f() AS $$ 1; 2 $$"),
+            ["CREATE FUNCTION f() AS $$ 1; 2 $$"]
+        );
+        assert!(split_statements(" ; -- nothing").is_empty());
+        assert_eq!(
+            split_statements("INSERT INTO t VALUES ('O''Brien'); SELECT 1"),
+            ["INSERT INTO t VALUES ('O''Brien')", "SELECT 1"]
+        );
+    }
+
+    #[test]
+    fn test_statement_type_id() {
+        let type_id = |sql| {
+            let mut statements = datafusion::sql::parser::DFParser::parse_sql_with_dialect(
+                sql,
+                &SnowflakeDialect {},
+            )
+            .unwrap();
+            statement_type_id(&statements.pop_front().unwrap())
+        };
+        assert_eq!(
+            type_id("/* insert */ WITH t AS (SELECT 1) SELECT * FROM t"),
+            0x1000
+        );
+        assert_eq!(type_id("SHOW TABLES"), 0x1000);
+        assert_eq!(type_id("INSERT INTO t VALUES (1)"), 0x3100);
+        assert_eq!(type_id("DELETE FROM t"), 0x3300);
+        assert_eq!(type_id("SET x = 1"), 0x4000);
+        assert_eq!(type_id("ALTER SESSION SET TIMEZONE = 'UTC'"), 0x4000);
+        assert_eq!(type_id("COMMIT"), 0x5000);
+        assert_eq!(type_id("CREATE TABLE t (a INT)"), 0x6000);
+    }
 }
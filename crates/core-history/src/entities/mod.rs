@@ -1,6 +1,7 @@
 pub mod query;
 pub mod query_id;
 pub mod query_id_param;
+pub mod query_profile;
 pub mod result_set;
 pub mod worksheet;
 pub mod worksheet_query_ref;
@@ -8,6 +9,7 @@ pub mod worksheet_query_ref;
 pub use query::*;
 pub use query_id::*;
 pub use query_id_param::*;
+pub use query_profile::*;
 pub use result_set::*;
 pub use worksheet::*;
 pub use worksheet_query_ref::*;
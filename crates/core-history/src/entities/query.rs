@@ -59,6 +59,9 @@ impl FromStr for QueryStatus {
 pub struct QueryRecord {
     pub id: QueryRecordId,
     pub worksheet_id: Option<WorksheetId>,
+    /// User the query was run by, `None` for the queries of anonymous sessions
+    #[serde(default)]
+    pub user: Option<String>,
     pub query: String,
     pub start_time: DateTime<Utc>,
     pub end_time: DateTime<Utc>,
@@ -78,6 +81,7 @@ impl QueryRecord {
         Self {
             id: Self::inverted_id(QueryRecordId(start_time.timestamp_micros())),
             worksheet_id,
+            user: None,
             query: String::from(query),
             start_time,
             end_time: start_time,
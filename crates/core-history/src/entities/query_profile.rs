@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+/// Statistics of an operator of the physical plan a query has executed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperatorStats {
+    /// Position of the operator in the pre-order traversal of the plan, the root is 0
+    pub operator_id: usize,
+    pub parent_operator_id: Option<usize>,
+    /// Name of the operator, like `ProjectionExec`
+    pub operator_type: String,
+    /// One line description of the operator, as shown by `EXPLAIN`
+    pub operator_attributes: String,
+    pub output_rows: Option<usize>,
+    pub elapsed_compute_ns: Option<usize>,
+    pub spilled_bytes: Option<usize>,
+    pub memory_peak_bytes: Option<usize>,
+}
@@ -97,6 +97,27 @@ pub enum Error {
         location: Location,
     },
 
+    #[snafu(display("Error adding query profile: {source}"))]
+    QueryProfileAdd {
+        source: core_utils::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Error getting query profile: {source}"))]
+    QueryProfileGet {
+        source: core_utils::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("No profile for query: {}", query_id.as_uuid()))]
+    QueryProfileNotFound {
+        query_id: QueryRecordId,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
     #[snafu(display("Error adding query record reference: {source}"))]
     QueryReferenceAdd {
         source: core_utils::Error,
@@ -147,6 +168,14 @@ pub enum Error {
         location: Location,
     },
 
+    #[snafu(display("Serialize error: {error}"))]
+    SerializeValue {
+        #[snafu(source)]
+        error: serde_json::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
     #[snafu(display("Deserialize error: {error}"))]
     DeserializeValue {
         #[snafu(source)]
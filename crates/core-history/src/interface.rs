@@ -1,6 +1,6 @@
 use crate::ResultSet;
 use crate::errors::Result;
-use crate::{QueryRecord, QueryRecordId, QueryStatus, Worksheet, WorksheetId};
+use crate::{OperatorStats, QueryRecord, QueryRecordId, QueryStatus, Worksheet, WorksheetId};
 use async_trait::async_trait;
 
 #[derive(Debug, Clone)]
@@ -84,4 +84,12 @@ pub trait HistoryStore: std::fmt::Debug + Send + Sync {
     fn new_query_record(&self, query: &str, worksheet_id: Option<WorksheetId>) -> QueryRecord;
     async fn save_query_record(&self, query_record: &QueryRecord, result_set: Option<ResultSet>);
     async fn get_query_result(&self, query_record_id: QueryRecordId) -> Result<ResultSet>;
+    /// Stores statistics of the operators of the plan executed by the query
+    async fn add_query_profile(
+        &self,
+        query_record_id: QueryRecordId,
+        operator_stats: &[OperatorStats],
+    ) -> Result<()>;
+    async fn get_query_profile(&self, query_record_id: QueryRecordId)
+    -> Result<Vec<OperatorStats>>;
 }
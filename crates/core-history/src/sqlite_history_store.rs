@@ -1,7 +1,7 @@
 use crate::ResultSet;
 use crate::errors::{self as history_err, Result};
 use crate::interface::{GetQueriesParams, HistoryStore};
-use crate::{QueryRecord, QueryRecordId, QueryStatus, Worksheet, WorksheetId};
+use crate::{OperatorStats, QueryRecord, QueryRecordId, QueryStatus, Worksheet, WorksheetId};
 use async_trait::async_trait;
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
@@ -45,9 +45,24 @@ CREATE TABLE IF NOT EXISTS queries (
     status TEXT NOT NULL,               -- enum as TEXT
     error TEXT,                         -- nullable
     diagnostic_error TEXT,              -- nullable
+    user TEXT,                          -- nullable
     FOREIGN KEY (worksheet_id) REFERENCES worksheets (id) ON DELETE SET NULL
 );";
 
+const QUERY_PROFILES_CREATE_TABLE: &str = "
+CREATE TABLE IF NOT EXISTS query_profiles (
+    id TEXT PRIMARY KEY,                -- table : queries.id
+    operator_stats TEXT NOT NULL        -- serialized Vec<OperatorStats>
+);";
+
+// Profiles are deleted along with their queries, foreign keys are not enforced
+const QUERY_PROFILES_DELETE_TRIGGER: &str = "
+CREATE TRIGGER IF NOT EXISTS query_profiles_delete
+AFTER DELETE ON queries
+BEGIN
+    DELETE FROM query_profiles WHERE id = OLD.id;
+END;";
+
 const WORKSHEET_ADD: &str = "
 INSERT INTO worksheets (id, name, content, created_at, updated_at)
     VALUES (:id, :name, :content, :created_at, :updated_at);
@@ -138,6 +153,8 @@ impl SlateDBHistoryStore {
                 conn.execute("BEGIN", [])?;
                 conn.execute(WORKSHEETS_CREATE_TABLE, [])?;
                 conn.execute(QUERIES_CREATE_TABLE, [])?;
+                conn.execute(QUERY_PROFILES_CREATE_TABLE, [])?;
+                conn.execute(QUERY_PROFILES_DELETE_TRIGGER, [])?;
                 conn.execute("COMMIT", [])
             }),
             results_connection
@@ -377,7 +394,8 @@ impl HistoryStore for SlateDBHistoryStore {
                     result_count,
                     status,
                     error,
-                    diagnostic_error )
+                    diagnostic_error,
+                    user )
                 VALUES (
                     :id,
                     :worksheet_id,
@@ -389,7 +407,8 @@ impl HistoryStore for SlateDBHistoryStore {
                     :result_count,
                     :status,
                     :error,
-                    :diagnostic_error
+                    :diagnostic_error,
+                    :user
                     )",
                 named_params! {
                     ":id": q.id.to_string(),
@@ -403,6 +422,7 @@ impl HistoryStore for SlateDBHistoryStore {
                     ":status": q.status.to_string(),
                     ":error": q.error,
                     ":diagnostic_error": q.diagnostic_error,
+                    ":user": q.user,
                 },
             )
         })
@@ -568,7 +588,8 @@ impl HistoryStore for SlateDBHistoryStore {
                     result_count,
                     status,
                     error,
-                    diagnostic_error
+                    diagnostic_error,
+                    user
                 FROM queries
                 WHERE id = ?1",
                 )?;
@@ -577,6 +598,7 @@ impl HistoryStore for SlateDBHistoryStore {
                     Ok(QueryRecord {
                         id: parse_query_record_id(&row.get::<_, String>(0)?)?,
                         worksheet_id: row.get::<_, Option<i64>>(1)?,
+                        user: row.get(11)?,
                         result_id: row.get::<_, Option<String>>(2)?,
                         query: row.get(3)?,
                         start_time: parse_date(&row.get::<_, String>(4)?)?,
@@ -641,7 +663,8 @@ impl HistoryStore for SlateDBHistoryStore {
                         result_count,
                         status,
                         error,
-                        diagnostic_error
+                        diagnostic_error,
+                        user
                     FROM queries
                     WHERE {} id > :cursor
                     ORDER BY start_time DESC
@@ -660,6 +683,7 @@ impl HistoryStore for SlateDBHistoryStore {
                         Ok(QueryRecord {
                             id: parse_query_record_id(&row.get::<_, String>(0)?)?,
                             worksheet_id: row.get::<_, Option<i64>>(1)?,
+                            user: row.get(11)?,
                             result_id: row.get::<_, Option<String>>(2)?,
                             query: row.get(3)?,
                             start_time: parse_date(&row.get::<_, String>(4)?)?,
@@ -770,6 +794,81 @@ impl HistoryStore for SlateDBHistoryStore {
 
         ResultSet::try_from(raw_result)
     }
+
+    #[instrument(
+        name = "SqliteHistoryStore::add_query_profile",
+        level = "debug",
+        skip(self, operator_stats),
+        fields(ok, operators_count = operator_stats.len()),
+        err
+    )]
+    async fn add_query_profile(
+        &self,
+        id: QueryRecordId,
+        operator_stats: &[OperatorStats],
+    ) -> Result<()> {
+        let serialized =
+            serde_json::to_string(operator_stats).context(history_err::SerializeValueSnafu)?;
+        let conn = self
+            .queries_db
+            .conn()
+            .await
+            .context(core_utils_err::CoreSqliteSnafu)
+            .context(history_err::QueryProfileAddSnafu)?;
+
+        conn.interact(move |conn| -> SqlResult<usize> {
+            conn.execute(
+                "INSERT OR REPLACE INTO query_profiles (id, operator_stats)
+                VALUES (:id, :operator_stats)",
+                named_params! {
+                    ":id": id.to_string(),
+                    ":operator_stats": serialized,
+                },
+            )
+        })
+        .await?
+        .context(core_utils_err::RuSqliteSnafu)
+        .context(history_err::QueryProfileAddSnafu)?;
+
+        tracing::Span::current().record("ok", true);
+        Ok(())
+    }
+
+    #[instrument(
+        name = "SqliteHistoryStore::get_query_profile",
+        level = "debug",
+        skip(self),
+        fields(ok),
+        err
+    )]
+    async fn get_query_profile(&self, id: QueryRecordId) -> Result<Vec<OperatorStats>> {
+        let conn = self
+            .queries_db
+            .conn()
+            .await
+            .context(core_utils_err::CoreSqliteSnafu)
+            .context(history_err::QueryProfileGetSnafu)?;
+
+        let res = conn
+            .interact(move |conn| -> SqlResult<String> {
+                conn.query_row(
+                    "SELECT operator_stats FROM query_profiles WHERE id = ?1",
+                    [id.to_string()],
+                    |row| row.get(0),
+                )
+            })
+            .await?;
+
+        if res == Err(rusqlite::Error::QueryReturnedNoRows) {
+            history_err::QueryProfileNotFoundSnafu { query_id: id }.fail()
+        } else {
+            let serialized = res
+                .context(core_utils_err::RuSqliteSnafu)
+                .context(history_err::QueryProfileGetSnafu)?;
+            tracing::Span::current().record("ok", true);
+            serde_json::from_str(&serialized).context(history_err::DeserializeValueSnafu)
+        }
+    }
 }
 
 fn parse_query_record_id(id: &str) -> SqlResult<QueryRecordId> {
@@ -124,3 +124,71 @@ async fn test_history() {
     }
     assert_eq!(worksheet_queries.len(), 0);
 }
+
+#[tokio::test]
+async fn test_query_profile() {
+    let db = SlateDBHistoryStore::new_in_memory().await;
+    let query_id = QueryRecordId(1);
+
+    let err = db
+        .get_query_profile(query_id)
+        .await
+        .expect_err("Query without profile");
+    assert!(matches!(err, Error::QueryProfileNotFound { .. }));
+
+    let operator_stats = vec![
+        OperatorStats {
+            operator_id: 0,
+            parent_operator_id: None,
+            operator_type: "ProjectionExec".to_string(),
+            operator_attributes: "ProjectionExec: expr=[a@0 as a]".to_string(),
+            output_rows: Some(2),
+            elapsed_compute_ns: Some(1000),
+            ..OperatorStats::default()
+        },
+        OperatorStats {
+            operator_id: 1,
+            parent_operator_id: Some(0),
+            operator_type: "DataSourceExec".to_string(),
+            output_rows: Some(2),
+            ..OperatorStats::default()
+        },
+    ];
+    db.add_query_profile(query_id, &operator_stats)
+        .await
+        .expect("Failed adding query profile");
+    assert_eq!(
+        db.get_query_profile(query_id)
+            .await
+            .expect("Failed getting query profile"),
+        operator_stats
+    );
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn test_query_profile_deleted_with_query() {
+    let db = SlateDBHistoryStore::new_in_memory().await;
+    let record = QueryRecord::new("SELECT 1", None);
+    let query_id = record.query_id();
+    db.add_query(&record).await.expect("Failed adding query");
+    db.add_query_profile(query_id, &[OperatorStats::default()])
+        .await
+        .expect("Failed adding query profile");
+
+    db.queries_db
+        .conn()
+        .await
+        .expect("Failed getting connection")
+        .interact(move |conn| {
+            conn.execute("DELETE FROM queries WHERE id = ?1", [query_id.to_string()])
+        })
+        .await
+        .expect("Failed interacting with connection")
+        .expect("Failed deleting query");
+    let err = db
+        .get_query_profile(query_id)
+        .await
+        .expect_err("Query profile deleted with its query");
+    assert!(matches!(err, Error::QueryProfileNotFound { .. }));
+}
@@ -0,0 +1,840 @@
+//! Local cache of Parquet data files read from remote volumes.
+//!
+//! Iceberg never rewrites a data file in place: a commit writes new files and drops the old
+//! ones from the table metadata. So the bytes of a data file, once read, stay valid for the
+//! lifetime of the file, and the cache doesn't need any invalidation beside writes and
+//! deletes done through the wrapped store itself.
+//!
+//! Files are cached in blocks of a fixed size, so the ranges Parquet readers request for the
+//! footer, the page indexes and the column chunks of a file share the blocks they overlap,
+//! whatever their bounds. Concurrent reads of a missing block wait for a single fetch of it.
+//!
+//! Blocks are kept in memory and, if a directory is configured, on local disk, where they are
+//! written in the background. Both tiers are bounded by bytes and evict the least recently
+//! used blocks first.
+use crate::error::{self as metastore_error, Result};
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use futures::future::{BoxFuture, Shared};
+use futures::stream::BoxStream;
+use futures::{FutureExt, TryFutureExt};
+use object_store::path::Path;
+use object_store::{
+    GetOptions, GetRange, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
+    PutMultipartOpts, PutOptions, PutPayload, PutResult,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use snafu::ResultExt;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Extension of the files which are cached, other files are always read from the volume
+const CACHED_EXTENSION: &str = "parquet";
+/// Subdirectory of the configured directory the disk cache owns, it's cleared on start
+const DISK_SUBDIRECTORY: &str = "embucket-data-file-cache";
+/// Extension of the block files in the disk cache directory
+const DISK_FILE_EXTENSION: &str = "block";
+pub const DEFAULT_BLOCK_SIZE_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataFileCacheConfig {
+    /// Bytes of data file blocks kept in memory
+    pub memory_capacity_bytes: u64,
+    /// Directory of the disk cache, no disk cache if not set
+    pub disk_dir: Option<PathBuf>,
+    /// Bytes of data file blocks kept in the disk cache directory
+    pub disk_capacity_bytes: u64,
+    /// Size of the blocks files are cached in, the last block of a file may be smaller
+    pub block_size_bytes: u64,
+}
+
+impl Default for DataFileCacheConfig {
+    fn default() -> Self {
+        Self {
+            memory_capacity_bytes: 0,
+            disk_dir: None,
+            disk_capacity_bytes: 0,
+            block_size_bytes: DEFAULT_BLOCK_SIZE_BYTES,
+        }
+    }
+}
+
+/// Counters of the cache, hits and misses are counted in blocks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataFileCacheStats {
+    pub memory_hits: u64,
+    pub disk_hits: u64,
+    pub misses: u64,
+    /// Misses which waited for the fetch of the same block by a concurrent read
+    pub shared_fetches: u64,
+    pub evictions: u64,
+    pub memory_bytes: u64,
+    pub disk_bytes: u64,
+}
+
+/// Block of a file, the file is identified by its store and path
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct BlockKey {
+    file: Arc<str>,
+    block: u64,
+}
+
+#[derive(Debug)]
+struct LruEntry<V> {
+    value: V,
+    size: u64,
+    tick: u64,
+}
+
+/// Entries bounded by their total size, evicted in least recently used order
+#[derive(Debug)]
+struct Lru<V> {
+    entries: HashMap<BlockKey, LruEntry<V>>,
+    order: BTreeMap<u64, BlockKey>,
+    /// Cached blocks of every file, so the blocks of a file are dropped without a scan
+    files: HashMap<Arc<str>, BTreeSet<u64>>,
+    capacity: u64,
+    size: u64,
+    tick: u64,
+}
+
+impl<V: Clone> Lru<V> {
+    fn new(capacity: u64) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            files: HashMap::new(),
+            capacity,
+            size: 0,
+            tick: 0,
+        }
+    }
+
+    fn get(&mut self, key: &BlockKey) -> Option<V> {
+        self.tick += 1;
+        let entry = self.entries.get_mut(key)?;
+        self.order.remove(&entry.tick);
+        entry.tick = self.tick;
+        self.order.insert(self.tick, key.clone());
+        Some(entry.value.clone())
+    }
+
+    /// Inserts the entry, if it fits into the capacity, and returns the evicted entries,
+    /// the replaced entry of the same key isn't one of them
+    fn insert(&mut self, key: BlockKey, value: V, size: u64) -> Vec<(BlockKey, V)> {
+        if size > self.capacity {
+            return Vec::new();
+        }
+        self.remove(&key);
+        let mut evicted = Vec::new();
+        while self.size + size > self.capacity {
+            let Some((_, oldest)) = self.order.first_key_value() else {
+                break;
+            };
+            let oldest = oldest.clone();
+            if let Some(removed) = self.remove(&oldest) {
+                evicted.push(removed);
+            }
+        }
+        self.tick += 1;
+        self.order.insert(self.tick, key.clone());
+        self.files
+            .entry(key.file.clone())
+            .or_default()
+            .insert(key.block);
+        self.entries.insert(
+            key,
+            LruEntry {
+                value,
+                size,
+                tick: self.tick,
+            },
+        );
+        self.size += size;
+        evicted
+    }
+
+    fn remove(&mut self, key: &BlockKey) -> Option<(BlockKey, V)> {
+        let entry = self.entries.remove(key)?;
+        self.order.remove(&entry.tick);
+        self.size -= entry.size;
+        if let Some(blocks) = self.files.get_mut(&key.file) {
+            blocks.remove(&key.block);
+            if blocks.is_empty() {
+                self.files.remove(&key.file);
+            }
+        }
+        Some((key.clone(), entry.value))
+    }
+
+    /// Removes the blocks of the file and returns them
+    fn remove_file(&mut self, file: &str) -> Vec<(BlockKey, V)> {
+        let Some(blocks) = self.files.remove(file) else {
+            return Vec::new();
+        };
+        let file: Arc<str> = Arc::from(file);
+        blocks
+            .into_iter()
+            .filter_map(|block| {
+                self.remove(&BlockKey {
+                    file: file.clone(),
+                    block,
+                })
+            })
+            .collect()
+    }
+}
+
+type BlockFetch = Shared<BoxFuture<'static, std::result::Result<Bytes, Arc<object_store::Error>>>>;
+
+/// Memory and disk cache of data file blocks, shared by the object stores of all volumes
+#[derive(Debug)]
+pub struct DataFileCache {
+    memory: Mutex<Lru<Bytes>>,
+    /// Blocks on disk with the generation their file was written at
+    disk: Option<(PathBuf, Mutex<Lru<u64>>)>,
+    block_size: u64,
+    /// Fetches of missing blocks in progress, with the generation they were started at
+    fetches: Mutex<HashMap<BlockKey, (u64, BlockFetch)>>,
+    /// Generation of each invalidated file, bumped by its invalidations. Blocks of the
+    /// file fetched before an invalidation are not cached after it.
+    generations: Mutex<HashMap<Arc<str>, u64>>,
+    memory_hits: AtomicU64,
+    disk_hits: AtomicU64,
+    misses: AtomicU64,
+    shared_fetches: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl DataFileCache {
+    /// Creates the cache, removing the blocks cached on disk by a previous run, since the
+    /// index of the cached blocks is only kept in memory. Only the subdirectory the cache
+    /// owns in the configured directory is cleared.
+    pub fn new(config: DataFileCacheConfig) -> Result<Self> {
+        let disk = match config.disk_dir {
+            Some(dir) => {
+                let dir = dir.join(DISK_SUBDIRECTORY);
+                let _ = std::fs::remove_dir_all(&dir);
+                std::fs::create_dir_all(&dir).context(metastore_error::CreateDirectorySnafu {
+                    path: dir.display().to_string(),
+                })?;
+                Some((dir, Mutex::new(Lru::new(config.disk_capacity_bytes))))
+            }
+            None => None,
+        };
+        Ok(Self {
+            memory: Mutex::new(Lru::new(config.memory_capacity_bytes)),
+            disk,
+            block_size: config.block_size_bytes.max(1),
+            fetches: Mutex::new(HashMap::new()),
+            generations: Mutex::new(HashMap::new()),
+            memory_hits: AtomicU64::new(0),
+            disk_hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            shared_fetches: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        })
+    }
+
+    #[must_use]
+    pub fn stats(&self) -> DataFileCacheStats {
+        DataFileCacheStats {
+            memory_hits: self.memory_hits.load(Ordering::Relaxed),
+            disk_hits: self.disk_hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            shared_fetches: self.shared_fetches.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            memory_bytes: self
+                .memory
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .size,
+            disk_bytes: self.disk.as_ref().map_or(0, |(_, lru)| {
+                lru.lock().unwrap_or_else(PoisonError::into_inner).size
+            }),
+        }
+    }
+
+    /// Current generation of the file, files never invalidated are at the first one
+    fn generation(&self, file: &str) -> u64 {
+        self.generations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(file)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn disk_file(dir: &std::path::Path, key: &BlockKey, generation: u64) -> PathBuf {
+        let hash = Sha256::digest(format!("{}#{}", key.file, key.block).as_bytes());
+        dir.join(format!("{hash:x}-{generation}.{DISK_FILE_EXTENSION}"))
+    }
+
+    /// Blocks of the file overlapped by the range
+    const fn blocks(&self, range: &Range<u64>) -> Range<u64> {
+        range.start / self.block_size..(range.end - 1) / self.block_size + 1
+    }
+
+    /// Block of the file, from the cache or fetched from the store
+    async fn block(
+        self: &Arc<Self>,
+        inner: &Arc<dyn ObjectStore>,
+        location: &Path,
+        key: BlockKey,
+    ) -> object_store::Result<Bytes> {
+        if let Some(bytes) = self.get(&key).await {
+            return Ok(bytes);
+        }
+        let fetch = {
+            let mut fetches = self.fetches.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some((_, fetch)) = fetches.get(&key) {
+                self.shared_fetches.fetch_add(1, Ordering::Relaxed);
+                fetch.clone()
+            } else {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                let generation = self.generation(&key.file);
+                let fetch = self
+                    .clone()
+                    .fetch(inner.clone(), location.clone(), key.clone(), generation)
+                    .boxed()
+                    .shared();
+                fetches.insert(key, (generation, fetch.clone()));
+                fetch
+            }
+        };
+        fetch.await.map_err(into_store_error)
+    }
+
+    async fn fetch(
+        self: Arc<Self>,
+        inner: Arc<dyn ObjectStore>,
+        location: Path,
+        key: BlockKey,
+        generation: u64,
+    ) -> std::result::Result<Bytes, Arc<object_store::Error>> {
+        let start = key.block * self.block_size;
+        let options = GetOptions {
+            range: Some(GetRange::Bounded(start..start + self.block_size)),
+            ..Default::default()
+        };
+        let fetched = inner
+            .get_opts(&location, options)
+            .and_then(GetResult::bytes)
+            .await;
+        if let Ok(bytes) = &fetched {
+            self.insert(&key, bytes.clone(), generation);
+        }
+        // The block is cached before the fetch is dropped, so that no reader misses both
+        {
+            let mut fetches = self.fetches.lock().unwrap_or_else(PoisonError::into_inner);
+            if fetches
+                .get(&key)
+                .is_some_and(|(started, _)| *started == generation)
+            {
+                fetches.remove(&key);
+            }
+        }
+        fetched.map_err(Arc::new)
+    }
+
+    async fn get(&self, key: &BlockKey) -> Option<Bytes> {
+        let cached = self
+            .memory
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(key);
+        if let Some(bytes) = cached {
+            self.memory_hits.fetch_add(1, Ordering::Relaxed);
+            return Some(bytes);
+        }
+        let (dir, lru) = self.disk.as_ref()?;
+        let current = self.generation(&key.file);
+        let generation = lru
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(key)?;
+        match tokio::fs::read(Self::disk_file(dir, key, generation)).await {
+            Ok(bytes) => {
+                self.disk_hits.fetch_add(1, Ordering::Relaxed);
+                let bytes = Bytes::from(bytes);
+                self.insert_memory(key, bytes.clone(), current);
+                Some(bytes)
+            }
+            Err(error) => {
+                tracing::warn!("Failed to read cached data file block {key:?}: {error}");
+                lru.lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .remove(key);
+                None
+            }
+        }
+    }
+
+    fn record_evictions(&self, evicted: usize) {
+        self.evictions.fetch_add(count(evicted), Ordering::Relaxed);
+    }
+
+    /// Caches the block in memory, unless its file was invalidated after the generation
+    fn insert_memory(&self, key: &BlockKey, bytes: Bytes, generation: u64) {
+        let size = byte_len(&bytes);
+        let mut memory = self.memory.lock().unwrap_or_else(PoisonError::into_inner);
+        if self.generation(&key.file) != generation {
+            return;
+        }
+        let evicted = memory.insert(key.clone(), bytes, size);
+        drop(memory);
+        self.record_evictions(evicted.len());
+    }
+
+    fn insert(self: &Arc<Self>, key: &BlockKey, bytes: Bytes, generation: u64) {
+        self.insert_memory(key, bytes.clone(), generation);
+        if self.disk.is_some() {
+            let cache = self.clone();
+            let key = key.clone();
+            tokio::spawn(async move { cache.insert_disk(key, bytes, generation).await });
+        }
+    }
+
+    /// Writes the block to the disk cache, run in the background of the reads
+    async fn insert_disk(&self, key: BlockKey, bytes: Bytes, generation: u64) {
+        let Some((dir, lru)) = &self.disk else {
+            return;
+        };
+        let size = byte_len(&bytes);
+        if size > lru.lock().unwrap_or_else(PoisonError::into_inner).capacity {
+            return;
+        }
+        // The file is written before it's added to the index, so that a concurrent reader
+        // never finds an indexed block without its file
+        let path = Self::disk_file(dir, &key, generation);
+        if let Err(error) = tokio::fs::write(&path, &bytes).await {
+            tracing::warn!("Failed to write data file block {key:?} to disk cache: {error}");
+            return;
+        }
+        let removed = {
+            let mut lru = lru.lock().unwrap_or_else(PoisonError::into_inner);
+            if self.generation(&key.file) == generation {
+                // A block written at another generation has another file
+                let replaced = lru
+                    .remove(&key)
+                    .filter(|(_, replaced)| *replaced != generation);
+                let evicted = lru.insert(key, generation, size);
+                self.record_evictions(evicted.len());
+                Some(replaced.into_iter().chain(evicted).collect::<Vec<_>>())
+            } else {
+                None
+            }
+        };
+        let Some(removed) = removed else {
+            // The file was invalidated while the block was written
+            let _ = tokio::fs::remove_file(path).await;
+            return;
+        };
+        for (key, generation) in removed {
+            let _ = tokio::fs::remove_file(Self::disk_file(dir, &key, generation)).await;
+        }
+    }
+
+    /// Drops the cached blocks of a file, written or deleted through the cache
+    async fn invalidate(&self, file: &str) {
+        // Blocks of the file fetched before the invalidation are not cached
+        *self
+            .generations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(Arc::from(file))
+            .or_insert(0) += 1;
+        self.fetches
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|key, _| &*key.file != file);
+        self.memory
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove_file(file);
+        if let Some((dir, lru)) = &self.disk {
+            let removed = lru
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .remove_file(file);
+            for (key, generation) in removed {
+                let _ = tokio::fs::remove_file(Self::disk_file(dir, &key, generation)).await;
+            }
+        }
+    }
+}
+
+/// Error of a fetch shared by concurrent reads, as returned to one of them
+fn into_store_error(error: Arc<object_store::Error>) -> object_store::Error {
+    Arc::try_unwrap(error).unwrap_or_else(|error| object_store::Error::Generic {
+        store: "DataFileCache",
+        source: Box::new(error),
+    })
+}
+
+fn count(value: usize) -> u64 {
+    u64::try_from(value).unwrap_or(u64::MAX)
+}
+
+fn byte_len(bytes: &Bytes) -> u64 {
+    count(bytes.len())
+}
+
+/// `ObjectStore` reading the ranges of Parquet files through a [`DataFileCache`]
+#[derive(Debug)]
+pub struct CachingObjectStore {
+    inner: Arc<dyn ObjectStore>,
+    cache: Arc<DataFileCache>,
+    /// Identifies the wrapped store in the keys of the cache shared with other stores
+    prefix: String,
+}
+
+impl CachingObjectStore {
+    #[must_use]
+    pub const fn new(
+        inner: Arc<dyn ObjectStore>,
+        cache: Arc<DataFileCache>,
+        prefix: String,
+    ) -> Self {
+        Self {
+            inner,
+            cache,
+            prefix,
+        }
+    }
+
+    fn is_cached(location: &Path) -> bool {
+        location.extension() == Some(CACHED_EXTENSION)
+    }
+
+    fn file_key(&self, location: &Path) -> String {
+        format!("{}/{location}", self.prefix)
+    }
+
+    async fn invalidate(&self, location: &Path) {
+        if Self::is_cached(location) {
+            self.cache.invalidate(&self.file_key(location)).await;
+        }
+    }
+}
+
+/// Bytes of the range out of the blocks it overlaps, `None` if the blocks end before it,
+/// past the end of the file
+fn slice_blocks(blocks: &[Bytes], first_block_start: u64, range: &Range<u64>) -> Option<Bytes> {
+    let start = usize::try_from(range.start - first_block_start).ok()?;
+    let end = usize::try_from(range.end - first_block_start).ok()?;
+    if let [block] = blocks {
+        return (end <= block.len()).then(|| block.slice(start..end));
+    }
+    let mut bytes = BytesMut::with_capacity(end - start);
+    for block in blocks {
+        bytes.extend_from_slice(block);
+    }
+    (end <= bytes.len()).then(|| bytes.freeze().slice(start..end))
+}
+
+impl std::fmt::Display for CachingObjectStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CachingObjectStore({})", self.inner)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for CachingObjectStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> object_store::Result<PutResult> {
+        let result = self.inner.put_opts(location, payload, opts).await;
+        self.invalidate(location).await;
+        result
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> object_store::Result<Box<dyn MultipartUpload>> {
+        self.invalidate(location).await;
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(
+        &self,
+        location: &Path,
+        options: GetOptions,
+    ) -> object_store::Result<GetResult> {
+        self.inner.get_opts(location, options).await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<u64>) -> object_store::Result<Bytes> {
+        if !Self::is_cached(location) || range.is_empty() {
+            return self.inner.get_range(location, range).await;
+        }
+        let file: Arc<str> = Arc::from(self.file_key(location));
+        let blocks = self.cache.blocks(&range);
+        let first_block_start = blocks.start * self.cache.block_size;
+        let blocks = futures::future::try_join_all(blocks.map(|block| {
+            let key = BlockKey {
+                file: file.clone(),
+                block,
+            };
+            self.cache.block(&self.inner, location, key)
+        }))
+        .await?;
+        match slice_blocks(&blocks, first_block_start, &range) {
+            Some(bytes) => Ok(bytes),
+            // The store reports a range past the end of the file
+            None => self.inner.get_range(location, range).await,
+        }
+    }
+
+    async fn get_ranges(
+        &self,
+        location: &Path,
+        ranges: &[Range<u64>],
+    ) -> object_store::Result<Vec<Bytes>> {
+        if !Self::is_cached(location) {
+            return self.inner.get_ranges(location, ranges).await;
+        }
+        // Ranges overlapping the same block share its fetch
+        futures::future::try_join_all(
+            ranges
+                .iter()
+                .map(|range| self.get_range(location, range.clone())),
+        )
+        .await
+    }
+
+    async fn head(&self, location: &Path) -> object_store::Result<ObjectMeta> {
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> object_store::Result<()> {
+        let result = self.inner.delete(location).await;
+        self.invalidate(location).await;
+        result
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+        self.inner.list_with_offset(prefix, offset)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> object_store::Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        let result = self.inner.copy(from, to).await;
+        self.invalidate(to).await;
+        result
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        let result = self.inner.rename(from, to).await;
+        self.invalidate(from).await;
+        self.invalidate(to).await;
+        result
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        let result = self.inner.copy_if_not_exists(from, to).await;
+        self.invalidate(to).await;
+        result
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        let result = self.inner.rename_if_not_exists(from, to).await;
+        self.invalidate(from).await;
+        self.invalidate(to).await;
+        result
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use object_store::memory::InMemory;
+
+    async fn caching_store(config: DataFileCacheConfig) -> (Arc<CachingObjectStore>, Path) {
+        let inner = Arc::new(InMemory::new());
+        let path = Path::from("db/schema/table/data/file.parquet");
+        inner
+            .put(&path, PutPayload::from_static(b"0123456789"))
+            .await
+            .unwrap();
+        let cache = Arc::new(DataFileCache::new(config).unwrap());
+        let store = CachingObjectStore::new(inner, cache, "s3://bucket".to_string());
+        (Arc::new(store), path)
+    }
+
+    /// Blocks are written to disk in the background of the reads
+    async fn wait_for_disk_bytes(cache: &DataFileCache, bytes: u64) {
+        for _ in 0..50 {
+            if cache.stats().disk_bytes == bytes {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(cache.stats().disk_bytes, bytes);
+    }
+
+    #[tokio::test]
+    async fn test_cached_blocks() {
+        let (store, path) = caching_store(DataFileCacheConfig {
+            memory_capacity_bytes: 8,
+            block_size_bytes: 4,
+            ..Default::default()
+        })
+        .await;
+
+        assert_eq!(store.get_range(&path, 0..2).await.unwrap(), "01");
+        // Ranges share the blocks they overlap
+        assert_eq!(store.get_range(&path, 1..6).await.unwrap(), "12345");
+        let stats = store.cache.stats();
+        assert_eq!((stats.memory_hits, stats.misses), (1, 2));
+        assert_eq!(stats.memory_bytes, 8);
+
+        // The least recently used block is evicted first, the last block of the file is
+        // smaller than the others
+        assert_eq!(store.get_range(&path, 8..10).await.unwrap(), "89");
+        let stats = store.cache.stats();
+        assert_eq!((stats.evictions, stats.memory_bytes), (1, 6));
+        assert_eq!(store.get_range(&path, 4..6).await.unwrap(), "45");
+        assert_eq!(store.get_range(&path, 0..2).await.unwrap(), "01");
+        let stats = store.cache.stats();
+        assert_eq!((stats.memory_hits, stats.misses), (2, 4));
+
+        let ranges = store.get_ranges(&path, &[0..1, 2..3]).await.unwrap();
+        assert_eq!(ranges, vec![Bytes::from("0"), Bytes::from("2")]);
+        assert_eq!(store.cache.stats().misses, 4);
+    }
+
+    #[tokio::test]
+    async fn test_block_over_capacity() {
+        let (store, path) = caching_store(DataFileCacheConfig {
+            memory_capacity_bytes: 2,
+            block_size_bytes: 4,
+            ..Default::default()
+        })
+        .await;
+
+        // A block which doesn't fit into the cache isn't cached
+        store.get_range(&path, 0..2).await.unwrap();
+        store.get_range(&path, 0..2).await.unwrap();
+        assert_eq!(store.cache.stats().misses, 2);
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        // Files of the directory other than the ones of the cache are kept
+        let other = dir.path().join("other.block");
+        std::fs::write(&other, b"other").unwrap();
+        let (store, path) = caching_store(DataFileCacheConfig {
+            memory_capacity_bytes: 4,
+            disk_dir: Some(dir.path().to_path_buf()),
+            disk_capacity_bytes: 100,
+            block_size_bytes: 4,
+        })
+        .await;
+        assert!(other.exists());
+
+        store.get_range(&path, 0..4).await.unwrap();
+        store.get_range(&path, 4..8).await.unwrap();
+        wait_for_disk_bytes(&store.cache, 8).await;
+        // Evicted from memory, read from disk
+        assert_eq!(store.get_range(&path, 0..4).await.unwrap(), "0123");
+        let stats = store.cache.stats();
+        assert_eq!((stats.disk_hits, stats.misses), (1, 2));
+
+        // The disk cache of a previous run is cleared
+        DataFileCache::new(DataFileCacheConfig {
+            disk_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap();
+        let cached_files = std::fs::read_dir(dir.path().join(DISK_SUBDIRECTORY))
+            .unwrap()
+            .count();
+        assert_eq!(cached_files, 0);
+        assert!(other.exists());
+    }
+
+    #[tokio::test]
+    async fn test_invalidated_on_write() {
+        let (store, path) = caching_store(DataFileCacheConfig {
+            memory_capacity_bytes: 100,
+            block_size_bytes: 4,
+            ..Default::default()
+        })
+        .await;
+
+        store.get_range(&path, 0..4).await.unwrap();
+        store
+            .put(&path, PutPayload::from_static(b"abcdefghij"))
+            .await
+            .unwrap();
+        assert_eq!(store.get_range(&path, 0..4).await.unwrap(), "abcd");
+        let stats = store.cache.stats();
+        assert_eq!((stats.misses, stats.memory_bytes), (2, 4));
+
+        // Files other than Parquet ones are not cached
+        let metadata = Path::from("db/schema/table/metadata/v1.metadata.json");
+        store
+            .put(&metadata, PutPayload::from_static(b"{}"))
+            .await
+            .unwrap();
+        store.get_range(&metadata, 0..2).await.unwrap();
+        store.get_range(&metadata, 0..2).await.unwrap();
+        assert_eq!(store.cache.stats().misses, 2);
+    }
+
+    #[tokio::test]
+    async fn test_invalidation_keeps_other_files() {
+        let (store, path) = caching_store(DataFileCacheConfig {
+            memory_capacity_bytes: 100,
+            block_size_bytes: 4,
+            ..Default::default()
+        })
+        .await;
+        let key = BlockKey {
+            file: Arc::from(store.file_key(&path)),
+            block: 0,
+        };
+
+        // A block fetched before another file is invalidated is still cached
+        let generation = store.cache.generation(&key.file);
+        store
+            .invalidate(&Path::from("db/schema/table/data/other.parquet"))
+            .await;
+        store
+            .cache
+            .insert_memory(&key, Bytes::from("0123"), generation);
+        assert_eq!(store.cache.stats().memory_bytes, 4);
+
+        // A block fetched before its own file is invalidated is not
+        let generation = store.cache.generation(&key.file);
+        store.invalidate(&path).await;
+        store
+            .cache
+            .insert_memory(&key, Bytes::from("0123"), generation);
+        assert_eq!(store.cache.stats().memory_bytes, 0);
+    }
+}
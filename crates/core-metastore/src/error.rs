@@ -175,6 +175,78 @@ pub enum Error {
         location: Location,
     },
 
+    #[snafu(display("File format {file_format} already exists"))]
+    FileFormatAlreadyExists {
+        file_format: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("File format {file_format} not found"))]
+    FileFormatNotFound {
+        file_format: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Stage {stage} already exists"))]
+    StageAlreadyExists {
+        stage: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Stage {stage} not found"))]
+    StageNotFound {
+        stage: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("User {user} already exists"))]
+    UserAlreadyExists {
+        user: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("User {user} not found"))]
+    UserNotFound {
+        user: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Role {role} already exists"))]
+    RoleAlreadyExists {
+        role: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Role {role} not found"))]
+    RoleNotFound {
+        role: String,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Password hash error: {error}"))]
+    PasswordHash {
+        #[snafu(source)]
+        error: argon2::password_hash::Error,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
+    #[snafu(display("Task join error: {error}"))]
+    TaskJoin {
+        #[snafu(source)]
+        error: tokio::task::JoinError,
+        #[snafu(implicit)]
+        location: Location,
+    },
+
     #[snafu(display("Volume in use by database(s): {database}"))]
     VolumeInUse {
         database: String,
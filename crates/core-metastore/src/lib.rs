@@ -1,3 +1,4 @@
+pub mod data_file_cache;
 pub mod error;
 pub mod metastore;
 pub mod models;
@@ -3,12 +3,20 @@ use std::{collections::HashMap, sync::Arc};
 #[allow(clippy::wildcard_imports)]
 use crate::models::*;
 use crate::{
+    data_file_cache::{DataFileCache, DataFileCacheStats},
     error::{self as metastore_error, Result},
     models::{
         RwObject,
         database::{Database, DatabaseIdent},
+        file_format::{FileFormat, FileFormatIdent},
+        load_history::LoadHistory,
+        role::{
+            ACCOUNTADMIN_ROLE, Grant, GrantObject, Grantee, PUBLIC_ROLE, Role, RoleGrant, RoleIdent,
+        },
         schema::{Schema, SchemaIdent},
+        stage::{Stage, StageIdent},
         table::{Table, TableCreateRequest, TableIdent, TableRequirementExt, TableUpdate},
+        user::{AuthenticatedUser, User, UserIdent, access_token_hash, credentials_digest},
         volumes::{Volume, VolumeIdent},
     },
 };
@@ -27,7 +35,7 @@ use iceberg_rust_spec::{
 };
 use object_store::{ObjectStore, PutPayload, path::Path};
 use serde::de::DeserializeOwned;
-use snafu::ResultExt;
+use snafu::{OptionExt, ResultExt};
 use strum::Display;
 use tracing::instrument;
 use uuid::Uuid;
@@ -39,6 +47,11 @@ pub enum MetastoreObjectType {
     Database,
     Schema,
     Table,
+    #[strum(serialize = "file format")]
+    FileFormat,
+    Stage,
+    User,
+    Role,
 }
 
 #[async_trait]
@@ -50,6 +63,8 @@ pub trait Metastore: std::fmt::Debug + Send + Sync {
     async fn delete_volume(&self, name: &VolumeIdent, cascade: bool) -> Result<()>;
     async fn volume_object_store(&self, name: &VolumeIdent)
     -> Result<Option<Arc<dyn ObjectStore>>>;
+    /// Counters of the cache of data files read from remote volumes, `None` without cache
+    fn data_file_cache_stats(&self) -> Option<DataFileCacheStats>;
 
     fn iter_databases(&self) -> VecScanIterator<RwObject<Database>>;
     async fn create_database(
@@ -89,6 +104,112 @@ pub trait Metastore: std::fmt::Debug + Send + Sync {
     async fn table_exists(&self, ident: &TableIdent) -> Result<bool>;
     async fn url_for_table(&self, ident: &TableIdent) -> Result<String>;
     async fn volume_for_table(&self, ident: &TableIdent) -> Result<Option<RwObject<Volume>>>;
+
+    fn iter_file_formats(&self, schema: &SchemaIdent) -> VecScanIterator<RwObject<FileFormat>>;
+    async fn create_file_format(
+        &self,
+        ident: &FileFormatIdent,
+        file_format: FileFormat,
+    ) -> Result<RwObject<FileFormat>>;
+    async fn get_file_format(
+        &self,
+        ident: &FileFormatIdent,
+    ) -> Result<Option<RwObject<FileFormat>>>;
+    async fn update_file_format(
+        &self,
+        ident: &FileFormatIdent,
+        file_format: FileFormat,
+    ) -> Result<RwObject<FileFormat>>;
+    async fn delete_file_format(&self, ident: &FileFormatIdent) -> Result<()>;
+
+    fn iter_stages(&self, schema: &SchemaIdent) -> VecScanIterator<RwObject<Stage>>;
+    async fn create_stage(&self, ident: &StageIdent, stage: Stage) -> Result<RwObject<Stage>>;
+    async fn get_stage(&self, ident: &StageIdent) -> Result<Option<RwObject<Stage>>>;
+    /// Deletes the stage along with the files uploaded to it
+    async fn delete_stage(&self, ident: &StageIdent) -> Result<()>;
+    async fn url_for_stage(&self, ident: &StageIdent) -> Result<String>;
+    async fn stage_object_store(&self, ident: &StageIdent) -> Result<Arc<dyn ObjectStore>>;
+
+    /// Load history of `table`, only the files whose URL starts with `prefix` when given
+    async fn list_load_history(
+        &self,
+        table: &TableIdent,
+        prefix: Option<&str>,
+    ) -> Result<Vec<RwObject<LoadHistory>>>;
+    async fn get_load_history(
+        &self,
+        table: &TableIdent,
+        file: &str,
+    ) -> Result<Option<RwObject<LoadHistory>>>;
+    /// Records the files loaded by a `COPY INTO` in a single write
+    async fn put_load_history(&self, loads: Vec<LoadHistory>) -> Result<()>;
+
+    fn iter_users(&self) -> VecScanIterator<RwObject<User>>;
+    async fn create_user(&self, name: &UserIdent, user: User) -> Result<RwObject<User>>;
+    async fn get_user(&self, name: &UserIdent) -> Result<Option<RwObject<User>>>;
+    async fn update_user(&self, name: &UserIdent, user: User) -> Result<RwObject<User>>;
+    async fn delete_user(&self, name: &UserIdent) -> Result<()>;
+    /// Authenticates an enabled user by their password or by one of their
+    /// programmatic access tokens, `None` unless the secret matches.
+    async fn authenticate(
+        &self,
+        name: &UserIdent,
+        secret: &str,
+    ) -> Result<Option<AuthenticatedUser>>;
+    /// Authenticates the enabled user a programmatic access token belongs to,
+    /// the token is looked up by its SHA-256 digest.
+    async fn authenticate_access_token(&self, secret: &str) -> Result<Option<AuthenticatedUser>>;
+
+    fn iter_roles(&self) -> VecScanIterator<RwObject<Role>>;
+    async fn create_role(&self, name: &RoleIdent, role: Role) -> Result<RwObject<Role>>;
+    async fn get_role(&self, name: &RoleIdent) -> Result<Option<RwObject<Role>>>;
+    /// Deletes the role, the privileges granted to it and the grants of the role
+    async fn delete_role(&self, name: &RoleIdent) -> Result<()>;
+
+    /// Privileges granted to the role, or to any role when `None`
+    fn iter_grants(&self, role: Option<&RoleIdent>) -> VecScanIterator<RwObject<Grant>>;
+    async fn grant_privilege(&self, grant: Grant) -> Result<RwObject<Grant>>;
+    async fn revoke_privilege(&self, grant: &Grant) -> Result<()>;
+    /// Revokes the privileges on a dropped object and on the objects it held
+    async fn revoke_grants_on(&self, object: &GrantObject) -> Result<()>;
+
+    /// Roles granted to the grantee, or to any grantee when `None`
+    fn iter_role_grants(&self, grantee: Option<&Grantee>) -> VecScanIterator<RwObject<RoleGrant>>;
+    async fn grant_role(&self, grant: RoleGrant) -> Result<RwObject<RoleGrant>>;
+    async fn revoke_role(&self, grant: &RoleGrant) -> Result<()>;
+}
+
+/// Creates the `ACCOUNTADMIN` and `PUBLIC` roles, if they don't exist yet
+pub async fn bootstrap_roles(metastore: &dyn Metastore) -> Result<()> {
+    for role in [ACCOUNTADMIN_ROLE, PUBLIC_ROLE] {
+        let role = role.to_string();
+        if metastore.get_role(&role).await?.is_none() {
+            metastore
+                .create_role(&role, Role::new(role.clone()))
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Creates the initial user given on startup, an existing user is left unchanged.
+/// The user is granted the `ACCOUNTADMIN` role, created along with the `PUBLIC` role.
+pub async fn bootstrap_user(metastore: &dyn Metastore, name: &str, password: &str) -> Result<()> {
+    bootstrap_roles(metastore).await?;
+    let name = name.to_string();
+    if metastore.get_user(&name).await?.is_none() {
+        let mut user = User::new(name.clone()).with_password(password)?;
+        user.default_role = Some(ACCOUNTADMIN_ROLE.to_string());
+        metastore.create_user(&name, user).await?;
+    }
+    metastore
+        .grant_role(RoleGrant {
+            role: ACCOUNTADMIN_ROLE.to_string(),
+            grantee: Grantee::User(name),
+            granted_by: None,
+        })
+        .await?;
+    Ok(())
 }
 
 ///
@@ -100,15 +221,47 @@ pub trait Metastore: std::fmt::Debug + Send + Sync {
 /// sch/<db>/<name> -> `Schema`
 /// tbl/<db>/<schema> -> List of tables for <schema> in <db>
 /// tbl/<db>/<schema>/<table> -> `Table`
+/// ff/<db>/<schema> -> List of file formats for <schema> in <db>
+/// ff/<db>/<schema>/<name> -> `FileFormat`
+/// stage/<db>/<schema> -> List of internal stages for <schema> in <db>
+/// stage/<db>/<schema>/<name> -> `Stage`
+/// lh/<db>/<schema>/<table> -> Load history of <table>
+/// lh/<db>/<schema>/<table>/<url encoded file> -> `LoadHistory`
+/// usr -> List of users
+/// usr/<lowercase name> -> `User`
+/// pat/<hex SHA-256 of the secret> -> Name of the user of the access token
+/// role -> List of roles
+/// role/<lowercase name> -> `Role`
+/// grant/<lowercase role> -> List of privileges granted to <role>
+/// grant/<lowercase role>/<object>/<privilege> -> `Grant`, <object> is `db/<db>`,
+///   `sch/<db>/<schema>` or `tbl/<db>/<schema>/<table>`
+/// rolegrant/<role|user>/<lowercase grantee> -> List of roles granted to <grantee>
+/// rolegrant/<role|user>/<lowercase grantee>/<lowercase role> -> `RoleGrant`
 ///
 const KEY_VOLUME: &str = "vol";
 const KEY_DATABASE: &str = "db";
 const KEY_SCHEMA: &str = "sch";
 const KEY_TABLE: &str = "tbl";
+const KEY_FILE_FORMAT: &str = "ff";
+const KEY_STAGE: &str = "stage";
+const KEY_LOAD_HISTORY: &str = "lh";
+const KEY_USER: &str = "usr";
+const KEY_ACCESS_TOKEN: &str = "pat";
+const KEY_ROLE: &str = "role";
+const KEY_GRANT: &str = "grant";
+const KEY_ROLE_GRANT: &str = "rolegrant";
+
+/// Load history entries read at once, the history of a table is read page by page
+const LOAD_HISTORY_PAGE_SIZE: u16 = 1000;
 
 pub struct SlateDBMetastore {
     db: Db,
     object_store_cache: DashMap<VolumeIdent, Arc<dyn ObjectStore>>,
+    data_file_cache: Option<Arc<DataFileCache>>,
+    /// Password hashes credentials were verified against, by their digest, so
+    /// clients sending the same credentials with every request don't pay for
+    /// argon2 each time. A changed password hash invalidates the entry.
+    verified_credentials: DashMap<[u8; 32], String>,
 }
 
 impl std::fmt::Debug for SlateDBMetastore {
@@ -123,9 +276,23 @@ impl SlateDBMetastore {
         Self {
             db,
             object_store_cache: DashMap::new(),
+            data_file_cache: None,
+            verified_credentials: DashMap::new(),
         }
     }
 
+    /// Reads data files of remote volumes through the cache
+    #[must_use]
+    pub fn with_data_file_cache(mut self, cache: Arc<DataFileCache>) -> Self {
+        self.data_file_cache = Some(cache);
+        self
+    }
+
+    #[must_use]
+    pub const fn data_file_cache(&self) -> Option<&Arc<DataFileCache>> {
+        self.data_file_cache.as_ref()
+    }
+
     // Create a new SlateDBMetastore with a new in-memory database
     pub async fn new_in_memory() -> Self {
         Self::new(Db::memory().await)
@@ -219,6 +386,141 @@ impl SlateDBMetastore {
         Ok(())
     }
 
+    /// Internal stages keep their files on the volume of their database
+    async fn stage_volume(&self, ident: &StageIdent) -> Result<RwObject<Volume>> {
+        let database = self.get_database(&ident.database).await?.ok_or_else(|| {
+            metastore_error::DatabaseNotFoundSnafu {
+                db: ident.database.clone(),
+            }
+            .build()
+        })?;
+        self.get_volume(&database.volume).await?.ok_or_else(|| {
+            metastore_error::VolumeNotFoundSnafu {
+                volume: database.volume.clone(),
+            }
+            .build()
+        })
+    }
+
+    fn load_history_table_key(table: &TableIdent) -> String {
+        format!(
+            "{KEY_LOAD_HISTORY}/{}/{}/{}",
+            table.database, table.schema, table.table
+        )
+    }
+
+    fn load_history_key(table: &TableIdent, file: &str) -> String {
+        format!(
+            "{}/{}",
+            Self::load_history_table_key(table),
+            url_encode(file)
+        )
+    }
+
+    fn user_key(name: &UserIdent) -> String {
+        format!("{KEY_USER}/{}", name.to_lowercase())
+    }
+
+    fn access_token_key(secret_hash: &str) -> String {
+        format!("{KEY_ACCESS_TOKEN}/{secret_hash}")
+    }
+
+    /// Points the digests of the access tokens of the user at the user and drops
+    /// the ones of the tokens the user had before and no longer has
+    async fn index_access_tokens(
+        &self,
+        previous: Option<&User>,
+        user: Option<&User>,
+    ) -> Result<()> {
+        for token in previous.iter().flat_map(|previous| &previous.access_tokens) {
+            let kept = user.is_some_and(|user| {
+                user.access_tokens
+                    .iter()
+                    .any(|kept| kept.secret_hash == token.secret_hash)
+            });
+            if kept {
+                continue;
+            }
+            let key = Self::access_token_key(&token.secret_hash);
+            // A renamed user is created before the previous one is deleted
+            let owner = self
+                .db
+                .get::<UserIdent>(&key)
+                .await
+                .context(metastore_error::UtilSlateDBSnafu)?;
+            if owner
+                .is_some_and(|owner| previous.is_some_and(|p| owner.eq_ignore_ascii_case(&p.name)))
+            {
+                self.delete_object(&key).await?;
+            }
+        }
+        if let Some(user) = user {
+            let entries: Vec<(String, UserIdent)> = user
+                .access_tokens
+                .iter()
+                .map(|token| {
+                    (
+                        Self::access_token_key(&token.secret_hash),
+                        user.name.clone(),
+                    )
+                })
+                .collect();
+            if !entries.is_empty() {
+                self.db
+                    .put_all(&entries)
+                    .await
+                    .context(metastore_error::UtilSlateDBSnafu)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn role_key(name: &RoleIdent) -> String {
+        format!("{KEY_ROLE}/{}", name.to_lowercase())
+    }
+
+    fn grant_key(grant: &Grant) -> String {
+        let object = match &grant.object {
+            GrantObject::Database(database) => format!("{KEY_DATABASE}/{database}"),
+            GrantObject::Schema(schema) => {
+                format!("{KEY_SCHEMA}/{}/{}", schema.database, schema.schema)
+            }
+            GrantObject::Table(table) => format!(
+                "{KEY_TABLE}/{}/{}/{}",
+                table.database, table.schema, table.table
+            ),
+            GrantObject::Stage(stage) => format!(
+                "{KEY_STAGE}/{}/{}/{}",
+                stage.database, stage.schema, stage.name
+            ),
+            GrantObject::FileFormat(file_format) => format!(
+                "{KEY_FILE_FORMAT}/{}/{}/{}",
+                file_format.database, file_format.schema, file_format.name
+            ),
+        };
+        format!(
+            "{KEY_GRANT}/{}/{object}/{}",
+            grant.role.to_lowercase(),
+            grant.privilege
+        )
+    }
+
+    fn grantee_key(grantee: &Grantee) -> String {
+        format!(
+            "{KEY_ROLE_GRANT}/{}/{}",
+            grantee.kind().to_lowercase(),
+            grantee.name().to_lowercase()
+        )
+    }
+
+    fn role_grant_key(grant: &RoleGrant) -> String {
+        format!(
+            "{}/{}",
+            Self::grantee_key(&grant.grantee),
+            grant.role.to_lowercase()
+        )
+    }
+
     fn generate_metadata_filename() -> String {
         format!("{}.metadata.json", Uuid::new_v4())
     }
@@ -253,7 +555,7 @@ impl Metastore for SlateDBMetastore {
     )]
     async fn create_volume(&self, name: &VolumeIdent, volume: Volume) -> Result<RwObject<Volume>> {
         let key = format!("{KEY_VOLUME}/{name}");
-        let object_store = volume.get_object_store()?;
+        let object_store = volume.get_cached_object_store(self.data_file_cache.as_ref())?;
         let rwobject = self
             .create_object(&key, MetastoreObjectType::Volume, volume)
             .await
@@ -289,7 +591,7 @@ impl Metastore for SlateDBMetastore {
     async fn update_volume(&self, name: &VolumeIdent, volume: Volume) -> Result<RwObject<Volume>> {
         let key = format!("{KEY_VOLUME}/{name}");
         let updated_volume = self.update_object(&key, volume.clone()).await?;
-        let object_store = updated_volume.get_object_store()?;
+        let object_store = updated_volume.get_cached_object_store(self.data_file_cache.as_ref())?;
         self.object_store_cache
             .alter(name, |_, _store| object_store.clone());
         Ok(updated_volume)
@@ -345,13 +647,17 @@ impl Metastore for SlateDBMetastore {
                 }
                 .build()
             })?;
-            let object_store = volume.get_object_store()?;
+            let object_store = volume.get_cached_object_store(self.data_file_cache.as_ref())?;
             self.object_store_cache
                 .insert(name.clone(), object_store.clone());
             Ok(Some(object_store))
         }
     }
 
+    fn data_file_cache_stats(&self) -> Option<DataFileCacheStats> {
+        self.data_file_cache.as_ref().map(|cache| cache.stats())
+    }
+
     #[instrument(name = "Metastore::iter_databases", level = "trace", skip(self))]
     fn iter_databases(&self) -> VecScanIterator<RwObject<Database>> {
         self.iter_objects(KEY_DATABASE.to_string())
@@ -494,6 +800,23 @@ impl Metastore for SlateDBMetastore {
                 .collect::<Vec<_>>();
             futures::future::try_join_all(futures).await?;
         }
+        // File formats have no data of their own, so they never block dropping a schema
+        let file_formats = self
+            .iter_file_formats(ident)
+            .collect()
+            .await
+            .context(metastore_error::UtilSlateDBSnafu)?;
+        for file_format in file_formats {
+            self.delete_file_format(&file_format.ident).await?;
+        }
+        let stages = self
+            .iter_stages(ident)
+            .collect()
+            .await
+            .context(metastore_error::UtilSlateDBSnafu)?;
+        for stage in stages {
+            self.delete_stage(&stage.ident).await?;
+        }
         let key = format!("{KEY_SCHEMA}/{}/{}", ident.database, ident.schema);
         self.delete_object(&key).await
     }
@@ -761,6 +1084,11 @@ impl Metastore for SlateDBMetastore {
                 );
                 self.delete_volume(&volume_ident, false).await?;
             }
+            // Load history belongs to the table, a table created again loads files anew
+            for load in self.list_load_history(ident, None).await? {
+                self.delete_object(&Self::load_history_key(ident, &load.file))
+                    .await?;
+            }
             let key = format!(
                 "{KEY_TABLE}/{}/{}/{}",
                 ident.database, ident.schema, ident.table
@@ -882,6 +1210,579 @@ impl Metastore for SlateDBMetastore {
         };
         self.get_volume(&volume_ident).await
     }
+
+    #[instrument(name = "Metastore::iter_file_formats", level = "debug", skip(self))]
+    fn iter_file_formats(&self, schema: &SchemaIdent) -> VecScanIterator<RwObject<FileFormat>> {
+        //If database and schema is empty, we are iterating over all file formats
+        let key = if schema.schema.is_empty() && schema.database.is_empty() {
+            KEY_FILE_FORMAT.to_string()
+        } else {
+            format!("{KEY_FILE_FORMAT}/{}/{}", schema.database, schema.schema)
+        };
+        self.iter_objects(key)
+    }
+
+    #[instrument(
+        name = "Metastore::create_file_format",
+        level = "debug",
+        skip(self, file_format),
+        err
+    )]
+    async fn create_file_format(
+        &self,
+        ident: &FileFormatIdent,
+        file_format: FileFormat,
+    ) -> Result<RwObject<FileFormat>> {
+        if self.get_schema(&ident.clone().into()).await?.is_none() {
+            return metastore_error::SchemaNotFoundSnafu {
+                schema: ident.schema.clone(),
+                db: ident.database.clone(),
+            }
+            .fail();
+        }
+        if self.get_file_format(ident).await?.is_some() {
+            return metastore_error::FileFormatAlreadyExistsSnafu {
+                file_format: ident.to_string(),
+            }
+            .fail();
+        }
+        let key = format!(
+            "{KEY_FILE_FORMAT}/{}/{}/{}",
+            ident.database, ident.schema, ident.name
+        );
+        self.create_object(&key, MetastoreObjectType::FileFormat, file_format)
+            .await
+    }
+
+    #[instrument(name = "Metastore::get_file_format", level = "debug", skip(self), err)]
+    async fn get_file_format(
+        &self,
+        ident: &FileFormatIdent,
+    ) -> Result<Option<RwObject<FileFormat>>> {
+        let key = format!(
+            "{KEY_FILE_FORMAT}/{}/{}/{}",
+            ident.database, ident.schema, ident.name
+        );
+        self.db
+            .get(&key)
+            .await
+            .context(metastore_error::UtilSlateDBSnafu)
+    }
+
+    #[instrument(
+        name = "Metastore::update_file_format",
+        level = "debug",
+        skip(self, file_format),
+        err
+    )]
+    async fn update_file_format(
+        &self,
+        ident: &FileFormatIdent,
+        file_format: FileFormat,
+    ) -> Result<RwObject<FileFormat>> {
+        if self.get_file_format(ident).await?.is_none() {
+            return metastore_error::FileFormatNotFoundSnafu {
+                file_format: ident.to_string(),
+            }
+            .fail();
+        }
+        let key = format!(
+            "{KEY_FILE_FORMAT}/{}/{}/{}",
+            ident.database, ident.schema, ident.name
+        );
+        self.update_object(&key, file_format).await
+    }
+
+    #[instrument(
+        name = "Metastore::delete_file_format",
+        level = "debug",
+        skip(self),
+        err
+    )]
+    async fn delete_file_format(&self, ident: &FileFormatIdent) -> Result<()> {
+        if self.get_file_format(ident).await?.is_none() {
+            return metastore_error::FileFormatNotFoundSnafu {
+                file_format: ident.to_string(),
+            }
+            .fail();
+        }
+        let key = format!(
+            "{KEY_FILE_FORMAT}/{}/{}/{}",
+            ident.database, ident.schema, ident.name
+        );
+        self.delete_object(&key).await
+    }
+
+    #[instrument(name = "Metastore::iter_stages", level = "debug", skip(self))]
+    fn iter_stages(&self, schema: &SchemaIdent) -> VecScanIterator<RwObject<Stage>> {
+        //If database and schema is empty, we are iterating over all stages
+        let key = if schema.schema.is_empty() && schema.database.is_empty() {
+            KEY_STAGE.to_string()
+        } else {
+            format!("{KEY_STAGE}/{}/{}", schema.database, schema.schema)
+        };
+        self.iter_objects(key)
+    }
+
+    #[instrument(
+        name = "Metastore::create_stage",
+        level = "debug",
+        skip(self, stage),
+        err
+    )]
+    async fn create_stage(&self, ident: &StageIdent, stage: Stage) -> Result<RwObject<Stage>> {
+        if self.get_schema(&ident.clone().into()).await?.is_none() {
+            return metastore_error::SchemaNotFoundSnafu {
+                schema: ident.schema.clone(),
+                db: ident.database.clone(),
+            }
+            .fail();
+        }
+        let key = format!(
+            "{KEY_STAGE}/{}/{}/{}",
+            ident.database, ident.schema, ident.name
+        );
+        self.create_object(&key, MetastoreObjectType::Stage, stage)
+            .await
+            .map_err(|e| {
+                if matches!(e, metastore_error::Error::ObjectAlreadyExists { .. }) {
+                    metastore_error::StageAlreadyExistsSnafu {
+                        stage: ident.to_string(),
+                    }
+                    .build()
+                } else {
+                    e
+                }
+            })
+    }
+
+    #[instrument(name = "Metastore::get_stage", level = "debug", skip(self), err)]
+    async fn get_stage(&self, ident: &StageIdent) -> Result<Option<RwObject<Stage>>> {
+        let key = format!(
+            "{KEY_STAGE}/{}/{}/{}",
+            ident.database, ident.schema, ident.name
+        );
+        self.db
+            .get(&key)
+            .await
+            .context(metastore_error::UtilSlateDBSnafu)
+    }
+
+    #[instrument(name = "Metastore::delete_stage", level = "debug", skip(self), err)]
+    async fn delete_stage(&self, ident: &StageIdent) -> Result<()> {
+        let object_store = self.stage_object_store(ident).await?;
+        let url = url::Url::parse(&self.url_for_stage(ident).await?)
+            .context(metastore_error::UrlParseSnafu)?;
+        let stage_path = Path::from(url.path());
+        let locations = object_store
+            .list(Some(&stage_path))
+            .map_ok(|m| m.location)
+            .boxed();
+        object_store
+            .delete_stream(locations)
+            .try_collect::<Vec<Path>>()
+            .await
+            .context(metastore_error::ObjectStoreSnafu)?;
+
+        let key = format!(
+            "{KEY_STAGE}/{}/{}/{}",
+            ident.database, ident.schema, ident.name
+        );
+        self.delete_object(&key).await
+    }
+
+    #[instrument(name = "Metastore::url_for_stage", level = "debug", skip(self))]
+    async fn url_for_stage(&self, ident: &StageIdent) -> Result<String> {
+        let stage = self.get_stage(ident).await?.ok_or_else(|| {
+            metastore_error::StageNotFoundSnafu {
+                stage: ident.to_string(),
+            }
+            .build()
+        })?;
+        let volume = self.stage_volume(ident).await?;
+        Ok(format!("{}/{}", volume.prefix(), stage.volume_location()))
+    }
+
+    #[instrument(name = "Metastore::stage_object_store", level = "debug", skip(self))]
+    async fn stage_object_store(&self, ident: &StageIdent) -> Result<Arc<dyn ObjectStore>> {
+        let volume = self.stage_volume(ident).await?;
+        self.volume_object_store(&volume.ident)
+            .await?
+            .ok_or_else(|| {
+                metastore_error::VolumeNotFoundSnafu {
+                    volume: volume.ident.clone(),
+                }
+                .build()
+            })
+    }
+
+    #[instrument(
+        name = "Metastore::list_load_history",
+        level = "debug",
+        skip(self),
+        err
+    )]
+    async fn list_load_history(
+        &self,
+        table: &TableIdent,
+        prefix: Option<&str>,
+    ) -> Result<Vec<RwObject<LoadHistory>>> {
+        // Keys end with the URL encoded file, the encoded prefix is a prefix of those keys
+        let token = prefix.map(url_encode);
+        let mut loads: Vec<RwObject<LoadHistory>> = Vec::new();
+        loop {
+            let page = self
+                .iter_objects::<LoadHistory>(Self::load_history_table_key(table))
+                .token(token.clone())
+                .cursor(loads.last().map(|load| url_encode(&load.file)))
+                .limit(Some(LOAD_HISTORY_PAGE_SIZE))
+                .collect()
+                .await
+                .context(metastore_error::UtilSlateDBSnafu)?;
+            let last_page = page.len() < usize::from(LOAD_HISTORY_PAGE_SIZE);
+            loads.extend(page);
+            if last_page {
+                return Ok(loads);
+            }
+        }
+    }
+
+    #[instrument(name = "Metastore::get_load_history", level = "debug", skip(self), err)]
+    async fn get_load_history(
+        &self,
+        table: &TableIdent,
+        file: &str,
+    ) -> Result<Option<RwObject<LoadHistory>>> {
+        self.db
+            .get(&Self::load_history_key(table, file))
+            .await
+            .context(metastore_error::UtilSlateDBSnafu)
+    }
+
+    #[instrument(
+        name = "Metastore::put_load_history",
+        level = "debug",
+        skip(self, loads),
+        err
+    )]
+    async fn put_load_history(&self, loads: Vec<LoadHistory>) -> Result<()> {
+        let mut entries = Vec::with_capacity(loads.len());
+        for load in loads {
+            let key = Self::load_history_key(&load.table, &load.file);
+            let rwobject = match self.get_load_history(&load.table, &load.file).await? {
+                // Loading the file again counts as a new load even with the same outcome
+                Some(mut rwobject) => {
+                    rwobject.update(load);
+                    rwobject.touch();
+                    rwobject
+                }
+                None => RwObject::new(load),
+            };
+            entries.push((key, rwobject));
+        }
+        self.db
+            .put_all(&entries)
+            .await
+            .context(metastore_error::UtilSlateDBSnafu)
+    }
+
+    #[instrument(name = "Metastore::iter_users", level = "debug", skip(self))]
+    fn iter_users(&self) -> VecScanIterator<RwObject<User>> {
+        self.iter_objects(KEY_USER.to_string())
+    }
+
+    #[instrument(
+        name = "Metastore::create_user",
+        level = "debug",
+        skip(self, user),
+        err
+    )]
+    async fn create_user(&self, name: &UserIdent, user: User) -> Result<RwObject<User>> {
+        if self.get_user(name).await?.is_some() {
+            return metastore_error::UserAlreadyExistsSnafu { user: name.clone() }.fail();
+        }
+        self.index_access_tokens(None, Some(&user)).await?;
+        self.create_object(&Self::user_key(name), MetastoreObjectType::User, user)
+            .await
+    }
+
+    #[instrument(name = "Metastore::get_user", level = "debug", skip(self), err)]
+    async fn get_user(&self, name: &UserIdent) -> Result<Option<RwObject<User>>> {
+        self.db
+            .get(&Self::user_key(name))
+            .await
+            .context(metastore_error::UtilSlateDBSnafu)
+    }
+
+    #[instrument(
+        name = "Metastore::update_user",
+        level = "debug",
+        skip(self, user),
+        err
+    )]
+    async fn update_user(&self, name: &UserIdent, user: User) -> Result<RwObject<User>> {
+        let previous = self
+            .get_user(name)
+            .await?
+            .context(metastore_error::UserNotFoundSnafu { user: name.clone() })?;
+        self.index_access_tokens(Some(&previous.data), Some(&user))
+            .await?;
+        self.update_object(&Self::user_key(name), user).await
+    }
+
+    #[instrument(name = "Metastore::delete_user", level = "debug", skip(self), err)]
+    async fn delete_user(&self, name: &UserIdent) -> Result<()> {
+        let user = self
+            .get_user(name)
+            .await?
+            .context(metastore_error::UserNotFoundSnafu { user: name.clone() })?;
+        self.index_access_tokens(Some(&user.data), None).await?;
+        let role_grants = self
+            .iter_role_grants(Some(&Grantee::User(name.clone())))
+            .collect()
+            .await
+            .context(metastore_error::UtilSlateDBSnafu)?;
+        for grant in role_grants {
+            self.revoke_role(&grant).await?;
+        }
+        self.delete_object(&Self::user_key(name)).await
+    }
+
+    #[instrument(
+        name = "Metastore::authenticate",
+        level = "debug",
+        skip(self, secret),
+        err
+    )]
+    async fn authenticate(
+        &self,
+        name: &UserIdent,
+        secret: &str,
+    ) -> Result<Option<AuthenticatedUser>> {
+        let Some(user) = self.get_user(name).await?.filter(|user| !user.disabled) else {
+            return Ok(None);
+        };
+        let user = user.data;
+        // Access tokens only take a SHA-256 digest to check
+        if let Some(token) = user.verify_access_token(secret) {
+            let role_restriction = token.role_restriction.clone();
+            return Ok(Some(AuthenticatedUser {
+                user,
+                role_restriction,
+            }));
+        }
+        let Some(password_hash) = user.password_hash.clone() else {
+            return Ok(None);
+        };
+        let digest = credentials_digest(name, secret);
+        let cached = self
+            .verified_credentials
+            .get(&digest)
+            .is_some_and(|verified| *verified == password_hash);
+        if !cached {
+            // argon2 takes tens of milliseconds of CPU, which would stall the async workers
+            let data = user.clone();
+            let password = secret.to_string();
+            let verified = tokio::task::spawn_blocking(move || data.verify_password(&password))
+                .await
+                .context(metastore_error::TaskJoinSnafu)?;
+            if !verified {
+                return Ok(None);
+            }
+            self.verified_credentials.insert(digest, password_hash);
+        }
+        Ok(Some(AuthenticatedUser {
+            user,
+            role_restriction: None,
+        }))
+    }
+
+    #[instrument(
+        name = "Metastore::authenticate_access_token",
+        level = "debug",
+        skip(self, secret),
+        err
+    )]
+    async fn authenticate_access_token(&self, secret: &str) -> Result<Option<AuthenticatedUser>> {
+        let key = Self::access_token_key(&access_token_hash(secret));
+        let Some(name) = self
+            .db
+            .get::<UserIdent>(&key)
+            .await
+            .context(metastore_error::UtilSlateDBSnafu)?
+        else {
+            return Ok(None);
+        };
+        let Some(user) = self.get_user(&name).await?.filter(|user| !user.disabled) else {
+            return Ok(None);
+        };
+        let user = user.data;
+        let Some(token) = user.verify_access_token(secret) else {
+            return Ok(None);
+        };
+        let role_restriction = token.role_restriction.clone();
+        Ok(Some(AuthenticatedUser {
+            user,
+            role_restriction,
+        }))
+    }
+
+    #[instrument(name = "Metastore::iter_roles", level = "debug", skip(self))]
+    fn iter_roles(&self) -> VecScanIterator<RwObject<Role>> {
+        self.iter_objects(KEY_ROLE.to_string())
+    }
+
+    #[instrument(
+        name = "Metastore::create_role",
+        level = "debug",
+        skip(self, role),
+        err
+    )]
+    async fn create_role(&self, name: &RoleIdent, role: Role) -> Result<RwObject<Role>> {
+        self.create_object(&Self::role_key(name), MetastoreObjectType::Role, role)
+            .await
+            .map_err(|e| {
+                if matches!(e, metastore_error::Error::ObjectAlreadyExists { .. }) {
+                    metastore_error::RoleAlreadyExistsSnafu { role: name.clone() }.build()
+                } else {
+                    e
+                }
+            })
+    }
+
+    #[instrument(name = "Metastore::get_role", level = "debug", skip(self), err)]
+    async fn get_role(&self, name: &RoleIdent) -> Result<Option<RwObject<Role>>> {
+        self.db
+            .get(&Self::role_key(name))
+            .await
+            .context(metastore_error::UtilSlateDBSnafu)
+    }
+
+    #[instrument(name = "Metastore::delete_role", level = "debug", skip(self), err)]
+    async fn delete_role(&self, name: &RoleIdent) -> Result<()> {
+        if self.get_role(name).await?.is_none() {
+            return metastore_error::RoleNotFoundSnafu { role: name.clone() }.fail();
+        }
+        let grants = self
+            .iter_grants(Some(name))
+            .collect()
+            .await
+            .context(metastore_error::UtilSlateDBSnafu)?;
+        for grant in grants {
+            self.revoke_privilege(&grant).await?;
+        }
+        let role_grants = self
+            .iter_role_grants(None)
+            .collect()
+            .await
+            .context(metastore_error::UtilSlateDBSnafu)?;
+        for grant in role_grants {
+            let granted = grant.role.eq_ignore_ascii_case(name);
+            let grantee =
+                matches!(&grant.grantee, Grantee::Role(role) if role.eq_ignore_ascii_case(name));
+            if granted || grantee {
+                self.revoke_role(&grant).await?;
+            }
+        }
+        self.delete_object(&Self::role_key(name)).await
+    }
+
+    #[instrument(name = "Metastore::iter_grants", level = "debug", skip(self))]
+    fn iter_grants(&self, role: Option<&RoleIdent>) -> VecScanIterator<RwObject<Grant>> {
+        let key = role.map_or_else(
+            || KEY_GRANT.to_string(),
+            |role| format!("{KEY_GRANT}/{}", role.to_lowercase()),
+        );
+        self.iter_objects(key)
+    }
+
+    #[instrument(name = "Metastore::grant_privilege", level = "debug", skip(self), err)]
+    async fn grant_privilege(&self, grant: Grant) -> Result<RwObject<Grant>> {
+        if self.get_role(&grant.role).await?.is_none() {
+            return metastore_error::RoleNotFoundSnafu { role: grant.role }.fail();
+        }
+        let key = Self::grant_key(&grant);
+        // Granting a privilege again keeps the original grant
+        if let Some(existing) = self
+            .db
+            .get::<RwObject<Grant>>(&key)
+            .await
+            .context(metastore_error::UtilSlateDBSnafu)?
+        {
+            return Ok(existing);
+        }
+        let rwobject = RwObject::new(grant);
+        self.db
+            .put(&key, &rwobject)
+            .await
+            .context(metastore_error::UtilSlateDBSnafu)?;
+        Ok(rwobject)
+    }
+
+    #[instrument(name = "Metastore::revoke_privilege", level = "debug", skip(self), err)]
+    async fn revoke_privilege(&self, grant: &Grant) -> Result<()> {
+        self.delete_object(&Self::grant_key(grant)).await
+    }
+
+    #[instrument(name = "Metastore::revoke_grants_on", level = "debug", skip(self), err)]
+    async fn revoke_grants_on(&self, object: &GrantObject) -> Result<()> {
+        let grants = self
+            .iter_grants(None)
+            .collect()
+            .await
+            .context(metastore_error::UtilSlateDBSnafu)?;
+        for grant in grants {
+            if object.contains(&grant.object) {
+                self.revoke_privilege(&grant).await?;
+            }
+        }
+        Ok(())
+    }
+
+    #[instrument(name = "Metastore::iter_role_grants", level = "debug", skip(self))]
+    fn iter_role_grants(&self, grantee: Option<&Grantee>) -> VecScanIterator<RwObject<RoleGrant>> {
+        let key = grantee.map_or_else(|| KEY_ROLE_GRANT.to_string(), Self::grantee_key);
+        self.iter_objects(key)
+    }
+
+    #[instrument(name = "Metastore::grant_role", level = "debug", skip(self), err)]
+    async fn grant_role(&self, grant: RoleGrant) -> Result<RwObject<RoleGrant>> {
+        if self.get_role(&grant.role).await?.is_none() {
+            return metastore_error::RoleNotFoundSnafu { role: grant.role }.fail();
+        }
+        match &grant.grantee {
+            Grantee::Role(role) => {
+                if self.get_role(role).await?.is_none() {
+                    return metastore_error::RoleNotFoundSnafu { role: role.clone() }.fail();
+                }
+            }
+            Grantee::User(user) => {
+                if self.get_user(user).await?.is_none() {
+                    return metastore_error::UserNotFoundSnafu { user: user.clone() }.fail();
+                }
+            }
+        }
+        let key = Self::role_grant_key(&grant);
+        if let Some(existing) = self
+            .db
+            .get::<RwObject<RoleGrant>>(&key)
+            .await
+            .context(metastore_error::UtilSlateDBSnafu)?
+        {
+            return Ok(existing);
+        }
+        let rwobject = RwObject::new(grant);
+        self.db
+            .put(&key, &rwobject)
+            .await
+            .context(metastore_error::UtilSlateDBSnafu)?;
+        Ok(rwobject)
+    }
+
+    #[instrument(name = "Metastore::revoke_role", level = "debug", skip(self), err)]
+    async fn revoke_role(&self, grant: &RoleGrant) -> Result<()> {
+        self.delete_object(&Self::role_grant_key(grant)).await
+    }
 }
 
 fn convert_schema_fields_to_lowercase(schema: &IcebergSchema) -> Result<IcebergSchema> {
@@ -1445,5 +2346,415 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_file_formats() {
+        let ms = get_metastore().await;
+        let schema_ident = SchemaIdent::new("testdb".to_owned(), "testschema".to_owned());
+        let ident = FileFormatIdent::new("testdb", "testschema", "my_csv");
+        let file_format = FileFormat::new(ident.clone(), FileFormatType::Csv).with_option(
+            "FIELD_DELIMITER",
+            FileFormatOptionValue::Single("|".to_owned()),
+        );
+
+        let no_schema_result = ms.create_file_format(&ident, file_format.clone()).await;
+        assert!(matches!(
+            no_schema_result,
+            Err(metastore_error::Error::SchemaNotFound { .. })
+        ));
+
+        ms.create_volume(
+            &"testv1".to_owned(),
+            Volume::new("testv1".to_owned(), VolumeType::Memory),
+        )
+        .await
+        .expect("create volume failed");
+        ms.create_database(
+            &"testdb".to_owned(),
+            Database {
+                ident: "testdb".to_owned(),
+                volume: "testv1".to_owned(),
+                properties: None,
+            },
+        )
+        .await
+        .expect("create database failed");
+        ms.create_schema(
+            &schema_ident,
+            Schema {
+                ident: schema_ident.clone(),
+                properties: None,
+            },
+        )
+        .await
+        .expect("create schema failed");
+
+        ms.create_file_format(&ident, file_format.clone())
+            .await
+            .expect("create file format failed");
+        let duplicate = ms.create_file_format(&ident, file_format.clone()).await;
+        assert!(matches!(
+            duplicate,
+            Err(metastore_error::Error::FileFormatAlreadyExists { .. })
+        ));
+
+        let replaced = FileFormat::new(ident.clone(), FileFormatType::Parquet);
+        ms.update_file_format(&ident, replaced.clone())
+            .await
+            .expect("update file format failed");
+        let fetched = ms
+            .get_file_format(&ident)
+            .await
+            .expect("get file format failed")
+            .expect("file format not found");
+        assert_eq!(fetched.data, replaced);
+
+        let listed = ms
+            .iter_file_formats(&schema_ident)
+            .collect()
+            .await
+            .expect("list file formats failed");
+        assert_eq!(listed.len(), 1);
+
+        ms.delete_schema(&schema_ident, false)
+            .await
+            .expect("delete schema failed");
+        let listed_after = ms
+            .iter_file_formats(&schema_ident)
+            .collect()
+            .await
+            .expect("list file formats failed");
+        assert!(listed_after.is_empty());
+        let delete_missing = ms.delete_file_format(&ident).await;
+        assert!(matches!(
+            delete_missing,
+            Err(metastore_error::Error::FileFormatNotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stages() {
+        let ms = get_metastore().await;
+        let schema_ident = SchemaIdent::new("testdb".to_owned(), "testschema".to_owned());
+        let ident = StageIdent::new("testdb", "testschema", "uploads");
+
+        ms.create_volume(
+            &"testv1".to_owned(),
+            Volume::new("testv1".to_owned(), VolumeType::Memory),
+        )
+        .await
+        .expect("create volume failed");
+        ms.create_database(
+            &"testdb".to_owned(),
+            Database {
+                ident: "testdb".to_owned(),
+                volume: "testv1".to_owned(),
+                properties: None,
+            },
+        )
+        .await
+        .expect("create database failed");
+        ms.create_schema(
+            &schema_ident,
+            Schema {
+                ident: schema_ident.clone(),
+                properties: None,
+            },
+        )
+        .await
+        .expect("create schema failed");
+
+        ms.create_stage(&ident, Stage::new(ident.clone()))
+            .await
+            .expect("create stage failed");
+        let duplicate = ms.create_stage(&ident, Stage::new(ident.clone())).await;
+        assert!(matches!(
+            duplicate,
+            Err(metastore_error::Error::StageAlreadyExists { .. })
+        ));
+        let url = ms
+            .url_for_stage(&ident)
+            .await
+            .expect("url for stage failed");
+        assert_eq!(url, "memory:///testdb/testschema/.stages/uploads");
+
+        let object_store = ms
+            .stage_object_store(&ident)
+            .await
+            .expect("stage object store failed");
+        let file = Path::from("testdb/testschema/.stages/uploads/data.csv.gz");
+        object_store
+            .put(&file, PutPayload::from_static(b"data"))
+            .await
+            .expect("put stage file failed");
+
+        ms.delete_stage(&ident).await.expect("delete stage failed");
+        assert!(object_store.head(&file).await.is_err());
+        let missing = ms.url_for_stage(&ident).await;
+        assert!(matches!(
+            missing,
+            Err(metastore_error::Error::StageNotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_load_history() {
+        let ms = get_metastore().await;
+        let table = TableIdent::new("testdb", "testschema", "t");
+        let other_table = TableIdent::new("testdb", "testschema", "t2");
+        let load = |table: &TableIdent, file: &str, rows: u64| LoadHistory {
+            table: table.clone(),
+            file: file.to_owned(),
+            size: 10,
+            etag: Some("etag".to_owned()),
+            last_modified: Utc::now(),
+            status: "LOADED".to_owned(),
+            rows_parsed: rows,
+            rows_loaded: rows,
+            error_count: 0,
+            error_limit: 1,
+            first_error: None,
+            first_error_line: None,
+            first_error_column_name: None,
+        };
+
+        ms.put_load_history(vec![
+            load(&table, "s3://bucket/data/a.csv", 1),
+            load(&table, "s3://bucket/data/b.csv", 2),
+            load(&table, "s3://bucket/other/c.csv", 5),
+            load(&other_table, "s3://bucket/data/a.csv", 3),
+        ])
+        .await
+        .expect("put load history failed");
+        ms.put_load_history(vec![load(&table, "s3://bucket/data/a.csv", 4)])
+            .await
+            .expect("put load history failed");
+
+        let loads = ms
+            .list_load_history(&table, Some("s3://bucket/data/"))
+            .await
+            .expect("list load history failed");
+        assert_eq!(
+            loads
+                .iter()
+                .map(|load| (load.file.as_str(), load.rows_loaded))
+                .collect::<Vec<_>>(),
+            vec![("s3://bucket/data/a.csv", 4), ("s3://bucket/data/b.csv", 2)]
+        );
+        let fetched = ms
+            .get_load_history(&other_table, "s3://bucket/data/a.csv")
+            .await
+            .expect("get load history failed")
+            .expect("load history not found");
+        assert_eq!(fetched.rows_loaded, 3);
+        assert!(
+            ms.get_load_history(&other_table, "s3://bucket/data/b.csv")
+                .await
+                .expect("get load history failed")
+                .is_none()
+        );
+
+        // Read page by page
+        let files = usize::from(LOAD_HISTORY_PAGE_SIZE) * 2 + 1;
+        ms.put_load_history(
+            (0..files)
+                .map(|i| load(&other_table, &format!("s3://bucket/many/{i:05}.csv"), 1))
+                .collect(),
+        )
+        .await
+        .expect("put load history failed");
+        let loads = ms
+            .list_load_history(&other_table, None)
+            .await
+            .expect("list load history failed");
+        assert_eq!(loads.len(), files + 1);
+    }
+
+    #[tokio::test]
+    async fn test_users() {
+        let ms = get_metastore().await;
+        let name = "Alice".to_owned();
+        let user = User::new(name.clone())
+            .with_password("secret")
+            .expect("hash password failed");
+
+        ms.create_user(&name, user.clone())
+            .await
+            .expect("create user failed");
+        let duplicate = ms.create_user(&"alice".to_owned(), user.clone()).await;
+        assert!(matches!(
+            duplicate,
+            Err(metastore_error::Error::UserAlreadyExists { .. })
+        ));
+
+        // Login names are case-insensitive
+        let authenticated = ms
+            .authenticate(&"ALICE".to_owned(), "secret")
+            .await
+            .expect("authenticate user failed");
+        assert_eq!(authenticated.map(|user| user.user), Some(user.clone()));
+        assert!(
+            ms.authenticate(&name, "wrong")
+                .await
+                .expect("authenticate user failed")
+                .is_none()
+        );
+
+        // Access tokens are accepted in place of the password and looked up on their own
+        let mut with_token = user.clone();
+        let token = with_token
+            .add_access_token("ci", 30, Some("ANALYST".to_string()), None)
+            .expect("add access token failed");
+        ms.update_user(&name, with_token.clone())
+            .await
+            .expect("update user failed");
+        let authenticated = ms
+            .authenticate_access_token(&token)
+            .await
+            .expect("authenticate access token failed")
+            .expect("access token not authenticated");
+        assert_eq!(authenticated.user.name, name);
+        assert_eq!(authenticated.role_restriction.as_deref(), Some("ANALYST"));
+        let authenticated = ms
+            .authenticate(&name, &token)
+            .await
+            .expect("authenticate user failed")
+            .expect("access token not authenticated");
+        assert_eq!(authenticated.role_restriction.as_deref(), Some("ANALYST"));
+        with_token.remove_access_token("ci");
+        ms.update_user(&name, with_token)
+            .await
+            .expect("update user failed");
+        assert!(
+            ms.authenticate_access_token(&token)
+                .await
+                .expect("authenticate access token failed")
+                .is_none()
+        );
+
+        // Verified credentials are remembered only while the password is unchanged
+        let changed = user
+            .clone()
+            .with_password("changed")
+            .expect("hash password failed");
+        ms.update_user(&name, changed)
+            .await
+            .expect("update user failed");
+        assert!(
+            ms.authenticate(&name, "secret")
+                .await
+                .expect("authenticate user failed")
+                .is_none()
+        );
+
+        let disabled = User {
+            disabled: true,
+            ..user
+        };
+        ms.update_user(&name, disabled)
+            .await
+            .expect("update user failed");
+        assert!(
+            ms.authenticate(&name, "secret")
+                .await
+                .expect("authenticate user failed")
+                .is_none()
+        );
+
+        bootstrap_user(&ms, "admin", "admin")
+            .await
+            .expect("bootstrap user failed");
+        let users = ms.iter_users().collect().await.expect("list users failed");
+        assert_eq!(users.len(), 2);
+
+        ms.delete_user(&name).await.expect("delete user failed");
+        let delete_missing = ms.delete_user(&name).await;
+        assert!(matches!(
+            delete_missing,
+            Err(metastore_error::Error::UserNotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_roles_and_grants() {
+        let ms = get_metastore().await;
+        bootstrap_user(&ms, "admin", "admin")
+            .await
+            .expect("bootstrap user failed");
+        let admin_roles = ms
+            .iter_role_grants(Some(&Grantee::User("ADMIN".to_owned())))
+            .collect()
+            .await
+            .expect("list role grants failed");
+        assert_eq!(admin_roles.len(), 1);
+        assert_eq!(admin_roles[0].role, ACCOUNTADMIN_ROLE);
+
+        let analyst = "analyst".to_owned();
+        ms.create_role(&analyst, Role::new(analyst.clone()))
+            .await
+            .expect("create role failed");
+        let grant = Grant {
+            privilege: Privilege::Select,
+            object: GrantObject::Table(TableIdent::new("db", "sch", "tbl")),
+            role: analyst.clone(),
+            granted_by: None,
+        };
+        ms.grant_privilege(grant.clone())
+            .await
+            .expect("grant privilege failed");
+        ms.grant_privilege(Grant {
+            privilege: Privilege::Usage,
+            object: GrantObject::Database("db".to_owned()),
+            ..grant.clone()
+        })
+        .await
+        .expect("grant privilege failed");
+        let missing_role = ms
+            .grant_privilege(Grant {
+                role: "missing".to_owned(),
+                ..grant.clone()
+            })
+            .await;
+        assert!(matches!(
+            missing_role,
+            Err(metastore_error::Error::RoleNotFound { .. })
+        ));
+        ms.grant_role(RoleGrant {
+            role: analyst.clone(),
+            grantee: Grantee::Role(ACCOUNTADMIN_ROLE.to_owned()),
+            granted_by: None,
+        })
+        .await
+        .expect("grant role failed");
+
+        // Dropping the schema revokes the privileges on its tables only
+        ms.revoke_grants_on(&GrantObject::Schema(SchemaIdent::new(
+            "db".to_owned(),
+            "sch".to_owned(),
+        )))
+        .await
+        .expect("revoke grants failed");
+        let grants = ms
+            .iter_grants(Some(&analyst))
+            .collect()
+            .await
+            .expect("list grants failed");
+        assert_eq!(grants.len(), 1);
+        assert_eq!(grants[0].privilege, Privilege::Usage);
+
+        ms.delete_role(&analyst).await.expect("delete role failed");
+        let grants = ms
+            .iter_grants(None)
+            .collect()
+            .await
+            .expect("list grants failed");
+        assert!(grants.is_empty());
+        let role_grants = ms
+            .iter_role_grants(None)
+            .collect()
+            .await
+            .expect("list role grants failed");
+        assert_eq!(role_grants.len(), 1);
+    }
+
     // TODO: Add custom table location tests
 }
@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use super::{DatabaseIdent, SchemaIdent};
+
+#[derive(Validate, Debug, Clone, Serialize, Deserialize, PartialEq, Eq, utoipa::ToSchema)]
+/// A named file format identifier
+pub struct FileFormatIdent {
+    #[validate(length(min = 1))]
+    /// The name of the file format
+    pub name: String,
+    #[validate(length(min = 1))]
+    /// The schema the file format belongs to
+    pub schema: String,
+    #[validate(length(min = 1))]
+    /// The database the file format belongs to
+    pub database: DatabaseIdent,
+}
+
+impl FileFormatIdent {
+    #[must_use]
+    pub fn new(database: &str, schema: &str, name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            schema: schema.to_string(),
+            database: database.to_string(),
+        }
+    }
+}
+
+impl From<FileFormatIdent> for SchemaIdent {
+    fn from(ident: FileFormatIdent) -> Self {
+        Self {
+            database: ident.database,
+            schema: ident.schema,
+        }
+    }
+}
+
+impl Display for FileFormatIdent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.database, self.schema, self.name)
+    }
+}
+
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    utoipa::ToSchema,
+    strum::EnumString,
+    strum::Display,
+)]
+#[serde(rename_all = "UPPERCASE")]
+#[strum(ascii_case_insensitive, serialize_all = "UPPERCASE")]
+pub enum FileFormatType {
+    #[default]
+    Csv,
+    Json,
+    Parquet,
+    Avro,
+}
+
+/// Value of a single file format option.
+///
+/// Most options hold a single value (`FIELD_DELIMITER = '|'`), but some of them
+/// accept a list of values (`NULL_IF = ('', 'NULL')`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(untagged)]
+pub enum FileFormatOptionValue {
+    Single(String),
+    List(Vec<String>),
+}
+
+impl FileFormatOptionValue {
+    #[must_use]
+    pub fn as_single(&self) -> Option<&str> {
+        match self {
+            Self::Single(value) => Some(value.as_str()),
+            Self::List(_) => None,
+        }
+    }
+
+    #[must_use]
+    pub fn to_list(&self) -> Vec<String> {
+        match self {
+            Self::Single(value) => vec![value.clone()],
+            Self::List(values) => values.clone(),
+        }
+    }
+}
+
+impl Display for FileFormatOptionValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Single(value) => write!(f, "{value}"),
+            Self::List(values) => write!(f, "[{}]", values.join(", ")),
+        }
+    }
+}
+
+/// A named file format, created with `CREATE FILE FORMAT` and referenced
+/// from `COPY INTO` and stages via `FILE_FORMAT = (FORMAT_NAME = '<name>')`.
+///
+/// Option names are stored uppercased, exactly as Snowflake reports them in
+/// `DESCRIBE FILE FORMAT`. `TYPE` is kept separately in `format_type`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, utoipa::ToSchema)]
+pub struct FileFormat {
+    pub ident: FileFormatIdent,
+    pub format_type: FileFormatType,
+    pub options: BTreeMap<String, FileFormatOptionValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+impl FileFormat {
+    #[must_use]
+    pub const fn new(ident: FileFormatIdent, format_type: FileFormatType) -> Self {
+        Self {
+            ident,
+            format_type,
+            options: BTreeMap::new(),
+            comment: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_option(mut self, name: &str, value: FileFormatOptionValue) -> Self {
+        self.options.insert(name.to_ascii_uppercase(), value);
+        self
+    }
+
+    #[must_use]
+    pub fn option(&self, name: &str) -> Option<&FileFormatOptionValue> {
+        self.options.get(&name.to_ascii_uppercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_file_format_type_from_str() {
+        assert_eq!(
+            FileFormatType::from_str("parquet").ok(),
+            Some(FileFormatType::Parquet)
+        );
+        assert_eq!(
+            FileFormatType::from_str("CSV").ok(),
+            Some(FileFormatType::Csv)
+        );
+        assert!(FileFormatType::from_str("xml").is_err());
+    }
+
+    #[test]
+    fn test_file_format_options() {
+        let format = FileFormat::new(
+            FileFormatIdent::new("db", "sch", "my_csv"),
+            FileFormatType::Csv,
+        )
+        .with_option("field_delimiter", FileFormatOptionValue::Single("|".into()))
+        .with_option(
+            "null_if",
+            FileFormatOptionValue::List(vec![String::new(), "NULL".into()]),
+        );
+        assert_eq!(
+            format
+                .option("FIELD_DELIMITER")
+                .and_then(FileFormatOptionValue::as_single),
+            Some("|")
+        );
+        assert_eq!(
+            format.option("NULL_IF").map(ToString::to_string),
+            Some("[, NULL]".to_string())
+        );
+        assert_eq!(format.ident.to_string(), "db.sch.my_csv");
+    }
+}
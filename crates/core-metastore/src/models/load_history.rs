@@ -0,0 +1,46 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::TableIdent;
+
+/// Load metadata of a single file loaded into a table by `COPY INTO <table>`.
+///
+/// It is what makes `COPY INTO` idempotent: files already loaded are skipped by later
+/// loads unless they changed since or `FORCE = TRUE` is given. The file is identified
+/// by its URL, its `ETag` and size tell whether it changed. The load time is the
+/// `updated_at` of the stored object.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LoadHistory {
+    pub table: TableIdent,
+    /// Full URL of the loaded file
+    pub file: String,
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    pub last_modified: DateTime<Utc>,
+    /// Load status, as reported by `COPY INTO`: `LOADED`, `PARTIALLY_LOADED` or `LOAD_FAILED`
+    pub status: String,
+    pub rows_parsed: u64,
+    pub rows_loaded: u64,
+    pub error_count: u64,
+    pub error_limit: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_error_line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_error_column_name: Option<String>,
+}
+
+impl LoadHistory {
+    /// Whether the file has not changed since it was loaded
+    #[must_use]
+    pub fn is_same_file(
+        &self,
+        size: u64,
+        etag: Option<&str>,
+        last_modified: DateTime<Utc>,
+    ) -> bool {
+        self.size == size && self.etag.as_deref() == etag && self.last_modified == last_modified
+    }
+}
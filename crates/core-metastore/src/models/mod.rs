@@ -4,13 +4,23 @@ use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 
 pub mod database;
+pub mod file_format;
+pub mod load_history;
+pub mod role;
 pub mod schema;
+pub mod stage;
 pub mod table;
+pub mod user;
 pub mod volumes;
 
 pub use database::*;
+pub use file_format::*;
+pub use load_history::*;
+pub use role::*;
 pub use schema::*;
+pub use stage::*;
 pub use table::*;
+pub use user::*;
 
 pub use volumes::*;
 
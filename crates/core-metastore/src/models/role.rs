@@ -0,0 +1,208 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use super::{DatabaseIdent, FileFormatIdent, SchemaIdent, StageIdent, TableIdent, UserIdent};
+
+/// A role name. Roles are looked up case-insensitively, like users.
+pub type RoleIdent = String;
+
+/// Role with every privilege, granted to the user created on startup
+pub const ACCOUNTADMIN_ROLE: &str = "ACCOUNTADMIN";
+/// Role every user has, in addition to the roles granted to them
+pub const PUBLIC_ROLE: &str = "PUBLIC";
+
+/// A role, created with `CREATE ROLE`. Privileges are granted to roles and
+/// roles are granted to users or to other roles.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, utoipa::ToSchema)]
+pub struct Role {
+    pub name: RoleIdent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+impl Role {
+    #[must_use]
+    pub const fn new(name: RoleIdent) -> Self {
+        Self {
+            name,
+            comment: None,
+        }
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    utoipa::ToSchema,
+    strum::EnumString,
+    strum::Display,
+)]
+#[serde(rename_all = "UPPERCASE")]
+#[strum(ascii_case_insensitive, serialize_all = "UPPERCASE")]
+pub enum Privilege {
+    Usage,
+    Create,
+    Select,
+    Insert,
+    Update,
+    Delete,
+    /// Full control of the object, the role creating an object owns it
+    Ownership,
+}
+
+impl Privilege {
+    /// Whether the privilege can be granted on the object
+    #[must_use]
+    pub const fn applies_to(self, object: &GrantObject) -> bool {
+        match self {
+            Self::Usage => !matches!(object, GrantObject::Table(_)),
+            Self::Create => matches!(object, GrantObject::Database(_) | GrantObject::Schema(_)),
+            Self::Select | Self::Insert | Self::Update | Self::Delete => {
+                matches!(object, GrantObject::Table(_))
+            }
+            Self::Ownership => true,
+        }
+    }
+}
+
+/// An object privileges are granted on
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(tag = "kind", content = "ident", rename_all = "UPPERCASE")]
+pub enum GrantObject {
+    Database(DatabaseIdent),
+    Schema(SchemaIdent),
+    Table(TableIdent),
+    Stage(StageIdent),
+    FileFormat(FileFormatIdent),
+}
+
+impl GrantObject {
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Database(_) => "DATABASE",
+            Self::Schema(_) => "SCHEMA",
+            Self::Table(_) => "TABLE",
+            Self::Stage(_) => "STAGE",
+            Self::FileFormat(_) => "FILE FORMAT",
+        }
+    }
+
+    /// Whether the object is this one or one of the objects it holds
+    #[must_use]
+    pub fn contains(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Database(database), Self::Schema(SchemaIdent { database: db, .. }))
+            | (Self::Database(database), Self::Table(TableIdent { database: db, .. }))
+            | (Self::Database(database), Self::Stage(StageIdent { database: db, .. }))
+            | (Self::Database(database), Self::FileFormat(FileFormatIdent { database: db, .. })) => {
+                database == db
+            }
+            (Self::Schema(schema), Self::Table(table)) => {
+                schema.database == table.database && schema.schema == table.schema
+            }
+            (Self::Schema(schema), Self::Stage(stage)) => {
+                schema.database == stage.database && schema.schema == stage.schema
+            }
+            (Self::Schema(schema), Self::FileFormat(file_format)) => {
+                schema.database == file_format.database && schema.schema == file_format.schema
+            }
+            _ => self == other,
+        }
+    }
+}
+
+impl Display for GrantObject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Database(database) => write!(f, "{database}"),
+            Self::Schema(schema) => write!(f, "{schema}"),
+            Self::Table(table) => write!(f, "{table}"),
+            Self::Stage(stage) => write!(f, "{stage}"),
+            Self::FileFormat(file_format) => write!(f, "{file_format}"),
+        }
+    }
+}
+
+/// A privilege on an object granted to a role
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, utoipa::ToSchema)]
+pub struct Grant {
+    pub privilege: Privilege,
+    pub object: GrantObject,
+    pub role: RoleIdent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub granted_by: Option<RoleIdent>,
+}
+
+/// Who a role is granted to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(tag = "kind", content = "name", rename_all = "UPPERCASE")]
+pub enum Grantee {
+    /// The grantee role inherits the privileges of the granted role
+    Role(RoleIdent),
+    User(UserIdent),
+}
+
+impl Grantee {
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Role(_) => "ROLE",
+            Self::User(_) => "USER",
+        }
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Role(name) | Self::User(name) => name,
+        }
+    }
+}
+
+/// A role granted to a user or to another role
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, utoipa::ToSchema)]
+pub struct RoleGrant {
+    pub role: RoleIdent,
+    pub grantee: Grantee,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub granted_by: Option<RoleIdent>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grant_object_contains() {
+        let database = GrantObject::Database("db".to_string());
+        let schema = GrantObject::Schema(SchemaIdent::new("db".to_string(), "sch".to_string()));
+        let table = GrantObject::Table(TableIdent::new("db", "sch", "tbl"));
+        let other_table = GrantObject::Table(TableIdent::new("db", "other", "tbl"));
+        let stage = GrantObject::Stage(StageIdent::new("db", "sch", "stg"));
+
+        assert!(database.contains(&table));
+        assert!(schema.contains(&table));
+        assert!(!schema.contains(&other_table));
+        assert!(!table.contains(&schema));
+        assert!(schema.contains(&stage));
+        assert!(table.contains(&table.clone()));
+        assert!(Privilege::Select.applies_to(&table));
+        assert!(!Privilege::Select.applies_to(&schema));
+        assert!(Privilege::Ownership.applies_to(&database));
+        assert!(Privilege::Usage.applies_to(&stage));
+        assert!(!Privilege::Create.applies_to(&stage));
+
+        let file_format = GrantObject::FileFormat(FileFormatIdent::new("db", "sch", "fmt"));
+        assert!(database.contains(&file_format));
+        assert!(schema.contains(&file_format));
+        assert!(Privilege::Usage.applies_to(&file_format));
+        assert!(!Privilege::Select.applies_to(&file_format));
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use super::{DatabaseIdent, SchemaIdent};
+
+/// Directory of the database volume holding the files of internal stages,
+/// under the schema directory: `<volume>/<db>/<schema>/.stages/<stage>`
+pub const STAGES_DIRECTORY: &str = ".stages";
+
+#[derive(Validate, Debug, Clone, Serialize, Deserialize, PartialEq, Eq, utoipa::ToSchema)]
+/// A named stage identifier
+pub struct StageIdent {
+    #[validate(length(min = 1))]
+    /// The name of the stage
+    pub name: String,
+    #[validate(length(min = 1))]
+    /// The schema the stage belongs to
+    pub schema: String,
+    #[validate(length(min = 1))]
+    /// The database the stage belongs to
+    pub database: DatabaseIdent,
+}
+
+impl StageIdent {
+    #[must_use]
+    pub fn new(database: &str, schema: &str, name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            schema: schema.to_string(),
+            database: database.to_string(),
+        }
+    }
+}
+
+impl From<StageIdent> for SchemaIdent {
+    fn from(ident: StageIdent) -> Self {
+        Self {
+            database: ident.database,
+            schema: ident.schema,
+        }
+    }
+}
+
+impl Display for StageIdent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.database, self.schema, self.name)
+    }
+}
+
+/// An internal stage, created with `CREATE STAGE` without a `URL`.
+///
+/// Files are uploaded to the stage with `PUT` and downloaded with `GET`. They are
+/// stored on the volume of the stage database, see [`STAGES_DIRECTORY`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, utoipa::ToSchema)]
+pub struct Stage {
+    pub ident: StageIdent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+impl Stage {
+    #[must_use]
+    pub const fn new(ident: StageIdent) -> Self {
+        Self {
+            ident,
+            comment: None,
+        }
+    }
+
+    /// Location of the stage files relative to the volume prefix
+    #[must_use]
+    pub fn volume_location(&self) -> String {
+        format!(
+            "{}/{}/{STAGES_DIRECTORY}/{}",
+            self.ident.database, self.ident.schema, self.ident.name
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_volume_location() {
+        let stage = Stage::new(StageIdent::new("db", "sch", "uploads"));
+        assert_eq!(stage.ident.to_string(), "db.sch.uploads");
+        assert_eq!(stage.volume_location(), "db/sch/.stages/uploads");
+    }
+}
@@ -0,0 +1,282 @@
+use argon2::password_hash::{
+    SaltString,
+    rand_core::{OsRng, RngCore},
+};
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use snafu::ResultExt;
+
+use crate::error::{self as metastore_error, Result};
+use crate::models::RoleIdent;
+
+/// A user name. Users are looked up case-insensitively, as Snowflake does with login names.
+pub type UserIdent = String;
+
+/// A user account, created with `CREATE USER` and shared by all the APIs
+/// authenticating against the metastore.
+///
+/// Only the argon2 hash of the password is stored, a user without a password
+/// can't log in with one. Users may also log in with a JWT signed by the private
+/// key of one of their RSA public keys, or with a programmatic access token.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, utoipa::ToSchema)]
+pub struct User {
+    pub name: UserIdent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password_hash: Option<String>,
+    #[serde(default)]
+    pub disabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_role: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_database: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_schema: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Base64 DER body of the RSA public key, without PEM armor
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rsa_public_key: Option<String>,
+    /// Second public key, so keys can be rotated without downtime
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rsa_public_key_2: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub access_tokens: Vec<AccessToken>,
+}
+
+/// A programmatic access token, a long-lived secret services log in with
+/// instead of a password. Only the SHA-256 digest of the secret is stored, the
+/// random 256-bit secrets don't need a slow hash, and tokens are looked up by it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, utoipa::ToSchema)]
+pub struct AccessToken {
+    pub name: String,
+    /// Hex SHA-256 digest of the secret
+    pub secret_hash: String,
+    /// The only role sessions opened with the token can use
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role_restriction: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+impl AccessToken {
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now().naive_utc()
+    }
+}
+
+impl User {
+    #[must_use]
+    pub const fn new(name: UserIdent) -> Self {
+        Self {
+            name,
+            password_hash: None,
+            disabled: false,
+            default_role: None,
+            default_database: None,
+            default_schema: None,
+            comment: None,
+            rsa_public_key: None,
+            rsa_public_key_2: None,
+            access_tokens: Vec::new(),
+        }
+    }
+
+    pub fn with_password(mut self, password: &str) -> Result<Self> {
+        self.set_password(password)?;
+        Ok(self)
+    }
+
+    pub fn set_password(&mut self, password: &str) -> Result<()> {
+        self.password_hash = Some(hash_secret(password)?);
+        Ok(())
+    }
+
+    /// Whether the password matches the stored hash. Always false for users without a password.
+    #[must_use]
+    pub fn verify_password(&self, password: &str) -> bool {
+        self.password_hash
+            .as_deref()
+            .is_some_and(|hash| verify_secret(password, hash))
+    }
+
+    /// The registered RSA public keys
+    pub fn rsa_public_keys(&self) -> impl Iterator<Item = &str> {
+        self.rsa_public_key
+            .iter()
+            .chain(self.rsa_public_key_2.iter())
+            .map(String::as_str)
+    }
+
+    #[must_use]
+    pub fn access_token(&self, name: &str) -> Option<&AccessToken> {
+        self.access_tokens
+            .iter()
+            .find(|token| token.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Adds a programmatic access token and returns its secret, which is not
+    /// stored and can't be shown again.
+    pub fn add_access_token(
+        &mut self,
+        name: &str,
+        days_to_expiry: i64,
+        role_restriction: Option<String>,
+        comment: Option<String>,
+    ) -> Result<String> {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let secret: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+        let created_at = Utc::now().naive_utc();
+        self.access_tokens.push(AccessToken {
+            name: name.to_string(),
+            secret_hash: access_token_hash(&secret),
+            role_restriction,
+            comment,
+            created_at,
+            expires_at: created_at + TimeDelta::days(days_to_expiry),
+        });
+        Ok(secret)
+    }
+
+    /// Removes the access token, returns false if the user has no token with that name
+    pub fn remove_access_token(&mut self, name: &str) -> bool {
+        let count = self.access_tokens.len();
+        self.access_tokens
+            .retain(|token| !token.name.eq_ignore_ascii_case(name));
+        self.access_tokens.len() != count
+    }
+
+    /// The unexpired access token the secret belongs to
+    #[must_use]
+    pub fn verify_access_token(&self, secret: &str) -> Option<&AccessToken> {
+        let secret_hash = access_token_hash(secret);
+        self.access_tokens
+            .iter()
+            .find(|token| !token.is_expired() && token.secret_hash == secret_hash)
+    }
+}
+
+/// A user who passed authentication
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    pub user: User,
+    /// Role of the access token the user authenticated with, their sessions
+    /// can't use another one
+    pub role_restriction: Option<RoleIdent>,
+}
+
+/// Hex SHA-256 digest of an access token secret
+#[must_use]
+pub fn access_token_hash(secret: &str) -> String {
+    Sha256::digest(secret)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn hash_secret(secret: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default()
+        .hash_password(secret.as_bytes(), &salt)
+        .context(metastore_error::PasswordHashSnafu)?;
+    Ok(hash.to_string())
+}
+
+fn verify_secret(secret: &str, hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(secret.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+/// SHA-256 digest of a user name and password, which verified credentials are
+/// remembered by without keeping the password
+pub(crate) fn credentials_digest(name: &str, password: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(name.to_lowercase());
+    hasher.update([0]);
+    hasher.update(password);
+    hasher.finalize().into()
+}
+
+/// Strips the PEM armor and the line breaks of a public key, keeping the base64
+/// DER body, which is how public keys are set on users and stored.
+#[must_use]
+pub fn normalize_public_key(key: &str) -> String {
+    key.lines()
+        .filter(|line| !line.starts_with("-----"))
+        .flat_map(|line| line.chars().filter(|c| !c.is_whitespace()))
+        .collect()
+}
+
+/// The `SHA256:<base64 digest>` fingerprint of a public key, as the Snowflake
+/// connectors put it in the issuer of their JWTs. `None` if the key isn't base64.
+#[must_use]
+pub fn public_key_fingerprint(key: &str) -> Option<String> {
+    let der = STANDARD.decode(normalize_public_key(key)).ok()?;
+    Some(format!("SHA256:{}", STANDARD.encode(Sha256::digest(der))))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_password() {
+        let user = User::new("alice".to_string())
+            .with_password("secret")
+            .unwrap();
+        let hash = user.password_hash.clone().unwrap();
+        assert!(hash.starts_with("$argon2"));
+        assert!(!hash.contains("secret"));
+        assert!(user.verify_password("secret"));
+        assert!(!user.verify_password("Secret"));
+        assert!(!User::new("bob".to_string()).verify_password(""));
+    }
+
+    #[test]
+    fn test_user_access_tokens() {
+        let mut user = User::new("alice".to_string());
+        let secret = user
+            .add_access_token("ci", 30, Some("ANALYST".to_string()), None)
+            .unwrap();
+        let expired = user.add_access_token("old", 0, None, None).unwrap();
+        assert_eq!(user.access_tokens.len(), 2);
+        assert!(!user.access_tokens[0].secret_hash.contains(&secret));
+        assert_eq!(
+            user.access_tokens[0].secret_hash,
+            access_token_hash(&secret)
+        );
+
+        let token = user.verify_access_token(&secret).unwrap();
+        assert_eq!(token.name, "ci");
+        assert_eq!(token.role_restriction.as_deref(), Some("ANALYST"));
+        assert!(user.verify_access_token(&expired).is_none());
+        assert!(user.verify_access_token("wrong").is_none());
+
+        assert!(user.remove_access_token("CI"));
+        assert!(!user.remove_access_token("ci"));
+        assert!(user.verify_access_token(&secret).is_none());
+    }
+
+    #[test]
+    fn test_public_key_fingerprint() {
+        let pem = "-----BEGIN PUBLIC KEY-----\nAAEC\nAwQ=\n-----END PUBLIC KEY-----\n";
+        assert_eq!(normalize_public_key(pem), "AAECAwQ=");
+        // sha256 of the bytes 0..=4
+        assert_eq!(
+            public_key_fingerprint(pem).as_deref(),
+            Some("SHA256:CLteXW6qwQSe3giT0w7QIrGk2bW0jbQUhx9Rycs1KD0=")
+        );
+        assert_eq!(public_key_fingerprint("not base64!"), None);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::data_file_cache::{CachingObjectStore, DataFileCache};
 use crate::error::{self as metastore_error, Result};
 use object_store::{
     ClientOptions, ObjectStore,
@@ -290,6 +291,24 @@ impl Volume {
         }
     }
 
+    /// Object store of the volume, reading data files through the cache if it's a remote one
+    pub fn get_cached_object_store(
+        &self,
+        cache: Option<&Arc<DataFileCache>>,
+    ) -> Result<Arc<dyn ObjectStore>> {
+        let object_store = self.get_object_store()?;
+        match (&self.volume, cache) {
+            (VolumeType::S3(_) | VolumeType::S3Tables(_), Some(cache)) => {
+                Ok(Arc::new(CachingObjectStore::new(
+                    object_store,
+                    cache.clone(),
+                    format!("{}:{}", self.ident, self.prefix()),
+                )) as Arc<dyn ObjectStore>)
+            }
+            _ => Ok(object_store),
+        }
+    }
+
     #[must_use]
     pub fn prefix(&self) -> String {
         match &self.volume {
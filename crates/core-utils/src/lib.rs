@@ -12,7 +12,7 @@ use serde::{Serialize, de::DeserializeOwned};
 use serde_json::de;
 use serde_json::ser;
 use slatedb::Db as SlateDb;
-use slatedb::DbIterator;
+use slatedb::{DbIterator, WriteBatch};
 // use slatedb::config::{PutOptions, WriteOptions};
 use snafu::location;
 use snafu::prelude::*;
@@ -110,6 +110,25 @@ impl Db {
             })
     }
 
+    /// Stores key-value pairs in the database in a single write.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerializeError` if a value cannot be serialized to JSON.
+    /// Returns a `DbError` if the underlying database operation fails.
+    #[instrument(name = "Db::put_all", level = "trace", skip(self, entries), err)]
+    pub async fn put_all<T: serde::Serialize + Sync>(&self, entries: &[(String, T)]) -> Result<()> {
+        let mut batch = WriteBatch::new();
+        for (key, value) in entries {
+            let serialized = ser::to_vec(value).context(errors::SerializeValueSnafu)?;
+            batch.put(key.as_bytes(), serialized);
+        }
+        self.slatedb
+            .write(batch)
+            .await
+            .context(errors::DatabaseSnafu)
+    }
+
     /// Retrieves a value from the database by its key.
     ///
     /// # Errors
@@ -1,6 +1,8 @@
 use core_history::SlateDBHistoryStore;
+use core_metastore::SlateDBMetastore;
 use datafusion::prelude::SessionContext;
 use embucket_functions::session_params::SessionParams;
+use embucket_functions::table::access::{SessionAccess, UnrestrictedAccess};
 use embucket_functions::table::register_udtfs;
 use embucket_functions::{register_udafs, register_udfs};
 use std::collections::BTreeSet;
@@ -44,11 +46,14 @@ pub async fn generate_implemented_functions_csv() -> Result<(), Box<dyn std::err
     // Create a SessionContext and register all the functions like in session.rs
     let mut ctx = SessionContext::new();
 
-    register_udfs(&mut ctx, &Arc::new(SessionParams::default()))?;
+    let session_params = Arc::new(SessionParams::default());
+    register_udfs(&mut ctx, &session_params)?;
     register_udafs(&mut ctx)?;
 
     let history_store = Arc::new(SlateDBHistoryStore::new_in_memory().await);
-    register_udtfs(&ctx, history_store);
+    let metastore = Arc::new(SlateDBMetastore::new_in_memory().await);
+    let access: Arc<dyn SessionAccess> = Arc::new(UnrestrictedAccess);
+    register_udtfs(&ctx, history_store, metastore, &session_params, &access);
 
     datafusion_functions_json::register_all(&mut ctx)?;
 
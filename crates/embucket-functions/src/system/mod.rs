@@ -1,5 +1,6 @@
 pub mod cancel_query;
 pub mod errors;
+pub mod query_progress;
 pub mod sleep;
 pub mod typeof_func;
 
@@ -10,6 +11,7 @@ pub use errors::Error;
 pub fn register_udfs(registry: &mut dyn FunctionRegistry) -> Result<()> {
     registry.register_udf(typeof_func::get_udf())?;
     registry.register_udf(cancel_query::get_udf())?;
+    registry.register_udf(query_progress::get_udf())?;
     // sleep is not for production use, returned udf created with `create_udf`
     registry.register_udf(sleep::get_udf())?;
     Ok(())
@@ -0,0 +1,79 @@
+use datafusion::arrow::datatypes::DataType;
+use datafusion::error::Result as DFResult;
+use datafusion::logical_expr::TypeSignature;
+use datafusion_common::ScalarValue;
+use datafusion_expr::{ColumnarValue, ScalarFunctionArgs, ScalarUDFImpl, Signature, Volatility};
+use std::any::Any;
+
+// This is actually a stub, as it's implementation is by `query_progress` function in `session_context.rs`
+// of core-executor crate.
+
+/// `SystemQueryProgress` is a scalar UDF named `SYSTEM$QUERY_PROGRESS`, which returns progress
+/// of a running query by query id (UUID).
+///
+/// # Purpose
+/// Shows how far along a long-running query is, derived from metrics of its physical plan.
+///
+/// # Arguments
+/// - `query_id`: The string containing UUID of the running query.
+///
+/// # SQL Usage
+/// ```sql
+/// SELECT SYSTEM$QUERY_PROGRESS('123e4567-e89b-12d3-a456-426614174000');
+/// ```
+///
+/// Returns a JSON object with `rowsScanned`, `bytesScanned`, `filesProcessed`, `filesTotal`
+/// and `currentOperator` of the query.
+///
+/// # Notes
+/// - The function is marked as `Volatile`, as it returns different results while the target
+///   query progresses, and cannot be optimized away by the query planner.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct SystemQueryProgress {
+    signature: Signature,
+}
+
+impl Default for SystemQueryProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemQueryProgress {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            signature: Signature {
+                type_signature: TypeSignature::OneOf(vec![
+                    TypeSignature::VariadicAny,
+                    TypeSignature::Nullary,
+                ]),
+                volatility: Volatility::Volatile,
+            },
+        }
+    }
+}
+
+impl ScalarUDFImpl for SystemQueryProgress {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &'static str {
+        "system$query_progress"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> DFResult<DataType> {
+        Ok(DataType::Utf8)
+    }
+
+    fn invoke_with_args(&self, _args: ScalarFunctionArgs) -> DFResult<ColumnarValue> {
+        Ok(ColumnarValue::Scalar(ScalarValue::Utf8(None)))
+    }
+}
+
+crate::macros::make_udf_function!(SystemQueryProgress);
@@ -0,0 +1,32 @@
+use async_trait::async_trait;
+use core_history::QueryRecordId;
+use core_metastore::TableIdent;
+use datafusion_common::Result as DFResult;
+use std::fmt::Debug;
+
+/// Privileges of the session calling a table function. Table functions reading
+/// the catalog or the query history themselves check them, their reads aren't
+/// part of a plan checked before execution.
+#[async_trait]
+pub trait SessionAccess: Debug + Send + Sync {
+    /// Reading the load history of a table needs `SELECT` on it
+    async fn check_table_select(&self, table: &TableIdent) -> DFResult<()>;
+
+    /// Queries are only visible to the user who ran them and to admins
+    async fn check_query(&self, query_id: QueryRecordId) -> DFResult<()>;
+}
+
+/// Access of contexts without a session, such as listing the functions
+#[derive(Debug, Default)]
+pub struct UnrestrictedAccess;
+
+#[async_trait]
+impl SessionAccess for UnrestrictedAccess {
+    async fn check_table_select(&self, _table: &TableIdent) -> DFResult<()> {
+        Ok(())
+    }
+
+    async fn check_query(&self, _query_id: QueryRecordId) -> DFResult<()> {
+        Ok(())
+    }
+}
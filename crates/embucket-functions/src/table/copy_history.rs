@@ -0,0 +1,255 @@
+use crate::session_params::SessionParams;
+use crate::table::access::SessionAccess;
+use crate::utils::block_in_new_runtime;
+use core_metastore::{LoadHistory, Metastore, RwObject, TableIdent};
+use datafusion::arrow::array::{RecordBatch, StringArray, TimestampMillisecondArray, UInt64Array};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use datafusion::catalog::{TableFunctionImpl, TableProvider};
+use datafusion::datasource::MemTable;
+use datafusion_common::{DataFusionError, Result as DFResult, ScalarValue, exec_err};
+use datafusion_expr::Expr;
+use std::sync::Arc;
+
+/// `COPY_HISTORY`
+/// Returns the load history of a table: one row per file loaded by `COPY INTO <table>`.
+///
+/// Syntax: `COPY_HISTORY`( `TABLE_NAME` => '<`table_name`>'
+///     [, `START_TIME` => <`constant_expr`> ] [, `END_TIME` => <`constant_expr`> ] )
+///
+/// Arguments
+/// `table_name`: the table, resolved against the current database and schema when not
+/// fully qualified.
+/// `start_time` / `end_time`: only loads within this time range are returned.
+///
+/// The load history of a table is only returned with `SELECT` on the table.
+#[derive(Debug, Clone)]
+pub struct CopyHistoryFunc {
+    metastore: Arc<dyn Metastore>,
+    session_params: Arc<SessionParams>,
+    access: Arc<dyn SessionAccess>,
+}
+
+impl CopyHistoryFunc {
+    #[must_use]
+    pub fn new(
+        metastore: Arc<dyn Metastore>,
+        session_params: Arc<SessionParams>,
+        access: Arc<dyn SessionAccess>,
+    ) -> Self {
+        Self {
+            metastore,
+            session_params,
+            access,
+        }
+    }
+
+    fn table_ident(&self, table_name: &str) -> DFResult<TableIdent> {
+        let parts = table_name
+            .split('.')
+            .map(|part| {
+                part.strip_prefix('"')
+                    .and_then(|part| part.strip_suffix('"'))
+                    .map_or_else(|| part.to_ascii_lowercase(), ToString::to_string)
+            })
+            .collect::<Vec<_>>();
+        let database = || {
+            self.session_params
+                .get_property("database")
+                .unwrap_or_else(|| "embucket".to_string())
+        };
+        let schema = || {
+            self.session_params
+                .get_property("schema")
+                .unwrap_or_else(|| "public".to_string())
+        };
+        match parts.as_slice() {
+            [table] => Ok(TableIdent::new(&database(), &schema(), table)),
+            [schema, table] => Ok(TableIdent::new(&database(), schema, table)),
+            [database, schema, table] => Ok(TableIdent::new(database, schema, table)),
+            _ => exec_err!("copy_history() got an invalid table name '{table_name}'"),
+        }
+    }
+
+    fn load_history(&self, table: TableIdent) -> DFResult<Vec<RwObject<LoadHistory>>> {
+        let metastore = self.metastore.clone();
+        let access = self.access.clone();
+        block_in_new_runtime(async move {
+            access.check_table_select(&table).await?;
+            metastore
+                .list_load_history(&table, None)
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))
+        })?
+    }
+}
+
+impl TableFunctionImpl for CopyHistoryFunc {
+    fn call(&self, args: &[(Expr, Option<String>)]) -> DFResult<Arc<dyn TableProvider>> {
+        let mut table_name = None;
+        let mut start_time = None;
+        let mut end_time = None;
+        for (index, (expr, name)) in args.iter().enumerate() {
+            let name = name
+                .as_deref()
+                .map(str::to_ascii_lowercase)
+                .or_else(|| (index == 0).then(|| "table_name".to_string()));
+            match name.as_deref() {
+                Some("table_name") => table_name = Some(utf8_arg(expr)?),
+                Some("start_time") => start_time = Some(timestamp_arg(expr)?),
+                Some("end_time") => end_time = Some(timestamp_arg(expr)?),
+                _ => return exec_err!("copy_history() got an unexpected argument {expr}"),
+            }
+        }
+        let Some(table_name) = table_name else {
+            return exec_err!("copy_history() expects a TABLE_NAME argument");
+        };
+
+        let table = self.table_ident(&table_name)?;
+        let loads = self
+            .load_history(table.clone())?
+            .into_iter()
+            .filter(|load| {
+                let loaded_at = load.updated_at.and_utc().timestamp_millis();
+                start_time.is_none_or(|start| loaded_at >= start)
+                    && end_time.is_none_or(|end| loaded_at <= end)
+            })
+            .collect::<Vec<_>>();
+        let batch = copy_history_batch(&table, &loads)?;
+        Ok(Arc::new(MemTable::try_new(
+            batch.schema(),
+            vec![vec![batch]],
+        )?))
+    }
+}
+
+impl PartialEq for CopyHistoryFunc {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.metastore, &other.metastore)
+            && Arc::ptr_eq(&self.session_params, &other.session_params)
+            && Arc::ptr_eq(&self.access, &other.access)
+    }
+}
+
+impl Eq for CopyHistoryFunc {}
+
+impl std::hash::Hash for CopyHistoryFunc {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        state.write(b"CopyHistoryFunc");
+    }
+}
+
+fn utf8_arg(expr: &Expr) -> DFResult<String> {
+    match expr {
+        Expr::Literal(ScalarValue::Utf8(Some(value)) | ScalarValue::Utf8View(Some(value)), _) => {
+            Ok(value.clone())
+        }
+        _ => exec_err!("copy_history() expects a string TABLE_NAME, got {expr}"),
+    }
+}
+
+/// Constant timestamp argument, in milliseconds since the epoch
+fn timestamp_arg(expr: &Expr) -> DFResult<i64> {
+    let Expr::Literal(value, _) = expr else {
+        return exec_err!("copy_history() expects a constant timestamp, got {expr}");
+    };
+    match value.cast_to(&DataType::Timestamp(TimeUnit::Millisecond, None))? {
+        ScalarValue::TimestampMillisecond(Some(millis), _) => Ok(millis),
+        _ => exec_err!("copy_history() expects a constant timestamp, got {expr}"),
+    }
+}
+
+/// `LOADED` -> `Loaded`, `PARTIALLY_LOADED` -> `Partially loaded`, as Snowflake shows it
+fn status_display(status: &str) -> String {
+    let status = status.replace('_', " ").to_ascii_lowercase();
+    let mut chars = status.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_ascii_uppercase().to_string() + chars.as_str()
+    })
+}
+
+fn copy_history_batch(
+    table: &TableIdent,
+    loads: &[RwObject<LoadHistory>],
+) -> DFResult<RecordBatch> {
+    let schema: SchemaRef = Arc::new(Schema::new(vec![
+        Field::new("file_name", DataType::Utf8, false),
+        Field::new(
+            "last_load_time",
+            DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+            false,
+        ),
+        Field::new("row_count", DataType::UInt64, false),
+        Field::new("row_parsed", DataType::UInt64, false),
+        Field::new("file_size", DataType::UInt64, false),
+        Field::new("first_error_message", DataType::Utf8, true),
+        Field::new("first_error_line_number", DataType::UInt64, true),
+        Field::new("first_error_column_name", DataType::Utf8, true),
+        Field::new("error_count", DataType::UInt64, false),
+        Field::new("error_limit", DataType::UInt64, false),
+        Field::new("status", DataType::Utf8, false),
+        Field::new("table_catalog_name", DataType::Utf8, false),
+        Field::new("table_schema_name", DataType::Utf8, false),
+        Field::new("table_name", DataType::Utf8, false),
+    ]));
+    let repeat = |value: &str| StringArray::from_iter_values(loads.iter().map(|_| value));
+    Ok(RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(StringArray::from_iter_values(
+                loads.iter().map(|load| load.file.clone()),
+            )),
+            Arc::new(
+                TimestampMillisecondArray::from_iter_values(
+                    loads
+                        .iter()
+                        .map(|load| load.updated_at.and_utc().timestamp_millis()),
+                )
+                .with_timezone("UTC"),
+            ),
+            Arc::new(UInt64Array::from_iter_values(
+                loads.iter().map(|load| load.rows_loaded),
+            )),
+            Arc::new(UInt64Array::from_iter_values(
+                loads.iter().map(|load| load.rows_parsed),
+            )),
+            Arc::new(UInt64Array::from_iter_values(
+                loads.iter().map(|load| load.size),
+            )),
+            Arc::new(StringArray::from_iter(
+                loads.iter().map(|load| load.first_error.clone()),
+            )),
+            Arc::new(UInt64Array::from_iter(
+                loads.iter().map(|load| load.first_error_line),
+            )),
+            Arc::new(StringArray::from_iter(
+                loads
+                    .iter()
+                    .map(|load| load.first_error_column_name.clone()),
+            )),
+            Arc::new(UInt64Array::from_iter_values(
+                loads.iter().map(|load| load.error_count),
+            )),
+            Arc::new(UInt64Array::from_iter_values(
+                loads.iter().map(|load| load.error_limit),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                loads.iter().map(|load| status_display(&load.status)),
+            )),
+            Arc::new(repeat(&table.database)),
+            Arc::new(repeat(&table.schema)),
+            Arc::new(repeat(&table.table)),
+        ],
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_display() {
+        assert_eq!(status_display("LOADED"), "Loaded");
+        assert_eq!(status_display("PARTIALLY_LOADED"), "Partially loaded");
+        assert_eq!(status_display("LOAD_FAILED"), "Load failed");
+    }
+}
@@ -1,15 +1,44 @@
+use crate::session_params::SessionParams;
+use crate::table::access::SessionAccess;
+use crate::table::copy_history::CopyHistoryFunc;
 use crate::table::flatten::func::FlattenTableFunc;
+use crate::table::query_operator_stats::QueryOperatorStatsFunc;
 use crate::table::result_scan::ResultScanFunc;
 use core_history::HistoryStore;
+use core_metastore::Metastore;
 use datafusion::prelude::SessionContext;
 use std::sync::Arc;
 
+pub mod access;
+pub mod copy_history;
 pub mod errors;
 pub mod flatten;
+pub mod query_operator_stats;
 pub mod result_scan;
 pub use errors::Error;
 
-pub fn register_udtfs(ctx: &SessionContext, history_store: Arc<dyn HistoryStore>) {
+pub fn register_udtfs(
+    ctx: &SessionContext,
+    history_store: Arc<dyn HistoryStore>,
+    metastore: Arc<dyn Metastore>,
+    session_params: &Arc<SessionParams>,
+    access: &Arc<dyn SessionAccess>,
+) {
     ctx.register_udtf("flatten", Arc::new(FlattenTableFunc::new()));
-    ctx.register_udtf("result_scan", Arc::new(ResultScanFunc::new(history_store)));
+    ctx.register_udtf(
+        "result_scan",
+        Arc::new(ResultScanFunc::new(history_store.clone())),
+    );
+    ctx.register_udtf(
+        "get_query_operator_stats",
+        Arc::new(QueryOperatorStatsFunc::new(history_store, access.clone())),
+    );
+    ctx.register_udtf(
+        "copy_history",
+        Arc::new(CopyHistoryFunc::new(
+            metastore,
+            session_params.clone(),
+            access.clone(),
+        )),
+    );
 }
@@ -0,0 +1,177 @@
+use crate::table::access::SessionAccess;
+use crate::table::result_scan::ResultScanFunc;
+use crate::utils::block_in_new_runtime;
+use core_history::{HistoryStore, OperatorStats, QueryIdParam, QueryRecordId};
+use datafusion::arrow::array::{Int64Array, RecordBatch, StringArray};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::catalog::{TableFunctionImpl, TableProvider};
+use datafusion::datasource::MemTable;
+use datafusion_common::{DataFusionError, Result as DFResult, ScalarValue, exec_err};
+use datafusion_expr::Expr;
+use serde_json::{Map, Value, json};
+use std::sync::Arc;
+
+/// `GET_QUERY_OPERATOR_STATS`
+/// Returns statistics about the individual query operators of a query that has completed:
+/// one row per operator of its executed physical plan.
+///
+/// Syntax: `GET_QUERY_OPERATOR_STATS` ( { '<`query_id`>' | `LAST_QUERY_ID()` } )
+#[derive(Debug, Clone)]
+pub struct QueryOperatorStatsFunc {
+    history_store: Arc<dyn HistoryStore>,
+    access: Arc<dyn SessionAccess>,
+}
+
+impl QueryOperatorStatsFunc {
+    #[must_use]
+    pub fn new(history_store: Arc<dyn HistoryStore>, access: Arc<dyn SessionAccess>) -> Self {
+        Self {
+            history_store,
+            access,
+        }
+    }
+
+    fn query_id(&self, args: &[(Expr, Option<String>)]) -> DFResult<String> {
+        match args {
+            [
+                (
+                    Expr::Literal(
+                        ScalarValue::Utf8(Some(query_id)) | ScalarValue::Utf8View(Some(query_id)),
+                        _,
+                    ),
+                    _,
+                ),
+            ] => Ok(query_id.clone()),
+            [(Expr::ScalarFunction(fun), _)]
+                if fun.name().eq_ignore_ascii_case("last_query_id") =>
+            {
+                let index = match fun.args.first() {
+                    Some(Expr::Literal(value, _)) => value.clone().try_into().unwrap_or(-1),
+                    _ => -1,
+                };
+                let query_id =
+                    ResultScanFunc::new(self.history_store.clone()).last_query_id(index)?;
+                Ok(query_id.to_string())
+            }
+            _ => exec_err!(
+                "get_query_operator_stats() expects a single string argument or last_query_id()"
+            ),
+        }
+    }
+
+    fn load_profile(&self, query_id: QueryRecordId) -> DFResult<Vec<OperatorStats>> {
+        let history_store = self.history_store.clone();
+        let access = self.access.clone();
+        block_in_new_runtime(async move {
+            access.check_query(query_id).await?;
+            history_store
+                .get_query_profile(query_id)
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))
+        })?
+    }
+}
+
+impl TableFunctionImpl for QueryOperatorStatsFunc {
+    fn call(&self, args: &[(Expr, Option<String>)]) -> DFResult<Arc<dyn TableProvider>> {
+        let query_id = self.query_id(args)?;
+        let query_record_id: QueryRecordId =
+            serde_json::from_value::<QueryIdParam>(Value::String(query_id))
+                .map_err(|e| DataFusionError::External(Box::new(e)))?
+                .into();
+        let stats = self.load_profile(query_record_id)?;
+        let batch = operator_stats_batch(&query_record_id.to_string(), &stats)?;
+        Ok(Arc::new(MemTable::try_new(
+            batch.schema(),
+            vec![vec![batch]],
+        )?))
+    }
+}
+
+impl PartialEq for QueryOperatorStatsFunc {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.history_store, &other.history_store)
+            && Arc::ptr_eq(&self.access, &other.access)
+    }
+}
+
+impl Eq for QueryOperatorStatsFunc {}
+
+impl std::hash::Hash for QueryOperatorStatsFunc {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        state.write(b"QueryOperatorStatsFunc");
+    }
+}
+
+/// Operator statistics in the layout of Snowflake's `GET_QUERY_OPERATOR_STATS`,
+/// shared with `EXPLAIN ANALYZE`
+pub fn operator_stats_batch(query_id: &str, stats: &[OperatorStats]) -> DFResult<RecordBatch> {
+    let schema: SchemaRef = Arc::new(Schema::new(vec![
+        Field::new("QUERY_ID", DataType::Utf8, false),
+        Field::new("STEP_ID", DataType::Int64, false),
+        Field::new("OPERATOR_ID", DataType::Int64, false),
+        Field::new("PARENT_OPERATORS", DataType::Utf8, true),
+        Field::new("OPERATOR_TYPE", DataType::Utf8, false),
+        Field::new("OPERATOR_STATISTICS", DataType::Utf8, false),
+        Field::new("EXECUTION_TIME_BREAKDOWN", DataType::Utf8, false),
+        Field::new("OPERATOR_ATTRIBUTES", DataType::Utf8, false),
+    ]));
+    let total_elapsed: usize = stats
+        .iter()
+        .filter_map(|stat| stat.elapsed_compute_ns)
+        .sum();
+    let to_int = |value: usize| i64::try_from(value).unwrap_or(i64::MAX);
+
+    Ok(RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(StringArray::from_iter_values(
+                stats.iter().map(|_| query_id),
+            )),
+            Arc::new(Int64Array::from_iter_values(stats.iter().map(|_| 1))),
+            Arc::new(Int64Array::from_iter_values(
+                stats.iter().map(|stat| to_int(stat.operator_id)),
+            )),
+            Arc::new(StringArray::from_iter(stats.iter().map(|stat| {
+                stat.parent_operator_id
+                    .map(|parent| json!([parent]).to_string())
+            }))),
+            Arc::new(StringArray::from_iter_values(
+                stats.iter().map(|stat| stat.operator_type.clone()),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                stats.iter().map(operator_statistics),
+            )),
+            Arc::new(StringArray::from_iter_values(stats.iter().map(|stat| {
+                let elapsed = stat.elapsed_compute_ns.unwrap_or_default();
+                execution_time_breakdown(elapsed, total_elapsed)
+            }))),
+            Arc::new(StringArray::from_iter_values(stats.iter().map(|stat| {
+                json!({ "plan": stat.operator_attributes }).to_string()
+            }))),
+        ],
+    )?)
+}
+
+fn operator_statistics(stat: &OperatorStats) -> String {
+    let statistics = [
+        ("output_rows", stat.output_rows),
+        ("elapsed_compute_ns", stat.elapsed_compute_ns),
+        ("spilled_bytes", stat.spilled_bytes),
+        ("memory_peak_bytes", stat.memory_peak_bytes),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| value.map(|value| (name.to_string(), json!(value))))
+    .collect::<Map<_, _>>();
+    Value::Object(statistics).to_string()
+}
+
+#[allow(clippy::cast_precision_loss, clippy::as_conversions)]
+fn execution_time_breakdown(elapsed: usize, total_elapsed: usize) -> String {
+    let percentage = if total_elapsed == 0 {
+        0.0
+    } else {
+        elapsed as f64 * 100.0 / total_elapsed as f64
+    };
+    json!({ "overall_percentage": (percentage * 1000.0).round() / 1000.0 }).to_string()
+}
@@ -1,10 +1,13 @@
 use crate::expr_planner::CustomExprPlanner;
 use crate::session::register_session_context_udfs;
 use crate::session_params::SessionParams;
+use crate::table::access::{SessionAccess, UnrestrictedAccess};
 use crate::table::register_udtfs;
+use crate::utils::block_in_new_runtime;
 use crate::{register_udafs, register_udfs};
 use bytes::Bytes;
 use core_history::{HistoryStore, MockHistoryStore, QueryRecord, QueryRecordId, ResultSet};
+use core_metastore::SlateDBMetastore;
 use datafusion::execution::SessionStateBuilder;
 use datafusion::prelude::{SessionConfig, SessionContext};
 use snafu::Location;
@@ -26,9 +29,20 @@ pub fn create_session() -> Arc<SessionContext> {
         .build();
     let mut ctx = SessionContext::new_with_state(state);
     register_session_context_udfs(&mut ctx).unwrap();
-    register_udfs(&mut ctx, &Arc::new(SessionParams::default())).expect("Cannot register UDFs");
+    let session_params = Arc::new(SessionParams::default());
+    register_udfs(&mut ctx, &session_params).expect("Cannot register UDFs");
     register_udafs(&mut ctx).expect("Cannot register UDAFs");
-    register_udtfs(&ctx, history_store_mock());
+    let metastore =
+        block_in_new_runtime(async { Arc::new(SlateDBMetastore::new_in_memory().await) })
+            .expect("Cannot create metastore");
+    let access: Arc<dyn SessionAccess> = Arc::new(UnrestrictedAccess);
+    register_udtfs(
+        &ctx,
+        history_store_mock(),
+        metastore,
+        &session_params,
+        &access,
+    );
     Arc::new(ctx)
 }
 pub fn history_store_mock() -> Arc<dyn HistoryStore> {
@@ -1,6 +1,6 @@
 use datafusion::logical_expr::sqlparser::ast::{Expr, TableFactor, VisitMut};
 use datafusion::sql::sqlparser::ast::{
-    Function, FunctionArguments, Query, SetExpr, Statement, VisitorMut,
+    Function, FunctionArguments, Ident, ObjectName, Query, SetExpr, Statement, VisitorMut,
 };
 use std::ops::ControlFlow;
 
@@ -55,7 +55,20 @@ impl VisitorMut for TableFunctionVisitor {
                 } = &mut item.relation
                 {
                     let func_name = name.to_string();
-                    if matches!(func_name.to_lowercase().as_str(), "result_scan" | "flatten") {
+                    // `COPY_HISTORY` is usually called as `INFORMATION_SCHEMA.COPY_HISTORY`,
+                    // these functions are registered under their lowercase names
+                    let registered_name = name.0.last().map(|part| part.to_string().to_lowercase());
+                    if let Some(registered_name @ ("copy_history" | "get_query_operator_stats")) =
+                        registered_name.as_deref()
+                    {
+                        item.relation = TableFactor::Function {
+                            name: ObjectName::from(vec![Ident::new(registered_name)]),
+                            args: args.args.clone(),
+                            alias: alias.clone(),
+                            lateral: false,
+                        };
+                    } else if matches!(func_name.to_lowercase().as_str(), "result_scan" | "flatten")
+                    {
                         item.relation = TableFactor::Function {
                             name: name.clone(),
                             args: args.args.clone(),
@@ -2397,12 +2397,6 @@ pub const SYSTEM_FUNCTIONS: &[(&str, FunctionInfo)] = &[
     )
     .with_docs("https://docs.snowflake.com/en/sql-reference/functions/get_python_profiler_output")
     ),
-    ("GET_QUERY_OPERATOR_STATS", FunctionInfo::new(
-        "GET_QUERY_OPERATOR_STATS",
-        "Returns statistics about individual query operators within a query that has completed."
-    )
-    .with_docs("https://docs.snowflake.com/en/sql-reference/functions/get_query_operator_stats")
-    ),
     ("SHOW_PYTHON_PACKAGES_DEPENDENCIES", FunctionInfo::new(
         "SHOW_PYTHON_PACKAGES_DEPENDENCIES",
         "Returns a list of the dependencies and their versions for the Python packages that were specified."
@@ -1,6 +1,10 @@
 use clap::{Parser, ValueEnum};
-use core_executor::utils::DEFAULT_QUERY_HISTORY_ROWS_LIMIT;
 use core_executor::utils::MemPoolType;
+use core_executor::utils::{
+    DEFAULT_QUERY_HISTORY_ROWS_LIMIT, DEFAULT_RESULT_CACHE_MEMORY_MB,
+    DEFAULT_RESULT_SPOOL_MEMORY_MB,
+};
+use core_metastore::data_file_cache::DataFileCacheConfig;
 use object_store::{
     ObjectStore, Result as ObjectStoreResult, aws::AmazonS3Builder, aws::S3ConditionalPut,
     local::LocalFileSystem, memory::InMemory,
@@ -114,6 +118,36 @@ pub struct CliOpts {
     )]
     pub assets_port: Option<u16>,
 
+    #[arg(
+        long,
+        env = "FLIGHT_SQL_PORT",
+        help = "Port of Arrow Flight SQL server to bind to, the server is started only when set"
+    )]
+    pub flight_sql_port: Option<u16>,
+
+    #[arg(
+        long,
+        env = "PG_WIRE_PORT",
+        help = "Port of PostgreSQL wire protocol server to bind to, the server is started only when set"
+    )]
+    pub pg_wire_port: Option<u16>,
+
+    #[arg(
+        long,
+        env = "PG_WIRE_TLS_CERT",
+        requires = "pg_wire_tls_key",
+        help = "PEM certificate chain of PostgreSQL wire protocol server, clients authenticate with a password only over TLS"
+    )]
+    pub pg_wire_tls_cert: Option<String>,
+
+    #[arg(
+        long,
+        env = "PG_WIRE_TLS_KEY",
+        requires = "pg_wire_tls_cert",
+        help = "PEM private key of PostgreSQL wire protocol server certificate"
+    )]
+    pub pg_wire_tls_key: Option<String>,
+
     #[arg(
         long,
         env = "CATALOG_URL",
@@ -148,6 +182,27 @@ pub struct CliOpts {
     )]
     pub data_format: Option<String>,
 
+    #[arg(
+        long,
+        env = "RESULT_CHUNK_ROWS",
+        help = "Return larger results in chunks of this many rows in Snowflake v1 API"
+    )]
+    pub result_chunk_rows: Option<usize>,
+
+    #[arg(
+        long,
+        env = "RESULT_CHUNKS_MEMORY_MB",
+        help = "Memory in MB the result chunks waiting for download may take, further chunks are spilled to disk"
+    )]
+    pub result_chunks_memory_mb: Option<usize>,
+
+    #[arg(
+        long,
+        env = "EXTERNAL_URL",
+        help = "URL clients reach the server at, used for result chunk and stage file downloads. Defaults to http://<host>:<port>"
+    )]
+    pub external_url: Option<String>,
+
     #[arg(
         long,
         env = "SQL_PARSER_DIALECT",
@@ -234,6 +289,53 @@ pub struct CliOpts {
     )]
     pub query_history_rows_limit: usize,
 
+    #[arg(
+        long,
+        env = "RESULT_SPOOL_MEMORY_MB",
+        default_value_t = DEFAULT_RESULT_SPOOL_MEMORY_MB,
+        help = "Memory in megabytes a streamed result may take before it's spooled to disk while the client is not reading it"
+    )]
+    pub result_spool_memory_mb: usize,
+
+    #[arg(
+        long,
+        env = "RESULT_CACHE_MEMORY_MB",
+        default_value_t = DEFAULT_RESULT_CACHE_MEMORY_MB,
+        help = "Memory in megabytes taken by the result cache of queries, least recently used results are evicted over it"
+    )]
+    pub result_cache_memory_mb: usize,
+
+    #[arg(
+        long,
+        env = "DATA_CACHE_MEMORY_MB",
+        default_value = "0",
+        help = "Memory in megabytes for caching Parquet data files read from S3 volumes, 0 disables the memory cache"
+    )]
+    pub data_cache_memory_mb: u64,
+
+    #[arg(
+        long,
+        env = "DATA_CACHE_DIR",
+        help = "Directory for caching Parquet data files read from S3 volumes on local disk"
+    )]
+    pub data_cache_dir: Option<PathBuf>,
+
+    #[arg(
+        long,
+        env = "DATA_CACHE_DISK_MB",
+        default_value = "10240",
+        help = "Maximum size in megabytes of the data files cache directory"
+    )]
+    pub data_cache_disk_mb: u64,
+
+    #[arg(
+        long,
+        env = "DATA_CACHE_BLOCK_KB",
+        default_value = "1024",
+        help = "Size in kilobytes of the blocks Parquet data files are cached in"
+    )]
+    pub data_cache_block_kb: u64,
+
     // should unset JWT_SECRET env var after loading
     #[arg(
         long,
@@ -248,7 +350,7 @@ pub struct CliOpts {
         env = "AUTH_DEMO_USER",
         value_parser = clap::builder::NonEmptyStringValueParser::new(),
         default_value = "embucket",
-        help = "User for auth demo"
+        help = "User created on startup unless it already exists"
     )]
     pub auth_demo_user: Option<String>,
 
@@ -257,10 +359,18 @@ pub struct CliOpts {
         env = "AUTH_DEMO_PASSWORD",
         value_parser = clap::builder::NonEmptyStringValueParser::new(),
         default_value = "embucket",
-        help = "Password for auth demo"
+        help = "Password of the user created on startup"
     )]
     pub auth_demo_password: Option<String>,
 
+    #[arg(
+        long,
+        env = "NO_AUTH",
+        default_value = "false",
+        help = "Serve all the APIs without authentication, for local development only. The UI acts as the startup user"
+    )]
+    pub no_auth: bool,
+
     #[arg(
         long,
         value_enum,
@@ -289,21 +399,21 @@ enum StoreBackend {
 
 impl CliOpts {
     #[allow(clippy::unwrap_used, clippy::as_conversions)]
-    pub fn object_store_backend(self) -> ObjectStoreResult<Arc<dyn ObjectStore>> {
+    pub fn object_store_backend(&self) -> ObjectStoreResult<Arc<dyn ObjectStore>> {
         match self.backend {
             StoreBackend::S3 => {
                 let s3_allow_http = self.allow_http.unwrap_or(false);
 
                 let s3_builder = AmazonS3Builder::new()
-                    .with_access_key_id(self.access_key_id.unwrap())
-                    .with_secret_access_key(self.secret_access_key.unwrap())
-                    .with_region(self.region.unwrap())
-                    .with_bucket_name(self.bucket.unwrap())
+                    .with_access_key_id(self.access_key_id.clone().unwrap())
+                    .with_secret_access_key(self.secret_access_key.clone().unwrap())
+                    .with_region(self.region.clone().unwrap())
+                    .with_bucket_name(self.bucket.clone().unwrap())
                     .with_conditional_put(S3ConditionalPut::ETagMatch);
 
-                if let Some(endpoint) = self.endpoint {
+                if let Some(endpoint) = &self.endpoint {
                     s3_builder
-                        .with_endpoint(&endpoint)
+                        .with_endpoint(endpoint)
                         .with_allow_http(s3_allow_http)
                         .build()
                         .map(|s3| Arc::new(s3) as Arc<dyn ObjectStore>)
@@ -314,7 +424,7 @@ impl CliOpts {
                 }
             }
             StoreBackend::File => {
-                let file_storage_path = self.file_storage_path.unwrap();
+                let file_storage_path = self.file_storage_path.clone().unwrap();
                 let path = file_storage_path.as_path();
                 if !path.exists() || !path.is_dir() {
                     fs::create_dir(path).unwrap();
@@ -333,6 +443,20 @@ impl CliOpts {
         }
         self.jwt_secret.clone().unwrap_or_default()
     }
+
+    // data files cache is enabled by setting its memory size or directory
+    #[must_use]
+    pub fn data_file_cache_config(&self) -> Option<DataFileCacheConfig> {
+        if self.data_cache_memory_mb == 0 && self.data_cache_dir.is_none() {
+            return None;
+        }
+        Some(DataFileCacheConfig {
+            memory_capacity_bytes: self.data_cache_memory_mb.saturating_mul(1024 * 1024),
+            disk_dir: self.data_cache_dir.clone(),
+            disk_capacity_bytes: self.data_cache_disk_mb.saturating_mul(1024 * 1024),
+            block_size_bytes: self.data_cache_block_kb.saturating_mul(1024),
+        })
+    }
 }
 
 #[derive(Debug, Clone, ValueEnum)]
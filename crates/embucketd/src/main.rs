@@ -5,16 +5,25 @@ pub(crate) mod cli;
 pub(crate) mod helpers;
 pub(crate) mod layers;
 
+use api_flight_sql::server::create_flight_sql_service;
+use api_flight_sql::state::Config as FlightSqlConfig;
+use api_flight_sql::state::State as FlightSqlState;
+use api_iceberg_rest::layer::require_auth as iceberg_require_auth;
 use api_iceberg_rest::router::create_router as create_iceberg_router;
 use api_iceberg_rest::state::Config as IcebergConfig;
 use api_iceberg_rest::state::State as IcebergAppState;
 use api_internal_rest::router::create_router as create_internal_router;
 use api_internal_rest::state::State as InternalAppState;
+use api_pgwire::state::Config as PgWireConfig;
+use api_pgwire::state::State as PgWireState;
 use api_sessions::layer::propagate_session_cookie;
 use api_sessions::session::{SESSION_EXPIRATION_SECONDS, SessionStore};
 use api_snowflake_rest::server::layer::require_auth as snowflake_require_auth;
+use api_snowflake_rest::server::result_chunks::ResultChunks;
 use api_snowflake_rest::server::router::create_auth_router as create_snowflake_auth_router;
 use api_snowflake_rest::server::router::create_router as create_snowflake_router;
+use api_snowflake_rest::server::router::create_sql_api_router as create_snowflake_sql_api_router;
+use api_snowflake_rest::server::router::create_stage_transfer_router as create_snowflake_stage_transfer_router;
 use api_snowflake_rest::server::server_models::Config;
 use api_snowflake_rest::server::state::AppState as SnowflakeAppState;
 use api_ui::auth::layer::require_auth as ui_require_auth;
@@ -36,7 +45,8 @@ use clap::Parser;
 use core_executor::service::CoreExecutionService;
 use core_executor::utils::Config as ExecutionConfig;
 use core_history::SlateDBHistoryStore;
-use core_metastore::SlateDBMetastore;
+use core_metastore::data_file_cache::DataFileCache;
+use core_metastore::{SlateDBMetastore, bootstrap_user};
 use core_utils::Db;
 use dotenv::dotenv;
 use object_store::path::Path;
@@ -80,12 +90,14 @@ mod alloc_tracing {
 #[global_allocator]
 static ALLOCATOR: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
-const TARGETS: [&str; 16] = [
+const TARGETS: [&str; 18] = [
     "embucketd",
     "api_ui",
     "api_sessions",
     "api_snowflake_rest",
     "api_iceberg_rest",
+    "api_flight_sql",
+    "api_pgwire",
     "core_executor",
     "core_utils",
     "core_history",
@@ -151,12 +163,26 @@ async fn async_main(
         .with_demo_credentials(
             opts.auth_demo_user.clone().unwrap(),
             opts.auth_demo_password.clone().unwrap(),
-        );
+        )
+        .with_auth_disabled(opts.no_auth)
+        .with_result_chunk_rows(opts.result_chunk_rows)
+        .with_result_chunks_memory_limit(
+            opts.result_chunks_memory_mb
+                .map(|memory_mb| memory_mb * 1024 * 1024),
+        )
+        .with_base_url(opts.external_url.clone().unwrap_or_else(|| {
+            format!(
+                "http://{}:{}",
+                opts.host.clone().unwrap(),
+                opts.port.unwrap()
+            )
+        }));
 
     // Bootstrap the service if no flag is present (`--no-bootstrap`) with:
     // 1. Creation of a default in-memory volume named `embucket`
     // 2. Creation of a default database `embucket` in the volume `embucket`
     // 3. Creation of a default schema `public` in the database `embucket`
+    // 4. Grant of `USAGE` and `CREATE` on them to `PUBLIC`, the role of sessions without a user
 
     let execution_cfg = ExecutionConfig {
         embucket_version: "0.1.0".to_string(),
@@ -169,13 +195,17 @@ async fn async_main(
         mem_enable_track_consumers_pool: opts.mem_enable_track_consumers_pool,
         disk_pool_size_mb: opts.disk_pool_size_mb,
         query_history_rows_limit: opts.query_history_rows_limit,
+        result_spool_memory_mb: opts.result_spool_memory_mb,
+        result_cache_memory_mb: opts.result_cache_memory_mb,
         use_duck_db: opts.use_duck_db.unwrap_or(false),
         use_duck_db_explain: opts.use_duck_db_explain.unwrap_or(false),
     };
-    let auth_config = UIAuthConfig::new(opts.jwt_secret()).with_demo_credentials(
-        opts.auth_demo_user.clone().unwrap(),
-        opts.auth_demo_password.clone().unwrap(),
-    );
+    let auth_config = UIAuthConfig::new(opts.jwt_secret())
+        .with_demo_credentials(
+            opts.auth_demo_user.clone().unwrap(),
+            opts.auth_demo_password.clone().unwrap(),
+        )
+        .with_auth_disabled(opts.no_auth);
     let web_config = UIWebConfig {
         host: opts.host.clone().unwrap(),
         port: opts.port.unwrap(),
@@ -202,7 +232,22 @@ async fn async_main(
 
     let db = Db::new(slate_db);
 
-    let metastore = Arc::new(SlateDBMetastore::new(db.clone()));
+    let mut metastore = SlateDBMetastore::new(db.clone());
+    if let Some(cache_config) = opts.data_file_cache_config() {
+        let cache = DataFileCache::new(cache_config).expect("Failed to create data files cache");
+        metastore = metastore.with_data_file_cache(Arc::new(cache));
+    }
+    let metastore = Arc::new(metastore);
+    let demo_user = opts.auth_demo_user.clone().unwrap_or_default();
+    if !demo_user.is_empty() {
+        bootstrap_user(
+            metastore.as_ref(),
+            &demo_user,
+            &opts.auth_demo_password.clone().unwrap_or_default(),
+        )
+        .await
+        .expect("Failed to create the startup user");
+    }
     let history_store = Arc::new(SlateDBHistoryStore::new(db.clone()).await?);
 
     tracing::info!("Creating execution service");
@@ -253,9 +298,34 @@ async fn async_main(
         ui_require_auth,
     ));
     let ui_auth_router = create_ui_auth_router().with_state(ui_state.clone());
+    let flight_sql_state = FlightSqlState::new(
+        execution_svc.clone(),
+        metastore.clone(),
+        FlightSqlConfig {
+            require_auth: !opts.no_auth,
+        },
+    );
+    let pgwire_tls = match (&opts.pg_wire_tls_cert, &opts.pg_wire_tls_key) {
+        (Some(cert_path), Some(key_path)) => {
+            Some(api_pgwire::tls::server_config(cert_path, key_path)?)
+        }
+        _ => None,
+    };
+    let pgwire_state = PgWireState::new(
+        execution_svc.clone(),
+        metastore.clone(),
+        PgWireConfig {
+            require_auth: !opts.no_auth,
+            tls: pgwire_tls,
+        },
+    );
     let snowflake_state = SnowflakeAppState {
         execution_svc,
+        metastore: metastore.clone(),
+        result_chunks: Arc::new(ResultChunks::from_config(&snowflake_rest_cfg)?),
         config: snowflake_rest_cfg,
+        stage_transfers: Arc::default(),
+        statements: Arc::default(),
     };
     let compression_layer = ServiceBuilder::new()
         .layer(CompressionLayer::new())
@@ -268,13 +338,30 @@ async fn async_main(
             snowflake_require_auth,
         ));
     let snowflake_auth_router = create_snowflake_auth_router()
+        .with_state(snowflake_state.clone())
+        .layer(compression_layer.clone());
+    let snowflake_sql_api_router = create_snowflake_sql_api_router()
         .with_state(snowflake_state.clone())
         .layer(compression_layer);
-    let snowflake_router = snowflake_router.merge(snowflake_auth_router);
-    let iceberg_router = create_iceberg_router().with_state(IcebergAppState {
+    let snowflake_stage_transfer_router =
+        create_snowflake_stage_transfer_router().with_state(snowflake_state.clone());
+    let snowflake_router = snowflake_router
+        .merge(snowflake_auth_router)
+        .merge(snowflake_sql_api_router)
+        .merge(snowflake_stage_transfer_router);
+    let iceberg_state = IcebergAppState {
         metastore,
         config: Arc::new(iceberg_config),
-    });
+    };
+    let iceberg_router = create_iceberg_router().with_state(iceberg_state.clone());
+    let iceberg_router = if opts.no_auth {
+        iceberg_router
+    } else {
+        iceberg_router.layer(middleware::from_fn_with_state(
+            iceberg_state,
+            iceberg_require_auth,
+        ))
+    };
 
     // --- OpenAPI specs ---
     let mut spec = ApiDoc::openapi();
@@ -323,6 +410,37 @@ async fn async_main(
     // Runs web assets server in background
     tokio::spawn(async { axum::serve(listener, web_assets_app()).await });
 
+    // Create Arrow Flight SQL server, when its port is set
+    if let Some(flight_sql_port) = opts.flight_sql_port {
+        let flight_sql_addr =
+            helpers::resolve_ipv4(format!("{}:{flight_sql_port}", web_config.host))
+                .expect("Failed to resolve Flight SQL server address");
+        tracing::info!(addr = %flight_sql_addr, "Listening on grpc");
+        // Runs Flight SQL server in background
+        tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(create_flight_sql_service(flight_sql_state))
+                .serve(flight_sql_addr)
+                .await
+        });
+    }
+
+    // Create PostgreSQL wire protocol server, when its port is set
+    if let Some(pg_wire_port) = opts.pg_wire_port {
+        let pgwire_addr = helpers::resolve_ipv4(format!("{}:{pg_wire_port}", web_config.host))?;
+        let listener = tokio::net::TcpListener::bind(pgwire_addr).await?;
+        tracing::info!(addr = %pgwire_addr, "Listening on pgwire");
+        if !opts.no_auth && opts.pg_wire_tls_cert.is_none() {
+            tracing::warn!("PostgreSQL clients can't authenticate without a TLS certificate");
+        }
+        // Runs PostgreSQL server in background
+        tokio::spawn(async move {
+            if let Err(error) = api_pgwire::server::serve(listener, pgwire_state).await {
+                tracing::error!(%error, "PostgreSQL wire protocol server stopped");
+            }
+        });
+    }
+
     // Create web server
     let web_addr = helpers::resolve_ipv4(format!("{}:{}", web_config.host, web_config.port))
         .expect("Failed to resolve web server address");